@@ -0,0 +1,114 @@
+//! Compares culling every AABB linearly against querying the renderer BVH, for static scenes with moving objects.
+//! Run with `cargo bench --bench bvh_culling`.
+
+use r3d::{
+    gfx::Bvh,
+    math::{Frustum, Mat4, Quat, Vec3, AABB},
+    specs::{Builder, Entity, World, WorldExt},
+    util::Rng,
+};
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+const FRAMES: u32 = 100;
+const MOVER_COUNT: usize = 1_000;
+
+struct Scene {
+    objects: Vec<(Entity, AABB)>,
+    bvh: Bvh,
+}
+
+fn unit(center: Vec3) -> AABB {
+    AABB::new(
+        center - Vec3::new(0.5, 0.5, 0.5),
+        center + Vec3::new(0.5, 0.5, 0.5),
+    )
+}
+
+fn random_center(rng: &mut Rng) -> Vec3 {
+    Vec3::new(
+        rng.range_f32(-500.0, 500.0),
+        rng.range_f32(-20.0, 20.0),
+        rng.range_f32(-500.0, 500.0),
+    )
+}
+
+fn build_scene(world: &mut World, rng: &mut Rng, count: usize) -> Scene {
+    let mut objects = Vec::with_capacity(count);
+    let mut bvh = Bvh::new();
+
+    for _ in 0..count {
+        let entity = world.create_entity().build();
+        let aabb = unit(random_center(rng));
+        bvh.insert(entity, aabb);
+        objects.push((entity, aabb));
+    }
+
+    Scene { objects, bvh }
+}
+
+fn frustum(frame: u32) -> Frustum {
+    let angle = frame as f32 * 0.05;
+    let view = Mat4::srt(Vec3::ZERO, Quat::from_eular(0.0, angle, 0.0), Vec3::ONE).inversed();
+    Frustum::from_view_projection(
+        &(view * Mat4::perspective(60f32.to_radians(), 16.0 / 9.0, 0.1, 300.0)),
+    )
+}
+
+fn run(world: &mut World, rng: &mut Rng, count: usize) {
+    let mut scene = build_scene(world, rng, count);
+    let mut visible = Vec::new();
+    let mut linear = Duration::ZERO;
+    let mut bvh = Duration::ZERO;
+    let mut refit = Duration::ZERO;
+
+    for frame in 0..FRAMES {
+        let started = Instant::now();
+        for index in 0..MOVER_COUNT {
+            let (entity, aabb) = &mut scene.objects[index];
+            *aabb = unit(aabb.center() + Vec3::new(0.05, 0.0, 0.0));
+            scene.bvh.update(*entity, *aabb);
+        }
+        scene.bvh.rebuild_incrementally(16);
+        refit += started.elapsed();
+
+        let frustum = frustum(frame);
+
+        let started = Instant::now();
+        visible.clear();
+        visible.extend(
+            scene
+                .objects
+                .iter()
+                .filter(|(_, aabb)| frustum.intersects_aabb(aabb))
+                .map(|(entity, _)| *entity),
+        );
+        black_box(&visible);
+        linear += started.elapsed();
+
+        let started = Instant::now();
+        visible.clear();
+        scene.bvh.query_frustum(&frustum, &mut visible);
+        black_box(&visible);
+        bvh += started.elapsed();
+    }
+
+    println!(
+        "{count} objects, {MOVER_COUNT} movers: linear {:?}/frame, bvh {:?}/frame (+ refit {:?}/frame), height {}",
+        linear / FRAMES,
+        bvh / FRAMES,
+        refit / FRAMES,
+        scene.bvh.height()
+    );
+}
+
+fn main() {
+    let mut world = World::new();
+    let mut rng = Rng::new(0);
+
+    for count in [10_000, 50_000] {
+        run(&mut world, &mut rng, count + MOVER_COUNT);
+    }
+}
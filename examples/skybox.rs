@@ -0,0 +1,109 @@
+//! Rotates a camera inside a skybox.
+//!
+//! Run it with a vertical strip of six faces, or with the six faces in order of `+x`, `-x`, `+y`, `-y`, `+z` and `-z`:
+//!
+//! ```sh
+//! cargo run --example skybox -- strip.png
+//! cargo run --example skybox -- px.png nx.png py.png ny.png pz.png nz.png
+//! ```
+
+use pollster::FutureExt;
+use r3d::{
+    event::{event_types, EventHandler},
+    gfx::{
+        Camera, CameraClearMode, CameraPerspectiveProjectionAspect, CameraProjection, Texture,
+        TextureHandle,
+    },
+    image::{DynamicImage, ImageError},
+    math::{Quat, Vec3},
+    specs::Builder,
+    transform::TransformComponent,
+    wgpu::TextureFormat,
+    Engine, EngineConfig, EngineLoopMode, EngineTargetFps,
+};
+use std::error::Error;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let paths = std::env::args().skip(1).collect::<Vec<_>>();
+
+    let engine = Engine::new(EngineConfig {
+        title: "skybox".to_owned(),
+        resizable: true,
+        width: 800,
+        height: 600,
+        glyph_atlas: Default::default(),
+        rng_seed: None,
+        settings_app_name: None,
+        restore_window_state: false,
+        gfx: Default::default(),
+    })
+    .block_on()?;
+    let ctx = engine.context();
+
+    let cubemap = {
+        let device = &ctx.gfx_ctx().device;
+        let queue = &ctx.gfx_ctx().queue;
+
+        match paths.as_slice() {
+            [strip] => Texture::cubemap_from_strip(
+                TextureFormat::Rgba8UnormSrgb,
+                &open_rgba(strip)?,
+                device,
+                queue,
+            )?,
+            [px, nx, py, ny, pz, nz] => Texture::cubemap_from_images(
+                TextureFormat::Rgba8UnormSrgb,
+                [
+                    &open_rgba(px)?,
+                    &open_rgba(nx)?,
+                    &open_rgba(py)?,
+                    &open_rgba(ny)?,
+                    &open_rgba(pz)?,
+                    &open_rgba(nz)?,
+                ],
+                device,
+                queue,
+            )?,
+            _ => return Err("expected a strip or six faces".into()),
+        }
+    };
+
+    let camera_component = Camera::new(
+        0xFFFF_FFFF,
+        0,
+        CameraClearMode::skybox(TextureHandle::new(cubemap), 1.0, 0),
+        CameraProjection::perspective(
+            60.0,
+            CameraPerspectiveProjectionAspect::Screen,
+            0.01,
+            1000.0,
+        ),
+        &ctx.gfx_ctx().device,
+        ctx.render_mgr_mut().bind_group_layout_cache(),
+    );
+    let camera = {
+        let mut object_mgr = ctx.object_mgr_mut();
+        let mut world = ctx.world_mut();
+        let (camera, builder) =
+            object_mgr.create_object_builder(&mut world, Some("camera".to_owned()), None);
+        builder.with(camera_component).build();
+        camera
+    };
+
+    ctx.event_mgr()
+        .add_handler(EventHandler::<event_types::Update>::new(move |_| {
+            let time = r3d::use_context().time_mgr().time().as_secs_f32();
+            camera
+                .component::<TransformComponent>()
+                .set_rotation(Quat::from_axis_angle(Vec3::UP, time * 0.25));
+        }));
+
+    engine.run(EngineLoopMode::Poll, EngineTargetFps::VSync)?;
+    Ok(())
+}
+
+fn open_rgba(path: &str) -> Result<DynamicImage, ImageError> {
+    Ok(DynamicImage::ImageRgba8(
+        r3d::image::open(path)?.into_rgba8(),
+    ))
+}
@@ -0,0 +1,173 @@
+//! Draws overlapping world-space sprites through an orthographic camera.
+//!
+//! The trees and the walking character share a sorting layer and are sorted along the world up axis, so the
+//! character is drawn in front of the trees above it and behind the trees below it. The ground sits on a lower
+//! sorting layer and is always drawn first.
+//!
+//! ```sh
+//! cargo run --example sprites
+//! ```
+
+use pollster::FutureExt;
+use r3d::{
+    event::{event_types, EventHandler},
+    gfx::{
+        Camera, CameraClearMode, CameraProjection, Color, Material, MaterialHandle, Sprite,
+        SpriteHandle, SpriteRenderer, SpriteSortAxis, SpriteTexelMapping, Texture, TextureHandle,
+        BUILT_IN_SHADER_SPRITE,
+    },
+    image::{DynamicImage, Rgba, RgbaImage},
+    math::{Vec2, Vec3},
+    specs::{Builder, WorldExt},
+    transform::TransformComponent,
+    wgpu::TextureFormat,
+    Engine, EngineConfig, EngineLoopMode, EngineTargetFps,
+};
+use std::error::Error;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let engine = Engine::new(EngineConfig {
+        title: "sprites".to_owned(),
+        resizable: true,
+        width: 800,
+        height: 600,
+        glyph_atlas: Default::default(),
+        rng_seed: None,
+        settings_app_name: None,
+        restore_window_state: false,
+        gfx: Default::default(),
+    })
+    .block_on()?;
+    let ctx = engine.context();
+
+    // A 96x32 atlas: a tree, a character and a plain tile for the ground.
+    let atlas = {
+        let image = RgbaImage::from_fn(96, 32, |x, y| {
+            let (tile, x, y) = (x / 32, (x % 32) as i32, y as i32);
+            match tile {
+                0 if (x - 16).pow(2) + (y - 12).pow(2) < 121 => Rgba([40, 150, 60, 255]),
+                0 if (14..18).contains(&x) && 20 <= y => Rgba([110, 70, 30, 255]),
+                1 if (8..24).contains(&x) && (4..30).contains(&y) => Rgba([220, 200, 170, 255]),
+                2 => Rgba([255, 255, 255, 255]),
+                _ => Rgba([0, 0, 0, 0]),
+            }
+        });
+        Texture::from_image(
+            TextureFormat::Rgba8UnormSrgb,
+            &DynamicImage::ImageRgba8(image),
+            &ctx.gfx_ctx().device,
+            &ctx.gfx_ctx().queue,
+        )
+    };
+    let atlas = TextureHandle::new(atlas);
+    let tree = SpriteHandle::new(Sprite::new(
+        atlas.clone(),
+        SpriteTexelMapping::new(0, 32, 0, 32),
+    ));
+    let character = SpriteHandle::new(Sprite::new(
+        atlas.clone(),
+        SpriteTexelMapping::new(32, 64, 0, 32),
+    ));
+    let ground = SpriteHandle::new(Sprite::new(atlas, SpriteTexelMapping::new(66, 94, 2, 30)));
+
+    let material = MaterialHandle::new(Material::new(
+        ctx.built_in_shader_mgr()
+            .find_shader(BUILT_IN_SHADER_SPRITE)
+            .unwrap(),
+        ctx.render_mgr_mut().pipeline_layout_cache(),
+    ));
+
+    let mut camera_component = Camera::new(
+        0xFFFF_FFFF,
+        0,
+        CameraClearMode::all(Color::from_rgb(0.2, 0.3, 0.4), 1.0, 0),
+        CameraProjection::orthographic(8.0, -100.0, 100.0),
+        &ctx.gfx_ctx().device,
+        ctx.render_mgr_mut().bind_group_layout_cache(),
+    );
+    camera_component.sprite_sort_axis = SpriteSortAxis::World(Vec3::UP);
+
+    let make_sprite = |sprite: &SpriteHandle, sorting_layer: i32, color: Color| {
+        let mut renderer = SpriteRenderer::new();
+        renderer.set_material(material.clone());
+        renderer.set_sprite(
+            sprite.clone(),
+            &ctx.gfx_ctx().device,
+            ctx.render_mgr_mut().bind_group_layout_cache(),
+        );
+        renderer.set_sorting_layer(sorting_layer);
+        renderer.set_pixels_per_unit(16.0);
+        renderer.set_pivot(Vec2::new(0.5, 0.0));
+        renderer.set_color(color);
+        renderer
+    };
+
+    let spawn = |name: &str, renderer: Option<SpriteRenderer>, position: Vec3| {
+        let object = {
+            let mut object_mgr = ctx.object_mgr_mut();
+            let mut world = ctx.world_mut();
+            let (object, builder) =
+                object_mgr.create_object_builder(&mut world, Some(name.to_owned()), None);
+            match renderer {
+                Some(renderer) => builder.with(renderer).build(),
+                None => builder.build(),
+            };
+            object
+        };
+        object
+            .component::<TransformComponent>()
+            .set_position(position);
+        object
+    };
+
+    let camera = spawn("camera", None, Vec3::new(0.0, 0.0, 10.0));
+    ctx.world()
+        .write_component::<Camera>()
+        .insert(camera.entity, camera_component)?;
+
+    let ground = spawn(
+        "ground",
+        Some(make_sprite(&ground, -1, Color::from_rgb(0.35, 0.55, 0.25))),
+        Vec3::new(0.0, -3.0, 0.0),
+    );
+    ground
+        .component::<TransformComponent>()
+        .set_scale(Vec3::new(8.0, 0.25, 1.0));
+
+    for (index, position) in [
+        Vec3::new(-1.0, 0.5, 0.0),
+        Vec3::new(0.5, -0.5, 0.0),
+        Vec3::new(-0.5, -1.5, 0.0),
+        Vec3::new(1.0, -2.5, 0.0),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        spawn(
+            &format!("tree-{}", index),
+            Some(make_sprite(&tree, 0, Color::white())),
+            position,
+        );
+    }
+
+    let walker = spawn(
+        "walker",
+        Some(make_sprite(&character, 0, Color::white())),
+        Vec3::ZERO,
+    );
+
+    ctx.event_mgr()
+        .add_handler(EventHandler::<event_types::Update>::new(move |_| {
+            let time = r3d::use_context().time_mgr().time().as_secs_f32();
+            walker
+                .component::<TransformComponent>()
+                .set_position(Vec3::new(
+                    (time * 0.7).sin() * 1.5,
+                    (time * 0.5).sin() * 2.0 - 1.0,
+                    0.0,
+                ));
+        }));
+
+    engine.run(EngineLoopMode::Poll, EngineTargetFps::VSync)?;
+    Ok(())
+}
@@ -0,0 +1,134 @@
+//! Lights a grid of spheres with the standard shader.
+//!
+//! The roughness increases from left to right, and the metallic from bottom to top. The spheres share a
+//! material, and each overrides its surface by a property block. The light orbits around the grid.
+//!
+//! ```sh
+//! cargo run --example standard_shader
+//! ```
+
+use pollster::FutureExt;
+use r3d::{
+    event::{event_types, EventHandler},
+    gfx::{
+        Camera, CameraClearMode, CameraPerspectiveProjectionAspect, CameraProjection, Color,
+        MaterialHandle, Mesh, MeshHandle, MeshRenderer, StandardMaterialTextures, Texture,
+        TextureHandle,
+    },
+    image::{DynamicImage, Rgba, RgbaImage},
+    math::Vec3,
+    specs::{Builder, WorldExt},
+    transform::TransformComponent,
+    wgpu::TextureFormat,
+    Engine, EngineConfig, EngineLoopMode, EngineTargetFps,
+};
+use std::error::Error;
+
+const GRID_SIZE: usize = 5;
+const SPACING: f32 = 1.25;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let engine = Engine::new(EngineConfig {
+        title: "standard shader".to_owned(),
+        resizable: true,
+        width: 800,
+        height: 600,
+        glyph_atlas: Default::default(),
+        rng_seed: None,
+        settings_app_name: None,
+        restore_window_state: false,
+        gfx: Default::default(),
+    })
+    .block_on()?;
+    let ctx = engine.context();
+    let camera_position = Vec3::new(0.0, 0.0, 9.0);
+
+    let white = TextureHandle::new(Texture::from_image(
+        TextureFormat::Rgba8UnormSrgb,
+        &DynamicImage::ImageRgba8(RgbaImage::from_pixel(1, 1, Rgba([255, 255, 255, 255]))),
+        &ctx.gfx_ctx().device,
+        &ctx.gfx_ctx().queue,
+    ));
+    let material = {
+        let mut material = StandardMaterialTextures::new(white).create_material(false);
+        material.set_uniform_property("camera_position", camera_position);
+        material.flush_uniforms(&ctx.gfx_ctx().device, &ctx.gfx_ctx().queue);
+        material.update_bind_group(&ctx.gfx_ctx().device);
+        MaterialHandle::new(material)
+    };
+    let sphere = MeshHandle::new(Mesh::uv_sphere(0.5, 24, 48));
+
+    let camera_component = Camera::new(
+        0xFFFF_FFFF,
+        0,
+        CameraClearMode::all(Color::from_rgb(0.05, 0.05, 0.08), 1.0, 0),
+        CameraProjection::perspective(45.0, CameraPerspectiveProjectionAspect::Screen, 0.1, 100.0),
+        &ctx.gfx_ctx().device,
+        ctx.render_mgr_mut().bind_group_layout_cache(),
+    );
+    let camera = {
+        let mut object_mgr = ctx.object_mgr_mut();
+        let mut world = ctx.world_mut();
+        let (camera, builder) =
+            object_mgr.create_object_builder(&mut world, Some("camera".to_owned()), None);
+        builder.build();
+        camera
+    };
+    camera
+        .component::<TransformComponent>()
+        .set_position(camera_position);
+    ctx.world()
+        .write_component::<Camera>()
+        .insert(camera.entity, camera_component)?;
+
+    let offset = (GRID_SIZE - 1) as f32 * SPACING * 0.5;
+
+    for row in 0..GRID_SIZE {
+        for column in 0..GRID_SIZE {
+            let roughness = (column as f32 + 0.5) / GRID_SIZE as f32;
+            let metallic = row as f32 / (GRID_SIZE - 1) as f32;
+
+            let mut renderer = MeshRenderer::new();
+            renderer.set_material(material.clone());
+            renderer.set_mesh(sphere.clone(), &ctx.gfx_ctx().device);
+            let property_block = renderer.property_block_mut();
+            property_block.set_uniform("base_color", [0.9, 0.6, 0.3, 1.0]);
+            property_block.set_uniform("roughness", roughness);
+            property_block.set_uniform("metallic", metallic);
+
+            let object = {
+                let mut object_mgr = ctx.object_mgr_mut();
+                let mut world = ctx.world_mut();
+                let (object, builder) = object_mgr.create_object_builder(
+                    &mut world,
+                    Some(format!("sphere-{}-{}", row, column)),
+                    None,
+                );
+                builder.with(renderer).build();
+                object
+            };
+            object
+                .component::<TransformComponent>()
+                .set_position(Vec3::new(
+                    column as f32 * SPACING - offset,
+                    row as f32 * SPACING - offset,
+                    0.0,
+                ));
+        }
+    }
+
+    ctx.event_mgr()
+        .add_handler(EventHandler::<event_types::Update>::new(move |_| {
+            let ctx = r3d::use_context();
+            let time = ctx.time_mgr().time().as_secs_f32() * 0.5;
+            let mut material = material.write();
+            material.set_uniform_property(
+                "light_direction",
+                Vec3::new(time.cos(), -0.6, time.sin() - 1.0),
+            );
+            material.flush_uniforms(&ctx.gfx_ctx().device, &ctx.gfx_ctx().queue);
+        }));
+
+    engine.run(EngineLoopMode::Poll, EngineTargetFps::VSync)?;
+    Ok(())
+}
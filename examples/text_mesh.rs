@@ -0,0 +1,115 @@
+//! Draws extruded text in the scene, lit by the standard shader and rotating.
+//!
+//! ```sh
+//! cargo run --example text_mesh --features text-mesh
+//! ```
+
+use pollster::FutureExt;
+use r3d::{
+    event::{event_types, EventHandler},
+    fontdue::layout::HorizontalAlign,
+    gfx::{
+        Camera, CameraClearMode, CameraPerspectiveProjectionAspect, CameraProjection, Color,
+        MaterialHandle, MeshRenderer, StandardMaterialTextures, TextMeshFont, TextMeshFontHandle,
+        TextMeshRenderer, Texture, TextureHandle,
+    },
+    image::{DynamicImage, Rgba, RgbaImage},
+    math::{Quat, Vec3},
+    specs::{Builder, WorldExt},
+    transform::TransformComponent,
+    wgpu::TextureFormat,
+    Engine, EngineConfig, EngineLoopMode, EngineTargetFps,
+};
+use std::error::Error;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let engine = Engine::new(EngineConfig {
+        title: "text mesh".to_owned(),
+        resizable: true,
+        width: 800,
+        height: 600,
+        glyph_atlas: Default::default(),
+        rng_seed: None,
+        settings_app_name: None,
+        restore_window_state: false,
+        gfx: Default::default(),
+    })
+    .block_on()?;
+    let ctx = engine.context();
+    let camera_position = Vec3::new(0.0, 0.0, 6.0);
+
+    let white = TextureHandle::new(Texture::from_image(
+        TextureFormat::Rgba8UnormSrgb,
+        &DynamicImage::ImageRgba8(RgbaImage::from_pixel(1, 1, Rgba([255, 255, 255, 255]))),
+        &ctx.gfx_ctx().device,
+        &ctx.gfx_ctx().queue,
+    ));
+    let material = {
+        let mut material = StandardMaterialTextures::new(white).create_material(false);
+        material.set_uniform_property("camera_position", camera_position);
+        material.set_uniform_property("light_direction", Vec3::new(-0.4, -0.6, -1.0));
+        material.flush_uniforms(&ctx.gfx_ctx().device, &ctx.gfx_ctx().queue);
+        material.update_bind_group(&ctx.gfx_ctx().device);
+        MaterialHandle::new(material)
+    };
+    let font = TextMeshFontHandle::new(TextMeshFont::from_bytes(
+        include_bytes!("../r3d-editor/assets/fonts/NotoSans-Regular.ttf").as_slice(),
+    )?);
+
+    let camera_component = Camera::new(
+        0xFFFF_FFFF,
+        0,
+        CameraClearMode::all(Color::from_rgb(0.05, 0.05, 0.08), 1.0, 0),
+        CameraProjection::perspective(45.0, CameraPerspectiveProjectionAspect::Screen, 0.1, 100.0),
+        &ctx.gfx_ctx().device,
+        ctx.render_mgr_mut().bind_group_layout_cache(),
+    );
+    let camera = {
+        let mut object_mgr = ctx.object_mgr_mut();
+        let mut world = ctx.world_mut();
+        let (camera, builder) =
+            object_mgr.create_object_builder(&mut world, Some("camera".to_owned()), None);
+        builder.build();
+        camera
+    };
+    camera
+        .component::<TransformComponent>()
+        .set_position(camera_position);
+    ctx.world()
+        .write_component::<Camera>()
+        .insert(camera.entity, camera_component)?;
+
+    let mut renderer = MeshRenderer::new();
+    renderer.set_material(material);
+    renderer
+        .property_block_mut()
+        .set_uniform("base_color", [0.9, 0.6, 0.3, 1.0]);
+
+    let mut text = TextMeshRenderer::new(font, "R3D", 2.0);
+    text.set_depth(0.4);
+    text.set_horizontal_align(HorizontalAlign::Center);
+
+    let title = {
+        let mut object_mgr = ctx.object_mgr_mut();
+        let mut world = ctx.world_mut();
+        let (title, builder) =
+            object_mgr.create_object_builder(&mut world, Some("title".to_owned()), None);
+        builder.with(renderer).with(text).build();
+        title
+    };
+    // Centers the caps vertically; the text starts on its baseline.
+    title
+        .component::<TransformComponent>()
+        .set_position(Vec3::new(0.0, -0.7, 0.0));
+
+    ctx.event_mgr()
+        .add_handler(EventHandler::<event_types::Update>::new(move |_| {
+            let time = r3d::use_context().time_mgr().time().as_secs_f32();
+            title
+                .component::<TransformComponent>()
+                .set_rotation(Quat::from_axis_angle(Vec3::UP, time * 0.8));
+        }));
+
+    engine.run(EngineLoopMode::Poll, EngineTargetFps::VSync)?;
+    Ok(())
+}
@@ -0,0 +1,147 @@
+use std::{
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Reads the files of assets for a loader, so that they can come from somewhere other than the file system.
+pub trait AssetIo {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+}
+
+/// Reads assets from the file system. This is the default of the loaders.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FileAssetIo;
+
+impl AssetIo for FileAssetIo {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        std::fs::read(path)
+    }
+}
+
+/// Reads assets over HTTP on the web, relative to a base URL. The browser only fetches asynchronously while the
+/// loaders are synchronous, so the assets are [`fetch`](Self::fetch)ed up front, e.g. before the engine runs, and
+/// read from memory afterwards.
+#[derive(Debug, Clone)]
+pub struct FetchAssetIo {
+    base_url: String,
+    files: HashMap<PathBuf, Vec<u8>>,
+}
+
+impl FetchAssetIo {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            files: HashMap::new(),
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// The URL that the file at the path is fetched from.
+    pub fn url(&self, path: &Path) -> String {
+        format!(
+            "{}/{}",
+            self.base_url.trim_end_matches('/'),
+            path.to_string_lossy().trim_start_matches('/')
+        )
+    }
+
+    /// Keeps the content of the file at the path to be read, e.g. for files that are embedded in the binary.
+    pub fn insert(&mut self, path: impl Into<PathBuf>, content: Vec<u8>) {
+        self.files.insert(path.into(), content);
+    }
+
+    /// Downloads the file at the path, relative to the base URL, and keeps it to be read.
+    #[cfg(target_arch = "wasm32")]
+    pub async fn fetch(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        use wasm_bindgen::JsCast;
+        use wasm_bindgen_futures::JsFuture;
+        use web_sys::Response;
+
+        let path = path.as_ref();
+        let url = self.url(path);
+        let window = web_sys::window()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "no browser window"))?;
+        let response = JsFuture::from(window.fetch_with_str(&url))
+            .await
+            .map_err(js_error)?;
+        let response: Response = response.dyn_into().map_err(js_error)?;
+
+        if !response.ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("failed to fetch `{}`: status {}", url, response.status()),
+            ));
+        }
+
+        let buffer = JsFuture::from(response.array_buffer().map_err(js_error)?)
+            .await
+            .map_err(js_error)?;
+        self.insert(path, js_sys::Uint8Array::new(&buffer).to_vec());
+
+        Ok(())
+    }
+}
+
+impl AssetIo for FetchAssetIo {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.files.get(path).cloned().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("`{}` has not been fetched", path.display()),
+            )
+        })
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn js_error(err: wasm_bindgen::JsValue) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, format!("{:?}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_io_reads_from_file_system() {
+        let path = std::env::temp_dir().join(format!("r3d-asset-io-{}", std::process::id()));
+        std::fs::write(&path, b"content").unwrap();
+
+        assert_eq!(FileAssetIo.read(&path).unwrap(), b"content");
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            FileAssetIo.read(&path).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+    }
+
+    #[test]
+    fn fetch_io_urls_are_relative_to_base_url() {
+        let io = FetchAssetIo::new("https://example.com/assets/");
+        assert_eq!(io.base_url(), "https://example.com/assets/");
+        assert_eq!(
+            io.url(Path::new("textures/wood.png")),
+            "https://example.com/assets/textures/wood.png"
+        );
+        assert_eq!(
+            io.url(Path::new("/shaders/sprite.wgsl")),
+            "https://example.com/assets/shaders/sprite.wgsl"
+        );
+    }
+
+    #[test]
+    fn fetch_io_reads_only_kept_files() {
+        let mut io = FetchAssetIo::new("assets");
+        io.insert("fonts/default.ttf", vec![1, 2, 3]);
+
+        assert_eq!(io.read(Path::new("fonts/default.ttf")).unwrap(), [1, 2, 3]);
+
+        let err = io.read(Path::new("fonts/other.ttf")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert_eq!(err.to_string(), "`fonts/other.ttf` has not been fetched");
+    }
+}
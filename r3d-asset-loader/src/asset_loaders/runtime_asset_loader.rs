@@ -1,13 +1,14 @@
-use crate::{AssetDatabase, AssetLoadError, AssetLoader};
+use crate::{AssetDatabase, AssetIo, AssetLoadError, AssetLoader, FileAssetIo};
 use asset::{AssetKey, AssetSource, GfxBridge, TypedAsset};
 use asset_pipeline::{
-    deduce_asset_type_from_path, process_asset, PipelineGfxBridge, TypedAssetSource,
+    deduce_asset_type_from_path, process_asset_bytes, PipelineGfxBridge, TypedAssetSource,
 };
-use std::collections::HashMap;
+use std::{collections::HashMap, path::Path};
 
 pub struct RuntimeAssetLoader {
     gfx_bridge: Box<dyn GfxBridge>,
     pipeline_gfx_bridge: Box<dyn PipelineGfxBridge>,
+    io: Box<dyn AssetIo>,
 }
 
 impl RuntimeAssetLoader {
@@ -18,8 +19,16 @@ impl RuntimeAssetLoader {
         Self {
             gfx_bridge: Box::new(gfx_bridge),
             pipeline_gfx_bridge: Box::new(pipeline_gfx_bridge),
+            io: Box::new(FileAssetIo),
         }
     }
+
+    /// Reads the assets through the given IO instead of the file system, e.g. with `FetchAssetIo` on the web.
+    /// Materials and shaders with `#include`s still read the files they refer to from the file system.
+    pub fn with_io(mut self, io: impl AssetIo + 'static) -> Self {
+        self.io = Box::new(io);
+        self
+    }
 }
 
 impl AssetLoader for RuntimeAssetLoader {
@@ -34,8 +43,9 @@ impl AssetLoader for RuntimeAssetLoader {
                 let data = database
                     .find_asset_by_id(id)
                     .ok_or_else(|| AssetLoadError::AssetNotFound(id))?;
-                let processed = process_asset(
+                let processed = process_asset_bytes(
                     &data.path,
+                    self.io.read(&data.path)?,
                     data.asset_type,
                     Some(&data.metadata_content),
                     &*self.pipeline_gfx_bridge,
@@ -45,8 +55,9 @@ impl AssetLoader for RuntimeAssetLoader {
             }
             AssetKey::Path(path) => {
                 let asset_type = deduce_asset_type_from_path(path)?;
-                let processed = process_asset(
+                let processed = process_asset_bytes(
                     path,
+                    self.io.read(Path::new(path))?,
                     asset_type,
                     None as Option<&str>,
                     &*self.pipeline_gfx_bridge,
@@ -61,7 +72,9 @@ impl AssetLoader for RuntimeAssetLoader {
             TypedAssetSource::Font(source) => source.dependencies(),
             TypedAssetSource::Material(source) => source.dependencies(),
             TypedAssetSource::Model(source) => source.dependencies(),
+            TypedAssetSource::Script(source) => source.dependencies(),
             TypedAssetSource::Shader(source) => source.dependencies(),
+            TypedAssetSource::StringTable(source) => source.dependencies(),
             TypedAssetSource::Texture(source) => source.dependencies(),
         };
         let deps = deps
@@ -83,9 +96,15 @@ impl AssetLoader for RuntimeAssetLoader {
             TypedAssetSource::Model(source) => {
                 TypedAsset::Model(source.load(key.clone(), &deps, &*self.gfx_bridge)?)
             }
+            TypedAssetSource::Script(source) => {
+                TypedAsset::Script(source.load(key.clone(), &deps, &*self.gfx_bridge)?)
+            }
             TypedAssetSource::Shader(source) => {
                 TypedAsset::Shader(source.load(key.clone(), &deps, &*self.gfx_bridge)?)
             }
+            TypedAssetSource::StringTable(source) => {
+                TypedAsset::StringTable(source.load(key.clone(), &deps, &*self.gfx_bridge)?)
+            }
             TypedAssetSource::Texture(source) => {
                 TypedAsset::Texture(source.load(key.clone(), &deps, &*self.gfx_bridge)?)
             }
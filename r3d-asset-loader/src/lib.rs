@@ -1,6 +1,8 @@
 mod asset_database;
+mod asset_io;
 mod asset_loader;
 pub mod asset_loaders;
 
 pub use asset_database::*;
+pub use asset_io::*;
 pub use asset_loader::*;
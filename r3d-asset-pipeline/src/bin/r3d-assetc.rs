@@ -0,0 +1,1061 @@
+//! The asset compiler, which processes assets without the engine or the editor, e.g. in CI and build scripts.
+//!
+//! ```sh
+//! r3d-assetc [--json] [--semantics <table.toml>] <command>
+//! ```
+//!
+//! - `build <src_dir> <out_dir>` processes the assets of the source directory into the output directory. Assets
+//!   whose files, metadata and dependencies are unchanged since the last build are skipped.
+//! - `watch <src_dir>` re-processes the assets of the source directory as their files change.
+//! - `inspect <asset>` prints the processed source of an asset as JSON.
+//! - `validate <src_dir>` validates the metadata of the assets of the source directory.
+//!
+//! An asset is a file with a sibling metadata file, e.g. `brick.png` with `brick.meta.toml`. The semantic table
+//! is described at [`HeadlessPipelineGfxBridge::from_toml`]. With `--json`, every line of the output is a JSON
+//! object with an `event` field. The exit code is 1 if any asset failed, and 2 if the arguments are invalid.
+
+use asset::{AssetKey, AssetType};
+use asset_pipeline::{
+    deduce_asset_type_from_path, process_asset, validate_metadata, HeadlessPipelineGfxBridge,
+    TypedAssetSource,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::{Duration, UNIX_EPOCH},
+};
+use uuid::Uuid;
+
+const USAGE: &str = "usage: r3d-assetc [--json] [--semantics <table.toml>] <command>
+
+commands:
+  build <src_dir> <out_dir>  process the changed assets into the output directory
+  watch <src_dir>            re-process the assets as they change
+  inspect <asset>            print the processed source of an asset as JSON
+  validate <src_dir>         validate the metadata of the assets";
+
+/// The file in the output directory that records the inputs of the built assets.
+const CACHE_FILE_NAME: &str = ".assetc-cache.json";
+/// The file in the output directory that lists the built assets and their dependencies.
+const MANIFEST_FILE_NAME: &str = "manifest.json";
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    let reporter = Reporter { json: options.json };
+    let bridge = match &options.semantics {
+        Some(path) => match std::fs::read_to_string(path)
+            .map_err(|err| err.to_string())
+            .and_then(|content| {
+                HeadlessPipelineGfxBridge::from_toml(content).map_err(|err| err.to_string())
+            }) {
+            Ok(bridge) => bridge,
+            Err(err) => {
+                reporter.error(
+                    Some(path),
+                    format!("failed to load semantic table: {}", err),
+                );
+                return ExitCode::from(2);
+            }
+        },
+        None => HeadlessPipelineGfxBridge::new(),
+    };
+
+    let result = match &options.command {
+        Command::Build { src_dir, out_dir } => {
+            build(src_dir, out_dir, &bridge, &reporter).map(|summary| summary.failed == 0)
+        }
+        Command::Watch { src_dir } => watch(src_dir, &bridge, &reporter).map(|_| true),
+        Command::Inspect { asset } => Ok(inspect(asset, &bridge, &reporter)),
+        Command::Validate { src_dir } => {
+            validate(src_dir, &reporter).map(|summary| summary.errors == 0)
+        }
+    };
+
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            reporter.error(None, err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Build { src_dir: PathBuf, out_dir: PathBuf },
+    Watch { src_dir: PathBuf },
+    Inspect { asset: PathBuf },
+    Validate { src_dir: PathBuf },
+}
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    json: bool,
+    semantics: Option<PathBuf>,
+    command: Command,
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+    let mut json = false;
+    let mut semantics = None;
+    let mut positionals = Vec::new();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--semantics" => {
+                let path = args.next().ok_or("`--semantics` requires a file")?;
+                semantics = Some(PathBuf::from(path));
+            }
+            _ if arg.starts_with("--") => return Err(format!("unknown option `{}`", arg)),
+            _ => positionals.push(arg),
+        }
+    }
+
+    let (command, operands) = match positionals.split_first() {
+        Some((command, operands)) => (command.as_str(), operands),
+        None => return Err("no command is given".to_owned()),
+    };
+    let command = match (command, operands) {
+        ("build", [src_dir, out_dir]) => Command::Build {
+            src_dir: src_dir.into(),
+            out_dir: out_dir.into(),
+        },
+        ("watch", [src_dir]) => Command::Watch {
+            src_dir: src_dir.into(),
+        },
+        ("inspect", [asset]) => Command::Inspect {
+            asset: asset.into(),
+        },
+        ("validate", [src_dir]) => Command::Validate {
+            src_dir: src_dir.into(),
+        },
+        ("build" | "watch" | "inspect" | "validate", _) => {
+            return Err(format!("wrong number of arguments for `{}`", command))
+        }
+        _ => return Err(format!("unknown command `{}`", command)),
+    };
+
+    Ok(Options {
+        json,
+        semantics,
+        command,
+    })
+}
+
+/// Prints the progress either for humans or as JSON lines.
+struct Reporter {
+    json: bool,
+}
+
+impl Reporter {
+    /// Prints an event. The fields must be an object; they are only printed in the JSON mode.
+    fn event(&self, event: &str, fields: Value, message: impl FnOnce() -> String) {
+        if self.json {
+            print_json_event(event, fields);
+        } else {
+            println!("{}", message());
+        }
+    }
+
+    /// Prints an error, which goes to the standard error unless in the JSON mode.
+    fn error(&self, path: Option<&Path>, message: impl ToString) {
+        let message = message.to_string();
+
+        if self.json {
+            print_json_event("error", json!({ "path": path, "message": message }));
+        } else {
+            match path {
+                Some(path) => eprintln!("error: {}: {}", path.display(), message),
+                None => eprintln!("error: {}", message),
+            }
+        }
+    }
+}
+
+fn print_json_event(event: &str, fields: Value) {
+    let mut object = serde_json::Map::new();
+    object.insert("event".to_owned(), event.into());
+
+    if let Value::Object(fields) = fields {
+        object.extend(fields);
+    }
+
+    println!("{}", Value::Object(object));
+}
+
+/// A file that has a metadata file next to it.
+#[derive(Debug, Clone)]
+struct SourceAsset {
+    path: PathBuf,
+    metadata_path: PathBuf,
+    asset_type: AssetType,
+}
+
+fn metadata_path_of(path: &Path) -> PathBuf {
+    path.with_extension("meta.toml")
+}
+
+fn is_metadata_path(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.ends_with(".meta.toml"))
+}
+
+/// Collects the files of the directory and its subdirectories, sorted by path.
+fn collect_files(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    fn collect(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+
+            if path.is_dir() {
+                collect(&path, files)?;
+            } else if path.is_file() {
+                files.push(path);
+            }
+        }
+
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+    collect(dir, &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+/// Returns the files of known types that have metadata. Files without metadata, e.g. shader includes, are not
+/// assets on their own.
+fn find_assets(files: &[PathBuf]) -> Vec<SourceAsset> {
+    let files = HashSet::<&PathBuf>::from_iter(files);
+    let mut assets = Vec::from_iter(files.iter().filter_map(|path| {
+        if is_metadata_path(path) {
+            return None;
+        }
+
+        let asset_type = deduce_asset_type_from_path(path).ok()?;
+        let metadata_path = metadata_path_of(path);
+
+        if !files.contains(&metadata_path) {
+            return None;
+        }
+
+        Some(SourceAsset {
+            path: (*path).clone(),
+            metadata_path,
+            asset_type,
+        })
+    }));
+    assets.sort_by(|a, b| a.path.cmp(&b.path));
+    assets
+}
+
+/// The ids of the assets, which are read from their metadata.
+#[derive(Default)]
+struct AssetIds {
+    /// The id of each asset, or `None` if its metadata is invalid or its id is a duplicate.
+    ids: Vec<Option<Uuid>>,
+    indices: HashMap<Uuid, usize>,
+    failed: usize,
+}
+
+/// Reads the ids of the assets. Assets with invalid metadata or duplicate ids are reported and left out.
+fn read_asset_ids(assets: &[SourceAsset], reporter: &Reporter) -> AssetIds {
+    let mut ids = AssetIds::default();
+
+    for (index, asset) in assets.iter().enumerate() {
+        let id = std::fs::read_to_string(&asset.metadata_path)
+            .map_err(|err| err.to_string())
+            .and_then(|content| {
+                validate_metadata(asset.asset_type, content).map_err(|err| err.to_string())
+            });
+
+        let id = match id {
+            Ok(id) => match ids.indices.get(&id) {
+                Some(other) => {
+                    reporter.error(
+                        Some(&asset.metadata_path),
+                        format!(
+                            "the id {} is also used by {}",
+                            id,
+                            assets[*other].path.display()
+                        ),
+                    );
+                    None
+                }
+                None => {
+                    ids.indices.insert(id, index);
+                    Some(id)
+                }
+            },
+            Err(err) => {
+                reporter.error(Some(&asset.metadata_path), err);
+                None
+            }
+        };
+
+        if id.is_none() {
+            ids.failed += 1;
+        }
+
+        ids.ids.push(id);
+    }
+
+    ids
+}
+
+/// The processed source of an asset, with the files that it was processed from.
+struct Import {
+    source: TypedAssetSource,
+    /// The file and metadata of the asset, and the files of its dependencies and includes.
+    inputs: Vec<PathBuf>,
+}
+
+fn import_asset(
+    asset: &SourceAsset,
+    assets: &[SourceAsset],
+    ids: &AssetIds,
+    bridge: &HeadlessPipelineGfxBridge,
+) -> Result<Import, String> {
+    let metadata_content =
+        std::fs::read_to_string(&asset.metadata_path).map_err(|err| err.to_string())?;
+    let source = process_asset(
+        &asset.path,
+        asset.asset_type,
+        Some(metadata_content),
+        bridge,
+    )
+    .map_err(|err| err.to_string())?;
+
+    let mut keys = source.dependencies();
+
+    match &source {
+        TypedAssetSource::Shader(shader) => keys.extend(shader.includes.iter().cloned()),
+        TypedAssetSource::Material(material) => keys.extend(material.includes.iter().cloned()),
+        _ => {}
+    }
+
+    let mut inputs = vec![asset.path.clone(), asset.metadata_path.clone()];
+
+    for key in keys {
+        let paths = match key {
+            AssetKey::Id(id) => match ids.indices.get(&id) {
+                Some(index) => vec![
+                    assets[*index].path.clone(),
+                    assets[*index].metadata_path.clone(),
+                ],
+                None => vec![],
+            },
+            AssetKey::Path(path) => {
+                let path = PathBuf::from(path);
+                let metadata_path = metadata_path_of(&path);
+                vec![path, metadata_path]
+            }
+        };
+
+        for path in paths {
+            if path.is_file() && !inputs.contains(&path) {
+                inputs.push(path);
+            }
+        }
+    }
+
+    Ok(Import { source, inputs })
+}
+
+/// The modification time and the length of a file, which are compared to detect changes.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    modified: u64,
+    len: u64,
+}
+
+impl FileStamp {
+    fn of(path: &Path) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+
+        Some(Self {
+            modified: modified.as_nanos() as u64,
+            len: metadata.len(),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct BuildCache {
+    /// The built assets, by their paths relative to the source directory.
+    assets: BTreeMap<PathBuf, BuildCacheEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BuildCacheEntry {
+    id: Uuid,
+    asset_type: String,
+    /// The path of the built source, relative to the output directory.
+    output: PathBuf,
+    inputs: BTreeMap<PathBuf, FileStamp>,
+    dependencies: Vec<AssetKey>,
+}
+
+impl BuildCacheEntry {
+    fn is_fresh(&self, id: Uuid, out_dir: &Path) -> bool {
+        self.id == id
+            && out_dir.join(&self.output).is_file()
+            && self
+                .inputs
+                .iter()
+                .all(|(path, stamp)| FileStamp::of(path) == Some(*stamp))
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct BuildSummary {
+    built: usize,
+    up_to_date: usize,
+    failed: usize,
+    removed: usize,
+}
+
+fn asset_type_name(asset_type: AssetType) -> &'static str {
+    match asset_type {
+        AssetType::Font => "font",
+        AssetType::Material => "material",
+        AssetType::Model => "model",
+        AssetType::Script => "script",
+        AssetType::Shader => "shader",
+        AssetType::StringTable => "string_table",
+        AssetType::Texture => "texture",
+    }
+}
+
+/// Processes the changed assets into `<id>.bin` files of the output directory, and writes the manifest that
+/// lists them. The outputs of assets that no longer exist are removed.
+fn build(
+    src_dir: &Path,
+    out_dir: &Path,
+    bridge: &HeadlessPipelineGfxBridge,
+    reporter: &Reporter,
+) -> Result<BuildSummary, String> {
+    std::fs::create_dir_all(out_dir).map_err(|err| err.to_string())?;
+
+    let cache_path = out_dir.join(CACHE_FILE_NAME);
+    let mut cache = std::fs::read(&cache_path)
+        .ok()
+        .and_then(|content| serde_json::from_slice::<BuildCache>(&content).ok())
+        .unwrap_or_default();
+
+    let files = collect_files(src_dir).map_err(|err| err.to_string())?;
+    let assets = find_assets(&files);
+    let ids = read_asset_ids(&assets, reporter);
+    let mut summary = BuildSummary {
+        failed: ids.failed,
+        ..Default::default()
+    };
+    let mut built_paths = HashSet::new();
+
+    for (index, asset) in assets.iter().enumerate() {
+        let id = match ids.ids[index] {
+            Some(id) => id,
+            None => continue,
+        };
+        let relative_path = asset
+            .path
+            .strip_prefix(src_dir)
+            .unwrap_or(&asset.path)
+            .to_path_buf();
+        let progress = format!("[{}/{}]", index + 1, assets.len());
+        built_paths.insert(relative_path.clone());
+
+        if let Some(entry) = cache.assets.get(&relative_path) {
+            if entry.is_fresh(id, out_dir) {
+                summary.up_to_date += 1;
+                reporter.event("up_to_date", json!({ "path": relative_path }), || {
+                    format!("{} up to date {}", progress, relative_path.display())
+                });
+                continue;
+            }
+        }
+
+        let output = PathBuf::from(format!("{}.bin", id));
+        let result = import_asset(asset, &assets, &ids, bridge).and_then(|import| {
+            let file = File::create(out_dir.join(&output)).map_err(|err| err.to_string())?;
+            let mut writer = BufWriter::new(file);
+            import
+                .source
+                .serialize_into(&mut writer)
+                .map_err(|err| err.to_string())?;
+            writer.flush().map_err(|err| err.to_string())?;
+            Ok(import)
+        });
+
+        match result {
+            Ok(import) => {
+                summary.built += 1;
+                reporter.event(
+                    "built",
+                    json!({ "path": relative_path, "id": id, "output": output }),
+                    || format!("{} built {}", progress, relative_path.display()),
+                );
+                cache.assets.insert(
+                    relative_path,
+                    BuildCacheEntry {
+                        id,
+                        asset_type: asset_type_name(asset.asset_type).to_owned(),
+                        output,
+                        inputs: BTreeMap::from_iter(
+                            import
+                                .inputs
+                                .into_iter()
+                                .filter_map(|path| FileStamp::of(&path).map(|stamp| (path, stamp))),
+                        ),
+                        dependencies: import.source.dependencies(),
+                    },
+                );
+            }
+            Err(err) => {
+                summary.failed += 1;
+                cache.assets.remove(&relative_path);
+                reporter.error(Some(&asset.path), err);
+            }
+        }
+    }
+
+    let removed_paths = Vec::from_iter(
+        cache
+            .assets
+            .keys()
+            .filter(|path| !built_paths.contains(*path))
+            .cloned(),
+    );
+
+    for path in removed_paths {
+        let entry = cache.assets.remove(&path).unwrap();
+        let _ = std::fs::remove_file(out_dir.join(&entry.output));
+        summary.removed += 1;
+        reporter.event("removed", json!({ "path": path, "id": entry.id }), || {
+            format!("removed {}", path.display())
+        });
+    }
+
+    let manifest = Vec::from_iter(cache.assets.iter().map(|(path, entry)| {
+        json!({
+            "id": entry.id,
+            "type": entry.asset_type,
+            "path": path,
+            "output": entry.output,
+            "dependencies": entry.dependencies,
+        })
+    }));
+    std::fs::write(
+        out_dir.join(MANIFEST_FILE_NAME),
+        serde_json::to_string_pretty(&manifest).unwrap(),
+    )
+    .map_err(|err| err.to_string())?;
+    std::fs::write(&cache_path, serde_json::to_string(&cache).unwrap())
+        .map_err(|err| err.to_string())?;
+
+    reporter.event(
+        "summary",
+        json!({
+            "built": summary.built,
+            "up_to_date": summary.up_to_date,
+            "failed": summary.failed,
+            "removed": summary.removed,
+        }),
+        || {
+            format!(
+                "{} built, {} up to date, {} failed, {} removed",
+                summary.built, summary.up_to_date, summary.failed, summary.removed
+            )
+        },
+    );
+
+    Ok(summary)
+}
+
+/// Processes the assets once, then polls the source directory and re-processes the assets whose inputs change.
+/// It does not return unless the directory cannot be read.
+fn watch(
+    src_dir: &Path,
+    bridge: &HeadlessPipelineGfxBridge,
+    reporter: &Reporter,
+) -> Result<(), String> {
+    let snapshot = || -> Result<HashMap<PathBuf, FileStamp>, String> {
+        let files = collect_files(src_dir).map_err(|err| err.to_string())?;
+        Ok(HashMap::from_iter(files.into_iter().filter_map(|path| {
+            FileStamp::of(&path).map(|stamp| (path, stamp))
+        })))
+    };
+
+    let mut stamps = snapshot()?;
+    // The inputs of the assets that are up to date; assets without inputs are (re-)processed.
+    let mut inputs = HashMap::<PathBuf, Vec<PathBuf>>::new();
+    let mut is_first = true;
+
+    loop {
+        let files = Vec::from_iter(stamps.keys().cloned());
+        let assets = find_assets(&files);
+        let ids = read_asset_ids(&assets, reporter);
+
+        for asset in &assets {
+            if inputs.contains_key(&asset.path) {
+                continue;
+            }
+
+            match import_asset(asset, &assets, &ids, bridge) {
+                Ok(import) => {
+                    if !is_first {
+                        reporter.event("reimported", json!({ "path": asset.path }), || {
+                            format!("reimported {}", asset.path.display())
+                        });
+                    }
+
+                    inputs.insert(asset.path.clone(), import.inputs);
+                }
+                Err(err) => {
+                    reporter.error(Some(&asset.path), err);
+                    inputs.insert(
+                        asset.path.clone(),
+                        vec![asset.path.clone(), asset.metadata_path.clone()],
+                    );
+                }
+            }
+        }
+
+        if is_first {
+            is_first = false;
+            reporter.event("watching", json!({ "assets": assets.len() }), || {
+                format!("watching {} assets", assets.len())
+            });
+        }
+
+        let changed = loop {
+            std::thread::sleep(WATCH_INTERVAL);
+
+            let current = snapshot()?;
+            let changed = HashSet::<PathBuf>::from_iter(
+                current
+                    .iter()
+                    .filter(|(path, stamp)| stamps.get(*path) != Some(*stamp))
+                    .map(|(path, _)| path.clone())
+                    .chain(
+                        stamps
+                            .keys()
+                            .filter(|path| !current.contains_key(*path))
+                            .cloned(),
+                    ),
+            );
+            stamps = current;
+
+            if !changed.is_empty() {
+                break changed;
+            }
+        };
+
+        inputs.retain(|_, asset_inputs| !asset_inputs.iter().any(|path| changed.contains(path)));
+    }
+}
+
+/// Prints the processed source of the asset as JSON. The metadata next to the asset is used if it exists.
+/// Returns whether the asset could be processed.
+fn inspect(path: &Path, bridge: &HeadlessPipelineGfxBridge, reporter: &Reporter) -> bool {
+    let result = deduce_asset_type_from_path(path)
+        .map_err(|err| err.to_string())
+        .and_then(|asset_type| {
+            let metadata_content = std::fs::read_to_string(metadata_path_of(path)).ok();
+            let metadata = metadata_content
+                .as_ref()
+                .map(|content| toml::from_str::<toml::Table>(content))
+                .transpose()
+                .map_err(|err| err.to_string())?;
+            let source = process_asset(path, asset_type, metadata_content.as_ref(), bridge)
+                .map_err(|err| err.to_string())?;
+            let mut serialized = Vec::new();
+            source
+                .serialize_into(&mut serialized)
+                .map_err(|err| err.to_string())?;
+
+            Ok(json!({
+                "path": path,
+                "type": asset_type_name(asset_type),
+                "metadata": metadata,
+                "dependencies": source.dependencies(),
+                "size": serialized.len(),
+                "stats": source_stats(&source),
+            }))
+        });
+
+    match result {
+        Ok(inspection) => {
+            if reporter.json {
+                println!("{}", inspection);
+            } else {
+                println!("{}", serde_json::to_string_pretty(&inspection).unwrap());
+            }
+
+            true
+        }
+        Err(err) => {
+            reporter.error(Some(path), err);
+            false
+        }
+    }
+}
+
+/// Returns the figures of the source that are worth a look, e.g. the size of a texture.
+fn source_stats(source: &TypedAssetSource) -> Value {
+    match source {
+        TypedAssetSource::Font(font) => json!({
+            "file_size": font.font_file.len(),
+            "sdf_font_size": font.sdf_font_size,
+            "sdf_inset": font.sdf_inset,
+            "sdf_radius": font.sdf_radius,
+            "sdf_cutoff": font.sdf_cutoff,
+        }),
+        TypedAssetSource::Material(material) => json!({
+            "shader": material.shader,
+            "defines": material.shader_variant.as_ref().map(|variant| &variant.defines),
+            "binding_props": material.binding_props.len(),
+            "instance_props": material.instance_props.len(),
+            "blend_mode": material.blend_mode,
+            "cull_mode": material.cull_mode,
+            "front_face": material.front_face,
+            "topology": material.topology,
+        }),
+        TypedAssetSource::Model(model) => json!({
+            "nodes": model.nodes.len(),
+            "meshes": model.meshes.len(),
+            "vertices": model.meshes.iter().map(|mesh| mesh.vertex_count as usize).sum::<usize>(),
+            "vertex_buffer_size": model.meshes.iter().map(|mesh| mesh.vertex_buffer.len()).sum::<usize>(),
+            "index_buffer_size": model.meshes.iter().map(|mesh| mesh.index_buffer.len()).sum::<usize>(),
+        }),
+        TypedAssetSource::Script(script) => json!({
+            "name": script.name,
+            "source_size": script.source.len(),
+        }),
+        TypedAssetSource::Shader(shader) => json!({
+            "vertex_entry_point": shader.reflection.vertex_entry_point,
+            "fragment_entry_point": shader.reflection.fragment_entry_point,
+            "globals": shader.reflection.globals.len(),
+            "vertex_inputs": shader.reflection.vertex_input.fields.len(),
+            "instance_inputs": shader.reflection.instance_input.fields.len(),
+            "outputs": shader.reflection.outputs.len(),
+            "defines": shader.defines,
+            "includes": shader.includes,
+        }),
+        TypedAssetSource::StringTable(table) => json!({
+            "locale": table.locale,
+            "entries": table.entries.len(),
+        }),
+        TypedAssetSource::Texture(texture) => json!({
+            "width": texture.width,
+            "height": texture.height,
+            "format": texture.format,
+            "texel_size": texture.texels.len(),
+            "sprites": texture.sprites.len(),
+            "nine_patches": texture.nine_patches.len(),
+        }),
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct ValidateSummary {
+    errors: usize,
+    warnings: usize,
+}
+
+/// Validates the metadata of the files of the directory against the schemas of their types. Metadata without a
+/// file and duplicate ids are errors; files of known types without metadata are warnings, as they may be
+/// included by other files.
+fn validate(src_dir: &Path, reporter: &Reporter) -> Result<ValidateSummary, String> {
+    let files = collect_files(src_dir).map_err(|err| err.to_string())?;
+    let mut summary = ValidateSummary::default();
+    let mut ids = HashMap::<Uuid, PathBuf>::new();
+    let mut claimed_metadata = HashSet::new();
+
+    let mut report = |severity: &str, path: &Path, message: String| {
+        match severity {
+            "error" => summary.errors += 1,
+            _ => summary.warnings += 1,
+        }
+
+        reporter.event(
+            "problem",
+            json!({ "severity": severity, "path": path, "message": message }),
+            || format!("{}: {}: {}", severity, path.display(), message),
+        );
+    };
+
+    for path in &files {
+        if is_metadata_path(path) {
+            continue;
+        }
+
+        let asset_type = match deduce_asset_type_from_path(path) {
+            Ok(asset_type) => asset_type,
+            Err(_) => continue,
+        };
+        let metadata_path = metadata_path_of(path);
+
+        if !files.contains(&metadata_path) {
+            report(
+                "warning",
+                path,
+                "no metadata; the file is not an asset".to_owned(),
+            );
+            continue;
+        }
+
+        if !claimed_metadata.insert(metadata_path.clone()) {
+            report(
+                "error",
+                path,
+                format!("the metadata {} is shared", metadata_path.display()),
+            );
+            continue;
+        }
+
+        let id = std::fs::read_to_string(&metadata_path)
+            .map_err(|err| err.to_string())
+            .and_then(|content| {
+                validate_metadata(asset_type, content).map_err(|err| err.to_string())
+            });
+
+        match id {
+            Ok(id) => {
+                if let Some(other) = ids.get(&id) {
+                    report(
+                        "error",
+                        &metadata_path,
+                        format!("the id {} is also used by {}", id, other.display()),
+                    );
+                } else {
+                    ids.insert(id, path.clone());
+                }
+            }
+            Err(err) => report("error", &metadata_path, err),
+        }
+    }
+
+    for path in files.iter().filter(|path| is_metadata_path(path)) {
+        if !claimed_metadata.contains(path) {
+            report("error", path, "metadata without an asset".to_owned());
+        }
+    }
+
+    reporter.event(
+        "summary",
+        json!({ "errors": summary.errors, "warnings": summary.warnings }),
+        || format!("{} errors, {} warnings", summary.errors, summary.warnings),
+    );
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &str) -> Result<Options, String> {
+        parse_args(args.split_whitespace().map(str::to_owned))
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("r3d-assetc-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn parse_commands_and_options() {
+        assert_eq!(
+            args("--json build assets out").unwrap(),
+            Options {
+                json: true,
+                semantics: None,
+                command: Command::Build {
+                    src_dir: "assets".into(),
+                    out_dir: "out".into()
+                }
+            }
+        );
+        assert_eq!(
+            args("inspect brick.png --semantics semantics.toml").unwrap(),
+            Options {
+                json: false,
+                semantics: Some("semantics.toml".into()),
+                command: Command::Inspect {
+                    asset: "brick.png".into()
+                }
+            }
+        );
+        assert!(args("").is_err());
+        assert!(args("build assets").is_err());
+        assert!(args("bundle assets out").is_err());
+        assert!(args("validate assets --verbose").is_err());
+        assert!(args("validate assets --semantics").is_err());
+    }
+
+    #[test]
+    fn build_skips_unchanged_assets() {
+        let dir = temp_dir("build");
+        let src_dir = dir.join("src");
+        let out_dir = dir.join("out");
+        std::fs::create_dir_all(src_dir.join("strings")).unwrap();
+        std::fs::write(src_dir.join("strings/en.lang"), "title = \"Adventure\"\n").unwrap();
+        std::fs::write(
+            src_dir.join("strings/en.meta.toml"),
+            "[asset]\nid = \"9c1d6e0a-3f4b-4a8e-b2d7-5e6f7a8b9c0d\"\n",
+        )
+        .unwrap();
+        std::fs::write(src_dir.join("strings/ko.lang"), "title = \"모험\"\n").unwrap();
+        std::fs::write(
+            src_dir.join("strings/ko.meta.toml"),
+            "[asset]\nid = \"1a2b3c4d-5e6f-4a7b-8c9d-0e1f2a3b4c5d\"\n",
+        )
+        .unwrap();
+
+        let reporter = Reporter { json: true };
+        let bridge = HeadlessPipelineGfxBridge::new();
+        let first = build(&src_dir, &out_dir, &bridge, &reporter).unwrap();
+        assert_eq!((first.built, first.up_to_date, first.failed), (2, 0, 0));
+        assert!(out_dir
+            .join("9c1d6e0a-3f4b-4a8e-b2d7-5e6f7a8b9c0d.bin")
+            .is_file());
+
+        std::fs::write(
+            src_dir.join("strings/en.lang"),
+            "title = \"Adventure\"\nstart = \"Start\"\n",
+        )
+        .unwrap();
+        std::fs::remove_file(src_dir.join("strings/ko.meta.toml")).unwrap();
+        let second = build(&src_dir, &out_dir, &bridge, &reporter).unwrap();
+
+        let third = build(&src_dir, &out_dir, &bridge, &reporter).unwrap();
+        let manifest: Value =
+            serde_json::from_slice(&std::fs::read(out_dir.join(MANIFEST_FILE_NAME)).unwrap())
+                .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            second,
+            BuildSummary {
+                built: 1,
+                up_to_date: 0,
+                failed: 0,
+                removed: 1
+            }
+        );
+        assert_eq!(
+            third,
+            BuildSummary {
+                built: 0,
+                up_to_date: 1,
+                failed: 0,
+                removed: 0
+            }
+        );
+        assert_eq!(manifest[0]["type"], "string_table");
+        assert_eq!(manifest[0]["id"], "9c1d6e0a-3f4b-4a8e-b2d7-5e6f7a8b9c0d");
+    }
+
+    #[test]
+    fn build_rebuilds_material_when_shader_include_changes() {
+        let dir = temp_dir("include");
+        let src_dir = dir.join("src");
+        let out_dir = dir.join("out");
+        std::fs::create_dir_all(src_dir.join("common")).unwrap();
+        std::fs::write(
+            src_dir.join("common/color.wgsl"),
+            "fn base_color() -> vec4<f32> {\n    return vec4<f32>(1.0);\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            src_dir.join("lit.wgsl"),
+            "#include \"common/color.wgsl\"\n\
+            @vertex\nfn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {\n    \
+            return vec4<f32>(f32(index), 0.0, 0.0, 1.0);\n}\n\
+            @fragment\nfn fs_main() -> @location(0) vec4<f32> {\n    return base_color();\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            src_dir.join("lit.meta.toml"),
+            "[asset]\nid = \"5e2d8c1a-7b4f-4e3a-9c6d-1f0a2b3c4d5e\"\n",
+        )
+        .unwrap();
+        std::fs::write(src_dir.join("red.mat"), "shader = \"lit.wgsl\"\n").unwrap();
+        std::fs::write(
+            src_dir.join("red.meta.toml"),
+            "[asset]\nid = \"8f7e6d5c-4b3a-4291-8a7b-6c5d4e3f2a1b\"\n",
+        )
+        .unwrap();
+
+        let reporter = Reporter { json: true };
+        let bridge = HeadlessPipelineGfxBridge::new();
+        let first = build(&src_dir, &out_dir, &bridge, &reporter).unwrap();
+
+        std::fs::write(
+            src_dir.join("common/color.wgsl"),
+            "fn base_color() -> vec4<f32> {\n    return vec4<f32>(1.0, 0.0, 0.0, 1.0);\n}\n",
+        )
+        .unwrap();
+        let second = build(&src_dir, &out_dir, &bridge, &reporter).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!((first.built, first.failed), (2, 0));
+        assert_eq!(
+            second,
+            BuildSummary {
+                built: 2,
+                up_to_date: 0,
+                failed: 0,
+                removed: 0
+            }
+        );
+    }
+
+    #[test]
+    fn validate_reports_problems() {
+        let dir = temp_dir("validate");
+        std::fs::write(dir.join("brick.png"), []).unwrap();
+        std::fs::write(
+            dir.join("brick.meta.toml"),
+            "[asset]\nid = \"4b1f9e5a-2f8e-4c61-9d4a-0a3c6f1e2b7d\"\n[texture]\nis_srgb = \"yes\"\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("common.wgsl"), "").unwrap();
+        std::fs::write(
+            dir.join("orphan.meta.toml"),
+            "[asset]\nid = \"0d7c3f2e-5b8a-4e19-a6f4-3c2b1d9e8f70\"\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("notes.txt"), "").unwrap();
+
+        let summary = validate(&dir, &Reporter { json: true }).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            summary,
+            ValidateSummary {
+                errors: 2,
+                warnings: 1
+            }
+        );
+    }
+}
@@ -0,0 +1,118 @@
+use crate::PipelineGfxBridge;
+use asset::assets::{
+    SemanticShaderBindingKey, SemanticShaderInputKey, SemanticShaderOutputKey, ShaderGlobalItemKind,
+};
+use serde::Deserialize;
+use std::{collections::HashMap, num::NonZeroU32};
+use wgpu::{VertexFormat, VertexStepMode};
+
+/// A [`PipelineGfxBridge`] that works without the engine, e.g. in command line tools.
+///
+/// The semantic keys are registered by the engine at runtime, so they must be given to the bridge up front,
+/// usually from a table exported from the engine. Since the table has no types, names are matched regardless
+/// of their types. Without a table, no item is semantic.
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct HeadlessPipelineGfxBridge {
+    bindings: HashMap<String, NonZeroU32>,
+    inputs: HashMap<String, NonZeroU32>,
+    outputs: HashMap<String, NonZeroU32>,
+}
+
+impl HeadlessPipelineGfxBridge {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses a semantic table, which maps names to keys under the `bindings`, `inputs` and `outputs` tables.
+    pub fn from_toml(content: impl AsRef<str>) -> Result<Self, toml::de::Error> {
+        toml::from_str(content.as_ref())
+    }
+
+    pub fn with_binding(mut self, name: impl Into<String>, key: NonZeroU32) -> Self {
+        self.bindings.insert(name.into(), key);
+        self
+    }
+
+    pub fn with_input(mut self, name: impl Into<String>, key: NonZeroU32) -> Self {
+        self.inputs.insert(name.into(), key);
+        self
+    }
+
+    pub fn with_output(mut self, name: impl Into<String>, key: NonZeroU32) -> Self {
+        self.outputs.insert(name.into(), key);
+        self
+    }
+}
+
+impl PipelineGfxBridge for HeadlessPipelineGfxBridge {
+    fn get_semantic_binding_key(
+        &self,
+        name: &str,
+        _kind: &ShaderGlobalItemKind,
+    ) -> Option<SemanticShaderBindingKey> {
+        self.bindings
+            .get(name)
+            .map(|key| SemanticShaderBindingKey::new(*key))
+    }
+
+    fn get_semantic_input_key(
+        &self,
+        name: &str,
+        _step_mode: VertexStepMode,
+        _format: VertexFormat,
+    ) -> Option<SemanticShaderInputKey> {
+        self.inputs
+            .get(name)
+            .map(|key| SemanticShaderInputKey::new(*key))
+    }
+
+    fn get_semantic_output_key(
+        &self,
+        name: &str,
+        _location: u32,
+    ) -> Option<SemanticShaderOutputKey> {
+        self.outputs
+            .get(name)
+            .map(|key| SemanticShaderOutputKey::new(*key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn semantic_table_from_toml() {
+        let bridge = HeadlessPipelineGfxBridge::from_toml(
+            "[bindings]\ncamera_transform = 1\n[inputs]\nposition = 2\n[outputs]\ncolor = 3\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            bridge.get_semantic_binding_key(
+                "camera_transform",
+                &ShaderGlobalItemKind::Buffer {
+                    size: std::num::NonZeroU64::new(64).unwrap()
+                }
+            ),
+            Some(SemanticShaderBindingKey::new(NonZeroU32::new(1).unwrap()))
+        );
+        assert_eq!(
+            bridge.get_semantic_input_key(
+                "position",
+                VertexStepMode::Vertex,
+                VertexFormat::Float32x3
+            ),
+            Some(SemanticShaderInputKey::new(NonZeroU32::new(2).unwrap()))
+        );
+        assert_eq!(
+            bridge.get_semantic_output_key("color", 0),
+            Some(SemanticShaderOutputKey::new(NonZeroU32::new(3).unwrap()))
+        );
+        assert_eq!(bridge.get_semantic_output_key("normal", 1), None);
+
+        assert!(HeadlessPipelineGfxBridge::from_toml("[globals]\ncolor = 1\n").is_err());
+        assert!(HeadlessPipelineGfxBridge::from_toml("[inputs]\nposition = 0\n").is_err());
+    }
+}
@@ -1,16 +1,27 @@
 use asset::{
-    assets::{FontSource, MaterialSource, ModelSource, ShaderSource, TextureSource},
-    AssetType,
+    assets::{
+        FontSource, MaterialSource, ModelSource, ScriptSource, ShaderSource, StringTableSource,
+        TextureSource,
+    },
+    AssetKey, AssetSource, AssetType,
+};
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
 };
-use std::path::{Path, PathBuf};
 use thiserror::Error;
+use uuid::Uuid;
 
+mod headless_pipeline_gfx_bridge;
 mod metadata;
+mod metadata_schema;
 mod pipeline;
 mod pipeline_gfx_bridge;
 pub mod pipelines;
 
+pub use headless_pipeline_gfx_bridge::*;
 pub use metadata::*;
+pub use metadata_schema::*;
 pub use pipeline::*;
 pub use pipeline_gfx_bridge::*;
 
@@ -18,10 +29,51 @@ pub enum TypedAssetSource {
     Font(FontSource),
     Material(MaterialSource),
     Model(ModelSource),
+    Script(ScriptSource),
     Shader(ShaderSource),
+    StringTable(StringTableSource),
     Texture(TextureSource),
 }
 
+impl TypedAssetSource {
+    pub fn asset_type(&self) -> AssetType {
+        match self {
+            Self::Font(_) => AssetType::Font,
+            Self::Material(_) => AssetType::Material,
+            Self::Model(_) => AssetType::Model,
+            Self::Script(_) => AssetType::Script,
+            Self::Shader(_) => AssetType::Shader,
+            Self::StringTable(_) => AssetType::StringTable,
+            Self::Texture(_) => AssetType::Texture,
+        }
+    }
+
+    pub fn dependencies(&self) -> Vec<AssetKey> {
+        match self {
+            Self::Font(source) => source.dependencies(),
+            Self::Material(source) => source.dependencies(),
+            Self::Model(source) => source.dependencies(),
+            Self::Script(source) => source.dependencies(),
+            Self::Shader(source) => source.dependencies(),
+            Self::StringTable(source) => source.dependencies(),
+            Self::Texture(source) => source.dependencies(),
+        }
+    }
+
+    /// Serializes the source with bincode, in the format that the source type deserializes from.
+    pub fn serialize_into(&self, dst: impl Write) -> bincode::Result<()> {
+        match self {
+            Self::Font(source) => bincode::serialize_into(dst, source),
+            Self::Material(source) => bincode::serialize_into(dst, source),
+            Self::Model(source) => bincode::serialize_into(dst, source),
+            Self::Script(source) => bincode::serialize_into(dst, source),
+            Self::Shader(source) => bincode::serialize_into(dst, source),
+            Self::StringTable(source) => bincode::serialize_into(dst, source),
+            Self::Texture(source) => bincode::serialize_into(dst, source),
+        }
+    }
+}
+
 impl From<FontSource> for TypedAssetSource {
     fn from(value: FontSource) -> Self {
         Self::Font(value)
@@ -40,12 +92,24 @@ impl From<ModelSource> for TypedAssetSource {
     }
 }
 
+impl From<ScriptSource> for TypedAssetSource {
+    fn from(value: ScriptSource) -> Self {
+        Self::Script(value)
+    }
+}
+
 impl From<ShaderSource> for TypedAssetSource {
     fn from(value: ShaderSource) -> Self {
         Self::Shader(value)
     }
 }
 
+impl From<StringTableSource> for TypedAssetSource {
+    fn from(value: StringTableSource) -> Self {
+        Self::StringTable(value)
+    }
+}
+
 impl From<TextureSource> for TypedAssetSource {
     fn from(value: TextureSource) -> Self {
         Self::Texture(value)
@@ -62,62 +126,128 @@ pub enum AssetProcessError {
     AssetPipelineError(#[from] anyhow::Error),
 }
 
+/// Reads the file at the path and processes it into an asset source. See [`process_asset_bytes`].
 pub fn process_asset(
     path: impl AsRef<Path>,
     asset_type: AssetType,
     metadata_content: Option<impl AsRef<str>>,
     gfx_bridge: &dyn PipelineGfxBridge,
+) -> Result<TypedAssetSource, AssetProcessError> {
+    let path = path.as_ref();
+    let file_content = std::fs::read(path)?;
+    process_asset_bytes(path, file_content, asset_type, metadata_content, gfx_bridge)
+}
+
+/// Processes the file content into an asset source without reading the file system, e.g. for uploaded
+/// buffers or files inside an archive. The path is only a hint for the pipelines, such as the file name
+/// shown in errors; it need not exist. Materials and shaders with `#include`s are the exception, as they read
+/// their shader or included files relative to the path.
+pub fn process_asset_bytes(
+    path: impl AsRef<Path>,
+    file_content: Vec<u8>,
+    asset_type: AssetType,
+    metadata_content: Option<impl AsRef<str>>,
+    gfx_bridge: &dyn PipelineGfxBridge,
 ) -> Result<TypedAssetSource, AssetProcessError> {
     let path = path.as_ref();
     match asset_type {
         AssetType::Font => {
             let metadata = metadata_content
-                .map(|content| Metadata::from_toml(content))
+                .map(|content| {
+                    Metadata::from_toml_with_schema(content, &FontSource::metadata_schema())
+                })
                 .transpose()?;
             let metadata = metadata.map(|metadata| metadata.extra).unwrap_or_default();
-            let file_content = std::fs::read(path)?;
             let asset = FontSource::process(path, file_content, &metadata, gfx_bridge)?;
             Ok(asset.into())
         }
         AssetType::Material => {
             let metadata = metadata_content
-                .map(|content| Metadata::from_toml(content))
+                .map(|content| {
+                    Metadata::from_toml_with_schema(content, &MaterialSource::metadata_schema())
+                })
                 .transpose()?;
             let metadata = metadata.map(|metadata| metadata.extra).unwrap_or_default();
-            let file_content = std::fs::read(path)?;
             let asset = MaterialSource::process(path, file_content, &metadata, gfx_bridge)?;
             Ok(asset.into())
         }
         AssetType::Model => {
             let metadata = metadata_content
-                .map(|content| Metadata::from_toml(content))
+                .map(|content| {
+                    Metadata::from_toml_with_schema(content, &ModelSource::metadata_schema())
+                })
                 .transpose()?;
             let metadata = metadata.map(|metadata| metadata.extra).unwrap_or_default();
-            let file_content = std::fs::read(path)?;
             let asset = ModelSource::process(path, file_content, &metadata, gfx_bridge)?;
             Ok(asset.into())
         }
+        AssetType::Script => {
+            let metadata = metadata_content
+                .map(|content| {
+                    Metadata::from_toml_with_schema(content, &ScriptSource::metadata_schema())
+                })
+                .transpose()?;
+            let metadata = metadata.map(|metadata| metadata.extra).unwrap_or_default();
+            let asset = ScriptSource::process(path, file_content, &metadata, gfx_bridge)?;
+            Ok(asset.into())
+        }
         AssetType::Shader => {
             let metadata = metadata_content
-                .map(|content| Metadata::from_toml(content))
+                .map(|content| {
+                    Metadata::from_toml_with_schema(content, &ShaderSource::metadata_schema())
+                })
                 .transpose()?;
             let metadata = metadata.map(|metadata| metadata.extra).unwrap_or_default();
-            let file_content = std::fs::read(path)?;
             let asset = ShaderSource::process(path, file_content, &metadata, gfx_bridge)?;
             Ok(asset.into())
         }
+        AssetType::StringTable => {
+            let metadata = metadata_content
+                .map(|content| {
+                    Metadata::from_toml_with_schema(content, &StringTableSource::metadata_schema())
+                })
+                .transpose()?;
+            let metadata = metadata.map(|metadata| metadata.extra).unwrap_or_default();
+            let asset = StringTableSource::process(path, file_content, &metadata, gfx_bridge)?;
+            Ok(asset.into())
+        }
         AssetType::Texture => {
             let metadata = metadata_content
-                .map(|content| Metadata::from_toml(content))
+                .map(|content| {
+                    Metadata::from_toml_with_schema(content, &TextureSource::metadata_schema())
+                })
                 .transpose()?;
             let metadata = metadata.map(|metadata| metadata.extra).unwrap_or_default();
-            let file_content = std::fs::read(path)?;
             let asset = TextureSource::process(path, file_content, &metadata, gfx_bridge)?;
             Ok(asset.into())
         }
     }
 }
 
+/// Parses and validates the metadata of an asset of the type without processing the asset, and returns the id
+/// of the asset.
+pub fn validate_metadata(
+    asset_type: AssetType,
+    metadata_content: impl AsRef<str>,
+) -> Result<Uuid, MetadataLoadError> {
+    fn validate<T: AssetPipeline>(content: &str) -> Result<Uuid, MetadataLoadError> {
+        let metadata =
+            Metadata::<T::Metadata>::from_toml_with_schema(content, &T::metadata_schema())?;
+        Ok(metadata.asset.id)
+    }
+
+    let content = metadata_content.as_ref();
+    match asset_type {
+        AssetType::Font => validate::<FontSource>(content),
+        AssetType::Material => validate::<MaterialSource>(content),
+        AssetType::Model => validate::<ModelSource>(content),
+        AssetType::Script => validate::<ScriptSource>(content),
+        AssetType::Shader => validate::<ShaderSource>(content),
+        AssetType::StringTable => validate::<StringTableSource>(content),
+        AssetType::Texture => validate::<TextureSource>(content),
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum AssetTypeDeduceError {
     #[error("io error: {0}")]
@@ -146,8 +276,223 @@ pub fn deduce_asset_type_from_path(
             Ok(AssetType::Texture)
         }
         "wgsl" => Ok(AssetType::Shader),
+        "lua" => Ok(AssetType::Script),
+        "lang" => Ok(AssetType::StringTable),
         _ => Err(AssetTypeDeduceError::UnsupportedExtension(
             path.to_path_buf(),
         )),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use asset::{
+        assets::{
+            LocalizedString, SemanticShaderBindingKey, SemanticShaderInputKey,
+            SemanticShaderOutputKey, ShaderGlobalItemKind,
+        },
+        AssetKey,
+    };
+    use image::{DynamicImage, ImageOutputFormat, Rgba, RgbaImage};
+    use std::io::Cursor;
+    use wgpu::{VertexFormat, VertexStepMode};
+
+    struct NoSemantics;
+
+    impl PipelineGfxBridge for NoSemantics {
+        fn get_semantic_binding_key(
+            &self,
+            _name: &str,
+            _kind: &ShaderGlobalItemKind,
+        ) -> Option<SemanticShaderBindingKey> {
+            None
+        }
+
+        fn get_semantic_input_key(
+            &self,
+            _name: &str,
+            _step_mode: VertexStepMode,
+            _format: VertexFormat,
+        ) -> Option<SemanticShaderInputKey> {
+            None
+        }
+
+        fn get_semantic_output_key(
+            &self,
+            _name: &str,
+            _location: u32,
+        ) -> Option<SemanticShaderOutputKey> {
+            None
+        }
+    }
+
+    #[test]
+    fn process_texture_from_memory() {
+        let mut png = Vec::new();
+        DynamicImage::ImageRgba8(RgbaImage::from_pixel(4, 2, Rgba([255, 0, 0, 255])))
+            .write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)
+            .unwrap();
+
+        // The path does not exist; it is only a hint.
+        let metadata = "[asset]\nid = \"4b1f9e5a-2f8e-4c61-9d4a-0a3c6f1e2b7d\"\n[texture]\nis_srgb = false\nfilter_mode = \"point\"\naddress_mode_u = \"clamp\"\naddress_mode_v = \"clamp\"\n[sprite]\n[nine_patch]\n";
+        let source = process_asset_bytes(
+            "uploads/does-not-exist.png",
+            png,
+            AssetType::Texture,
+            Some(metadata),
+            &NoSemantics,
+        )
+        .unwrap();
+
+        match source {
+            TypedAssetSource::Texture(texture) => {
+                assert_eq!((texture.width, texture.height), (4, 2));
+                assert_eq!(&texture.texels[..4], &[255, 0, 0, 255]);
+            }
+            _ => panic!("expected a texture source"),
+        }
+    }
+
+    #[test]
+    fn process_shader_with_include() {
+        let dir = std::env::temp_dir().join(format!("r3d-shader-include-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("common")).unwrap();
+        std::fs::write(
+            dir.join("common/lighting.wgsl"),
+            "fn lambert(n: vec3<f32>, l: vec3<f32>) -> f32 {\n    return max(dot(n, l), 0.0);\n}\n",
+        )
+        .unwrap();
+
+        let shader_path = dir.join("lit.wgsl");
+        std::fs::write(
+            &shader_path,
+            "#include \"common/lighting.wgsl\"\n#include \"./common/lighting.wgsl\"\n\
+            @vertex\nfn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {\n    \
+            return vec4<f32>(f32(index), 0.0, 0.0, 1.0);\n}\n\
+            @fragment\nfn fs_main() -> @location(0) vec4<f32> {\n    \
+            return vec4<f32>(lambert(vec3<f32>(0.0, 1.0, 0.0), vec3<f32>(0.0, 1.0, 0.0)));\n}\n",
+        )
+        .unwrap();
+
+        let metadata = "[asset]\nid = \"0d7c3f2e-5b8a-4e19-a6f4-3c2b1d9e8f70\"\n";
+        let source = process_asset(
+            &shader_path,
+            AssetType::Shader,
+            Some(metadata),
+            &NoSemantics,
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        match source.unwrap() {
+            TypedAssetSource::Shader(shader) => {
+                assert_eq!(shader.reflection.vertex_entry_point, "vs_main");
+                assert_eq!(shader.source.matches("fn lambert").count(), 1);
+                assert_eq!(
+                    shader.includes,
+                    vec![AssetKey::Path(
+                        dir.join("common/lighting.wgsl")
+                            .to_string_lossy()
+                            .into_owned()
+                    )]
+                );
+            }
+            _ => panic!("expected a shader source"),
+        }
+    }
+
+    #[test]
+    fn process_material_shader_variants() {
+        let dir = std::env::temp_dir().join(format!("r3d-shader-variants-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("lit.wgsl"),
+            "struct VertexIn {\n    @location(0) position: vec3<f32>,\n\
+            #ifdef SKINNED\n    @location(1) weights: vec4<f32>,\n#endif\n}\n\
+            @vertex\nfn vs_main(vertex: VertexIn) -> @builtin(position) vec4<f32> {\n    \
+            return vec4<f32>(vertex.position, 1.0);\n}\n\
+            @fragment\nfn fs_main() -> @location(0) vec4<f32> {\n    return vec4<f32>(1.0);\n}\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("plain.mat"), "shader = \"lit.wgsl\"\n").unwrap();
+        std::fs::write(
+            dir.join("skinned.mat"),
+            "shader = \"lit.wgsl\"\ndefines = [\"SKINNED\"]\n",
+        )
+        .unwrap();
+
+        let process = |name: &str| {
+            let metadata = "[asset]\nid = \"6a0e4c1b-9d27-4f38-b5e2-7c81d3f04a96\"\n";
+            process_asset(
+                dir.join(name),
+                AssetType::Material,
+                Some(metadata),
+                &NoSemantics,
+            )
+        };
+        let plain = process("plain.mat");
+        let skinned = process("skinned.mat");
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let (plain, skinned) = match (plain.unwrap(), skinned.unwrap()) {
+            (TypedAssetSource::Material(plain), TypedAssetSource::Material(skinned)) => {
+                (plain, skinned)
+            }
+            _ => panic!("expected material sources"),
+        };
+        assert!(plain.shader_variant.is_none());
+
+        let variant = skinned.shader_variant.unwrap();
+        assert_eq!(variant.defines, ["SKINNED"]);
+        assert_eq!(
+            Vec::from_iter(
+                variant
+                    .reflection
+                    .vertex_input
+                    .fields
+                    .iter()
+                    .map(|field| field.name.as_str())
+            ),
+            ["position", "weights"]
+        );
+    }
+
+    #[test]
+    fn process_string_table() {
+        let content = "title = \"Adventure\"\n[menu]\nstart = \"Start, {name}!\"\n[menu.apples]\none = \"{count} apple\"\nother = \"{count} apples\"\n";
+        let source = process_asset_bytes(
+            "strings/ko-KR.lang",
+            content.as_bytes().to_vec(),
+            deduce_asset_type_from_path("strings/ko-KR.lang").unwrap(),
+            None as Option<&str>,
+            &NoSemantics,
+        )
+        .unwrap();
+
+        let table = match source {
+            TypedAssetSource::StringTable(table) => table,
+            _ => panic!("expected a string table source"),
+        };
+        assert_eq!(table.locale, "ko-KR");
+        assert_eq!(
+            Vec::from_iter(table.entries.keys().map(String::as_str)),
+            ["menu.apples", "menu.start", "title"]
+        );
+        assert_eq!(
+            table.entries["menu.apples"],
+            LocalizedString::Plural {
+                one: Some("{count} apple".to_owned()),
+                other: "{count} apples".to_owned()
+            }
+        );
+
+        assert!(process_asset_bytes(
+            "en.lang",
+            b"count = 3".to_vec(),
+            AssetType::StringTable,
+            None as Option<&str>,
+            &NoSemantics,
+        )
+        .is_err());
+    }
+}
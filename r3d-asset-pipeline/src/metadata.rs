@@ -1,3 +1,4 @@
+use crate::{MetadataSchema, MetadataType, MetadataValidationError};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use uuid::Uuid;
@@ -6,6 +7,8 @@ use uuid::Uuid;
 pub enum MetadataLoadError {
     #[error("toml error: {0}")]
     TOMLError(#[from] toml::de::Error),
+    #[error("invalid metadata: {0}")]
+    ValidationError(#[from] MetadataValidationError),
 }
 
 /// Metadata for an asset.
@@ -32,4 +35,90 @@ where
     pub fn from_toml(content: impl AsRef<str>) -> Result<Self, MetadataLoadError> {
         toml::from_str(content.as_ref()).map_err(MetadataLoadError::from)
     }
+
+    /// Parses the metadata like `from_toml`, but first validates it against the schema of the asset type.
+    /// The `asset` table is always accepted and need not be part of the schema.
+    pub fn from_toml_with_schema(
+        content: impl AsRef<str>,
+        schema: &MetadataSchema,
+    ) -> Result<Self, MetadataLoadError> {
+        let table = toml::from_str::<toml::Table>(content.as_ref())?;
+        schema
+            .clone()
+            .field(
+                "asset",
+                MetadataType::Table(MetadataSchema::new().field("id", MetadataType::String)),
+            )
+            .validate(&table)?;
+        Ok(toml::Value::Table(table).try_into()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{pipelines::TextureMetadata, AssetPipeline};
+    use asset::assets::TextureSource;
+
+    fn load(content: &str) -> Result<Metadata<TextureMetadata>, MetadataLoadError> {
+        Metadata::from_toml_with_schema(content, &TextureSource::metadata_schema())
+    }
+
+    fn validation_error(content: &str) -> MetadataValidationError {
+        match load(content) {
+            Err(MetadataLoadError::ValidationError(err)) => err,
+            Err(err) => panic!("expected a validation error, but got `{}`", err),
+            Ok(_) => panic!("expected a validation error"),
+        }
+    }
+
+    #[test]
+    fn metadata_rejects_unknown_and_mistyped_keys() {
+        let asset = "[asset]\nid = \"4b1f9e5a-2f8e-4c61-9d4a-0a3c6f1e2b7d\"\n";
+        let texture = "[texture]\nis_srgb = true\nfilter_mode = \"point\"\naddress_mode_u = \"repeat\"\naddress_mode_v = \"clamp\"\n";
+
+        let metadata = load(&format!(
+            "{}{}[sprite.idle]\nx_min = 0\nx_max = 16\ny_min = 0\ny_max = 16\n[nine_patch]\n",
+            asset, texture
+        ))
+        .unwrap();
+        assert!(metadata.extra.texture.is_srgb);
+        assert_eq!(metadata.extra.sprite["idle"].x_max, 16);
+
+        assert_eq!(
+            validation_error(&format!(
+                "{}{}",
+                asset,
+                texture.replace("is_srgb", "is_srbg")
+            )),
+            MetadataValidationError::UnknownKey {
+                key: "texture.is_srbg".to_owned()
+            }
+        );
+        assert_eq!(
+            validation_error(&format!(
+                "{}{}[sprite.idle]\nx_min = 0\nx_max = 16\ny_min = 0\ny_mx = 16\n",
+                asset, texture
+            )),
+            MetadataValidationError::UnknownKey {
+                key: "sprite.idle.y_mx".to_owned()
+            }
+        );
+        assert_eq!(
+            validation_error(&format!("{}{}", asset, texture.replace("true", "\"yes\""))),
+            MetadataValidationError::WrongType {
+                key: "texture.is_srgb".to_owned(),
+                expected: "a boolean".to_owned(),
+                found: "string",
+            }
+        );
+        assert_eq!(
+            validation_error(&format!("{}{}", asset, texture.replace("point", "nearest"))),
+            MetadataValidationError::WrongType {
+                key: "texture.filter_mode".to_owned(),
+                expected: "one of `point`, `bilinear`, `trilinear`".to_owned(),
+                found: "string",
+            }
+        );
+    }
 }
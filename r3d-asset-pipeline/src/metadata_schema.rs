@@ -0,0 +1,119 @@
+use std::fmt::Display;
+use thiserror::Error;
+use toml::{Table, Value};
+
+#[derive(Error, Debug, PartialEq)]
+pub enum MetadataValidationError {
+    #[error("unknown metadata key `{key}`")]
+    UnknownKey { key: String },
+    #[error("metadata key `{key}` must be {expected}, but found {found}")]
+    WrongType {
+        key: String,
+        expected: String,
+        found: &'static str,
+    },
+}
+
+/// The type of a metadata value.
+#[derive(Debug, Clone)]
+pub enum MetadataType {
+    Bool,
+    Integer,
+    /// Floats also accept integers, e.g. `size = 32`.
+    Float,
+    String,
+    /// A string that must be one of the given variants.
+    Enum(&'static [&'static str]),
+    /// A table with a fixed set of keys.
+    Table(MetadataSchema),
+    /// A table with arbitrary keys whose values all have the same type, e.g. named sprites.
+    Map(Box<MetadataType>),
+}
+
+impl MetadataType {
+    fn validate(&self, key: &str, value: &Value) -> Result<(), MetadataValidationError> {
+        match (self, value) {
+            (Self::Bool, Value::Boolean(_))
+            | (Self::Integer, Value::Integer(_))
+            | (Self::Float, Value::Float(_) | Value::Integer(_))
+            | (Self::String, Value::String(_)) => Ok(()),
+            (Self::Enum(variants), Value::String(variant))
+                if variants.contains(&variant.as_str()) =>
+            {
+                Ok(())
+            }
+            (Self::Table(schema), Value::Table(table)) => schema.validate_table(key, table),
+            (Self::Map(ty), Value::Table(table)) => table
+                .iter()
+                .try_for_each(|(name, value)| ty.validate(&format!("{}.{}", key, name), value)),
+            _ => Err(MetadataValidationError::WrongType {
+                key: key.to_owned(),
+                expected: self.to_string(),
+                found: value.type_str(),
+            }),
+        }
+    }
+}
+
+impl Display for MetadataType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Bool => write!(f, "a boolean"),
+            Self::Integer => write!(f, "an integer"),
+            Self::Float => write!(f, "a number"),
+            Self::String => write!(f, "a string"),
+            Self::Enum(variants) => {
+                write!(f, "one of ")?;
+                for (index, variant) in variants.iter().enumerate() {
+                    if index != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "`{}`", variant)?;
+                }
+                Ok(())
+            }
+            Self::Table(_) | Self::Map(_) => write!(f, "a table"),
+        }
+    }
+}
+
+/// The keys and value types that a metadata table accepts. Keys are optional; the schema only rejects keys that
+/// are unknown or have the wrong type, so that typos in `.meta` files are reported instead of silently ignored.
+#[derive(Debug, Clone, Default)]
+pub struct MetadataSchema {
+    fields: Vec<(&'static str, MetadataType)>,
+}
+
+impl MetadataSchema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a key to the schema.
+    pub fn field(mut self, key: &'static str, ty: MetadataType) -> Self {
+        self.fields.push((key, ty));
+        self
+    }
+
+    /// Validates a top-level table. Errors name the offending key by its dotted path, e.g. `texture.is_srgb`.
+    pub fn validate(&self, table: &Table) -> Result<(), MetadataValidationError> {
+        self.validate_table("", table)
+    }
+
+    fn validate_table(&self, prefix: &str, table: &Table) -> Result<(), MetadataValidationError> {
+        for (key, value) in table {
+            let path = if prefix.is_empty() {
+                key.clone()
+            } else {
+                format!("{}.{}", prefix, key)
+            };
+
+            match self.fields.iter().find(|(field, _)| field == key) {
+                Some((_, ty)) => ty.validate(&path, value)?,
+                None => return Err(MetadataValidationError::UnknownKey { key: path }),
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use crate::PipelineGfxBridge;
+use crate::{MetadataSchema, PipelineGfxBridge};
 use asset::AssetSource;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
@@ -10,6 +10,9 @@ where
 {
     type Metadata: for<'de> Deserialize<'de> + Default;
 
+    /// Returns the keys and value types that `Self::Metadata` accepts, excluding the `asset` table.
+    fn metadata_schema() -> MetadataSchema;
+
     /// Process the file content and metadata into a new asset source.
     fn process(
         file_path: &Path,
@@ -1,11 +1,23 @@
 mod font;
 mod material;
 mod model;
+#[cfg(feature = "assimp")]
+mod model_assimp;
+mod model_import;
+mod mtl;
+mod script;
 mod shader;
+mod shader_preprocessor;
+mod string_table;
 mod texture;
 
 pub use font::*;
 pub use material::*;
 pub use model::*;
+pub use model_import::*;
+pub use mtl::*;
+pub use script::*;
 pub use shader::*;
+pub use shader_preprocessor::*;
+pub use string_table::*;
 pub use texture::*;
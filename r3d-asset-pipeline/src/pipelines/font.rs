@@ -1,4 +1,4 @@
-use crate::{AssetPipeline, PipelineGfxBridge};
+use crate::{AssetPipeline, MetadataSchema, MetadataType, PipelineGfxBridge};
 use asset::assets::FontSource;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
@@ -30,6 +30,19 @@ impl Default for FontTable {
 impl AssetPipeline for FontSource {
     type Metadata = FontMetadata;
 
+    fn metadata_schema() -> MetadataSchema {
+        MetadataSchema::new().field(
+            "font",
+            MetadataType::Table(
+                MetadataSchema::new()
+                    .field("sdf_font_size", MetadataType::Float)
+                    .field("sdf_inset", MetadataType::Integer)
+                    .field("sdf_radius", MetadataType::Integer)
+                    .field("sdf_cutoff", MetadataType::Float),
+            ),
+        )
+    }
+
     fn process(
         _file_path: &Path,
         file_content: Vec<u8>,
@@ -1,23 +1,653 @@
-use crate::{AssetPipeline, PipelineGfxBridge};
+use super::{reflect_shader, ShaderMetadata};
+use crate::{AssetPipeline, MetadataSchema, PipelineGfxBridge};
 use anyhow::{anyhow, Context};
-use asset::assets::MaterialSource;
+use asset::{
+    assets::{
+        find_built_in_shader, specialize_shader_source, MaterialBindingKey,
+        MaterialBindingPropSource, MaterialBindingValueSource, MaterialBlendMode, MaterialCullMode,
+        MaterialFrontFace, MaterialInstanceProp, MaterialInstancePropKey,
+        MaterialInstancePropValue, MaterialShaderSource, MaterialSource, MaterialTopology,
+        ShaderGlobalItemKind, ShaderReflection, ShaderSource, ShaderVariantSource,
+    },
+    AssetKey,
+};
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
+use thiserror::Error;
+use wgpu::VertexFormat;
+
+#[derive(Error, Debug)]
+pub enum MaterialDefinitionError {
+    #[error(
+        "unknown texture binding `{name}`; available texture and sampler bindings: [{available}]"
+    )]
+    UnknownTexture { name: String, available: String },
+    #[error("unknown property `{name}`; available properties: [{available}]")]
+    UnknownProperty { name: String, available: String },
+    #[error("texture binding `{name}` expects {expected}")]
+    TextureMismatch { name: String, expected: String },
+    #[error("property `{name}` expects {expected}")]
+    PropertyMismatch { name: String, expected: String },
+}
 
 #[derive(Default, Serialize, Deserialize)]
 pub struct MaterialMetadata;
 
+/// A material definition, written in TOML. Paths are relative to the material file.
+///
+/// ```toml
+/// shader = "shaders/lit.wgsl"
+/// defines = ["HAS_NORMAL_MAP"]  # the shader is specialized for these defines
+/// blend = "alpha"  # opaque (default), alpha, additive or premultiplied_alpha
+/// cull = "none"    # none, front or back (default)
+/// front_face = "ccw"  # cw (default) or ccw
+/// topology = "line_list"  # triangle_list (default), line_list or point_list
+///
+/// [textures]
+/// albedo = "textures/brick.png"                 # texture binding
+/// albedo_sampler = "textures/brick.png"         # sampler binding; uses the sampler of the texture
+/// layers = ["textures/a.png", "textures/b.png"] # texture array binding
+/// button_sampler = { texture = "textures/ui.png", nine_patch = "button" }
+///
+/// [properties]
+/// roughness = 0.4            # per-instance input or uniform buffer binding
+/// tint = [1.0, 0.5, 0.5, 1.0]
+/// ```
+///
+/// Texture and property names are the names reflected from the shader, and they are validated
+/// against the shader at import time. With `defines`, they are validated against the specialized shader,
+/// see [`specialize_shader_source`](asset::assets::specialize_shader_source).
+///
+/// A shader that comes with the engine is requested by name, e.g. `shader = { built_in = "standard" }`.
+/// Its defines are also selected by the textures that are set, e.g. `HAS_NORMAL_MAP` by `normal_texture`,
+/// see [`BuiltInShader`](asset::assets::BuiltInShader).
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct MaterialDefinition {
+    pub shader: MaterialShaderDefinition,
+    #[serde(default)]
+    pub defines: Vec<String>,
+    #[serde(default)]
+    pub blend: MaterialBlendMode,
+    #[serde(default)]
+    pub cull: MaterialCullMode,
+    #[serde(default)]
+    pub front_face: MaterialFrontFace,
+    #[serde(default)]
+    pub topology: MaterialTopology,
+    #[serde(default)]
+    pub textures: BTreeMap<String, MaterialTextureDefinition>,
+    #[serde(default)]
+    pub properties: BTreeMap<String, MaterialPropertyDefinition>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum MaterialShaderDefinition {
+    Path(String),
+    BuiltIn { built_in: String },
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum MaterialTextureDefinition {
+    Texture(String),
+    TextureArray(Vec<String>),
+    Sprite { texture: String, sprite: String },
+    NinePatch { texture: String, nine_patch: String },
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum MaterialPropertyDefinition {
+    Scalar(f64),
+    Vector(Vec<f64>),
+}
+
+impl MaterialPropertyDefinition {
+    fn components(&self) -> Vec<f64> {
+        match self {
+            Self::Scalar(value) => vec![*value],
+            Self::Vector(values) => values.clone(),
+        }
+    }
+}
+
 impl AssetPipeline for MaterialSource {
     type Metadata = MaterialMetadata;
 
+    fn metadata_schema() -> MetadataSchema {
+        MetadataSchema::new()
+    }
+
     fn process(
-        _file_path: &Path,
+        file_path: &Path,
         file_content: Vec<u8>,
         _metadata: &Self::Metadata,
-        _gfx_bridge: &dyn PipelineGfxBridge,
+        gfx_bridge: &dyn PipelineGfxBridge,
     ) -> anyhow::Result<Self> {
-        Self::deserialize(&file_content)
-            .with_context(|| "failed to deserialize material")
-            .map_err(|err| anyhow!(err))
+        let content = std::str::from_utf8(&file_content)
+            .with_context(|| "failed to decode material into utf8 string")?;
+        let definition = toml::from_str::<MaterialDefinition>(content)
+            .with_context(|| "failed to parse material definition")?;
+        let base_path = file_path.parent().unwrap_or_else(|| Path::new(""));
+
+        let (reflection, shader_variant, includes) = match &definition.shader {
+            MaterialShaderDefinition::Path(path) => {
+                // The shader is processed here to validate the names against its reflection.
+                let shader_path = base_path.join(path);
+                let shader_content = std::fs::read(&shader_path).with_context(|| {
+                    format!("failed to read shader `{}`", shader_path.display())
+                })?;
+                let shader = ShaderSource::process(
+                    &shader_path,
+                    shader_content,
+                    &ShaderMetadata,
+                    gfx_bridge,
+                )
+                .with_context(|| format!("failed to process shader `{}`", shader_path.display()))?;
+
+                if definition.defines.is_empty() {
+                    (shader.reflection, None, shader.includes)
+                } else {
+                    let variant = specialize_variant(
+                        gfx_bridge,
+                        &shader.source,
+                        BTreeSet::from_iter(definition.defines.iter().cloned()),
+                    )
+                    .with_context(|| {
+                        format!("failed to specialize shader `{}`", shader_path.display())
+                    })?;
+                    (variant.reflection.clone(), Some(variant), shader.includes)
+                }
+            }
+            MaterialShaderDefinition::BuiltIn { built_in } => {
+                let shader = find_built_in_shader(built_in)
+                    .ok_or_else(|| anyhow!("unknown built-in shader `{}`", built_in))?;
+                let mut defines = BTreeSet::from_iter(definition.defines.iter().cloned());
+                defines.extend(
+                    shader.defines_for_textures(definition.textures.keys().map(String::as_str)),
+                );
+                let variant =
+                    specialize_variant(gfx_bridge, shader.source, defines).with_context(|| {
+                        format!("failed to specialize built-in shader `{}`", built_in)
+                    })?;
+                (variant.reflection.clone(), Some(variant), vec![])
+            }
+        };
+        let source = build_material_source(&definition, &reflection, |path| {
+            AssetKey::Path(base_path.join(path).to_string_lossy().into_owned())
+        })?;
+
+        Ok(MaterialSource {
+            shader_variant,
+            includes,
+            ..source
+        })
+    }
+}
+
+fn specialize_variant(
+    gfx_bridge: &dyn PipelineGfxBridge,
+    source: &str,
+    defines: BTreeSet<String>,
+) -> anyhow::Result<ShaderVariantSource> {
+    let specialized = specialize_shader_source(source, &defines)?;
+
+    Ok(ShaderVariantSource {
+        defines: Vec::from_iter(defines),
+        reflection: reflect_shader(gfx_bridge, &specialized)?,
+    })
+}
+
+/// Builds a material source from the definition, validating it against the shader reflection.
+pub fn build_material_source(
+    definition: &MaterialDefinition,
+    reflection: &ShaderReflection,
+    resolve_path: impl Fn(&str) -> AssetKey,
+) -> Result<MaterialSource, MaterialDefinitionError> {
+    let mut binding_props = Vec::new();
+    let mut instance_props = Vec::new();
+
+    for (name, texture) in &definition.textures {
+        let global = reflection
+            .globals
+            .iter()
+            .find(|global| &global.name == name && !is_buffer(&global.kind))
+            .ok_or_else(|| MaterialDefinitionError::UnknownTexture {
+                name: name.clone(),
+                available: join_names(
+                    reflection
+                        .globals
+                        .iter()
+                        .filter(|global| !is_buffer(&global.kind))
+                        .map(|global| global.name.as_str()),
+                ),
+            })?;
+        let mismatch = |expected: &str| MaterialDefinitionError::TextureMismatch {
+            name: name.clone(),
+            expected: expected.to_owned(),
+        };
+        let value = match (&global.kind, texture) {
+            (
+                ShaderGlobalItemKind::Texture {
+                    array_size: None, ..
+                },
+                MaterialTextureDefinition::Texture(path),
+            ) => MaterialBindingValueSource::TextureView {
+                texture: resolve_path(path),
+            },
+            (
+                ShaderGlobalItemKind::Texture {
+                    array_size: None, ..
+                },
+                _,
+            ) => {
+                return Err(mismatch("a texture path"));
+            }
+            (
+                ShaderGlobalItemKind::Texture {
+                    array_size: Some(array_size),
+                    ..
+                },
+                MaterialTextureDefinition::TextureArray(paths),
+            ) if paths.len() == array_size.get() as usize => {
+                MaterialBindingValueSource::TextureViewArray {
+                    textures: paths.iter().map(|path| resolve_path(path)).collect(),
+                }
+            }
+            (
+                ShaderGlobalItemKind::Texture {
+                    array_size: Some(array_size),
+                    ..
+                },
+                _,
+            ) => {
+                return Err(mismatch(&format!(
+                    "an array of {} texture paths",
+                    array_size
+                )));
+            }
+            (ShaderGlobalItemKind::Sampler { .. }, MaterialTextureDefinition::Texture(path)) => {
+                MaterialBindingValueSource::SamplerTexture {
+                    texture: resolve_path(path),
+                }
+            }
+            (
+                ShaderGlobalItemKind::Sampler { .. },
+                MaterialTextureDefinition::Sprite { texture, sprite },
+            ) => MaterialBindingValueSource::SamplerSprite {
+                texture: resolve_path(texture),
+                sprite: sprite.clone(),
+            },
+            (
+                ShaderGlobalItemKind::Sampler { .. },
+                MaterialTextureDefinition::NinePatch {
+                    texture,
+                    nine_patch,
+                },
+            ) => MaterialBindingValueSource::SamplerNinePatch {
+                texture: resolve_path(texture),
+                nine_patch: nine_patch.clone(),
+            },
+            (ShaderGlobalItemKind::Sampler { .. }, _) => {
+                return Err(mismatch("a texture path, a sprite or a nine-patch"));
+            }
+            (ShaderGlobalItemKind::Buffer { .. }, _) => unreachable!(),
+        };
+
+        binding_props.push(MaterialBindingPropSource {
+            key: match global.sematic_key {
+                Some(key) => MaterialBindingKey::Semantic(key),
+                None => MaterialBindingKey::Named(name.clone()),
+            },
+            value,
+        });
+    }
+
+    for (name, property) in &definition.properties {
+        let components = property.components();
+
+        if let Some(field) = reflection
+            .instance_input
+            .fields
+            .iter()
+            .find(|field| &field.name == name)
+        {
+            let value =
+                to_instance_prop_value(field.attribute.format, &components).ok_or_else(|| {
+                    MaterialDefinitionError::PropertyMismatch {
+                        name: name.clone(),
+                        expected: format!("a value of format {:?}", field.attribute.format),
+                    }
+                })?;
+            instance_props.push(MaterialInstanceProp {
+                key: MaterialInstancePropKey::Named(name.clone()),
+                value,
+            });
+            continue;
+        }
+
+        let global = reflection
+            .globals
+            .iter()
+            .find(|global| &global.name == name && is_buffer(&global.kind))
+            .ok_or_else(|| MaterialDefinitionError::UnknownProperty {
+                name: name.clone(),
+                available: join_names(
+                    reflection
+                        .instance_input
+                        .fields
+                        .iter()
+                        .map(|field| field.name.as_str())
+                        .chain(
+                            reflection
+                                .globals
+                                .iter()
+                                .filter(|global| is_buffer(&global.kind))
+                                .map(|global| global.name.as_str()),
+                        ),
+                ),
+            })?;
+        let size = match global.kind {
+            ShaderGlobalItemKind::Buffer { size } => size.get(),
+            _ => unreachable!(),
+        };
+        // Uniform buffers are filled with 32-bit floats; up to a vec4 is supported.
+        let components = Vec::from_iter(components.iter().map(|&value| value as f32));
+        let value = match *components.as_slice() {
+            [x] if size == 4 => MaterialBindingValueSource::Float32([x]),
+            [x, y] if size == 8 => MaterialBindingValueSource::Float32x2([x, y]),
+            [x, y, z] if size == 12 || size == 16 => {
+                MaterialBindingValueSource::Float32x3([x, y, z])
+            }
+            [x, y, z, w] if size == 16 => MaterialBindingValueSource::Float32x4([x, y, z, w]),
+            _ => {
+                return Err(MaterialDefinitionError::PropertyMismatch {
+                    name: name.clone(),
+                    expected: format!("{} bytes of 32-bit floats, up to 4 components", size),
+                });
+            }
+        };
+
+        binding_props.push(MaterialBindingPropSource {
+            key: match global.sematic_key {
+                Some(key) => MaterialBindingKey::Semantic(key),
+                None => MaterialBindingKey::Named(name.clone()),
+            },
+            value,
+        });
+    }
+
+    Ok(MaterialSource {
+        shader: match &definition.shader {
+            MaterialShaderDefinition::Path(path) => MaterialShaderSource::Asset(resolve_path(path)),
+            MaterialShaderDefinition::BuiltIn { built_in } => {
+                MaterialShaderSource::BuiltIn(built_in.clone())
+            }
+        },
+        shader_variant: None,
+        binding_props,
+        instance_props,
+        blend_mode: definition.blend,
+        cull_mode: definition.cull,
+        front_face: definition.front_face,
+        topology: definition.topology,
+        includes: vec![],
+    })
+}
+
+fn is_buffer(kind: &ShaderGlobalItemKind) -> bool {
+    matches!(kind, ShaderGlobalItemKind::Buffer { .. })
+}
+
+fn join_names<'a>(names: impl Iterator<Item = &'a str>) -> String {
+    names.collect::<Vec<_>>().join(", ")
+}
+
+fn to_instance_prop_value(
+    format: VertexFormat,
+    components: &[f64],
+) -> Option<MaterialInstancePropValue> {
+    fn array<T: Copy + Default, const N: usize>(
+        components: &[f64],
+        convert: impl Fn(f64) -> T,
+    ) -> Option<[T; N]> {
+        if components.len() != N {
+            return None;
+        }
+
+        let mut array = [T::default(); N];
+
+        for (item, &component) in array.iter_mut().zip(components) {
+            *item = convert(component);
+        }
+
+        Some(array)
+    }
+
+    let float32 = |value: f64| value as f32;
+    let uint32 = |value: f64| value as u32;
+    let sint32 = |value: f64| value as i32;
+    let float64 = |value: f64| value;
+
+    Some(match format {
+        VertexFormat::Float32 => MaterialInstancePropValue::Float32(array(components, float32)?),
+        VertexFormat::Float32x2 => {
+            MaterialInstancePropValue::Float32x2(array(components, float32)?)
+        }
+        VertexFormat::Float32x3 => {
+            MaterialInstancePropValue::Float32x3(array(components, float32)?)
+        }
+        VertexFormat::Float32x4 => {
+            MaterialInstancePropValue::Float32x4(array(components, float32)?)
+        }
+        VertexFormat::Uint32 => MaterialInstancePropValue::Uint32(array(components, uint32)?),
+        VertexFormat::Uint32x2 => MaterialInstancePropValue::Uint32x2(array(components, uint32)?),
+        VertexFormat::Uint32x3 => MaterialInstancePropValue::Uint32x3(array(components, uint32)?),
+        VertexFormat::Uint32x4 => MaterialInstancePropValue::Uint32x4(array(components, uint32)?),
+        VertexFormat::Sint32 => MaterialInstancePropValue::Sint32(array(components, sint32)?),
+        VertexFormat::Sint32x2 => MaterialInstancePropValue::Sint32x2(array(components, sint32)?),
+        VertexFormat::Sint32x3 => MaterialInstancePropValue::Sint32x3(array(components, sint32)?),
+        VertexFormat::Sint32x4 => MaterialInstancePropValue::Sint32x4(array(components, sint32)?),
+        VertexFormat::Float64 => MaterialInstancePropValue::Float64(array(components, float64)?),
+        VertexFormat::Float64x2 => {
+            MaterialInstancePropValue::Float64x2(array(components, float64)?)
+        }
+        VertexFormat::Float64x3 => {
+            MaterialInstancePropValue::Float64x3(array(components, float64)?)
+        }
+        VertexFormat::Float64x4 => {
+            MaterialInstancePropValue::Float64x4(array(components, float64)?)
+        }
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use asset::assets::{ShaderGlobalItem, ShaderInput, ShaderInputField};
+    use std::num::NonZeroU64;
+    use wgpu::{
+        SamplerBindingType, TextureSampleType, TextureViewDimension, VertexAttribute,
+        VertexStepMode,
+    };
+
+    fn reflection() -> ShaderReflection {
+        let global = |name: &str, binding: u32, kind: ShaderGlobalItemKind| ShaderGlobalItem {
+            sematic_key: None,
+            name: name.to_owned(),
+            group: 0,
+            binding,
+            kind,
+        };
+
+        ShaderReflection {
+            vertex_entry_point: "vs_main".to_owned(),
+            fragment_entry_point: "fs_main".to_owned(),
+            globals: vec![
+                global(
+                    "albedo",
+                    0,
+                    ShaderGlobalItemKind::Texture {
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                        array_size: None,
+                    },
+                ),
+                global(
+                    "albedo_sampler",
+                    1,
+                    ShaderGlobalItemKind::Sampler {
+                        binding_type: SamplerBindingType::Filtering,
+                    },
+                ),
+                global(
+                    "roughness",
+                    2,
+                    ShaderGlobalItemKind::Buffer {
+                        size: NonZeroU64::new(4).unwrap(),
+                    },
+                ),
+            ],
+            vertex_input: ShaderInput {
+                step_mode: VertexStepMode::Vertex,
+                stride: 0,
+                fields: vec![],
+            },
+            instance_input: ShaderInput {
+                step_mode: VertexStepMode::Instance,
+                stride: 16,
+                fields: vec![ShaderInputField {
+                    semantic_key: None,
+                    name: "tint".to_owned(),
+                    attribute: VertexAttribute {
+                        format: VertexFormat::Float32x4,
+                        offset: 0,
+                        shader_location: 0,
+                    },
+                }],
+            },
+            outputs: vec![],
+        }
+    }
+
+    fn build(content: &str) -> Result<MaterialSource, MaterialDefinitionError> {
+        let definition = toml::from_str::<MaterialDefinition>(content).unwrap();
+        build_material_source(&definition, &reflection(), |path| {
+            AssetKey::Path(format!("assets/{}", path))
+        })
+    }
+
+    #[test]
+    fn build_material_source_from_definition() {
+        let source = build(
+            r#"
+            shader = "lit.wgsl"
+            blend = "alpha"
+            cull = "none"
+            front_face = "ccw"
+            topology = "line_list"
+
+            [textures]
+            albedo = "brick.png"
+            albedo_sampler = "brick.png"
+
+            [properties]
+            roughness = 0.4
+            tint = [1, 0.5, 0.5, 1]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            source.shader,
+            MaterialShaderSource::Asset(AssetKey::Path("assets/lit.wgsl".to_owned()))
+        );
+        assert_eq!(source.blend_mode, MaterialBlendMode::Alpha);
+        assert_eq!(source.cull_mode, MaterialCullMode::None);
+        assert_eq!(source.front_face, MaterialFrontFace::Ccw);
+        assert_eq!(source.topology, MaterialTopology::LineList);
+        assert_eq!(source.binding_props.len(), 3);
+        assert!(matches!(
+            &source.binding_props[0].value,
+            MaterialBindingValueSource::TextureView { texture }
+                if *texture == AssetKey::Path("assets/brick.png".to_owned())
+        ));
+        assert!(matches!(
+            source.binding_props[1].value,
+            MaterialBindingValueSource::SamplerTexture { .. }
+        ));
+        assert!(matches!(
+            source.binding_props[2].value,
+            MaterialBindingValueSource::Float32([value]) if value == 0.4
+        ));
+        assert!(matches!(
+            source.instance_props[0].value,
+            MaterialInstancePropValue::Float32x4([1.0, 0.5, 0.5, 1.0])
+        ));
+    }
+
+    #[test]
+    fn build_material_source_with_built_in_shader() {
+        let source = build(
+            r#"
+            shader = { built_in = "standard" }
+            defines = ["SKINNED"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            source.shader,
+            MaterialShaderSource::BuiltIn("standard".to_owned())
+        );
+
+        // Textures select the defines of the built-in shader.
+        let defines = find_built_in_shader("standard")
+            .unwrap()
+            .defines_for_textures(["base_color_texture", "normal_texture", "shadow_map"]);
+        assert_eq!(
+            Vec::from_iter(defines),
+            vec!["HAS_NORMAL_MAP".to_owned(), "RECEIVE_SHADOWS".to_owned()]
+        );
+    }
+
+    #[test]
+    fn build_material_source_reports_typos() {
+        let err = build(
+            r#"
+            shader = "lit.wgsl"
+
+            [textures]
+            albedoo = "brick.png"
+            "#,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "unknown texture binding `albedoo`; available texture and sampler bindings: [albedo, albedo_sampler]"
+        );
+
+        let err = build(
+            r#"
+            shader = "lit.wgsl"
+
+            [properties]
+            tint = [1, 0.5]
+            "#,
+        )
+        .unwrap_err();
+
+        assert!(matches!(
+            err,
+            MaterialDefinitionError::PropertyMismatch { .. }
+        ));
     }
 }
@@ -1,52 +1,118 @@
-use crate::{AssetPipeline, PipelineGfxBridge};
-use anyhow::{anyhow, Context};
-use asset::assets::{
-    MeshAABB, MeshSource, ModelSource, NodeSource, NodeTransform, VertexAttribute,
-    VertexAttributeKind, VertexIndexType,
+#[cfg(feature = "assimp")]
+use super::model_assimp::process_assimp_model;
+use super::{apply_import_options, obj_material_libraries, parse_mtl};
+use crate::{AssetPipeline, MetadataSchema, MetadataType, PipelineGfxBridge};
+use anyhow::Context;
+use asset::{
+    assets::{
+        MaterialCullMode, MaterialFrontFace, MeshAABB, MeshMaterialSource, MeshSource, ModelSource,
+        NodeSource, NodeTransform, VertexAttribute, VertexAttributeKind, VertexIndexType,
+    },
+    AssetKey,
 };
-use byteorder::ByteOrder;
 use pmx::Pmx;
-use russimp::{
-    mesh::PrimitiveType,
-    scene::{PostProcess, Scene},
-    Color4D, Vector3D,
-};
 use serde::{Deserialize, Serialize};
 use std::{
     collections::{hash_map::Entry, HashMap},
     mem::size_of,
-    path::Path,
+    path::{Path, PathBuf},
 };
 use zerocopy::AsBytes;
 
 #[derive(Default, Serialize, Deserialize)]
 pub struct MeshMetadata {
+    #[serde(default)]
     pub mesh: MeshTable,
 }
 
-#[derive(Default, Serialize, Deserialize)]
-pub struct MeshTable {}
+/// Import options of a model. Every key is optional.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct MeshTable {
+    /// Uniform scale of the whole model, e.g. `0.01` for a model in centimeters.
+    pub scale: f32,
+    /// The up axis of the file. The model is converted to the Y-up space of the engine.
+    pub up_axis: ModelUpAxis,
+    /// Flips the V texture coordinate, for files that put the origin of textures at the bottom-left.
+    pub flip_uvs: bool,
+    /// Generates normals for meshes that have none.
+    pub generate_normals: bool,
+    /// Generates tangents and bitangents for meshes that have normals and texture coordinates but no tangents.
+    pub generate_tangents: bool,
+}
+
+impl Default for MeshTable {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            up_axis: ModelUpAxis::Y,
+            flip_uvs: false,
+            generate_normals: true,
+            generate_tangents: true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelUpAxis {
+    Y,
+    Z,
+}
 
 impl AssetPipeline for ModelSource {
     type Metadata = MeshMetadata;
 
+    fn metadata_schema() -> MetadataSchema {
+        MetadataSchema::new().field(
+            "mesh",
+            MetadataType::Table(
+                MetadataSchema::new()
+                    .field("scale", MetadataType::Float)
+                    .field("up_axis", MetadataType::Enum(&["y", "z"]))
+                    .field("flip_uvs", MetadataType::Bool)
+                    .field("generate_normals", MetadataType::Bool)
+                    .field("generate_tangents", MetadataType::Bool),
+            ),
+        )
+    }
+
     fn process(
         file_path: &Path,
         file_content: Vec<u8>,
-        _metadata: &Self::Metadata,
+        metadata: &Self::Metadata,
         _gfx_bridge: &dyn PipelineGfxBridge,
     ) -> anyhow::Result<Self> {
-        if file_path
+        let extension = file_path
             .extension()
-            .map_or(false, |ext| ext.to_ascii_lowercase() == "pmx")
-        {
-            process_pmx_model(&file_content)
+            .map(|ext| ext.to_ascii_lowercase())
+            .unwrap_or_default();
+
+        let mut model = if extension == "pmx" {
+            process_pmx_model(&file_content)?
+        } else if extension == "obj" {
+            // Assimp cannot follow `mtllib` when loading from memory, so the materials are read here.
+            let materials = load_obj_materials(file_path, &file_content)?;
+            process_assimp_model(&file_content, &materials, &metadata.mesh)?
         } else {
-            process_assimp_model(&file_content)
-        }
+            process_assimp_model(&file_content, &HashMap::new(), &metadata.mesh)?
+        };
+
+        apply_import_options(&mut model, &metadata.mesh);
+        Ok(model)
     }
 }
 
+/// Models other than PMX go through assimp, which cannot be built for every target (e.g. wasm32).
+#[cfg(not(feature = "assimp"))]
+fn process_assimp_model(
+    _content: &[u8],
+    _materials: &HashMap<String, MeshMaterialSource>,
+    _options: &MeshTable,
+) -> anyhow::Result<ModelSource> {
+    anyhow::bail!("only PMX models can be imported without the `assimp` feature")
+}
+
 fn process_pmx_model(content: &[u8]) -> anyhow::Result<ModelSource> {
     let pmx = Pmx::parse(content).with_context(|| "failed to load mesh from file")?;
 
@@ -196,6 +262,20 @@ fn process_pmx_model(content: &[u8]) -> anyhow::Result<ModelSource> {
             }
         };
 
+        // Textures of PMX materials are not imported yet.
+        let mut mesh_material = MeshMaterialSource::new(material.name_local.clone());
+        let diffuse = &material.diffuse_color;
+        let specular = &material.specular_color;
+        mesh_material.diffuse_color = [diffuse.x, diffuse.y, diffuse.z, diffuse.w];
+        mesh_material.specular_color = [specular.x, specular.y, specular.z];
+        mesh_material.shininess = material.specular_strength;
+        mesh_material.cull_mode = if material.flags.cull_back_face {
+            MaterialCullMode::Back
+        } else {
+            MaterialCullMode::None
+        };
+        mesh_material.front_face = MaterialFrontFace::Cw;
+
         meshes.push(MeshSource {
             index: material_index as u32,
             aabb,
@@ -204,7 +284,7 @@ fn process_pmx_model(content: &[u8]) -> anyhow::Result<ModelSource> {
             vertex_attributes,
             vertex_buffer: vertices,
             vertex_count: surfaces.len() as u32 * 3,
-            material: None,
+            material: Some(mesh_material),
         });
     }
 
@@ -232,302 +312,107 @@ fn process_pmx_model(content: &[u8]) -> anyhow::Result<ModelSource> {
     })
 }
 
-fn process_assimp_model(content: &[u8]) -> anyhow::Result<ModelSource> {
-    let scene = Scene::from_buffer(
-        &content,
-        vec![
-            PostProcess::JoinIdenticalVertices,
-            PostProcess::Triangulate,
-            PostProcess::SortByPrimitiveType,
-            PostProcess::SplitLargeMeshes,
-            PostProcess::GenerateNormals,
-            PostProcess::FixInfacingNormals,
-            PostProcess::CalculateTangentSpace,
-            PostProcess::GenerateUVCoords,
-            PostProcess::GenerateBoundingBoxes,
-            PostProcess::ImproveCacheLocality,
-            PostProcess::OptimizeGraph,
-            PostProcess::OptimizeMeshes,
-        ],
-        "",
-    )
-    .with_context(|| "failed to load mesh from file")
-    .map_err(|err| anyhow!(err))?;
-    let mut extractor = SceneExtractor::new();
-
-    let root_node_index = scene
-        .root
-        .as_ref()
-        .map(|root| extractor.extract_node(&scene, root, None));
-    let nodes = extractor.nodes;
-    let meshes = extractor.meshes;
-
-    Ok(ModelSource {
-        root_node_index,
-        nodes,
-        meshes,
-    })
-}
-
-#[derive(Default)]
-struct SceneExtractor {
-    pub nodes: Vec<NodeSource>,
-    pub meshes: Vec<MeshSource>,
-}
-
-impl SceneExtractor {
-    pub fn new() -> Self {
-        Default::default()
-    }
-
-    pub fn extract_node(
-        &mut self,
-        scene: &Scene,
-        node: &russimp::node::Node,
-        parent_index: Option<u32>,
-    ) -> u32 {
-        let index = self.nodes.len() as u32;
-        self.nodes.push(NodeSource {
-            index,
-            parent_index,
-            children_indices: vec![],
-            name: node.name.clone(),
-            transform: NodeTransform {
-                matrix: [
-                    node.transformation.a1,
-                    node.transformation.b1,
-                    node.transformation.c1,
-                    node.transformation.d1,
-                    node.transformation.a2,
-                    node.transformation.b2,
-                    node.transformation.c2,
-                    node.transformation.d2,
-                    node.transformation.a3,
-                    node.transformation.b3,
-                    node.transformation.c3,
-                    node.transformation.d3,
-                    node.transformation.a4,
-                    node.transformation.b4,
-                    node.transformation.c4,
-                    node.transformation.d4,
-                ],
-            },
-            mesh_indices: vec![],
-        });
-
-        let children_indices = Vec::from_iter(
-            node.children
-                .borrow()
-                .iter()
-                .map(|child| self.extract_node(scene, child, Some(index))),
-        );
-        self.nodes[index as usize].children_indices = children_indices;
-
-        let mesh_indices = Vec::from_iter(
-            node.meshes
-                .iter()
-                .filter(|&index| {
-                    scene.meshes[*index as usize].primitive_types == PrimitiveType::Triangle as u32
-                })
-                .map(|index| self.extract_mesh(&scene.meshes[*index as usize])),
-        );
-        self.nodes[index as usize].mesh_indices = mesh_indices;
-
-        index as u32
-    }
-
-    fn extract_mesh(&mut self, mesh: &russimp::mesh::Mesh) -> u32 {
-        let index = self.meshes.len() as u32;
-        let mesh = convert_mesh(index, mesh);
-        self.meshes.push(mesh);
-        index
-    }
-}
-
-fn convert_mesh(index: u32, mesh: &russimp::mesh::Mesh) -> MeshSource {
-    let mut vertex_attributes = Vec::with_capacity(8);
-    let mut offset = 0;
-
-    // Position
-    vertex_attributes.push(VertexAttribute {
-        offset,
-        kind: VertexAttributeKind::Position,
-    });
-    offset += size_of::<[f32; 3]>() as u32;
-
-    // Normal
-    if !mesh.normals.is_empty() {
-        vertex_attributes.push(VertexAttribute {
-            offset,
-            kind: VertexAttributeKind::Normal,
-        });
-    }
-    offset += size_of::<[f32; 3]>() as u32;
-
-    // Colors
-    for (index, colors) in mesh.colors.iter().enumerate() {
-        if colors.as_ref().is_some_and(|colors| !colors.is_empty()) {
-            vertex_attributes.push(VertexAttribute {
-                offset,
-                kind: VertexAttributeKind::Color {
-                    index: index as u32,
-                },
-            });
-            offset += size_of::<[f32; 4]>() as u32;
-        }
+/// Reads the materials of an OBJ model, keyed by name. The `.mtl` files are the ones referenced by `mtllib`,
+/// or the sibling file with the same stem if there is none. Missing files are skipped, as assimp does.
+/// Texture paths are relative to the `.mtl` file.
+fn load_obj_materials(
+    file_path: &Path,
+    content: &[u8],
+) -> anyhow::Result<HashMap<String, MeshMaterialSource>> {
+    let base_path = file_path.parent().unwrap_or_else(|| Path::new(""));
+    let mut mtl_paths = Vec::from_iter(
+        obj_material_libraries(&String::from_utf8_lossy(content))
+            .into_iter()
+            .map(|name| base_path.join(name)),
+    );
+
+    if mtl_paths.is_empty() {
+        mtl_paths.push(file_path.with_extension("mtl"));
     }
 
-    // Texture coordinates
-    for (index, texture_coords) in mesh.texture_coords.iter().enumerate() {
-        if texture_coords
-            .as_ref()
-            .is_some_and(|texture_coords| !texture_coords.is_empty())
-        {
-            vertex_attributes.push(VertexAttribute {
-                offset,
-                kind: VertexAttributeKind::TexCoord {
-                    index: index as u32,
-                },
-            });
-            offset += size_of::<[f32; 2]>() as u32;
-        }
-    }
-
-    // Tangent
-    if !mesh.tangents.is_empty() {
-        vertex_attributes.push(VertexAttribute {
-            offset,
-            kind: VertexAttributeKind::Tangent,
-        });
-        offset += size_of::<[f32; 3]>() as u32;
-    }
+    let mut materials = HashMap::new();
 
-    // Bitangent
-    if !mesh.bitangents.is_empty() {
-        vertex_attributes.push(VertexAttribute {
-            offset,
-            kind: VertexAttributeKind::Bitangent,
-        });
-        offset += size_of::<[f32; 3]>() as u32;
-    }
-
-    let stride = (offset / size_of::<f32>() as u32) as usize;
-    let mut vertex_buffer = vec![0f32; mesh.vertices.len() * stride];
-
-    for attribute in &vertex_attributes {
-        let source = match attribute.kind {
-            VertexAttributeKind::Position => VertexDataCopySource::Vector3D(&mesh.vertices),
-            VertexAttributeKind::Normal => VertexDataCopySource::Vector3D(&mesh.normals),
-            VertexAttributeKind::Color { index } => {
-                VertexDataCopySource::Color4D(mesh.colors[index as usize].as_ref().unwrap())
+    for mtl_path in mtl_paths {
+        let mtl_content = match std::fs::read(&mtl_path) {
+            Ok(mtl_content) => mtl_content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => {
+                return Err(err).with_context(|| {
+                    format!("failed to read material library `{}`", mtl_path.display())
+                });
             }
-            VertexAttributeKind::TexCoord { index } => VertexDataCopySource::Vector2D(
-                mesh.texture_coords[index as usize].as_ref().unwrap(),
-            ),
-            VertexAttributeKind::Tangent => VertexDataCopySource::Vector3D(&mesh.tangents),
-            VertexAttributeKind::Bitangent => VertexDataCopySource::Vector3D(&mesh.bitangents),
-            _ => unreachable!(),
         };
-
-        for index in 0..mesh.vertices.len() {
-            source.copy_into(
-                index,
-                &mut vertex_buffer[index * stride + attribute.offset as usize / size_of::<f32>()..],
-            );
+        let mtl_base_path = mtl_path
+            .parent()
+            .map_or_else(PathBuf::new, Path::to_path_buf);
+        let parsed = parse_mtl(&String::from_utf8_lossy(&mtl_content), |path| {
+            AssetKey::Path(mtl_base_path.join(path).to_string_lossy().into_owned())
+        })
+        .with_context(|| format!("failed to parse material library `{}`", mtl_path.display()))?;
+
+        for material in parsed {
+            materials.insert(material.name.clone(), material);
         }
     }
 
-    let mut raw_vertex_buffer = vec![0u8; vertex_buffer.len() * size_of::<f32>()];
-    byteorder::LE::write_f32_into(&vertex_buffer, &mut raw_vertex_buffer);
-    drop(vertex_buffer);
-
-    let vertex_count = mesh.vertices.len();
-    let (index_type, raw_index_buffer) = if vertex_count < u8::MAX as usize {
-        let mut index_buffer = Vec::with_capacity(mesh.faces.len() * 3);
-
-        for face in &mesh.faces {
-            debug_assert_eq!(face.0.len(), 3);
-            index_buffer.push(face.0[0] as u8);
-            index_buffer.push(face.0[1] as u8);
-            index_buffer.push(face.0[2] as u8);
-        }
-
-        (VertexIndexType::U8, index_buffer)
-    } else if vertex_count < u16::MAX as usize {
-        let mut index_buffer = Vec::with_capacity(mesh.faces.len() * 3);
-
-        for face in &mesh.faces {
-            debug_assert_eq!(face.0.len(), 3);
-            index_buffer.push(face.0[0] as u16);
-            index_buffer.push(face.0[1] as u16);
-            index_buffer.push(face.0[2] as u16);
-        }
-
-        let mut raw_index_buffer = vec![0u8; index_buffer.len() * size_of::<u16>()];
-        byteorder::LE::write_u16_into(&index_buffer, &mut raw_index_buffer);
-
-        (VertexIndexType::U16, raw_index_buffer)
-    } else {
-        let mut index_buffer = Vec::with_capacity(mesh.faces.len() * 3);
-
-        for face in &mesh.faces {
-            debug_assert_eq!(face.0.len(), 3);
-            index_buffer.push(face.0[0] as u32);
-            index_buffer.push(face.0[1] as u32);
-            index_buffer.push(face.0[2] as u32);
-        }
-
-        let mut raw_index_buffer = vec![0u8; index_buffer.len() * size_of::<u32>()];
-        byteorder::LE::write_u32_into(&index_buffer, &mut raw_index_buffer);
-
-        (VertexIndexType::U32, raw_index_buffer)
-    };
-
-    let aabb = MeshAABB {
-        min: [mesh.aabb.min.x, mesh.aabb.min.y, mesh.aabb.min.z],
-        max: [mesh.aabb.max.x, mesh.aabb.max.y, mesh.aabb.max.z],
-    };
-
-    MeshSource {
-        index,
-        aabb,
-        index_type,
-        index_buffer: raw_index_buffer,
-        vertex_attributes,
-        vertex_buffer: raw_vertex_buffer,
-        vertex_count: vertex_count as u32,
-        material: None,
-    }
-}
-
-#[derive(Clone, Copy)]
-enum VertexDataCopySource<'a> {
-    Vector2D(&'a [Vector3D]),
-    Vector3D(&'a [Vector3D]),
-    Color4D(&'a [Color4D]),
+    Ok(materials)
 }
 
-impl<'a> VertexDataCopySource<'a> {
-    pub fn copy_into(&self, index: usize, dst: &mut [f32]) {
-        match self {
-            &VertexDataCopySource::Vector2D(src) => {
-                let src = src[index];
-                dst[0] = src.x;
-                dst[1] = src.y;
-            }
-            &VertexDataCopySource::Vector3D(src) => {
-                let src = src[index];
-                dst[0] = src.x;
-                dst[1] = src.y;
-                dst[2] = src.z;
-            }
-            &VertexDataCopySource::Color4D(src) => {
-                let src = src[index];
-                dst[0] = src.r;
-                dst[1] = src.g;
-                dst[2] = src.b;
-                dst[3] = src.a;
-            }
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn obj_materials_are_read_from_mtllib() {
+        let dir = std::env::temp_dir().join(format!("r3d-obj-mtl-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("materials")).unwrap();
+        std::fs::write(
+            dir.join("materials/crate.mtl"),
+            "newmtl wood\nKd 0.8 0.6 0.4\nKs 0.5 0.5 0.5\nNs 32\nd 0.5\nmap_Kd ../textures/wood.png\n",
+        )
+        .unwrap();
+
+        let obj_path = dir.join("crate.obj");
+        let obj = "mtllib materials/crate.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl wood\nf 1 2 3\n";
+        let materials = load_obj_materials(&obj_path, obj.as_bytes()).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let wood = &materials["wood"];
+        assert_eq!(wood.diffuse_color, [0.8, 0.6, 0.4, 0.5]);
+        assert_eq!(wood.specular_color, [0.5, 0.5, 0.5]);
+        assert_eq!(wood.shininess, 32.0);
+        assert_eq!(
+            wood.diffuse_texture,
+            Some(AssetKey::Path(
+                dir.join("materials")
+                    .join("../textures/wood.png")
+                    .to_string_lossy()
+                    .into_owned()
+            ))
+        );
+        assert_eq!(wood.specular_texture, None);
+
+        // The textures become dependencies of the model, so that they are loaded along with it.
+        let model = ModelSource {
+            root_node_index: None,
+            nodes: vec![],
+            meshes: vec![MeshSource {
+                index: 0,
+                aabb: MeshAABB {
+                    min: [0.0; 3],
+                    max: [1.0; 3],
+                },
+                index_type: VertexIndexType::U8,
+                index_buffer: vec![],
+                vertex_attributes: vec![],
+                vertex_buffer: vec![],
+                vertex_count: 0,
+                material: Some(wood.clone()),
+            }],
+        };
+        assert_eq!(
+            asset::AssetSource::dependencies(&model),
+            vec![wood.diffuse_texture.clone().unwrap()]
+        );
     }
 }
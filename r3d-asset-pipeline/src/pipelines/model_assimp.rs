@@ -0,0 +1,345 @@
+use super::MeshTable;
+use anyhow::{anyhow, Context};
+use asset::assets::{
+    MeshAABB, MeshMaterialSource, MeshSource, ModelSource, NodeSource, NodeTransform,
+    VertexAttribute, VertexAttributeKind, VertexIndexType,
+};
+use byteorder::ByteOrder;
+use russimp::{
+    mesh::PrimitiveType,
+    scene::{PostProcess, Scene},
+    Color4D, Vector3D,
+};
+use std::{collections::HashMap, mem::size_of};
+
+pub(super) fn process_assimp_model(
+    content: &[u8],
+    materials: &HashMap<String, MeshMaterialSource>,
+    options: &MeshTable,
+) -> anyhow::Result<ModelSource> {
+    let mut post_processes = vec![
+        PostProcess::JoinIdenticalVertices,
+        PostProcess::Triangulate,
+        PostProcess::SortByPrimitiveType,
+        PostProcess::SplitLargeMeshes,
+        PostProcess::FixInfacingNormals,
+        PostProcess::GenerateUVCoords,
+        PostProcess::GenerateBoundingBoxes,
+        PostProcess::ImproveCacheLocality,
+        PostProcess::OptimizeGraph,
+        PostProcess::OptimizeMeshes,
+    ];
+
+    // Assimp does these better than `apply_import_options`, e.g. it respects smoothing groups.
+    if options.generate_normals {
+        post_processes.push(PostProcess::GenerateNormals);
+    }
+
+    if options.generate_tangents {
+        post_processes.push(PostProcess::CalculateTangentSpace);
+    }
+
+    let scene = Scene::from_buffer(content, post_processes, "")
+        .with_context(|| "failed to load mesh from file")
+        .map_err(|err| anyhow!(err))?;
+    let mut extractor = SceneExtractor::new(materials);
+
+    let root_node_index = scene
+        .root
+        .as_ref()
+        .map(|root| extractor.extract_node(&scene, root, None));
+    let nodes = extractor.nodes;
+    let meshes = extractor.meshes;
+
+    Ok(ModelSource {
+        root_node_index,
+        nodes,
+        meshes,
+    })
+}
+
+struct SceneExtractor<'a> {
+    pub nodes: Vec<NodeSource>,
+    pub meshes: Vec<MeshSource>,
+    /// Materials that assimp could not read itself, matched to the scene materials by name.
+    pub materials: &'a HashMap<String, MeshMaterialSource>,
+}
+
+impl<'a> SceneExtractor<'a> {
+    pub fn new(materials: &'a HashMap<String, MeshMaterialSource>) -> Self {
+        Self {
+            nodes: vec![],
+            meshes: vec![],
+            materials,
+        }
+    }
+
+    pub fn extract_node(
+        &mut self,
+        scene: &Scene,
+        node: &russimp::node::Node,
+        parent_index: Option<u32>,
+    ) -> u32 {
+        let index = self.nodes.len() as u32;
+        self.nodes.push(NodeSource {
+            index,
+            parent_index,
+            children_indices: vec![],
+            name: node.name.clone(),
+            transform: NodeTransform {
+                matrix: [
+                    node.transformation.a1,
+                    node.transformation.b1,
+                    node.transformation.c1,
+                    node.transformation.d1,
+                    node.transformation.a2,
+                    node.transformation.b2,
+                    node.transformation.c2,
+                    node.transformation.d2,
+                    node.transformation.a3,
+                    node.transformation.b3,
+                    node.transformation.c3,
+                    node.transformation.d3,
+                    node.transformation.a4,
+                    node.transformation.b4,
+                    node.transformation.c4,
+                    node.transformation.d4,
+                ],
+            },
+            mesh_indices: vec![],
+        });
+
+        let children_indices = Vec::from_iter(
+            node.children
+                .borrow()
+                .iter()
+                .map(|child| self.extract_node(scene, child, Some(index))),
+        );
+        self.nodes[index as usize].children_indices = children_indices;
+
+        let mesh_indices = Vec::from_iter(
+            node.meshes
+                .iter()
+                .filter(|&index| {
+                    scene.meshes[*index as usize].primitive_types == PrimitiveType::Triangle as u32
+                })
+                .map(|index| self.extract_mesh(scene, &scene.meshes[*index as usize])),
+        );
+        self.nodes[index as usize].mesh_indices = mesh_indices;
+
+        index as u32
+    }
+
+    fn extract_mesh(&mut self, scene: &Scene, mesh: &russimp::mesh::Mesh) -> u32 {
+        let index = self.meshes.len() as u32;
+        let material = scene
+            .materials
+            .get(mesh.material_index as usize)
+            .and_then(material_name)
+            .and_then(|name| self.materials.get(name))
+            .cloned();
+        let mut mesh = convert_mesh(index, mesh);
+        mesh.material = material;
+        self.meshes.push(mesh);
+        index
+    }
+}
+
+fn material_name(material: &russimp::material::Material) -> Option<&str> {
+    material
+        .properties
+        .iter()
+        .find(|property| property.key == "?mat.name")
+        .and_then(|property| match &property.data {
+            russimp::material::PropertyTypeInfo::String(name) => Some(name.as_str()),
+            _ => None,
+        })
+}
+
+fn convert_mesh(index: u32, mesh: &russimp::mesh::Mesh) -> MeshSource {
+    let mut vertex_attributes = Vec::with_capacity(8);
+    let mut offset = 0;
+
+    // Position
+    vertex_attributes.push(VertexAttribute {
+        offset,
+        kind: VertexAttributeKind::Position,
+    });
+    offset += size_of::<[f32; 3]>() as u32;
+
+    // Normal
+    if !mesh.normals.is_empty() {
+        vertex_attributes.push(VertexAttribute {
+            offset,
+            kind: VertexAttributeKind::Normal,
+        });
+    }
+    offset += size_of::<[f32; 3]>() as u32;
+
+    // Colors
+    for (index, colors) in mesh.colors.iter().enumerate() {
+        if colors.as_ref().is_some_and(|colors| !colors.is_empty()) {
+            vertex_attributes.push(VertexAttribute {
+                offset,
+                kind: VertexAttributeKind::Color {
+                    index: index as u32,
+                },
+            });
+            offset += size_of::<[f32; 4]>() as u32;
+        }
+    }
+
+    // Texture coordinates
+    for (index, texture_coords) in mesh.texture_coords.iter().enumerate() {
+        if texture_coords
+            .as_ref()
+            .is_some_and(|texture_coords| !texture_coords.is_empty())
+        {
+            vertex_attributes.push(VertexAttribute {
+                offset,
+                kind: VertexAttributeKind::TexCoord {
+                    index: index as u32,
+                },
+            });
+            offset += size_of::<[f32; 2]>() as u32;
+        }
+    }
+
+    // Tangent
+    if !mesh.tangents.is_empty() {
+        vertex_attributes.push(VertexAttribute {
+            offset,
+            kind: VertexAttributeKind::Tangent,
+        });
+        offset += size_of::<[f32; 3]>() as u32;
+    }
+
+    // Bitangent
+    if !mesh.bitangents.is_empty() {
+        vertex_attributes.push(VertexAttribute {
+            offset,
+            kind: VertexAttributeKind::Bitangent,
+        });
+        offset += size_of::<[f32; 3]>() as u32;
+    }
+
+    let stride = (offset / size_of::<f32>() as u32) as usize;
+    let mut vertex_buffer = vec![0f32; mesh.vertices.len() * stride];
+
+    for attribute in &vertex_attributes {
+        let source = match attribute.kind {
+            VertexAttributeKind::Position => VertexDataCopySource::Vector3D(&mesh.vertices),
+            VertexAttributeKind::Normal => VertexDataCopySource::Vector3D(&mesh.normals),
+            VertexAttributeKind::Color { index } => {
+                VertexDataCopySource::Color4D(mesh.colors[index as usize].as_ref().unwrap())
+            }
+            VertexAttributeKind::TexCoord { index } => VertexDataCopySource::Vector2D(
+                mesh.texture_coords[index as usize].as_ref().unwrap(),
+            ),
+            VertexAttributeKind::Tangent => VertexDataCopySource::Vector3D(&mesh.tangents),
+            VertexAttributeKind::Bitangent => VertexDataCopySource::Vector3D(&mesh.bitangents),
+            _ => unreachable!(),
+        };
+
+        for index in 0..mesh.vertices.len() {
+            source.copy_into(
+                index,
+                &mut vertex_buffer[index * stride + attribute.offset as usize / size_of::<f32>()..],
+            );
+        }
+    }
+
+    let mut raw_vertex_buffer = vec![0u8; vertex_buffer.len() * size_of::<f32>()];
+    byteorder::LE::write_f32_into(&vertex_buffer, &mut raw_vertex_buffer);
+    drop(vertex_buffer);
+
+    let vertex_count = mesh.vertices.len();
+    let (index_type, raw_index_buffer) = if vertex_count < u8::MAX as usize {
+        let mut index_buffer = Vec::with_capacity(mesh.faces.len() * 3);
+
+        for face in &mesh.faces {
+            debug_assert_eq!(face.0.len(), 3);
+            index_buffer.push(face.0[0] as u8);
+            index_buffer.push(face.0[1] as u8);
+            index_buffer.push(face.0[2] as u8);
+        }
+
+        (VertexIndexType::U8, index_buffer)
+    } else if vertex_count < u16::MAX as usize {
+        let mut index_buffer = Vec::with_capacity(mesh.faces.len() * 3);
+
+        for face in &mesh.faces {
+            debug_assert_eq!(face.0.len(), 3);
+            index_buffer.push(face.0[0] as u16);
+            index_buffer.push(face.0[1] as u16);
+            index_buffer.push(face.0[2] as u16);
+        }
+
+        let mut raw_index_buffer = vec![0u8; index_buffer.len() * size_of::<u16>()];
+        byteorder::LE::write_u16_into(&index_buffer, &mut raw_index_buffer);
+
+        (VertexIndexType::U16, raw_index_buffer)
+    } else {
+        let mut index_buffer = Vec::with_capacity(mesh.faces.len() * 3);
+
+        for face in &mesh.faces {
+            debug_assert_eq!(face.0.len(), 3);
+            index_buffer.push(face.0[0] as u32);
+            index_buffer.push(face.0[1] as u32);
+            index_buffer.push(face.0[2] as u32);
+        }
+
+        let mut raw_index_buffer = vec![0u8; index_buffer.len() * size_of::<u32>()];
+        byteorder::LE::write_u32_into(&index_buffer, &mut raw_index_buffer);
+
+        (VertexIndexType::U32, raw_index_buffer)
+    };
+
+    let aabb = MeshAABB {
+        min: [mesh.aabb.min.x, mesh.aabb.min.y, mesh.aabb.min.z],
+        max: [mesh.aabb.max.x, mesh.aabb.max.y, mesh.aabb.max.z],
+    };
+
+    MeshSource {
+        index,
+        aabb,
+        index_type,
+        index_buffer: raw_index_buffer,
+        vertex_attributes,
+        vertex_buffer: raw_vertex_buffer,
+        vertex_count: vertex_count as u32,
+        material: None,
+    }
+}
+
+#[derive(Clone, Copy)]
+enum VertexDataCopySource<'a> {
+    Vector2D(&'a [Vector3D]),
+    Vector3D(&'a [Vector3D]),
+    Color4D(&'a [Color4D]),
+}
+
+impl<'a> VertexDataCopySource<'a> {
+    pub fn copy_into(&self, index: usize, dst: &mut [f32]) {
+        match self {
+            &VertexDataCopySource::Vector2D(src) => {
+                let src = src[index];
+                dst[0] = src.x;
+                dst[1] = src.y;
+            }
+            &VertexDataCopySource::Vector3D(src) => {
+                let src = src[index];
+                dst[0] = src.x;
+                dst[1] = src.y;
+                dst[2] = src.z;
+            }
+            &VertexDataCopySource::Color4D(src) => {
+                let src = src[index];
+                dst[0] = src.r;
+                dst[1] = src.g;
+                dst[2] = src.b;
+                dst[3] = src.a;
+            }
+        }
+    }
+}
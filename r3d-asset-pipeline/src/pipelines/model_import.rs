@@ -0,0 +1,437 @@
+use super::{MeshTable, ModelUpAxis};
+use asset::assets::{
+    MeshSource, ModelSource, VertexAttribute, VertexAttributeKind, VertexIndexType,
+};
+
+type Vec3 = [f32; 3];
+
+/// Applies the import options of the metadata to a processed model.
+///
+/// The scale and the axis conversion are applied to the vertices and the node transforms, so that the model
+/// is in the Y-up space of the engine without an extra root transform. Normals and tangents are only generated
+/// for meshes that do not have them. Meshes with invalid vertex or index buffers are left as they are.
+pub fn apply_import_options(model: &mut ModelSource, options: &MeshTable) {
+    let convert = |v: Vec3| match options.up_axis {
+        ModelUpAxis::Y => v,
+        ModelUpAxis::Z => [v[0], v[2], -v[1]],
+    };
+    let scale = options.scale;
+
+    if options.up_axis != ModelUpAxis::Y || scale != 1.0 {
+        for node in &mut model.nodes {
+            node.transform.matrix = convert_matrix(&node.transform.matrix, convert, scale);
+        }
+
+        for mesh in model.meshes.iter_mut().filter(|mesh| is_layout_valid(mesh)) {
+            let min = convert(mesh.aabb.min).map(|value| value * scale);
+            let max = convert(mesh.aabb.max).map(|value| value * scale);
+            mesh.aabb.min = [0, 1, 2].map(|axis| min[axis].min(max[axis]));
+            mesh.aabb.max = [0, 1, 2].map(|axis| min[axis].max(max[axis]));
+
+            for attribute in mesh.vertex_attributes.clone() {
+                match attribute.kind {
+                    VertexAttributeKind::Position => {
+                        map_vec3(mesh, attribute, |v| convert(v).map(|value| value * scale));
+                    }
+                    VertexAttributeKind::Normal
+                    | VertexAttributeKind::Tangent
+                    | VertexAttributeKind::Bitangent => map_vec3(mesh, attribute, convert),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    for mesh in model.meshes.iter_mut().filter(|mesh| is_layout_valid(mesh)) {
+        if options.flip_uvs {
+            for attribute in mesh.vertex_attributes.clone() {
+                if let VertexAttributeKind::TexCoord { .. } = attribute.kind {
+                    let offset = attribute.offset as usize + 4;
+                    for_each_vertex(mesh, |buffer, base| {
+                        let v = read_f32(buffer, base + offset);
+                        write_f32(buffer, base + offset, 1.0 - v);
+                    });
+                }
+            }
+        }
+
+        if options.generate_normals && !has_attribute(mesh, VertexAttributeKind::Normal) {
+            if let Some(normals) = generate_normals(mesh) {
+                append_attributes(mesh, vec![(VertexAttributeKind::Normal, normals)]);
+            }
+        }
+
+        if options.generate_tangents && !has_attribute(mesh, VertexAttributeKind::Tangent) {
+            if let Some((tangents, bitangents)) = generate_tangents(mesh) {
+                append_attributes(
+                    mesh,
+                    vec![
+                        (VertexAttributeKind::Tangent, tangents),
+                        (VertexAttributeKind::Bitangent, bitangents),
+                    ],
+                );
+            }
+        }
+    }
+}
+
+/// Converts a node transform, which maps row vectors, into the converted space. For the axis rotation `R`,
+/// the linear part `L` becomes `R^T * L * R`, and the translation is converted and scaled.
+fn convert_matrix(matrix: &[f32; 16], convert: impl Fn(Vec3) -> Vec3, scale: f32) -> [f32; 16] {
+    let rotation = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]].map(&convert);
+    let transposed = [0, 1, 2].map(|row| [0, 1, 2].map(|column| rotation[column][row]));
+    let linear = [0, 1, 2].map(|row| [matrix[row * 4], matrix[row * 4 + 1], matrix[row * 4 + 2]]);
+    let linear = mul(&mul(&transposed, &linear), &rotation);
+    let mut result = *matrix;
+
+    for (row, linear) in linear.iter().enumerate() {
+        result[row * 4..row * 4 + 3].copy_from_slice(linear);
+    }
+
+    let translation = [matrix[12], matrix[13], matrix[14]];
+    result[12..15].copy_from_slice(&convert(translation).map(|value| value * scale));
+    result
+}
+
+/// Returns `true` if every attribute fits in the stride of the vertex buffer.
+fn is_layout_valid(mesh: &MeshSource) -> bool {
+    let stride = stride(mesh);
+
+    stride * mesh.vertex_count as usize == mesh.vertex_buffer.len()
+        && mesh.vertex_attributes.iter().all(|attribute| {
+            let size = match attribute.kind {
+                VertexAttributeKind::TexCoord { .. } => 8,
+                VertexAttributeKind::Color { .. } | VertexAttributeKind::Extra { .. } => 16,
+                _ => 12,
+            };
+            attribute.offset as usize + size <= stride
+        })
+}
+
+fn has_attribute(mesh: &MeshSource, kind: VertexAttributeKind) -> bool {
+    mesh.vertex_attributes
+        .iter()
+        .any(|attribute| attribute.kind == kind)
+}
+
+fn stride(mesh: &MeshSource) -> usize {
+    mesh.vertex_buffer
+        .len()
+        .checked_div(mesh.vertex_count as usize)
+        .unwrap_or(0)
+}
+
+fn read_f32(buffer: &[u8], at: usize) -> f32 {
+    f32::from_le_bytes(buffer[at..at + 4].try_into().unwrap())
+}
+
+fn write_f32(buffer: &mut [u8], at: usize, value: f32) {
+    buffer[at..at + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+fn for_each_vertex(mesh: &mut MeshSource, mut f: impl FnMut(&mut [u8], usize)) {
+    let stride = stride(mesh);
+
+    for index in 0..mesh.vertex_count as usize {
+        f(&mut mesh.vertex_buffer, index * stride);
+    }
+}
+
+fn map_vec3(mesh: &mut MeshSource, attribute: VertexAttribute, f: impl Fn(Vec3) -> Vec3) {
+    let offset = attribute.offset as usize;
+    for_each_vertex(mesh, |buffer, base| {
+        let at = base + offset;
+        let value = f([0, 1, 2].map(|component| read_f32(buffer, at + component * 4)));
+
+        for (component, value) in value.into_iter().enumerate() {
+            write_f32(buffer, at + component * 4, value);
+        }
+    });
+}
+
+fn read_attribute(
+    mesh: &MeshSource,
+    kind: VertexAttributeKind,
+    components: usize,
+) -> Option<Vec<Vec3>> {
+    let attribute = mesh
+        .vertex_attributes
+        .iter()
+        .find(|attribute| attribute.kind == kind)?;
+    let stride = stride(mesh);
+
+    Some(Vec::from_iter((0..mesh.vertex_count as usize).map(
+        |index| {
+            let at = index * stride + attribute.offset as usize;
+            let mut value = [0.0; 3];
+
+            for (component, value) in value.iter_mut().enumerate().take(components) {
+                *value = read_f32(&mesh.vertex_buffer, at + component * 4);
+            }
+
+            value
+        },
+    )))
+}
+
+/// Returns the triangles of the mesh, or `None` if the index buffer is invalid.
+fn triangles(mesh: &MeshSource) -> Option<Vec<[usize; 3]>> {
+    let index_size = match mesh.index_type {
+        VertexIndexType::U8 => 1,
+        VertexIndexType::U16 => 2,
+        VertexIndexType::U32 => 4,
+    };
+    let indices =
+        Vec::from_iter(mesh.index_buffer.chunks_exact(index_size).map(
+            |bytes| match mesh.index_type {
+                VertexIndexType::U8 => bytes[0] as usize,
+                VertexIndexType::U16 => u16::from_le_bytes([bytes[0], bytes[1]]) as usize,
+                VertexIndexType::U32 => u32::from_le_bytes(bytes.try_into().unwrap()) as usize,
+            },
+        ));
+
+    if !mesh.index_buffer.len().is_multiple_of(index_size * 3)
+        || indices
+            .iter()
+            .any(|&index| mesh.vertex_count as usize <= index)
+    {
+        return None;
+    }
+
+    Some(Vec::from_iter(
+        indices
+            .chunks_exact(3)
+            .map(|triangle| [triangle[0], triangle[1], triangle[2]]),
+    ))
+}
+
+/// Generates smooth normals, weighted by the area of the triangles.
+fn generate_normals(mesh: &MeshSource) -> Option<Vec<Vec3>> {
+    let positions = read_attribute(mesh, VertexAttributeKind::Position, 3)?;
+    let mut normals = vec![[0.0; 3]; positions.len()];
+
+    for [a, b, c] in triangles(mesh)? {
+        let normal = cross(
+            sub(positions[b], positions[a]),
+            sub(positions[c], positions[a]),
+        );
+
+        for index in [a, b, c] {
+            normals[index] = add(normals[index], normal);
+        }
+    }
+
+    Some(Vec::from_iter(normals.into_iter().map(normalize)))
+}
+
+/// Generates tangents and bitangents from the first texture coordinates, orthogonal to the normals.
+fn generate_tangents(mesh: &MeshSource) -> Option<(Vec<Vec3>, Vec<Vec3>)> {
+    let positions = read_attribute(mesh, VertexAttributeKind::Position, 3)?;
+    let normals = read_attribute(mesh, VertexAttributeKind::Normal, 3)?;
+    let uvs = read_attribute(mesh, VertexAttributeKind::TexCoord { index: 0 }, 2)?;
+    let mut tangents = vec![[0.0; 3]; positions.len()];
+    let mut bitangents = vec![[0.0; 3]; positions.len()];
+
+    for [a, b, c] in triangles(mesh)? {
+        let edge_ab = sub(positions[b], positions[a]);
+        let edge_ac = sub(positions[c], positions[a]);
+        let uv_ab = sub(uvs[b], uvs[a]);
+        let uv_ac = sub(uvs[c], uvs[a]);
+        let det = uv_ab[0] * uv_ac[1] - uv_ac[0] * uv_ab[1];
+
+        if det.abs() < f32::EPSILON {
+            continue;
+        }
+
+        let r = det.recip();
+        let tangent = sub(
+            edge_ab.map(|v| v * uv_ac[1] * r),
+            edge_ac.map(|v| v * uv_ab[1] * r),
+        );
+        let bitangent = sub(
+            edge_ac.map(|v| v * uv_ab[0] * r),
+            edge_ab.map(|v| v * uv_ac[0] * r),
+        );
+
+        for index in [a, b, c] {
+            tangents[index] = add(tangents[index], tangent);
+            bitangents[index] = add(bitangents[index], bitangent);
+        }
+    }
+
+    let mut result_bitangents = Vec::with_capacity(positions.len());
+
+    for index in 0..positions.len() {
+        let normal = normals[index];
+        let tangent = normalize(sub(
+            tangents[index],
+            normal.map(|v| v * dot(normal, tangents[index])),
+        ));
+        let handedness = if dot(cross(normal, tangent), bitangents[index]) < 0.0 {
+            -1.0
+        } else {
+            1.0
+        };
+        tangents[index] = tangent;
+        result_bitangents.push(cross(normal, tangent).map(|v| v * handedness));
+    }
+
+    Some((tangents, result_bitangents))
+}
+
+/// Appends vec3 attributes to every vertex, re-interleaving the vertex buffer.
+fn append_attributes(mesh: &mut MeshSource, attributes: Vec<(VertexAttributeKind, Vec<Vec3>)>) {
+    let stride = stride(mesh);
+    let vertex_count = mesh.vertex_count as usize;
+    let new_stride = stride + attributes.len() * std::mem::size_of::<Vec3>();
+    let mut vertex_buffer = Vec::with_capacity(vertex_count * new_stride);
+
+    for index in 0..vertex_count {
+        vertex_buffer.extend_from_slice(&mesh.vertex_buffer[index * stride..(index + 1) * stride]);
+
+        for (_, values) in &attributes {
+            for value in values[index] {
+                vertex_buffer.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+    }
+
+    for (index, (kind, _)) in attributes.iter().enumerate() {
+        mesh.vertex_attributes.push(VertexAttribute {
+            offset: (stride + index * std::mem::size_of::<Vec3>()) as u32,
+            kind: *kind,
+        });
+    }
+
+    mesh.vertex_buffer = vertex_buffer;
+}
+
+fn add(lhs: Vec3, rhs: Vec3) -> Vec3 {
+    [lhs[0] + rhs[0], lhs[1] + rhs[1], lhs[2] + rhs[2]]
+}
+
+fn sub(lhs: Vec3, rhs: Vec3) -> Vec3 {
+    [lhs[0] - rhs[0], lhs[1] - rhs[1], lhs[2] - rhs[2]]
+}
+
+fn dot(lhs: Vec3, rhs: Vec3) -> f32 {
+    lhs[0] * rhs[0] + lhs[1] * rhs[1] + lhs[2] * rhs[2]
+}
+
+fn cross(lhs: Vec3, rhs: Vec3) -> Vec3 {
+    [
+        lhs[1] * rhs[2] - lhs[2] * rhs[1],
+        lhs[2] * rhs[0] - lhs[0] * rhs[2],
+        lhs[0] * rhs[1] - lhs[1] * rhs[0],
+    ]
+}
+
+fn mul(lhs: &[Vec3; 3], rhs: &[Vec3; 3]) -> [Vec3; 3] {
+    [0, 1, 2].map(|row| {
+        [0, 1, 2].map(|column| {
+            (0..3)
+                .map(|index| lhs[row][index] * rhs[index][column])
+                .sum()
+        })
+    })
+}
+
+fn normalize(v: Vec3) -> Vec3 {
+    let len = dot(v, v).sqrt();
+
+    if len < f32::EPSILON {
+        v
+    } else {
+        v.map(|value| value / len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use asset::assets::{MeshAABB, NodeSource, NodeTransform};
+
+    fn triangle_model(positions: [Vec3; 3]) -> ModelSource {
+        ModelSource {
+            root_node_index: Some(0),
+            nodes: vec![NodeSource {
+                index: 0,
+                parent_index: None,
+                children_indices: vec![],
+                name: "root".to_owned(),
+                transform: NodeTransform {
+                    matrix: [
+                        1.0, 0.0, 0.0, 0.0, //
+                        0.0, 1.0, 0.0, 0.0, //
+                        0.0, 0.0, 1.0, 0.0, //
+                        0.0, 0.0, 4.0, 1.0, //
+                    ],
+                },
+                mesh_indices: vec![0],
+            }],
+            meshes: vec![MeshSource {
+                index: 0,
+                aabb: MeshAABB {
+                    min: [0.0; 3],
+                    max: [1.0; 3],
+                },
+                index_type: VertexIndexType::U8,
+                index_buffer: vec![0, 1, 2],
+                vertex_attributes: vec![VertexAttribute {
+                    offset: 0,
+                    kind: VertexAttributeKind::Position,
+                }],
+                vertex_buffer: Vec::from_iter(
+                    positions
+                        .iter()
+                        .flatten()
+                        .flat_map(|value| value.to_le_bytes()),
+                ),
+                vertex_count: 3,
+                material: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn z_up_model_converts_to_y_up() {
+        let mut model = triangle_model([[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 2.0]]);
+        let options = MeshTable {
+            scale: 0.5,
+            up_axis: ModelUpAxis::Z,
+            generate_normals: false,
+            ..Default::default()
+        };
+        apply_import_options(&mut model, &options);
+
+        let mesh = &model.meshes[0];
+        assert_eq!(
+            read_attribute(mesh, VertexAttributeKind::Position, 3).unwrap(),
+            vec![[0.0, 0.0, 0.0], [0.5, 0.0, 0.0], [0.0, 1.0, 0.0]]
+        );
+        assert_eq!(mesh.aabb.min, [0.0, 0.0, -0.5]);
+        assert_eq!(mesh.aabb.max, [0.5, 0.5, 0.0]);
+        assert_eq!(&model.nodes[0].transform.matrix[12..15], &[0.0, 2.0, 0.0]);
+        assert!(!has_attribute(mesh, VertexAttributeKind::Normal));
+    }
+
+    #[test]
+    fn model_without_normals_gets_generated_ones() {
+        // Lies on the ground of a Z-up model, so the normal points up after the conversion.
+        let mut model = triangle_model([[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]);
+        let options = MeshTable {
+            up_axis: ModelUpAxis::Z,
+            ..Default::default()
+        };
+        apply_import_options(&mut model, &options);
+
+        let mesh = &model.meshes[0];
+        assert_eq!(mesh.vertex_buffer.len(), 3 * 24);
+        assert_eq!(
+            read_attribute(mesh, VertexAttributeKind::Normal, 3).unwrap(),
+            vec![[0.0, 1.0, 0.0]; 3]
+        );
+        // Without texture coordinates, tangents cannot be generated.
+        assert!(!has_attribute(mesh, VertexAttributeKind::Tangent));
+    }
+}
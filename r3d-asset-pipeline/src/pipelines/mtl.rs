@@ -0,0 +1,154 @@
+use asset::{assets::MeshMaterialSource, AssetKey};
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum MtlParseError {
+    #[error("line {line}: `{keyword}` appears before any `newmtl`")]
+    NoMaterial { line: usize, keyword: String },
+    #[error("line {line}: `{keyword}` expects {expected}")]
+    InvalidValue {
+        line: usize,
+        keyword: String,
+        expected: &'static str,
+    },
+}
+
+/// The statements that are read, besides `newmtl`.
+const KEYWORDS: &[&str] = &[
+    "Kd", "Ks", "Ns", "d", "Tr", "map_Kd", "map_Ks", "map_Bump", "map_bump", "bump", "norm",
+];
+
+/// Parses the materials of a Wavefront `.mtl` file.
+///
+/// Diffuse (`Kd`, `d`, `Tr`), specular (`Ks`, `Ns`) and texture (`map_Kd`, `map_Ks`, `map_Bump`, `bump`, `norm`)
+/// statements are read; the others are ignored. Texture paths are resolved to asset keys by `resolve_path`.
+/// Texture options such as `-s 1 1 1` are skipped, as the path is taken from the last word of the statement.
+pub fn parse_mtl(
+    content: &str,
+    resolve_path: impl Fn(&str) -> AssetKey,
+) -> Result<Vec<MeshMaterialSource>, MtlParseError> {
+    let mut materials = Vec::<MeshMaterialSource>::new();
+
+    for (index, line) in content.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        let (keyword, rest) = match line.split_once(char::is_whitespace) {
+            Some((keyword, rest)) => (keyword, rest.trim()),
+            None => (line, ""),
+        };
+
+        if keyword.is_empty() {
+            continue;
+        }
+
+        if keyword == "newmtl" {
+            materials.push(MeshMaterialSource::new(rest));
+            continue;
+        }
+
+        let invalid = |expected: &'static str| MtlParseError::InvalidValue {
+            line: index + 1,
+            keyword: keyword.to_owned(),
+            expected,
+        };
+        let floats = |count: usize| {
+            let values = rest
+                .split_whitespace()
+                .map(|value| value.parse::<f32>())
+                .collect::<Result<Vec<_>, _>>()
+                .ok()
+                .filter(|values| values.len() == count);
+            values.ok_or_else(|| {
+                invalid(if count == 1 {
+                    "a number"
+                } else {
+                    "three numbers"
+                })
+            })
+        };
+        let texture = || {
+            rest.split_whitespace()
+                .last()
+                .map(|path| resolve_path(&path.replace('\\', "/")))
+                .ok_or_else(|| invalid("a texture path"))
+        };
+
+        if !KEYWORDS.contains(&keyword) {
+            continue;
+        }
+
+        let material = materials
+            .last_mut()
+            .ok_or_else(|| MtlParseError::NoMaterial {
+                line: index + 1,
+                keyword: keyword.to_owned(),
+            })?;
+
+        match keyword {
+            "Kd" => {
+                let color = floats(3)?;
+                material.diffuse_color[..3].copy_from_slice(&color);
+            }
+            "Ks" => {
+                let color = floats(3)?;
+                material.specular_color.copy_from_slice(&color);
+            }
+            "Ns" => material.shininess = floats(1)?[0],
+            "d" => material.diffuse_color[3] = floats(1)?[0],
+            "Tr" => material.diffuse_color[3] = 1.0 - floats(1)?[0],
+            "map_Kd" => material.diffuse_texture = Some(texture()?),
+            "map_Ks" => material.specular_texture = Some(texture()?),
+            _ => material.normal_texture = Some(texture()?),
+        }
+    }
+
+    Ok(materials)
+}
+
+/// Returns the `.mtl` file names referenced by `mtllib` statements of a Wavefront `.obj` file.
+pub fn obj_material_libraries(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("mtllib"))
+        .filter(|rest| rest.starts_with(char::is_whitespace))
+        .flat_map(|rest| rest.split_whitespace())
+        .map(|name| name.replace('\\', "/"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_mtl_reports_misplaced_and_invalid_statements() {
+        let resolve = |path: &str| AssetKey::Path(path.to_owned());
+
+        assert_eq!(
+            parse_mtl("Kd 1 1 1", resolve),
+            Err(MtlParseError::NoMaterial {
+                line: 1,
+                keyword: "Kd".to_owned()
+            })
+        );
+        assert_eq!(
+            parse_mtl("newmtl a\n\nKs 1 x 1", resolve),
+            Err(MtlParseError::InvalidValue {
+                line: 3,
+                keyword: "Ks".to_owned(),
+                expected: "three numbers"
+            })
+        );
+
+        let materials = parse_mtl(
+            "# exported\nnewmtl glass\nillum 4\nTr 0.25\nbump -bm 0.5 maps\\glass_n.png\n",
+            resolve,
+        )
+        .unwrap();
+        assert_eq!(materials.len(), 1);
+        assert_eq!(materials[0].diffuse_color, [1.0, 1.0, 1.0, 0.75]);
+        assert_eq!(
+            materials[0].normal_texture,
+            Some(AssetKey::Path("maps/glass_n.png".to_owned()))
+        );
+    }
+}
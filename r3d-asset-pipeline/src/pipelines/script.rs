@@ -0,0 +1,47 @@
+use crate::{AssetPipeline, MetadataSchema, MetadataType, PipelineGfxBridge};
+use asset::assets::ScriptSource;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct ScriptMetadata {
+    #[serde(default)]
+    pub script: ScriptTable,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct ScriptTable {
+    /// The name of the script, shown in error messages. Defaults to the file name.
+    pub name: Option<String>,
+}
+
+impl AssetPipeline for ScriptSource {
+    type Metadata = ScriptMetadata;
+
+    fn metadata_schema() -> MetadataSchema {
+        MetadataSchema::new().field(
+            "script",
+            MetadataType::Table(MetadataSchema::new().field("name", MetadataType::String)),
+        )
+    }
+
+    fn process(
+        file_path: &Path,
+        file_content: Vec<u8>,
+        metadata: &Self::Metadata,
+        _gfx_bridge: &dyn PipelineGfxBridge,
+    ) -> anyhow::Result<Self> {
+        let name = match &metadata.script.name {
+            Some(name) => name.clone(),
+            None => file_path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        };
+
+        Ok(Self {
+            name,
+            source: String::from_utf8(file_content)?,
+        })
+    }
+}
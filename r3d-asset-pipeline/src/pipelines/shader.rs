@@ -1,8 +1,12 @@
-use crate::{AssetPipeline, PipelineGfxBridge};
+use super::preprocess_shader;
+use crate::{AssetPipeline, MetadataSchema, PipelineGfxBridge};
 use anyhow::Context;
-use asset::assets::{
-    ShaderGlobalItem, ShaderGlobalItemKind, ShaderInput, ShaderInputField, ShaderOutputItem,
-    ShaderReflection, ShaderSource,
+use asset::{
+    assets::{
+        specialize_shader_source, ShaderGlobalItem, ShaderGlobalItemKind, ShaderInput,
+        ShaderInputField, ShaderOutputItem, ShaderReflection, ShaderSource,
+    },
+    AssetKey,
 };
 use naga::{
     AddressSpace, ArraySize, Binding, Function, FunctionArgument, GlobalVariable, ImageClass,
@@ -11,6 +15,7 @@ use naga::{
 };
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::BTreeSet,
     num::{NonZeroU32, NonZeroU64},
     path::Path,
 };
@@ -36,78 +41,102 @@ pub struct ShaderMetadata;
 impl AssetPipeline for ShaderSource {
     type Metadata = ShaderMetadata;
 
+    fn metadata_schema() -> MetadataSchema {
+        MetadataSchema::new()
+    }
+
     fn process(
-        _file_path: &Path,
+        file_path: &Path,
         file_content: Vec<u8>,
         _metadata: &Self::Metadata,
         gfx_bridge: &dyn PipelineGfxBridge,
     ) -> anyhow::Result<Self> {
         let source = std::str::from_utf8(&file_content)
             .with_context(|| "failed to decode shader source into utf8 string")?;
-        let module = naga::front::wgsl::parse_str(&source)
-            .with_context(|| "failed to parse wgsl shader source")?;
-
-        let globals = reflect_globals(gfx_bridge, &module);
-
-        let mut vertex_entry_point = None;
-        let mut fragment_entry_point = None;
-        let mut vertex_input = None;
-        let mut instance_input = None;
-        let mut outputs = None;
-
-        for entry_point in &module.entry_points {
-            match entry_point.stage {
-                ShaderStage::Vertex => {
-                    vertex_entry_point = Some(entry_point.name.clone());
-
-                    for input in
-                        reflect_vertex_entry_point(gfx_bridge, &module, &entry_point.function)
-                    {
-                        match input.step_mode {
-                            VertexStepMode::Vertex => {
-                                vertex_input = Some(input);
-                            }
-                            VertexStepMode::Instance => {
-                                instance_input = Some(input);
-                            }
+        let preprocessed =
+            preprocess_shader(file_path, source, |path| std::fs::read_to_string(path))
+                .with_context(|| "failed to preprocess shader source")?;
+        let specialized = specialize_shader_source(&preprocessed.source, &BTreeSet::new())
+            .with_context(|| "failed to specialize shader source")?;
+        let reflection = reflect_shader(gfx_bridge, &specialized)?;
+
+        Ok(ShaderSource {
+            source: preprocessed.source,
+            reflection,
+            defines: vec![],
+            includes: Vec::from_iter(
+                preprocessed
+                    .includes
+                    .iter()
+                    .map(|path| AssetKey::Path(path.to_string_lossy().into_owned())),
+            ),
+        })
+    }
+}
+
+/// Reflects a specialized WGSL source, i.e. without any preprocessor directive.
+pub fn reflect_shader(
+    gfx_bridge: &dyn PipelineGfxBridge,
+    source: &str,
+) -> anyhow::Result<ShaderReflection> {
+    let module = naga::front::wgsl::parse_str(source)
+        .with_context(|| "failed to parse wgsl shader source")?;
+
+    let globals = reflect_globals(gfx_bridge, &module);
+
+    let mut vertex_entry_point = None;
+    let mut fragment_entry_point = None;
+    let mut vertex_input = None;
+    let mut instance_input = None;
+    let mut outputs = None;
+
+    for entry_point in &module.entry_points {
+        match entry_point.stage {
+            ShaderStage::Vertex => {
+                vertex_entry_point = Some(entry_point.name.clone());
+
+                for input in reflect_vertex_entry_point(gfx_bridge, &module, &entry_point.function)
+                {
+                    match input.step_mode {
+                        VertexStepMode::Vertex => {
+                            vertex_input = Some(input);
+                        }
+                        VertexStepMode::Instance => {
+                            instance_input = Some(input);
                         }
                     }
                 }
-                ShaderStage::Fragment => {
-                    fragment_entry_point = Some(entry_point.name.clone());
+            }
+            ShaderStage::Fragment => {
+                fragment_entry_point = Some(entry_point.name.clone());
 
-                    if let Some(fragment_outputs) =
-                        reflect_fragment_entry_point(gfx_bridge, &module, &entry_point.function)
-                    {
-                        outputs = Some(fragment_outputs);
-                    }
+                if let Some(fragment_outputs) =
+                    reflect_fragment_entry_point(gfx_bridge, &module, &entry_point.function)
+                {
+                    outputs = Some(fragment_outputs);
                 }
-                ShaderStage::Compute => continue,
             }
+            ShaderStage::Compute => continue,
         }
-
-        Ok(ShaderSource {
-            source: source.to_owned(),
-            reflection: ShaderReflection {
-                vertex_entry_point: vertex_entry_point
-                    .ok_or(ShaderReflectionError::NoVertexEntryPoint)?,
-                fragment_entry_point: fragment_entry_point
-                    .ok_or(ShaderReflectionError::NoFragmentEntryPoint)?,
-                globals,
-                vertex_input: vertex_input.unwrap_or_else(|| ShaderInput {
-                    step_mode: VertexStepMode::Vertex,
-                    stride: 0,
-                    fields: vec![],
-                }),
-                instance_input: instance_input.unwrap_or_else(|| ShaderInput {
-                    step_mode: VertexStepMode::Instance,
-                    stride: 0,
-                    fields: vec![],
-                }),
-                outputs: outputs.unwrap_or_else(|| vec![]),
-            },
-        })
     }
+
+    Ok(ShaderReflection {
+        vertex_entry_point: vertex_entry_point.ok_or(ShaderReflectionError::NoVertexEntryPoint)?,
+        fragment_entry_point: fragment_entry_point
+            .ok_or(ShaderReflectionError::NoFragmentEntryPoint)?,
+        globals,
+        vertex_input: vertex_input.unwrap_or_else(|| ShaderInput {
+            step_mode: VertexStepMode::Vertex,
+            stride: 0,
+            fields: vec![],
+        }),
+        instance_input: instance_input.unwrap_or_else(|| ShaderInput {
+            step_mode: VertexStepMode::Instance,
+            stride: 0,
+            fields: vec![],
+        }),
+        outputs: outputs.unwrap_or_else(Vec::new),
+    })
 }
 
 fn reflect_globals(gfx_bridge: &dyn PipelineGfxBridge, module: &Module) -> Vec<ShaderGlobalItem> {
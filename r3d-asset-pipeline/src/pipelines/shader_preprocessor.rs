@@ -0,0 +1,167 @@
+use std::path::{Component, Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ShaderPreprocessError {
+    #[error("failed to read included file `{}`: {source}", path.display())]
+    ReadError {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("`{}`, line {line}: expected `#include \"path\"`", path.display())]
+    InvalidInclude { path: PathBuf, line: usize },
+    #[error("`{}` includes itself through: {}", path.display(), display_chain(chain))]
+    CyclicInclude { path: PathBuf, chain: Vec<PathBuf> },
+}
+
+fn display_chain(chain: &[PathBuf]) -> String {
+    Vec::from_iter(chain.iter().map(|path| format!("`{}`", path.display()))).join(" -> ")
+}
+
+/// A shader source with its includes expanded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreprocessedShader {
+    pub source: String,
+    /// Every file that was included, directly or not, in the order of inclusion.
+    pub includes: Vec<PathBuf>,
+}
+
+/// Expands `#include "path"` lines of a WGSL source. Paths are relative to the file that includes them.
+///
+/// Each file is included at most once, so that shared snippets can be included by several files without
+/// duplicated definitions. A file that includes itself, directly or not, is an error. Files are read by `read`,
+/// so that the sources need not come from the file system.
+pub fn preprocess_shader(
+    path: &Path,
+    source: &str,
+    read: impl Fn(&Path) -> std::io::Result<String>,
+) -> Result<PreprocessedShader, ShaderPreprocessError> {
+    let mut preprocessor = Preprocessor {
+        read,
+        stack: vec![],
+        result: PreprocessedShader {
+            source: String::with_capacity(source.len()),
+            includes: vec![],
+        },
+    };
+    preprocessor.expand(&normalize(path), source)?;
+    Ok(preprocessor.result)
+}
+
+struct Preprocessor<F> {
+    read: F,
+    /// The files being expanded, outermost first.
+    stack: Vec<PathBuf>,
+    result: PreprocessedShader,
+}
+
+impl<F> Preprocessor<F>
+where
+    F: Fn(&Path) -> std::io::Result<String>,
+{
+    fn expand(&mut self, path: &Path, source: &str) -> Result<(), ShaderPreprocessError> {
+        self.stack.push(path.to_path_buf());
+
+        for (index, line) in source.lines().enumerate() {
+            let directive = match line.trim_start().strip_prefix("#include") {
+                Some(directive) => directive.trim(),
+                None => {
+                    self.result.source.push_str(line);
+                    self.result.source.push('\n');
+                    continue;
+                }
+            };
+            let include = directive
+                .strip_prefix('"')
+                .and_then(|directive| directive.strip_suffix('"'))
+                .filter(|include| !include.is_empty())
+                .ok_or_else(|| ShaderPreprocessError::InvalidInclude {
+                    path: path.to_path_buf(),
+                    line: index + 1,
+                })?;
+            let include_path =
+                normalize(&path.parent().unwrap_or_else(|| Path::new("")).join(include));
+
+            if let Some(position) = self.stack.iter().position(|path| path == &include_path) {
+                let mut chain = self.stack[position..].to_vec();
+                chain.push(include_path.clone());
+                return Err(ShaderPreprocessError::CyclicInclude {
+                    path: include_path,
+                    chain,
+                });
+            }
+
+            if self.result.includes.contains(&include_path) {
+                continue;
+            }
+
+            let include_source =
+                (self.read)(&include_path).map_err(|err| ShaderPreprocessError::ReadError {
+                    path: include_path.clone(),
+                    source: err,
+                })?;
+            self.result.includes.push(include_path.clone());
+            self.expand(&include_path, &include_source)?;
+        }
+
+        self.stack.pop();
+        Ok(())
+    }
+}
+
+/// Removes `.` and `..` components without touching the file system, so that the same file is recognized
+/// regardless of how it is reached.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                normalized.pop();
+            }
+            _ => normalized.push(component),
+        }
+    }
+
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preprocess_shader_reports_cyclic_includes() {
+        let read = |path: &Path| match path.to_str() {
+            Some("shaders/a.wgsl") => Ok("#include \"../shaders/b.wgsl\"".to_owned()),
+            Some("shaders/b.wgsl") => Ok("#include \"a.wgsl\"".to_owned()),
+            _ => Err(std::io::ErrorKind::NotFound.into()),
+        };
+
+        match preprocess_shader(Path::new("shaders/main.wgsl"), "#include \"a.wgsl\"", read) {
+            Err(ShaderPreprocessError::CyclicInclude { path, chain }) => {
+                assert_eq!(path, Path::new("shaders/a.wgsl"));
+                assert_eq!(
+                    chain,
+                    vec![
+                        PathBuf::from("shaders/a.wgsl"),
+                        PathBuf::from("shaders/b.wgsl"),
+                        PathBuf::from("shaders/a.wgsl")
+                    ]
+                );
+            }
+            result => panic!("expected a cyclic include, got {:?}", result),
+        }
+
+        assert!(matches!(
+            preprocess_shader(Path::new("main.wgsl"), "#include <a.wgsl>", read),
+            Err(ShaderPreprocessError::InvalidInclude { line: 1, .. })
+        ));
+    }
+}
@@ -0,0 +1,101 @@
+use crate::{AssetPipeline, MetadataSchema, MetadataType, PipelineGfxBridge};
+use anyhow::{anyhow, Context};
+use asset::assets::{LocalizedString, StringTableSource};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::Path};
+use toml::{Table, Value};
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct StringTableMetadata {
+    #[serde(default)]
+    pub string_table: StringTableTable,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct StringTableTable {
+    /// The locale of the texts. Defaults to the file name without the extension, e.g. `en` for `en.lang`.
+    pub locale: Option<String>,
+}
+
+/// Processes a string table, which is a TOML file of texts:
+///
+/// ```toml
+/// title = "Adventure"
+///
+/// [menu]
+/// start = "Start, {name}!" # The key is `menu.start`.
+///
+/// [apples]                 # A plural string, selected by the `count` argument.
+/// one = "{count} apple"
+/// other = "{count} apples"
+/// ```
+impl AssetPipeline for StringTableSource {
+    type Metadata = StringTableMetadata;
+
+    fn metadata_schema() -> MetadataSchema {
+        MetadataSchema::new().field(
+            "string_table",
+            MetadataType::Table(MetadataSchema::new().field("locale", MetadataType::String)),
+        )
+    }
+
+    fn process(
+        file_path: &Path,
+        file_content: Vec<u8>,
+        metadata: &Self::Metadata,
+        _gfx_bridge: &dyn PipelineGfxBridge,
+    ) -> anyhow::Result<Self> {
+        let locale = match &metadata.string_table.locale {
+            Some(locale) => locale.clone(),
+            None => file_path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .ok_or_else(|| anyhow!("the locale of `{}` is unknown", file_path.display()))?,
+        };
+        let table = String::from_utf8(file_content)?
+            .parse::<Table>()
+            .with_context(|| format!("failed to parse string table `{}`", file_path.display()))?;
+        let mut entries = BTreeMap::new();
+        collect_entries("", &table, &mut entries)?;
+
+        Ok(Self { locale, entries })
+    }
+}
+
+fn collect_entries(
+    prefix: &str,
+    table: &Table,
+    entries: &mut BTreeMap<String, LocalizedString>,
+) -> anyhow::Result<()> {
+    for (name, value) in table {
+        let key = format!("{}{}", prefix, name);
+
+        match value {
+            Value::String(text) => {
+                entries.insert(key, LocalizedString::Text(text.clone()));
+            }
+            Value::Table(table) if is_plural(table) => {
+                let text = |name: &str| table.get(name).and_then(Value::as_str).map(str::to_owned);
+                entries.insert(
+                    key,
+                    LocalizedString::Plural {
+                        one: text("one"),
+                        other: text("other").unwrap(),
+                    },
+                );
+            }
+            Value::Table(table) => collect_entries(&format!("{}.", key), table, entries)?,
+            _ => return Err(anyhow!("`{}` must be a string or a table", key)),
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns whether the table is a plural string, which has `other` and optionally `one` as its only texts.
+fn is_plural(table: &Table) -> bool {
+    table.get("other").is_some_and(Value::is_str)
+        && table
+            .iter()
+            .all(|(name, value)| matches!(name.as_str(), "one" | "other") && value.is_str())
+}
@@ -1,4 +1,4 @@
-use crate::{AssetPipeline, PipelineGfxBridge};
+use crate::{AssetPipeline, MetadataSchema, MetadataType, PipelineGfxBridge};
 use asset::assets::{
     NinePatchSource, NinePatchTexelRange, SpriteSource, SpriteTexelRange, TextureAddressMode,
     TextureFilterMode, TextureFormat, TextureSource,
@@ -100,6 +100,51 @@ pub struct NinePatchTable {
 impl AssetPipeline for TextureSource {
     type Metadata = TextureMetadata;
 
+    fn metadata_schema() -> MetadataSchema {
+        let filter_mode = || MetadataType::Enum(&["point", "bilinear", "trilinear"]);
+        let address_mode = || MetadataType::Enum(&["clamp", "repeat"]);
+        let range = |keys: &[&'static str]| {
+            keys.iter()
+                .fold(MetadataSchema::new(), |schema, key| {
+                    schema.field(key, MetadataType::Integer)
+                })
+                .field("filter_mode", filter_mode())
+                .field("address_mode_u", address_mode())
+                .field("address_mode_v", address_mode())
+        };
+
+        MetadataSchema::new()
+            .field(
+                "texture",
+                MetadataType::Table(
+                    MetadataSchema::new()
+                        .field("is_srgb", MetadataType::Bool)
+                        .field("filter_mode", filter_mode())
+                        .field("address_mode_u", address_mode())
+                        .field("address_mode_v", address_mode()),
+                ),
+            )
+            .field(
+                "sprite",
+                MetadataType::Map(Box::new(MetadataType::Table(range(&[
+                    "x_min", "x_max", "y_min", "y_max",
+                ])))),
+            )
+            .field(
+                "nine_patch",
+                MetadataType::Map(Box::new(MetadataType::Table(range(&[
+                    "x_min",
+                    "x_mid_min",
+                    "x_mid_max",
+                    "x_max",
+                    "y_min",
+                    "y_mid_min",
+                    "y_mid_max",
+                    "y_max",
+                ])))),
+            )
+    }
+
     fn process(
         _file_path: &Path,
         file_content: Vec<u8>,
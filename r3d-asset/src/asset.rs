@@ -1,5 +1,5 @@
 use crate::{
-    assets::{Font, Material, Model, Shader, Texture},
+    assets::{Font, Material, Model, Script, Shader, StringTable, Texture},
     AssetKey,
 };
 use std::{fmt::Display, sync::Arc};
@@ -9,7 +9,9 @@ pub enum AssetType {
     Font,
     Material,
     Model,
+    Script,
     Shader,
+    StringTable,
     Texture,
 }
 
@@ -19,7 +21,9 @@ impl Display for AssetType {
             AssetType::Font => write!(f, "font"),
             AssetType::Material => write!(f, "material"),
             AssetType::Model => write!(f, "model"),
+            AssetType::Script => write!(f, "script"),
             AssetType::Shader => write!(f, "shader"),
+            AssetType::StringTable => write!(f, "string table"),
             AssetType::Texture => write!(f, "texture"),
         }
     }
@@ -30,7 +34,9 @@ pub enum TypedAsset {
     Font(Font),
     Material(Material),
     Model(Model),
+    Script(Script),
     Shader(Shader),
+    StringTable(StringTable),
     Texture(Texture),
 }
 
@@ -40,7 +46,9 @@ impl TypedAsset {
             TypedAsset::Font(_) => AssetType::Font,
             TypedAsset::Material(_) => AssetType::Material,
             TypedAsset::Model(_) => AssetType::Model,
+            TypedAsset::Script(_) => AssetType::Script,
             TypedAsset::Shader(_) => AssetType::Shader,
+            TypedAsset::StringTable(_) => AssetType::StringTable,
             TypedAsset::Texture(_) => AssetType::Texture,
         }
     }
@@ -57,10 +65,18 @@ impl TypedAsset {
         matches!(self, TypedAsset::Model(_))
     }
 
+    pub fn is_script(&self) -> bool {
+        matches!(self, TypedAsset::Script(_))
+    }
+
     pub fn is_shader(&self) -> bool {
         matches!(self, TypedAsset::Shader(_))
     }
 
+    pub fn is_string_table(&self) -> bool {
+        matches!(self, TypedAsset::StringTable(_))
+    }
+
     pub fn is_texture(&self) -> bool {
         matches!(self, TypedAsset::Texture(_))
     }
@@ -86,6 +102,13 @@ impl TypedAsset {
         }
     }
 
+    pub fn as_script(&self) -> Option<&Script> {
+        match self {
+            TypedAsset::Script(script) => Some(script),
+            _ => None,
+        }
+    }
+
     pub fn as_shader(&self) -> Option<&Shader> {
         match self {
             TypedAsset::Shader(shader) => Some(shader),
@@ -93,6 +116,13 @@ impl TypedAsset {
         }
     }
 
+    pub fn as_string_table(&self) -> Option<&StringTable> {
+        match self {
+            TypedAsset::StringTable(string_table) => Some(string_table),
+            _ => None,
+        }
+    }
+
     pub fn as_texture(&self) -> Option<&Texture> {
         match self {
             TypedAsset::Texture(texture) => Some(texture),
@@ -0,0 +1,82 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// The kind of memory an asset takes. Usages and budgets are kept per category, summed over every cache that
+/// accounts for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AssetCategory {
+    Texture,
+    Mesh,
+    Other,
+}
+
+impl AssetCategory {
+    pub const ALL: [Self; 3] = [Self::Texture, Self::Mesh, Self::Other];
+
+    /// Returns the bytes of the assets of the category that are loaded in caches.
+    pub fn usage(self) -> u64 {
+        USAGES[self as usize].load(Ordering::Relaxed)
+    }
+
+    /// Returns the budget in bytes, or `None` if the category has no budget.
+    pub fn budget(self) -> Option<u64> {
+        match BUDGETS[self as usize].load(Ordering::Relaxed) {
+            u64::MAX => None,
+            budget => Some(budget),
+        }
+    }
+
+    /// Sets the budget in bytes. Caches unload the assets of the category in their
+    /// [`update`](crate::AssetCache::update) while the usage exceeds it.
+    pub fn set_budget(self, budget: Option<u64>) {
+        BUDGETS[self as usize].store(budget.unwrap_or(u64::MAX), Ordering::Relaxed);
+    }
+
+    pub fn is_over_budget(self) -> bool {
+        self.budget().is_some_and(|budget| budget < self.usage())
+    }
+
+    pub(crate) fn add_usage(self, bytes: u64) {
+        USAGES[self as usize].fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub(crate) fn sub_usage(self, bytes: u64) {
+        USAGES[self as usize].fetch_sub(bytes, Ordering::Relaxed);
+    }
+}
+
+/// Memory of a category that is held outside of the asset caches, e.g. the CPU copy of a mesh that has been
+/// uploaded. It counts toward the usage of the category until dropped.
+#[derive(Debug)]
+pub struct AssetUsage {
+    category: AssetCategory,
+    bytes: u64,
+}
+
+impl AssetUsage {
+    pub fn new(category: AssetCategory, bytes: u64) -> Self {
+        category.add_usage(bytes);
+        Self { category, bytes }
+    }
+
+    pub fn category(&self) -> AssetCategory {
+        self.category
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+}
+
+impl Drop for AssetUsage {
+    fn drop(&mut self) {
+        self.category.sub_usage(self.bytes);
+    }
+}
+
+static USAGES: [AtomicU64; AssetCategory::ALL.len()] =
+    [AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0)];
+static BUDGETS: [AtomicU64; AssetCategory::ALL.len()] = [
+    AtomicU64::new(u64::MAX),
+    AtomicU64::new(u64::MAX),
+    AtomicU64::new(u64::MAX),
+];
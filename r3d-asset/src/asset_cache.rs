@@ -0,0 +1,429 @@
+use crate::{AssetCategory, AssetHandle, AssetKey, AssetLoadError, AssetSlot};
+use logging::log_warn;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+/// Loads the asset of a key that is not cached yet.
+pub type AssetResolver<A> = dyn FnMut(&AssetKey) -> Result<Arc<A>, AssetLoadError>;
+
+/// Receives the key and the new asset whenever a cached asset is reloaded.
+pub type AssetReloadCallback<A> = dyn FnMut(&AssetKey, &Arc<A>);
+
+/// Returns the bytes that an asset takes, e.g. [`TextureAsset::memory_size`](crate::assets::TextureAsset::memory_size).
+pub type AssetSizer<A> = dyn Fn(&A) -> u64;
+
+/// Loads assets by key and keeps them, so that every request of the same key shares one asset.
+/// The asset type is usually one of the asset traits, e.g. `AssetCache<dyn FontAsset>`.
+///
+/// With [`set_accounting`](Self::set_accounting), the bytes of the cached assets count towards the usage of a
+/// category, and [`update`](Self::update) unloads the least recently used assets while it exceeds the budget.
+pub struct AssetCache<A>
+where
+    A: ?Sized,
+{
+    resolver: Box<AssetResolver<A>>,
+    entries: HashMap<AssetKey, CacheEntry<A>>,
+    subscribers: Vec<Box<AssetReloadCallback<A>>>,
+    /// Advanced on every update; handles record it when accessed, which orders the assets by their last use.
+    clock: Arc<AtomicU64>,
+    accounting: Option<(AssetCategory, Box<AssetSizer<A>>)>,
+    is_over_budget: bool,
+}
+
+struct CacheEntry<A>
+where
+    A: ?Sized,
+{
+    slot: Arc<AssetSlot<A>>,
+    /// The bytes accounted for the asset, or zero while it is unloaded.
+    size: u64,
+}
+
+impl<A> AssetCache<A>
+where
+    A: ?Sized,
+{
+    /// Creates an empty cache. The resolver loads the asset of a key that is not cached yet.
+    pub fn new(
+        resolver: impl FnMut(&AssetKey) -> Result<Arc<A>, AssetLoadError> + 'static,
+    ) -> Self {
+        Self {
+            resolver: Box::new(resolver),
+            entries: HashMap::new(),
+            subscribers: Vec::new(),
+            clock: Arc::new(AtomicU64::new(0)),
+            accounting: None,
+            is_over_budget: false,
+        }
+    }
+
+    /// Accounts the bytes of the assets to the category, as measured by the sizer. Assets cached before are
+    /// accounted too.
+    pub fn set_accounting(&mut self, category: AssetCategory, sizer: impl Fn(&A) -> u64 + 'static) {
+        let previous_category = self.accounting.as_ref().map(|(category, _)| *category);
+
+        for entry in self.entries.values_mut() {
+            if let Some(previous_category) = previous_category {
+                previous_category.sub_usage(entry.size);
+            }
+
+            entry.size = entry
+                .slot
+                .asset
+                .read()
+                .unwrap()
+                .as_ref()
+                .map_or(0, |asset| sizer(asset));
+            category.add_usage(entry.size);
+        }
+
+        self.accounting = Some((category, Box::new(sizer)));
+    }
+
+    /// Returns the bytes of the cached assets, as accounted to the category.
+    pub fn usage(&self) -> u64 {
+        self.entries.values().map(|entry| entry.size).sum()
+    }
+
+    /// Returns the number of the loaded assets.
+    pub fn len(&self) -> usize {
+        self.entries
+            .values()
+            .filter(|entry| entry.slot.asset.read().unwrap().is_some())
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if the asset of the key is loaded.
+    pub fn contains(&self, key: &AssetKey) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Returns the cached asset of the key, without loading it.
+    pub fn get(&self, key: &AssetKey) -> Option<Arc<A>> {
+        self.entries
+            .get(key)
+            .and_then(|entry| entry.slot.asset.read().unwrap().clone())
+    }
+
+    /// Returns the asset of the key, loading it with the resolver on the first request or after it has been unloaded.
+    /// Failed loads are not cached, so they are retried on the next request.
+    pub fn load(&mut self, key: &AssetKey) -> Result<Arc<A>, AssetLoadError> {
+        self.load_slot(key).map(|(_, asset)| asset)
+    }
+
+    /// Returns a handle to the asset of the key, loading it like [`load`](Self::load). Prefer it over keeping the
+    /// asset, so that the asset can be unloaded while it is not used.
+    pub fn handle(&mut self, key: &AssetKey) -> Result<AssetHandle<A>, AssetLoadError> {
+        self.load_slot(key).map(|(slot, _)| AssetHandle::new(slot))
+    }
+
+    fn load_slot(&mut self, key: &AssetKey) -> Result<(Arc<AssetSlot<A>>, Arc<A>), AssetLoadError> {
+        if let Some(entry) = self.entries.get(key) {
+            entry.slot.touch();
+
+            if let Some(asset) = entry.slot.asset.read().unwrap().clone() {
+                return Ok((entry.slot.clone(), asset));
+            }
+        }
+
+        let asset = (self.resolver)(key)?;
+        let size = self.account(&asset);
+
+        match self.entries.get_mut(key) {
+            Some(entry) => {
+                *entry.slot.asset.write().unwrap() = Some(asset.clone());
+                entry.slot.is_requested.store(false, Ordering::Relaxed);
+                entry.size = size;
+                Ok((entry.slot.clone(), asset))
+            }
+            None => {
+                let slot = Arc::new(AssetSlot::new(
+                    key.clone(),
+                    asset.clone(),
+                    self.clock.clone(),
+                ));
+                self.entries.insert(
+                    key.clone(),
+                    CacheEntry {
+                        slot: slot.clone(),
+                        size,
+                    },
+                );
+                Ok((slot, asset))
+            }
+        }
+    }
+
+    /// Removes the asset of the key from the cache. Handles given out before stay valid, but the next
+    /// request loads the asset again.
+    pub fn evict(&mut self, key: &AssetKey) -> Option<Arc<A>> {
+        let entry = self.entries.remove(key)?;
+        self.unaccount(entry.size);
+        let asset = entry.slot.asset.read().unwrap().clone();
+        asset
+    }
+
+    pub fn clear(&mut self) {
+        for entry in std::mem::take(&mut self.entries).into_values() {
+            self.unaccount(entry.size);
+        }
+    }
+
+    /// Registers a callback that is called after every reload, e.g. to rebind a reloaded texture.
+    pub fn subscribe(&mut self, callback: impl FnMut(&AssetKey, &Arc<A>) + 'static) {
+        self.subscribers.push(Box::new(callback));
+    }
+
+    /// Loads the asset of the key again and replaces the cached one, then notifies the subscribers. Handles of the
+    /// key refer to the new asset.
+    /// Keys that are not loaded are ignored and return `None`. If the load fails, the previous asset stays cached.
+    pub fn reload(&mut self, key: &AssetKey) -> Result<Option<Arc<A>>, AssetLoadError> {
+        if !self.contains(key) {
+            return Ok(None);
+        }
+
+        let asset = (self.resolver)(key)?;
+        let size = self.account(&asset);
+        let entry = self.entries.get_mut(key).unwrap();
+        *entry.slot.asset.write().unwrap() = Some(asset.clone());
+        let previous_size = std::mem::replace(&mut entry.size, size);
+        self.unaccount(previous_size);
+
+        for subscriber in &mut self.subscribers {
+            subscriber(key, &asset);
+        }
+
+        Ok(Some(asset))
+    }
+
+    /// Reloads every cached asset among the keys, e.g. the keys reported by an `AssetWatcher`.
+    /// Returns the keys that failed to reload along with their errors.
+    pub fn reload_all<'a>(
+        &mut self,
+        keys: impl IntoIterator<Item = &'a AssetKey>,
+    ) -> Vec<(AssetKey, AssetLoadError)> {
+        keys.into_iter()
+            .filter_map(|key| match self.reload(key) {
+                Ok(_) => None,
+                Err(err) => Some((key.clone(), err)),
+            })
+            .collect()
+    }
+
+    /// Loads the unloaded assets that handles have asked for, then unloads assets while the category is over the
+    /// budget, and advances the clock. Call it once per frame, after rendering.
+    /// Returns the keys that failed to load along with their errors; they are asked for again on the next access.
+    pub fn update(&mut self) -> Vec<(AssetKey, AssetLoadError)> {
+        let requested = Vec::from_iter(
+            self.entries
+                .values()
+                .filter(|entry| entry.slot.is_requested.swap(false, Ordering::Relaxed))
+                .map(|entry| entry.slot.key.clone()),
+        );
+        let errors = requested
+            .iter()
+            .filter_map(|key| match self.load_slot(key) {
+                Ok(_) => None,
+                Err(err) => Some((key.clone(), err)),
+            })
+            .collect();
+
+        self.enforce_budget();
+        self.clock.fetch_add(1, Ordering::Relaxed);
+
+        errors
+    }
+
+    /// Unloads the least recently used assets until the category is within its budget. Assets without handles
+    /// are removed first, then assets with handles are unloaded until they are used again. Assets used in this
+    /// frame, or kept alive outside the cache, are never unloaded, as that would not free them.
+    fn enforce_budget(&mut self) {
+        let category = match &self.accounting {
+            Some((category, _)) => *category,
+            None => return,
+        };
+
+        if !category.is_over_budget() {
+            self.is_over_budget = false;
+            return;
+        }
+
+        let clock = self.clock.load(Ordering::Relaxed);
+        let mut candidates = Vec::from_iter(
+            self.entries
+                .iter()
+                .filter(|(_, entry)| {
+                    entry.size != 0
+                        && entry.slot.last_used.load(Ordering::Relaxed) < clock
+                        && entry
+                            .slot
+                            .asset
+                            .read()
+                            .unwrap()
+                            .as_ref()
+                            .is_some_and(|asset| Arc::strong_count(asset) == 1)
+                })
+                .map(|(key, entry)| {
+                    let has_handles = 1 < Arc::strong_count(&entry.slot);
+                    (
+                        has_handles,
+                        entry.slot.last_used.load(Ordering::Relaxed),
+                        key.clone(),
+                    )
+                }),
+        );
+        candidates.sort_unstable_by_key(|(has_handles, last_used, _)| (*has_handles, *last_used));
+
+        for (has_handles, _, key) in candidates {
+            if !category.is_over_budget() {
+                break;
+            }
+
+            if has_handles {
+                let entry = self.entries.get_mut(&key).unwrap();
+                *entry.slot.asset.write().unwrap() = None;
+                let size = std::mem::take(&mut entry.size);
+                self.unaccount(size);
+            } else {
+                self.evict(&key);
+            }
+        }
+
+        if category.is_over_budget() {
+            if !self.is_over_budget {
+                log_warn!(
+                    "the {:?} assets in use take {} KiB, which does not fit in the budget of {} KiB",
+                    category,
+                    category.usage() / 1024,
+                    category.budget().unwrap_or_default() / 1024
+                );
+            }
+
+            self.is_over_budget = true;
+        } else {
+            self.is_over_budget = false;
+        }
+    }
+
+    fn account(&self, asset: &A) -> u64 {
+        match &self.accounting {
+            Some((category, sizer)) => {
+                let size = sizer(asset);
+                category.add_usage(size);
+                size
+            }
+            None => 0,
+        }
+    }
+
+    fn unaccount(&self, size: u64) {
+        if let Some((category, _)) = &self.accounting {
+            category.sub_usage(size);
+        }
+    }
+}
+
+impl<A> Drop for AssetCache<A>
+where
+    A: ?Sized,
+{
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::Cell, rc::Rc};
+
+    #[test]
+    fn cache_loads_each_key_once() {
+        let loads = Rc::new(Cell::new(0));
+        let mut cache = AssetCache::<str>::new({
+            let loads = loads.clone();
+            move |key| {
+                loads.set(loads.get() + 1);
+                match key {
+                    AssetKey::Path(path) => Ok(Arc::from(path.as_str())),
+                    AssetKey::Id(_) => Err(AssetLoadError::Other("not found".to_owned())),
+                }
+            }
+        });
+        let key = AssetKey::Path("textures/grass.png".to_owned());
+
+        assert!(!cache.contains(&key));
+        let first = cache.load(&key).unwrap();
+        let second = cache.load(&key).unwrap();
+        assert_eq!(loads.get(), 1);
+        assert!(Arc::ptr_eq(&first, &second));
+        assert!(cache.contains(&key));
+
+        // Evicted keys are loaded again.
+        assert!(cache.evict(&key).is_some());
+        assert!(!cache.contains(&key));
+        let third = cache.load(&key).unwrap();
+        assert_eq!(loads.get(), 2);
+        assert!(!Arc::ptr_eq(&first, &third));
+
+        // Failures are not cached.
+        let missing = AssetKey::Id(Default::default());
+        assert!(cache.load(&missing).is_err());
+        assert!(cache.load(&missing).is_err());
+        assert_eq!(loads.get(), 4);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn cache_unloads_least_recently_used_assets_over_budget() {
+        let loads = Rc::new(Cell::new(0));
+        let mut cache = AssetCache::<str>::new({
+            let loads = loads.clone();
+            move |key| {
+                loads.set(loads.get() + 1);
+                match key {
+                    AssetKey::Path(path) => Ok(Arc::from(path.as_str())),
+                    AssetKey::Id(_) => Err(AssetLoadError::Other("not found".to_owned())),
+                }
+            }
+        });
+        cache.set_accounting(AssetCategory::Other, |asset: &str| asset.len() as u64);
+        AssetCategory::Other.set_budget(Some(8));
+
+        let [a, b, c] = ["aaaa", "bbbb", "cccc"].map(|path| AssetKey::Path(path.to_owned()));
+        let handle = cache.handle(&a).unwrap();
+        cache.update();
+        cache.load(&b).unwrap();
+        cache.update();
+        cache.load(&c).unwrap();
+        cache.update();
+
+        // `b` has no handles, so it goes before `a`, which is used less recently.
+        assert!(cache.contains(&a));
+        assert!(!cache.contains(&b));
+        assert!(cache.contains(&c));
+        assert_eq!(cache.usage(), 8);
+
+        // Assets with handles are unloaded last, and loaded again once used.
+        AssetCategory::Other.set_budget(Some(0));
+        cache.update();
+        assert!(cache.is_empty());
+        assert_eq!(cache.usage(), 0);
+        assert!(handle.get().is_loading());
+
+        AssetCategory::Other.set_budget(None);
+        assert!(cache.update().is_empty());
+        assert_eq!(&*handle.get().loaded().unwrap(), "aaaa");
+        assert_eq!(cache.usage(), 4);
+        assert_eq!(loads.get(), 4);
+    }
+}
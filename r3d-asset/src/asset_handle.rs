@@ -0,0 +1,112 @@
+use crate::AssetKey;
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc, RwLock,
+};
+
+/// The asset behind a handle at the time of the access.
+#[derive(Debug)]
+pub enum AssetState<A>
+where
+    A: ?Sized,
+{
+    Loaded(Arc<A>),
+    /// The asset has been unloaded to stay within the budget, and is loaded again by the next
+    /// [`update`](crate::AssetCache::update) of its cache. Renderers should skip it or draw a placeholder.
+    Loading,
+}
+
+impl<A> AssetState<A>
+where
+    A: ?Sized,
+{
+    pub fn loaded(self) -> Option<Arc<A>> {
+        match self {
+            Self::Loaded(asset) => Some(asset),
+            Self::Loading => None,
+        }
+    }
+
+    pub fn is_loading(&self) -> bool {
+        matches!(self, Self::Loading)
+    }
+}
+
+/// Refers to an asset of an [`AssetCache`](crate::AssetCache) without keeping it loaded. Unlike an `Arc` of the
+/// asset, it lets the cache unload the asset when it goes over the budget, and reload it once it is used again.
+pub struct AssetHandle<A>
+where
+    A: ?Sized,
+{
+    slot: Arc<AssetSlot<A>>,
+}
+
+impl<A> AssetHandle<A>
+where
+    A: ?Sized,
+{
+    pub(crate) fn new(slot: Arc<AssetSlot<A>>) -> Self {
+        Self { slot }
+    }
+
+    pub fn key(&self) -> &AssetKey {
+        &self.slot.key
+    }
+
+    /// Returns the asset, marking it as used in this frame. If it has been unloaded, requests it to be loaded again.
+    pub fn get(&self) -> AssetState<A> {
+        self.slot.touch();
+
+        match self.slot.asset.read().unwrap().as_ref() {
+            Some(asset) => AssetState::Loaded(asset.clone()),
+            None => {
+                self.slot.is_requested.store(true, Ordering::Relaxed);
+                AssetState::Loading
+            }
+        }
+    }
+}
+
+impl<A> Clone for AssetHandle<A>
+where
+    A: ?Sized,
+{
+    fn clone(&self) -> Self {
+        Self {
+            slot: self.slot.clone(),
+        }
+    }
+}
+
+/// The cache entry that handles share. The cache keeps the asset in it, and takes it out to unload it.
+pub(crate) struct AssetSlot<A>
+where
+    A: ?Sized,
+{
+    pub key: AssetKey,
+    pub asset: RwLock<Option<Arc<A>>>,
+    /// The value of the clock at the last access.
+    pub last_used: AtomicU64,
+    pub is_requested: AtomicBool,
+    clock: Arc<AtomicU64>,
+}
+
+impl<A> AssetSlot<A>
+where
+    A: ?Sized,
+{
+    pub fn new(key: AssetKey, asset: Arc<A>, clock: Arc<AtomicU64>) -> Self {
+        Self {
+            key,
+            asset: RwLock::new(Some(asset)),
+            last_used: AtomicU64::new(clock.load(Ordering::Relaxed)),
+            is_requested: AtomicBool::new(false),
+            clock,
+        }
+    }
+
+    pub fn touch(&self) {
+        self.last_used
+            .store(self.clock.load(Ordering::Relaxed), Ordering::Relaxed);
+    }
+}
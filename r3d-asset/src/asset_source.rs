@@ -1,4 +1,6 @@
-use crate::{Asset, AssetDepsProvider, AssetKey, AssetType, GfxBridge};
+use crate::{
+    assets::ShaderSpecializationError, Asset, AssetDepsProvider, AssetKey, AssetType, GfxBridge,
+};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use thiserror::Error;
@@ -32,6 +34,8 @@ pub enum AssetLoadError {
         texture_key: AssetKey,
         nine_patch_name: String,
     },
+    #[error("failed to specialize shader: {0}")]
+    ShaderSpecializationError(#[from] ShaderSpecializationError),
     #[error("{0}")]
     Other(String),
 }
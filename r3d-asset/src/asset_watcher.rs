@@ -0,0 +1,138 @@
+use crate::AssetKey;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::{
+    path::{Component, Path, PathBuf},
+    sync::mpsc::{channel, Receiver},
+};
+
+/// Watches an asset directory and reports the keys of the files that changed in it.
+/// Files are reported as `AssetKey::Path` keys relative to the watched directory, using `/` as the separator.
+///
+/// The watcher only collects the changes; pass them to `AssetCache::reload_all` of every cache that should pick
+/// them up, usually once per frame.
+pub struct AssetWatcher {
+    root: PathBuf,
+    events: Receiver<notify::Result<Event>>,
+    _watcher: RecommendedWatcher,
+}
+
+impl AssetWatcher {
+    /// Starts watching the directory and all of its subdirectories.
+    pub fn new(root: impl AsRef<Path>) -> notify::Result<Self> {
+        let root = root.as_ref().canonicalize()?;
+        let (sender, events) = channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        watcher.watch(&root, RecursiveMode::Recursive)?;
+
+        Ok(Self {
+            root,
+            events,
+            _watcher: watcher,
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Drains the pending file events and returns the keys of the changed files, without duplicates.
+    /// Watcher errors are skipped; the affected files are picked up again on their next change.
+    pub fn changed_keys(&self) -> Vec<AssetKey> {
+        let mut keys = Vec::new();
+
+        for event in self.events.try_iter().flatten() {
+            for key in changed_keys_of(&self.root, &event) {
+                if !keys.contains(&key) {
+                    keys.push(key);
+                }
+            }
+        }
+
+        keys
+    }
+}
+
+/// Returns the keys of the files that the event created or modified. Accesses and removals are ignored,
+/// so that a removed file keeps its last cached asset.
+fn changed_keys_of(root: &Path, event: &Event) -> Vec<AssetKey> {
+    match event.kind {
+        EventKind::Create(_) | EventKind::Modify(_) => {}
+        _ => return Vec::new(),
+    }
+
+    event
+        .paths
+        .iter()
+        .filter_map(|path| {
+            let components = path
+                .strip_prefix(root)
+                .ok()?
+                .components()
+                .map(|component| match component {
+                    Component::Normal(name) => name.to_str(),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()?;
+
+            if components.is_empty() {
+                None
+            } else {
+                Some(AssetKey::Path(components.join("/")))
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AssetCache;
+    use notify::event::{AccessKind, DataChange, ModifyKind};
+    use std::{cell::RefCell, rc::Rc, sync::Arc};
+
+    #[test]
+    fn file_change_reloads_cached_asset() {
+        let version = Rc::new(RefCell::new(1));
+        let mut cache = AssetCache::<String>::new({
+            let version = version.clone();
+            move |key| Ok(Arc::new(format!("{} v{}", key, version.borrow())))
+        });
+        let reloaded = Rc::new(RefCell::new(Vec::new()));
+        cache.subscribe({
+            let reloaded = reloaded.clone();
+            move |key, asset| reloaded.borrow_mut().push((key.clone(), asset.clone()))
+        });
+
+        let root = Path::new("/project/assets");
+        let key = AssetKey::Path("textures/grass.png".to_owned());
+        let before = cache.load(&key).unwrap();
+
+        // Only creations and modifications count as changes.
+        let access = Event::new(EventKind::Access(AccessKind::Any))
+            .add_path(root.join("textures/grass.png"));
+        assert!(changed_keys_of(root, &access).is_empty());
+
+        *version.borrow_mut() = 2;
+        let modify = Event::new(EventKind::Modify(ModifyKind::Data(DataChange::Content)))
+            .add_path(root.join("textures/grass.png"))
+            .add_path(root.join("shaders/unlit.wgsl"))
+            .add_path(PathBuf::from("/elsewhere/grass.png"));
+        let keys = changed_keys_of(root, &modify);
+        assert_eq!(
+            keys,
+            vec![key.clone(), AssetKey::Path("shaders/unlit.wgsl".to_owned())]
+        );
+        assert!(cache.reload_all(&keys).is_empty());
+
+        // The cached asset is replaced and the subscriber sees the new one. Keys that were never loaded stay unloaded.
+        let after = cache.get(&key).unwrap();
+        assert!(!Arc::ptr_eq(&before, &after));
+        assert_eq!(after.as_str(), "[asset path=textures/grass.png] v2");
+        assert_eq!(cache.len(), 1);
+
+        let reloaded = reloaded.borrow();
+        assert_eq!(reloaded.len(), 1);
+        assert_eq!(reloaded[0].0, key);
+        assert!(Arc::ptr_eq(&reloaded[0].1, &after));
+    }
+}
@@ -1,13 +1,21 @@
+mod built_in_shader;
 mod font_asset;
 mod material_asset;
 mod model_asset;
+mod script_asset;
 mod shader_asset;
+mod shader_specialization;
+mod string_table_asset;
 mod texture_asset;
 
+pub use built_in_shader::*;
 pub use font_asset::*;
 pub use material_asset::*;
 pub use model_asset::*;
+pub use script_asset::*;
 pub use shader_asset::*;
+pub use shader_specialization::*;
+pub use string_table_asset::*;
 pub use texture_asset::*;
 
 use std::sync::Arc;
@@ -15,5 +23,7 @@ use std::sync::Arc;
 pub type Font = Arc<dyn FontAsset>;
 pub type Material = Arc<dyn MaterialAsset>;
 pub type Model = Arc<dyn ModelAsset>;
+pub type Script = Arc<dyn ScriptAsset>;
 pub type Shader = Arc<dyn ShaderAsset>;
+pub type StringTable = Arc<dyn StringTableAsset>;
 pub type Texture = Arc<dyn TextureAsset>;
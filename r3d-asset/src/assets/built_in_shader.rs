@@ -0,0 +1,46 @@
+use std::collections::BTreeSet;
+
+/// A shader that comes with the engine, so that materials can request it by name instead of by asset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BuiltInShader {
+    pub name: &'static str,
+    /// The source, which may contain [`specialize_shader_source`](super::specialize_shader_source)
+    /// directives; it must be specialized even without defines.
+    pub source: &'static str,
+    /// The defines that are selected by the texture bindings that are set, e.g. `HAS_NORMAL_MAP` by `normal_texture`.
+    pub texture_defines: &'static [(&'static str, &'static str)],
+}
+
+impl BuiltInShader {
+    /// Returns the defines that the bound textures select.
+    pub fn defines_for_textures<'a>(
+        &self,
+        bound_textures: impl IntoIterator<Item = &'a str>,
+    ) -> BTreeSet<String> {
+        BTreeSet::from_iter(bound_textures.into_iter().filter_map(|texture| {
+            self.texture_defines
+                .iter()
+                .find(|(name, _)| *name == texture)
+                .map(|(_, define)| (*define).to_owned())
+        }))
+    }
+}
+
+/// The standard lit surface shader. Its defines are `HAS_NORMAL_MAP`, `HAS_EMISSIVE`, `SKINNED`,
+/// `RECEIVE_SHADOWS` and `HAS_ENVIRONMENT`.
+pub const STANDARD_SHADER: BuiltInShader = BuiltInShader {
+    name: "standard",
+    source: include_str!("./built_in_shaders/standard.wgsl"),
+    texture_defines: &[
+        ("normal_texture", "HAS_NORMAL_MAP"),
+        ("emissive_texture", "HAS_EMISSIVE"),
+        ("shadow_map", "RECEIVE_SHADOWS"),
+        ("environment_map", "HAS_ENVIRONMENT"),
+    ],
+};
+
+pub const BUILT_IN_SHADERS: &[BuiltInShader] = &[STANDARD_SHADER];
+
+pub fn find_built_in_shader(name: &str) -> Option<&'static BuiltInShader> {
+    BUILT_IN_SHADERS.iter().find(|shader| shader.name == name)
+}
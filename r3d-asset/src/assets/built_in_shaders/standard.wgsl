@@ -0,0 +1,182 @@
+// The standard lit surface: Lambert diffuse and normalized Blinn-Phong specular under one directional light
+// and an ambient light. Optional features are selected by the defines HAS_NORMAL_MAP, HAS_EMISSIVE, SKINNED,
+// RECEIVE_SHADOWS and HAS_ENVIRONMENT, so that the bindings of unused features are left out of the variant.
+
+@group(0) @binding(0) var<uniform> camera_transform: mat4x4<f32>;
+
+struct Surface {
+  base_color: vec4<f32>,
+  emissive_color: vec3<f32>,
+  roughness: f32,
+  metallic: f32,
+};
+
+struct Lighting {
+  // The direction that the light travels in, in world space.
+  light_direction: vec3<f32>,
+  light_color: vec3<f32>,
+  ambient_color: vec3<f32>,
+  camera_position: vec3<f32>,
+#ifdef RECEIVE_SHADOWS
+  light_view_projection: mat4x4<f32>,
+  shadow_bias: f32,
+#endif
+#ifdef HAS_ENVIRONMENT
+  environment_intensity: f32,
+  // -1 for cubemaps captured by reflection probes, which are stored with the z axis flipped.
+  environment_z_sign: f32,
+#endif
+};
+
+@group(1) @binding(0) var<uniform> surface: Surface;
+@group(1) @binding(1) var<uniform> lighting: Lighting;
+@group(1) @binding(2) var base_color_texture: texture_2d<f32>;
+@group(1) @binding(3) var base_color_sampler: sampler;
+#ifdef HAS_NORMAL_MAP
+@group(1) @binding(4) var normal_texture: texture_2d<f32>;
+@group(1) @binding(5) var normal_sampler: sampler;
+#endif
+#ifdef HAS_EMISSIVE
+@group(1) @binding(6) var emissive_texture: texture_2d<f32>;
+@group(1) @binding(7) var emissive_sampler: sampler;
+#endif
+#ifdef RECEIVE_SHADOWS
+@group(1) @binding(8) var shadow_map: texture_depth_2d;
+@group(1) @binding(9) var shadow_sampler: sampler_comparison;
+#endif
+#ifdef HAS_ENVIRONMENT
+@group(1) @binding(10) var environment_map: texture_cube<f32>;
+@group(1) @binding(11) var environment_sampler: sampler;
+#endif
+
+#ifdef SKINNED
+@group(2) @binding(0) var<uniform> joint_matrices: array<mat4x4<f32>, 64>;
+#endif
+
+struct InstanceInput {
+  @location(0) transform_row_0: vec4<f32>,
+  @location(1) transform_row_1: vec4<f32>,
+  @location(2) transform_row_2: vec4<f32>,
+  @location(3) transform_row_3: vec4<f32>,
+};
+
+struct VertexInput {
+  @location(4) position: vec3<f32>,
+  @location(5) normal: vec3<f32>,
+  @location(6) uv: vec2<f32>,
+#ifdef SKINNED
+  @location(7) joint_indices: vec4<u32>,
+  @location(8) joint_weights: vec4<f32>,
+#endif
+};
+
+struct VertexOutput {
+  @builtin(position) position: vec4<f32>,
+  @location(0) world_position: vec3<f32>,
+  @location(1) world_normal: vec3<f32>,
+  @location(2) uv: vec2<f32>,
+#ifdef RECEIVE_SHADOWS
+  @location(3) light_space_position: vec4<f32>,
+#endif
+};
+
+struct FragmentOutput {
+  @location(0) color: vec4<f32>,
+};
+
+@vertex
+fn vs_main(instance: InstanceInput, vertex: VertexInput) -> VertexOutput {
+  var out: VertexOutput;
+  var transform = mat4x4<f32>(instance.transform_row_0, instance.transform_row_1, instance.transform_row_2, instance.transform_row_3);
+#ifdef SKINNED
+  let skin = joint_matrices[vertex.joint_indices.x] * vertex.joint_weights.x
+    + joint_matrices[vertex.joint_indices.y] * vertex.joint_weights.y
+    + joint_matrices[vertex.joint_indices.z] * vertex.joint_weights.z
+    + joint_matrices[vertex.joint_indices.w] * vertex.joint_weights.w;
+  transform = transform * skin;
+#endif
+  let world_position = transform * vec4<f32>(vertex.position, 1.0);
+  out.position = camera_transform * world_position;
+  out.world_position = world_position.xyz;
+  // Non-uniform scales skew the normal, since there is no normal matrix per instance.
+  out.world_normal = (transform * vec4<f32>(vertex.normal, 0.0)).xyz;
+  out.uv = vertex.uv;
+#ifdef RECEIVE_SHADOWS
+  out.light_space_position = lighting.light_view_projection * world_position;
+#endif
+  return out;
+}
+
+#ifdef HAS_NORMAL_MAP
+// Meshes have no tangents, so the tangent frame is derived from the screen-space derivatives.
+fn perturb_normal(normal: vec3<f32>, position: vec3<f32>, uv: vec2<f32>) -> vec3<f32> {
+  let dp1 = dpdx(position);
+  let dp2 = dpdy(position);
+  let duv1 = dpdx(uv);
+  let duv2 = dpdy(uv);
+  let dp2_perp = cross(dp2, normal);
+  let dp1_perp = cross(normal, dp1);
+  let tangent = dp2_perp * duv1.x + dp1_perp * duv2.x;
+  let bitangent = dp2_perp * duv1.y + dp1_perp * duv2.y;
+  let scale = inverseSqrt(max(max(dot(tangent, tangent), dot(bitangent, bitangent)), 1e-12));
+  let tbn = mat3x3<f32>(tangent * scale, bitangent * scale, normal);
+  let sampled = textureSample(normal_texture, normal_sampler, uv).xyz * 2.0 - 1.0;
+  return normalize(tbn * sampled);
+}
+#endif
+
+#ifdef RECEIVE_SHADOWS
+fn shadow_visibility(light_space_position: vec4<f32>) -> f32 {
+  let ndc = light_space_position.xyz / light_space_position.w;
+  let uv = vec2<f32>(ndc.x * 0.5 + 0.5, 0.5 - ndc.y * 0.5);
+  let visibility = textureSampleCompare(shadow_map, shadow_sampler, uv, ndc.z - lighting.shadow_bias);
+  // Fragments outside of the shadow map are lit.
+  let is_outside = any(uv < vec2<f32>(0.0)) | any(uv > vec2<f32>(1.0)) | (ndc.z > 1.0);
+  return select(visibility, 1.0, is_outside);
+}
+#endif
+
+@fragment
+fn fs_main(in: VertexOutput) -> FragmentOutput {
+  var out: FragmentOutput;
+  let base_color = surface.base_color * textureSample(base_color_texture, base_color_sampler, in.uv);
+  var normal = normalize(in.world_normal);
+#ifdef HAS_NORMAL_MAP
+  normal = perturb_normal(normal, in.world_position, in.uv);
+#endif
+
+  let light = normalize(-lighting.light_direction);
+  let view = normalize(lighting.camera_position - in.world_position);
+  let half_vector = normalize(light + view);
+  let n_dot_l = max(dot(normal, light), 0.0);
+  let n_dot_h = max(dot(normal, half_vector), 0.0);
+
+  // The Blinn-Phong exponent that approximates the GGX lobe of the roughness, normalized to conserve energy.
+  let alpha = max(surface.roughness * surface.roughness, 0.002);
+  let shininess = 2.0 / (alpha * alpha) - 2.0;
+  let diffuse_color = base_color.rgb * (1.0 - surface.metallic);
+  let specular_color = mix(vec3<f32>(0.04), base_color.rgb, surface.metallic);
+  let specular = specular_color * (shininess + 8.0) / 8.0 * pow(n_dot_h, shininess);
+
+  var visibility = 1.0;
+#ifdef RECEIVE_SHADOWS
+  visibility = shadow_visibility(in.light_space_position);
+#endif
+
+  var color = lighting.ambient_color * diffuse_color
+    + (diffuse_color + specular) * lighting.light_color * n_dot_l * visibility;
+#ifdef HAS_ENVIRONMENT
+  let reflected = reflect(-view, normal);
+  let environment = textureSample(environment_map, environment_sampler, vec3<f32>(reflected.xy, reflected.z * lighting.environment_z_sign)).rgb;
+  // The cubemap has no mips prefiltered by roughness, so rough surfaces fade the reflection out instead of blurring it.
+  color += environment * specular_color * (1.0 - surface.roughness) * lighting.environment_intensity;
+#endif
+#ifdef HAS_EMISSIVE
+  color += surface.emissive_color * textureSample(emissive_texture, emissive_sampler, in.uv).rgb;
+#else
+  color += surface.emissive_color;
+#endif
+
+  out.color = vec4<f32>(color, base_color.a);
+  return out;
+}
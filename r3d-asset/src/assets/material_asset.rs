@@ -1,4 +1,6 @@
-use super::{SemanticShaderBindingKey, Shader};
+use super::{
+    find_built_in_shader, SemanticShaderBindingKey, Shader, ShaderReflection, ShaderSource,
+};
 use crate::{
     Asset, AssetDepsProvider, AssetKey, AssetLoadError, AssetSource, AssetType, GfxBridge,
     GfxBuffer, GfxSampler, GfxTextureView, TypedAsset,
@@ -8,6 +10,9 @@ use std::{io::Write, sync::Arc};
 use wgpu::{BufferAddress, BufferSize, BufferUsages};
 use zerocopy::AsBytes;
 
+/// The default value of `min_uniform_buffer_offset_alignment` limit.
+const UNIFORM_BUFFER_OFFSET_ALIGNMENT: BufferAddress = 256;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum MaterialBindingKey {
     Semantic(SemanticShaderBindingKey),
@@ -86,11 +91,56 @@ pub struct MaterialInstanceProp {
     pub value: MaterialInstancePropValue,
 }
 
+/// How the output of a material is blended into the render target.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum MaterialBlendMode {
+    #[default]
+    Opaque,
+    Alpha,
+    Additive,
+    /// Alpha blending of colors that are already multiplied by their alpha.
+    PremultipliedAlpha,
+}
+
+/// Which faces of triangles are culled.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum MaterialCullMode {
+    None,
+    Front,
+    #[default]
+    Back,
+}
+
+/// The winding of the front faces of triangles. It defaults to clockwise, which is the winding of PMX models.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum MaterialFrontFace {
+    #[default]
+    Cw,
+    Ccw,
+}
+
+/// The primitives that the vertices drawn with a material form.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum MaterialTopology {
+    #[default]
+    TriangleList,
+    LineList,
+    PointList,
+}
+
 #[derive(Clone)]
 pub struct MaterialPreset {
     pub shader: Shader,
     pub binding_props: Vec<MaterialBindingProp>,
     pub instance_props: Vec<MaterialInstanceProp>,
+    pub blend_mode: MaterialBlendMode,
+    pub cull_mode: MaterialCullMode,
+    pub front_face: MaterialFrontFace,
+    pub topology: MaterialTopology,
 }
 
 // TODO: I think we should provide shared default material instance for each material preset.
@@ -164,11 +214,40 @@ pub struct MaterialBindingPropSource {
 
 pub type MaterialInstancePropSource = MaterialInstanceProp;
 
-#[derive(Serialize, Deserialize)]
+/// A variant of a shader that is specialized for a set of defines.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ShaderVariantSource {
+    pub defines: Vec<String>,
+    /// The reflection of the specialized source, which differs from the reflection of the shader asset.
+    pub reflection: ShaderReflection,
+}
+
+/// The shader of a material source.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MaterialShaderSource {
+    Asset(AssetKey),
+    /// A [`BuiltInShader`](super::BuiltInShader) by name. It requires a variant, even without defines.
+    BuiltIn(String),
+}
+
+#[derive(Serialize, Deserialize, Debug)]
 pub struct MaterialSource {
-    pub shader: AssetKey,
+    pub shader: MaterialShaderSource,
+    /// The variant of the shader that the material uses. The shader asset is used as is if `None`.
+    #[serde(default)]
+    pub shader_variant: Option<ShaderVariantSource>,
     pub binding_props: Vec<MaterialBindingPropSource>,
     pub instance_props: Vec<MaterialInstancePropSource>,
+    pub blend_mode: MaterialBlendMode,
+    pub cull_mode: MaterialCullMode,
+    #[serde(default)]
+    pub front_face: MaterialFrontFace,
+    #[serde(default)]
+    pub topology: MaterialTopology,
+    /// The files included by the shader. They are not dependencies, as they are not assets on their own; they are
+    /// kept so that the material can be re-processed when they change, as its reflection is taken from the shader.
+    #[serde(default)]
+    pub includes: Vec<AssetKey>,
 }
 
 impl MaterialSource {
@@ -186,7 +265,10 @@ impl AssetSource for MaterialSource {
 
     fn dependencies(&self) -> Vec<AssetKey> {
         let mut deps = Vec::with_capacity(1 + self.binding_props.len());
-        deps.push(self.shader.clone());
+
+        if let MaterialShaderSource::Asset(shader) = &self.shader {
+            deps.push(shader.clone());
+        }
 
         for prop in &self.binding_props {
             match &prop.value {
@@ -218,19 +300,54 @@ impl AssetSource for MaterialSource {
         deps_provider: &dyn AssetDepsProvider,
         gfx_bridge: &dyn GfxBridge,
     ) -> Result<Arc<Self::Asset>, AssetLoadError> {
-        let shader = deps_provider.find_dependency(&self.shader).ok_or_else(|| {
-            AssetLoadError::MissingDependency {
-                expected_key: self.shader.clone(),
-                expected_ty: AssetType::Shader,
+        let shader = match &self.shader {
+            MaterialShaderSource::Asset(shader_key) => {
+                let shader = deps_provider.find_dependency(shader_key).ok_or_else(|| {
+                    AssetLoadError::MissingDependency {
+                        expected_key: shader_key.clone(),
+                        expected_ty: AssetType::Shader,
+                    }
+                })?;
+                let shader =
+                    shader
+                        .as_shader()
+                        .ok_or_else(|| AssetLoadError::DependencyTypeMismatch {
+                            expected_key: shader_key.clone(),
+                            expected_ty: AssetType::Shader,
+                            actual_ty: shader.ty(),
+                        })?;
+
+                match self.shader_variant {
+                    Some(variant) => ShaderSource {
+                        source: shader.source().to_owned(),
+                        reflection: variant.reflection,
+                        defines: variant.defines,
+                        includes: vec![],
+                    }
+                    .load(shader_key.clone(), deps_provider, gfx_bridge)?,
+                    None => shader.clone(),
+                }
             }
-        })?;
-        let shader = shader
-            .as_shader()
-            .ok_or_else(|| AssetLoadError::DependencyTypeMismatch {
-                expected_key: self.shader.clone(),
-                expected_ty: AssetType::Shader,
-                actual_ty: shader.ty(),
-            })?;
+            MaterialShaderSource::BuiltIn(name) => {
+                let built_in = find_built_in_shader(name).ok_or_else(|| {
+                    AssetLoadError::Other(format!("unknown built-in shader `{}`", name))
+                })?;
+                let variant = self.shader_variant.ok_or_else(|| {
+                    AssetLoadError::Other(format!("built-in shader `{}` has no variant", name))
+                })?;
+                ShaderSource {
+                    source: built_in.source.to_owned(),
+                    reflection: variant.reflection,
+                    defines: variant.defines,
+                    includes: vec![],
+                }
+                .load(
+                    AssetKey::Path(format!("built-in/{}", name)),
+                    deps_provider,
+                    gfx_bridge,
+                )?
+            }
+        };
 
         let mut binding_data = Vec::new();
         let mut binding_offsets = Vec::new();
@@ -289,8 +406,12 @@ impl AssetSource for MaterialSource {
 
             debug_assert_eq!(bytes.is_empty(), false);
 
+            // Each binding must start at an offset aligned to the uniform buffer offset alignment.
+            let offset = (binding_data.len() as BufferAddress)
+                .next_multiple_of(UNIFORM_BUFFER_OFFSET_ALIGNMENT);
+            binding_data.resize(offset as usize, 0);
             binding_data.extend_from_slice(bytes);
-            binding_offsets.push(binding_data.len() as BufferAddress);
+            binding_offsets.push(offset);
             binding_sizes.push(BufferSize::new(bytes.len() as u64).unwrap());
         }
 
@@ -445,9 +566,13 @@ impl AssetSource for MaterialSource {
         Ok(Arc::new(Material {
             key,
             preset: MaterialPreset {
-                shader: shader.clone(),
+                shader,
                 binding_props,
                 instance_props,
+                blend_mode: self.blend_mode,
+                cull_mode: self.cull_mode,
+                front_face: self.front_face,
+                topology: self.topology,
             },
         }))
     }
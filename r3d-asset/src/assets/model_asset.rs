@@ -1,3 +1,4 @@
+use super::{MaterialCullMode, MaterialFrontFace};
 use crate::{
     Asset, AssetDepsProvider, AssetKey, AssetLoadError, AssetSource, GfxBridge, GfxBuffer,
     TypedAsset,
@@ -75,9 +76,50 @@ pub struct Mesh {
     pub material: Option<MeshMaterial>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+/// Material of a sub mesh as described by the model file, e.g. an OBJ `.mtl` file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct MeshMaterial {
-    // TODO: Add more fields.
+    pub name: String,
+    /// RGBA; the alpha is the opacity.
+    pub diffuse_color: [f32; 4],
+    pub specular_color: [f32; 3],
+    pub shininess: f32,
+    pub diffuse_texture: Option<AssetKey>,
+    pub specular_texture: Option<AssetKey>,
+    pub normal_texture: Option<AssetKey>,
+    #[serde(default)]
+    pub cull_mode: MaterialCullMode,
+    #[serde(default)]
+    pub front_face: MaterialFrontFace,
+}
+
+impl MeshMaterial {
+    /// Creates a material that culls back faces. Front faces are wound counter-clockwise, as in most model
+    /// files; PMX models override it.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            diffuse_color: [1.0, 1.0, 1.0, 1.0],
+            specular_color: [0.0, 0.0, 0.0],
+            shininess: 0.0,
+            diffuse_texture: None,
+            specular_texture: None,
+            normal_texture: None,
+            cull_mode: MaterialCullMode::Back,
+            front_face: MaterialFrontFace::Ccw,
+        }
+    }
+
+    /// Returns the keys of all textures that the material refers to.
+    pub fn textures(&self) -> impl Iterator<Item = &AssetKey> {
+        [
+            &self.diffuse_texture,
+            &self.specular_texture,
+            &self.normal_texture,
+        ]
+        .into_iter()
+        .flatten()
+    }
 }
 
 /// Represents a mesy asset.
@@ -85,6 +127,15 @@ pub trait ModelAsset: Asset {
     fn root_node_index(&self) -> Option<u32>;
     fn nodes(&self) -> &[Node];
     fn meshes(&self) -> &[Mesh];
+
+    /// Returns the bytes of the uploaded vertex and index buffers, e.g. for
+    /// [`AssetCache::set_accounting`](crate::AssetCache::set_accounting).
+    fn memory_size(&self) -> u64 {
+        self.meshes()
+            .iter()
+            .map(|mesh| mesh.index_buffer.size() + mesh.vertex_buffer.size())
+            .sum()
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -115,7 +166,17 @@ impl AssetSource for ModelSource {
     type Asset = dyn ModelAsset;
 
     fn dependencies(&self) -> Vec<AssetKey> {
-        vec![]
+        let mut deps = Vec::new();
+
+        for material in self.meshes.iter().filter_map(|mesh| mesh.material.as_ref()) {
+            for texture in material.textures() {
+                if !deps.contains(texture) {
+                    deps.push(texture.clone());
+                }
+            }
+        }
+
+        deps
     }
 
     fn load(
@@ -0,0 +1,66 @@
+use crate::{
+    Asset, AssetDepsProvider, AssetKey, AssetLoadError, AssetSource, GfxBridge, TypedAsset,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Represents a script asset. It holds the script source to be evaluated by the scripting runtime.
+/// It is `Send` and `Sync`, so that components can hold it.
+pub trait ScriptAsset: Asset + Send + Sync {
+    /// The name of the script, used as the chunk name in error messages.
+    fn name(&self) -> &str;
+    fn source(&self) -> &str;
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ScriptSource {
+    pub name: String,
+    pub source: String,
+}
+
+impl AssetSource for ScriptSource {
+    type Asset = dyn ScriptAsset;
+
+    fn dependencies(&self) -> Vec<AssetKey> {
+        vec![]
+    }
+
+    fn load(
+        self,
+        key: AssetKey,
+        _deps_provider: &dyn AssetDepsProvider,
+        _gfx_bridge: &dyn GfxBridge,
+    ) -> Result<Arc<Self::Asset>, AssetLoadError> {
+        Ok(Arc::new(Script {
+            key,
+            name: self.name,
+            source: self.source,
+        }))
+    }
+}
+
+struct Script {
+    key: AssetKey,
+    name: String,
+    source: String,
+}
+
+impl Asset for Script {
+    fn key(&self) -> &AssetKey {
+        &self.key
+    }
+
+    fn as_typed(self: Arc<Self>) -> TypedAsset {
+        TypedAsset::Script(self)
+    }
+}
+
+impl ScriptAsset for Script {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn source(&self) -> &str {
+        &self.source
+    }
+}
@@ -1,10 +1,11 @@
+use super::specialize_shader_source;
 use crate::{
     Asset, AssetDepsProvider, AssetKey, AssetLoadError, AssetSource, GfxBridge, GfxShaderModule,
     TypedAsset,
 };
 use serde::{Deserialize, Serialize};
 use std::{
-    borrow::Cow,
+    collections::BTreeSet,
     num::{NonZeroU32, NonZeroU64},
     sync::Arc,
 };
@@ -20,6 +21,10 @@ impl SemanticShaderBindingKey {
     pub const fn new(key: NonZeroU32) -> Self {
         Self(key)
     }
+
+    pub const fn get(self) -> NonZeroU32 {
+        self.0
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -108,12 +113,25 @@ pub struct ShaderOutputItem {
 pub trait ShaderAsset: Asset {
     fn handle(&self) -> &GfxShaderModule;
     fn reflection(&self) -> &ShaderReflection;
+    /// Returns the source before specialization, from which other variants can be built.
+    fn source(&self) -> &str;
+    /// Returns the defines that the source is specialized for.
+    fn defines(&self) -> &[String];
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct ShaderSource {
+    /// The source before specialization; see [`specialize_shader_source`].
     pub source: String,
+    /// The reflection of the source specialized for the defines.
     pub reflection: ShaderReflection,
+    /// The defines that the source is specialized for when compiled.
+    #[serde(default)]
+    pub defines: Vec<String>,
+    /// The files included by the source, which is already merged with them. They are not dependencies, as they
+    /// are not assets on their own; they are kept so that the shader can be re-processed when they change.
+    #[serde(default)]
+    pub includes: Vec<AssetKey>,
 }
 
 impl AssetSource for ShaderSource {
@@ -129,11 +147,17 @@ impl AssetSource for ShaderSource {
         _deps_provider: &dyn AssetDepsProvider,
         gfx_bridge: &dyn GfxBridge,
     ) -> Result<Arc<Self::Asset>, AssetLoadError> {
+        let specialized = specialize_shader_source(
+            &self.source,
+            &BTreeSet::from_iter(self.defines.iter().cloned()),
+        )?;
+
         Ok(Arc::new(Shader {
             key,
-            handle: gfx_bridge
-                .compile_shader(wgpu::ShaderSource::Wgsl(Cow::Borrowed(&self.source))),
+            handle: gfx_bridge.compile_shader(wgpu::ShaderSource::Wgsl(specialized.into())),
             reflection: self.reflection,
+            source: self.source,
+            defines: self.defines,
         }))
     }
 }
@@ -142,6 +166,8 @@ struct Shader {
     key: AssetKey,
     handle: GfxShaderModule,
     reflection: ShaderReflection,
+    source: String,
+    defines: Vec<String>,
 }
 
 impl Asset for Shader {
@@ -162,4 +188,12 @@ impl ShaderAsset for Shader {
     fn reflection(&self) -> &ShaderReflection {
         &self.reflection
     }
+
+    fn source(&self) -> &str {
+        &self.source
+    }
+
+    fn defines(&self) -> &[String] {
+        &self.defines
+    }
 }
@@ -0,0 +1,176 @@
+use std::collections::BTreeSet;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ShaderSpecializationError {
+    #[error("line {line}: `{directive}` expects a name")]
+    MissingName { line: usize, directive: String },
+    #[error("line {line}: `{directive}` without a matching `#ifdef` or `#ifndef`")]
+    UnmatchedDirective {
+        line: usize,
+        directive: &'static str,
+    },
+    #[error("line {line}: `#else` appears twice in the same conditional")]
+    DuplicateElse { line: usize },
+    #[error("line {line}: the conditional is not closed with `#endif`")]
+    UnterminatedConditional { line: usize },
+}
+
+/// Specializes a WGSL source for the given set of defines.
+///
+/// The source may use `#ifdef NAME`, `#ifndef NAME`, `#else` and `#endif` to select lines, and `#define NAME`
+/// to add a define for the rest of the source. Directives and the lines that are not selected are replaced
+/// with empty lines, so that the line numbers of errors still match the original source.
+pub fn specialize_shader_source(
+    source: &str,
+    defines: &BTreeSet<String>,
+) -> Result<String, ShaderSpecializationError> {
+    struct Conditional {
+        line: usize,
+        is_parent_active: bool,
+        is_selected: bool,
+        has_else: bool,
+    }
+
+    let mut defines = defines.clone();
+    let mut conditionals = Vec::<Conditional>::new();
+    let mut specialized = String::with_capacity(source.len());
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let is_active = conditionals
+            .last()
+            .is_none_or(|conditional| conditional.is_parent_active && conditional.is_selected);
+        let trimmed = line.trim();
+        let (directive, name) = match trimmed.split_once(char::is_whitespace) {
+            Some((directive, name)) => (directive, name.trim()),
+            None => (trimmed, ""),
+        };
+        let name = || {
+            if name.is_empty() {
+                Err(ShaderSpecializationError::MissingName {
+                    line: line_number,
+                    directive: directive.to_owned(),
+                })
+            } else {
+                Ok(name)
+            }
+        };
+
+        match directive {
+            "#define" => {
+                let name = name()?;
+
+                if is_active {
+                    defines.insert(name.to_owned());
+                }
+            }
+            "#ifdef" | "#ifndef" => {
+                let is_defined = defines.contains(name()?);
+                conditionals.push(Conditional {
+                    line: line_number,
+                    is_parent_active: is_active,
+                    is_selected: is_defined == (directive == "#ifdef"),
+                    has_else: false,
+                });
+            }
+            "#else" => {
+                let conditional = conditionals.last_mut().ok_or(
+                    ShaderSpecializationError::UnmatchedDirective {
+                        line: line_number,
+                        directive: "#else",
+                    },
+                )?;
+
+                if conditional.has_else {
+                    return Err(ShaderSpecializationError::DuplicateElse { line: line_number });
+                }
+
+                conditional.is_selected = !conditional.is_selected;
+                conditional.has_else = true;
+            }
+            "#endif" => {
+                conditionals
+                    .pop()
+                    .ok_or(ShaderSpecializationError::UnmatchedDirective {
+                        line: line_number,
+                        directive: "#endif",
+                    })?;
+            }
+            _ if is_active => {
+                specialized.push_str(line);
+            }
+            _ => {}
+        }
+
+        specialized.push('\n');
+    }
+
+    if let Some(conditional) = conditionals.last() {
+        return Err(ShaderSpecializationError::UnterminatedConditional {
+            line: conditional.line,
+        });
+    }
+
+    Ok(specialized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn specialize_nested_conditionals() {
+        let source = "a\n#ifdef SKINNED\nb\n#ifndef HAS_NORMAL_MAP\nc\n#define FLAT\n#else\nd\n#endif\n#endif\n#ifdef FLAT\ne\n#endif\n";
+        let defines =
+            |names: &[&str]| BTreeSet::from_iter(names.iter().map(|&name| name.to_owned()));
+        let lines = |source: String| {
+            Vec::from_iter(
+                source
+                    .lines()
+                    .filter(|line| !line.is_empty())
+                    .map(str::to_owned),
+            )
+        };
+
+        assert_eq!(
+            lines(specialize_shader_source(source, &defines(&[])).unwrap()),
+            ["a"]
+        );
+        assert_eq!(
+            lines(specialize_shader_source(source, &defines(&["SKINNED"])).unwrap()),
+            ["a", "b", "c", "e"]
+        );
+        assert_eq!(
+            lines(
+                specialize_shader_source(source, &defines(&["SKINNED", "HAS_NORMAL_MAP"])).unwrap()
+            ),
+            ["a", "b", "d"]
+        );
+
+        // Line numbers are kept.
+        assert_eq!(
+            specialize_shader_source(source, &defines(&[]))
+                .unwrap()
+                .lines()
+                .count(),
+            source.lines().count()
+        );
+
+        assert_eq!(
+            specialize_shader_source("#ifdef A\n#else\n#else\n#endif", &defines(&[])),
+            Err(ShaderSpecializationError::DuplicateElse { line: 3 })
+        );
+        assert_eq!(
+            specialize_shader_source("#endif", &defines(&[])),
+            Err(ShaderSpecializationError::UnmatchedDirective {
+                line: 1,
+                directive: "#endif"
+            })
+        );
+        assert_eq!(
+            specialize_shader_source("a\n#ifndef A\nb", &defines(&[])),
+            Err(ShaderSpecializationError::UnterminatedConditional { line: 2 })
+        );
+    }
+}
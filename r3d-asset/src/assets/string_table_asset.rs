@@ -0,0 +1,94 @@
+use crate::{
+    Asset, AssetDepsProvider, AssetKey, AssetLoadError, AssetSource, GfxBridge, TypedAsset,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, sync::Arc};
+
+/// Represents a string table asset. It maps the keys of localized strings to their texts in one locale.
+/// It is `Send` and `Sync`, so that components can hold it.
+pub trait StringTableAsset: Asset + Send + Sync {
+    /// The locale of the texts, e.g. `en` or `ko-KR`.
+    fn locale(&self) -> &str;
+    fn entries(&self) -> &BTreeMap<String, LocalizedString>;
+
+    fn get(&self, key: &str) -> Option<&LocalizedString> {
+        self.entries().get(key)
+    }
+}
+
+/// The text of a localized string. Texts may contain `{name}` placeholders, which are replaced with arguments.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum LocalizedString {
+    Text(String),
+    /// A text that depends on a count. `one` is used if the count is 1, and `other` otherwise.
+    Plural {
+        one: Option<String>,
+        other: String,
+    },
+}
+
+impl LocalizedString {
+    /// Returns the text for the count. Plural strings use `other` if there is no count.
+    pub fn select(&self, count: Option<f64>) -> &str {
+        match self {
+            LocalizedString::Text(text) => text,
+            LocalizedString::Plural { one, other } => match (one, count) {
+                (Some(one), Some(1.0)) => one,
+                _ => other,
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StringTableSource {
+    pub locale: String,
+    pub entries: BTreeMap<String, LocalizedString>,
+}
+
+impl AssetSource for StringTableSource {
+    type Asset = dyn StringTableAsset;
+
+    fn dependencies(&self) -> Vec<AssetKey> {
+        vec![]
+    }
+
+    fn load(
+        self,
+        key: AssetKey,
+        _deps_provider: &dyn AssetDepsProvider,
+        _gfx_bridge: &dyn GfxBridge,
+    ) -> Result<Arc<Self::Asset>, AssetLoadError> {
+        Ok(Arc::new(StringTable {
+            key,
+            locale: self.locale,
+            entries: self.entries,
+        }))
+    }
+}
+
+struct StringTable {
+    key: AssetKey,
+    locale: String,
+    entries: BTreeMap<String, LocalizedString>,
+}
+
+impl Asset for StringTable {
+    fn key(&self) -> &AssetKey {
+        &self.key
+    }
+
+    fn as_typed(self: Arc<Self>) -> TypedAsset {
+        TypedAsset::StringTable(self)
+    }
+}
+
+impl StringTableAsset for StringTable {
+    fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    fn entries(&self) -> &BTreeMap<String, LocalizedString> {
+        &self.entries
+    }
+}
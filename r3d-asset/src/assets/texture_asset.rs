@@ -79,6 +79,14 @@ pub trait TextureAsset: Asset {
     fn address_mode(&self) -> (TextureAddressMode, TextureAddressMode);
     fn sprites(&self) -> &[Sprite];
     fn nine_patches(&self) -> &[NinePatch];
+
+    /// Returns the bytes of the uploaded texels, e.g. for [`AssetCache::set_accounting`](crate::AssetCache::set_accounting).
+    fn memory_size(&self) -> u64 {
+        let bytes_per_texel = match self.format() {
+            TextureFormat::RGBA8 => 4,
+        };
+        self.width() as u64 * self.height() as u64 * bytes_per_texel
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
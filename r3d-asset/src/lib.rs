@@ -1,12 +1,22 @@
 mod asset;
+mod asset_budget;
+mod asset_cache;
 mod asset_deps_provider;
+mod asset_handle;
 mod asset_key;
 mod asset_source;
+#[cfg(feature = "hot-reload")]
+mod asset_watcher;
 pub mod assets;
 mod gfx_bridge;
 
 pub use asset::*;
+pub use asset_budget::*;
+pub use asset_cache::*;
 pub use asset_deps_provider::*;
+pub use asset_handle::*;
 pub use asset_key::*;
 pub use asset_source::*;
+#[cfg(feature = "hot-reload")]
+pub use asset_watcher::*;
 pub use gfx_bridge::*;
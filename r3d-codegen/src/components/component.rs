@@ -1,26 +1,58 @@
 use proc_macro::TokenStream;
+use proc_macro_error::abort;
 use quote::quote;
-use syn::{parse_macro_input, DeriveInput};
+use syn::{parse_macro_input, DeriveInput, Path};
 
+/// Implements `specs::Component`. The storage is selected by `#[storage(...)]` as the derive of
+/// specs does, and defaults to `DenseVecStorage`. Types marked with `#[auto_register]` are
+/// registered to the world by the engine, so they do not have to be registered manually.
 pub fn component(item: TokenStream) -> TokenStream {
     let derive = parse_macro_input!(item as DeriveInput);
     let ty_name = &derive.ident;
     let generics = &derive.generics;
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
-    TokenStream::from(quote! {
-        impl #impl_generics crate::object::new::ComponentCommon for #ty_name #ty_generics #where_clause {
-            fn type_id(&self) -> std::any::TypeId {
-                std::any::TypeId::of::<Self>()
-            }
+    let mut storage = None;
+    let mut auto_register = false;
 
-            fn as_any(&self) -> &dyn std::any::Any {
-                self
+    for attr in &derive.attrs {
+        if attr.path().is_ident("storage") {
+            match attr.parse_args::<Path>() {
+                Ok(path) => storage = Some(quote! { #path }),
+                Err(err) => abort!(
+                    attr,
+                    "expected a storage type, e.g. `#[storage(VecStorage)]`: {}",
+                    err
+                ),
+            }
+        } else if attr.path().is_ident("auto_register") {
+            if !generics.params.is_empty() {
+                abort!(
+                    attr,
+                    "generic components cannot be registered automatically"
+                );
             }
 
-            fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
-                self
+            auto_register = true;
+        }
+    }
+
+    let storage = storage.unwrap_or_else(|| quote! { ::r3d::specs::DenseVecStorage });
+    let registration = if auto_register {
+        quote! {
+            ::r3d::inventory::submit! {
+                ::r3d::component_registry::ComponentRegistration::new::<#ty_name>()
             }
         }
+    } else {
+        quote! {}
+    };
+
+    TokenStream::from(quote! {
+        impl #impl_generics ::r3d::specs::Component for #ty_name #ty_generics #where_clause {
+            type Storage = #storage<Self>;
+        }
+
+        #registration
     })
 }
@@ -3,3 +3,133 @@ mod handle_mut;
 
 pub use handle::*;
 pub use handle_mut::*;
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::DeriveInput;
+
+/// Generates a strong handle and a weak handle that share the given inner type.
+/// The `new_inner` expression builds the inner value from a variable named `inner`.
+fn expand_handle(
+    derive: &DeriveInput,
+    inner_ty: TokenStream,
+    new_inner: TokenStream,
+) -> TokenStream {
+    let handle_name = format_ident!("{}Handle", derive.ident);
+    let weak_handle_name = format_ident!("Weak{}Handle", derive.ident);
+    let ty_name = &derive.ident;
+    let generics = &derive.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let handle_name_str = handle_name.to_string();
+    let weak_handle_name_str = weak_handle_name.to_string();
+
+    quote! {
+        #[derive(Clone)]
+        pub struct #handle_name #generics #where_clause {
+            inner: std::sync::Arc<#inner_ty>,
+            label: Option<std::sync::Arc<str>>,
+        }
+
+        impl #impl_generics #handle_name #ty_generics #where_clause {
+            pub fn new(inner: #ty_name #ty_generics) -> Self {
+                Self {
+                    inner: std::sync::Arc::new(#new_inner),
+                    label: None,
+                }
+            }
+
+            /// Creates a handle with a label, which is printed by the `Debug` impl to help tracking leaks.
+            pub fn with_label(inner: #ty_name #ty_generics, label: impl Into<String>) -> Self {
+                Self {
+                    inner: std::sync::Arc::new(#new_inner),
+                    label: Some(label.into().into()),
+                }
+            }
+
+            pub fn label(&self) -> Option<&str> {
+                self.label.as_deref()
+            }
+
+            pub fn as_ptr(&self) -> *const #inner_ty {
+                std::sync::Arc::as_ptr(&self.inner)
+            }
+
+            /// Creates a weak handle, which does not keep the inner value alive.
+            pub fn downgrade(&self) -> #weak_handle_name #ty_generics {
+                #weak_handle_name {
+                    inner: std::sync::Arc::downgrade(&self.inner),
+                    label: self.label.clone(),
+                }
+            }
+
+            pub fn ptr_eq(&self, other: &Self) -> bool {
+                std::sync::Arc::ptr_eq(&self.inner, &other.inner)
+            }
+        }
+
+        impl #impl_generics std::ops::Deref for #handle_name #ty_generics #where_clause {
+            type Target = #inner_ty;
+
+            fn deref(&self) -> &Self::Target {
+                &self.inner
+            }
+        }
+
+        impl #impl_generics PartialEq for #handle_name #ty_generics #where_clause {
+            fn eq(&self, other: &Self) -> bool {
+                std::sync::Arc::ptr_eq(&self.inner, &other.inner)
+            }
+        }
+
+        impl #impl_generics Eq for #handle_name #ty_generics #where_clause {}
+
+        impl #impl_generics std::hash::Hash for #handle_name #ty_generics #where_clause {
+            fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                std::sync::Arc::as_ptr(&self.inner).hash(state);
+            }
+        }
+
+        impl #impl_generics std::fmt::Debug for #handle_name #ty_generics #where_clause {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.debug_struct(#handle_name_str)
+                    .field("label", &self.label())
+                    .field("ptr", &self.as_ptr())
+                    .finish()
+            }
+        }
+
+        #[derive(Clone)]
+        pub struct #weak_handle_name #generics #where_clause {
+            inner: std::sync::Weak<#inner_ty>,
+            label: Option<std::sync::Arc<str>>,
+        }
+
+        impl #impl_generics #weak_handle_name #ty_generics #where_clause {
+            /// Returns a strong handle if the inner value is still alive.
+            pub fn upgrade(&self) -> Option<#handle_name #ty_generics> {
+                Some(#handle_name {
+                    inner: self.inner.upgrade()?,
+                    label: self.label.clone(),
+                })
+            }
+
+            pub fn label(&self) -> Option<&str> {
+                self.label.as_deref()
+            }
+
+            pub fn ptr_eq(&self, other: &Self) -> bool {
+                std::sync::Weak::ptr_eq(&self.inner, &other.inner)
+            }
+        }
+
+        impl #impl_generics std::fmt::Debug for #weak_handle_name #ty_generics #where_clause {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.debug_struct(#weak_handle_name_str)
+                    .field("label", &self.label())
+                    .field("ptr", &self.inner.as_ptr())
+                    .field("alive", &(self.inner.strong_count() != 0))
+                    .finish()
+            }
+        }
+    }
+}
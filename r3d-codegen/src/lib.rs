@@ -16,7 +16,7 @@ pub fn handle_mut(item: TokenStream) -> TokenStream {
     handles::handle_mut(item)
 }
 
-#[proc_macro_derive(Component)]
+#[proc_macro_derive(Component, attributes(storage, auto_register))]
 #[proc_macro_error]
 pub fn component(item: TokenStream) -> TokenStream {
     components::component(item)
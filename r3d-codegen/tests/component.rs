@@ -0,0 +1,45 @@
+use codegen::Component;
+use r3d::{
+    component_registry::register_components,
+    specs::{prelude::*, HashMapStorage, VecStorage},
+};
+
+#[derive(Component)]
+struct DefaultStorageComponent;
+
+#[derive(Component)]
+#[storage(VecStorage)]
+struct VecStorageComponent;
+
+#[derive(Debug, PartialEq, Component)]
+#[storage(HashMapStorage)]
+#[auto_register]
+struct AutoRegisteredComponent(u32);
+
+fn storage_name<T: Component>() -> &'static str {
+    std::any::type_name::<T::Storage>()
+}
+
+#[test]
+fn storage_is_selected_by_attribute() {
+    assert!(storage_name::<DefaultStorageComponent>().contains("DenseVecStorage"));
+    assert!(storage_name::<VecStorageComponent>().contains("::VecStorage"));
+    assert!(storage_name::<AutoRegisteredComponent>().contains("HashMapStorage"));
+}
+
+#[test]
+fn auto_registered_component_is_usable() {
+    let mut world = World::new();
+    register_components(&mut world);
+    // Registering again must not drop the storage.
+    let entity = world
+        .create_entity()
+        .with(AutoRegisteredComponent(42))
+        .build();
+    register_components(&mut world);
+
+    assert_eq!(
+        world.read_storage::<AutoRegisteredComponent>().get(entity),
+        Some(&AutoRegisteredComponent(42))
+    );
+}
@@ -0,0 +1,36 @@
+use codegen::Handle;
+
+#[derive(Handle)]
+pub struct Resource {
+    value: u32,
+}
+
+#[test]
+fn weak_handle_upgrades_while_alive() {
+    let handle = ResourceHandle::new(Resource { value: 42 });
+    let weak = handle.downgrade();
+
+    assert_eq!(weak.upgrade().map(|handle| handle.value), Some(42));
+    assert!(weak.upgrade().unwrap().ptr_eq(&handle));
+
+    drop(handle);
+    assert!(weak.upgrade().is_none());
+}
+
+#[test]
+fn label_is_kept_and_printed() {
+    let handle = ResourceHandle::with_label(Resource { value: 0 }, "atlas");
+    let weak = handle.downgrade();
+
+    assert_eq!(handle.label(), Some("atlas"));
+    assert_eq!(weak.upgrade().unwrap().label(), Some("atlas"));
+    assert!(format!("{:?}", handle).starts_with("ResourceHandle { label: Some(\"atlas\")"));
+    assert_eq!(ResourceHandle::new(Resource { value: 0 }).label(), None);
+}
+
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/ui/pass_*.rs");
+    t.compile_fail("tests/ui/fail_*.rs");
+}
@@ -0,0 +1,14 @@
+use codegen::Handle;
+
+#[derive(Handle)]
+pub struct Resource {
+    value: u32,
+}
+
+fn main() {
+    let handle = ResourceHandle::new(Resource { value: 42 });
+    let weak = handle.downgrade();
+
+    // A weak handle must be upgraded before accessing the inner value.
+    let _ = weak.value;
+}
@@ -0,0 +1,23 @@
+use codegen::Handle;
+
+#[derive(Handle)]
+pub struct Pair<T: Clone> {
+    first: T,
+    second: T,
+}
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+fn main() {
+    assert_send_sync::<PairHandle<u32>>();
+    assert_send_sync::<WeakPairHandle<u32>>();
+
+    let handle = PairHandle::new(Pair {
+        first: 1,
+        second: 2,
+    });
+    let weak: WeakPairHandle<u32> = handle.downgrade();
+    let upgraded = weak.upgrade().unwrap();
+    assert_eq!(upgraded.first + upgraded.second, 3);
+    assert_eq!(upgraded, handle);
+}
@@ -0,0 +1,16 @@
+-- Spins the object around the z axis. Hold space to spin faster.
+local rotate = {
+  speed = 1.0,
+}
+
+function rotate:on_init()
+  self.angle = 0.0
+end
+
+function rotate:on_update(dt)
+  local speed = self.speed * (1.0 + input.key("space") * 3.0)
+  self.angle = self.angle + speed * dt
+  self.object.transform.rotation = Quat.from_axis_angle(Vec3.new(0.0, 0.0, 1.0), self.angle)
+end
+
+return rotate
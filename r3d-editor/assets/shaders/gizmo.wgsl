@@ -0,0 +1,41 @@
+@group(0) @binding(0) var<uniform> camera_transform: mat4x4<f32>;
+
+struct Gizmo {
+  color: vec4<f32>,
+};
+
+@group(1) @binding(0) var<uniform> gizmo: Gizmo;
+
+struct InstanceInput {
+  @location(0) transform_row_0: vec4<f32>,
+  @location(1) transform_row_1: vec4<f32>,
+  @location(2) transform_row_2: vec4<f32>,
+  @location(3) transform_row_3: vec4<f32>,
+};
+
+struct VertexInput {
+  @location(4) position: vec3<f32>,
+};
+
+struct VertexOutput {
+  @builtin(position) position: vec4<f32>,
+};
+
+struct FragmentOutput {
+  @location(0) color: vec4<f32>,
+};
+
+@vertex
+fn vs_main(instance: InstanceInput, vertex: VertexInput) -> VertexOutput {
+  var out: VertexOutput;
+  let transform = mat4x4<f32>(instance.transform_row_0, instance.transform_row_1, instance.transform_row_2, instance.transform_row_3);
+  out.position = camera_transform * transform * vec4<f32>(vertex.position, 1.0);
+  return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> FragmentOutput {
+  var out: FragmentOutput;
+  out.color = gizmo.color;
+  return out;
+}
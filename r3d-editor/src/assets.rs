@@ -1,18 +1,28 @@
 use lazy_static::lazy_static;
 use r3d::{
-    gfx::{Font, FontHandle, Material, MaterialHandle, ShaderHandle},
+    gfx::{
+        BuiltInShaderKey, Font, FontHandle, Material, MaterialBlendMode, MaterialDepthMode,
+        MaterialHandle, ShaderHandle, BUILT_IN_SHADER_UI_TEXT_BITMAP, BUILT_IN_SHADER_UI_TEXT_SDF,
+    },
     use_context,
+    wgpu::PrimitiveTopology,
 };
 use std::path::Path;
 
 lazy_static! {
     static ref SHADER_SPRITE: ShaderHandle = create_shader("r3d-editor/assets/shaders/sprite.wgsl");
     static ref SHADER_GLYPH: ShaderHandle = create_shader("r3d-editor/assets/shaders/glyph.wgsl");
+    static ref SHADER_GIZMO: ShaderHandle = create_shader("r3d-editor/assets/shaders/gizmo.wgsl");
 }
 
 lazy_static! {
     pub static ref MATERIAL_SPRITE: MaterialHandle = create_sprite_material();
     pub static ref MATERIAL_GLYPH: MaterialHandle = create_glyph_material();
+    pub static ref MATERIAL_GLYPH_SDF: MaterialHandle =
+        create_built_in_material(BUILT_IN_SHADER_UI_TEXT_SDF);
+    pub static ref MATERIAL_GLYPH_BITMAP: MaterialHandle =
+        create_built_in_material(BUILT_IN_SHADER_UI_TEXT_BITMAP);
+    pub static ref MATERIAL_GIZMO: MaterialHandle = create_gizmo_material();
 }
 
 lazy_static! {
@@ -36,16 +46,40 @@ fn create_font(path: impl AsRef<Path>) -> FontHandle {
 
 pub fn create_sprite_material() -> MaterialHandle {
     let ctx = use_context();
-    MaterialHandle::new(Material::new(
+    let mut material = Material::new(
         SHADER_SPRITE.clone(),
         ctx.render_mgr_mut().pipeline_layout_cache(),
-    ))
+    );
+    material.blend_mode = Some(MaterialBlendMode::Alpha);
+    MaterialHandle::new(material)
 }
 
 pub fn create_glyph_material() -> MaterialHandle {
     let ctx = use_context();
-    MaterialHandle::new(Material::new(
+    let mut material = Material::new(
         SHADER_GLYPH.clone(),
         ctx.render_mgr_mut().pipeline_layout_cache(),
+    );
+    material.blend_mode = Some(MaterialBlendMode::Alpha);
+    MaterialHandle::new(material)
+}
+
+/// Draws lines over the scene, colored by the `color` property of the renderer.
+pub fn create_gizmo_material() -> MaterialHandle {
+    let ctx = use_context();
+    let mut material = Material::new(
+        SHADER_GIZMO.clone(),
+        ctx.render_mgr_mut().pipeline_layout_cache(),
+    );
+    material.topology = PrimitiveTopology::LineList;
+    material.depth_mode = Some(MaterialDepthMode::Always);
+    MaterialHandle::new(material)
+}
+
+pub fn create_built_in_material(key: BuiltInShaderKey) -> MaterialHandle {
+    let ctx = use_context();
+    MaterialHandle::new(Material::new(
+        ctx.built_in_shader_mgr().find_shader(key).unwrap(),
+        ctx.render_mgr_mut().pipeline_layout_cache(),
     ))
 }
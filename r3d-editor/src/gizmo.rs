@@ -0,0 +1,801 @@
+use crate::{
+    assets::MATERIAL_GIZMO,
+    selection::{self, SelectionListener},
+};
+use r3d::{
+    camera_controller::OrbitCameraController,
+    gfx::{Camera, Mesh, MeshHandle, MeshRenderer, ObjectPicker},
+    input::InputDevice,
+    math::{Quat, Ray, Vec2, Vec3, Vec4},
+    object::ObjectHandle,
+    specs::{Builder, WorldExt},
+    transform::{Transform, TransformComponent},
+    use_context,
+};
+
+/// The mask of the gizmo renderers. Pickers of the editor must leave it out, so that the gizmo does not select
+/// itself.
+pub const GIZMO_MASK: u32 = 0x8000_0000;
+
+/// The length of the handles in logical pixels, regardless of the distance to the camera.
+const GIZMO_SIZE: f32 = 100.0;
+/// How far from a handle the cursor may be to grab it, in logical pixels.
+const HANDLE_TOLERANCE: f32 = 8.0;
+/// Presses that move less than this many logical pixels before the release are clicks, which select objects.
+const CLICK_TOLERANCE: f32 = 4.0;
+const RING_SEGMENTS: u32 = 64;
+
+const TRANSLATION_SNAP: f32 = 0.5;
+const ROTATION_SNAP: f32 = std::f32::consts::PI / 12.0;
+const SCALE_SNAP: f32 = 0.1;
+/// Scales are kept above this, so that dragging through the center does not collapse or flip the object.
+const MIN_SCALE: f32 = 0.01;
+
+const AXIS_COLORS: [[f32; 4]; 3] = [
+    [0.9, 0.25, 0.25, 1.0],
+    [0.35, 0.85, 0.3, 1.0],
+    [0.3, 0.5, 0.95, 1.0],
+];
+const HOVERED_COLOR: [f32; 4] = [1.0, 0.85, 0.2, 1.0];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GizmoMode {
+    Translate,
+    Rotate,
+    Scale,
+}
+
+/// The space that the handles are aligned to. Scale handles are always aligned to the object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GizmoSpace {
+    World,
+    Local,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GizmoAxis {
+    X,
+    Y,
+    Z,
+}
+
+impl GizmoAxis {
+    pub const ALL: [Self; 3] = [Self::X, Self::Y, Self::Z];
+
+    pub fn index(self) -> usize {
+        match self {
+            Self::X => 0,
+            Self::Y => 1,
+            Self::Z => 2,
+        }
+    }
+
+    pub fn direction(self) -> Vec3 {
+        match self {
+            Self::X => Vec3::RIGHT,
+            Self::Y => Vec3::UP,
+            Self::Z => Vec3::BACKWARD,
+        }
+    }
+}
+
+/// Receives the edits of the gizmo, e.g. to record them for undo. It is invoked once per drag, with the local
+/// transforms of the object before and after the drag.
+pub trait GizmoUndoHook {
+    fn on_transform_edited(&mut self, object: &ObjectHandle, before: &Transform, after: &Transform);
+}
+
+/// The placement of the gizmo in world space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GizmoFrame {
+    pub center: Vec3,
+    /// The rotation of the handles, which point along the axes of the rotated space.
+    pub rotation: Quat,
+    /// The length of the handles in world units.
+    pub size: f32,
+}
+
+impl GizmoFrame {
+    pub fn direction(&self, axis: GizmoAxis) -> Vec3 {
+        self.rotation * axis.direction()
+    }
+
+    /// Returns the handle under the ray, if any. The tolerance is in world units at the gizmo.
+    pub fn hit_test(&self, mode: GizmoMode, ray: &Ray, tolerance: f32) -> Option<GizmoAxis> {
+        GizmoAxis::ALL
+            .into_iter()
+            .filter_map(|axis| {
+                let direction = self.direction(axis);
+                let (distance, gap) = match mode {
+                    GizmoMode::Translate | GizmoMode::Scale => {
+                        let along =
+                            closest_axis_param(ray, self.center, direction)?.clamp(0.0, self.size);
+                        let point = self.center + direction * along;
+                        let distance = Vec3::dot(point - ray.origin, ray.direction).max(0.0);
+                        (distance, Vec3::distance(ray.at(distance), point))
+                    }
+                    GizmoMode::Rotate => {
+                        let distance = intersect_plane(ray, self.center, direction)?;
+                        let radius = Vec3::distance(ray.at(distance), self.center);
+                        (distance, (radius - self.size).abs())
+                    }
+                };
+
+                (gap <= tolerance).then_some((axis, distance))
+            })
+            .min_by(|lhs, rhs| lhs.1.total_cmp(&rhs.1))
+            .map(|(axis, _)| axis)
+    }
+
+    /// Starts dragging the handle, given the ray that grabbed it and the transform of the object.
+    /// Returns `None` if the ray does not reach the handle, e.g. if it is parallel to the axis.
+    pub fn begin_drag(
+        &self,
+        mode: GizmoMode,
+        axis: GizmoAxis,
+        ray: &Ray,
+        start: GizmoDragStart,
+    ) -> Option<GizmoDrag> {
+        let direction = self.direction(axis);
+        let anchor = match mode {
+            GizmoMode::Translate | GizmoMode::Scale => {
+                direction * closest_axis_param(ray, self.center, direction)?
+            }
+            GizmoMode::Rotate => {
+                ray.at(intersect_plane(ray, self.center, direction)?) - self.center
+            }
+        };
+
+        if mode == GizmoMode::Scale && Vec3::dot(anchor, direction).abs() < f32::EPSILON {
+            return None;
+        }
+
+        Some(GizmoDrag {
+            mode,
+            axis,
+            center: self.center,
+            direction,
+            anchor,
+            start,
+        })
+    }
+}
+
+/// The transform of the object when a drag starts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GizmoDragStart {
+    pub world_position: Vec3,
+    pub world_rotation: Quat,
+    pub local_scale: Vec3,
+}
+
+/// The change that a drag makes to the object.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GizmoEdit {
+    WorldPosition(Vec3),
+    WorldRotation(Quat),
+    LocalScale(Vec3),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GizmoDrag {
+    mode: GizmoMode,
+    axis: GizmoAxis,
+    center: Vec3,
+    direction: Vec3,
+    /// Where the handle was grabbed, relative to the center.
+    anchor: Vec3,
+    start: GizmoDragStart,
+}
+
+impl GizmoDrag {
+    pub fn axis(&self) -> GizmoAxis {
+        self.axis
+    }
+
+    /// Returns the edit for the current ray, relative to the start of the drag. The amount is snapped to
+    /// steps if `is_snapping` is set. Returns `None` if the ray does not reach the handle.
+    pub fn edit(&self, ray: &Ray, is_snapping: bool) -> Option<GizmoEdit> {
+        let snap = |value: f32, step: f32| {
+            if is_snapping {
+                (value / step).round() * step
+            } else {
+                value
+            }
+        };
+
+        match self.mode {
+            GizmoMode::Translate => {
+                let along = closest_axis_param(ray, self.center, self.direction)?;
+                let delta = snap(
+                    along - Vec3::dot(self.anchor, self.direction),
+                    TRANSLATION_SNAP,
+                );
+                Some(GizmoEdit::WorldPosition(
+                    self.start.world_position + self.direction * delta,
+                ))
+            }
+            GizmoMode::Rotate => {
+                let point = ray.at(intersect_plane(ray, self.center, self.direction)?);
+                let angle = snap(
+                    Vec3::angle_signed(self.anchor, point - self.center, self.direction),
+                    ROTATION_SNAP,
+                );
+                Some(GizmoEdit::WorldRotation(
+                    Quat::from_axis_angle(self.direction, angle) * self.start.world_rotation,
+                ))
+            }
+            GizmoMode::Scale => {
+                let along = closest_axis_param(ray, self.center, self.direction)?;
+                let ratio = snap(along / Vec3::dot(self.anchor, self.direction), SCALE_SNAP);
+                let mut scale = self.start.local_scale.to_array();
+                scale[self.axis.index()] = (scale[self.axis.index()] * ratio).max(MIN_SCALE);
+                Some(GizmoEdit::LocalScale(Vec3::from_array(scale)))
+            }
+        }
+    }
+}
+
+/// Returns the parameter of the point on the axis that is closest to the ray. The direction must be
+/// normalized. Returns `None` if the ray is parallel to the axis.
+fn closest_axis_param(ray: &Ray, origin: Vec3, direction: Vec3) -> Option<f32> {
+    let b = Vec3::dot(direction, ray.direction);
+    let denominator = 1.0 - b * b;
+
+    if denominator < 1e-6 {
+        return None;
+    }
+
+    let offset = origin - ray.origin;
+    let d = Vec3::dot(direction, offset);
+    let e = Vec3::dot(ray.direction, offset);
+    Some((b * e - d) / denominator)
+}
+
+/// Returns the distance to the intersection with the plane, if it is in front of the ray.
+fn intersect_plane(ray: &Ray, origin: Vec3, normal: Vec3) -> Option<f32> {
+    let denominator = Vec3::dot(normal, ray.direction);
+
+    if denominator.abs() < 1e-6 {
+        return None;
+    }
+
+    let distance = Vec3::dot(origin - ray.origin, normal) / denominator;
+    (0.0 <= distance).then_some(distance)
+}
+
+/// Selects objects by clicking them, and moves, rotates and scales the selected object with handles.
+///
+/// `W`, `E` and `R` switch between the translate, rotate and scale handles, and `X` toggles between world and
+/// local space. Holding control snaps the drags to steps. While a handle is dragged, the orbit controller of the
+/// camera is disabled, so that it does not orbit along.
+pub struct TransformGizmo {
+    camera: ObjectHandle,
+    root: ObjectHandle,
+    /// The handles of each mode, indexed by the axis.
+    handles: [(GizmoMode, [ObjectHandle; 3]); 3],
+    mode: GizmoMode,
+    space: GizmoSpace,
+    selected: Option<ObjectHandle>,
+    hovered: Option<GizmoAxis>,
+    drag: Option<(GizmoDrag, Transform)>,
+    press_position: Option<Vec2>,
+    keys: [(&'static str, bool); 4],
+    undo_hook: Option<Box<dyn GizmoUndoHook>>,
+    selection_listener: SelectionListener,
+}
+
+impl TransformGizmo {
+    pub fn new(camera: ObjectHandle) -> Self {
+        let ctx = use_context();
+        let mut object_mgr = ctx.object_mgr_mut();
+        let mut world = ctx.world_mut();
+        let (root, builder) =
+            object_mgr.create_object_builder(&mut world, Some("gizmo".to_owned()), None);
+        builder.build();
+
+        let handles = [
+            (GizmoMode::Translate, "translate"),
+            (GizmoMode::Rotate, "rotate"),
+            (GizmoMode::Scale, "scale"),
+        ]
+        .map(|(mode, name)| {
+            let objects = GizmoAxis::ALL.map(|axis| {
+                let mut renderer = MeshRenderer::new();
+                renderer.set_mask(GIZMO_MASK);
+                renderer.set_material(MATERIAL_GIZMO.clone());
+                renderer.set_mesh(
+                    MeshHandle::new(handle_mesh(mode, axis)),
+                    &ctx.gfx_ctx().device,
+                );
+                renderer
+                    .property_block_mut()
+                    .set_uniform("color", AXIS_COLORS[axis.index()]);
+
+                let (object, builder) = object_mgr.create_object_builder(
+                    &mut world,
+                    Some(format!("gizmo-{}-{:?}", name, axis).to_lowercase()),
+                    None,
+                );
+                builder.with(renderer).build();
+                object_mgr
+                    .object_hierarchy_mut()
+                    .set_parent(object.object_id, Some(root.object_id));
+                object
+            });
+            (mode, objects)
+        });
+
+        drop(world);
+        drop(object_mgr);
+
+        let gizmo = Self {
+            camera,
+            root,
+            handles,
+            mode: GizmoMode::Translate,
+            space: GizmoSpace::World,
+            selected: None,
+            hovered: None,
+            drag: None,
+            press_position: None,
+            keys: [("w", false), ("e", false), ("r", false), ("x", false)],
+            undo_hook: None,
+            selection_listener: SelectionListener::new(),
+        };
+        gizmo.sync_visibility();
+        gizmo
+    }
+
+    pub fn set_mode(&mut self, mode: GizmoMode) {
+        self.mode = mode;
+        self.sync_visibility();
+    }
+
+    /// The object that the handles are parented to, e.g. to leave them out of the hierarchy panel.
+    pub fn root(&self) -> &ObjectHandle {
+        &self.root
+    }
+
+    /// Selects the object. A drag in progress is cancelled. The selection usually follows
+    /// [`SelectionChanged`](selection::SelectionChanged) instead.
+    pub fn select(&mut self, object: Option<ObjectHandle>) {
+        self.end_drag(false);
+        self.selected = object;
+        self.sync_visibility();
+    }
+
+    pub fn set_undo_hook(&mut self, undo_hook: impl GizmoUndoHook + 'static) {
+        self.undo_hook = Some(Box::new(undo_hook));
+    }
+
+    pub fn update(&mut self) {
+        if let Some(event) = self.selection_listener.take() {
+            self.select(event.object);
+        }
+
+        let ctx = use_context();
+        let (cursor, is_left_down, is_snapping, pressed_keys) = {
+            let input_mgr = ctx.input_mgr();
+            let key = |name: &str| {
+                input_mgr
+                    .keyboard()
+                    .input(name)
+                    .is_some_and(|input| input.value != 0.0)
+            };
+            let mouse = |name: &str| {
+                input_mgr
+                    .mouse()
+                    .input(name)
+                    .map_or(0.0, |input| input.value)
+            };
+            let pressed_keys = self.keys.map(|(name, is_down)| key(name) && !is_down);
+            self.keys = self.keys.map(|(name, _)| (name, key(name)));
+
+            (
+                ctx.screen_mgr()
+                    .physical_to_logical(Vec2::new(mouse("x"), mouse("y"))),
+                mouse("button:left") != 0.0,
+                key("control:l") || key("control:r"),
+                pressed_keys,
+            )
+        };
+
+        if self.drag.is_none() {
+            let [translate, rotate, scale, toggle_space] = pressed_keys;
+
+            if translate {
+                self.set_mode(GizmoMode::Translate);
+            } else if rotate {
+                self.set_mode(GizmoMode::Rotate);
+            } else if scale {
+                self.set_mode(GizmoMode::Scale);
+            }
+
+            if toggle_space {
+                self.space = match self.space {
+                    GizmoSpace::World => GizmoSpace::Local,
+                    GizmoSpace::Local => GizmoSpace::World,
+                };
+            }
+        }
+
+        let frame_and_ray = self.frame().zip(self.ray(cursor));
+        let was_left_down = self.press_position.is_some();
+
+        if let Some((drag, _)) = &self.drag {
+            if is_left_down {
+                if let Some(edit) = frame_and_ray.and_then(|(_, ray)| drag.edit(&ray, is_snapping))
+                {
+                    self.apply(edit);
+                }
+            } else {
+                self.end_drag(true);
+            }
+        } else if is_left_down && !was_left_down && !ctx.ui_event_mgr().is_pointer_over_ui() {
+            self.press_position = Some(cursor);
+            self.begin_drag(frame_and_ray);
+        } else if !is_left_down && was_left_down {
+            let is_click = self
+                .press_position
+                .is_some_and(|position| (cursor - position).len() < CLICK_TOLERANCE);
+
+            if is_click {
+                let picker = ObjectPicker {
+                    mask: !GIZMO_MASK,
+                    ..ObjectPicker::new()
+                };
+                selection::select(picker.pick(cursor, &self.camera).map(|hit| hit.object));
+            }
+        }
+
+        if !is_left_down {
+            self.press_position = None;
+        }
+
+        let hovered = match &self.drag {
+            Some((drag, _)) => Some(drag.axis()),
+            None => frame_and_ray.and_then(|(frame, ray)| {
+                frame.hit_test(self.mode, &ray, HANDLE_TOLERANCE * frame.size / GIZMO_SIZE)
+            }),
+        };
+        self.set_hovered(hovered);
+        self.sync_placement();
+    }
+
+    fn begin_drag(&mut self, frame_and_ray: Option<(GizmoFrame, Ray)>) {
+        let (frame, ray) = if let Some(frame_and_ray) = frame_and_ray {
+            frame_and_ray
+        } else {
+            return;
+        };
+        let selected = if let Some(selected) = &self.selected {
+            selected
+        } else {
+            return;
+        };
+        let axis = if let Some(axis) =
+            frame.hit_test(self.mode, &ray, HANDLE_TOLERANCE * frame.size / GIZMO_SIZE)
+        {
+            axis
+        } else {
+            return;
+        };
+
+        let transform = selected.component::<TransformComponent>();
+        let start = GizmoDragStart {
+            world_position: transform.world_position(),
+            world_rotation: transform.world_rotation(),
+            local_scale: transform.scale(),
+        };
+        let before = if let Some(before) = local_transform(selected) {
+            before
+        } else {
+            return;
+        };
+
+        if let Some(drag) = frame.begin_drag(self.mode, axis, &ray, start) {
+            self.drag = Some((drag, before));
+            self.set_orbit_enabled(false);
+        }
+    }
+
+    fn end_drag(&mut self, is_committed: bool) {
+        let (_, before) = if let Some(drag) = self.drag.take() {
+            drag
+        } else {
+            return;
+        };
+
+        self.set_orbit_enabled(true);
+
+        if !is_committed {
+            return;
+        }
+
+        let selected = if let Some(selected) = &self.selected {
+            selected
+        } else {
+            return;
+        };
+
+        if let (Some(undo_hook), Some(after)) = (&mut self.undo_hook, local_transform(selected)) {
+            undo_hook.on_transform_edited(selected, &before, &after);
+        }
+    }
+
+    fn apply(&self, edit: GizmoEdit) {
+        let selected = if let Some(selected) = &self.selected {
+            selected
+        } else {
+            return;
+        };
+        let transform = selected.component::<TransformComponent>();
+
+        match edit {
+            GizmoEdit::WorldPosition(position) => transform.set_world_position(position),
+            GizmoEdit::WorldRotation(rotation) => transform.set_world_rotation(rotation),
+            GizmoEdit::LocalScale(scale) => transform.set_scale(scale),
+        }
+    }
+
+    /// Returns the placement of the gizmo at the selected object, sized to be constant on the screen.
+    fn frame(&self) -> Option<GizmoFrame> {
+        let transform = self.selected.as_ref()?.component::<TransformComponent>();
+        let center = transform.world_position();
+        let rotation = match (self.mode, self.space) {
+            (GizmoMode::Scale, _) | (_, GizmoSpace::Local) => transform.world_rotation(),
+            (_, GizmoSpace::World) => Quat::IDENTITY,
+        };
+
+        let ctx = use_context();
+        let world = ctx.world();
+        let cameras = world.read_component::<Camera>();
+        let camera = cameras.get(self.camera.entity)?;
+        let object_mgr = ctx.object_mgr();
+        let camera_matrix = object_mgr.object_hierarchy().matrix(self.camera.object_id);
+        let screen_size = ctx.screen_mgr().logical_size();
+        let camera_right = Vec3::from(Vec4::from_vec3(Vec3::RIGHT, 0.0) * camera_matrix);
+        let pixels_per_unit = Vec2::len(
+            camera.world_to_screen(camera_matrix, center + camera_right, screen_size)?
+                - camera.world_to_screen(camera_matrix, center, screen_size)?,
+        );
+
+        if pixels_per_unit < f32::EPSILON {
+            return None;
+        }
+
+        Some(GizmoFrame {
+            center,
+            rotation,
+            size: GIZMO_SIZE / pixels_per_unit,
+        })
+    }
+
+    fn ray(&self, cursor: Vec2) -> Option<Ray> {
+        let ctx = use_context();
+        let world = ctx.world();
+        let cameras = world.read_component::<Camera>();
+        let camera = cameras.get(self.camera.entity)?;
+        let object_mgr = ctx.object_mgr();
+        let camera_matrix = object_mgr.object_hierarchy().matrix(self.camera.object_id);
+        let (origin, direction) =
+            camera.screen_to_ray(camera_matrix, cursor, ctx.screen_mgr().logical_size());
+        Some(Ray::new(origin, direction))
+    }
+
+    /// Moves the gizmo to the selected object. The handles are modeled with a length of one, so the gizmo is
+    /// scaled by the size of the frame.
+    fn sync_placement(&self) {
+        let frame = if let Some(frame) = self.frame() {
+            frame
+        } else {
+            return;
+        };
+        let transform = self.root.component::<TransformComponent>();
+        transform.set_position(frame.center);
+        transform.set_rotation(frame.rotation);
+        transform.set_scale(Vec3::ONE * frame.size);
+    }
+
+    fn sync_visibility(&self) {
+        self.root.set_active(self.selected.is_some());
+
+        for (mode, objects) in &self.handles {
+            for object in objects {
+                object.set_active(*mode == self.mode);
+            }
+        }
+    }
+
+    fn set_hovered(&mut self, hovered: Option<GizmoAxis>) {
+        if self.hovered == hovered {
+            return;
+        }
+
+        self.hovered = hovered;
+
+        let ctx = use_context();
+        let world = ctx.world();
+        let mut renderers = world.write_component::<MeshRenderer>();
+
+        for (_, objects) in &self.handles {
+            for (axis, object) in GizmoAxis::ALL.into_iter().zip(objects) {
+                let color = if hovered == Some(axis) {
+                    HOVERED_COLOR
+                } else {
+                    AXIS_COLORS[axis.index()]
+                };
+
+                if let Some(renderer) = renderers.get_mut(object.entity) {
+                    renderer.property_block_mut().set_uniform("color", color);
+                }
+            }
+        }
+    }
+
+    fn set_orbit_enabled(&self, is_enabled: bool) {
+        let world = use_context().world();
+        let mut controllers = world.write_component::<OrbitCameraController>();
+
+        if let Some(controller) = controllers.get_mut(self.camera.entity) {
+            controller.is_enabled = is_enabled;
+        }
+    }
+}
+
+fn local_transform(object: &ObjectHandle) -> Option<Transform> {
+    let world = use_context().world();
+    let transforms = world.read_component::<Transform>();
+    transforms.get(object.entity).cloned()
+}
+
+/// Builds the lines of a handle, along the axis with a length of one.
+fn handle_mesh(mode: GizmoMode, axis: GizmoAxis) -> Mesh {
+    let direction = axis.direction();
+    let (side, up) = match axis {
+        GizmoAxis::X => (Vec3::UP, Vec3::BACKWARD),
+        GizmoAxis::Y => (Vec3::BACKWARD, Vec3::RIGHT),
+        GizmoAxis::Z => (Vec3::RIGHT, Vec3::UP),
+    };
+    let mut segments = Vec::new();
+
+    match mode {
+        GizmoMode::Translate => {
+            let base = direction * 0.8;
+            let corners = [side, up, -side, -up].map(|offset| base + offset * 0.06);
+            segments.push((Vec3::ZERO, direction));
+
+            for (index, &corner) in corners.iter().enumerate() {
+                segments.push((corner, direction));
+                segments.push((corner, corners[(index + 1) % corners.len()]));
+            }
+        }
+        GizmoMode::Rotate => {
+            let point = |segment: u32| {
+                let angle = segment as f32 / RING_SEGMENTS as f32 * std::f32::consts::TAU;
+                side * angle.cos() + up * angle.sin()
+            };
+            segments.extend((0..RING_SEGMENTS).map(|segment| (point(segment), point(segment + 1))));
+        }
+        GizmoMode::Scale => {
+            let half = 0.05;
+            segments.push((Vec3::ZERO, direction * (1.0 - half)));
+
+            for (a, b) in [(direction, side), (side, up), (up, direction)] {
+                let c = Vec3::cross(a, b);
+
+                for (sign_b, sign_c) in [(1.0, 1.0), (1.0, -1.0), (-1.0, 1.0), (-1.0, -1.0)] {
+                    let offset = (b * sign_b + c * sign_c) * half;
+                    segments.push((direction + offset - a * half, direction + offset + a * half));
+                }
+            }
+        }
+    }
+
+    Mesh::lines(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(lhs: Vec3, rhs: Vec3) -> bool {
+        Vec3::distance(lhs, rhs) < 1e-4
+    }
+
+    fn frame() -> GizmoFrame {
+        GizmoFrame {
+            center: Vec3::ZERO,
+            rotation: Quat::IDENTITY,
+            size: 2.0,
+        }
+    }
+
+    /// A ray that looks down the negative z-axis at the point.
+    fn ray_at(x: f32, y: f32) -> Ray {
+        Ray::new(Vec3::new(x, y, 10.0), Vec3::FORWARD)
+    }
+
+    fn start() -> GizmoDragStart {
+        GizmoDragStart {
+            world_position: Vec3::ZERO,
+            world_rotation: Quat::IDENTITY,
+            local_scale: Vec3::ONE,
+        }
+    }
+
+    #[test]
+    fn hit_test_finds_handles_under_the_ray() {
+        let frame = frame();
+
+        assert_eq!(
+            frame.hit_test(GizmoMode::Translate, &ray_at(1.5, 0.05), 0.1),
+            Some(GizmoAxis::X)
+        );
+        assert_eq!(
+            frame.hit_test(GizmoMode::Translate, &ray_at(0.05, 1.0), 0.1),
+            Some(GizmoAxis::Y)
+        );
+        // Beyond the end of the handle.
+        assert_eq!(
+            frame.hit_test(GizmoMode::Translate, &ray_at(2.5, 0.0), 0.1),
+            None
+        );
+        // On the ring around the z-axis, which faces the ray.
+        assert_eq!(
+            frame.hit_test(GizmoMode::Rotate, &ray_at(0.0, -1.95), 0.1),
+            Some(GizmoAxis::Z)
+        );
+        assert_eq!(
+            frame.hit_test(GizmoMode::Rotate, &ray_at(0.5, 0.5), 0.1),
+            None
+        );
+    }
+
+    #[test]
+    fn drags_follow_the_ray_and_snap() {
+        let frame = frame();
+
+        let drag = frame
+            .begin_drag(
+                GizmoMode::Translate,
+                GizmoAxis::X,
+                &ray_at(1.0, 0.0),
+                start(),
+            )
+            .unwrap();
+        let position = match drag.edit(&ray_at(1.7, 0.3), false) {
+            Some(GizmoEdit::WorldPosition(position)) => position,
+            edit => panic!("unexpected edit: {:?}", edit),
+        };
+        assert!(close(position, Vec3::new(0.7, 0.0, 0.0)));
+        assert_eq!(
+            drag.edit(&ray_at(1.7, 0.3), true),
+            Some(GizmoEdit::WorldPosition(Vec3::new(0.5, 0.0, 0.0)))
+        );
+
+        let drag = frame
+            .begin_drag(GizmoMode::Rotate, GizmoAxis::Z, &ray_at(2.0, 0.0), start())
+            .unwrap();
+        let rotation = match drag.edit(&ray_at(1.8, 0.9), true) {
+            Some(GizmoEdit::WorldRotation(rotation)) => rotation,
+            edit => panic!("unexpected edit: {:?}", edit),
+        };
+        // About 26.6 degrees, snapped to 30.
+        let expected = Quat::from_axis_angle(Vec3::BACKWARD, ROTATION_SNAP * 2.0) * Vec3::RIGHT;
+        assert!(close(rotation * Vec3::RIGHT, expected));
+
+        let drag = frame
+            .begin_drag(GizmoMode::Scale, GizmoAxis::Y, &ray_at(0.0, 2.0), start())
+            .unwrap();
+        assert_eq!(
+            drag.edit(&ray_at(0.0, 3.0), false),
+            Some(GizmoEdit::LocalScale(Vec3::new(1.0, 1.5, 1.0)))
+        );
+        assert_eq!(
+            drag.edit(&ray_at(0.0, -1.0), false),
+            Some(GizmoEdit::LocalScale(Vec3::new(1.0, MIN_SCALE, 1.0)))
+        );
+    }
+}
@@ -0,0 +1,648 @@
+use crate::{
+    create_ui_element, create_ui_label,
+    selection::{self, HierarchyListener, SelectionListener},
+    widgets::{make_interactable, set_active, set_color, set_margin, set_text},
+};
+use r3d::{
+    fontdue::layout::HorizontalAlign,
+    gfx::{Color, NinePatchHandle},
+    input::InputDevice,
+    math::Vec2,
+    object::{Object, ObjectHandle, ObjectHierarchy, ObjectId, ObjectNameRegistry},
+    object_event::{object_event_types, ObjectEventHandler},
+    ui::{UIAnchor, UIMargin},
+    use_context,
+};
+use std::{cell::RefCell, collections::HashSet, rc::Rc};
+
+const WIDTH: f32 = 220.0;
+/// The number of rows shown at once. Only the shown part of the hierarchy has rows, so long hierarchies scroll
+/// through the same rows.
+const ROW_COUNT: usize = 20;
+const ROW_HEIGHT: f32 = 22.0;
+const PADDING: f32 = 6.0;
+const INDENT: f32 = 14.0;
+const TOGGLE_WIDTH: f32 = 16.0;
+/// How many rows a notch of the mouse wheel scrolls.
+const SCROLL_ROWS: usize = 3;
+/// Presses that move less than this many logical pixels before the release are clicks, which select objects.
+/// Longer ones are drags, which re-parent them.
+const CLICK_TOLERANCE: f32 = 4.0;
+
+const PANEL_COLOR: &str = "262626";
+const ROW_COLOR: &str = "303030";
+const SELECTED_COLOR: &str = "2F5A8A";
+const DROP_TARGET_COLOR: &str = "6A5A20";
+
+/// An object listed in the hierarchy panel.
+#[derive(Debug, Clone, PartialEq)]
+struct HierarchyEntry {
+    object: ObjectId,
+    depth: usize,
+    has_children: bool,
+    is_expanded: bool,
+    name: String,
+}
+
+/// Flattens the hierarchy into the entries to list, parents first. The children of the collapsed objects are
+/// left out, and so are the hidden objects along with their children.
+fn flatten_hierarchy(
+    hierarchy: &ObjectHierarchy,
+    names: &ObjectNameRegistry,
+    collapsed: &HashSet<ObjectId>,
+    hidden: &HashSet<ObjectId>,
+) -> Vec<HierarchyEntry> {
+    let objects = hierarchy.objects();
+    let mut entries = Vec::new();
+    let mut index = 0;
+
+    while index < objects.len() {
+        let object = objects[index];
+        let span = hierarchy.object_and_children(object).len();
+
+        if hidden.contains(&object) {
+            index += span;
+            continue;
+        }
+
+        let is_expanded = !collapsed.contains(&object);
+        entries.push(HierarchyEntry {
+            object,
+            depth: hierarchy.parents(object).len(),
+            has_children: 1 < span,
+            is_expanded,
+            name: names
+                .name(object)
+                .cloned()
+                .unwrap_or_else(|| format!("<object {}>", object.get())),
+        });
+
+        index += if is_expanded { 1 } else { span };
+    }
+
+    entries
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Hover {
+    Row(usize),
+    /// The panel below the rows. Dropping an object there moves it to the top level.
+    Empty,
+}
+
+/// A change requested from the UI events. The events are dispatched while the UI is being updated, so the
+/// changes are applied later in [`HierarchyPanel::update`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Request {
+    Select(ObjectId),
+    Toggle(ObjectId),
+    Reparent {
+        object: ObjectId,
+        parent: Option<ObjectId>,
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Press {
+    object: ObjectId,
+    distance: f32,
+}
+
+impl Press {
+    fn is_drag(&self) -> bool {
+        CLICK_TOLERANCE <= self.distance
+    }
+}
+
+/// The state shared with the event handlers of the rows.
+#[derive(Default)]
+struct Interaction {
+    /// The objects shown by the rows, so that the events of a row resolve to its object.
+    row_objects: Vec<Option<ObjectId>>,
+    hovered: Option<Hover>,
+    press: Option<Press>,
+    requests: Vec<Request>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RowState {
+    Normal,
+    Selected,
+    DropTarget,
+}
+
+impl RowState {
+    fn color(self) -> Color {
+        Color::parse_hex(match self {
+            Self::Normal => ROW_COLOR,
+            Self::Selected => SELECTED_COLOR,
+            Self::DropTarget => DROP_TARGET_COLOR,
+        })
+        .unwrap()
+    }
+}
+
+struct HierarchyRow {
+    background: ObjectHandle,
+    toggle: ObjectHandle,
+    label: ObjectHandle,
+    shown: Option<(HierarchyEntry, RowState)>,
+}
+
+/// Lists the objects in a tree, on the left side of the screen.
+///
+/// Clicking a row selects its object, and clicking the `+` or `-` in front of it expands or collapses its
+/// children. Dragging a row onto another re-parents its object, and dragging it onto the empty space below the
+/// rows moves it to the top level. The mouse wheel scrolls the list. The list is refreshed only when the
+/// hierarchy or the selection has changed.
+pub struct HierarchyPanel {
+    rows: Vec<HierarchyRow>,
+    interaction: Rc<RefCell<Interaction>>,
+    hierarchy_listener: HierarchyListener,
+    selection_listener: SelectionListener,
+    hidden: HashSet<ObjectId>,
+    collapsed: HashSet<ObjectId>,
+    entries: Vec<HierarchyEntry>,
+    selected: Option<ObjectId>,
+    scroll: usize,
+    drop_row: Option<usize>,
+}
+
+impl HierarchyPanel {
+    /// Creates the panel under the UI object. The hidden objects and their children are not listed, e.g. the
+    /// objects of the editor itself.
+    pub fn new(
+        parent: &ObjectHandle,
+        nine_patch: &NinePatchHandle,
+        hidden: impl IntoIterator<Item = ObjectId>,
+    ) -> Self {
+        let ctx = use_context();
+        let mut object_mgr = ctx.object_mgr_mut();
+        let mut world = ctx.world_mut();
+
+        let root = create_ui_element(
+            &mut object_mgr,
+            &mut world,
+            "hierarchy-panel",
+            parent,
+            UIAnchor::new(Vec2::ZERO, Vec2::new(0.0, 1.0)),
+            UIMargin::new(0.0, -WIDTH, 0.0, 0.0),
+            Some((nine_patch, Color::parse_hex(PANEL_COLOR).unwrap())),
+        );
+        make_interactable(&world, &root);
+
+        let rows = (0..ROW_COUNT)
+            .map(|index| {
+                let top = PADDING + index as f32 * ROW_HEIGHT;
+                let background = create_ui_element(
+                    &mut object_mgr,
+                    &mut world,
+                    "hierarchy-row",
+                    &root,
+                    UIAnchor::new(Vec2::new(0.0, 1.0), Vec2::ONE),
+                    UIMargin::new(PADDING, PADDING, top, -(top + ROW_HEIGHT - 2.0)),
+                    Some((nine_patch, RowState::Normal.color())),
+                );
+                let toggle = create_ui_label(
+                    &mut object_mgr,
+                    &mut world,
+                    "hierarchy-row-toggle",
+                    &background,
+                    UIAnchor::new(Vec2::ZERO, Vec2::new(0.0, 1.0)),
+                    UIMargin::new(0.0, -TOGGLE_WIDTH, 0.0, 0.0),
+                    HorizontalAlign::Center,
+                );
+                let label = create_ui_label(
+                    &mut object_mgr,
+                    &mut world,
+                    "hierarchy-row-label",
+                    &background,
+                    UIAnchor::full(),
+                    UIMargin::new(TOGGLE_WIDTH, 0.0, 0.0, 0.0),
+                    HorizontalAlign::Left,
+                );
+                make_interactable(&world, &background);
+                make_interactable(&world, &toggle);
+
+                HierarchyRow {
+                    background,
+                    toggle,
+                    label,
+                    shown: None,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        drop(world);
+        drop(object_mgr);
+
+        // The rows are shown once there is something to list.
+        for row in &rows {
+            set_active(&row.background, false);
+        }
+
+        let interaction = Rc::new(RefCell::new(Interaction {
+            row_objects: vec![None; ROW_COUNT],
+            ..Default::default()
+        }));
+        bind_events(&interaction, &root, &rows);
+
+        let mut hidden = hidden.into_iter().collect::<HashSet<_>>();
+        hidden.insert(root.object_id);
+
+        Self {
+            rows,
+            interaction,
+            hierarchy_listener: HierarchyListener::new(),
+            selection_listener: SelectionListener::new(),
+            hidden,
+            collapsed: HashSet::new(),
+            entries: Vec::new(),
+            selected: None,
+            scroll: 0,
+            drop_row: None,
+        }
+    }
+
+    pub fn update(&mut self) {
+        let ctx = use_context();
+        let mut is_dirty = self.hierarchy_listener.take();
+
+        if let Some(event) = self.selection_listener.take() {
+            self.selected = event.object.map(|object| object.object_id);
+
+            // Reveals the selected object, in case it was selected in the scene.
+            if let Some(selected) = self.selected {
+                for parent in ctx.object_mgr().object_hierarchy().parents(selected) {
+                    self.collapsed.remove(parent);
+                }
+            }
+
+            is_dirty = true;
+        }
+
+        let (requests, hovered) = {
+            let mut interaction = self.interaction.borrow_mut();
+            (
+                std::mem::take(&mut interaction.requests),
+                interaction.hovered,
+            )
+        };
+
+        for request in requests {
+            self.apply(request);
+            is_dirty = true;
+        }
+
+        if hovered.is_some() {
+            let scroll = ctx
+                .input_mgr()
+                .mouse()
+                .input("scroll:y")
+                .map_or(0.0, |input| input.value);
+
+            if 0.0 < scroll {
+                self.scroll = self.scroll.saturating_sub(SCROLL_ROWS);
+                is_dirty = true;
+            } else if scroll < 0.0 {
+                self.scroll += SCROLL_ROWS;
+                is_dirty = true;
+            }
+        }
+
+        if is_dirty {
+            self.entries = {
+                let object_mgr = ctx.object_mgr();
+                flatten_hierarchy(
+                    object_mgr.object_hierarchy(),
+                    object_mgr.object_name_registry(),
+                    &self.collapsed,
+                    &self.hidden,
+                )
+            };
+            self.scroll = self
+                .scroll
+                .min(self.entries.len().saturating_sub(ROW_COUNT));
+        }
+
+        let drop_row = {
+            let interaction = self.interaction.borrow();
+            match (interaction.press, interaction.hovered) {
+                (Some(press), Some(Hover::Row(row))) if press.is_drag() => Some(row),
+                _ => None,
+            }
+        };
+
+        if is_dirty || drop_row != self.drop_row {
+            self.drop_row = drop_row;
+            self.render();
+        }
+    }
+
+    fn apply(&mut self, request: Request) {
+        match request {
+            Request::Select(object) => {
+                let object = use_context().object_mgr().object_handle(object);
+                selection::select(Some(object));
+            }
+            Request::Toggle(object) => {
+                if !self.collapsed.remove(&object) {
+                    self.collapsed.insert(object);
+                }
+            }
+            Request::Reparent { object, parent } => {
+                let ctx = use_context();
+                let (object, parent) = {
+                    let object_mgr = ctx.object_mgr();
+                    let hierarchy = object_mgr.object_hierarchy();
+
+                    // An object cannot be moved under itself or one of its children.
+                    if parent.is_some_and(|parent| {
+                        hierarchy.object_and_children(object).contains(&parent)
+                    }) || hierarchy.parent(object) == parent
+                    {
+                        return;
+                    }
+
+                    (
+                        object_mgr.object_handle(object),
+                        parent.map(|parent| object_mgr.object_handle(parent)),
+                    )
+                };
+
+                object.set_parent(parent.as_ref());
+
+                if let Some(parent) = parent {
+                    self.collapsed.remove(&parent.object_id);
+                }
+            }
+        }
+    }
+
+    /// Shows the entries from the scroll position in the rows. Only the rows that show something else than
+    /// before are touched.
+    fn render(&mut self) {
+        let mut interaction = self.interaction.borrow_mut();
+
+        for (index, row) in self.rows.iter_mut().enumerate() {
+            let entry = self.entries.get(self.scroll + index);
+            interaction.row_objects[index] = entry.map(|entry| entry.object);
+
+            let shown = entry.map(|entry| {
+                let state = if self.drop_row == Some(index) {
+                    RowState::DropTarget
+                } else if self.selected == Some(entry.object) {
+                    RowState::Selected
+                } else {
+                    RowState::Normal
+                };
+                (entry.clone(), state)
+            });
+
+            if row.shown == shown {
+                continue;
+            }
+
+            match &shown {
+                Some((entry, state)) => {
+                    let indent = entry.depth as f32 * INDENT;
+                    set_active(&row.background, true);
+                    set_margin(
+                        &row.toggle,
+                        UIMargin::new(indent, -(indent + TOGGLE_WIDTH), 0.0, 0.0),
+                    );
+                    set_margin(
+                        &row.label,
+                        UIMargin::new(indent + TOGGLE_WIDTH, 0.0, 0.0, 0.0),
+                    );
+                    set_text(
+                        &row.toggle,
+                        match (entry.has_children, entry.is_expanded) {
+                            (false, _) => "",
+                            (true, true) => "-",
+                            (true, false) => "+",
+                        },
+                    );
+                    set_text(&row.label, &entry.name);
+                    set_color(&row.background, state.color());
+                }
+                None => {
+                    set_active(&row.background, false);
+                }
+            }
+
+            row.shown = shown;
+        }
+    }
+}
+
+fn bind_events(interaction: &Rc<RefCell<Interaction>>, root: &ObjectHandle, rows: &[HierarchyRow]) {
+    let object_event_mgr = use_context().object_event_mgr();
+    let object = |object: &ObjectHandle| Object::new(object.entity, object.object_id);
+
+    let hover_targets = std::iter::once((root, Hover::Empty)).chain(
+        rows.iter().enumerate().flat_map(|(index, row)| {
+            [
+                (&row.background, Hover::Row(index)),
+                (&row.toggle, Hover::Row(index)),
+            ]
+        }),
+    );
+
+    for (target, hover) in hover_targets {
+        object_event_mgr.add_handler(
+            ObjectEventHandler::<object_event_types::MouseEnterEvent>::new(object(target), {
+                let interaction = interaction.clone();
+                move |_, _| interaction.borrow_mut().hovered = Some(hover)
+            }),
+        );
+        object_event_mgr.add_handler(
+            ObjectEventHandler::<object_event_types::MouseLeaveEvent>::new(object(target), {
+                let interaction = interaction.clone();
+                move |_, _| {
+                    let mut interaction = interaction.borrow_mut();
+
+                    if interaction.hovered == Some(hover) {
+                        interaction.hovered = None;
+                    }
+                }
+            }),
+        );
+    }
+
+    for (index, row) in rows.iter().enumerate() {
+        object_event_mgr.add_handler(
+            ObjectEventHandler::<object_event_types::DragStartEvent>::new(
+                object(&row.background),
+                {
+                    let interaction = interaction.clone();
+                    move |_, _| {
+                        let mut interaction = interaction.borrow_mut();
+                        interaction.press = interaction.row_objects[index].map(|object| Press {
+                            object,
+                            distance: 0.0,
+                        });
+                    }
+                },
+            ),
+        );
+        object_event_mgr.add_handler(ObjectEventHandler::<object_event_types::DragEvent>::new(
+            object(&row.background),
+            {
+                let interaction = interaction.clone();
+                move |_, event| {
+                    if let Some(press) = &mut interaction.borrow_mut().press {
+                        press.distance += event.delta.len();
+                    }
+                }
+            },
+        ));
+        object_event_mgr.add_handler(ObjectEventHandler::<object_event_types::DragEndEvent>::new(
+            object(&row.background),
+            {
+                let interaction = interaction.clone();
+                move |_, _| end_press(&mut interaction.borrow_mut())
+            },
+        ));
+        object_event_mgr.add_handler(ObjectEventHandler::<object_event_types::DragEndEvent>::new(
+            object(&row.toggle),
+            {
+                let interaction = interaction.clone();
+                move |_, _| {
+                    let mut interaction = interaction.borrow_mut();
+
+                    if let Some(object) = interaction.row_objects[index] {
+                        interaction.requests.push(Request::Toggle(object));
+                    }
+                }
+            },
+        ));
+    }
+}
+
+/// Turns a released press into a selection if it was a click, or into a re-parent onto the hovered row if it
+/// was a drag. Drags released outside the panel are cancelled.
+fn end_press(interaction: &mut Interaction) {
+    let press = if let Some(press) = interaction.press.take() {
+        press
+    } else {
+        return;
+    };
+
+    if !press.is_drag() {
+        interaction.requests.push(Request::Select(press.object));
+        return;
+    }
+
+    let parent = match interaction.hovered {
+        Some(Hover::Row(row)) => match interaction.row_objects[row] {
+            Some(parent) => Some(parent),
+            None => return,
+        },
+        Some(Hover::Empty) => None,
+        None => return,
+    };
+
+    interaction.requests.push(Request::Reparent {
+        object: press.object,
+        parent,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use r3d::specs::{Builder, World, WorldExt};
+
+    #[test]
+    fn flatten_skips_collapsed_and_hidden_objects() {
+        let mut world = World::new();
+        let mut hierarchy = ObjectHierarchy::new();
+        let mut names = ObjectNameRegistry::new();
+        let [root, child, grandchild, editor, editor_child] =
+            [0, 1, 2, 3, 4].map(ObjectId::from_u32);
+
+        for (id, name) in [
+            (root, "root"),
+            (child, "child"),
+            (grandchild, "grandchild"),
+            (editor, "editor"),
+            (editor_child, "editor-child"),
+        ] {
+            hierarchy.add(id, world.create_entity().build());
+            names.set_name(id, Some(name.to_owned()));
+        }
+
+        hierarchy.set_parent(child, Some(root));
+        hierarchy.set_parent(grandchild, Some(child));
+        hierarchy.set_parent(editor_child, Some(editor));
+
+        let hidden = HashSet::from([editor]);
+        let listed = |collapsed: &HashSet<ObjectId>| {
+            flatten_hierarchy(&hierarchy, &names, collapsed, &hidden)
+                .into_iter()
+                .map(|entry| {
+                    (
+                        entry.name,
+                        entry.depth,
+                        entry.has_children,
+                        entry.is_expanded,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            listed(&HashSet::new()),
+            [
+                ("root".to_owned(), 0, true, true),
+                ("child".to_owned(), 1, true, true),
+                ("grandchild".to_owned(), 2, false, true),
+            ]
+        );
+        assert_eq!(
+            listed(&HashSet::from([child])),
+            [
+                ("root".to_owned(), 0, true, true),
+                ("child".to_owned(), 1, true, false),
+            ]
+        );
+        assert_eq!(
+            listed(&HashSet::from([root])),
+            [("root".to_owned(), 0, true, false)]
+        );
+    }
+
+    #[test]
+    fn short_presses_select_and_drags_reparent() {
+        let [object, target] = [1, 2].map(ObjectId::from_u32);
+        let mut interaction = Interaction {
+            row_objects: vec![Some(object), Some(target), None],
+            ..Default::default()
+        };
+        let mut release = |distance: f32, hovered: Option<Hover>| {
+            interaction.press = Some(Press { object, distance });
+            interaction.hovered = hovered;
+            end_press(&mut interaction);
+            std::mem::take(&mut interaction.requests)
+        };
+
+        assert_eq!(release(1.0, Some(Hover::Row(1))), [Request::Select(object)]);
+        assert_eq!(
+            release(10.0, Some(Hover::Row(1))),
+            [Request::Reparent {
+                object,
+                parent: Some(target)
+            }]
+        );
+        assert_eq!(
+            release(10.0, Some(Hover::Empty)),
+            [Request::Reparent {
+                object,
+                parent: None
+            }]
+        );
+        assert_eq!(release(10.0, Some(Hover::Row(2))), []);
+        assert_eq!(release(10.0, None), []);
+    }
+}
@@ -0,0 +1,691 @@
+use crate::{
+    create_ui_element, create_ui_label,
+    selection::{HierarchyListener, SelectionListener},
+    widgets::{make_interactable, set_active, set_color, set_text, set_text_color},
+};
+use r3d::{
+    camera_controller::OrbitCameraController,
+    fontdue::layout::HorizontalAlign,
+    gfx::{Color, NinePatchHandle},
+    input::InputDevice,
+    math::{Quat, Vec2, Vec3},
+    object::{Object, ObjectHandle},
+    object_event::{object_event_types, ObjectEventHandler},
+    specs::{Component, WorldExt},
+    transform::TransformComponent,
+    ui::{UIAnchor, UIMargin},
+    use_context,
+};
+use std::{cell::Cell, marker::PhantomData, rc::Rc};
+
+const WIDTH: f32 = 260.0;
+/// The number of rows of the panel. Fields past the last row are not shown.
+const ROW_COUNT: usize = 24;
+const ROW_HEIGHT: f32 = 22.0;
+const PADDING: f32 = 6.0;
+
+const PANEL_COLOR: &str = "262626";
+const FIELD_COLOR: &str = "383838";
+const EDITING_COLOR: &str = "2F5A8A";
+const HEADER_COLOR: &str = "8AB4F8";
+const LABEL_COLOR: &str = "E0E0E0";
+
+/// The keys that edit the focused field, named as by the keyboard device.
+const EDIT_KEYS: [&str; 28] = [
+    "0",
+    "1",
+    "2",
+    "3",
+    "4",
+    "5",
+    "6",
+    "7",
+    "8",
+    "9",
+    "numpad0",
+    "numpad1",
+    "numpad2",
+    "numpad3",
+    "numpad4",
+    "numpad5",
+    "numpad6",
+    "numpad7",
+    "numpad8",
+    "numpad9",
+    "period",
+    "numpaddecimal",
+    "minus",
+    "numpadsubtract",
+    "backspace",
+    "enter",
+    "numpadenter",
+    "escape",
+];
+
+/// A component whose fields can be edited in the inspector panel. Only numeric fields are supported.
+pub trait InspectableComponent: Component {
+    /// The title of the section of the component.
+    const NAME: &'static str;
+
+    /// Returns the names and the values of the fields, in the order they are listed.
+    fn fields(&self) -> Vec<(&'static str, f32)>;
+
+    /// Sets the field with the name, which is one of [`Self::fields`].
+    fn set_field(&mut self, name: &str, value: f32);
+}
+
+/// Accesses an [`InspectableComponent`] of objects without knowing its type.
+trait ComponentInspector {
+    fn name(&self) -> &'static str;
+
+    fn fields(&self, object: &ObjectHandle) -> Option<Vec<(&'static str, f32)>>;
+
+    fn set_field(&self, object: &ObjectHandle, name: &str, value: f32);
+}
+
+struct TypedComponentInspector<T>(PhantomData<fn() -> T>);
+
+impl<T> ComponentInspector for TypedComponentInspector<T>
+where
+    T: InspectableComponent,
+{
+    fn name(&self) -> &'static str {
+        T::NAME
+    }
+
+    fn fields(&self, object: &ObjectHandle) -> Option<Vec<(&'static str, f32)>> {
+        use_context()
+            .world()
+            .read_component::<T>()
+            .get(object.entity)
+            .map(T::fields)
+    }
+
+    fn set_field(&self, object: &ObjectHandle, name: &str, value: f32) {
+        if let Some(component) = use_context()
+            .world()
+            .write_component::<T>()
+            .get_mut(object.entity)
+        {
+            component.set_field(name, value);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TransformField {
+    Position,
+    /// The euler angles in degrees.
+    Rotation,
+    Scale,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FieldSource {
+    Transform(TransformField, usize),
+    Component {
+        inspector: usize,
+        name: &'static str,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum InspectorLine {
+    Header(&'static str),
+    Field { label: String, source: FieldSource },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EditOutcome {
+    Commit(f32),
+    Cancel,
+}
+
+/// The text of the field being edited. It starts out as the value, and the first typed character replaces it
+/// as if it were selected.
+#[derive(Debug, Clone)]
+struct NumberEdit {
+    text: String,
+    is_replacing: bool,
+}
+
+impl NumberEdit {
+    fn new(value: f32) -> Self {
+        Self {
+            text: format_value(value),
+            is_replacing: true,
+        }
+    }
+
+    /// Handles a pressed key, named as by the keyboard device. Returns the outcome once the edit is over.
+    fn press(&mut self, key: &str) -> Option<EditOutcome> {
+        let character = match key {
+            "enter" | "numpadenter" => {
+                return Some(match self.text.parse::<f32>() {
+                    Ok(value) if value.is_finite() => EditOutcome::Commit(value),
+                    _ => EditOutcome::Cancel,
+                });
+            }
+            "escape" => return Some(EditOutcome::Cancel),
+            "backspace" => {
+                if std::mem::take(&mut self.is_replacing) {
+                    self.text.clear();
+                } else {
+                    self.text.pop();
+                }
+                return None;
+            }
+            "period" | "numpaddecimal" => '.',
+            "minus" | "numpadsubtract" => '-',
+            key => match key.strip_prefix("numpad").unwrap_or(key).parse::<u8>() {
+                Ok(digit) if digit < 10 => char::from(b'0' + digit),
+                _ => return None,
+            },
+        };
+
+        if std::mem::take(&mut self.is_replacing) {
+            self.text.clear();
+        }
+
+        self.text.push(character);
+        None
+    }
+}
+
+fn format_value(value: f32) -> String {
+    format!("{:.3}", value)
+}
+
+struct InspectorRow {
+    label: ObjectHandle,
+    field: ObjectHandle,
+    value: ObjectHandle,
+    line: Option<InspectorLine>,
+}
+
+/// Shows the transform of the selected object and the fields of its [`InspectableComponent`]s, on the right
+/// side of the screen.
+///
+/// Clicking a field focuses it for typing. Enter applies the typed number, and escape or an invalid number
+/// leaves the field as it was. Focusing another field applies the typed number first. The rows are laid out
+/// again only when the selection changes, and the values are updated only when they differ from the shown ones.
+pub struct InspectorPanel {
+    title: ObjectHandle,
+    rows: Vec<InspectorRow>,
+    clicked: Rc<Cell<Option<usize>>>,
+    hierarchy_listener: HierarchyListener,
+    selection_listener: SelectionListener,
+    inspectors: Vec<Box<dyn ComponentInspector>>,
+    selected: Option<ObjectHandle>,
+    editing: Option<(usize, NumberEdit)>,
+    keys: [(&'static str, bool); EDIT_KEYS.len()],
+}
+
+impl InspectorPanel {
+    pub fn new(parent: &ObjectHandle, nine_patch: &NinePatchHandle) -> Self {
+        let ctx = use_context();
+        let mut object_mgr = ctx.object_mgr_mut();
+        let mut world = ctx.world_mut();
+
+        let root = create_ui_element(
+            &mut object_mgr,
+            &mut world,
+            "inspector-panel",
+            parent,
+            UIAnchor::new(Vec2::new(1.0, 0.0), Vec2::ONE),
+            UIMargin::new(-WIDTH, 0.0, 0.0, 0.0),
+            Some((nine_patch, Color::parse_hex(PANEL_COLOR).unwrap())),
+        );
+        make_interactable(&world, &root);
+
+        let row_margin = |index: usize| {
+            let top = PADDING + index as f32 * ROW_HEIGHT;
+            UIMargin::new(PADDING, PADDING, top, -(top + ROW_HEIGHT - 2.0))
+        };
+        let title = create_ui_label(
+            &mut object_mgr,
+            &mut world,
+            "inspector-title",
+            &root,
+            UIAnchor::new(Vec2::new(0.0, 1.0), Vec2::ONE),
+            row_margin(0),
+            HorizontalAlign::Left,
+        );
+
+        let rows = (0..ROW_COUNT)
+            .map(|index| {
+                let row = create_ui_element(
+                    &mut object_mgr,
+                    &mut world,
+                    "inspector-row",
+                    &root,
+                    UIAnchor::new(Vec2::new(0.0, 1.0), Vec2::ONE),
+                    row_margin(index + 1),
+                    None,
+                );
+                let label = create_ui_label(
+                    &mut object_mgr,
+                    &mut world,
+                    "inspector-row-label",
+                    &row,
+                    UIAnchor::new(Vec2::ZERO, Vec2::new(0.5, 1.0)),
+                    UIMargin::zero(),
+                    HorizontalAlign::Left,
+                );
+                let field = create_ui_element(
+                    &mut object_mgr,
+                    &mut world,
+                    "inspector-row-field",
+                    &row,
+                    UIAnchor::new(Vec2::new(0.5, 0.0), Vec2::ONE),
+                    UIMargin::zero(),
+                    Some((nine_patch, Color::parse_hex(FIELD_COLOR).unwrap())),
+                );
+                let value = create_ui_label(
+                    &mut object_mgr,
+                    &mut world,
+                    "inspector-row-value",
+                    &field,
+                    UIAnchor::full(),
+                    UIMargin::new(4.0, 4.0, 0.0, 0.0),
+                    HorizontalAlign::Left,
+                );
+                make_interactable(&world, &field);
+
+                InspectorRow {
+                    label,
+                    field,
+                    value,
+                    line: None,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        drop(world);
+        drop(object_mgr);
+
+        let clicked = Rc::new(Cell::new(None));
+        let object_event_mgr = ctx.object_event_mgr();
+
+        for (index, row) in rows.iter().enumerate() {
+            object_event_mgr.add_handler(
+                ObjectEventHandler::<object_event_types::DragEndEvent>::new(
+                    Object::new(row.field.entity, row.field.object_id),
+                    {
+                        let clicked = clicked.clone();
+                        move |_, _| clicked.set(Some(index))
+                    },
+                ),
+            );
+        }
+
+        let mut panel = Self {
+            title,
+            rows,
+            clicked,
+            hierarchy_listener: HierarchyListener::new(),
+            selection_listener: SelectionListener::new(),
+            inspectors: Vec::new(),
+            selected: None,
+            editing: None,
+            keys: EDIT_KEYS.map(|key| (key, false)),
+        };
+        panel.show(None);
+        panel
+    }
+
+    /// Lists the fields of the components of the type, for the objects that have one.
+    pub fn register<T>(&mut self)
+    where
+        T: InspectableComponent,
+    {
+        self.inspectors
+            .push(Box::new(TypedComponentInspector::<T>(PhantomData)));
+    }
+
+    pub fn update(&mut self) {
+        if let Some(event) = self.selection_listener.take() {
+            self.show(event.object);
+        }
+
+        if self.hierarchy_listener.take() {
+            // The selected object may have been removed or renamed.
+            let is_alive = self
+                .selected
+                .as_ref()
+                .map(|selected| use_context().world().is_alive(selected.entity));
+
+            match is_alive {
+                Some(false) => self.show(None),
+                _ => self.show_title(),
+            }
+        }
+
+        if let Some(index) = self.clicked.take() {
+            self.focus(index);
+        }
+
+        self.handle_keys();
+        self.show_values();
+    }
+
+    /// Lays out the rows for the fields of the object.
+    fn show(&mut self, object: Option<ObjectHandle>) {
+        self.editing = None;
+        self.selected = object;
+        self.show_title();
+
+        let mut lines = Vec::new();
+
+        if let Some(object) = &self.selected {
+            lines.push(InspectorLine::Header("Transform"));
+
+            for (field, name) in [
+                (TransformField::Position, "position"),
+                (TransformField::Rotation, "rotation"),
+                (TransformField::Scale, "scale"),
+            ] {
+                for (axis, axis_name) in ["x", "y", "z"].into_iter().enumerate() {
+                    lines.push(InspectorLine::Field {
+                        label: format!("{} {}", name, axis_name),
+                        source: FieldSource::Transform(field, axis),
+                    });
+                }
+            }
+
+            for (index, inspector) in self.inspectors.iter().enumerate() {
+                let fields = if let Some(fields) = inspector.fields(object) {
+                    fields
+                } else {
+                    continue;
+                };
+
+                lines.push(InspectorLine::Header(inspector.name()));
+                lines.extend(fields.into_iter().map(|(name, _)| InspectorLine::Field {
+                    label: name.to_owned(),
+                    source: FieldSource::Component {
+                        inspector: index,
+                        name,
+                    },
+                }));
+            }
+        }
+
+        let mut lines = lines.into_iter();
+
+        for row in &mut self.rows {
+            let line = lines.next();
+
+            if row.line == line {
+                continue;
+            }
+
+            match &line {
+                Some(InspectorLine::Header(name)) => {
+                    set_active(&row.label, true);
+                    set_active(&row.field, false);
+                    set_text(&row.label, name);
+                    set_text_color(&row.label, Color::parse_hex(HEADER_COLOR).unwrap());
+                }
+                Some(InspectorLine::Field { label, .. }) => {
+                    set_active(&row.label, true);
+                    set_active(&row.field, true);
+                    set_text(&row.label, label);
+                    set_text_color(&row.label, Color::parse_hex(LABEL_COLOR).unwrap());
+                    set_color(&row.field, Color::parse_hex(FIELD_COLOR).unwrap());
+                }
+                None => {
+                    set_active(&row.label, false);
+                    set_active(&row.field, false);
+                }
+            }
+
+            row.line = line;
+        }
+    }
+
+    fn show_title(&self) {
+        let title = match &self.selected {
+            Some(object) => object.name().unwrap_or_else(|| "<unnamed>".to_owned()),
+            None => "Nothing selected".to_owned(),
+        };
+        set_text(&self.title, &title);
+    }
+
+    fn focus(&mut self, index: usize) {
+        if self
+            .editing
+            .as_ref()
+            .is_some_and(|(editing, _)| *editing == index)
+        {
+            return;
+        }
+
+        if let Some((editing, edit)) = &mut self.editing {
+            let editing = *editing;
+
+            if let Some(EditOutcome::Commit(value)) = edit.press("enter") {
+                self.write(editing, value);
+            }
+
+            self.end_edit();
+        }
+
+        let value = if let Some(value) = self.read(index) {
+            value
+        } else {
+            return;
+        };
+
+        set_color(
+            &self.rows[index].field,
+            Color::parse_hex(EDITING_COLOR).unwrap(),
+        );
+        self.editing = Some((index, NumberEdit::new(value)));
+    }
+
+    fn end_edit(&mut self) {
+        if let Some((index, _)) = self.editing.take() {
+            set_color(
+                &self.rows[index].field,
+                Color::parse_hex(FIELD_COLOR).unwrap(),
+            );
+        }
+    }
+
+    fn handle_keys(&mut self) {
+        let pressed_keys = {
+            let ctx = use_context();
+            let input_mgr = ctx.input_mgr();
+            let is_down = |name: &str| {
+                input_mgr
+                    .keyboard()
+                    .input(name)
+                    .is_some_and(|input| input.value != 0.0)
+            };
+            let pressed_keys = self
+                .keys
+                .map(|(name, was_down)| (name, is_down(name) && !was_down));
+            self.keys = self.keys.map(|(name, _)| (name, is_down(name)));
+            pressed_keys
+        };
+
+        for (key, is_pressed) in pressed_keys {
+            let (index, edit) = match &mut self.editing {
+                Some(editing) if is_pressed => editing,
+                _ => continue,
+            };
+            let index = *index;
+
+            match edit.press(key) {
+                Some(EditOutcome::Commit(value)) => {
+                    self.write(index, value);
+                    self.end_edit();
+                }
+                Some(EditOutcome::Cancel) => self.end_edit(),
+                None => {}
+            }
+        }
+    }
+
+    /// Shows the current values in the fields, or the typed text in the focused one.
+    fn show_values(&self) {
+        for (index, row) in self.rows.iter().enumerate() {
+            let text = match &self.editing {
+                Some((editing, edit)) if *editing == index => format!("{}|", edit.text),
+                _ => match self.read(index) {
+                    Some(value) => format_value(value),
+                    None => continue,
+                },
+            };
+            set_text(&row.value, &text);
+        }
+    }
+
+    fn source(&self, index: usize) -> Option<FieldSource> {
+        match &self.rows[index].line {
+            Some(InspectorLine::Field { source, .. }) => Some(*source),
+            _ => None,
+        }
+    }
+
+    fn read(&self, index: usize) -> Option<f32> {
+        let object = self.selected.as_ref()?;
+
+        match self.source(index)? {
+            FieldSource::Transform(field, axis) => {
+                let transform = object.component::<TransformComponent>();
+                let value = match field {
+                    TransformField::Position => transform.position(),
+                    TransformField::Rotation => {
+                        let eular = transform.rotation().into_eular();
+                        Vec3::new(
+                            eular.x.to_degrees(),
+                            eular.y.to_degrees(),
+                            eular.z.to_degrees(),
+                        )
+                    }
+                    TransformField::Scale => transform.scale(),
+                };
+                Some(axis_value(value, axis))
+            }
+            FieldSource::Component { inspector, name } => self.inspectors[inspector]
+                .fields(object)?
+                .into_iter()
+                .find(|(field, _)| *field == name)
+                .map(|(_, value)| value),
+        }
+    }
+
+    fn write(&self, index: usize, value: f32) {
+        let (object, source) = match (self.selected.as_ref(), self.source(index)) {
+            (Some(object), Some(source)) => (object, source),
+            _ => return,
+        };
+
+        match source {
+            FieldSource::Transform(field, axis) => {
+                let transform = object.component::<TransformComponent>();
+
+                match field {
+                    TransformField::Position => {
+                        transform.set_position(with_axis_value(transform.position(), axis, value))
+                    }
+                    TransformField::Rotation => {
+                        let eular = transform.rotation().into_eular();
+                        let eular = with_axis_value(
+                            Vec3::new(
+                                eular.x.to_degrees(),
+                                eular.y.to_degrees(),
+                                eular.z.to_degrees(),
+                            ),
+                            axis,
+                            value,
+                        );
+                        transform.set_rotation(Quat::from_eular(
+                            eular.x.to_radians(),
+                            eular.y.to_radians(),
+                            eular.z.to_radians(),
+                        ));
+                    }
+                    TransformField::Scale => {
+                        transform.set_scale(with_axis_value(transform.scale(), axis, value))
+                    }
+                }
+            }
+            FieldSource::Component { inspector, name } => {
+                self.inspectors[inspector].set_field(object, name, value);
+            }
+        }
+    }
+}
+
+fn axis_value(vector: Vec3, axis: usize) -> f32 {
+    [vector.x, vector.y, vector.z][axis]
+}
+
+fn with_axis_value(vector: Vec3, axis: usize, value: f32) -> Vec3 {
+    let mut values = [vector.x, vector.y, vector.z];
+    values[axis] = value;
+    Vec3::new(values[0], values[1], values[2])
+}
+
+impl InspectableComponent for OrbitCameraController {
+    const NAME: &'static str = "Orbit Camera Controller";
+
+    fn fields(&self) -> Vec<(&'static str, f32)> {
+        vec![
+            ("target x", self.target.x),
+            ("target y", self.target.y),
+            ("target z", self.target.z),
+            ("distance", self.distance),
+            ("yaw", self.yaw.to_degrees()),
+            ("pitch", self.pitch.to_degrees()),
+        ]
+    }
+
+    fn set_field(&mut self, name: &str, value: f32) {
+        match name {
+            "target x" => self.target.x = value,
+            "target y" => self.target.y = value,
+            "target z" => self.target.z = value,
+            "distance" => self.distance = value.clamp(self.min_distance, self.max_distance),
+            "yaw" => self.yaw = value.to_radians(),
+            "pitch" => self.pitch = value.to_radians(),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_keys(keys: &[&str]) -> Option<EditOutcome> {
+        let mut edit = NumberEdit::new(1.5);
+        keys.iter().find_map(|key| edit.press(key))
+    }
+
+    #[test]
+    fn number_edits_parse_the_typed_text() {
+        assert_eq!(
+            type_keys(&["minus", "2", "period", "numpad5", "enter"]),
+            Some(EditOutcome::Commit(-2.5))
+        );
+        // The first backspace clears the whole value, like the first typed character.
+        assert_eq!(
+            type_keys(&["f1", "backspace", "enter"]),
+            Some(EditOutcome::Cancel)
+        );
+        assert_eq!(
+            type_keys(&["period", "backspace", "backspace", "7", "enter"]),
+            Some(EditOutcome::Commit(7.0))
+        );
+        assert_eq!(
+            type_keys(&["minus", "minus", "enter"]),
+            Some(EditOutcome::Cancel)
+        );
+        assert_eq!(type_keys(&["3", "escape"]), Some(EditOutcome::Cancel));
+        assert_eq!(type_keys(&["3", "a", "4"]), None);
+    }
+}
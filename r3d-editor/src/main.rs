@@ -1,34 +1,58 @@
-use assets::{FONT, MATERIAL_GLYPH, MATERIAL_SPRITE};
+use assets::{FONT, MATERIAL_GLYPH, MATERIAL_GLYPH_BITMAP, MATERIAL_GLYPH_SDF, MATERIAL_SPRITE};
+use gizmo::{GizmoUndoHook, TransformGizmo};
+use hierarchy_panel::HierarchyPanel;
+use inspector_panel::InspectorPanel;
 use pollster::FutureExt;
 use r3d::{
+    camera_controller::OrbitCameraController,
+    debug_overlay::{DebugOverlay, DebugOverlayConfig},
     event::{event_types, EventHandler},
     fontdue::layout::{HorizontalAlign, VerticalAlign},
     gfx::{
         Camera, CameraClearMode, CameraPerspectiveProjectionAspect, CameraProjection, Color,
-        NinePatch, NinePatchHandle, NinePatchTexelMapping, Texture, TextureHandle,
+        MaterialHandle, Mesh, MeshHandle, MeshRenderer, NinePatch, NinePatchHandle,
+        NinePatchTexelMapping, StandardMaterialTextures, TextRenderMode, Texture, TextureHandle,
         UIElementRenderer, UIElementSprite, UITextRenderer,
     },
+    image::{DynamicImage, Rgba, RgbaImage},
+    logging::{
+        log_debug,
+        transports::{ConsoleTransport, RingBufferTransport},
+        Logger, StandardLogLevel,
+    },
     math::{Quat, Vec2, Vec3},
-    object::{Object, ObjectHandle},
+    object::{Object, ObjectHandle, ObjectManager},
     object_event::{object_event_types, ObjectEventHandler},
-    specs::{Builder, WorldExt},
+    script::{load_script, ScriptComponent},
+    specs::{Builder, World, WorldExt},
     transform::{Transform, TransformComponent},
-    ui::{UIAnchor, UIElement, UIMargin, UIScaleMode, UIScaler, UISize},
+    ui::{
+        UIAnchor, UIElement, UIFillDirection, UIMargin, UIProgressBar, UIProgressBarComponent,
+        UIScaleMode, UIScaler, UISize, UISlider, UISliderComponent,
+    },
     use_context,
     wgpu::TextureFormat,
     ContextHandle, Engine, EngineConfig, EngineExecError, EngineInitError, EngineLoopMode,
     EngineTargetFps,
 };
-use std::mem::MaybeUninit;
+use std::{mem::MaybeUninit, sync::Arc};
 use thiserror::Error;
 
 mod assets;
+mod gizmo;
+mod hierarchy_panel;
+mod inspector_panel;
+mod selection;
+mod widgets;
 
 pub struct Application {
     pub camera: ObjectHandle,
     pub ui_root: ObjectHandle,
     pub ui_root_under: ObjectHandle,
     pub ui_text: ObjectHandle,
+    pub ui_progress_bar: ObjectHandle,
+    pub ui_slider: ObjectHandle,
+    pub ui_slider_label: ObjectHandle,
 }
 
 static mut APP: MaybeUninit<Application> = MaybeUninit::uninit();
@@ -46,21 +70,33 @@ pub enum Error {
 }
 
 fn main() -> Result<(), Error> {
+    // The global logger also feeds the console of the overlay, so that script errors show up in it.
+    let logs = Arc::new(RingBufferTransport::new(32));
+    let mut logger = Logger::new();
+    logger.wire(Arc::new(ConsoleTransport::new()));
+    logger.wire(logs.clone());
+    r3d::logging::set_global(logger).ok();
+
     let engine = Engine::new(EngineConfig {
         title: format!("{} v{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
         resizable: true,
         width: 800,
         height: 600,
+        glyph_atlas: Default::default(),
+        rng_seed: None,
+        settings_app_name: Some("r3d-editor".to_owned()),
+        restore_window_state: true,
+        gfx: Default::default(),
     })
     .block_on()?;
 
-    init(engine.context());
+    init(engine.context(), logs);
 
     engine.run(EngineLoopMode::Poll, EngineTargetFps::VSync)?;
     Ok(())
 }
 
-fn init(ctx: ContextHandle) {
+fn init(ctx: ContextHandle, logs: Arc<RingBufferTransport<StandardLogLevel>>) {
     let camera_component = Camera::new(
         0xFFFF_FFFF,
         0,
@@ -84,7 +120,48 @@ fn init(ctx: ContextHandle) {
     let mut world = ctx.world_mut();
     let (camera, builder) =
         object_mgr.create_object_builder(&mut world, Some("camera".to_owned()), None);
-    builder.with(camera_component).build();
+    // Drag to orbit, middle-drag to pan and scroll to zoom around the origin.
+    builder
+        .with(camera_component)
+        .with(OrbitCameraController::new(Vec3::ZERO, 5.0))
+        .build();
+
+    // A few objects to select and edit with the gizmo.
+    let white = TextureHandle::new(Texture::from_image(
+        TextureFormat::Rgba8UnormSrgb,
+        &DynamicImage::ImageRgba8(RgbaImage::from_pixel(1, 1, Rgba([255, 255, 255, 255]))),
+        &ctx.gfx_ctx().device,
+        &ctx.gfx_ctx().queue,
+    ));
+    let scene_material = {
+        let mut material = StandardMaterialTextures::new(white).create_material(false);
+        material.flush_uniforms(&ctx.gfx_ctx().device, &ctx.gfx_ctx().queue);
+        material.update_bind_group(&ctx.gfx_ctx().device);
+        MaterialHandle::new(material)
+    };
+
+    for (name, mesh, position) in [
+        ("cube", Mesh::cube(1.0), Vec3::new(-1.5, 0.0, 0.0)),
+        (
+            "sphere",
+            Mesh::uv_sphere(0.5, 16, 32),
+            Vec3::new(1.5, 0.0, 0.0),
+        ),
+    ] {
+        let mut renderer = MeshRenderer::new();
+        renderer.set_material(scene_material.clone());
+        renderer.set_mesh(MeshHandle::new(mesh), &ctx.gfx_ctx().device);
+
+        let (_, builder) = object_mgr.create_object_builder(
+            &mut world,
+            Some(name.to_owned()),
+            Some(Transform {
+                position,
+                ..Transform::new()
+            }),
+        );
+        builder.with(renderer).build();
+    }
 
     let (ui_root, builder) =
         object_mgr.create_object_builder(&mut world, Some("ui-root".to_owned()), None);
@@ -93,10 +170,7 @@ fn init(ctx: ContextHandle) {
             mode: UIScaleMode::Stretch,
             reference_size: Vec2::new(800.0, 600.0),
         })
-        .with(UISize {
-            width: 0.0,
-            height: 0.0,
-        })
+        .with(UISize::new())
         .build();
 
     let texture = TextureHandle::new(Texture::from_image(
@@ -108,22 +182,13 @@ fn init(ctx: ContextHandle) {
         &ctx.gfx_ctx().queue,
     ));
     let nine_patch = NinePatchHandle::new(NinePatch::new(
-        texture.clone(),
-        NinePatchTexelMapping::new(
-            0,
-            20,
-            texture.width - 20,
-            texture.width,
-            0,
-            20,
-            texture.height - 20,
-            texture.height,
-        ),
+        &texture,
+        NinePatchTexelMapping::from_borders(texture.width, texture.height, 20, 20, 20, 20).unwrap(),
     ));
     let mut ui_element_renderer = UIElementRenderer::new();
     ui_element_renderer.set_material(MATERIAL_SPRITE.clone());
     ui_element_renderer.set_sprite(
-        UIElementSprite::nine_patch(nine_patch),
+        UIElementSprite::nine_patch(nine_patch.clone()),
         &ctx.gfx_ctx().device,
         ctx.render_mgr_mut().bind_group_layout_cache(),
     );
@@ -135,11 +200,9 @@ fn init(ctx: ContextHandle) {
             anchor: UIAnchor::new(Vec2::ZERO, Vec2::ONE * 0.5f32),
             margin: UIMargin::zero(),
             is_interactable: true,
+            ..Default::default()
         })
-        .with(UISize {
-            width: 0.0,
-            height: 0.0,
-        })
+        .with(UISize::new())
         .with(ui_element_renderer)
         .build();
 
@@ -165,11 +228,9 @@ fn init(ctx: ContextHandle) {
             anchor: UIAnchor::full(),
             margin: UIMargin::zero(),
             is_interactable: false,
+            ..Default::default()
         })
-        .with(UISize {
-            width: 0.0,
-            height: 0.0,
-        })
+        .with(UISize::new())
         .with(ui_text_renderer)
         .build();
 
@@ -177,8 +238,192 @@ fn init(ctx: ContextHandle) {
         .object_hierarchy_mut()
         .set_parent(ui_text.object_id, Some(ui_root_under.object_id));
 
+    let ui_demo_panel = create_ui_element(
+        &mut object_mgr,
+        &mut world,
+        "ui-demo-panel",
+        &ui_root,
+        UIAnchor::new(Vec2::new(0.5, 0.0), Vec2::new(1.0, 0.5)),
+        UIMargin::new(20.0, 20.0, 20.0, 20.0),
+        Some((&nine_patch, Color::parse_hex("303030").unwrap())),
+    );
+
+    let ui_progress_bar = create_ui_element(
+        &mut object_mgr,
+        &mut world,
+        "ui-progress-bar",
+        &ui_demo_panel,
+        UIAnchor::new(Vec2::new(0.0, 0.7), Vec2::new(1.0, 0.7)),
+        UIMargin::new(20.0, 20.0, -10.0, -10.0),
+        Some((&nine_patch, Color::parse_hex("505050").unwrap())),
+    );
+    let ui_progress_bar_fill = create_ui_element(
+        &mut object_mgr,
+        &mut world,
+        "ui-progress-bar-fill",
+        &ui_progress_bar,
+        UIAnchor::full(),
+        UIMargin::zero(),
+        Some((&nine_patch, Color::parse_hex("3CB371").unwrap())),
+    );
+    world
+        .write_component::<UIProgressBar>()
+        .insert(
+            ui_progress_bar.entity,
+            UIProgressBar::new(ui_progress_bar_fill.object_id, UIFillDirection::LeftToRight),
+        )
+        .unwrap();
+
+    let ui_slider = create_ui_element(
+        &mut object_mgr,
+        &mut world,
+        "ui-slider",
+        &ui_demo_panel,
+        UIAnchor::new(Vec2::new(0.0, 0.4), Vec2::new(1.0, 0.4)),
+        UIMargin::new(20.0, 20.0, -10.0, -10.0),
+        Some((&nine_patch, Color::parse_hex("505050").unwrap())),
+    );
+    let ui_slider_fill = create_ui_element(
+        &mut object_mgr,
+        &mut world,
+        "ui-slider-fill",
+        &ui_slider,
+        UIAnchor::full(),
+        UIMargin::zero(),
+        Some((&nine_patch, Color::parse_hex("4682B4").unwrap())),
+    );
+    let ui_slider_handle = create_ui_element(
+        &mut object_mgr,
+        &mut world,
+        "ui-slider-handle",
+        &ui_slider,
+        UIAnchor::new(Vec2::ZERO, Vec2::new(0.0, 1.0)),
+        UIMargin::new(-10.0, -10.0, -5.0, -5.0),
+        Some((&nine_patch, Color::parse_hex("FFFFFF").unwrap())),
+    );
+    world
+        .write_component::<UIElement>()
+        .get_mut(ui_slider.entity)
+        .unwrap()
+        .is_interactable = true;
+    world
+        .write_component::<UIProgressBar>()
+        .insert(
+            ui_slider.entity,
+            UIProgressBar::new(ui_slider_fill.object_id, UIFillDirection::LeftToRight),
+        )
+        .unwrap();
+    world
+        .write_component::<UISlider>()
+        .insert(
+            ui_slider.entity,
+            UISlider::new(ui_slider_handle.object_id, 0.0, 100.0, 5.0),
+        )
+        .unwrap();
+
+    let ui_slider_label = create_ui_element(
+        &mut object_mgr,
+        &mut world,
+        "ui-slider-label",
+        &ui_demo_panel,
+        UIAnchor::new(Vec2::new(0.0, 0.1), Vec2::new(1.0, 0.3)),
+        UIMargin::zero(),
+        None,
+    );
+    let mut ui_slider_label_renderer = UITextRenderer::new();
+    ui_slider_label_renderer.with_config(|config| {
+        config.horizontal_align = HorizontalAlign::Center;
+        config.vertical_align = VerticalAlign::Middle;
+    });
+    ui_slider_label_renderer.set_color(Color::parse_hex("FFFFFF").unwrap());
+    ui_slider_label_renderer.set_font_size_with_recommended_values(24.0);
+    ui_slider_label_renderer.set_material(MATERIAL_GLYPH.clone());
+    ui_slider_label_renderer.set_font(FONT.clone());
+    ui_slider_label_renderer.set_text("value: 0".to_owned());
+    world
+        .write_component::<UITextRenderer>()
+        .insert(ui_slider_label.entity, ui_slider_label_renderer)
+        .unwrap();
+
+    // Compares the bitmap and the sdf render modes side by side.
+    let ui_text_comparison_panel = create_ui_element(
+        &mut object_mgr,
+        &mut world,
+        "ui-text-comparison-panel",
+        &ui_root,
+        UIAnchor::new(Vec2::new(0.0, 0.5), Vec2::new(0.5, 1.0)),
+        UIMargin::new(20.0, 20.0, 20.0, 20.0),
+        Some((&nine_patch, Color::parse_hex("303030").unwrap())),
+    );
+
+    let mut ui_text_bitmap_renderer = UITextRenderer::new();
+    ui_text_bitmap_renderer.set_render_mode(TextRenderMode::Bitmap);
+    ui_text_bitmap_renderer.set_material(MATERIAL_GLYPH_BITMAP.clone());
+    create_ui_text(
+        &mut object_mgr,
+        &mut world,
+        "ui-text-bitmap",
+        &ui_text_comparison_panel,
+        UIAnchor::new(Vec2::new(0.0, 0.0), Vec2::new(0.5, 1.0)),
+        ui_text_bitmap_renderer,
+    );
+
+    let mut ui_text_sdf_renderer = UITextRenderer::new();
+    ui_text_sdf_renderer.set_render_mode(TextRenderMode::Sdf { px_range: 1.0 });
+    ui_text_sdf_renderer.set_outline_color(Color::parse_hex("FF6060").unwrap());
+    ui_text_sdf_renderer.set_outline_width(0.1);
+    ui_text_sdf_renderer.set_material(MATERIAL_GLYPH_SDF.clone());
+    create_ui_text(
+        &mut object_mgr,
+        &mut world,
+        "ui-text-sdf",
+        &ui_text_comparison_panel,
+        UIAnchor::new(Vec2::new(0.5, 0.0), Vec2::new(1.0, 1.0)),
+        ui_text_sdf_renderer,
+    );
+
+    let rotate_script = load_script(&ctx, "r3d-editor/assets/scripts/rotate.lua").unwrap();
+    world
+        .write_component::<ScriptComponent>()
+        .insert(ui_text.entity, ScriptComponent::new(rotate_script))
+        .unwrap();
+
+    // The panels of the editor have their own root, so that the hierarchy panel can leave them out.
+    let (editor_ui_root, builder) =
+        object_mgr.create_object_builder(&mut world, Some("editor-ui-root".to_owned()), None);
+    builder
+        .with(UIScaler {
+            mode: UIScaleMode::Stretch,
+            reference_size: Vec2::new(800.0, 600.0),
+        })
+        .with(UISize::new())
+        .build();
+
+    drop(world);
+    drop(object_mgr);
+
+    let mut gizmo = TransformGizmo::new(camera.clone());
+    gizmo.set_undo_hook(EditLog);
+
+    let mut hierarchy_panel = HierarchyPanel::new(
+        &editor_ui_root,
+        &nine_patch,
+        [editor_ui_root.object_id, gizmo.root().object_id],
+    );
+    let mut inspector_panel = InspectorPanel::new(&editor_ui_root, &nine_patch);
+    inspector_panel.register::<OrbitCameraController>();
+
+    let slider = ui_slider.component::<UISliderComponent>();
+    slider.bind_drag_events();
+    slider.on_value_changed(|_, value| on_slider_value_changed(value));
+
     ctx.event_mgr()
-        .add_handler(EventHandler::<event_types::Update>::new(|_| update()));
+        .add_handler(EventHandler::<event_types::Update>::new(move |_| {
+            gizmo.update();
+            hierarchy_panel.update();
+            inspector_panel.update();
+            update()
+        }));
     ctx.event_mgr()
         .add_handler(EventHandler::<event_types::LateUpdate>::new(|_| {
             late_update()
@@ -201,18 +446,166 @@ fn init(ctx: ContextHandle) {
         ),
     );
 
+    // The overlay is created last, so that it is drawn over the other UI. Press F3 to toggle it.
+    let mut overlay_config = DebugOverlayConfig::new(
+        FONT.clone(),
+        MATERIAL_GLYPH.clone(),
+        MATERIAL_SPRITE.clone(),
+    );
+    overlay_config.logs = Some(logs);
+    DebugOverlay::install(&ctx, overlay_config);
+
     unsafe {
         APP = MaybeUninit::new(Application {
             camera,
             ui_root,
             ui_root_under,
             ui_text,
+            ui_progress_bar,
+            ui_slider,
+            ui_slider_label,
         });
     }
 }
 
+fn create_ui_element(
+    object_mgr: &mut ObjectManager,
+    world: &mut World,
+    name: &str,
+    parent: &ObjectHandle,
+    anchor: UIAnchor,
+    margin: UIMargin,
+    sprite: Option<(&NinePatchHandle, Color)>,
+) -> ObjectHandle {
+    let (object, builder) = object_mgr.create_object_builder(world, Some(name.to_owned()), None);
+    let builder = builder
+        .with(UIElement {
+            anchor,
+            margin,
+            is_interactable: false,
+            ..Default::default()
+        })
+        .with(UISize::new());
+
+    match sprite {
+        Some((nine_patch, color)) => {
+            let ctx = use_context();
+            let mut ui_element_renderer = UIElementRenderer::new();
+            ui_element_renderer.set_material(MATERIAL_SPRITE.clone());
+            ui_element_renderer.set_color(color);
+            ui_element_renderer.set_sprite(
+                UIElementSprite::nine_patch(nine_patch.clone()),
+                &ctx.gfx_ctx().device,
+                ctx.render_mgr_mut().bind_group_layout_cache(),
+            );
+            builder.with(ui_element_renderer).build();
+        }
+        None => {
+            builder.build();
+        }
+    }
+
+    object_mgr
+        .object_hierarchy_mut()
+        .set_parent(object.object_id, Some(parent.object_id));
+
+    object
+}
+
+fn create_ui_text(
+    object_mgr: &mut ObjectManager,
+    world: &mut World,
+    name: &str,
+    parent: &ObjectHandle,
+    anchor: UIAnchor,
+    mut renderer: UITextRenderer,
+) -> ObjectHandle {
+    let object = create_ui_element(
+        object_mgr,
+        world,
+        name,
+        parent,
+        anchor,
+        UIMargin::zero(),
+        None,
+    );
+
+    renderer.with_config(|config| {
+        config.horizontal_align = HorizontalAlign::Center;
+        config.vertical_align = VerticalAlign::Middle;
+    });
+    renderer.set_color(Color::parse_hex("FFFFFF").unwrap());
+    renderer.set_font_size_with_recommended_values(14.0);
+    renderer.set_font(FONT.clone());
+    renderer.set_text("The quick brown fox\njumps over the lazy dog".to_owned());
+    world
+        .write_component::<UITextRenderer>()
+        .insert(object.entity, renderer)
+        .unwrap();
+
+    object
+}
+
+/// Creates an empty single line label, for the editor panels.
+fn create_ui_label(
+    object_mgr: &mut ObjectManager,
+    world: &mut World,
+    name: &str,
+    parent: &ObjectHandle,
+    anchor: UIAnchor,
+    margin: UIMargin,
+    horizontal_align: HorizontalAlign,
+) -> ObjectHandle {
+    let object = create_ui_element(object_mgr, world, name, parent, anchor, margin, None);
+
+    let mut renderer = UITextRenderer::new();
+    renderer.with_config(|config| {
+        config.horizontal_align = horizontal_align;
+        config.vertical_align = VerticalAlign::Middle;
+    });
+    renderer.set_color(Color::parse_hex("E0E0E0").unwrap());
+    renderer.set_font_size_with_recommended_values(14.0);
+    renderer.set_material(MATERIAL_GLYPH.clone());
+    renderer.set_font(FONT.clone());
+    renderer.set_text(String::new());
+    world
+        .write_component::<UITextRenderer>()
+        .insert(object.entity, renderer)
+        .unwrap();
+
+    object
+}
+
+fn on_slider_value_changed(value: f32) {
+    let app = use_app();
+    app.ui_progress_bar
+        .component::<UIProgressBarComponent>()
+        .set_value(value / 100.0);
+
+    let world = use_context().world();
+    let mut text_renderers = world.write_component::<UITextRenderer>();
+    text_renderers
+        .get_mut(app.ui_slider_label.entity)
+        .unwrap()
+        .set_text(format!("value: {}", value));
+}
+
 fn update() {}
 
+/// Logs the edits of the gizmo at debug level. It stands in for the undo stack, which the editor does not have yet.
+struct EditLog;
+
+impl GizmoUndoHook for EditLog {
+    fn on_transform_edited(
+        &mut self,
+        object: &ObjectHandle,
+        before: &Transform,
+        after: &Transform,
+    ) {
+        log_debug!("edited {:?}: {:?} -> {:?}", object.name(), before, after);
+    }
+}
+
 fn late_update() {
     // let world = use_context().world();
     // let sizes = world.read_component::<UISize>();
@@ -0,0 +1,72 @@
+use r3d::{
+    event::{event_types, EventHandler},
+    object::ObjectHandle,
+    use_context,
+};
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+/// Dispatched when the object selected in the editor changes, e.g. by a click in the scene or in the hierarchy
+/// panel.
+#[derive(Clone)]
+pub struct SelectionChanged {
+    pub object: Option<ObjectHandle>,
+}
+
+/// Selects the object, or clears the selection. Everything that shows the selection is notified by
+/// [`SelectionChanged`].
+pub fn select(object: Option<ObjectHandle>) {
+    use_context()
+        .event_mgr()
+        .dispatch(&SelectionChanged { object });
+}
+
+/// Keeps the latest [`SelectionChanged`] until it is taken. The selection is usually changed while the UI
+/// events are dispatched, so the listeners apply it later in the update, when they are free to create or remove
+/// objects.
+pub struct SelectionListener {
+    pending: Rc<RefCell<Option<SelectionChanged>>>,
+}
+
+impl SelectionListener {
+    pub fn new() -> Self {
+        let pending = Rc::new(RefCell::new(None));
+        use_context()
+            .event_mgr()
+            .add_handler(EventHandler::<SelectionChanged>::new({
+                let pending = pending.clone();
+                move |event: &SelectionChanged| *pending.borrow_mut() = Some(event.clone())
+            }));
+        Self { pending }
+    }
+
+    pub fn take(&self) -> Option<SelectionChanged> {
+        self.pending.borrow_mut().take()
+    }
+}
+
+/// Raises a flag on [`HierarchyChanged`](event_types::HierarchyChanged), so that views of the hierarchy are
+/// refreshed only when it has changed.
+pub struct HierarchyListener {
+    is_changed: Rc<Cell<bool>>,
+}
+
+impl HierarchyListener {
+    pub fn new() -> Self {
+        // Starts raised, so that the first update shows the objects created so far.
+        let is_changed = Rc::new(Cell::new(true));
+        use_context()
+            .event_mgr()
+            .add_handler(EventHandler::<event_types::HierarchyChanged>::new({
+                let is_changed = is_changed.clone();
+                move |_| is_changed.set(true)
+            }));
+        Self { is_changed }
+    }
+
+    pub fn take(&self) -> bool {
+        self.is_changed.take()
+    }
+}
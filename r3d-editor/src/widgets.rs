@@ -0,0 +1,69 @@
+use r3d::{
+    gfx::{Color, UIElementRenderer, UITextRenderer},
+    object::ObjectHandle,
+    specs::{World, WorldExt},
+    ui::{UIElement, UIMargin},
+    use_context,
+};
+
+/// Makes the element receive the mouse events, e.g. to be clicked or dragged.
+pub fn make_interactable(world: &World, object: &ObjectHandle) {
+    if let Some(element) = world.write_component::<UIElement>().get_mut(object.entity) {
+        element.is_interactable = true;
+    }
+}
+
+/// Sets the text of the label, if it differs from the current one.
+pub fn set_text(object: &ObjectHandle, text: &str) {
+    let world = use_context().world();
+    let mut renderers = world.write_component::<UITextRenderer>();
+
+    if let Some(renderer) = renderers.get_mut(object.entity) {
+        if renderer.text().map(|current| current.as_str()) != Some(text) {
+            renderer.set_text(text.to_owned());
+        }
+    }
+}
+
+pub fn set_text_color(object: &ObjectHandle, color: Color) {
+    let world = use_context().world();
+    let mut renderers = world.write_component::<UITextRenderer>();
+
+    if let Some(renderer) = renderers.get_mut(object.entity) {
+        renderer.set_color(color);
+    }
+}
+
+pub fn set_color(object: &ObjectHandle, color: Color) {
+    let world = use_context().world();
+    let mut renderers = world.write_component::<UIElementRenderer>();
+
+    if let Some(renderer) = renderers.get_mut(object.entity) {
+        renderer.set_color(color);
+    }
+}
+
+/// Sets the margin of the element and marks it dirty, so that it is laid out again.
+pub fn set_margin(object: &ObjectHandle, margin: UIMargin) {
+    let ctx = use_context();
+
+    {
+        let world = ctx.world();
+        let mut elements = world.write_component::<UIElement>();
+
+        match elements.get_mut(object.entity) {
+            Some(element) if element.margin != margin => element.margin = margin,
+            _ => return,
+        }
+    }
+
+    ctx.object_mgr_mut()
+        .object_hierarchy_mut()
+        .set_dirty(object.object_id);
+}
+
+pub fn set_active(object: &ObjectHandle, is_active: bool) {
+    if object.is_active_self() != is_active {
+        object.set_active(is_active);
+    }
+}
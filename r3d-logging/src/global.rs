@@ -0,0 +1,47 @@
+use crate::{Logger, StandardLogLevel};
+use std::sync::OnceLock;
+
+static GLOBAL: OnceLock<Logger<StandardLogLevel>> = OnceLock::new();
+
+/// Installs the global logger, which the logging macros use when no logger is given.
+/// It can be installed only once; otherwise the logger is given back.
+pub fn set_global(logger: Logger<StandardLogLevel>) -> Result<(), Logger<StandardLogLevel>> {
+    GLOBAL.set(logger)
+}
+
+/// Returns the global logger, or `None` if it has not been installed yet.
+pub fn global() -> Option<&'static Logger<StandardLogLevel>> {
+    GLOBAL.get()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{transports::*, *};
+    use std::sync::Arc;
+
+    #[test]
+    fn it_should_log_to_global_logger() {
+        let mut logger = Logger::new();
+        let transport = Arc::new(RingBufferTransport::new(8));
+        logger.wire(transport.clone());
+
+        assert!(set_global(logger).is_ok());
+        assert!(set_global(Logger::new()).is_err());
+
+        log_info!("{} {}", "global", 42);
+        log_warn!(global().unwrap(), "explicit");
+
+        let logs = transport
+            .logs()
+            .into_iter()
+            .map(|log| (log.level, log.message))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            logs,
+            vec![
+                (StandardLogLevel::Info, "global 42".to_owned()),
+                (StandardLogLevel::Warning, "explicit".to_owned())
+            ]
+        );
+    }
+}
@@ -3,13 +3,20 @@ use colored::Color;
 use std::{fmt::Display, sync::Arc};
 use uuid::Uuid;
 
+mod global;
+mod macros;
 pub mod transports;
 
+pub use global::*;
+
 pub trait LogLevel
 where
-    Self: 'static + Clone + PartialEq + Eq + Display,
+    Self: 'static + Clone + PartialEq + Eq + Display + Send + Sync,
 {
     fn color(&self) -> Color;
+
+    /// Returns how severe the level is. Higher is more severe; it is used to filter logs by a minimum level.
+    fn severity(&self) -> u8;
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -31,6 +38,16 @@ impl LogLevel for StandardLogLevel {
             StandardLogLevel::Fatal => Color::BrightRed,
         }
     }
+
+    fn severity(&self) -> u8 {
+        match self {
+            StandardLogLevel::Debug => 0,
+            StandardLogLevel::Info => 1,
+            StandardLogLevel::Warning => 2,
+            StandardLogLevel::Error => 3,
+            StandardLogLevel::Fatal => 4,
+        }
+    }
 }
 
 impl Display for StandardLogLevel {
@@ -90,11 +107,24 @@ impl<L: LogLevel> Logger<L> {
             transport.forward(&log);
         }
     }
+
+    /// Returns `true` if any of the transports may forward logs of the level.
+    pub fn accepts(&self, level: &L) -> bool {
+        self.transports
+            .iter()
+            .any(|transport| transport.accepts(level))
+    }
 }
 
-pub trait Transport<L: LogLevel> {
+pub trait Transport<L: LogLevel>: Send + Sync {
     fn id(&self) -> Uuid;
     fn forward(&self, log: &Log<L>);
+
+    /// Returns `true` if the transport may forward logs of the level.
+    /// Loggers use it to skip formatting messages that no transport would forward.
+    fn accepts(&self, _level: &L) -> bool {
+        true
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,125 @@
+/// Logs at the level, formatting the message only if a transport of the logger accepts the level.
+/// Without a logger, it logs to the [global](crate::global) logger if one is installed.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log {
+    (@global $level:expr, $($arg:tt)+) => {{
+        if let ::std::option::Option::Some(logger) = $crate::global() {
+            $crate::__log!(logger, $level, $($arg)+);
+        }
+    }};
+    ($logger:expr, $level:expr, $($arg:tt)+) => {{
+        let logger = &$logger;
+        let level = $level;
+
+        if logger.accepts(&level) {
+            logger.log(level, ::std::format!($($arg)+));
+        }
+    }};
+}
+
+/// Logs a [`Debug`](crate::StandardLogLevel::Debug) message with `format!`-style arguments.
+/// Without a logger, it logs to the global logger.
+#[macro_export]
+macro_rules! log_debug {
+    ($fmt:literal $($arg:tt)*) => {
+        $crate::__log!(@global $crate::StandardLogLevel::Debug, $fmt $($arg)*)
+    };
+    ($logger:expr, $($arg:tt)+) => {
+        $crate::__log!($logger, $crate::StandardLogLevel::Debug, $($arg)+)
+    };
+}
+
+/// Logs an [`Info`](crate::StandardLogLevel::Info) message with `format!`-style arguments.
+/// Without a logger, it logs to the global logger.
+#[macro_export]
+macro_rules! log_info {
+    ($fmt:literal $($arg:tt)*) => {
+        $crate::__log!(@global $crate::StandardLogLevel::Info, $fmt $($arg)*)
+    };
+    ($logger:expr, $($arg:tt)+) => {
+        $crate::__log!($logger, $crate::StandardLogLevel::Info, $($arg)+)
+    };
+}
+
+/// Logs a [`Warning`](crate::StandardLogLevel::Warning) message with `format!`-style arguments.
+/// Without a logger, it logs to the global logger.
+#[macro_export]
+macro_rules! log_warn {
+    ($fmt:literal $($arg:tt)*) => {
+        $crate::__log!(@global $crate::StandardLogLevel::Warning, $fmt $($arg)*)
+    };
+    ($logger:expr, $($arg:tt)+) => {
+        $crate::__log!($logger, $crate::StandardLogLevel::Warning, $($arg)+)
+    };
+}
+
+/// Logs an [`Error`](crate::StandardLogLevel::Error) message with `format!`-style arguments.
+/// Without a logger, it logs to the global logger.
+#[macro_export]
+macro_rules! log_error {
+    ($fmt:literal $($arg:tt)*) => {
+        $crate::__log!(@global $crate::StandardLogLevel::Error, $fmt $($arg)*)
+    };
+    ($logger:expr, $($arg:tt)+) => {
+        $crate::__log!($logger, $crate::StandardLogLevel::Error, $($arg)+)
+    };
+}
+
+/// Logs a [`Fatal`](crate::StandardLogLevel::Fatal) message with `format!`-style arguments.
+/// Without a logger, it logs to the global logger.
+#[macro_export]
+macro_rules! log_fatal {
+    ($fmt:literal $($arg:tt)*) => {
+        $crate::__log!(@global $crate::StandardLogLevel::Fatal, $fmt $($arg)*)
+    };
+    ($logger:expr, $($arg:tt)+) => {
+        $crate::__log!($logger, $crate::StandardLogLevel::Fatal, $($arg)+)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{transports::*, *};
+    use std::{cell::Cell, fmt::Display, sync::Arc};
+
+    struct CountFormats<'a>(&'a Cell<u32>);
+
+    impl Display for CountFormats<'_> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            self.0.set(self.0.get() + 1);
+            write!(f, "formatted")
+        }
+    }
+
+    #[test]
+    fn it_should_forward_formatted_messages() {
+        let mut logger = Logger::new();
+        let mut filter =
+            FilterTransport::new(vec![StandardLogLevel::Warning, StandardLogLevel::Error]);
+        let transport = Arc::new(RingBufferTransport::new(8));
+        let formats = Cell::new(0);
+
+        filter.wire(transport.clone());
+        logger.wire(Arc::new(filter));
+
+        log_info!(logger, "{} {}", "filtered", CountFormats(&formats));
+        log_warn!(logger, "{} {}", 42, CountFormats(&formats));
+        log_error!(logger, "error: {}", "message");
+
+        let logs = transport
+            .logs()
+            .into_iter()
+            .map(|log| (log.level, log.message))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            logs,
+            vec![
+                (StandardLogLevel::Warning, "42 formatted".to_owned()),
+                (StandardLogLevel::Error, "error: message".to_owned())
+            ]
+        );
+        // The filtered message is never formatted.
+        assert_eq!(formats.get(), 1);
+    }
+}
@@ -3,10 +3,12 @@ use chrono::{DateTime, Datelike, Local, Timelike, Utc};
 mod console_transport;
 mod file_transport;
 mod filter_transport;
+mod ring_buffer_transport;
 
 pub use console_transport::*;
 pub use file_transport::*;
 pub use filter_transport::*;
+pub use ring_buffer_transport::*;
 
 fn format_timestamp(timestamp: DateTime<Utc>) -> String {
     let local = timestamp.with_timezone(&Local);
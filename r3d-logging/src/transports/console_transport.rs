@@ -1,16 +1,77 @@
-use super::format_timestamp;
 use crate::{Log, LogLevel, Transport};
-use colored::Colorize;
+use chrono::Local;
+use std::io::{IsTerminal, Write};
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColorMode {
+    /// Colors the output only if stdout is a terminal, e.g. not when it is piped or captured by CI.
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ConsoleOptions {
+    pub color: ColorMode,
+    /// The format of the timestamp in local time, in the syntax of [`chrono::format::strftime`].
+    pub timestamp_format: String,
+}
+
+impl Default for ConsoleOptions {
+    fn default() -> Self {
+        Self {
+            color: ColorMode::Auto,
+            // ISO 8601
+            timestamp_format: "%Y-%m-%dT%H:%M:%S%.3f%:z".to_owned(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ConsoleTransport {
     id: Uuid,
+    use_color: bool,
+    timestamp_format: String,
 }
 
 impl ConsoleTransport {
     pub fn new() -> Self {
-        Self { id: Uuid::new_v4() }
+        Self::with_options(ConsoleOptions::default())
+    }
+
+    pub fn with_options(options: ConsoleOptions) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            use_color: match options.color {
+                ColorMode::Auto => std::io::stdout().is_terminal(),
+                ColorMode::Always => true,
+                ColorMode::Never => false,
+            },
+            timestamp_format: options.timestamp_format,
+        }
+    }
+
+    fn write<L: LogLevel>(&self, out: &mut impl Write, log: &Log<L>) -> std::io::Result<()> {
+        let timestamp = log
+            .timestamp
+            .with_timezone(&Local)
+            .format(&self.timestamp_format);
+        let level = format!("{}", log.level);
+        let message = log.message.split('\n').collect::<Vec<_>>().join("\n\t");
+
+        if self.use_color {
+            // The escapes are written directly, as `colored` drops them when stdout is not a terminal or
+            // `NO_COLOR` is set, which defeats `ColorMode::Always`.
+            let color = log.level.color().to_fg_str();
+            writeln!(
+                out,
+                "[{}] \x1b[{}m{}\x1b[0m \x1b[{}m{}\x1b[0m",
+                timestamp, color, level, color, message
+            )
+        } else {
+            writeln!(out, "[{}] {} {}", timestamp, level, message)
+        }
     }
 }
 
@@ -20,16 +81,7 @@ impl<L: LogLevel> Transport<L> for ConsoleTransport {
     }
 
     fn forward(&self, log: &Log<L>) {
-        let timestamp = format_timestamp(log.timestamp);
-        let level = <_ as Colorize>::color(format!("{}", log.level).as_str(), log.level.color());
-        let message = log
-            .message
-            .split('\n')
-            .collect::<Vec<_>>()
-            .join("\n\t")
-            .color(log.level.color());
-
-        println!("[{}] {} {}", timestamp, level, message);
+        self.write(&mut std::io::stdout().lock(), log).ok();
     }
 }
 
@@ -66,4 +118,55 @@ mod tests {
             "Some fatal message\nwith multiple lines",
         );
     }
+
+    #[test]
+    fn it_should_color_if_forced() {
+        let transport = ConsoleTransport::with_options(ConsoleOptions {
+            color: ColorMode::Always,
+            timestamp_format: "%Y".to_owned(),
+        });
+        let log = Log {
+            level: StandardLogLevel::Error,
+            message: "Some error message".to_owned(),
+            timestamp: chrono::Utc::now(),
+        };
+        let mut out = Vec::new();
+
+        transport.write(&mut out, &log).unwrap();
+
+        // The output is captured, so it is not a terminal.
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!(
+                "[{}] \x1b[31mERROR\x1b[0m \x1b[31mSome error message\x1b[0m\n",
+                log.timestamp.with_timezone(&Local).format("%Y")
+            )
+        );
+    }
+
+    #[test]
+    fn it_should_not_color_if_disabled() {
+        let transport = ConsoleTransport::with_options(ConsoleOptions {
+            color: ColorMode::Never,
+            timestamp_format: "%Y".to_owned(),
+        });
+        let log = Log {
+            level: StandardLogLevel::Error,
+            message: "Some error message\nwith multiple lines".to_owned(),
+            timestamp: chrono::Utc::now(),
+        };
+        let mut out = Vec::new();
+
+        transport.write(&mut out, &log).unwrap();
+
+        let out = String::from_utf8(out).unwrap();
+        assert!(!out.contains('\x1b'));
+        assert_eq!(
+            out,
+            format!(
+                "[{}] ERROR Some error message\n\twith multiple lines\n",
+                log.timestamp.with_timezone(&Local).format("%Y")
+            )
+        );
+    }
 }
@@ -2,9 +2,11 @@ use crate::{Log, LogLevel, Transport};
 use std::sync::Arc;
 use uuid::Uuid;
 
+/// Forwards only the logs of the given levels, or of at least the given severity.
 pub struct FilterTransport<L: LogLevel> {
     id: Uuid,
     levels: Vec<L>,
+    min_level: Option<L>,
     transports: Vec<Arc<dyn Transport<L>>>,
 }
 
@@ -13,6 +15,17 @@ impl<L: LogLevel> FilterTransport<L> {
         Self {
             id: Uuid::new_v4(),
             levels,
+            min_level: None,
+            transports: Vec::new(),
+        }
+    }
+
+    /// Creates a filter that forwards the logs whose [severity](LogLevel::severity) is at least that of the level.
+    pub fn with_min_level(min_level: L) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            levels: Vec::new(),
+            min_level: Some(min_level),
             transports: Vec::new(),
         }
     }
@@ -21,6 +34,10 @@ impl<L: LogLevel> FilterTransport<L> {
         &self.levels
     }
 
+    pub fn min_level(&self) -> Option<&L> {
+        self.min_level.as_ref()
+    }
+
     pub fn wire(&mut self, transport: Arc<dyn Transport<L>>) {
         if self
             .transports
@@ -36,6 +53,14 @@ impl<L: LogLevel> FilterTransport<L> {
     pub fn unwire(&mut self, transport: Arc<dyn Transport<L>>) {
         self.transports.retain(|item| item.id() != transport.id());
     }
+
+    fn is_match(&self, level: &L) -> bool {
+        self.levels.contains(level)
+            || self
+                .min_level
+                .as_ref()
+                .is_some_and(|min_level| min_level.severity() <= level.severity())
+    }
 }
 
 impl<L: LogLevel> Transport<L> for FilterTransport<L> {
@@ -44,7 +69,7 @@ impl<L: LogLevel> Transport<L> for FilterTransport<L> {
     }
 
     fn forward(&self, log: &Log<L>) {
-        if !self.levels.contains(&log.level) {
+        if !self.is_match(&log.level) {
             return;
         }
 
@@ -52,4 +77,77 @@ impl<L: LogLevel> Transport<L> for FilterTransport<L> {
             transport.forward(log);
         }
     }
+
+    fn accepts(&self, level: &L) -> bool {
+        self.is_match(level)
+            && self
+                .transports
+                .iter()
+                .any(|transport| transport.accepts(level))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{transports::RingBufferTransport, Logger};
+    use colored::Color;
+    use std::fmt::Display;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum GameLogLevel {
+        Trace,
+        Notice,
+        Warning,
+        Crash,
+    }
+
+    impl LogLevel for GameLogLevel {
+        fn color(&self) -> Color {
+            Color::White
+        }
+
+        fn severity(&self) -> u8 {
+            match self {
+                GameLogLevel::Trace => 0,
+                GameLogLevel::Notice => 1,
+                GameLogLevel::Warning => 2,
+                GameLogLevel::Crash => 3,
+            }
+        }
+    }
+
+    impl Display for GameLogLevel {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self)
+        }
+    }
+
+    #[test]
+    fn it_should_filter_below_min_level() {
+        let mut logger = Logger::new();
+        let mut filter = FilterTransport::with_min_level(GameLogLevel::Warning);
+        let transport = Arc::new(RingBufferTransport::new(8));
+
+        filter.wire(transport.clone());
+        logger.wire(Arc::new(filter));
+
+        for level in [
+            GameLogLevel::Trace,
+            GameLogLevel::Notice,
+            GameLogLevel::Warning,
+            GameLogLevel::Crash,
+        ] {
+            logger.log(level, level.to_string());
+        }
+
+        let levels = transport
+            .logs()
+            .into_iter()
+            .map(|log| log.level)
+            .collect::<Vec<_>>();
+        assert_eq!(levels, vec![GameLogLevel::Warning, GameLogLevel::Crash]);
+        assert!(!logger.accepts(&GameLogLevel::Notice));
+        assert!(logger.accepts(&GameLogLevel::Crash));
+    }
 }
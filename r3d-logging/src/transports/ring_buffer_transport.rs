@@ -0,0 +1,80 @@
+use crate::{Log, LogLevel, Transport};
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use uuid::Uuid;
+
+/// Keeps the last logs in memory, e.g. to show them in an in-game console.
+pub struct RingBufferTransport<L: LogLevel> {
+    id: Uuid,
+    capacity: usize,
+    logs: Mutex<VecDeque<Log<L>>>,
+}
+
+impl<L: LogLevel> RingBufferTransport<L> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            capacity,
+            logs: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the kept logs, from the oldest to the newest.
+    pub fn logs(&self) -> Vec<Log<L>> {
+        self.logs.lock().iter().cloned().collect()
+    }
+
+    pub fn clear(&self) {
+        self.logs.lock().clear();
+    }
+}
+
+impl<L: LogLevel> Transport<L> for RingBufferTransport<L> {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn forward(&self, log: &Log<L>) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut logs = self.logs.lock();
+
+        if logs.len() == self.capacity {
+            logs.pop_front();
+        }
+
+        logs.push_back(log.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn it_should_keep_last_logs() {
+        let mut logger = Logger::new();
+        let transport = Arc::new(RingBufferTransport::new(2));
+
+        logger.wire(transport.clone());
+
+        logger.log(StandardLogLevel::Info, "first");
+        logger.log(StandardLogLevel::Warning, "second");
+        logger.log(StandardLogLevel::Error, "third");
+
+        let messages = transport
+            .logs()
+            .into_iter()
+            .map(|log| log.message)
+            .collect::<Vec<_>>();
+        assert_eq!(messages, vec!["second", "third"]);
+    }
+}
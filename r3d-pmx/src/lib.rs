@@ -19,15 +19,23 @@ use pmx_bone::PmxBone;
 use pmx_display::PmxDisplay;
 use pmx_header::PmxHeader;
 use pmx_joint::PmxJoint;
-use pmx_material::PmxMaterial;
-use pmx_morph::PmxMorph;
+use pmx_material::{PmxMaterial, PmxMaterialToonMode};
+use pmx_morph::{PmxMorph, PmxMorphOffset};
+use pmx_primitives::{PmxVec3, PmxVertexIndex};
 use pmx_rigidbody::PmxRigidbody;
 use pmx_surface::PmxSurface;
 use pmx_texture::PmxTexture;
 use pmx_vertex::PmxVertex;
-use std::fmt::Display;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    path::{Path, PathBuf},
+};
 use thiserror::Error;
 
+pub use pmx_material::{toon_texture_name, PmxToonTexture};
+pub use pmx_texture::PmxTextureResolveError;
+
 #[derive(Error, Debug)]
 pub enum PmxParseError {
     #[error("failed to parse PMX header: {0}")]
@@ -94,6 +102,189 @@ impl Pmx {
             joints,
         })
     }
+
+    /// Resolves the paths of the textures against the directory of the model, in the order of the textures.
+    /// See [`PmxTexture::resolve_path`] for details.
+    pub fn resolve_texture_paths(
+        &self,
+        base_dir: impl AsRef<Path>,
+    ) -> Vec<Result<PathBuf, PmxTextureResolveError>> {
+        self.textures
+            .iter()
+            .map(|texture| texture.resolve_path(base_dir.as_ref()))
+            .collect()
+    }
+
+    /// Merges the vertices whose attributes differ by at most `epsilon`, and rewrites the vertex indices
+    /// of the surfaces and the morphs. Returns the new index of each old vertex.
+    ///
+    /// Vertices that are referenced by vertex or UV morphs are never merged, since they may be offset differently.
+    pub fn weld_vertices(&mut self, epsilon: f32) -> Vec<u32> {
+        let epsilon = epsilon.max(0.0);
+        let mut pinned = HashSet::new();
+
+        for morph in &self.morphs {
+            match &morph.offset {
+                PmxMorphOffset::Vertex(offsets) => {
+                    pinned.extend(offsets.iter().map(|offset| offset.index.get()))
+                }
+                PmxMorphOffset::Uv { offsets, .. } => {
+                    pinned.extend(offsets.iter().map(|offset| offset.index.get()))
+                }
+                _ => {}
+            }
+        }
+
+        // Near vertices are in the same or adjacent cells, since the cells are at least `epsilon` wide.
+        let cell_size = if epsilon == 0.0 { 1.0 } else { epsilon };
+        let cell_of = |vertex: &PmxVertex| {
+            [
+                (vertex.position.x / cell_size).floor() as i64,
+                (vertex.position.y / cell_size).floor() as i64,
+                (vertex.position.z / cell_size).floor() as i64,
+            ]
+        };
+
+        let mut cells = HashMap::<[i64; 3], Vec<u32>>::new();
+        let mut vertices = Vec::with_capacity(self.vertices.len());
+        let mut remap = Vec::with_capacity(self.vertices.len());
+
+        for (index, vertex) in self.vertices.drain(..).enumerate() {
+            let cell = cell_of(&vertex);
+            let is_pinned = pinned.contains(&(index as u32));
+
+            let existing = if is_pinned {
+                None
+            } else {
+                adjacent_cells(cell)
+                    .filter_map(|cell| cells.get(&cell))
+                    .flatten()
+                    .copied()
+                    .find(|&candidate| vertex.is_near(&vertices[candidate as usize], epsilon))
+            };
+
+            match existing {
+                Some(candidate) => remap.push(candidate),
+                None => {
+                    let new_index = vertices.len() as u32;
+
+                    if !is_pinned {
+                        cells.entry(cell).or_default().push(new_index);
+                    }
+
+                    vertices.push(vertex);
+                    remap.push(new_index);
+                }
+            }
+        }
+
+        self.vertices = vertices;
+
+        let remap_index =
+            |index: &mut PmxVertexIndex| *index = PmxVertexIndex::new(remap[index.get() as usize]);
+
+        for surface in &mut self.surfaces {
+            surface.vertex_indices.iter_mut().for_each(remap_index);
+        }
+
+        for morph in &mut self.morphs {
+            match &mut morph.offset {
+                PmxMorphOffset::Vertex(offsets) => offsets
+                    .iter_mut()
+                    .for_each(|offset| remap_index(&mut offset.index)),
+                PmxMorphOffset::Uv { offsets, .. } => offsets
+                    .iter_mut()
+                    .for_each(|offset| remap_index(&mut offset.index)),
+                _ => {}
+            }
+        }
+
+        remap
+    }
+
+    /// Returns the indices of the surfaces whose geometric normal, derived from the CW winding, points away
+    /// from the averaged normals of their vertices. Such surfaces are culled and render as holes.
+    /// Degenerate surfaces and surfaces without a meaningful vertex normal are skipped.
+    pub fn check_winding(&self) -> Vec<usize> {
+        self.surfaces
+            .iter()
+            .enumerate()
+            .filter(|(_, surface)| self.is_reversed(surface))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Flips the winding of the surfaces that [`Pmx::check_winding`] reports, and returns their indices.
+    pub fn fix_winding(&mut self) -> Vec<usize> {
+        let reversed = self.check_winding();
+
+        for &index in &reversed {
+            self.surfaces[index].vertex_indices.swap(1, 2);
+        }
+
+        reversed
+    }
+
+    fn is_reversed(&self, surface: &PmxSurface) -> bool {
+        let vertices = match surface
+            .vertex_indices
+            .map(|index| self.vertices.get(index.get() as usize))
+        {
+            [Some(v0), Some(v1), Some(v2)] => [v0, v1, v2],
+            _ => return false,
+        };
+
+        let sub = |lhs: PmxVec3, rhs: PmxVec3| [lhs.x - rhs.x, lhs.y - rhs.y, lhs.z - rhs.z];
+        let edge_1 = sub(vertices[1].position, vertices[0].position);
+        let edge_2 = sub(vertices[2].position, vertices[0].position);
+        // With the left-handed coordinates of PMX, the cross product of a CW surface faces its front.
+        let geometric_normal = [
+            edge_1[1] * edge_2[2] - edge_1[2] * edge_2[1],
+            edge_1[2] * edge_2[0] - edge_1[0] * edge_2[2],
+            edge_1[0] * edge_2[1] - edge_1[1] * edge_2[0],
+        ];
+        let vertex_normal = vertices.iter().fold([0.0; 3], |sum, vertex| {
+            [
+                sum[0] + vertex.normal.x,
+                sum[1] + vertex.normal.y,
+                sum[2] + vertex.normal.z,
+            ]
+        });
+
+        let dot = geometric_normal[0] * vertex_normal[0]
+            + geometric_normal[1] * vertex_normal[1]
+            + geometric_normal[2] * vertex_normal[2];
+        dot < 0.0
+    }
+
+    /// Resolves the toon texture of the material. Shared toon textures are looked up in `shared_toon_dir`.
+    /// Returns `None` if the material has no valid toon texture.
+    pub fn resolve_toon(
+        &self,
+        material_index: usize,
+        shared_toon_dir: impl AsRef<Path>,
+    ) -> Option<PmxToonTexture<'_>> {
+        match self.materials.get(material_index)?.toon_mode {
+            PmxMaterialToonMode::Texture { index } => {
+                let index = usize::try_from(index.get()).ok()?;
+                let texture = self.textures.get(index)?;
+
+                Some(PmxToonTexture::Texture {
+                    index,
+                    path: &texture.path,
+                })
+            }
+            PmxMaterialToonMode::InternalTexture { index } => Some(PmxToonTexture::Shared(
+                shared_toon_dir.as_ref().join(toon_texture_name(index)?),
+            )),
+        }
+    }
+}
+
+fn adjacent_cells(cell: [i64; 3]) -> impl Iterator<Item = [i64; 3]> {
+    (-1..=1).flat_map(move |x| {
+        (-1..=1).flat_map(move |y| (-1..=1).map(move |z| [cell[0] + x, cell[1] + y, cell[2] + z]))
+    })
 }
 
 impl Display for Pmx {
@@ -127,3 +318,135 @@ impl Display for Pmx {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pmx_header::{PmxConfig, PmxIndexSize, PmxTextEncoding};
+    use pmx_primitives::{PmxBoneIndex, PmxVec2, PmxVec3, PmxVec4};
+    use pmx_vertex::PmxVertexDeformKind;
+
+    fn vertex(x: f32, bone_weight: f32) -> PmxVertex {
+        vertex_at([x, 0.0, 0.0], bone_weight)
+    }
+
+    fn vertex_at([x, y, z]: [f32; 3], bone_weight: f32) -> PmxVertex {
+        PmxVertex {
+            position: PmxVec3 { x, y, z },
+            normal: PmxVec3 {
+                x: 0.0,
+                y: 1.0,
+                z: 0.0,
+            },
+            uv: PmxVec2 { x: 0.0, y: 0.0 },
+            additional_vec4s: [PmxVec4 {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+                w: 0.0,
+            }; 4],
+            deform_kind: PmxVertexDeformKind::Bdef2 {
+                bone_index_1: PmxBoneIndex::new(0),
+                bone_index_2: PmxBoneIndex::new(1),
+                bone_weight,
+            },
+            edge_size: 1.0,
+        }
+    }
+
+    fn surface(indices: [u32; 3]) -> PmxSurface {
+        PmxSurface {
+            vertex_indices: indices.map(PmxVertexIndex::new),
+        }
+    }
+
+    fn pmx(vertices: Vec<PmxVertex>, surfaces: Vec<PmxSurface>) -> Pmx {
+        Pmx {
+            header: PmxHeader {
+                signature: *b"PMX ",
+                version: 2.0,
+                config: PmxConfig {
+                    text_encoding: PmxTextEncoding::Utf8,
+                    additional_vec4_count: 0,
+                    vertex_index_size: PmxIndexSize::U8,
+                    texture_index_size: PmxIndexSize::U8,
+                    material_index_size: PmxIndexSize::U8,
+                    bone_index_size: PmxIndexSize::U8,
+                    morph_index_size: PmxIndexSize::U8,
+                    rigidbody_index_size: PmxIndexSize::U8,
+                },
+                model_name_local: String::new(),
+                model_name_universal: String::new(),
+                model_comment_local: String::new(),
+                model_comment_universal: String::new(),
+            },
+            vertices,
+            surfaces,
+            textures: Vec::new(),
+            materials: Vec::new(),
+            bones: Vec::new(),
+            morphs: Vec::new(),
+            displays: Vec::new(),
+            rigidbodies: Vec::new(),
+            joints: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn it_should_weld_coincident_vertices() {
+        let mut pmx = pmx(
+            vec![
+                vertex(0.0, 0.5),
+                vertex(1.0, 0.5),
+                // Coincident with the first vertex, within the epsilon.
+                vertex(0.00001, 0.5),
+                // Same position as the first vertex, but deformed differently.
+                vertex(0.0, 0.25),
+                vertex(2.0, 0.5),
+            ],
+            vec![surface([0, 1, 4]), surface([2, 3, 4])],
+        );
+
+        let remap = pmx.weld_vertices(0.0001);
+
+        assert_eq!(remap, vec![0, 1, 0, 2, 3]);
+        assert_eq!(pmx.vertices.len(), 4);
+        assert_eq!(pmx.vertices[3].position.x, 2.0);
+        assert_eq!(
+            Vec::from_iter(
+                pmx.surfaces
+                    .iter()
+                    .map(|surface| surface.vertex_indices.map(|index| index.get()))
+            ),
+            vec![[0, 1, 3], [0, 2, 3]]
+        );
+    }
+
+    #[test]
+    fn it_should_detect_and_fix_reversed_surfaces() {
+        // Every vertex normal points up.
+        let mut pmx = pmx(
+            vec![
+                vertex_at([0.0, 0.0, 0.0], 0.5),
+                vertex_at([0.0, 0.0, 1.0], 0.5),
+                vertex_at([1.0, 0.0, 0.0], 0.5),
+                vertex_at([1.0, 0.0, 1.0], 0.5),
+            ],
+            vec![
+                surface([0, 1, 2]),
+                // Reversed; its front faces down.
+                surface([2, 3, 1]),
+                // Degenerate.
+                surface([0, 0, 1]),
+            ],
+        );
+
+        assert_eq!(pmx.check_winding(), vec![1]);
+        assert_eq!(pmx.fix_winding(), vec![1]);
+        assert_eq!(
+            pmx.surfaces[1].vertex_indices.map(|index| index.get()),
+            [2, 1, 3]
+        );
+        assert!(pmx.check_winding().is_empty());
+    }
+}
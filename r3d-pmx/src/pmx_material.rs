@@ -4,8 +4,29 @@ use crate::{
     pmx_header::PmxConfig,
     pmx_primitives::{PmxTextureIndex, PmxVec3, PmxVec4},
 };
+use std::path::PathBuf;
 use thiserror::Error;
 
+/// The file names of the shared toon textures, in the order of their internal indices.
+const TOON_TEXTURE_NAMES: [&str; 10] = [
+    "toon01.bmp",
+    "toon02.bmp",
+    "toon03.bmp",
+    "toon04.bmp",
+    "toon05.bmp",
+    "toon06.bmp",
+    "toon07.bmp",
+    "toon08.bmp",
+    "toon09.bmp",
+    "toon10.bmp",
+];
+
+/// Returns the file name of the shared toon texture that [`PmxMaterialToonMode::InternalTexture`] refers to,
+/// e.g. `toon01.bmp` for the index `0`.
+pub fn toon_texture_name(index: u8) -> Option<&'static str> {
+    TOON_TEXTURE_NAMES.get(index as usize).copied()
+}
+
 #[derive(Error, Debug)]
 pub enum PmxMaterialParseError {
     #[error("unexpected EOF detected")]
@@ -210,6 +231,15 @@ pub enum PmxMaterialToonMode {
     InternalTexture { index: u8 },
 }
 
+/// The toon texture of a material, resolved by [`Pmx::resolve_toon`](crate::Pmx::resolve_toon).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PmxToonTexture<'a> {
+    /// Refers to `textures[index]`, whose path is relative to the directory of the model.
+    Texture { index: usize, path: &'a str },
+    /// Refers to the shared toon texture in the shared toon directory.
+    Shared(PathBuf),
+}
+
 impl Parse for PmxMaterialToonMode {
     type Error = PmxMaterialParseError;
 
@@ -243,3 +273,16 @@ impl Parse for PmxMaterialToonMode {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_map_internal_toon_indices_to_names() {
+        assert_eq!(toon_texture_name(0), Some("toon01.bmp"));
+        assert_eq!(toon_texture_name(9), Some("toon10.bmp"));
+        assert_eq!(toon_texture_name(10), None);
+        assert_eq!(toon_texture_name(u8::MAX), None);
+    }
+}
@@ -3,6 +3,10 @@ use crate::{
     parse::{Parse, ParseError},
     pmx_header::PmxConfig,
 };
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -21,11 +25,91 @@ impl ParseError for PmxTextureParseError {
     }
 }
 
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum PmxTextureResolveError {
+    #[error("texture path is empty")]
+    EmptyPath,
+    #[error("texture not found: {0}")]
+    NotFound(PathBuf),
+}
+
 #[derive(Debug, Clone)]
 pub struct PmxTexture {
     pub path: String,
 }
 
+impl PmxTexture {
+    /// Resolves the path against the directory of the model. Both `\` and `/` are accepted as separators,
+    /// `..` segments are normalized, and each segment is matched case-insensitively if there is no exact match,
+    /// so that the returned path has the casing of the actual file.
+    pub fn resolve_path(
+        &self,
+        base_dir: impl AsRef<Path>,
+    ) -> Result<PathBuf, PmxTextureResolveError> {
+        let normalized = self.path.trim().replace('\\', "/");
+
+        if normalized.is_empty() {
+            return Err(PmxTextureResolveError::EmptyPath);
+        }
+
+        let mut resolved = if normalized.starts_with('/') {
+            PathBuf::from("/")
+        } else {
+            base_dir.as_ref().to_path_buf()
+        };
+
+        for segment in normalized.split('/') {
+            match segment {
+                "" | "." => {}
+                ".." => {
+                    if !resolved.pop() {
+                        resolved.push("..");
+                    }
+                }
+                segment => match find_entry(&resolved, segment) {
+                    Some(name) => resolved.push(name),
+                    None => resolved.push(segment),
+                },
+            }
+        }
+
+        if resolved.is_file() {
+            Ok(resolved)
+        } else {
+            Err(PmxTextureResolveError::NotFound(resolved))
+        }
+    }
+}
+
+/// Finds the entry of the directory with the name, preferring an exact match over a case-insensitive one.
+fn find_entry(dir: &Path, name: &str) -> Option<OsString> {
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    let lowercase_name = name.to_lowercase();
+    let mut case_insensitive_match = None;
+
+    for entry in dir.read_dir().ok()?.flatten() {
+        let entry_name = entry.file_name();
+
+        if entry_name == name {
+            return Some(entry_name);
+        }
+
+        if case_insensitive_match.is_none()
+            && entry_name
+                .to_str()
+                .is_some_and(|entry_name| entry_name.to_lowercase() == lowercase_name)
+        {
+            case_insensitive_match = Some(entry_name);
+        }
+    }
+
+    case_insensitive_match
+}
+
 impl Parse for PmxTexture {
     type Error = PmxTextureParseError;
 
@@ -55,3 +139,53 @@ impl Parse for Vec<PmxTexture> {
         Ok(textures)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn texture(path: &str) -> PmxTexture {
+        PmxTexture {
+            path: path.to_owned(),
+        }
+    }
+
+    #[test]
+    fn it_should_resolve_texture_paths() {
+        let root = std::env::temp_dir().join(format!("r3d-pmx-resolve-{}", std::process::id()));
+        let model_dir = root.join("model");
+        fs::create_dir_all(model_dir.join("tex")).unwrap();
+        fs::create_dir_all(root.join("Shared")).unwrap();
+        fs::write(model_dir.join("tex").join("face.png"), []).unwrap();
+        fs::write(root.join("Shared").join("toon01.bmp"), []).unwrap();
+
+        // Backslash separators.
+        assert_eq!(
+            texture("tex\\face.png").resolve_path(&model_dir),
+            Ok(model_dir.join("tex").join("face.png"))
+        );
+        // Mismatched casing resolves to the actual file.
+        assert_eq!(
+            texture("TEX\\Face.PNG").resolve_path(&model_dir),
+            Ok(model_dir.join("tex").join("face.png"))
+        );
+        // Parent segments are relative to the model directory.
+        assert_eq!(
+            texture(".\\tex\\..\\..\\shared/TOON01.bmp").resolve_path(&model_dir),
+            Ok(root.join("Shared").join("toon01.bmp"))
+        );
+        assert_eq!(
+            texture("tex\\body.png").resolve_path(&model_dir),
+            Err(PmxTextureResolveError::NotFound(
+                model_dir.join("tex").join("body.png")
+            ))
+        );
+        assert_eq!(
+            texture("").resolve_path(&model_dir),
+            Err(PmxTextureResolveError::EmptyPath)
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -35,6 +35,24 @@ pub struct PmxVertex {
     pub edge_size: f32,
 }
 
+impl PmxVertex {
+    /// Returns `true` if every attribute of the vertices differs by at most `epsilon`, including
+    /// the additional vec4s and the deform. Bone indices of the deforms must be identical.
+    pub fn is_near(&self, other: &Self, epsilon: f32) -> bool {
+        is_near_vec3(self.position, other.position, epsilon)
+            && is_near_vec3(self.normal, other.normal, epsilon)
+            && is_near(self.uv.x, other.uv.x, epsilon)
+            && is_near(self.uv.y, other.uv.y, epsilon)
+            && self
+                .additional_vec4s
+                .iter()
+                .zip(&other.additional_vec4s)
+                .all(|(&lhs, &rhs)| is_near_vec4(lhs, rhs, epsilon))
+            && self.deform_kind.is_near(&other.deform_kind, epsilon)
+            && is_near(self.edge_size, other.edge_size, epsilon)
+    }
+}
+
 impl Parse for PmxVertex {
     type Error = PmxVertexParseError;
 
@@ -129,6 +147,89 @@ pub enum PmxVertexDeformKind {
     },
 }
 
+impl PmxVertexDeformKind {
+    /// Returns `true` if the deforms are of the same kind with identical bone indices, and their weights
+    /// and SDEF parameters differ by at most `epsilon`.
+    pub fn is_near(&self, other: &Self, epsilon: f32) -> bool {
+        match (self, other) {
+            (Self::Bdef1 { bone_index: lhs }, Self::Bdef1 { bone_index: rhs }) => lhs == rhs,
+            (
+                Self::Bdef2 {
+                    bone_index_1: lhs_index_1,
+                    bone_index_2: lhs_index_2,
+                    bone_weight: lhs_weight,
+                },
+                Self::Bdef2 {
+                    bone_index_1: rhs_index_1,
+                    bone_index_2: rhs_index_2,
+                    bone_weight: rhs_weight,
+                },
+            ) => {
+                lhs_index_1 == rhs_index_1
+                    && lhs_index_2 == rhs_index_2
+                    && is_near(*lhs_weight, *rhs_weight, epsilon)
+            }
+            (
+                Self::Bdef4 {
+                    bone_index_1: lhs_index_1,
+                    bone_index_2: lhs_index_2,
+                    bone_index_3: lhs_index_3,
+                    bone_index_4: lhs_index_4,
+                    bone_weight_1: lhs_weight_1,
+                    bone_weight_2: lhs_weight_2,
+                    bone_weight_3: lhs_weight_3,
+                    bone_weight_4: lhs_weight_4,
+                },
+                Self::Bdef4 {
+                    bone_index_1: rhs_index_1,
+                    bone_index_2: rhs_index_2,
+                    bone_index_3: rhs_index_3,
+                    bone_index_4: rhs_index_4,
+                    bone_weight_1: rhs_weight_1,
+                    bone_weight_2: rhs_weight_2,
+                    bone_weight_3: rhs_weight_3,
+                    bone_weight_4: rhs_weight_4,
+                },
+            ) => {
+                lhs_index_1 == rhs_index_1
+                    && lhs_index_2 == rhs_index_2
+                    && lhs_index_3 == rhs_index_3
+                    && lhs_index_4 == rhs_index_4
+                    && is_near(*lhs_weight_1, *rhs_weight_1, epsilon)
+                    && is_near(*lhs_weight_2, *rhs_weight_2, epsilon)
+                    && is_near(*lhs_weight_3, *rhs_weight_3, epsilon)
+                    && is_near(*lhs_weight_4, *rhs_weight_4, epsilon)
+            }
+            (
+                Self::Sdef {
+                    bone_index_1: lhs_index_1,
+                    bone_index_2: lhs_index_2,
+                    bone_weight: lhs_weight,
+                    c: lhs_c,
+                    r0: lhs_r0,
+                    r1: lhs_r1,
+                },
+                Self::Sdef {
+                    bone_index_1: rhs_index_1,
+                    bone_index_2: rhs_index_2,
+                    bone_weight: rhs_weight,
+                    c: rhs_c,
+                    r0: rhs_r0,
+                    r1: rhs_r1,
+                },
+            ) => {
+                lhs_index_1 == rhs_index_1
+                    && lhs_index_2 == rhs_index_2
+                    && is_near(*lhs_weight, *rhs_weight, epsilon)
+                    && is_near_vec3(*lhs_c, *rhs_c, epsilon)
+                    && is_near_vec3(*lhs_r0, *rhs_r0, epsilon)
+                    && is_near_vec3(*lhs_r1, *rhs_r1, epsilon)
+            }
+            _ => false,
+        }
+    }
+}
+
 impl Parse for PmxVertexDeformKind {
     type Error = PmxVertexParseError;
 
@@ -220,3 +321,20 @@ impl Parse for PmxVertexDeformKind {
         })
     }
 }
+
+fn is_near(lhs: f32, rhs: f32, epsilon: f32) -> bool {
+    lhs == rhs || (lhs - rhs).abs() <= epsilon
+}
+
+fn is_near_vec3(lhs: PmxVec3, rhs: PmxVec3, epsilon: f32) -> bool {
+    is_near(lhs.x, rhs.x, epsilon)
+        && is_near(lhs.y, rhs.y, epsilon)
+        && is_near(lhs.z, rhs.z, epsilon)
+}
+
+fn is_near_vec4(lhs: PmxVec4, rhs: PmxVec4, epsilon: f32) -> bool {
+    is_near(lhs.x, rhs.x, epsilon)
+        && is_near(lhs.y, rhs.y, epsilon)
+        && is_near(lhs.z, rhs.z, epsilon)
+        && is_near(lhs.w, rhs.w, epsilon)
+}
@@ -0,0 +1,189 @@
+//! Renders a small UI into a browser canvas, which the engine appends to the page.
+//!
+//! ```sh
+//! wasm-pack build r3d-web-example --target web
+//! ```
+//!
+//! Then serve the `r3d-web-example` directory and open `index.html`. The scene is built by [`init`], which runs
+//! natively as well. `cargo check-wasm` checks the example and the engine core for the web without wasm-pack.
+
+use r3d::{
+    gfx::{
+        Camera, CameraClearMode, CameraProjection, Color, Material, MaterialBlendMode,
+        MaterialHandle, Sprite, SpriteHandle, SpriteTexelMapping, Texture, TextureHandle,
+        UIElementRenderer, UIElementSprite, BUILT_IN_SHADER_UI_ELEMENT_NORMAL,
+    },
+    image::{DynamicImage, Rgba, RgbaImage},
+    math::Vec2,
+    object::ObjectHandle,
+    specs::Builder,
+    tween::ObjectTween,
+    ui::{UIAnchor, UIElement, UIMargin, UIScaleMode, UIScaler, UISize},
+    wgpu::TextureFormat,
+    ContextHandle, EngineConfig,
+};
+use std::time::Duration;
+
+const EMPTY_BAR_MARGIN: UIMargin = UIMargin {
+    left: 20.0,
+    right: 380.0,
+    top: 20.0,
+    bottom: 20.0,
+};
+const FULL_BAR_MARGIN: UIMargin = UIMargin {
+    left: 20.0,
+    right: 20.0,
+    top: 20.0,
+    bottom: 20.0,
+};
+
+pub fn config() -> EngineConfig {
+    EngineConfig {
+        title: "web example".to_owned(),
+        resizable: true,
+        width: 800,
+        height: 600,
+        glyph_atlas: Default::default(),
+        rng_seed: None,
+        settings_app_name: None,
+        restore_window_state: false,
+        gfx: Default::default(),
+    }
+}
+
+/// Builds a panel with a bar that fills up over and over.
+pub fn init(ctx: &ContextHandle) {
+    let camera = Camera::new(
+        0xFFFF_FFFF,
+        0,
+        CameraClearMode::all(Color::parse_hex("141414").unwrap(), 1.0, 0),
+        CameraProjection::orthographic(8.0, -100.0, 100.0),
+        &ctx.gfx_ctx().device,
+        ctx.render_mgr_mut().bind_group_layout_cache(),
+    );
+    spawn(ctx, "camera", None, |builder| builder.with(camera));
+
+    let white = TextureHandle::new(Texture::from_image(
+        TextureFormat::Rgba8UnormSrgb,
+        &DynamicImage::ImageRgba8(RgbaImage::from_pixel(1, 1, Rgba([255, 255, 255, 255]))),
+        &ctx.gfx_ctx().device,
+        &ctx.gfx_ctx().queue,
+    ));
+    let white = SpriteHandle::new(Sprite::new(white, SpriteTexelMapping::new(0, 1, 0, 1)));
+    let material = {
+        let mut material = Material::new(
+            ctx.built_in_shader_mgr()
+                .find_shader(BUILT_IN_SHADER_UI_ELEMENT_NORMAL)
+                .unwrap(),
+            ctx.render_mgr_mut().pipeline_layout_cache(),
+        );
+        material.blend_mode = Some(MaterialBlendMode::Alpha);
+        MaterialHandle::new(material)
+    };
+    let renderer = |color: Color| {
+        let mut renderer = UIElementRenderer::new();
+        renderer.set_material(material.clone());
+        renderer.set_color(color);
+        renderer.set_sprite(
+            UIElementSprite::sprite(white.clone()),
+            &ctx.gfx_ctx().device,
+            ctx.render_mgr_mut().bind_group_layout_cache(),
+        );
+        renderer
+    };
+
+    let root = spawn(ctx, "ui-root", None, |builder| {
+        builder
+            .with(UIScaler {
+                mode: UIScaleMode::Stretch,
+                reference_size: Vec2::new(800.0, 600.0),
+            })
+            .with(UISize::new())
+    });
+    let panel = spawn(ctx, "ui-panel", Some(&root), |builder| {
+        builder
+            .with(UIElement {
+                anchor: UIAnchor::new(Vec2::new(0.25, 0.4), Vec2::new(0.75, 0.6)),
+                margin: UIMargin::zero(),
+                is_interactable: false,
+                ..Default::default()
+            })
+            .with(UISize::new())
+            .with(renderer(Color::parse_hex("303030").unwrap()))
+    });
+    let bar = spawn(ctx, "ui-bar", Some(&panel), |builder| {
+        builder
+            .with(UIElement {
+                anchor: UIAnchor::full(),
+                margin: EMPTY_BAR_MARGIN,
+                is_interactable: false,
+                ..Default::default()
+            })
+            .with(UISize::new())
+            .with(renderer(Color::parse_hex("4fa3e0").unwrap()))
+    });
+
+    fill(bar);
+}
+
+/// Fills the bar, then empties it and starts over.
+fn fill(bar: ObjectHandle) {
+    ObjectTween::new(bar.clone())
+        .margin_to(FULL_BAR_MARGIN, Duration::from_secs(2))
+        .on_complete(move || empty(bar))
+        .start();
+}
+
+fn empty(bar: ObjectHandle) {
+    ObjectTween::new(bar.clone())
+        .margin_to(EMPTY_BAR_MARGIN, Duration::from_millis(300))
+        .on_complete(move || fill(bar))
+        .start();
+}
+
+fn spawn(
+    ctx: &ContextHandle,
+    name: &str,
+    parent: Option<&ObjectHandle>,
+    build: impl FnOnce(r3d::specs::EntityBuilder<'_>) -> r3d::specs::EntityBuilder<'_>,
+) -> ObjectHandle {
+    let mut object_mgr = ctx.object_mgr_mut();
+    let mut world = ctx.world_mut();
+    let (object, builder) =
+        object_mgr.create_object_builder(&mut world, Some(name.to_owned()), None);
+    build(builder).build();
+
+    if let Some(parent) = parent {
+        object_mgr
+            .object_hierarchy_mut()
+            .set_parent(object.object_id, Some(parent.object_id));
+    }
+
+    object
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen::prelude::wasm_bindgen(start)]
+pub fn start() {
+    use r3d::{logging::log_error, Engine, EngineLoopMode, EngineTargetFps};
+
+    wasm_bindgen_futures::spawn_local(async {
+        let engine = match Engine::new(config()).await {
+            Ok(engine) => engine,
+            Err(err) => {
+                log_error!("failed to create the engine: {}", err);
+                return;
+            }
+        };
+
+        init(&engine.context());
+
+        // The browser keeps driving the loop after this returns.
+        if let Err(err) = engine.run(
+            EngineLoopMode::Adaptive { idle_frames: 60 },
+            EngineTargetFps::VSync,
+        ) {
+            log_error!("failed to run the engine: {}", err);
+        }
+    });
+}
@@ -0,0 +1,105 @@
+use super::{look_direction, CameraControllerInput, ControllerButton, MAX_PITCH};
+use crate::{
+    math::{Quat, Vec3},
+    transform::Transform,
+};
+use codegen::Component;
+use specs::prelude::*;
+
+/// Moves the object like a free-flying camera.
+///
+/// `W`, `A`, `S` and `D` move along the view, `Q` and `E` move down and up, and shift speeds up. Holding the right
+/// mouse button locks the cursor and looks around. Scrolling changes the speed.
+#[derive(Debug, Clone, Component)]
+#[storage(HashMapStorage)]
+#[auto_register]
+pub struct FlyCameraController {
+    /// Units per second.
+    pub speed: f32,
+    pub min_speed: f32,
+    pub max_speed: f32,
+    /// The speed is multiplied by this while shift is held.
+    pub boost_multiplier: f32,
+    /// The speed is multiplied by this for every scroll step.
+    pub scroll_speed_step: f32,
+    /// Radians per unit of mouse motion.
+    pub look_sensitivity: f32,
+    yaw: f32,
+    pitch: f32,
+    is_initialized: bool,
+    look: ControllerButton,
+}
+
+impl FlyCameraController {
+    pub fn new() -> Self {
+        Self {
+            speed: 5.0,
+            min_speed: 0.1,
+            max_speed: 1000.0,
+            boost_multiplier: 4.0,
+            scroll_speed_step: 1.1,
+            look_sensitivity: 0.003,
+            yaw: 0.0,
+            pitch: 0.0,
+            is_initialized: false,
+            look: ControllerButton::default(),
+        }
+    }
+
+    /// Returns `true` while the controller is looking around, which is when the cursor should be locked.
+    pub fn is_looking(&self) -> bool {
+        self.look.is_captured
+    }
+
+    pub(crate) fn update(
+        &mut self,
+        transform: &mut Transform,
+        input: &CameraControllerInput,
+        delta_time: f32,
+    ) {
+        // Starts from the rotation the object already has.
+        if !self.is_initialized {
+            let forward = transform.rotation * Vec3::FORWARD;
+            self.yaw = (-forward.x).atan2(-forward.z);
+            self.pitch = forward
+                .y
+                .clamp(-1.0, 1.0)
+                .asin()
+                .clamp(-MAX_PITCH, MAX_PITCH);
+            self.is_initialized = true;
+        }
+
+        if input.scroll != 0.0 && !input.is_pointer_over_ui {
+            self.speed = (self.speed * self.scroll_speed_step.powf(input.scroll))
+                .clamp(self.min_speed, self.max_speed);
+        }
+
+        if self
+            .look
+            .update(input.is_right_down, input.is_pointer_over_ui)
+        {
+            self.yaw -= input.motion.x * self.look_sensitivity;
+            self.pitch =
+                (self.pitch - input.motion.y * self.look_sensitivity).clamp(-MAX_PITCH, MAX_PITCH);
+        }
+
+        let forward = look_direction(self.yaw, self.pitch);
+        let right = Vec3::cross(forward, Vec3::UP).normalized();
+        let speed = if input.is_boosted {
+            self.speed * self.boost_multiplier
+        } else {
+            self.speed
+        };
+
+        transform.rotation = Quat::look_rotation(forward, Vec3::UP);
+        transform.position +=
+            (right * input.movement.x + Vec3::UP * input.movement.y + forward * input.movement.z)
+                * (speed * delta_time);
+    }
+}
+
+impl Default for FlyCameraController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
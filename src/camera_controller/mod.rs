@@ -0,0 +1,142 @@
+use crate::{
+    frame_snapshot::InputSnapshot,
+    math::{Vec2, Vec3},
+};
+
+mod fly_camera_controller;
+mod orbit_camera_controller;
+
+pub use fly_camera_controller::*;
+pub use orbit_camera_controller::*;
+
+/// Pitch is kept a little below straight up and down, where yaw is undefined.
+const MAX_PITCH: f32 = 89.0f32 * std::f32::consts::PI / 180.0f32;
+
+/// The input of a frame, as seen by the camera controllers.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub(crate) struct CameraControllerInput {
+    /// `D - A` on x, `E - Q` on y and `W - S` on z.
+    pub movement: Vec3,
+    pub is_boosted: bool,
+    /// Raw mouse motion, which keeps reporting while the cursor is locked.
+    pub motion: Vec2,
+    /// Cursor movement in physical pixels, y pointing down.
+    pub cursor_delta: Vec2,
+    pub scroll: f32,
+    pub is_left_down: bool,
+    pub is_right_down: bool,
+    pub is_middle_down: bool,
+    pub is_pointer_over_ui: bool,
+}
+
+impl CameraControllerInput {
+    pub fn read(input: &InputSnapshot, is_pointer_over_ui: bool) -> Self {
+        let key = |name: &str| if input.is_key_down(name) { 1.0 } else { 0.0 };
+
+        Self {
+            movement: Vec3::new(
+                key("d") - key("a"),
+                key("e") - key("q"),
+                key("w") - key("s"),
+            ),
+            is_boosted: input.is_key_down("shift:l") || input.is_key_down("shift:r"),
+            motion: input.mouse_motion,
+            cursor_delta: input.mouse_delta,
+            scroll: input.scroll.y,
+            is_left_down: input.is_mouse_button_down("button:left"),
+            is_right_down: input.is_mouse_button_down("button:right"),
+            is_middle_down: input.is_mouse_button_down("button:middle"),
+            is_pointer_over_ui,
+        }
+    }
+}
+
+/// Tracks a mouse button that a controller reacts to. A press that starts over the UI is ignored until the
+/// button is released, even if the pointer leaves the UI meanwhile.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct ControllerButton {
+    is_down: bool,
+    is_captured: bool,
+}
+
+impl ControllerButton {
+    /// Updates the button state and returns whether the controller owns the press.
+    fn update(&mut self, is_down: bool, is_pointer_over_ui: bool) -> bool {
+        if is_down && !self.is_down {
+            self.is_captured = !is_pointer_over_ui;
+        } else if !is_down {
+            self.is_captured = false;
+        }
+
+        self.is_down = is_down;
+        self.is_captured
+    }
+}
+
+/// Returns the forward direction of the yaw and pitch, both in radians. Zero looks along `Vec3::FORWARD`.
+fn look_direction(yaw: f32, pitch: f32) -> Vec3 {
+    Vec3::new(
+        -yaw.sin() * pitch.cos(),
+        pitch.sin(),
+        -yaw.cos() * pitch.cos(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{math::Quat, transform::Transform};
+
+    fn drag(left: bool, over_ui: bool, cursor_delta: Vec2) -> CameraControllerInput {
+        CameraControllerInput {
+            cursor_delta,
+            is_left_down: left,
+            is_pointer_over_ui: over_ui,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn orbit_ignores_drags_that_start_over_ui() {
+        let mut controller = OrbitCameraController::new(Vec3::ZERO, 4.0);
+        let mut transform = Transform::new();
+
+        controller.update(&mut transform, &CameraControllerInput::default());
+        assert!(Vec3::distance(transform.position, Vec3::new(0.0, 0.0, 4.0)) < 1e-4);
+        assert!(Vec3::distance(transform.rotation * Vec3::FORWARD, Vec3::FORWARD) < 1e-4);
+
+        // A drag that starts over the UI stays ignored after the pointer leaves it.
+        controller.update(&mut transform, &drag(true, true, Vec2::ZERO));
+        controller.update(&mut transform, &drag(true, false, Vec2::new(100.0, 0.0)));
+        assert_eq!(controller.yaw, 0.0);
+
+        // A drag that starts over the scene keeps rotating while the pointer crosses the UI.
+        controller.update(&mut transform, &drag(false, false, Vec2::ZERO));
+        controller.update(&mut transform, &drag(true, false, Vec2::ZERO));
+        controller.update(&mut transform, &drag(true, true, Vec2::new(100.0, 0.0)));
+        assert!(controller.yaw < 0.0);
+
+        // The camera stays on the sphere around the target and looks at it.
+        let forward = transform.rotation * Vec3::FORWARD;
+        assert!((transform.position.len() - 4.0).abs() < 1e-4);
+        assert!(Vec3::distance(forward, -transform.position.normalized()) < 1e-4);
+    }
+
+    #[test]
+    fn fly_starts_from_current_rotation() {
+        let mut controller = FlyCameraController::new();
+        let mut transform = Transform::new();
+        let direction = Vec3::new(1.0, 0.5, 1.0).normalized();
+        transform.rotation = Quat::look_rotation(direction, Vec3::UP);
+
+        let input = CameraControllerInput {
+            movement: Vec3::new(0.0, 0.0, 1.0),
+            ..Default::default()
+        };
+        controller.update(&mut transform, &input, 0.5);
+
+        assert!(Vec3::distance(transform.rotation * Vec3::FORWARD, direction) < 1e-4);
+        assert!(Vec3::distance(transform.position, direction * controller.speed * 0.5) < 1e-4);
+        assert!(!controller.is_looking());
+    }
+}
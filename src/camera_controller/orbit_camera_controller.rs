@@ -0,0 +1,83 @@
+use super::{look_direction, CameraControllerInput, ControllerButton, MAX_PITCH};
+use crate::{
+    math::{Quat, Vec3},
+    transform::Transform,
+};
+use codegen::Component;
+use specs::prelude::*;
+
+/// Moves the object around a target point, always looking at it.
+///
+/// Dragging with the left mouse button orbits, dragging with the middle mouse button pans the target, and
+/// scrolling zooms in and out.
+#[derive(Debug, Clone, Component)]
+#[storage(HashMapStorage)]
+#[auto_register]
+pub struct OrbitCameraController {
+    pub target: Vec3,
+    pub distance: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    /// Radians around the up axis. Zero places the camera on the positive z-axis of the target.
+    pub yaw: f32,
+    /// Radians above the horizon; negative values look down at the target.
+    pub pitch: f32,
+    /// Radians per pixel of dragging.
+    pub rotate_sensitivity: f32,
+    /// Fraction of the distance per pixel of panning, so that panning feels the same at every zoom.
+    pub pan_sensitivity: f32,
+    /// The distance is divided by this for every scroll step.
+    pub zoom_step: f32,
+    /// While disabled, the mouse is ignored as if it was over the UI, e.g. while an editor tool owns the drag.
+    /// Drags that start while disabled stay ignored until the button is released.
+    pub is_enabled: bool,
+    rotate: ControllerButton,
+    pan: ControllerButton,
+}
+
+impl OrbitCameraController {
+    pub fn new(target: Vec3, distance: f32) -> Self {
+        Self {
+            target,
+            distance,
+            min_distance: 0.1,
+            max_distance: 1000.0,
+            yaw: 0.0,
+            pitch: 0.0,
+            rotate_sensitivity: 0.005,
+            pan_sensitivity: 0.001,
+            zoom_step: 1.1,
+            is_enabled: true,
+            rotate: ControllerButton::default(),
+            pan: ControllerButton::default(),
+        }
+    }
+
+    pub(crate) fn update(&mut self, transform: &mut Transform, input: &CameraControllerInput) {
+        let is_ignored = input.is_pointer_over_ui || !self.is_enabled;
+
+        if self.rotate.update(input.is_left_down, is_ignored) {
+            self.yaw -= input.cursor_delta.x * self.rotate_sensitivity;
+            self.pitch = (self.pitch - input.cursor_delta.y * self.rotate_sensitivity)
+                .clamp(-MAX_PITCH, MAX_PITCH);
+        }
+
+        let forward = look_direction(self.yaw, self.pitch);
+
+        if self.pan.update(input.is_middle_down, is_ignored) {
+            let rotation = Quat::look_rotation(forward, Vec3::UP);
+            let right = rotation * Vec3::RIGHT;
+            let up = rotation * Vec3::UP;
+            self.target += (up * input.cursor_delta.y - right * input.cursor_delta.x)
+                * (self.distance * self.pan_sensitivity);
+        }
+
+        if input.scroll != 0.0 && !is_ignored {
+            self.distance = (self.distance * self.zoom_step.powf(-input.scroll))
+                .clamp(self.min_distance, self.max_distance);
+        }
+
+        transform.position = self.target - forward * self.distance;
+        transform.look_at(self.target, Vec3::UP);
+    }
+}
@@ -0,0 +1,38 @@
+use specs::{Component, World, WorldExt};
+
+/// A component type to be registered to the world when the engine starts.
+/// It is submitted by `#[derive(Component)]` of `codegen` with `#[auto_register]`, and it works for
+/// components defined in other crates as well.
+pub struct ComponentRegistration {
+    register: fn(&mut World),
+}
+
+impl ComponentRegistration {
+    pub const fn new<T>() -> Self
+    where
+        T: Component,
+        T::Storage: Default,
+    {
+        Self {
+            register: register_component::<T>,
+        }
+    }
+}
+
+fn register_component<T>(world: &mut World)
+where
+    T: Component,
+    T::Storage: Default,
+{
+    world.register::<T>();
+}
+
+inventory::collect!(ComponentRegistration);
+
+/// Registers all components submitted with `#[auto_register]`.
+/// Registering a component that is already registered has no effect.
+pub fn register_components(world: &mut World) {
+    for registration in inventory::iter::<ComponentRegistration> {
+        (registration.register)(world);
+    }
+}
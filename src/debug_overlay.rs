@@ -0,0 +1,490 @@
+use crate::{
+    event::{event_types, EventHandler},
+    gfx::{
+        Color, FontHandle, MaterialHandle, Sprite, SpriteHandle, SpriteTexelMapping, Texture,
+        TextureHandle, UIElementRenderer, UIElementSprite, UITextRenderer,
+    },
+    input::{InputDevice, InputManager},
+    math::Vec2,
+    object::{ObjectHandle, ObjectManager},
+    time::FRAME_TIME_HISTORY_LEN,
+    ui::{UIAnchor, UIElement, UIMargin, UIScaleMode, UIScaler, UISize},
+    ContextHandle,
+};
+use asset::AssetCategory;
+use fontdue::layout::{HorizontalAlign, VerticalAlign};
+use image::{DynamicImage, Rgba, RgbaImage};
+use logging::{transports::RingBufferTransport, StandardLogLevel};
+use specs::prelude::*;
+use std::{sync::Arc, time::Duration};
+use wgpu::TextureFormat;
+
+/// Frame times at or above this fill the whole height of the graph.
+const GRAPH_MAX_FRAME_TIME: Duration = Duration::from_millis(50);
+/// Labels are re-laid out at this interval instead of every frame, to keep the overlay cheap.
+const LABEL_REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+const LOG_LINE_COUNT: usize = 8;
+
+pub struct DebugOverlayConfig {
+    pub font: FontHandle,
+    pub text_material: MaterialHandle,
+    pub sprite_material: MaterialHandle,
+    /// The logs shown in the console. The console is left empty if `None`.
+    pub logs: Option<Arc<RingBufferTransport<StandardLogLevel>>>,
+    /// The name of the keyboard input that toggles the overlay.
+    pub toggle_key: String,
+    /// The name of the keyboard input that pauses and resumes the game time while the overlay is visible.
+    pub pause_key: String,
+    /// The name of the keyboard input that advances a paused game by [`step_time`](Self::step_time) while the
+    /// overlay is visible.
+    pub step_key: String,
+    pub step_time: Duration,
+    pub visible: bool,
+}
+
+impl DebugOverlayConfig {
+    pub fn new(
+        font: FontHandle,
+        text_material: MaterialHandle,
+        sprite_material: MaterialHandle,
+    ) -> Self {
+        Self {
+            font,
+            text_material,
+            sprite_material,
+            logs: None,
+            toggle_key: "f3".to_owned(),
+            pause_key: "f5".to_owned(),
+            step_key: "f6".to_owned(),
+            step_time: Duration::from_secs_f64(1.0 / 60.0),
+            visible: true,
+        }
+    }
+}
+
+/// Shows the frame rate, a frame time graph, the render stats and the recent logs over the screen.
+/// While visible, the pause and step keys pause the game time and advance it frame by frame.
+/// Its objects are created under a dedicated root; create it after the other UI so that it is drawn on top.
+pub struct DebugOverlay {
+    ctx: ContextHandle,
+    root: ObjectHandle,
+    stats_label: ObjectHandle,
+    log_label: ObjectHandle,
+    bars: Vec<ObjectHandle>,
+    logs: Option<Arc<RingBufferTransport<StandardLogLevel>>>,
+    toggle_key: KeyTrigger,
+    pause_key: KeyTrigger,
+    step_key: KeyTrigger,
+    step_time: Duration,
+    visible: bool,
+    since_label_refresh: Duration,
+}
+
+impl DebugOverlay {
+    /// Creates the overlay and updates it on every [`Update`](event_types::Update) event.
+    pub fn install(ctx: &ContextHandle, config: DebugOverlayConfig) {
+        let mut overlay = Self::new(ctx, config);
+        ctx.event_mgr()
+            .add_handler(EventHandler::new(move |_: &event_types::Update| {
+                overlay.update()
+            }));
+    }
+
+    pub fn new(ctx: &ContextHandle, config: DebugOverlayConfig) -> Self {
+        let white = create_white_sprite(ctx);
+
+        let mut object_mgr = ctx.object_mgr_mut();
+        let mut world = ctx.world_mut();
+
+        let (root, builder) =
+            object_mgr.create_object_builder(&mut world, Some("debug-overlay".to_owned()), None);
+        builder
+            .with(UIScaler {
+                mode: UIScaleMode::Constant,
+                reference_size: Vec2::new(800.0, 600.0),
+            })
+            .with(UISize::new())
+            .build();
+
+        let panel = create_element(
+            &mut object_mgr,
+            &mut world,
+            "debug-overlay-panel",
+            &root,
+            UIAnchor::new(Vec2::new(0.0, 1.0), Vec2::new(0.0, 1.0)),
+            UIMargin::from_size(
+                Vec2::new(0.0, 1.0),
+                Vec2::new(10.0, -10.0),
+                Vec2::new(360.0, 320.0),
+            ),
+        );
+        insert_quad(
+            ctx,
+            &mut world,
+            &panel,
+            &config,
+            &white,
+            Color::from_rgba(0.0, 0.0, 0.0, 0.6),
+        );
+
+        let stats_label = create_element(
+            &mut object_mgr,
+            &mut world,
+            "debug-overlay-stats",
+            &panel,
+            UIAnchor::new(Vec2::new(0.0, 0.65), Vec2::ONE),
+            UIMargin::new(8.0, -8.0, -8.0, 0.0),
+        );
+        insert_label(&mut world, &stats_label, &config, 14.0);
+
+        let graph = create_element(
+            &mut object_mgr,
+            &mut world,
+            "debug-overlay-graph",
+            &panel,
+            UIAnchor::new(Vec2::new(0.0, 0.4), Vec2::new(1.0, 0.65)),
+            UIMargin::new(8.0, -8.0, -4.0, 4.0),
+        );
+        let bars = Vec::from_iter((0..FRAME_TIME_HISTORY_LEN).map(|index| {
+            let bar = create_element(
+                &mut object_mgr,
+                &mut world,
+                "debug-overlay-graph-bar",
+                &graph,
+                bar_anchor(index, 0.0),
+                UIMargin::zero(),
+            );
+            insert_quad(
+                ctx,
+                &mut world,
+                &bar,
+                &config,
+                &white,
+                Color::from_rgb(0.0, 0.0, 0.0),
+            );
+            bar
+        }));
+
+        let log_label = create_element(
+            &mut object_mgr,
+            &mut world,
+            "debug-overlay-log",
+            &panel,
+            UIAnchor::new(Vec2::ZERO, Vec2::new(1.0, 0.4)),
+            UIMargin::new(8.0, -8.0, 0.0, 8.0),
+        );
+        insert_label(&mut world, &log_label, &config, 11.0);
+
+        object_mgr
+            .object_hierarchy_mut()
+            .set_active(root.object_id, config.visible);
+
+        Self {
+            ctx: ctx.clone(),
+            root,
+            stats_label,
+            log_label,
+            bars,
+            logs: config.logs,
+            toggle_key: KeyTrigger::new(config.toggle_key),
+            pause_key: KeyTrigger::new(config.pause_key),
+            step_key: KeyTrigger::new(config.step_key),
+            step_time: config.step_time,
+            visible: config.visible,
+            // Refreshes the labels on the first update.
+            since_label_refresh: LABEL_REFRESH_INTERVAL,
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+        self.root.set_active(visible);
+        self.since_label_refresh = LABEL_REFRESH_INTERVAL;
+    }
+
+    /// Handles the keys and refreshes the overlay. It does nothing else while hidden.
+    pub fn update(&mut self) {
+        let (is_toggle_pressed, is_pause_pressed, is_step_pressed) = {
+            let input_mgr = self.ctx.input_mgr();
+            (
+                self.toggle_key.update(&input_mgr),
+                self.pause_key.update(&input_mgr),
+                self.step_key.update(&input_mgr),
+            )
+        };
+
+        if is_toggle_pressed {
+            self.set_visible(!self.visible);
+        }
+
+        if !self.visible {
+            return;
+        }
+
+        if is_pause_pressed || is_step_pressed {
+            let mut time_mgr = self.ctx.time_mgr_mut();
+
+            if is_pause_pressed && time_mgr.is_paused() {
+                time_mgr.resume();
+            } else if is_pause_pressed {
+                time_mgr.pause();
+            } else {
+                time_mgr.step_frame(self.step_time);
+            }
+
+            self.since_label_refresh = LABEL_REFRESH_INTERVAL;
+        }
+
+        self.update_graph();
+
+        self.since_label_refresh += self.ctx.time_mgr().ui_delta_time();
+
+        if self.since_label_refresh < LABEL_REFRESH_INTERVAL {
+            return;
+        }
+
+        self.since_label_refresh = Duration::ZERO;
+        self.update_labels();
+    }
+
+    fn update_graph(&self) {
+        let time_mgr = self.ctx.time_mgr();
+        let frame_times = time_mgr.frame_times();
+        // Aligns the newest frame to the right edge.
+        let offset = self.bars.len() - frame_times.len();
+
+        let mut object_mgr = self.ctx.object_mgr_mut();
+        let hierarchy = object_mgr.object_hierarchy_mut();
+        let world = self.ctx.world();
+        let mut elements = world.write_component::<UIElement>();
+        let mut renderers = world.write_component::<UIElementRenderer>();
+
+        for (index, bar) in self.bars.iter().enumerate() {
+            let frame_time = index
+                .checked_sub(offset)
+                .map_or(Duration::ZERO, |index| frame_times[index]);
+            let height = (frame_time.as_secs_f32() / GRAPH_MAX_FRAME_TIME.as_secs_f32()).min(1.0);
+            let anchor = bar_anchor(index, height);
+
+            if let Some(element) = elements.get_mut(bar.entity) {
+                if element.anchor != anchor {
+                    element.anchor = anchor;
+                    hierarchy.set_dirty(bar.object_id);
+                }
+            }
+
+            if let Some(renderer) = renderers.get_mut(bar.entity) {
+                renderer.set_color(frame_time_color(frame_time));
+            }
+        }
+    }
+
+    fn update_labels(&self) {
+        let stats = {
+            let time_mgr = self.ctx.time_mgr();
+            let render_mgr = self.ctx.render_mgr();
+            let frame_stats = render_mgr.frame_stats();
+            let frame_time = time_mgr.ui_delta_time().as_secs_f32() * 1000.0;
+            let gpu_time = frame_stats.gpu_time().map_or_else(
+                || "n/a".to_owned(),
+                |time| format!("{:.2} ms", time.as_secs_f32() * 1000.0),
+            );
+
+            format!(
+                "FPS: {:.1} ({:.0} Hz){}\nframe: {:.2} ms, gpu: {}\ndraw calls: {}, state changes: {}, triangles: {}, passes: {}\nframe buffers: {} / {} KiB",
+                time_mgr.fps(),
+                self.ctx.screen_mgr().refresh_rate(),
+                if time_mgr.is_paused() { " [paused]" } else { "" },
+                frame_time,
+                gpu_time,
+                frame_stats.draw_calls,
+                frame_stats.state_changes,
+                frame_stats.triangles,
+                frame_stats.render_passes,
+                frame_stats.frame_buffers.device.last_frame_usage / 1024,
+                frame_stats.frame_buffers.device.capacity / 1024,
+            )
+        };
+        #[cfg(feature = "gpu-resource-tracking")]
+        let stats = {
+            use crate::gfx::{gpu_resource_registry, GpuResourceKind};
+
+            let registry = gpu_resource_registry();
+            format!(
+                "{}\nlive: {} buffers, {} textures, {} bind groups, {} pipelines",
+                stats,
+                registry.live_count(GpuResourceKind::Buffer),
+                registry.live_count(GpuResourceKind::Texture),
+                registry.live_count(GpuResourceKind::BindGroup),
+                registry.live_count(GpuResourceKind::Pipeline),
+            )
+        };
+        let stats = format!(
+            "{}\nassets: {}",
+            stats,
+            AssetCategory::ALL
+                .into_iter()
+                .map(|category| match category.budget() {
+                    Some(budget) => format!(
+                        "{:?} {} / {} KiB",
+                        category,
+                        category.usage() / 1024,
+                        budget / 1024
+                    ),
+                    None => format!("{:?} {} KiB", category, category.usage() / 1024),
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        let stats = match self.ctx.render_mgr().capture_stats() {
+            Some(capture_stats) => format!(
+                "{}\n[REC] {} frames, {} dropped",
+                stats, capture_stats.frames, capture_stats.dropped_frames
+            ),
+            None => stats,
+        };
+        let logs = self.logs.as_ref().map_or_else(String::new, |logs| {
+            let logs = logs.logs();
+            let skip = logs.len().saturating_sub(LOG_LINE_COUNT);
+            logs.iter()
+                .skip(skip)
+                .map(|log| format!("{} {}", log.level, log.message.lines().next().unwrap_or("")))
+                .collect::<Vec<_>>()
+                .join("\n")
+        });
+
+        let world = self.ctx.world();
+        let mut renderers = world.write_component::<UITextRenderer>();
+
+        if let Some(renderer) = renderers.get_mut(self.stats_label.entity) {
+            renderer.set_text(stats);
+        }
+
+        if let Some(renderer) = renderers.get_mut(self.log_label.entity) {
+            renderer.set_text(logs);
+        }
+    }
+}
+
+/// Reports the frames that a keyboard input gets pressed in.
+struct KeyTrigger {
+    key: String,
+    is_pressed: bool,
+}
+
+impl KeyTrigger {
+    fn new(key: String) -> Self {
+        Self {
+            key,
+            is_pressed: false,
+        }
+    }
+
+    fn update(&mut self, input_mgr: &InputManager) -> bool {
+        let is_pressed = input_mgr
+            .keyboard()
+            .input(&self.key)
+            .is_some_and(|input| 0.5 <= input.value);
+        let is_triggered = is_pressed && !self.is_pressed;
+        self.is_pressed = is_pressed;
+        is_triggered
+    }
+}
+
+fn bar_anchor(index: usize, height: f32) -> UIAnchor {
+    let width = 1.0 / FRAME_TIME_HISTORY_LEN as f32;
+    UIAnchor::new(
+        Vec2::new(index as f32 * width, 0.0),
+        Vec2::new((index + 1) as f32 * width, height),
+    )
+}
+
+/// Green within 60 FPS, yellow within 30 FPS and red otherwise.
+fn frame_time_color(frame_time: Duration) -> Color {
+    if frame_time <= Duration::from_micros(16_667) {
+        Color::from_rgb(0.3, 0.8, 0.3)
+    } else if frame_time <= Duration::from_micros(33_334) {
+        Color::from_rgb(0.9, 0.8, 0.2)
+    } else {
+        Color::from_rgb(0.9, 0.3, 0.3)
+    }
+}
+
+fn create_white_sprite(ctx: &ContextHandle) -> SpriteHandle {
+    let image = DynamicImage::ImageRgba8(RgbaImage::from_pixel(1, 1, Rgba([255, 255, 255, 255])));
+    let texture = TextureHandle::new(Texture::from_image(
+        TextureFormat::Rgba8Unorm,
+        &image,
+        &ctx.gfx_ctx().device,
+        &ctx.gfx_ctx().queue,
+    ));
+    SpriteHandle::new(Sprite::new(texture, SpriteTexelMapping::new(0, 1, 0, 1)))
+}
+
+fn create_element(
+    object_mgr: &mut ObjectManager,
+    world: &mut World,
+    name: &str,
+    parent: &ObjectHandle,
+    anchor: UIAnchor,
+    margin: UIMargin,
+) -> ObjectHandle {
+    let (object, builder) = object_mgr.create_object_builder(world, Some(name.to_owned()), None);
+    builder
+        .with(UIElement::new(anchor, margin, false))
+        .with(UISize::new())
+        .build();
+
+    object_mgr
+        .object_hierarchy_mut()
+        .set_parent(object.object_id, Some(parent.object_id));
+
+    object
+}
+
+fn insert_quad(
+    ctx: &ContextHandle,
+    world: &mut World,
+    object: &ObjectHandle,
+    config: &DebugOverlayConfig,
+    sprite: &SpriteHandle,
+    color: Color,
+) {
+    let mut renderer = UIElementRenderer::new();
+    renderer.set_material(config.sprite_material.clone());
+    renderer.set_color(color);
+    renderer.set_sprite(
+        UIElementSprite::sprite(sprite.clone()),
+        &ctx.gfx_ctx().device,
+        ctx.render_mgr_mut().bind_group_layout_cache(),
+    );
+    world
+        .write_component::<UIElementRenderer>()
+        .insert(object.entity, renderer)
+        .unwrap();
+}
+
+fn insert_label(
+    world: &mut World,
+    object: &ObjectHandle,
+    config: &DebugOverlayConfig,
+    font_size: f32,
+) {
+    let mut renderer = UITextRenderer::new();
+    renderer.with_config(|config| {
+        config.horizontal_align = HorizontalAlign::Left;
+        config.vertical_align = VerticalAlign::Top;
+    });
+    renderer.set_color(Color::from_rgb(1.0, 1.0, 1.0));
+    renderer.set_font_size_with_recommended_values(font_size);
+    renderer.set_material(config.text_material.clone());
+    renderer.set_font(config.font.clone());
+    world
+        .write_component::<UITextRenderer>()
+        .insert(object.entity, renderer)
+        .unwrap();
+}
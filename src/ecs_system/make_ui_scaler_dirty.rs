@@ -1,4 +1,8 @@
-use crate::{object::Object, ui::UIScaler, ContextHandle};
+use crate::{
+    object::{Object, ObjectHierarchy},
+    ui::UIScaler,
+    ContextHandle,
+};
 use specs::prelude::*;
 
 pub struct MakeUIScalerDirty {
@@ -24,10 +28,18 @@ impl<'a> System<'a> for MakeUIScalerDirty {
         screen_mgr.reset_dirty();
 
         let mut object_mgr = self.ctx.object_mgr_mut();
-        let hierarchy = object_mgr.object_hierarchy_mut();
+        mark_ui_scalers_dirty(&objects, &scalers, object_mgr.object_hierarchy_mut());
+    }
+}
 
-        for (object, _) in (&objects, &scalers).join() {
-            hierarchy.set_dirty(object.object_id());
-        }
+/// Marks the objects of all [`UIScaler`]s dirty, so that the next update lays out every UI root and its
+/// descendants again, e.g. after the scale factor of the screen has changed.
+pub fn mark_ui_scalers_dirty(
+    objects: &ReadStorage<Object>,
+    scalers: &ReadStorage<UIScaler>,
+    hierarchy: &mut ObjectHierarchy,
+) {
+    for (object, _) in (objects, scalers).join() {
+        hierarchy.set_dirty(object.object_id());
     }
 }
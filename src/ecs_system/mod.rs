@@ -1,6 +1,17 @@
 pub mod make_ui_scaler_dirty;
 pub mod render;
+pub mod update_camera_controllers;
 pub mod update_camera_transform_buffer;
+pub mod update_particle_emitters;
+pub mod update_renderer_bvh;
+#[cfg(feature = "scripting")]
+pub mod update_scripts;
+pub mod update_sprite_animations;
+#[cfg(feature = "text-mesh")]
+pub mod update_text_meshes;
+pub mod update_tweens;
 pub mod update_ui_element;
+pub mod update_ui_localized_text;
+pub mod update_ui_progress_bar;
 pub mod update_ui_raycast_grid;
 pub mod update_ui_scaler;
@@ -1,22 +1,49 @@
 use crate::{
     gfx::{
-        BindGroupLayoutCache, Camera, MeshRenderer, Renderer, UIElementRenderer, UITextRenderer,
+        BindGroupLayoutCache, Camera, CameraTarget, FrameGraph, FrameGraphLoadOp,
+        FrameGraphTexture, Material, MaterialBlendMode, MaterialDepthMode, MeshRenderer,
+        MeshSubRenderer, ParticleEmitter, ReflectionProbe, RenderManager, RenderPassState,
+        Renderer, RenderingCommand, SecondaryWindow, SpriteBatch, SpriteDraw, SpriteInstance,
+        SpriteRenderer, SpriteView, UIElementRenderer, UIElementSubRenderer, UITextRenderer,
+        UITextSubRenderer,
     },
-    object::Object,
-    ui::UISize,
+    math::{Frustum, Mat4, Vec2, Vec3},
+    object::{is_object_hidden_for_camera, Object, ObjectId, ObjectVisibility},
+    ui::{effective_ui_color, UIElement, UISize},
     use_context,
 };
 use image::EncodableLayout;
+use logging::log_warn;
 use specs::prelude::*;
-use std::mem::size_of;
+use std::{
+    mem::{size_of, ManuallyDrop, MaybeUninit},
+    sync::Arc,
+};
 use wgpu::{
     BindGroup, BindGroupDescriptor, BindGroupLayoutEntry, BindingType, Buffer, BufferAddress,
-    BufferBindingType, BufferDescriptor, BufferSize, BufferUsages, Device, ShaderStages,
+    BufferBindingType, BufferDescriptor, BufferSize, BufferUsages, Device, LoadOp, Operations,
+    ShaderStages, SurfaceError, SurfaceTexture, TextureView,
 };
 
 pub struct RenderSystem {
     screen_size_buffer: Buffer,
     screen_size_bind_group: BindGroup,
+    // The per-frame storage below is cleared instead of dropped, so that a frame does not allocate
+    // once the storage has grown enough. The vectors that hold borrows are lent out by `FrameVec`.
+    camera_objects: FrameVec<CameraObjectElement>,
+    views: FrameVec<CameraViewElement>,
+    /// Mesh renderers whose AABB intersects the frustum of the current camera.
+    visible_mesh_entities: Vec<Entity>,
+    mesh_sub_renderers: Vec<(ObjectId, MeshSubRenderer)>,
+    /// Mesh renderers whose materials ignore the depth. They are drawn over the meshes and the sprites.
+    overlay_mesh_sub_renderers: Vec<(ObjectId, MeshSubRenderer)>,
+    sprite_draws: Vec<(u32, ObjectId, SpriteDraw)>,
+    sprite_batches: Vec<(ObjectId, SpriteBatch)>,
+    sprite_instance_pool: Vec<Vec<SpriteInstance>>,
+    ui_element_sub_renderers: Vec<(u32, ObjectId, UIElementSubRenderer)>,
+    ui_text_sub_renderers: Vec<(u32, ObjectId, UITextSubRenderer)>,
+    ui_sub_renderers: FrameVec<UISubRendererElement>,
+    commands: FrameVec<RenderingCommandElement>,
 }
 
 impl RenderSystem {
@@ -50,6 +77,18 @@ impl RenderSystem {
         Self {
             screen_size_buffer,
             screen_size_bind_group,
+            camera_objects: FrameVec::new(),
+            views: FrameVec::new(),
+            visible_mesh_entities: Vec::new(),
+            mesh_sub_renderers: Vec::new(),
+            overlay_mesh_sub_renderers: Vec::new(),
+            sprite_draws: Vec::new(),
+            sprite_batches: Vec::new(),
+            sprite_instance_pool: Vec::new(),
+            ui_element_sub_renderers: Vec::new(),
+            ui_text_sub_renderers: Vec::new(),
+            ui_sub_renderers: FrameVec::new(),
+            commands: FrameVec::new(),
         }
     }
 }
@@ -59,9 +98,14 @@ impl<'a> System<'a> for RenderSystem {
         ReadStorage<'a, Object>,
         ReadStorage<'a, Camera>,
         WriteStorage<'a, MeshRenderer>,
+        WriteStorage<'a, SpriteRenderer>,
+        WriteStorage<'a, ParticleEmitter>,
         WriteStorage<'a, UIElementRenderer>,
         WriteStorage<'a, UITextRenderer>,
+        ReadStorage<'a, UIElement>,
         ReadStorage<'a, UISize>,
+        ReadStorage<'a, ObjectVisibility>,
+        WriteStorage<'a, ReflectionProbe>,
     );
 
     fn run(
@@ -70,56 +114,266 @@ impl<'a> System<'a> for RenderSystem {
             objects,
             cameras,
             mut mesh_renderers,
+            mut sprite_renderers,
+            mut particle_emitters,
             mut ui_element_renderers,
             mut ui_text_renderers,
+            ui_elements,
             ui_sizes,
+            visibilities,
+            mut reflection_probes,
         ): Self::SystemData,
     ) {
         let context = use_context();
         let mut glyph_mgr = context.glyph_mgr_mut();
+        glyph_mgr.next_frame();
+
         let mut render_mgr = context.render_mgr_mut();
         let shader_mgr = context.shader_mgr();
         let world_mgr = context.object_mgr();
         let object_hierarchy = world_mgr.object_hierarchy();
+        let screen = context.frame().screen;
+        let scale_factor = screen.scale_factor as f32;
 
-        context
-            .gfx_ctx()
-            .queue
-            .write_buffer(&self.screen_size_buffer, 0, {
-                let screen_mgr = context.screen_mgr();
-                [
-                    screen_mgr.width() as f32,
-                    screen_mgr.height() as f32,
-                    0.0f32,
-                    0.0f32,
-                ]
-                .as_bytes()
-            });
+        context.gfx_ctx().queue.write_buffer(
+            &self.screen_size_buffer,
+            0,
+            [screen.logical_size.x, screen.logical_size.y, 0.0f32, 0.0f32].as_bytes(),
+        );
 
-        let surface_texture = context.gfx_ctx().surface.get_current_texture().unwrap();
-        let surface_texture_view = surface_texture.texture.create_view(&Default::default());
+        let (surface_width, surface_height) = {
+            let surface_config = context.gfx_ctx().surface_config.borrow();
+            (surface_config.width as f32, surface_config.height as f32)
+        };
+        let surface_texture = match context
+            .gfx_ctx()
+            .surface
+            .as_ref()
+            .map(|surface| surface.get_current_texture())
+        {
+            Some(Ok(surface_texture)) => surface_texture,
+            // The window has been resized or moved to another display; the next frame uses the new surface.
+            Some(Err(SurfaceError::Lost | SurfaceError::Outdated)) => {
+                context.gfx_ctx().reconfigure_surface();
+                return;
+            }
+            Some(Err(SurfaceError::Timeout)) => {
+                return;
+            }
+            Some(Err(err @ SurfaceError::OutOfMemory)) => {
+                panic!("{}", crate::EngineExecError::from(err));
+            }
+            // The context of the engine always has a surface.
+            None => {
+                return;
+            }
+        };
         let mut encoder = render_mgr.create_encoder();
+        render_mgr.begin_frame();
+
+        let mut draw_calls = 0;
+        let mut triangles = 0;
+        let mut render_passes = 0;
+        let mut state_changes = 0;
 
-        let mut camera_objects = (&objects, &cameras).join().collect::<Vec<_>>();
+        let mut camera_objects = self.camera_objects.take();
+        camera_objects.extend((&objects, &cameras).join());
         camera_objects.sort_unstable_by_key(|&(_, camera)| camera.depth);
 
-        for (object, camera) in camera_objects {
-            let standard_ui_vertex_buffer = render_mgr.standard_ui_vertex_buffer().clone();
-            let (bind_group_layout_cache, pipeline_cache) = render_mgr.split_caches();
+        let mut frame_graph = FrameGraph::new();
+        let mut targets = vec![RenderTarget::new(
+            RenderTargetKind::Camera(CameraTarget::Main),
+            surface_texture,
+            surface_width,
+            surface_height,
+            &mut frame_graph,
+            render_mgr.has_depth_stencil(),
+        )];
 
+        {
+            let mut secondary_window_mgr = context.secondary_window_mgr_mut();
+
+            for &(object, camera) in &camera_objects {
+                let id = match camera.target {
+                    CameraTarget::Window(id) => id,
+                    CameraTarget::Main => continue,
+                };
+
+                if !object_hierarchy.is_active(object.object_id())
+                    || targets
+                        .iter()
+                        .any(|target| target.kind == RenderTargetKind::Camera(camera.target))
+                {
+                    continue;
+                }
+
+                let window = match secondary_window_mgr.get_mut(id) {
+                    Some(window) => window,
+                    None => continue,
+                };
+                let surface_texture = match window.acquire_texture() {
+                    Ok(Some(surface_texture)) => surface_texture,
+                    Ok(None) => continue,
+                    Err(err) => {
+                        log_warn!("skipped rendering into a secondary window: {}", err);
+                        continue;
+                    }
+                };
+                let size = window.size();
+                let has_depth_stencil = window.depth_stencil_view().is_some();
+                targets.push(RenderTarget::new(
+                    RenderTargetKind::Camera(camera.target),
+                    surface_texture,
+                    size.width as f32,
+                    size.height as f32,
+                    &mut frame_graph,
+                    has_depth_stencil,
+                ));
+            }
+        }
+
+        let secondary_window_mgr = context.secondary_window_mgr();
+
+        for (object, reflection_probe) in (&objects, &mut reflection_probes).join() {
+            if object_hierarchy.is_active(object.object_id()) {
+                let position = Vec3::from(object_hierarchy.matrix(object.object_id()).row(3));
+                reflection_probe.begin_frame(position, &context.gfx_ctx().queue);
+            }
+        }
+
+        let mut views = self.views.take();
+
+        // Probes are captured before the cameras, so that the cameras reflect the captures of this frame.
+        for (object, reflection_probe) in (&objects, &reflection_probes).join() {
+            if !object_hierarchy.is_active(object.object_id()) || !reflection_probe.is_capturing() {
+                continue;
+            }
+
+            let size = reflection_probe.size() as f32;
+            let has_depth_stencil = reflection_probe.depth_stencil_view().is_some();
+
+            for (camera, transform_matrix, texture_view) in reflection_probe.faces() {
+                targets.push(RenderTarget::with_view(
+                    RenderTargetKind::ReflectionProbe(object.entity()),
+                    texture_view.clone(),
+                    size,
+                    size,
+                    &mut frame_graph,
+                    has_depth_stencil,
+                ));
+                views.push(CameraView {
+                    entity: object.entity(),
+                    transform_matrix,
+                    camera,
+                    target_index: targets.len() - 1,
+                });
+            }
+        }
+
+        for &(object, camera) in &camera_objects {
             if !object_hierarchy.is_active(object.object_id()) {
                 continue;
             }
 
-            let mut mesh_sub_renderers = Vec::with_capacity(1024);
+            // Cameras of closed or minimized windows are skipped.
+            let target_index = match targets
+                .iter()
+                .position(|target| target.kind == RenderTargetKind::Camera(camera.target))
+            {
+                Some(target_index) => target_index,
+                None => continue,
+            };
+            views.push(CameraView {
+                entity: object.entity(),
+                transform_matrix: object_hierarchy.matrix(object.object_id()),
+                camera,
+                target_index,
+            });
+        }
+
+        for (index, view) in views.iter().enumerate() {
+            let camera = view.camera;
+            let target = &targets[view.target_index];
+            let viewport = camera.viewport.to_physical(target.width, target.height);
+
+            if viewport.width < 1.0 || viewport.height < 1.0 {
+                continue;
+            }
+
+            let mut pass = frame_graph.add_pass(format!("camera {}", index), (index, viewport));
+            // Load operations clear the whole attachment, so cameras of partial viewports clear by drawing instead.
+            let is_full = camera.viewport.is_full();
 
-            let mut ui_element_sub_renderers = Vec::with_capacity(1024);
-            let mut ui_text_sub_renderers = Vec::with_capacity(1024);
+            if is_full && matches!(camera.clear_mode.color_load_op(), LoadOp::Clear(_)) {
+                pass.clear(target.color);
+            } else {
+                pass.write(target.color);
+            }
+
+            if let Some(depth_stencil) = target.depth_stencil {
+                if is_full && matches!(camera.clear_mode.depth_load_op(), LoadOp::Clear(_)) {
+                    pass.clear(depth_stencil);
+                } else {
+                    pass.write(depth_stencil);
+                }
+            }
+        }
+
+        let frame_graph = match frame_graph.build() {
+            Ok(frame_graph) => frame_graph,
+            Err(err) => {
+                debug_assert!(false, "invalid frame graph: {}", err);
+                log_warn!(
+                    "skipped rendering the frame due to an invalid frame graph: {}",
+                    err
+                );
+                FrameGraph::new().build().unwrap()
+            }
+        };
+
+        for pass in frame_graph.passes() {
+            let &(index, viewport) = pass.payload();
+            let view = &views[index];
+            let camera = view.camera;
+            let target = &targets[view.target_index];
+            let standard_ui_vertex_buffer = render_mgr.standard_ui_vertex_buffer().clone();
+            let (bind_group_layout_cache, pipeline_cache) = render_mgr.split_caches();
+
+            let camera_entity = view.entity;
+            let is_hidden = |object_id: ObjectId| {
+                is_object_hidden_for_camera(
+                    object_hierarchy,
+                    &visibilities,
+                    object_id,
+                    camera_entity,
+                )
+            };
+            let transform_matrix = view.transform_matrix;
+            let view_projection = camera
+                .view_projection_matrix(Vec2::new(target.width, target.height), transform_matrix);
 
-            for (object, mesh_renderer) in (&objects, &mut mesh_renderers).join() {
+            self.mesh_sub_renderers.clear();
+            self.overlay_mesh_sub_renderers.clear();
+            self.ui_element_sub_renderers.clear();
+            self.ui_text_sub_renderers.clear();
+
+            self.visible_mesh_entities.clear();
+            context.renderer_bvh().query_frustum(
+                &Frustum::from_view_projection(&view_projection),
+                &mut self.visible_mesh_entities,
+            );
+            // Keeps the order of the storage, so that the drawing order does not depend on the shape of the tree.
+            self.visible_mesh_entities.sort_unstable();
+
+            for &entity in &self.visible_mesh_entities {
+                let (object, mesh_renderer) =
+                    match (objects.get(entity), mesh_renderers.get_mut(entity)) {
+                        (Some(object), Some(mesh_renderer)) => (object, mesh_renderer),
+                        _ => continue,
+                    };
                 let object_id = object.object_id();
 
-                if !object_hierarchy.is_active(object.object_id()) {
+                if is_hidden(object_id) {
                     continue;
                 }
 
@@ -127,6 +381,9 @@ impl<'a> System<'a> for RenderSystem {
                     continue;
                 }
 
+                mesh_renderer
+                    .prepare_property_block(&context.gfx_ctx().device, &context.gfx_ctx().queue);
+
                 let renderer = if let Some(renderer) =
                     mesh_renderer.sub_renderer(shader_mgr, pipeline_cache)
                 {
@@ -135,7 +392,105 @@ impl<'a> System<'a> for RenderSystem {
                     continue;
                 };
 
-                mesh_sub_renderers.push((object_id, renderer));
+                if renderer.material().depth_mode == Some(MaterialDepthMode::Always) {
+                    self.overlay_mesh_sub_renderers.push((object_id, renderer));
+                } else {
+                    self.mesh_sub_renderers.push((object_id, renderer));
+                }
+            }
+
+            group_opaque_meshes(&mut self.mesh_sub_renderers);
+
+            let sprite_view = SpriteView::from_matrix(transform_matrix);
+
+            for (object, sprite_renderer) in (&objects, &mut sprite_renderers).join() {
+                let object_id = object.object_id();
+
+                if is_hidden(object_id) {
+                    continue;
+                }
+
+                if sprite_renderer.mask() & camera.mask == 0 {
+                    continue;
+                }
+
+                sprite_renderer
+                    .prepare_property_block(&context.gfx_ctx().device, &context.gfx_ctx().queue);
+
+                let draw = if let Some(draw) = sprite_renderer.draw(
+                    object_hierarchy.matrix(object_id),
+                    &sprite_view,
+                    camera.sprite_sort_axis,
+                    shader_mgr,
+                    pipeline_cache,
+                ) {
+                    draw
+                } else {
+                    continue;
+                };
+
+                if !draw.instance.is_visible(&view_projection) {
+                    continue;
+                }
+
+                self.sprite_draws
+                    .push((object_hierarchy.index(object_id), object_id, draw));
+            }
+
+            for (object, particle_emitter) in (&objects, &mut particle_emitters).join() {
+                let object_id = object.object_id();
+
+                if is_hidden(object_id) {
+                    continue;
+                }
+
+                if particle_emitter.renderer().mask() & camera.mask == 0 {
+                    continue;
+                }
+
+                particle_emitter
+                    .renderer_mut()
+                    .prepare_property_block(&context.gfx_ctx().device, &context.gfx_ctx().queue);
+
+                let index = object_hierarchy.index(object_id);
+                let sprite_draws = &mut self.sprite_draws;
+
+                particle_emitter.draw(
+                    &sprite_view,
+                    camera.sprite_sort_axis,
+                    shader_mgr,
+                    pipeline_cache,
+                    |draw| {
+                        if draw.instance.is_visible(&view_projection) {
+                            sprite_draws.push((index, object_id, draw));
+                        }
+                    },
+                );
+            }
+
+            // Ties are broken by the hierarchy, so that the order does not flicker between frames.
+            self.sprite_draws
+                .sort_unstable_by(|(lhs_index, _, lhs), (rhs_index, _, rhs)| {
+                    lhs.sort_key
+                        .drawing_order(&rhs.sort_key)
+                        .then(lhs_index.cmp(rhs_index))
+                });
+
+            for (_, batch) in self.sprite_batches.drain(..) {
+                self.sprite_instance_pool.push(batch.into_instances());
+            }
+
+            for (_, object_id, draw) in self.sprite_draws.drain(..) {
+                match self.sprite_batches.last_mut() {
+                    Some((_, batch)) if batch.can_merge(&draw) => batch.push(draw.instance),
+                    _ => {
+                        let instances = self.sprite_instance_pool.pop().unwrap_or_default();
+                        self.sprite_batches.push((
+                            object_id,
+                            SpriteBatch::new(draw, &standard_ui_vertex_buffer, instances),
+                        ));
+                    }
+                }
             }
 
             for (object, ui_element_renderer, ui_size) in
@@ -143,7 +498,7 @@ impl<'a> System<'a> for RenderSystem {
             {
                 let object_id = object.object_id();
 
-                if !object_hierarchy.is_active(object.object_id()) {
+                if is_hidden(object_id) {
                     continue;
                 }
 
@@ -151,8 +506,12 @@ impl<'a> System<'a> for RenderSystem {
                     continue;
                 }
 
+                ui_element_renderer
+                    .prepare_property_block(&context.gfx_ctx().device, &context.gfx_ctx().queue);
+
                 let renderer = if let Some(renderer) = ui_element_renderer.sub_renderer(
                     *ui_size,
+                    effective_ui_color(object_id, object_hierarchy, &ui_elements),
                     &standard_ui_vertex_buffer,
                     shader_mgr,
                     pipeline_cache,
@@ -162,7 +521,7 @@ impl<'a> System<'a> for RenderSystem {
                     continue;
                 };
 
-                ui_element_sub_renderers.push((
+                self.ui_element_sub_renderers.push((
                     object_hierarchy.index(object_id),
                     object_id,
                     renderer,
@@ -174,7 +533,7 @@ impl<'a> System<'a> for RenderSystem {
             {
                 let object_id = object.object_id();
 
-                if !object_hierarchy.is_active(object.object_id()) {
+                if is_hidden(object_id) {
                     continue;
                 }
 
@@ -185,6 +544,8 @@ impl<'a> System<'a> for RenderSystem {
                 let renderers = if let Some(renderers) = ui_text_renderer.sub_renderers(
                     object_hierarchy.is_current_frame_dirty(object_id),
                     *ui_size,
+                    scale_factor,
+                    effective_ui_color(object_id, object_hierarchy, &ui_elements),
                     &standard_ui_vertex_buffer,
                     shader_mgr,
                     &mut glyph_mgr,
@@ -196,61 +557,518 @@ impl<'a> System<'a> for RenderSystem {
                     continue;
                 };
 
-                for renderer in renderers {
-                    ui_text_sub_renderers.push((
-                        object_hierarchy.index(object_id),
-                        object_id,
-                        renderer,
-                    ));
-                }
+                let index = object_hierarchy.index(object_id);
+                self.ui_text_sub_renderers
+                    .extend(renderers.map(|renderer| (index, object_id, renderer)));
             }
 
-            let mut ui_sub_renderers =
-                Vec::with_capacity(ui_element_sub_renderers.len() + ui_text_sub_renderers.len());
+            let mut ui_sub_renderers = self.ui_sub_renderers.take();
 
-            for (index, object_id, renderer) in &ui_element_sub_renderers {
+            for (index, object_id, renderer) in &self.ui_element_sub_renderers {
                 ui_sub_renderers.push((*index, *object_id, renderer as &dyn Renderer));
             }
 
-            for (index, object_id, renderer) in &ui_text_sub_renderers {
+            for (index, object_id, renderer) in &self.ui_text_sub_renderers {
                 ui_sub_renderers.push((*index, *object_id, renderer as &dyn Renderer));
             }
 
             ui_sub_renderers.sort_unstable_by_key(|&(index, _, _)| index);
 
-            let mut commands =
-                Vec::with_capacity(mesh_sub_renderers.len() + ui_sub_renderers.len());
+            let mut commands = self.commands.take();
 
-            for (object_id, renderer) in &mesh_sub_renderers {
-                let command =
-                    render_mgr.build_rendering_command(*object_id, object_hierarchy, renderer);
-                commands.push(command);
+            for (object_id, renderer) in &self.mesh_sub_renderers {
+                render_mgr.build_rendering_command(
+                    *object_id,
+                    object_hierarchy,
+                    renderer,
+                    &mut commands,
+                );
+            }
+
+            for (object_id, batch) in &self.sprite_batches {
+                render_mgr.build_rendering_command(
+                    *object_id,
+                    object_hierarchy,
+                    batch,
+                    &mut commands,
+                );
+            }
+
+            for (object_id, renderer) in &self.overlay_mesh_sub_renderers {
+                render_mgr.build_rendering_command(
+                    *object_id,
+                    object_hierarchy,
+                    renderer,
+                    &mut commands,
+                );
             }
 
             for (_, object_id, renderer) in &ui_sub_renderers {
-                let command =
-                    render_mgr.build_rendering_command(*object_id, object_hierarchy, *renderer);
-                commands.push(command);
+                render_mgr.build_rendering_command(
+                    *object_id,
+                    object_hierarchy,
+                    *renderer,
+                    &mut commands,
+                );
+            }
+
+            let skybox =
+                render_mgr.prepare_skybox(&camera.clear_mode, transform_matrix, &view_projection);
+
+            if skybox.is_some() {
+                draw_calls += 1;
+                triangles += 1;
             }
 
-            let mut render_pass = render_mgr
-                .begin_frame_buffer_render_pass(
+            draw_calls += commands.len() as u32;
+            triangles += commands
+                .iter()
+                .map(|cmd| (cmd.vertex_count / 3) as u64 * cmd.instance_count as u64)
+                .sum::<u64>();
+            render_passes += 1;
+
+            let pass_timestamp = render_mgr.begin_pass_timestamp(&mut encoder);
+
+            {
+                let color_ops = Operations {
+                    load: load_op(
+                        pass.load_op(target.color),
+                        camera.clear_mode.color_load_op(),
+                    ),
+                    store: pass.store(target.color),
+                };
+                let depth_load_op = target
+                    .depth_stencil
+                    .and_then(|texture| pass.load_op(texture));
+                let depth_store = target
+                    .depth_stencil
+                    .is_some_and(|texture| pass.store(texture));
+                let depth_stencil_view = match target.kind {
+                    RenderTargetKind::Camera(CameraTarget::Main) => render_mgr.depth_stencil_view(),
+                    RenderTargetKind::Camera(CameraTarget::Window(id)) => secondary_window_mgr
+                        .get(id)
+                        .and_then(SecondaryWindow::depth_stencil_view),
+                    RenderTargetKind::ReflectionProbe(entity) => reflection_probes
+                        .get(entity)
+                        .and_then(ReflectionProbe::depth_stencil_view),
+                };
+                let mut render_pass = RenderManager::begin_render_pass(
                     &mut encoder,
-                    &surface_texture_view,
-                    &camera.clear_mode,
-                )
-                .unwrap();
+                    &target.view,
+                    depth_stencil_view,
+                    color_ops,
+                    Operations {
+                        load: load_op(depth_load_op, camera.clear_mode.depth_load_op()),
+                        store: depth_store,
+                    },
+                    Operations {
+                        load: load_op(depth_load_op, camera.clear_mode.stencil_load_op()),
+                        store: depth_store,
+                    },
+                );
+
+                if !camera.viewport.is_full() {
+                    render_mgr.clear_viewport(&mut render_pass, &camera.clear_mode, viewport);
+                }
+
+                if let Some(skybox) = skybox {
+                    render_mgr.draw_skybox(&mut render_pass, skybox);
+                }
+
+                let mut pass_state = RenderPassState::new();
+
+                for cmd in &commands {
+                    cmd.render(
+                        &mut render_pass,
+                        &mut pass_state,
+                        &camera.bind_group,
+                        &self.screen_size_bind_group,
+                    );
+                }
+
+                state_changes += pass_state.state_changes();
+            }
+
+            render_mgr.end_pass_timestamp(&mut encoder, pass_timestamp);
+
+            self.commands.restore(commands);
+            self.ui_sub_renderers.restore(ui_sub_renderers);
+        }
+
+        self.views.restore(views);
+        self.camera_objects.restore(camera_objects);
+
+        if let Some(surface_texture) = &targets[0].surface_texture {
+            render_mgr.capture_frame(&mut encoder, &surface_texture.texture);
+        }
+
+        render_mgr.record_draw_stats(draw_calls, triangles, render_passes, state_changes);
+        render_mgr.resolve_timestamps(&mut encoder);
+        render_mgr.finish_frame(std::iter::once(encoder.finish()));
+
+        for target in targets {
+            if let Some(surface_texture) = target.surface_texture {
+                surface_texture.present();
+            }
+        }
+    }
+}
+
+/// A camera that renders in the frame: a camera object, or a face of a reflection probe that captures.
+struct CameraView<'a> {
+    entity: Entity,
+    transform_matrix: &'a Mat4,
+    camera: &'a Camera,
+    target_index: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RenderTargetKind {
+    Camera(CameraTarget),
+    /// A face of the cubemap of the probe of the entity.
+    ReflectionProbe(Entity),
+}
+
+/// A surface or a cubemap face that cameras render into, with the textures imported into the frame graph for it.
+struct RenderTarget {
+    kind: RenderTargetKind,
+    /// The texture to present at the end of the frame, if the target is a surface.
+    surface_texture: Option<SurfaceTexture>,
+    view: Arc<TextureView>,
+    width: f32,
+    height: f32,
+    color: FrameGraphTexture,
+    depth_stencil: Option<FrameGraphTexture>,
+}
+
+impl RenderTarget {
+    fn new<P>(
+        kind: RenderTargetKind,
+        surface_texture: SurfaceTexture,
+        width: f32,
+        height: f32,
+        frame_graph: &mut FrameGraph<P>,
+        has_depth_stencil: bool,
+    ) -> Self {
+        let view = Arc::new(surface_texture.texture.create_view(&Default::default()));
+        let mut target = Self::with_view(kind, view, width, height, frame_graph, has_depth_stencil);
+        target.surface_texture = Some(surface_texture);
+        target
+    }
+
+    fn with_view<P>(
+        kind: RenderTargetKind,
+        view: Arc<TextureView>,
+        width: f32,
+        height: f32,
+        frame_graph: &mut FrameGraph<P>,
+        has_depth_stencil: bool,
+    ) -> Self {
+        let name = match kind {
+            RenderTargetKind::Camera(CameraTarget::Main) => "surface".to_owned(),
+            RenderTargetKind::Camera(CameraTarget::Window(id)) => format!("{:?} surface", id),
+            RenderTargetKind::ReflectionProbe(entity) => {
+                format!("{:?} reflection probe face", entity)
+            }
+        };
+        let color = frame_graph.import_texture(&name);
+        let depth_stencil = has_depth_stencil
+            .then(|| frame_graph.import_texture(format!("{} depth stencil", name)));
+
+        Self {
+            kind,
+            surface_texture: None,
+            view,
+            width,
+            height,
+            color,
+            depth_stencil,
+        }
+    }
+}
+
+/// Takes the clear value of the camera only if the frame graph clears the attachment.
+fn load_op<V>(graph_load_op: Option<FrameGraphLoadOp>, camera_load_op: LoadOp<V>) -> LoadOp<V> {
+    match (graph_load_op, camera_load_op) {
+        (Some(FrameGraphLoadOp::Clear), LoadOp::Clear(value)) => LoadOp::Clear(value),
+        _ => LoadOp::Load,
+    }
+}
+
+/// Sorts each run of consecutive opaque meshes by pipeline and material, so that fewer states change between
+/// draws. Opaque meshes test and write the depth, so the order within a run does not change the result. The runs
+/// stay in place, so the order relative to blended meshes is kept.
+fn group_opaque_meshes(sub_renderers: &mut [(ObjectId, MeshSubRenderer)]) {
+    let is_opaque = |renderer: &MeshSubRenderer| {
+        let material = renderer.material();
+        material.blend_mode == Some(MaterialBlendMode::Opaque)
+            && matches!(
+                material.depth_mode,
+                None | Some(MaterialDepthMode::ReadWrite)
+            )
+    };
+    let mut start = 0;
+
+    while start < sub_renderers.len() {
+        if !is_opaque(&sub_renderers[start].1) {
+            start += 1;
+            continue;
+        }
+
+        let end = sub_renderers[start..]
+            .iter()
+            .position(|(_, renderer)| !is_opaque(renderer))
+            .map_or(sub_renderers.len(), |count| start + count);
+
+        // The cached sort is stable, so the meshes that share both keep their order.
+        sub_renderers[start..end].sort_by_cached_key(|(_, renderer)| {
+            (
+                renderer.pipeline().id(),
+                &*renderer.material() as *const Material as usize,
+            )
+        });
+        start = end;
+    }
+}
+
+/// A family of element types that differ only in their lifetimes, e.g. the rendering commands of any frame.
+trait FrameElement {
+    type Of<'a>;
+}
+
+struct CameraObjectElement;
 
-            for cmd in &commands {
-                cmd.render(
-                    &mut render_pass,
-                    &camera.bind_group,
-                    &self.screen_size_bind_group,
+impl FrameElement for CameraObjectElement {
+    type Of<'a> = (&'a Object, &'a Camera);
+}
+
+struct CameraViewElement;
+
+impl FrameElement for CameraViewElement {
+    type Of<'a> = CameraView<'a>;
+}
+
+struct UISubRendererElement;
+
+impl FrameElement for UISubRendererElement {
+    type Of<'a> = (u32, ObjectId, &'a dyn Renderer);
+}
+
+struct RenderingCommandElement;
+
+impl FrameElement for RenderingCommandElement {
+    type Of<'a> = RenderingCommand<'a>;
+}
+
+/// The allocation of a per-frame vector whose elements borrow the data of a frame. It holds no element between
+/// frames, so it is kept as uninitialized elements with the `'static` lifetime, and lent out as an empty vector
+/// of elements with the lifetime of the frame.
+struct FrameVec<E: FrameElement> {
+    buffer: Vec<MaybeUninit<E::Of<'static>>>,
+}
+
+impl<E: FrameElement> FrameVec<E> {
+    fn new() -> Self {
+        Self { buffer: Vec::new() }
+    }
+
+    /// Lends the allocation as an empty vector of elements with the given lifetime.
+    fn take<'a>(&mut self) -> Vec<E::Of<'a>> {
+        let mut buffer = ManuallyDrop::new(std::mem::take(&mut self.buffer));
+        // SAFETY: The vector is empty, and the element types differ only in their lifetimes, which do not change
+        // the layout, so the allocation is one of `E::Of<'a>`s.
+        unsafe { Vec::from_raw_parts(buffer.as_mut_ptr().cast(), 0, buffer.capacity()) }
+    }
+
+    /// Drops the elements of the vector and keeps its allocation for the next frame.
+    fn restore(&mut self, mut vec: Vec<E::Of<'_>>) {
+        vec.clear();
+        let mut vec = ManuallyDrop::new(vec);
+        // SAFETY: The vector is empty, and the element types differ only in their lifetimes, so the allocation
+        // is one of `E::Of<'static>`s.
+        self.buffer = unsafe { Vec::from_raw_parts(vec.as_mut_ptr().cast(), 0, vec.capacity()) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        gfx::{
+            build_rendering_command, test_gfx_ctx, FrameBufferAllocator, MaterialHandle, Mesh,
+            MeshHandle, PipelineCache, PipelineLayoutCache, RenderTargetState, ShaderManager,
+        },
+        object::ObjectHierarchy,
+    };
+    use std::{
+        alloc::{GlobalAlloc, Layout, System},
+        cell::Cell,
+    };
+    use wgpu::TextureFormat;
+    use winit::dpi::PhysicalSize;
+
+    /// Counts the allocations of each thread, so that the tests running in parallel do not count each other.
+    struct CountingAllocator;
+
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+            System.realloc(ptr, layout, new_size)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    fn allocations() -> usize {
+        ALLOCATIONS.with(Cell::get)
+    }
+
+    struct ArcElement;
+
+    impl FrameElement for ArcElement {
+        type Of<'a> = Arc<()>;
+    }
+
+    #[test]
+    fn commands_are_built_without_allocating_after_the_first_frame() {
+        let gfx_ctx = match test_gfx_ctx(PhysicalSize::new(4, 4)) {
+            Some(gfx_ctx) => gfx_ctx,
+            None => {
+                eprintln!("skipped: no adapter found");
+                return;
+            }
+        };
+        let shader_mgr = ShaderManager::new(gfx_ctx.clone());
+        let mut bind_group_layout_cache = BindGroupLayoutCache::new(gfx_ctx.clone());
+        let mut pipeline_layout_cache = PipelineLayoutCache::new(gfx_ctx.clone());
+        let mut pipeline_cache = PipelineCache::new(
+            gfx_ctx.clone(),
+            RenderTargetState {
+                color_format: TextureFormat::Rgba8Unorm,
+                depth_format: Some(TextureFormat::Depth32Float),
+                sample_count: 1,
+            },
+        );
+        let mut frame_buffer_allocator = FrameBufferAllocator::new(gfx_ctx.clone());
+        // The shader has no per-instance input, so the commands upload nothing. Uploads are recorded by wgpu,
+        // which allocates for each of them.
+        let shader = shader_mgr
+            .create_shader(
+                &mut bind_group_layout_cache,
+                r#"
+struct VertexInput {
+  @location(0) position: vec3<f32>,
+};
+
+struct FragmentOutput {
+  @location(0) color: vec4<f32>,
+};
+
+@vertex
+fn vs_main(vertex: VertexInput) -> @builtin(position) vec4<f32> {
+  return vec4<f32>(vertex.position, 1.0);
+}
+
+@fragment
+fn fs_main() -> FragmentOutput {
+  var out: FragmentOutput;
+  out.color = vec4<f32>(1.0, 1.0, 1.0, 1.0);
+  return out;
+}
+"#,
+            )
+            .unwrap();
+        let material = MaterialHandle::new(Material::new(shader, &mut pipeline_layout_cache));
+        let mesh = MeshHandle::new(Mesh::cube(1.0));
+
+        let mut world = World::new();
+        world.register::<MeshRenderer>();
+        let mut hierarchy = ObjectHierarchy::new();
+        let objects = (0..4)
+            .map(|id| {
+                let mut mesh_renderer = MeshRenderer::new();
+                mesh_renderer.set_material(material.clone());
+                mesh_renderer.set_mesh(mesh.clone(), &gfx_ctx.device);
+
+                let object_id = ObjectId::from_u32(id);
+                let entity = world.create_entity().with(mesh_renderer).build();
+                hierarchy.add(object_id, entity);
+                (object_id, entity)
+            })
+            .collect::<Vec<_>>();
+
+        let mut mesh_renderers = world.write_component::<MeshRenderer>();
+        let mut sub_renderers = Vec::new();
+        let mut commands = FrameVec::<RenderingCommandElement>::new();
+
+        for frame in 0..4 {
+            sub_renderers.clear();
+            sub_renderers.extend(objects.iter().map(|&(object_id, entity)| {
+                let mesh_renderer = mesh_renderers.get_mut(entity).unwrap();
+                let renderer = mesh_renderer
+                    .sub_renderer(&shader_mgr, &mut pipeline_cache)
+                    .unwrap();
+                (object_id, renderer)
+            }));
+
+            let before = allocations();
+            let mut frame_commands = commands.take();
+
+            for (object_id, renderer) in &sub_renderers {
+                build_rendering_command(
+                    *object_id,
+                    &hierarchy,
+                    renderer,
+                    &mut frame_buffer_allocator,
+                    &mut frame_commands,
                 );
             }
+
+            let command_count = frame_commands.len();
+            commands.restore(frame_commands);
+            let allocated = allocations() - before;
+
+            assert_eq!(command_count, objects.len());
+
+            if frame == 0 {
+                assert!(allocated > 0);
+            } else {
+                assert_eq!(allocated, 0, "frame {} allocated", frame);
+            }
+
+            gfx_ctx
+                .queue
+                .submit(std::iter::once(frame_buffer_allocator.finish()));
+            frame_buffer_allocator.recall();
+            gfx_ctx.device.poll(wgpu::Maintain::Wait);
         }
+    }
+
+    #[test]
+    fn frame_vec_drops_the_elements_it_is_given_back() {
+        let value = Arc::new(());
+        let mut frame_vec = FrameVec::<ArcElement>::new();
+
+        let mut vec = frame_vec.take();
+        vec.extend((0..8).map(|_| value.clone()));
+        assert_eq!(Arc::strong_count(&value), 9);
 
-        render_mgr.finish_frame(vec![encoder.finish()]);
-        surface_texture.present();
+        frame_vec.restore(vec);
+        assert_eq!(Arc::strong_count(&value), 1);
+        assert!(frame_vec.take().capacity() >= 8);
     }
 }
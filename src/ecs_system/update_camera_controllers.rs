@@ -0,0 +1,85 @@
+use crate::{
+    camera_controller::{CameraControllerInput, FlyCameraController, OrbitCameraController},
+    object::Object,
+    transform::Transform,
+    ContextHandle,
+};
+use specs::prelude::*;
+
+/// Drives [`FlyCameraController`] and [`OrbitCameraController`]. It must run after the UI raycast grid is
+/// updated, so that clicks over the UI are recognized in the same frame.
+///
+/// The cursor is locked while a fly controller is looking around. Controllers use the unscaled delta time,
+/// so that the scene can be inspected while the game is paused.
+pub struct UpdateCameraControllers {
+    ctx: ContextHandle,
+    is_cursor_locked: bool,
+}
+
+impl UpdateCameraControllers {
+    pub fn new(ctx: ContextHandle) -> Self {
+        Self {
+            ctx,
+            is_cursor_locked: false,
+        }
+    }
+
+    fn lock_cursor(&mut self, lock: bool) {
+        if self.is_cursor_locked == lock {
+            return;
+        }
+
+        self.is_cursor_locked = lock;
+
+        let _ = self.ctx.set_cursor_grab(lock);
+        self.ctx.set_cursor_visible(!lock);
+    }
+}
+
+impl<'a> System<'a> for UpdateCameraControllers {
+    type SystemData = (
+        ReadStorage<'a, Object>,
+        WriteStorage<'a, Transform>,
+        WriteStorage<'a, FlyCameraController>,
+        WriteStorage<'a, OrbitCameraController>,
+    );
+
+    fn run(
+        &mut self,
+        (objects, mut transforms, mut fly_controllers, mut orbit_controllers): Self::SystemData,
+    ) {
+        let frame = self.ctx.frame();
+        let input =
+            CameraControllerInput::read(&frame.input, self.ctx.ui_event_mgr().is_pointer_over_ui());
+        let delta_time = frame.time.unscaled_delta_time.as_secs_f32();
+        let mut object_mgr = self.ctx.object_mgr_mut();
+        let hierarchy = object_mgr.object_hierarchy_mut();
+        let mut is_looking = false;
+
+        for (object, transform, controller) in
+            (&objects, &mut transforms, &mut fly_controllers).join()
+        {
+            if !hierarchy.is_active(object.object_id()) {
+                continue;
+            }
+
+            controller.update(transform, &input, delta_time);
+            hierarchy.set_dirty(object.object_id());
+            is_looking |= controller.is_looking();
+        }
+
+        for (object, transform, controller) in
+            (&objects, &mut transforms, &mut orbit_controllers).join()
+        {
+            if !hierarchy.is_active(object.object_id()) {
+                continue;
+            }
+
+            controller.update(transform, &input);
+            hierarchy.set_dirty(object.object_id());
+        }
+
+        drop(object_mgr);
+        self.lock_cursor(is_looking);
+    }
+}
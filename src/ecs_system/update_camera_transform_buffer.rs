@@ -1,4 +1,9 @@
-use crate::{gfx::Camera, object::Object, ContextHandle};
+use crate::{
+    gfx::{Camera, CameraTarget},
+    math::Vec2,
+    object::Object,
+    ContextHandle,
+};
 use specs::prelude::*;
 
 pub struct UpdateCameraTransformBufferSystem {
@@ -16,7 +21,8 @@ impl<'a> System<'a> for UpdateCameraTransformBufferSystem {
 
     fn run(&mut self, (objects, cameras): Self::SystemData) {
         let world_mgr = self.ctx.object_mgr();
-        let screen_mgr = self.ctx.screen_mgr();
+        let screen_size = self.ctx.frame().screen.logical_size;
+        let secondary_window_mgr = self.ctx.secondary_window_mgr();
         let object_hierarchy = world_mgr.object_hierarchy();
 
         for (object, camera) in (&objects, &cameras).join() {
@@ -27,7 +33,15 @@ impl<'a> System<'a> for UpdateCameraTransformBufferSystem {
             let object_id = object.object_id();
             let matrix = object_hierarchy.matrix(object_id);
 
-            camera.update_buffer(&screen_mgr, &self.ctx.gfx_ctx.queue, matrix);
+            let screen_size = match camera.target {
+                CameraTarget::Main => screen_size,
+                CameraTarget::Window(id) => match secondary_window_mgr.size(id) {
+                    Some(size) => Vec2::new(size.width as f32, size.height as f32),
+                    None => continue,
+                },
+            };
+
+            camera.update_buffer(screen_size, &self.ctx.gfx_ctx.queue, matrix);
         }
     }
 }
@@ -0,0 +1,28 @@
+use crate::{gfx::ParticleEmitter, object::Object, ContextHandle};
+use specs::prelude::*;
+
+/// Moves the particles of [`ParticleEmitter`]s on the game time and spawns new ones. It must run after the object
+/// matrices are updated, so that particles are spawned where the emitters are drawn in the same frame.
+pub struct UpdateParticleEmitters {
+    ctx: ContextHandle,
+}
+
+impl UpdateParticleEmitters {
+    pub fn new(ctx: ContextHandle) -> Self {
+        Self { ctx }
+    }
+}
+
+impl<'a> System<'a> for UpdateParticleEmitters {
+    type SystemData = (ReadStorage<'a, Object>, WriteStorage<'a, ParticleEmitter>);
+
+    fn run(&mut self, (objects, mut emitters): Self::SystemData) {
+        let delta_time = self.ctx.frame().time.delta_time.as_secs_f32();
+        let object_mgr = self.ctx.object_mgr();
+        let hierarchy = object_mgr.object_hierarchy();
+
+        for (object, emitter) in (&objects, &mut emitters).join() {
+            emitter.update(delta_time, hierarchy.matrix(object.object_id()));
+        }
+    }
+}
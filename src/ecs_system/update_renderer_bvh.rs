@@ -0,0 +1,80 @@
+use crate::{gfx::MeshRenderer, math::AABB, object::Object, ContextHandle};
+use specs::prelude::*;
+use std::collections::HashMap;
+
+/// Keeps the renderer BVH in sync with the mesh renderers. Only the objects whose matrix has changed in this frame,
+/// or whose mesh has changed, are updated.
+pub struct UpdateRendererBvh {
+    ctx: ContextHandle,
+    local_aabbs: HashMap<Entity, AABB>,
+}
+
+impl UpdateRendererBvh {
+    /// The number of leaves reinserted per frame, to keep the tree in shape as objects move around.
+    const REBUILD_COUNT_PER_FRAME: usize = 16;
+
+    pub fn new(ctx: ContextHandle) -> Self {
+        Self {
+            ctx,
+            local_aabbs: HashMap::new(),
+        }
+    }
+}
+
+impl<'a> System<'a> for UpdateRendererBvh {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Object>,
+        ReadStorage<'a, MeshRenderer>,
+    );
+
+    fn run(&mut self, (entities, objects, mesh_renderers): Self::SystemData) {
+        let mut bvh = self.ctx.renderer_bvh_mut();
+        let object_mgr = self.ctx.object_mgr();
+        let hierarchy = object_mgr.object_hierarchy();
+        let mut count = 0;
+
+        for (entity, object, mesh_renderer) in (&entities, &objects, &mesh_renderers).join() {
+            let local_aabb = if let Some(local_aabb) = mesh_renderer.local_aabb() {
+                local_aabb
+            } else {
+                continue;
+            };
+            count += 1;
+
+            let is_mesh_changed = self.local_aabbs.get(&entity) != Some(&local_aabb);
+
+            if is_mesh_changed {
+                self.local_aabbs.insert(entity, local_aabb);
+            } else if !hierarchy.is_current_frame_dirty(object.object_id()) {
+                continue;
+            }
+
+            bvh.insert(
+                entity,
+                local_aabb.transformed(hierarchy.matrix(object.object_id())),
+            );
+        }
+
+        // Some renderers have been removed or lost their mesh.
+        if count != bvh.len() {
+            let stale = bvh
+                .entities()
+                .filter(|&entity| {
+                    !entities.is_alive(entity)
+                        || mesh_renderers
+                            .get(entity)
+                            .and_then(|mesh_renderer| mesh_renderer.local_aabb())
+                            .is_none()
+                })
+                .collect::<Vec<_>>();
+
+            for entity in stale {
+                bvh.remove(entity);
+                self.local_aabbs.remove(&entity);
+            }
+        }
+
+        bvh.rebuild_incrementally(Self::REBUILD_COUNT_PER_FRAME);
+    }
+}
@@ -0,0 +1,41 @@
+use crate::{object::Object, script::ScriptComponent, ContextHandle};
+use specs::prelude::*;
+
+/// Calls `on_update` of the scripts, creating their instances first if needed.
+/// It also drops the instances of the entities whose script component has been removed.
+pub struct UpdateScripts {
+    ctx: ContextHandle,
+}
+
+impl UpdateScripts {
+    pub fn new(ctx: ContextHandle) -> Self {
+        Self { ctx }
+    }
+}
+
+impl<'a> System<'a> for UpdateScripts {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Object>,
+        ReadStorage<'a, ScriptComponent>,
+    );
+
+    fn run(&mut self, (entities, objects, scripts): Self::SystemData) {
+        let dt = self.ctx.frame().time.delta_time.as_secs_f32();
+        let script_mgr = self.ctx.script_mgr();
+
+        script_mgr.retain_instances(|entity| scripts.contains(entity));
+
+        // Collects first, since scripts may access the other managers while they are running.
+        let targets = Vec::from_iter(
+            (&entities, &objects, &scripts)
+                .join()
+                .map(|(entity, object, script)| (entity, *object, script.script.clone())),
+        );
+
+        for (entity, object, script) in targets {
+            let object = self.ctx.object_mgr().object_handle(object.object_id());
+            script_mgr.update_instance(entity, object, &script, dt);
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use crate::{
+    gfx::{SpriteAnimation, SpriteRenderer, UIElementRenderer},
+    ContextHandle,
+};
+use specs::prelude::*;
+
+/// Advances [`SpriteAnimation`]s and shows their frames on the sprite and UI element renderers of their objects.
+pub struct UpdateSpriteAnimations {
+    ctx: ContextHandle,
+}
+
+impl UpdateSpriteAnimations {
+    pub fn new(ctx: ContextHandle) -> Self {
+        Self { ctx }
+    }
+}
+
+impl<'a> System<'a> for UpdateSpriteAnimations {
+    type SystemData = (
+        WriteStorage<'a, SpriteAnimation>,
+        WriteStorage<'a, SpriteRenderer>,
+        WriteStorage<'a, UIElementRenderer>,
+    );
+
+    fn run(&mut self, (mut animations, mut sprite_renderers, mut ui_renderers): Self::SystemData) {
+        let time = self.ctx.frame().time;
+        let delta_time = time.delta_time.as_secs_f32();
+        let ui_delta_time = time.ui_delta_time.as_secs_f32();
+
+        for (animation, sprite_renderer, ui_renderer) in (
+            &mut animations,
+            (&mut sprite_renderers).maybe(),
+            (&mut ui_renderers).maybe(),
+        )
+            .join()
+        {
+            if let Some(ui_renderer) = ui_renderer {
+                animation.advance(ui_delta_time);
+                ui_renderer.set_mapping(animation.mapping());
+            } else if let Some(sprite_renderer) = sprite_renderer {
+                animation.advance(delta_time);
+                sprite_renderer.set_mapping(animation.mapping());
+            }
+        }
+    }
+}
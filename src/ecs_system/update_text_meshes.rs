@@ -0,0 +1,34 @@
+use crate::{
+    gfx::{MeshHandle, MeshRenderer, TextMeshRenderer},
+    ContextHandle,
+};
+use specs::prelude::*;
+
+/// Regenerates the meshes of the text mesh renderers whose text has changed.
+/// It must run before [`UpdateRendererBvh`](super::update_renderer_bvh::UpdateRendererBvh).
+pub struct UpdateTextMeshes {
+    ctx: ContextHandle,
+}
+
+impl UpdateTextMeshes {
+    pub fn new(ctx: ContextHandle) -> Self {
+        Self { ctx }
+    }
+}
+
+impl<'a> System<'a> for UpdateTextMeshes {
+    type SystemData = (
+        WriteStorage<'a, TextMeshRenderer>,
+        WriteStorage<'a, MeshRenderer>,
+    );
+
+    fn run(&mut self, (mut text_mesh_renderers, mut mesh_renderers): Self::SystemData) {
+        for (text_mesh_renderer, mesh_renderer) in
+            (&mut text_mesh_renderers, &mut mesh_renderers).join()
+        {
+            if let Some(mesh) = text_mesh_renderer.take_mesh() {
+                mesh_renderer.set_mesh(MeshHandle::new(mesh), &self.ctx.gfx_ctx().device);
+            }
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use crate::{
+    gfx::{Color, MeshRenderer, PerInstancePropertyValue, UIElementRenderer, UITextRenderer},
+    transform::Transform,
+    tween::TweenValue,
+    ui::{UIElement, UISize},
+    ContextHandle,
+};
+use specs::prelude::*;
+
+/// The property of mesh renderers that color tweens write into.
+const MESH_COLOR_PROPERTY: &str = "color";
+
+/// Drives the tweens of [`TweenManager`](crate::tween::TweenManager). It must run before the UI systems,
+/// so that tweened UI properties are laid out in the same frame.
+///
+/// The completion callbacks are collected instead of being called while the storages are borrowed;
+/// call [`UpdateTweens::run_completion_callbacks`] right after running the system.
+pub struct UpdateTweens {
+    ctx: ContextHandle,
+    completed: Vec<Box<dyn FnOnce()>>,
+}
+
+impl UpdateTweens {
+    pub fn new(ctx: ContextHandle) -> Self {
+        Self {
+            ctx,
+            completed: Vec::new(),
+        }
+    }
+
+    pub fn run_completion_callbacks(&mut self) {
+        for callback in self.completed.drain(..) {
+            callback();
+        }
+    }
+}
+
+impl<'a> System<'a> for UpdateTweens {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, Transform>,
+        WriteStorage<'a, UISize>,
+        WriteStorage<'a, UIElement>,
+        WriteStorage<'a, UIElementRenderer>,
+        WriteStorage<'a, UITextRenderer>,
+        WriteStorage<'a, MeshRenderer>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            mut transforms,
+            mut sizes,
+            mut elements,
+            mut element_renderers,
+            mut text_renderers,
+            mut mesh_renderers,
+        ): Self::SystemData,
+    ) {
+        let time = self.ctx.frame().time;
+        let (delta_time, ui_delta_time) = (time.delta_time, time.ui_delta_time);
+        let mut tween_mgr = self.ctx.tween_mgr_mut();
+        let mut object_mgr = self.ctx.object_mgr_mut();
+        let hierarchy = object_mgr.object_hierarchy_mut();
+        let completed = &mut self.completed;
+
+        tween_mgr.tweens_mut().retain_mut(|running| {
+            let entity = running.tween.target.entity;
+
+            if !entities.is_alive(entity) {
+                return false;
+            }
+
+            let t = running.advance(delta_time, ui_delta_time);
+
+            if let Some(to) = &running.tween.to {
+                if running.from.is_none() {
+                    running.from = match to {
+                        TweenValue::Position(_) => transforms
+                            .get(entity)
+                            .map(|transform| TweenValue::Position(transform.position)),
+                        TweenValue::Rotation(_) => transforms
+                            .get(entity)
+                            .map(|transform| TweenValue::Rotation(transform.rotation)),
+                        TweenValue::Scale(_) => transforms
+                            .get(entity)
+                            .map(|transform| TweenValue::Scale(transform.scale)),
+                        TweenValue::Width(_) => {
+                            sizes.get(entity).map(|size| TweenValue::Width(size.width))
+                        }
+                        TweenValue::Height(_) => sizes
+                            .get(entity)
+                            .map(|size| TweenValue::Height(size.height)),
+                        TweenValue::Margin(_) => elements
+                            .get(entity)
+                            .map(|element| TweenValue::Margin(element.margin.clone())),
+                        TweenValue::Color(_) => {
+                            read_color(entity, &element_renderers, &text_renderers, &mesh_renderers)
+                                .map(TweenValue::Color)
+                        }
+                        TweenValue::Alpha(_) => {
+                            read_color(entity, &element_renderers, &text_renderers, &mesh_renderers)
+                                .map(|color| TweenValue::Alpha(color.a))
+                        }
+                    };
+                }
+
+                // The object has no component to animate.
+                let from = match &running.from {
+                    Some(from) => from,
+                    None => return false,
+                };
+
+                let is_written = match from.interpolate(to, t) {
+                    TweenValue::Position(position) => transforms
+                        .get_mut(entity)
+                        .map(|transform| transform.position = position)
+                        .is_some(),
+                    TweenValue::Rotation(rotation) => transforms
+                        .get_mut(entity)
+                        .map(|transform| transform.rotation = rotation)
+                        .is_some(),
+                    TweenValue::Scale(scale) => transforms
+                        .get_mut(entity)
+                        .map(|transform| transform.scale = scale)
+                        .is_some(),
+                    TweenValue::Width(width) => sizes
+                        .get_mut(entity)
+                        .map(|size| size.width = width)
+                        .is_some(),
+                    TweenValue::Height(height) => sizes
+                        .get_mut(entity)
+                        .map(|size| size.height = height)
+                        .is_some(),
+                    TweenValue::Margin(margin) => elements
+                        .get_mut(entity)
+                        .map(|element| element.margin = margin)
+                        .is_some(),
+                    TweenValue::Color(color) => write_color(
+                        entity,
+                        |_| color,
+                        &mut element_renderers,
+                        &mut text_renderers,
+                        &mut mesh_renderers,
+                    ),
+                    TweenValue::Alpha(alpha) => write_color(
+                        entity,
+                        |color| Color { a: alpha, ..color },
+                        &mut element_renderers,
+                        &mut text_renderers,
+                        &mut mesh_renderers,
+                    ),
+                };
+
+                if !is_written {
+                    return false;
+                }
+
+                hierarchy.set_dirty(running.tween.target.object_id);
+            }
+
+            if !running.is_finished() {
+                return true;
+            }
+
+            if let Some(callback) = running.tween.on_complete.take() {
+                completed.push(callback);
+            }
+
+            false
+        });
+    }
+}
+
+fn read_color(
+    entity: Entity,
+    element_renderers: &WriteStorage<UIElementRenderer>,
+    text_renderers: &WriteStorage<UITextRenderer>,
+    mesh_renderers: &WriteStorage<MeshRenderer>,
+) -> Option<Color> {
+    if let Some(renderer) = element_renderers.get(entity) {
+        return Some(renderer.color());
+    }
+
+    if let Some(renderer) = text_renderers.get(entity) {
+        return Some(renderer.color());
+    }
+
+    mesh_renderers.get(entity).map(mesh_color)
+}
+
+/// Writes the color into every renderer of the object. Returns `false` if the object has none.
+fn write_color(
+    entity: Entity,
+    f: impl Fn(Color) -> Color,
+    element_renderers: &mut WriteStorage<UIElementRenderer>,
+    text_renderers: &mut WriteStorage<UITextRenderer>,
+    mesh_renderers: &mut WriteStorage<MeshRenderer>,
+) -> bool {
+    let mut is_written = false;
+
+    if let Some(renderer) = element_renderers.get_mut(entity) {
+        renderer.set_color(f(renderer.color()));
+        is_written = true;
+    }
+
+    if let Some(renderer) = text_renderers.get_mut(entity) {
+        renderer.set_color(f(renderer.color()));
+        is_written = true;
+    }
+
+    if let Some(renderer) = mesh_renderers.get_mut(entity) {
+        let color = f(mesh_color(renderer));
+        renderer
+            .property_block_mut()
+            .set(MESH_COLOR_PROPERTY, [color.r, color.g, color.b, color.a]);
+        is_written = true;
+    }
+
+    is_written
+}
+
+/// Mesh renderers without the override are assumed to be untinted.
+fn mesh_color(renderer: &MeshRenderer) -> Color {
+    match renderer.property_block().per_instance(MESH_COLOR_PROPERTY) {
+        Some(PerInstancePropertyValue::Float32x4([r, g, b, a])) => Color {
+            r: *r,
+            g: *g,
+            b: *b,
+            a: *a,
+        },
+        _ => Color::white(),
+    }
+}
@@ -1,32 +1,154 @@
 use crate::{
-    math::Vec3,
+    gfx::{UIElementRenderer, UITextRenderer},
+    math::{Vec2, Vec3},
     object::Object,
     transform::Transform,
-    ui::{UIElement, UISize},
+    ui::{
+        UIAspectRatioFitter, UIContentSizeFitter, UIElement, UIGridLayout, UIHorizontalLayout,
+        UISafeArea, UISize, UISizeMode, UIVerticalLayout,
+    },
     ContextHandle,
 };
 use specs::prelude::*;
-use std::cmp::Ordering;
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+};
 
 pub struct UpdateUIElement {
     ctx: ContextHandle,
+    /// The layout generations of the texts that the content size fitters and the sizes fitting their content were
+    /// last fitted to.
+    fitted_generations: HashMap<Entity, u64>,
 }
 
 impl UpdateUIElement {
     pub fn new(ctx: ContextHandle) -> Self {
-        Self { ctx }
+        Self {
+            ctx,
+            fitted_generations: HashMap::new(),
+        }
+    }
+
+    /// Marks the elements whose texts have changed dirty, so that they are fitted again.
+    fn mark_content_changes_dirty(
+        &mut self,
+        entities: &Entities,
+        objects: &ReadStorage<Object>,
+        content_fitters: &ReadStorage<UIContentSizeFitter>,
+        sizes: &WriteStorage<UISize>,
+        text_renderers: &ReadStorage<UITextRenderer>,
+    ) {
+        let mut object_mgr = self.ctx.object_mgr_mut();
+        let hierarchy = object_mgr.object_hierarchy_mut();
+        let mut fitted_generations = HashMap::with_capacity(self.fitted_generations.len());
+
+        for (entity, object, content_fitter, size, text_renderer) in (
+            entities,
+            objects,
+            content_fitters.maybe(),
+            sizes.maybe(),
+            text_renderers,
+        )
+            .join()
+        {
+            let fits_content = matches!(
+                size.map(|size| size.mode),
+                Some(UISizeMode::FitContent { .. })
+            );
+
+            if content_fitter.is_none() && !fits_content {
+                continue;
+            }
+
+            let generation = text_renderer.layout_generation();
+
+            if self.fitted_generations.get(&entity) != Some(&generation) {
+                hierarchy.set_dirty(object.object_id());
+            }
+
+            fitted_generations.insert(entity, generation);
+        }
+
+        self.fitted_generations = fitted_generations;
+    }
+
+    /// Marks the parents with a layout of the dirty elements dirty, so that all of their children are laid out
+    /// again, e.g. when one child has been resized.
+    fn mark_layout_changes_dirty(
+        &self,
+        objects: &ReadStorage<Object>,
+        elements: &ReadStorage<UIElement>,
+        layouts: &Layouts,
+    ) {
+        let mut object_mgr = self.ctx.object_mgr_mut();
+        let hierarchy = object_mgr.object_hierarchy_mut();
+
+        let parents = Vec::from_iter((objects, elements).join().filter_map(|(object, _)| {
+            if !hierarchy.is_dirty(object.object_id()) {
+                return None;
+            }
+
+            let parent = hierarchy.parent(object.object_id())?;
+            layouts.contains(hierarchy.entity(parent)).then_some(parent)
+        }));
+
+        for parent in parents {
+            hierarchy.set_dirty(parent);
+        }
     }
 }
 
 impl<'a> System<'a> for UpdateUIElement {
     type SystemData = (
+        Entities<'a>,
         ReadStorage<'a, Object>,
         ReadStorage<'a, UIElement>,
+        ReadStorage<'a, UIAspectRatioFitter>,
+        ReadStorage<'a, UIContentSizeFitter>,
+        ReadStorage<'a, UITextRenderer>,
+        ReadStorage<'a, UIElementRenderer>,
+        ReadStorage<'a, UISafeArea>,
+        ReadStorage<'a, UIHorizontalLayout>,
+        ReadStorage<'a, UIVerticalLayout>,
+        ReadStorage<'a, UIGridLayout>,
         WriteStorage<'a, Transform>,
         WriteStorage<'a, UISize>,
     );
 
-    fn run(&mut self, (objects, elements, mut transforms, mut sizes): Self::SystemData) {
+    fn run(
+        &mut self,
+        (
+            entities,
+            objects,
+            elements,
+            fitters,
+            content_fitters,
+            text_renderers,
+            element_renderers,
+            safe_areas,
+            horizontal_layouts,
+            vertical_layouts,
+            grid_layouts,
+            mut transforms,
+            mut sizes,
+        ): Self::SystemData,
+    ) {
+        self.mark_content_changes_dirty(
+            &entities,
+            &objects,
+            &content_fitters,
+            &sizes,
+            &text_renderers,
+        );
+
+        let layouts = Layouts {
+            horizontal: &horizontal_layouts,
+            vertical: &vertical_layouts,
+            grid: &grid_layouts,
+        };
+        self.mark_layout_changes_dirty(&objects, &elements, &layouts);
+
         let object_mgr = self.ctx.object_mgr();
         let hierarchy = object_mgr.object_hierarchy();
 
@@ -49,8 +171,66 @@ impl<'a> System<'a> for UpdateUIElement {
         }));
         pairs.sort_unstable();
 
+        let safe_area_insets = self.ctx.frame().screen.safe_area_insets;
+        let mut laid_out_parents = HashSet::new();
+
         for pair in pairs {
-            compute_pair(pair, &elements, &mut transforms, &mut sizes);
+            if layouts.contains(pair.parent) {
+                if laid_out_parents.insert(pair.parent) {
+                    let parent = objects.get(pair.parent).unwrap().object_id();
+                    let children = Vec::from_iter(
+                        hierarchy
+                            .direct_children_iter(parent)
+                            .into_iter()
+                            .flatten()
+                            .map(|child| hierarchy.entity(child)),
+                    );
+
+                    lay_out_children(
+                        pair.parent,
+                        &children,
+                        &layouts,
+                        &elements,
+                        &mut transforms,
+                        &mut sizes,
+                        |child, mode, size| {
+                            mode.fit(Vec2::ZERO, size, || {
+                                measure_content(child, &text_renderers, &element_renderers)
+                            })
+                            .1
+                        },
+                    );
+                }
+
+                continue;
+            }
+
+            compute_pair(
+                pair,
+                &elements,
+                &mut transforms,
+                &mut sizes,
+                |child, mode, mut position, mut size| {
+                    if let Some(safe_area) = safe_areas.get(child) {
+                        (position, size) = safe_area.apply(&safe_area_insets, position, size);
+                    }
+
+                    if let Some(fitter) = fitters.get(child) {
+                        (position, size) = fitter.fit(position, size);
+                    }
+
+                    if let (Some(fitter), Some(text_renderer)) =
+                        (content_fitters.get(child), text_renderers.get(child))
+                    {
+                        (position, size) = fitter
+                            .fit(position, size, |max_width| text_renderer.measure(max_width));
+                    }
+
+                    mode.fit(position, size, || {
+                        measure_content(child, &text_renderers, &element_renderers)
+                    })
+                },
+            );
         }
     }
 }
@@ -81,13 +261,58 @@ impl Ord for Pair {
     }
 }
 
+/// The layout components, which position the direct children of their elements.
+struct Layouts<'s, 'a> {
+    horizontal: &'s ReadStorage<'a, UIHorizontalLayout>,
+    vertical: &'s ReadStorage<'a, UIVerticalLayout>,
+    grid: &'s ReadStorage<'a, UIGridLayout>,
+}
+
+impl Layouts<'_, '_> {
+    fn contains(&self, entity: Entity) -> bool {
+        self.horizontal.contains(entity)
+            || self.vertical.contains(entity)
+            || self.grid.contains(entity)
+    }
+
+    /// Returns the positions of the children of the given sizes, or `None` if the element has no layout.
+    fn arrange(&self, entity: Entity, size: Vec2, child_sizes: &[Vec2]) -> Option<Vec<Vec2>> {
+        if let Some(layout) = self.horizontal.get(entity) {
+            Some(layout.arrange(size, child_sizes))
+        } else if let Some(layout) = self.vertical.get(entity) {
+            Some(layout.arrange(size, child_sizes))
+        } else {
+            self.grid
+                .get(entity)
+                .map(|layout| layout.arrange(size, child_sizes))
+        }
+    }
+}
+
+/// Returns the size of the content of the element: its unwrapped text if it has one, or else its sprite.
+fn measure_content(
+    entity: Entity,
+    text_renderers: &ReadStorage<UITextRenderer>,
+    element_renderers: &ReadStorage<UIElementRenderer>,
+) -> Option<Vec2> {
+    if let Some(text_renderer) = text_renderers.get(entity) {
+        return Some(text_renderer.measure(None));
+    }
+
+    element_renderers
+        .get(entity)
+        .and_then(|element_renderer| element_renderer.content_size())
+}
+
 /// Computes the position and size of the child element based on the parent element.
-/// It is left-bottom based.
+/// It is left-bottom based. The rect resolved by the anchor and margin is adjusted by `adjust`, e.g. to
+/// the safe area, fitters and size mode of the child.
 fn compute_pair(
     pair: Pair,
     elements: &ReadStorage<UIElement>,
     transforms: &mut WriteStorage<Transform>,
     sizes: &mut WriteStorage<UISize>,
+    adjust: impl FnOnce(Entity, UISizeMode, Vec2, Vec2) -> (Vec2, Vec2),
 ) {
     let (parent_width, parent_height) = if let Some(parent) = sizes.get(pair.parent) {
         (parent.width, parent.height)
@@ -104,14 +329,195 @@ fn compute_pair(
     let width = margin_right - margin_left - element.margin.left - element.margin.right;
     let height = margin_top - margin_bottom - element.margin.bottom - element.margin.top;
 
+    let mode = sizes.get(pair.child).unwrap().mode;
+    let (position, size) = adjust(
+        pair.child,
+        mode,
+        Vec2::new(
+            margin_left + element.margin.left,
+            margin_bottom + element.margin.bottom,
+        ),
+        Vec2::new(width, height),
+    );
+
     let transform = transforms.get_mut(pair.child).unwrap();
-    transform.position = Vec3::new(
-        margin_left + element.margin.left,
-        margin_bottom + element.margin.bottom,
-        0.0,
+    transform.position = Vec3::new(position.x, position.y, 0.0);
+
+    let ui_size = sizes.get_mut(pair.child).unwrap();
+    ui_size.width = size.x;
+    ui_size.height = size.y;
+}
+
+/// Positions the child elements of a parent with a layout. The children keep their sizes, adjusted by `fit` to
+/// their size mode, e.g. fitted to their content.
+fn lay_out_children(
+    parent: Entity,
+    children: &[Entity],
+    layouts: &Layouts,
+    elements: &ReadStorage<UIElement>,
+    transforms: &mut WriteStorage<Transform>,
+    sizes: &mut WriteStorage<UISize>,
+    fit: impl Fn(Entity, UISizeMode, Vec2) -> Vec2,
+) {
+    let size = if let Some(size) = sizes.get(parent) {
+        size.to_vec2()
+    } else {
+        return;
+    };
+    let children = Vec::from_iter(
+        children
+            .iter()
+            .copied()
+            .filter(|&child| elements.contains(child) && sizes.contains(child)),
     );
+    let child_sizes = Vec::from_iter(children.iter().map(|&child| {
+        let size = sizes.get(child).unwrap();
+        fit(child, size.mode, size.to_vec2())
+    }));
+    let positions = if let Some(positions) = layouts.arrange(parent, size, &child_sizes) {
+        positions
+    } else {
+        return;
+    };
+
+    for ((&child, child_size), position) in children.iter().zip(child_sizes).zip(positions) {
+        if let Some(transform) = transforms.get_mut(child) {
+            transform.position = Vec3::new(position.x, position.y, 0.0);
+        }
 
-    let size = sizes.get_mut(pair.child).unwrap();
-    size.width = width;
-    size.height = height;
+        let ui_size = sizes.get_mut(child).unwrap();
+        ui_size.width = child_size.x;
+        ui_size.height = child_size.y;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        gfx::{Font, FontHandle},
+        ui::{UIAnchor, UILayoutAlignment, UIMargin},
+    };
+    use fontdue::FontSettings;
+
+    #[test]
+    fn fit_content_sizes_to_the_text_and_padding() {
+        let data = include_bytes!("../../r3d-editor/assets/fonts/NotoSans-Regular.ttf");
+        let font = fontdue::Font::from_bytes(data.as_slice(), FontSettings::default()).unwrap();
+
+        let mut world = World::new();
+        world.register::<UIElement>();
+        world.register::<Transform>();
+        world.register::<UISize>();
+        world.register::<UITextRenderer>();
+        world.register::<UIElementRenderer>();
+
+        let mut text_renderer = UITextRenderer::new();
+        text_renderer.set_font(FontHandle::new(Font::with_default(font)));
+        text_renderer.set_text("Start game".to_owned());
+        let text_size = text_renderer.measure(None);
+        assert!(0.0 < text_size.x && 0.0 < text_size.y);
+
+        let parent = world
+            .create_entity()
+            .with(UISize::from_vec2(Vec2::new(800.0, 600.0)))
+            .build();
+        let child = world
+            .create_entity()
+            .with(UIElement {
+                anchor: UIAnchor::full(),
+                margin: UIMargin::zero(),
+                ..Default::default()
+            })
+            .with(Transform::new())
+            .with(UISize::fit_content(Vec2::new(12.0, 6.0)))
+            .with(text_renderer)
+            .build();
+
+        compute_pair(
+            Pair {
+                index: 1,
+                parent,
+                child,
+            },
+            &world.read_storage(),
+            &mut world.write_storage(),
+            &mut world.write_storage(),
+            |child, mode, position, size| {
+                mode.fit(position, size, || {
+                    measure_content(child, &world.read_storage(), &world.read_storage())
+                })
+            },
+        );
+
+        let size = world.read_storage::<UISize>().get(child).unwrap().to_vec2();
+        assert_eq!(size, text_size + Vec2::new(24.0, 12.0));
+
+        // The fitted element stays centered in the rect of its anchor.
+        let position = world
+            .read_storage::<Transform>()
+            .get(child)
+            .unwrap()
+            .position;
+        assert_eq!(position.x, (800.0 - size.x) * 0.5);
+        assert_eq!(position.y, (600.0 - size.y) * 0.5);
+    }
+
+    #[test]
+    fn vertical_layout_stacks_the_children_from_the_top() {
+        let mut world = World::new();
+        world.register::<UIElement>();
+        world.register::<Transform>();
+        world.register::<UISize>();
+        world.register::<UIHorizontalLayout>();
+        world.register::<UIVerticalLayout>();
+        world.register::<UIGridLayout>();
+
+        let parent = world
+            .create_entity()
+            .with(UISize::from_vec2(Vec2::new(200.0, 300.0)))
+            .with(UIVerticalLayout::new(
+                UIMargin::new(10.0, 10.0, 10.0, 10.0),
+                5.0,
+                UILayoutAlignment::Start,
+            ))
+            .build();
+        let children = Vec::from_iter((0..3).map(|_| {
+            world
+                .create_entity()
+                .with(UIElement::default())
+                .with(Transform::new())
+                .with(UISize::from_vec2(Vec2::new(100.0, 50.0)))
+                .build()
+        }));
+
+        lay_out_children(
+            parent,
+            &children,
+            &Layouts {
+                horizontal: &world.read_storage(),
+                vertical: &world.read_storage(),
+                grid: &world.read_storage(),
+            },
+            &world.read_storage(),
+            &mut world.write_storage(),
+            &mut world.write_storage(),
+            |_, _, size| size,
+        );
+
+        let transforms = world.read_storage::<Transform>();
+        let positions = Vec::from_iter(
+            children
+                .iter()
+                .map(|&child| transforms.get(child).unwrap().position),
+        );
+        assert_eq!(
+            positions,
+            vec![
+                Vec3::new(10.0, 240.0, 0.0),
+                Vec3::new(10.0, 185.0, 0.0),
+                Vec3::new(10.0, 130.0, 0.0),
+            ]
+        );
+    }
 }
@@ -0,0 +1,57 @@
+use crate::{
+    event::{event_types, EventHandler},
+    gfx::UITextRenderer,
+    ui::UILocalizedText,
+    ContextHandle,
+};
+use specs::prelude::*;
+use std::{cell::Cell, rc::Rc};
+
+/// Sets the texts of [`UILocalizedText`]s that have changed, or all of them after a
+/// [`LocaleChanged`](event_types::LocaleChanged) event.
+pub struct UpdateUILocalizedText {
+    ctx: ContextHandle,
+    is_locale_changed: Rc<Cell<bool>>,
+}
+
+impl UpdateUILocalizedText {
+    pub fn new(ctx: ContextHandle) -> Self {
+        let is_locale_changed = Rc::new(Cell::new(false));
+        ctx.event_mgr().add_handler(EventHandler::new({
+            let is_locale_changed = is_locale_changed.clone();
+            move |_: &event_types::LocaleChanged| is_locale_changed.set(true)
+        }));
+
+        Self {
+            ctx,
+            is_locale_changed,
+        }
+    }
+}
+
+impl<'a> System<'a> for UpdateUILocalizedText {
+    type SystemData = (
+        WriteStorage<'a, UILocalizedText>,
+        WriteStorage<'a, UITextRenderer>,
+    );
+
+    fn run(&mut self, (mut localized_texts, mut text_renderers): Self::SystemData) {
+        let is_locale_changed = self.is_locale_changed.take();
+        let localization_mgr = self.ctx.localization_mgr();
+
+        for (localized_text, text_renderer) in (&mut localized_texts, &mut text_renderers).join() {
+            if !is_locale_changed && !localized_text.is_dirty() {
+                continue;
+            }
+
+            let args = Vec::from_iter(
+                localized_text
+                    .args()
+                    .iter()
+                    .map(|(name, value)| (name.as_str(), value.as_str())),
+            );
+            text_renderer.set_text(localization_mgr.tr_args(localized_text.key(), &args));
+            localized_text.reset_dirty();
+        }
+    }
+}
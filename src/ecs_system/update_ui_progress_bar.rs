@@ -0,0 +1,71 @@
+use crate::{
+    object::{ObjectHierarchy, ObjectId},
+    ui::{UIAnchor, UIElement, UIProgressBar, UISlider},
+    ContextHandle,
+};
+use specs::prelude::*;
+
+/// Drives the anchors of the fill and handle objects from the values of progress bars and sliders.
+/// It must run before [`UpdateUIElement`](super::update_ui_element::UpdateUIElement).
+pub struct UpdateUIProgressBar {
+    ctx: ContextHandle,
+}
+
+impl UpdateUIProgressBar {
+    pub fn new(ctx: ContextHandle) -> Self {
+        Self { ctx }
+    }
+}
+
+impl<'a> System<'a> for UpdateUIProgressBar {
+    type SystemData = (
+        ReadStorage<'a, UIProgressBar>,
+        ReadStorage<'a, UISlider>,
+        WriteStorage<'a, UIElement>,
+    );
+
+    fn run(&mut self, (progress_bars, sliders, mut elements): Self::SystemData) {
+        let mut object_mgr = self.ctx.object_mgr_mut();
+        let hierarchy = object_mgr.object_hierarchy_mut();
+
+        for (progress_bar, slider) in (&progress_bars, sliders.maybe()).join() {
+            let value = progress_bar.value.clamp(0.0, 1.0);
+
+            update_anchor(
+                progress_bar.fill,
+                progress_bar.fill_direction.fill_anchor(value),
+                hierarchy,
+                &mut elements,
+            );
+
+            if let Some(slider) = slider {
+                update_anchor(
+                    slider.handle,
+                    progress_bar.fill_direction.point_anchor(value),
+                    hierarchy,
+                    &mut elements,
+                );
+            }
+        }
+    }
+}
+
+fn update_anchor(
+    object: ObjectId,
+    anchor: UIAnchor,
+    hierarchy: &mut ObjectHierarchy,
+    elements: &mut WriteStorage<UIElement>,
+) {
+    let element = if let Some(element) = elements.get_mut(hierarchy.entity(object)) {
+        element
+    } else {
+        return;
+    };
+
+    if element.anchor == anchor {
+        return;
+    }
+
+    element.anchor = anchor;
+    hierarchy.set_dirty(object);
+}
@@ -138,3 +138,77 @@ fn compute_pair(
     size.width = width;
     size.height = height;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Vec2;
+    use winit::dpi::PhysicalSize;
+
+    fn resolve_root_size(world: &World, root: Entity, screen_mgr: &ScreenManager) -> UISize {
+        let pair = Pair {
+            index: 0,
+            parent: None,
+            child: root,
+        };
+        compute_pair(
+            pair,
+            screen_mgr,
+            &world.read_storage(),
+            &mut world.write_storage(),
+            &mut world.write_storage(),
+        );
+
+        *world.read_storage::<UISize>().get(root).unwrap()
+    }
+
+    #[test]
+    fn root_size_follows_scale_factor_changes() {
+        let mut world = World::new();
+        world.register::<Transform>();
+        world.register::<UIScaler>();
+        world.register::<UISize>();
+
+        let root = world
+            .create_entity()
+            .with(Transform::new())
+            .with(UIScaler {
+                mode: UIScaleMode::Stretch,
+                reference_size: Vec2::new(1280.0, 720.0),
+            })
+            .with(UISize::new())
+            .build();
+        let mut screen_mgr = ScreenManager::new(1280, 720);
+        assert_eq!(
+            resolve_root_size(&world, root, &screen_mgr).to_vec2(),
+            Vec2::new(1280.0, 720.0)
+        );
+
+        // The window is dragged onto a 2x monitor and keeps its physical size, as winit reports it: the
+        // scale factor changes first, then the window is resized to the suggested size.
+        screen_mgr.update_scale_factor(2.0, PhysicalSize::new(1280, 720));
+        screen_mgr.update_size(PhysicalSize::new(1280, 720));
+        assert!(screen_mgr.is_dirty());
+        assert_eq!(
+            resolve_root_size(&world, root, &screen_mgr).to_vec2(),
+            Vec2::new(640.0, 360.0)
+        );
+        assert_eq!(
+            world
+                .read_storage::<Transform>()
+                .get(root)
+                .unwrap()
+                .position,
+            Vec3::new(-320.0, -180.0, 0.0)
+        );
+
+        // And back onto a 1x monitor.
+        screen_mgr.reset_dirty();
+        screen_mgr.update_scale_factor(1.0, PhysicalSize::new(1280, 720));
+        assert!(screen_mgr.is_dirty());
+        assert_eq!(
+            resolve_root_size(&world, root, &screen_mgr).to_vec2(),
+            Vec2::new(1280.0, 720.0)
+        );
+    }
+}
@@ -1,5 +1,54 @@
+use crate::math::Vec2;
+
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Update;
 
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct LateUpdate;
+
+/// Dispatched at the start of the frame after [`TimeManager::pause`](crate::time::TimeManager::pause).
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Paused;
+
+/// Dispatched at the start of the frame after [`TimeManager::resume`](crate::time::TimeManager::resume).
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Resumed;
+
+/// Dispatched at the start of the frame after the locale, the default locale or one of their string tables has
+/// changed. See [`LocalizationManager`](crate::localization::LocalizationManager).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LocaleChanged {
+    pub locale: String,
+}
+
+/// Dispatched at the start of the frame after objects have been added, removed, re-parented or renamed, e.g. to
+/// keep a view of the hierarchy up to date without rebuilding it every frame.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HierarchyChanged;
+
+/// Dispatched after the screen size or the scale factor has changed, once the renderer has been resized.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScreenResized {
+    pub logical: Vec2,
+    pub physical: Vec2,
+    pub scale_factor: f64,
+}
+
+/// Dispatched once the gfx device is lost, before the engine loop exits. GPU resources cannot be used anymore, so
+/// it is the last chance to save the state of the game.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DeviceLost;
+
+/// Dispatched after a device error other than the loss of the device has been captured while rendering a frame.
+/// The frame may be incomplete, but the engine keeps running.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GfxErrorCaptured {
+    pub error: crate::gfx::GfxError,
+}
+
+/// Dispatched after a [`SecondaryWindow`](crate::gfx::SecondaryWindow) has been closed by the user and removed.
+/// Cameras targeting it are skipped from then on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SecondaryWindowClosed {
+    pub window_id: winit::window::WindowId,
+}
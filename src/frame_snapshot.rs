@@ -0,0 +1,173 @@
+use crate::{
+    gfx::{SafeAreaInsets, ScreenManager},
+    input::{InputDevice, InputManager, KEY_NAMES},
+    math::Vec2,
+    time::TimeManager,
+};
+use std::time::Duration;
+
+// The keys down are kept in a `u128`, one bit per key.
+const _: () = assert!(KEY_NAMES.len() <= 128);
+
+/// The names of the mouse buttons that [`InputSnapshot`] keeps, in the order of their bits.
+const MOUSE_BUTTON_NAMES: [&str; 3] = ["button:left", "button:right", "button:middle"];
+
+/// The clocks of the frame, as of [`TimeManager::update`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeSnapshot {
+    /// The index of the frame, counting from 1. It is 0 before the first frame.
+    pub frame_index: u64,
+    pub time: Duration,
+    pub delta_time: Duration,
+    pub unscaled_delta_time: Duration,
+    pub ui_time: Duration,
+    pub ui_delta_time: Duration,
+    pub time_scale: f64,
+    pub is_paused: bool,
+}
+
+/// The size of the screen at the start of the frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScreenSnapshot {
+    pub logical_size: Vec2,
+    pub physical_size: Vec2,
+    pub scale_factor: f64,
+    pub safe_area_insets: SafeAreaInsets,
+}
+
+/// The state of the keyboard and the mouse, as of the input poll of the frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InputSnapshot {
+    /// The cursor position, in physical pixels.
+    pub mouse_position: Vec2,
+    /// How far the cursor moved in the frame, in physical pixels.
+    pub mouse_delta: Vec2,
+    /// The raw motion of the mouse in the frame, which keeps reporting while the cursor is locked.
+    pub mouse_motion: Vec2,
+    pub scroll: Vec2,
+    mouse_buttons_down: u8,
+    keys_down: u128,
+}
+
+impl InputSnapshot {
+    /// Returns whether the key of the given [`Keyboard`](crate::input::Keyboard) input name is held down.
+    pub fn is_key_down(&self, name: &str) -> bool {
+        bit_index(&KEY_NAMES, name).is_some_and(|index| self.keys_down & (1 << index) != 0)
+    }
+
+    /// Returns whether the mouse button of the given input name, e.g. `"button:left"`, is held down.
+    pub fn is_mouse_button_down(&self, name: &str) -> bool {
+        bit_index(&MOUSE_BUTTON_NAMES, name)
+            .is_some_and(|index| self.mouse_buttons_down & (1 << index) != 0)
+    }
+}
+
+/// The state of the managers that almost every system reads, copied once per frame so that it can be read
+/// without borrowing them. Read it with [`Context::frame`](crate::Context::frame).
+///
+/// It is captured after the clocks are updated and the input is polled, before the update systems run. Changes
+/// made during the frame, e.g. a new time scale or safe area, only show up in the snapshot of the next frame;
+/// borrow the managers themselves where that matters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameSnapshot {
+    pub time: TimeSnapshot,
+    pub screen: ScreenSnapshot,
+    pub input: InputSnapshot,
+}
+
+impl FrameSnapshot {
+    /// Copies the state of the managers.
+    pub fn capture(
+        frame_index: u64,
+        time_mgr: &TimeManager,
+        screen_mgr: &ScreenManager,
+        input_mgr: &InputManager,
+    ) -> Self {
+        let keyboard = input_mgr.keyboard();
+        let mouse = input_mgr.mouse();
+        let keys_down = keyboard
+            .inputs()
+            .iter()
+            .enumerate()
+            .filter(|(_, input)| input.value != 0.0)
+            .fold(0, |bits, (index, _)| bits | 1 << index);
+        let mouse_buttons_down = MOUSE_BUTTON_NAMES
+            .iter()
+            .enumerate()
+            .filter(|(_, &name)| mouse.input(name).is_some_and(|input| input.value != 0.0))
+            .fold(0, |bits, (index, _)| bits | 1 << index);
+        let mouse_value = |name: &str| mouse.input(name).map_or(0.0, |input| input.value);
+
+        Self {
+            time: TimeSnapshot {
+                frame_index,
+                time: time_mgr.time(),
+                delta_time: time_mgr.delta_time(),
+                unscaled_delta_time: time_mgr.unscaled_delta_time(),
+                ui_time: time_mgr.ui_time(),
+                ui_delta_time: time_mgr.ui_delta_time(),
+                time_scale: time_mgr.time_scale(),
+                is_paused: time_mgr.is_paused(),
+            },
+            screen: ScreenSnapshot {
+                logical_size: screen_mgr.logical_size(),
+                physical_size: screen_mgr.physical_size(),
+                scale_factor: screen_mgr.scale_factor(),
+                safe_area_insets: screen_mgr.safe_area_insets(),
+            },
+            input: InputSnapshot {
+                mouse_position: mouse.position(),
+                mouse_delta: mouse.delta(),
+                mouse_motion: mouse.motion(),
+                scroll: Vec2::new(mouse_value("scroll:x"), mouse_value("scroll:y")),
+                mouse_buttons_down,
+                keys_down,
+            },
+        }
+    }
+}
+
+fn bit_index(names: &[&str], name: &str) -> Option<usize> {
+    names.iter().position(|&candidate| candidate == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winit::{
+        dpi::PhysicalSize,
+        event::{ElementState, KeyboardInput, VirtualKeyCode},
+    };
+
+    #[allow(deprecated)]
+    fn press(input_mgr: &mut InputManager, virtual_keycode: VirtualKeyCode) {
+        input_mgr.keyboard_mut().handle_window_event(KeyboardInput {
+            scancode: 0,
+            state: ElementState::Pressed,
+            virtual_keycode: Some(virtual_keycode),
+            modifiers: Default::default(),
+        });
+    }
+
+    #[test]
+    fn captures_the_keys_down() {
+        let mut input_mgr = InputManager::new();
+        press(&mut input_mgr, VirtualKeyCode::W);
+        press(&mut input_mgr, VirtualKeyCode::RShift);
+        input_mgr.poll();
+
+        let mut screen_mgr = ScreenManager::new(800, 600);
+        screen_mgr.update_scale_factor(2.0, PhysicalSize::new(1600, 1200));
+
+        let snapshot = FrameSnapshot::capture(3, &TimeManager::new(), &screen_mgr, &input_mgr);
+        assert_eq!(snapshot.time.frame_index, 3);
+        assert_eq!(snapshot.screen.logical_size, Vec2::new(800.0, 600.0));
+        assert_eq!(snapshot.screen.physical_size, Vec2::new(1600.0, 1200.0));
+
+        assert!(snapshot.input.is_key_down("w"));
+        assert!(snapshot.input.is_key_down("shift:r"));
+        assert!(!snapshot.input.is_key_down("s"));
+        assert!(!snapshot.input.is_key_down("unknown"));
+        assert!(!snapshot.input.is_mouse_button_down("button:left"));
+    }
+}
@@ -1,5 +1,9 @@
 use super::{BindGroupLayoutCache, ShaderHandle, ShaderManager};
-use std::{collections::HashMap, num::NonZeroU64};
+use asset::assets::STANDARD_SHADER;
+use std::{
+    collections::{BTreeSet, HashMap},
+    num::NonZeroU64,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct BuiltInShaderKey(NonZeroU64);
@@ -14,6 +18,46 @@ pub const BUILT_IN_SHADER_UI_ELEMENT_NORMAL: BuiltInShaderKey =
     BuiltInShaderKey::new(unsafe { NonZeroU64::new_unchecked(1) });
 pub const BUILT_IN_SHADER_UI_TEXT_NORMAL: BuiltInShaderKey =
     BuiltInShaderKey::new(unsafe { NonZeroU64::new_unchecked(11) });
+pub const BUILT_IN_SHADER_UI_TEXT_SDF: BuiltInShaderKey =
+    BuiltInShaderKey::new(unsafe { NonZeroU64::new_unchecked(12) });
+pub const BUILT_IN_SHADER_UI_TEXT_BITMAP: BuiltInShaderKey =
+    BuiltInShaderKey::new(unsafe { NonZeroU64::new_unchecked(13) });
+pub const BUILT_IN_SHADER_SPRITE: BuiltInShaderKey =
+    BuiltInShaderKey::new(unsafe { NonZeroU64::new_unchecked(21) });
+/// The standard lit shader without any features. See [`BuiltInShaderManager::standard_shader`] for the variants.
+pub const BUILT_IN_SHADER_STANDARD: BuiltInShaderKey =
+    BuiltInShaderKey::new(unsafe { NonZeroU64::new_unchecked(31) });
+
+/// The optional features of the standard shader. Each set of features is a distinct variant of the shader,
+/// so that the bindings of unused features are left out, and it gets its own pipelines.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StandardShaderFeatures {
+    pub has_normal_map: bool,
+    pub has_emissive: bool,
+    /// Skins the vertices by `joint_indices` and `joint_weights` with `joint_matrices`.
+    pub skinned: bool,
+    pub receive_shadows: bool,
+    /// Reflects an [`Environment`](super::Environment) cubemap.
+    pub has_environment: bool,
+}
+
+impl StandardShaderFeatures {
+    /// Returns the defines of the source that select the features.
+    pub fn defines(&self) -> BTreeSet<String> {
+        BTreeSet::from_iter(
+            [
+                (self.has_normal_map, "HAS_NORMAL_MAP"),
+                (self.has_emissive, "HAS_EMISSIVE"),
+                (self.skinned, "SKINNED"),
+                (self.receive_shadows, "RECEIVE_SHADOWS"),
+                (self.has_environment, "HAS_ENVIRONMENT"),
+            ]
+            .into_iter()
+            .filter(|(is_enabled, _)| *is_enabled)
+            .map(|(_, define)| define.to_owned()),
+        )
+    }
+}
 
 pub struct BuiltInShaderManager {
     shaders: HashMap<BuiltInShaderKey, ShaderHandle>,
@@ -43,6 +87,29 @@ impl BuiltInShaderManager {
             BUILT_IN_SHADER_UI_TEXT_NORMAL,
             include_str!("./built_in_shaders/ui_text.normal.wgsl"),
         );
+        self.add_shader(
+            shader_mgr,
+            bind_group_layout_cache,
+            BUILT_IN_SHADER_UI_TEXT_SDF,
+            include_str!("./built_in_shaders/ui_text.sdf.wgsl"),
+        );
+        self.add_shader(
+            shader_mgr,
+            bind_group_layout_cache,
+            BUILT_IN_SHADER_UI_TEXT_BITMAP,
+            include_str!("./built_in_shaders/ui_text.bitmap.wgsl"),
+        );
+        self.add_shader(
+            shader_mgr,
+            bind_group_layout_cache,
+            BUILT_IN_SHADER_SPRITE,
+            include_str!("./built_in_shaders/sprite.wgsl"),
+        );
+
+        let standard_shader =
+            self.standard_shader(shader_mgr, bind_group_layout_cache, Default::default());
+        self.shaders
+            .insert(BUILT_IN_SHADER_STANDARD, standard_shader);
     }
 
     fn add_shader(
@@ -61,4 +128,65 @@ impl BuiltInShaderManager {
     pub fn find_shader(&self, key: BuiltInShaderKey) -> Option<ShaderHandle> {
         self.shaders.get(&key).cloned()
     }
+
+    /// Returns the variant of the standard shader for the features. Variants are created on first use,
+    /// and shared afterwards.
+    pub fn standard_shader(
+        &self,
+        shader_mgr: &ShaderManager,
+        bind_group_layout_cache: &mut BindGroupLayoutCache,
+        features: StandardShaderFeatures,
+    ) -> ShaderHandle {
+        shader_mgr
+            .create_shader_variant(
+                bind_group_layout_cache,
+                STANDARD_SHADER.source,
+                &features.defines(),
+            )
+            .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use asset::assets::specialize_shader_source;
+    use naga::{
+        front::wgsl::parse_str,
+        valid::{Capabilities, ValidationFlags, Validator},
+    };
+
+    #[test]
+    fn standard_shader_variants_bind_only_their_features() {
+        for bits in 0..32 {
+            let features = StandardShaderFeatures {
+                has_normal_map: bits & 1 != 0,
+                has_emissive: bits & 2 != 0,
+                skinned: bits & 4 != 0,
+                receive_shadows: bits & 8 != 0,
+                has_environment: bits & 16 != 0,
+            };
+            let source =
+                specialize_shader_source(STANDARD_SHADER.source, &features.defines()).unwrap();
+            let module = parse_str(&source)
+                .unwrap_or_else(|err| panic!("{:?}: {}", features, err.emit_to_string(&source)));
+            Validator::new(ValidationFlags::all(), Capabilities::empty())
+                .validate(&module)
+                .unwrap_or_else(|err| panic!("{:?}: {:?}", features, err));
+
+            // The camera, the surface, the lighting and the base color texture with its sampler.
+            let mut expected = 5;
+            expected += if features.has_normal_map { 2 } else { 0 };
+            expected += if features.has_emissive { 2 } else { 0 };
+            expected += if features.skinned { 1 } else { 0 };
+            expected += if features.receive_shadows { 2 } else { 0 };
+            expected += if features.has_environment { 2 } else { 0 };
+            let bindings = module
+                .global_variables
+                .iter()
+                .filter(|(_, global)| global.binding.is_some())
+                .count();
+            assert_eq!(bindings, expected, "{:?}", features);
+        }
+    }
 }
@@ -0,0 +1,36 @@
+
+struct SkyboxView {
+  inverse_view_projection: mat4x4<f32>,
+  forward: vec4<f32>,
+};
+
+@group(0) @binding(0) var<uniform> view: SkyboxView;
+@group(0) @binding(1) var skybox_texture: texture_cube<f32>;
+@group(0) @binding(2) var skybox_sampler: sampler;
+
+struct VertexOutput {
+  @builtin(position) position: vec4<f32>,
+  @location(0) ndc: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+  var out: VertexOutput;
+  let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+  out.ndc = uv * 2.0 - 1.0;
+  out.position = vec4<f32>(out.ndc, 1.0, 1.0);
+  return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+  let near = view.inverse_view_projection * vec4<f32>(in.ndc, 0.0, 1.0);
+  let far = view.inverse_view_projection * vec4<f32>(in.ndc, 1.0, 1.0);
+  var direction = far.xyz / far.w - near.xyz / near.w;
+
+  if (dot(direction, view.forward.xyz) < 0.0) {
+    direction = -direction;
+  }
+
+  return textureSample(skybox_texture, skybox_sampler, direction);
+}
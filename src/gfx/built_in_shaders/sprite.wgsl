@@ -0,0 +1,47 @@
+
+@group(0) @binding(0) var<uniform> camera_transform: mat4x4<f32>;
+@group(1) @binding(0) var sprite_texture: texture_2d<f32>;
+@group(2) @binding(0) var sprite_sampler: sampler;
+
+struct InstanceInput {
+  @location(0) sprite_origin: vec3<f32>,
+  @location(1) sprite_axis_x: vec3<f32>,
+  @location(2) sprite_axis_y: vec3<f32>,
+  @location(3) sprite_size: vec2<f32>,
+  @location(4) sprite_offset: vec2<f32>,
+  @location(5) sprite_uv_min: vec2<f32>,
+  @location(6) sprite_uv_max: vec2<f32>,
+  @location(7) sprite_color: vec4<f32>,
+};
+
+struct VertexInput {
+  @location(8) position: vec3<f32>,
+};
+
+struct VertexOutput {
+  @builtin(position) position: vec4<f32>,
+  @location(0) color: vec4<f32>,
+  @location(1) uv: vec2<f32>,
+};
+
+struct FragmentOutput {
+  @location(0) color: vec4<f32>,
+};
+
+@vertex
+fn vs_main(instance: InstanceInput, vertex: VertexInput) -> VertexOutput {
+  var out: VertexOutput;
+  let local = instance.sprite_offset + instance.sprite_size * vertex.position.xy;
+  let world = instance.sprite_origin + instance.sprite_axis_x * local.x + instance.sprite_axis_y * local.y;
+  out.position = camera_transform * vec4<f32>(world, 1.0);
+  out.color = instance.sprite_color;
+  out.uv = instance.sprite_uv_min + (instance.sprite_uv_max - instance.sprite_uv_min) * vertex.position.xy;
+  return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> FragmentOutput {
+  var out: FragmentOutput;
+  out.color = in.color * textureSample(sprite_texture, sprite_sampler, in.uv);
+  return out;
+}
@@ -0,0 +1,67 @@
+
+@group(0) @binding(0) var<uniform> screen_size: vec2<f32>;
+@group(1) @binding(0) var sprite_texture: texture_2d<f32>;
+@group(2) @binding(0) var sprite_sampler: sampler;
+
+struct InstanceInput {
+  @location(0) transform_row_0: vec4<f32>,
+  @location(1) transform_row_1: vec4<f32>,
+  @location(2) transform_row_2: vec4<f32>,
+  @location(3) transform_row_3: vec4<f32>,
+  @location(4) sprite_size: vec2<f32>,
+  @location(5) sprite_offset: vec2<f32>,
+  @location(6) sprite_uv_min: vec2<f32>,
+  @location(7) sprite_uv_max: vec2<f32>,
+  @location(8) sprite_color: vec4<f32>,
+  @location(9) glyph_thickness: f32,
+  @location(10) glyph_px_range: f32,
+  @location(11) glyph_outline_color: vec4<f32>,
+  @location(12) glyph_outline_width: f32,
+};
+
+struct VertexInput {
+  @location(13) position: vec3<f32>,
+};
+
+struct VertexOutput {
+  @builtin(position) position: vec4<f32>,
+  @location(0) color: vec4<f32>,
+  @location(1) uv: vec2<f32>,
+  @location(2) thickness: f32,
+  @location(3) px_range: f32,
+  @location(4) outline_color: vec4<f32>,
+  @location(5) outline_width: f32,
+};
+
+struct FragmentOutput {
+  @location(0) color: vec4<f32>,
+};
+
+@vertex
+fn vs_main(instance: InstanceInput, vertex: VertexInput) -> VertexOutput {
+  var out: VertexOutput;
+  let transform = mat4x4<f32>(instance.transform_row_0, instance.transform_row_1, instance.transform_row_2, instance.transform_row_3);
+  out.position = (transform * (vec4<f32>(instance.sprite_offset, 0.0, 0.0) + vec4<f32>(instance.sprite_size, 1.0, 1.0) * vec4<f32>(vertex.position, 1.0))) / vec4<f32>(screen_size * 0.5, 1.0, 1.0);
+  out.color = instance.sprite_color;
+  out.uv = instance.sprite_uv_min + (instance.sprite_uv_max - instance.sprite_uv_min) * vertex.position.xy;
+  out.thickness = instance.glyph_thickness;
+  out.px_range = instance.glyph_px_range;
+  out.outline_color = instance.glyph_outline_color;
+  out.outline_width = instance.glyph_outline_width;
+  return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> FragmentOutput {
+  var out: FragmentOutput;
+  let distance = textureSample(sprite_texture, sprite_sampler, in.uv).r;
+  // The change of the distance per screen pixel keeps the edge sharp at any scale.
+  let half_width = max(fwidth(distance) * in.px_range, 0.0001) * 0.5;
+  let edge = 1.0 - in.thickness;
+  let fill_alpha = smoothstep(edge - half_width, edge + half_width, distance);
+  let outline_edge = edge - in.outline_width;
+  let outline_alpha = smoothstep(outline_edge - half_width, outline_edge + half_width, distance);
+  let color = mix(in.outline_color, in.color, fill_alpha);
+  out.color = vec4<f32>(color.rgb, color.a * outline_alpha);
+  return out;
+}
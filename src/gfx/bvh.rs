@@ -0,0 +1,505 @@
+use crate::math::{Frustum, Ray, AABB};
+use specs::Entity;
+use std::collections::HashMap;
+
+const NULL: u32 = u32::MAX;
+
+#[derive(Debug, Clone)]
+struct BvhNode {
+    /// For leaves, the fattened AABB of the entity. For internal nodes, the union of the children.
+    aabb: AABB,
+    parent: u32,
+    children: [u32; 2],
+    /// Zero for leaves, `-1` for free nodes.
+    height: i32,
+    entity: Option<Entity>,
+}
+
+impl BvhNode {
+    fn is_leaf(&self) -> bool {
+        self.children[0] == NULL
+    }
+}
+
+/// A dynamic bounding volume hierarchy over the world-space AABBs of renderers.
+///
+/// Leaves keep a fattened AABB, so that small movements don't touch the tree at all; a leaf is reinserted only
+/// when its object moves out of it. The tree is kept balanced by rotations on insertion and removal, and
+/// [`Bvh::rebuild_incrementally`] reinserts a few leaves per call to recover from the quality lost over time.
+#[derive(Debug, Clone)]
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+    free_nodes: Vec<u32>,
+    root: u32,
+    leaves: HashMap<Entity, u32>,
+    margin: f32,
+    rebuild_cursor: usize,
+}
+
+impl Bvh {
+    /// The default margin that the AABBs of leaves are fattened by.
+    pub const DEFAULT_MARGIN: f32 = 0.1;
+
+    pub fn new() -> Self {
+        Self::with_margin(Self::DEFAULT_MARGIN)
+    }
+
+    pub fn with_margin(margin: f32) -> Self {
+        Self {
+            nodes: Vec::new(),
+            free_nodes: Vec::new(),
+            root: NULL,
+            leaves: HashMap::new(),
+            margin,
+            rebuild_cursor: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.leaves.contains_key(&entity)
+    }
+
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.leaves.keys().copied()
+    }
+
+    /// Returns the height of the tree, which is zero for a tree with one or no leaf.
+    pub fn height(&self) -> u32 {
+        if self.root == NULL {
+            0
+        } else {
+            self.nodes[self.root as usize].height as u32
+        }
+    }
+
+    /// Inserts the entity with the given world-space AABB, or updates it if it's already in the tree.
+    pub fn insert(&mut self, entity: Entity, aabb: AABB) {
+        if self.update(entity, aabb) {
+            return;
+        }
+
+        let leaf = self.allocate_node(aabb.expanded(self.margin));
+        self.nodes[leaf as usize].entity = Some(entity);
+        self.leaves.insert(entity, leaf);
+        self.insert_leaf(leaf);
+    }
+
+    /// Updates the world-space AABB of the entity. The tree is changed only if the AABB leaves the fattened AABB
+    /// of the entity. Returns `false` if the entity is not in the tree.
+    pub fn update(&mut self, entity: Entity, aabb: AABB) -> bool {
+        let leaf = if let Some(&leaf) = self.leaves.get(&entity) {
+            leaf
+        } else {
+            return false;
+        };
+
+        if self.nodes[leaf as usize].aabb.contains_aabb(&aabb) {
+            return true;
+        }
+
+        self.remove_leaf(leaf);
+        self.nodes[leaf as usize].aabb = aabb.expanded(self.margin);
+        self.insert_leaf(leaf);
+        true
+    }
+
+    /// Removes the entity from the tree. Returns `false` if the entity is not in the tree.
+    pub fn remove(&mut self, entity: Entity) -> bool {
+        let leaf = if let Some(leaf) = self.leaves.remove(&entity) {
+            leaf
+        } else {
+            return false;
+        };
+
+        self.remove_leaf(leaf);
+        self.free_node(leaf);
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.free_nodes.clear();
+        self.root = NULL;
+        self.leaves.clear();
+        self.rebuild_cursor = 0;
+    }
+
+    /// Reinserts up to `count` leaves, continuing from where the last call stopped.
+    /// Calling it every frame with a small count keeps the tree in shape without a full rebuild.
+    pub fn rebuild_incrementally(&mut self, count: usize) {
+        let mut remaining = count.min(self.leaves.len());
+        let mut visited = 0;
+
+        while remaining != 0 && visited < self.nodes.len() {
+            if self.nodes.len() <= self.rebuild_cursor {
+                self.rebuild_cursor = 0;
+            }
+
+            let index = self.rebuild_cursor as u32;
+            self.rebuild_cursor += 1;
+            visited += 1;
+
+            let node = &self.nodes[index as usize];
+
+            if node.height != 0 || node.entity.is_none() {
+                continue;
+            }
+
+            self.remove_leaf(index);
+            self.insert_leaf(index);
+            remaining -= 1;
+        }
+    }
+
+    /// Collects the entities whose AABB may intersect the frustum. The output is not cleared.
+    pub fn query_frustum(&self, frustum: &Frustum, output: &mut Vec<Entity>) {
+        if self.root == NULL {
+            return;
+        }
+
+        let mut stack = Vec::with_capacity(64);
+        stack.push(self.root);
+
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index as usize];
+
+            if !frustum.intersects_aabb(&node.aabb) {
+                continue;
+            }
+
+            if node.is_leaf() {
+                output.extend(node.entity);
+            } else if frustum.contains_aabb(&node.aabb) {
+                // Everything below is visible, so the planes don't have to be tested any further.
+                self.collect_leaves(index, output);
+            } else {
+                stack.extend(node.children);
+            }
+        }
+    }
+
+    /// Collects the entities whose AABB may be hit by the ray. The output is not cleared.
+    pub fn query_ray(&self, ray: &Ray, output: &mut Vec<Entity>) {
+        if self.root == NULL {
+            return;
+        }
+
+        let mut stack = Vec::with_capacity(64);
+        stack.push(self.root);
+
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index as usize];
+
+            if ray.intersect_aabb(&node.aabb).is_none() {
+                continue;
+            }
+
+            if node.is_leaf() {
+                output.extend(node.entity);
+            } else {
+                stack.extend(node.children);
+            }
+        }
+    }
+
+    fn collect_leaves(&self, index: u32, output: &mut Vec<Entity>) {
+        let mut stack = Vec::with_capacity(64);
+        stack.push(index);
+
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index as usize];
+
+            if node.is_leaf() {
+                output.extend(node.entity);
+            } else {
+                stack.extend(node.children);
+            }
+        }
+    }
+
+    fn allocate_node(&mut self, aabb: AABB) -> u32 {
+        let node = BvhNode {
+            aabb,
+            parent: NULL,
+            children: [NULL, NULL],
+            height: 0,
+            entity: None,
+        };
+
+        if let Some(index) = self.free_nodes.pop() {
+            self.nodes[index as usize] = node;
+            index
+        } else {
+            self.nodes.push(node);
+            (self.nodes.len() - 1) as u32
+        }
+    }
+
+    fn free_node(&mut self, index: u32) {
+        let node = &mut self.nodes[index as usize];
+        node.height = -1;
+        node.entity = None;
+        self.free_nodes.push(index);
+    }
+
+    fn insert_leaf(&mut self, leaf: u32) {
+        if self.root == NULL {
+            self.root = leaf;
+            self.nodes[leaf as usize].parent = NULL;
+            return;
+        }
+
+        // Descends to the sibling that costs the least, by the surface area heuristic.
+        let leaf_aabb = self.nodes[leaf as usize].aabb;
+        let mut index = self.root;
+
+        while !self.nodes[index as usize].is_leaf() {
+            let node = &self.nodes[index as usize];
+            let area = node.aabb.surface_area();
+            let combined_area = AABB::union(&node.aabb, &leaf_aabb).surface_area();
+
+            // The cost of creating a new parent for this node and the leaf.
+            let cost = 2.0 * combined_area;
+            // The minimum cost of pushing the leaf further down the tree.
+            let inheritance_cost = 2.0 * (combined_area - area);
+
+            let child_cost = |child: u32| {
+                let child = &self.nodes[child as usize];
+                let combined_area = AABB::union(&child.aabb, &leaf_aabb).surface_area();
+
+                if child.is_leaf() {
+                    combined_area + inheritance_cost
+                } else {
+                    combined_area - child.aabb.surface_area() + inheritance_cost
+                }
+            };
+            let [lhs, rhs] = node.children;
+            let lhs_cost = child_cost(lhs);
+            let rhs_cost = child_cost(rhs);
+
+            if cost < lhs_cost && cost < rhs_cost {
+                break;
+            }
+
+            index = if lhs_cost < rhs_cost { lhs } else { rhs };
+        }
+
+        let sibling = index;
+        let old_parent = self.nodes[sibling as usize].parent;
+        let new_parent =
+            self.allocate_node(AABB::union(&leaf_aabb, &self.nodes[sibling as usize].aabb));
+        {
+            let height = self.nodes[sibling as usize].height + 1;
+            let node = &mut self.nodes[new_parent as usize];
+            node.parent = old_parent;
+            node.children = [sibling, leaf];
+            node.height = height;
+        }
+
+        if old_parent == NULL {
+            self.root = new_parent;
+        } else {
+            self.replace_child(old_parent, sibling, new_parent);
+        }
+
+        self.nodes[sibling as usize].parent = new_parent;
+        self.nodes[leaf as usize].parent = new_parent;
+
+        self.fix_upwards(new_parent);
+    }
+
+    fn remove_leaf(&mut self, leaf: u32) {
+        if leaf == self.root {
+            self.root = NULL;
+            return;
+        }
+
+        let parent = self.nodes[leaf as usize].parent;
+        let grand_parent = self.nodes[parent as usize].parent;
+        let [lhs, rhs] = self.nodes[parent as usize].children;
+        let sibling = if lhs == leaf { rhs } else { lhs };
+
+        if grand_parent == NULL {
+            self.root = sibling;
+            self.nodes[sibling as usize].parent = NULL;
+            self.free_node(parent);
+        } else {
+            self.replace_child(grand_parent, parent, sibling);
+            self.nodes[sibling as usize].parent = grand_parent;
+            self.free_node(parent);
+            self.fix_upwards(grand_parent);
+        }
+
+        self.nodes[leaf as usize].parent = NULL;
+    }
+
+    fn replace_child(&mut self, parent: u32, old_child: u32, new_child: u32) {
+        let children = &mut self.nodes[parent as usize].children;
+
+        if children[0] == old_child {
+            children[0] = new_child;
+        } else {
+            children[1] = new_child;
+        }
+    }
+
+    /// Rebalances and refits the node and all of its ancestors.
+    fn fix_upwards(&mut self, mut index: u32) {
+        while index != NULL {
+            index = self.balance(index);
+            self.refit(index);
+            index = self.nodes[index as usize].parent;
+        }
+    }
+
+    fn refit(&mut self, index: u32) {
+        let [lhs, rhs] = self.nodes[index as usize].children;
+        let (lhs, rhs) = (&self.nodes[lhs as usize], &self.nodes[rhs as usize]);
+        let height = 1 + lhs.height.max(rhs.height);
+        let aabb = AABB::union(&lhs.aabb, &rhs.aabb);
+
+        let node = &mut self.nodes[index as usize];
+        node.height = height;
+        node.aabb = aabb;
+    }
+
+    /// Rotates the taller child up if the subtree of the node is imbalanced. Returns the new root of the subtree.
+    fn balance(&mut self, a: u32) -> u32 {
+        if self.nodes[a as usize].is_leaf() || self.nodes[a as usize].height < 2 {
+            return a;
+        }
+
+        let [b, c] = self.nodes[a as usize].children;
+        let balance = self.nodes[c as usize].height - self.nodes[b as usize].height;
+
+        if 1 < balance {
+            self.rotate_up(a, c, 1)
+        } else if balance < -1 {
+            self.rotate_up(a, b, 0)
+        } else {
+            a
+        }
+    }
+
+    /// Rotates the child at the given slot of `a` up to the place of `a`.
+    /// The taller grandchild stays with the child; the shorter one takes the place of the child under `a`.
+    fn rotate_up(&mut self, a: u32, child: u32, slot: usize) -> u32 {
+        let [f, g] = self.nodes[child as usize].children;
+        let parent = self.nodes[a as usize].parent;
+
+        self.nodes[child as usize].children[0] = a;
+        self.nodes[child as usize].parent = parent;
+        self.nodes[a as usize].parent = child;
+
+        if parent == NULL {
+            self.root = child;
+        } else {
+            self.replace_child(parent, a, child);
+        }
+
+        let (taller, shorter) = if self.nodes[g as usize].height < self.nodes[f as usize].height {
+            (f, g)
+        } else {
+            (g, f)
+        };
+
+        self.nodes[child as usize].children[1] = taller;
+        self.nodes[a as usize].children[slot] = shorter;
+        self.nodes[shorter as usize].parent = a;
+
+        self.refit(a);
+        self.refit(child);
+        child
+    }
+}
+
+impl Default for Bvh {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{Mat4, Vec3};
+    use specs::{Builder, World, WorldExt};
+
+    fn unit(center: Vec3) -> AABB {
+        AABB::new(
+            center - Vec3::new(0.5, 0.5, 0.5),
+            center + Vec3::new(0.5, 0.5, 0.5),
+        )
+    }
+
+    #[test]
+    fn queries_match_linear_tests() {
+        let mut world = World::new();
+        let mut bvh = Bvh::new();
+        let mut boxes = Vec::new();
+
+        for index in 0..500 {
+            let entity = world.create_entity().build();
+            let center = Vec3::new(
+                (index % 10) as f32 * 3.0 - 15.0,
+                (index / 10 % 10) as f32 * 3.0 - 15.0,
+                -((index / 100) as f32 * 3.0),
+            );
+            bvh.insert(entity, unit(center));
+            boxes.push((entity, center));
+        }
+
+        // Moves some of them far enough to be reinserted, and removes some.
+        for (entity, center) in boxes.iter_mut().step_by(7) {
+            *center += Vec3::new(0.0, 0.0, -20.0);
+            bvh.insert(*entity, unit(*center));
+        }
+
+        for (entity, _) in boxes.iter().step_by(11) {
+            assert!(bvh.remove(*entity));
+        }
+
+        boxes.retain(|(entity, _)| bvh.contains(*entity));
+        bvh.rebuild_incrementally(100);
+        assert_eq!(bvh.len(), boxes.len());
+        assert!(bvh.height() < 32);
+
+        let sort = |mut entities: Vec<Entity>| {
+            entities.sort();
+            entities
+        };
+
+        let frustum =
+            Frustum::from_view_projection(&Mat4::perspective(60f32.to_radians(), 1.0, 0.1, 20.0));
+        let mut found = Vec::new();
+        bvh.query_frustum(&frustum, &mut found);
+        let expected = boxes
+            .iter()
+            .filter(|(_, center)| frustum.intersects_aabb(&unit(*center)))
+            .map(|(entity, _)| *entity)
+            .collect();
+        // Fattened AABBs may report a few more, but never miss one.
+        let found = sort(found);
+        assert!(sort(expected).iter().all(|entity| found.contains(entity)));
+
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 10.0), Vec3::FORWARD);
+        let mut found = Vec::new();
+        bvh.query_ray(&ray, &mut found);
+        let expected = boxes
+            .iter()
+            .filter(|(_, center)| ray.intersect_aabb(&unit(*center)).is_some())
+            .map(|(entity, _)| *entity)
+            .collect::<Vec<_>>();
+        assert!(!expected.is_empty());
+        let found = sort(found);
+        assert!(expected.iter().all(|entity| found.contains(entity)));
+    }
+}
@@ -1,23 +1,34 @@
-use super::{BindGroupLayoutCache, Color, ScreenManager};
-use crate::math::Mat4;
-use specs::{prelude::*, Component};
+use super::{
+    BindGroupLayoutCache, Color, PhysicalViewport, ScreenManager, SpriteSortAxis, TextureHandle,
+};
+use crate::math::{Mat4, Vec2, Vec3, Vec4};
+use codegen::Component;
+use specs::prelude::*;
 use std::{mem::size_of, sync::Arc};
 use wgpu::{
     BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayoutEntry, BindingResource,
     BindingType, Buffer, BufferAddress, BufferBinding, BufferBindingType, BufferDescriptor,
-    BufferSize, BufferUsages, Device, Queue, ShaderStages,
+    BufferSize, BufferUsages, Device, LoadOp, Queue, ShaderStages,
 };
+use winit::window::WindowId;
 use zerocopy::AsBytes;
 
 #[derive(Debug, Clone)]
 pub enum CameraClearMode {
+    /// Keeps the color, depth and stencil, so that the camera composites onto the results of the previous cameras.
     Keep,
     All {
         color: Color,
         depth: f32,
         stencil: u32,
     },
-    DepthOnly {
+    /// Keeps the color but clears the depth and stencil, e.g. for overlay cameras.
+    DepthOnly { depth: f32, stencil: u32 },
+    /// Draws the cubemap as the background before any geometry, and clears the depth and stencil.
+    /// The cubemap must be created by [`Texture::cubemap_from_images`](super::Texture::cubemap_from_images)
+    /// or [`Texture::cubemap_from_strip`](super::Texture::cubemap_from_strip) with a filterable format.
+    Skybox {
+        cubemap: TextureHandle,
         depth: f32,
         stencil: u32,
     },
@@ -39,6 +50,46 @@ impl CameraClearMode {
     pub fn depth_only(depth: f32, stencil: u32) -> Self {
         Self::DepthOnly { depth, stencil }
     }
+
+    pub fn skybox(cubemap: TextureHandle, depth: f32, stencil: u32) -> Self {
+        Self::Skybox {
+            cubemap,
+            depth,
+            stencil,
+        }
+    }
+
+    pub fn color_load_op(&self) -> LoadOp<wgpu::Color> {
+        match self {
+            Self::All { color, .. } => LoadOp::Clear(wgpu::Color {
+                r: color.r as f64,
+                g: color.g as f64,
+                b: color.b as f64,
+                a: color.a as f64,
+            }),
+            // The skybox covers the whole viewport, so the previous color is never visible.
+            Self::Skybox { .. } => LoadOp::Clear(wgpu::Color::BLACK),
+            Self::Keep | Self::DepthOnly { .. } => LoadOp::Load,
+        }
+    }
+
+    pub fn depth_load_op(&self) -> LoadOp<f32> {
+        match self {
+            Self::Keep => LoadOp::Load,
+            Self::All { depth, .. }
+            | Self::DepthOnly { depth, .. }
+            | Self::Skybox { depth, .. } => LoadOp::Clear(*depth),
+        }
+    }
+
+    pub fn stencil_load_op(&self) -> LoadOp<u32> {
+        match self {
+            Self::Keep => LoadOp::Load,
+            Self::All { stencil, .. }
+            | Self::DepthOnly { stencil, .. }
+            | Self::Skybox { stencil, .. } => LoadOp::Clear(*stencil),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -72,6 +123,80 @@ impl CameraProjection {
             Self::Perspective(projection) => projection.as_matrix(screen_mgr),
         }
     }
+
+    pub fn as_matrix_with_screen_size(&self, screen_size: Vec2) -> Mat4 {
+        match self {
+            Self::Orthographic(projection) => projection.as_matrix_with_screen_size(screen_size),
+            Self::Perspective(projection) => projection.as_matrix_with_screen_size(screen_size),
+        }
+    }
+
+    /// Builds a world-space ray that starts from the camera and passes through the given screen position.
+    /// The screen position must be in pixels, with the origin at the top-left corner of the screen.
+    /// Returns the origin and the normalized direction of the ray.
+    pub fn screen_to_ray(
+        &self,
+        transform_matrix: &Mat4,
+        screen_pos: Vec2,
+        screen_size: Vec2,
+    ) -> (Vec3, Vec3) {
+        let ndc = Vec2::new(
+            screen_pos.x / screen_size.x * 2.0 - 1.0,
+            1.0 - screen_pos.y / screen_size.y * 2.0,
+        );
+        let inverse_view_projection =
+            (transform_matrix.inversed() * self.as_matrix_with_screen_size(screen_size)).inversed();
+        let unproject = |z: f32| {
+            let point = Vec4::new(ndc.x, ndc.y, z, 1.0) * &inverse_view_projection;
+            Vec3::from(point / point.w)
+        };
+
+        let near = unproject(0.0);
+        let far = unproject(1.0);
+        let position = Vec3::from(transform_matrix.row(3));
+        let forward = Vec3::from(Vec4::from_vec3(Vec3::FORWARD, 0.0) * transform_matrix);
+
+        let mut direction = (far - near).normalized();
+
+        if Vec3::dot(direction, forward) < 0.0 {
+            direction = -direction;
+        }
+
+        match self {
+            // Rays of an orthographic camera are parallel, so they start from the camera plane.
+            Self::Orthographic(_) => (
+                near - direction * Vec3::dot(near - position, direction),
+                direction,
+            ),
+            Self::Perspective(_) => (position, direction),
+        }
+    }
+
+    /// Projects the given world-space position onto the screen.
+    /// The result is in pixels, with the origin at the top-left corner of the screen.
+    /// Returns `None` if the position is behind the camera.
+    pub fn world_to_screen(
+        &self,
+        transform_matrix: &Mat4,
+        world_pos: Vec3,
+        screen_size: Vec2,
+    ) -> Option<Vec2> {
+        let view_matrix = transform_matrix.inversed();
+        let view_pos = Vec4::from_vec3(world_pos, 1.0) * &view_matrix;
+
+        // The camera looks at the negative z-axis.
+        if 0.0 <= view_pos.z {
+            return None;
+        }
+
+        let clip_pos = view_pos * &self.as_matrix_with_screen_size(screen_size);
+        let ndc = Vec2::new(clip_pos.x / clip_pos.w, clip_pos.y / clip_pos.w);
+
+        Some(Vec2::new(
+            (ndc.x + 1.0) * 0.5 * screen_size.x,
+            (1.0 - ndc.y) * 0.5 * screen_size.y,
+        ))
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -83,7 +208,11 @@ pub struct CamereOrthographicProjection {
 
 impl CamereOrthographicProjection {
     pub fn as_matrix(&self, screen_mgr: &ScreenManager) -> Mat4 {
-        let aspect = screen_mgr.width() as f32 / screen_mgr.height() as f32;
+        self.as_matrix_with_screen_size(screen_mgr.logical_size())
+    }
+
+    pub fn as_matrix_with_screen_size(&self, screen_size: Vec2) -> Mat4 {
+        let aspect = screen_size.x / screen_size.y;
         Mat4::orthographic(
             self.width * -0.5,
             self.width * 0.5,
@@ -105,12 +234,18 @@ pub struct CameraPerspectiveProjection {
 
 impl CameraPerspectiveProjection {
     pub fn as_matrix(&self, screen_mgr: &ScreenManager) -> Mat4 {
+        self.as_matrix_with_screen_aspect(screen_mgr.aspect_ratio())
+    }
+
+    pub fn as_matrix_with_screen_size(&self, screen_size: Vec2) -> Mat4 {
+        self.as_matrix_with_screen_aspect(screen_size.x / screen_size.y)
+    }
+
+    fn as_matrix_with_screen_aspect(&self, screen_aspect: f32) -> Mat4 {
         Mat4::perspective(
             self.fov,
             match self.aspect {
-                CameraPerspectiveProjectionAspect::Screen => {
-                    screen_mgr.width() as f32 / screen_mgr.height() as f32
-                }
+                CameraPerspectiveProjectionAspect::Screen => screen_aspect,
                 CameraPerspectiveProjectionAspect::Fixed(aspect) => aspect,
             },
             self.near,
@@ -125,13 +260,78 @@ pub enum CameraPerspectiveProjectionAspect {
     Fixed(f32),
 }
 
+/// A normalized rect of the screen that a camera renders into, with the origin at the top-left corner.
+/// All components are in range `[0, 1]`; the default viewport covers the whole screen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraViewport {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl CameraViewport {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn full() -> Self {
+        Self::new(0.0, 0.0, 1.0, 1.0)
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.x <= 0.0 && self.y <= 0.0 && 1.0 <= self.x + self.width && 1.0 <= self.y + self.height
+    }
+
+    /// Converts the viewport into pixels of a screen of the given size.
+    /// The result is clamped into the screen.
+    pub fn to_physical(&self, screen_width: f32, screen_height: f32) -> PhysicalViewport {
+        let left = self.x.clamp(0.0, 1.0);
+        let top = self.y.clamp(0.0, 1.0);
+        let right = (self.x + self.width).clamp(left, 1.0);
+        let bottom = (self.y + self.height).clamp(top, 1.0);
+
+        PhysicalViewport {
+            x: left * screen_width,
+            y: top * screen_height,
+            width: (right - left) * screen_width,
+            height: (bottom - top) * screen_height,
+        }
+    }
+}
+
+impl Default for CameraViewport {
+    fn default() -> Self {
+        Self::full()
+    }
+}
+
+/// The window that a camera renders into.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CameraTarget {
+    #[default]
+    Main,
+    /// A [`SecondaryWindow`](super::SecondaryWindow). The camera is skipped once the window is closed.
+    Window(WindowId),
+}
+
 #[derive(Debug, Clone, Component)]
 #[storage(HashMapStorage)]
+#[auto_register]
 pub struct Camera {
     pub mask: u32,
     pub depth: u32,
     pub clear_mode: CameraClearMode,
+    pub viewport: CameraViewport,
+    pub target: CameraTarget,
     pub projection: CameraProjection,
+    /// The axis that the sprites of the same sorting layer and order are sorted along.
+    pub sprite_sort_axis: SpriteSortAxis,
     pub buffer: Arc<Buffer>,
     pub bind_group: Arc<BindGroup>,
 }
@@ -183,22 +383,230 @@ impl Camera {
             mask,
             depth,
             clear_mode,
+            viewport: CameraViewport::full(),
+            target: CameraTarget::Main,
             projection,
+            sprite_sort_axis: SpriteSortAxis::CameraForward,
             buffer,
             bind_group,
         }
     }
 
-    pub fn update_buffer(
-        &self,
-        screen_mgr: &ScreenManager,
-        queue: &Queue,
-        transform_matrix: &Mat4,
-    ) {
+    pub fn update_buffer(&self, screen_size: Vec2, queue: &Queue, transform_matrix: &Mat4) {
         queue.write_buffer(
             &self.buffer,
             0,
-            (transform_matrix.inversed() * self.projection.as_matrix(screen_mgr)).as_bytes(),
+            self.view_projection_matrix(screen_size, transform_matrix)
+                .as_bytes(),
+        );
+    }
+
+    /// Returns the matrix that the camera buffer holds, which transforms world-space positions into clip space.
+    /// The screen size is the one of the [`target`](Self::target) of the camera.
+    pub fn view_projection_matrix(&self, screen_size: Vec2, transform_matrix: &Mat4) -> Mat4 {
+        let viewport = self.viewport.to_physical(screen_size.x, screen_size.y);
+        transform_matrix.inversed()
+            * self
+                .projection
+                .as_matrix_with_screen_size(Vec2::new(viewport.width, viewport.height))
+    }
+
+    /// Builds a world-space ray that starts from the camera and passes through the given screen position.
+    /// The viewport of the camera is taken into account. See [`CameraProjection::screen_to_ray`] for details.
+    pub fn screen_to_ray(
+        &self,
+        transform_matrix: &Mat4,
+        screen_pos: Vec2,
+        screen_size: Vec2,
+    ) -> (Vec3, Vec3) {
+        let viewport = self.viewport.to_physical(screen_size.x, screen_size.y);
+        self.projection.screen_to_ray(
+            transform_matrix,
+            screen_pos - Vec2::new(viewport.x, viewport.y),
+            Vec2::new(viewport.width, viewport.height),
+        )
+    }
+
+    /// Projects the given world-space position onto the screen.
+    /// The viewport of the camera is taken into account. See [`CameraProjection::world_to_screen`] for details.
+    pub fn world_to_screen(
+        &self,
+        transform_matrix: &Mat4,
+        world_pos: Vec3,
+        screen_size: Vec2,
+    ) -> Option<Vec2> {
+        let viewport = self.viewport.to_physical(screen_size.x, screen_size.y);
+        self.projection
+            .world_to_screen(
+                transform_matrix,
+                world_pos,
+                Vec2::new(viewport.width, viewport.height),
+            )
+            .map(|screen_pos| screen_pos + Vec2::new(viewport.x, viewport.y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Quat;
+
+    fn equals_vec3(a: Vec3, b: Vec3) -> bool {
+        (a - b).len() <= 1e-3
+    }
+
+    fn equals_vec2(a: Vec2, b: Vec2) -> bool {
+        (a - b).len() <= 1e-2
+    }
+
+    fn check_round_trip(projection: &CameraProjection, transform_matrix: &Mat4, world_pos: Vec3) {
+        let screen_size = Vec2::new(800.0, 600.0);
+        let screen_pos = projection
+            .world_to_screen(transform_matrix, world_pos, screen_size)
+            .unwrap();
+        let (origin, direction) =
+            projection.screen_to_ray(transform_matrix, screen_pos, screen_size);
+
+        // The ray must pass through the world position.
+        let distance = Vec3::dot(world_pos - origin, direction);
+        assert!(0.0 < distance);
+        assert!(equals_vec3(origin + direction * distance, world_pos));
+
+        // Projecting the point on the ray must give the same screen position.
+        let reprojected = projection
+            .world_to_screen(
+                transform_matrix,
+                origin + direction * distance * 0.5,
+                screen_size,
+            )
+            .unwrap();
+        assert!(equals_vec2(reprojected, screen_pos));
+    }
+
+    #[test]
+    fn check_perspective_round_trip() {
+        let projection = CameraProjection::perspective(
+            60f32.to_radians(),
+            CameraPerspectiveProjectionAspect::Screen,
+            0.1,
+            100.0,
+        );
+        let transform_matrix = Mat4::srt(
+            Vec3::new(1.0, 2.0, 10.0),
+            Quat::from_eular(0.2, -0.3, 0.0),
+            Vec3::ONE,
+        );
+
+        check_round_trip(&projection, &transform_matrix, Vec3::new(0.0, 0.0, 0.0));
+        check_round_trip(&projection, &transform_matrix, Vec3::new(2.5, 1.0, -3.0));
+    }
+
+    #[test]
+    fn check_orthographic_round_trip() {
+        let projection = CameraProjection::orthographic(20.0, 0.1, 100.0);
+        let transform_matrix = Mat4::srt(
+            Vec3::new(-1.0, 0.5, 10.0),
+            Quat::from_eular(-0.1, 0.4, 0.0),
+            Vec3::ONE,
+        );
+
+        check_round_trip(&projection, &transform_matrix, Vec3::new(0.0, 0.0, 0.0));
+        check_round_trip(&projection, &transform_matrix, Vec3::new(3.0, -2.0, 1.0));
+    }
+
+    #[test]
+    fn check_screen_center_is_forward() {
+        let projection = CameraProjection::perspective(
+            60f32.to_radians(),
+            CameraPerspectiveProjectionAspect::Screen,
+            0.1,
+            100.0,
+        );
+        let (origin, direction) = projection.screen_to_ray(
+            &Mat4::identity(),
+            Vec2::new(400.0, 300.0),
+            Vec2::new(800.0, 600.0),
+        );
+
+        assert!(equals_vec3(origin, Vec3::ZERO));
+        assert!(equals_vec3(direction, Vec3::FORWARD));
+    }
+
+    #[test]
+    fn check_behind_camera_is_none() {
+        let projection = CameraProjection::perspective(
+            60f32.to_radians(),
+            CameraPerspectiveProjectionAspect::Screen,
+            0.1,
+            100.0,
+        );
+        let screen_size = Vec2::new(800.0, 600.0);
+
+        assert!(projection
+            .world_to_screen(&Mat4::identity(), Vec3::new(0.0, 0.0, 5.0), screen_size)
+            .is_none());
+        assert!(projection
+            .world_to_screen(&Mat4::identity(), Vec3::new(0.0, 0.0, -5.0), screen_size)
+            .is_some());
+    }
+
+    #[test]
+    fn check_viewport_to_physical() {
+        let viewport = CameraViewport::full();
+        assert!(viewport.is_full());
+        assert_eq!(
+            viewport.to_physical(800.0, 600.0),
+            PhysicalViewport {
+                x: 0.0,
+                y: 0.0,
+                width: 800.0,
+                height: 600.0,
+            }
+        );
+
+        let viewport = CameraViewport::new(0.5, 0.0, 0.5, 1.0);
+        assert!(!viewport.is_full());
+        assert_eq!(
+            viewport.to_physical(800.0, 600.0),
+            PhysicalViewport {
+                x: 400.0,
+                y: 0.0,
+                width: 400.0,
+                height: 600.0,
+            }
+        );
+
+        // Viewports exceeding the screen are clamped.
+        let viewport = CameraViewport::new(-0.5, 0.75, 1.0, 0.5);
+        assert_eq!(
+            viewport.to_physical(800.0, 600.0),
+            PhysicalViewport {
+                x: 0.0,
+                y: 450.0,
+                width: 400.0,
+                height: 150.0,
+            }
+        );
+    }
+
+    #[test]
+    fn check_overlay_clear_modes_load_color() {
+        for clear_mode in [CameraClearMode::keep(), CameraClearMode::depth_only(1.0, 0)] {
+            assert_eq!(clear_mode.color_load_op(), LoadOp::Load);
+        }
+
+        // The depth is cleared independently of the color.
+        let clear_mode = CameraClearMode::depth_only(0.5, 3);
+        assert_eq!(clear_mode.depth_load_op(), LoadOp::Clear(0.5));
+        assert_eq!(clear_mode.stencil_load_op(), LoadOp::Clear(3));
+        assert_eq!(CameraClearMode::keep().depth_load_op(), LoadOp::Load);
+        assert_eq!(CameraClearMode::keep().stencil_load_op(), LoadOp::Load);
+
+        let clear_mode = CameraClearMode::all(Color::white(), 1.0, 0);
+        assert_eq!(
+            clear_mode.color_load_op(),
+            LoadOp::Clear(wgpu::Color::WHITE)
         );
+        assert_eq!(clear_mode.depth_load_op(), LoadOp::Clear(1.0));
     }
 }
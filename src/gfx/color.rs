@@ -105,6 +105,23 @@ impl Color {
         }
     }
 
+    pub fn lerp(from: Self, to: Self, t: f32) -> Self {
+        match t {
+            t if t <= 0f32 => from,
+            t if 1f32 <= t => to,
+            t => Self::lerp_unclamped(from, to, t),
+        }
+    }
+
+    pub fn lerp_unclamped(from: Self, to: Self, t: f32) -> Self {
+        Self {
+            r: from.r + (to.r - from.r) * t,
+            g: from.g + (to.g - from.g) * t,
+            b: from.b + (to.b - from.b) * t,
+            a: from.a + (to.a - from.a) * t,
+        }
+    }
+
     pub fn transparent() -> Self {
         Self {
             r: 0f32,
@@ -1,4 +1,4 @@
-use super::GfxContextHandle;
+use super::{GfxContextHandle, GpuResourceTracker};
 use wgpu::{
     Device, Extent3d, Texture, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
     TextureView,
@@ -35,6 +35,7 @@ pub struct DepthStencil {
     mode: DepthStencilMode,
     texture: Option<Texture>,
     texture_view: Option<TextureView>,
+    tracker: GpuResourceTracker,
 }
 
 impl DepthStencil {
@@ -51,6 +52,7 @@ impl DepthStencil {
         Some(Self {
             gfx_ctx,
             mode,
+            tracker: track_texture(mode, texture.as_ref()),
             texture,
             texture_view,
         })
@@ -75,12 +77,13 @@ impl DepthStencil {
 
         let (texture, texture_view) =
             create_texture_and_view(&self.gfx_ctx.device, self.mode, size);
+        self.tracker = track_texture(self.mode, texture.as_ref());
         self.texture = texture;
         self.texture_view = texture_view;
     }
 }
 
-fn create_texture_and_view(
+pub(super) fn create_texture_and_view(
     device: &Device,
     mode: DepthStencilMode,
     size: PhysicalSize<u32>,
@@ -95,6 +98,12 @@ fn create_texture_and_view(
     }
 }
 
+fn track_texture(mode: DepthStencilMode, texture: Option<&Texture>) -> GpuResourceTracker {
+    texture.map_or_else(Default::default, |texture| {
+        GpuResourceTracker::texture(texture, Some(mode.as_label_str()))
+    })
+}
+
 fn create_texture(
     device: &Device,
     mode: DepthStencilMode,
@@ -0,0 +1,444 @@
+use super::GfxContext;
+use crate::time::Instant;
+use image::{
+    codecs::gif::{GifEncoder, Repeat},
+    Delay, Frame, ImageResult, RgbaImage,
+};
+use logging::{log_info, log_warn};
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::BufWriter,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        mpsc::{sync_channel, Receiver, SyncSender, TrySendError},
+        Arc,
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+use thiserror::Error;
+use wgpu::{
+    Buffer, BufferAddress, BufferDescriptor, BufferUsages, CommandEncoder, Extent3d,
+    ImageCopyBuffer, ImageDataLayout, MapMode, Texture, TextureFormat, TextureUsages,
+    COPY_BYTES_PER_ROW_ALIGNMENT,
+};
+use winit::dpi::PhysicalSize;
+
+/// The number of readbacks in flight, so that a frame is copied while the previous one is being mapped.
+const STAGING_BUFFER_COUNT: usize = 2;
+/// The number of frames waiting for the encoder. Frames are dropped while it is full.
+const ENCODER_QUEUE_LEN: usize = 8;
+
+const READBACK_IDLE: u8 = 0;
+const READBACK_MAPPING: u8 = 1;
+const READBACK_MAPPED: u8 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CaptureFormat {
+    /// Numbered PNGs, from `frame_00000.png` on.
+    Png,
+    /// A looping `capture.gif`. GIF frames have 256 colors at most; with `quantize`, the palette of each frame is
+    /// computed from all of its pixels, which looks better but encodes much slower than from a sample of them.
+    Gif { quantize: bool },
+}
+
+#[derive(Debug, Clone)]
+pub struct CaptureConfig {
+    /// The frames per second to capture at. Engine frames in between are skipped.
+    pub fps: u32,
+    /// The capture stops by itself after this many frames.
+    pub max_frames: u32,
+    /// The directory to write the frames into. It is created if it does not exist.
+    pub dir: PathBuf,
+    pub format: CaptureFormat,
+}
+
+#[derive(Error, Debug)]
+pub enum FrameCaptureError {
+    #[error("the fps and the max frames of a capture must not be zero")]
+    InvalidConfig,
+    #[error("the surface of format {0:?} cannot be captured")]
+    UnsupportedSurface(TextureFormat),
+    #[error("io error: {0}")]
+    IoError(#[from] std::io::Error),
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CaptureStats {
+    /// The frames handed to the encoder.
+    pub frames: u32,
+    /// The frames skipped because the readbacks or the encoder could not keep up.
+    pub dropped_frames: u32,
+}
+
+/// Records the frames of the main surface into a directory. See [`CaptureConfig`].
+///
+/// Frames are copied into staging buffers that are mapped asynchronously, so the GPU never stalls on a map, and
+/// are written by a background thread. It is driven by the [`RenderManager`](super::RenderManager).
+pub struct FrameCapture {
+    size: PhysicalSize<u32>,
+    is_bgra: bool,
+    padded_bytes_per_row: u32,
+    pacer: CapturePacer,
+    max_frames: u32,
+    staging_buffers: Vec<StagingBuffer>,
+    /// The staging buffers being mapped, in the order of their frames.
+    pending: VecDeque<usize>,
+    /// The staging buffer copied into in the frame being rendered.
+    copying: Option<usize>,
+    sender: Option<SyncSender<CapturedFrame>>,
+    encoder_thread: Option<JoinHandle<()>>,
+    stats: CaptureStats,
+}
+
+impl FrameCapture {
+    /// Starts capturing the main surface. The surface must be copyable and of an 8-bit RGBA or BGRA format.
+    pub fn start(gfx_ctx: &GfxContext, config: CaptureConfig) -> Result<Self, FrameCaptureError> {
+        if config.fps == 0 || config.max_frames == 0 {
+            return Err(FrameCaptureError::InvalidConfig);
+        }
+
+        let surface_config = gfx_ctx.surface_config.borrow();
+        let is_bgra = match surface_config.format {
+            TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => true,
+            TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => false,
+            format => return Err(FrameCaptureError::UnsupportedSurface(format)),
+        };
+
+        if !surface_config.usage.contains(TextureUsages::COPY_SRC) {
+            return Err(FrameCaptureError::UnsupportedSurface(surface_config.format));
+        }
+
+        std::fs::create_dir_all(&config.dir)?;
+
+        let size = PhysicalSize::new(surface_config.width, surface_config.height);
+        let padded_bytes_per_row =
+            (size.width * 4).div_ceil(COPY_BYTES_PER_ROW_ALIGNMENT) * COPY_BYTES_PER_ROW_ALIGNMENT;
+        let staging_buffers = Vec::from_iter((0..STAGING_BUFFER_COUNT).map(|_| StagingBuffer {
+            buffer: gfx_ctx.device.create_buffer(&BufferDescriptor {
+                label: Some("frame capture staging buffer"),
+                size: padded_bytes_per_row as BufferAddress * size.height as BufferAddress,
+                usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            }),
+            state: Arc::new(AtomicU8::new(READBACK_IDLE)),
+        }));
+
+        let (sender, receiver) = sync_channel(ENCODER_QUEUE_LEN);
+        let encoder_thread = std::thread::Builder::new()
+            .name("frame capture encoder".to_owned())
+            .spawn(move || encode_frames(receiver, config.dir, config.format, config.fps))?;
+
+        Ok(Self {
+            size,
+            is_bgra,
+            padded_bytes_per_row,
+            pacer: CapturePacer::new(config.fps),
+            max_frames: config.max_frames,
+            staging_buffers,
+            pending: VecDeque::new(),
+            copying: None,
+            sender: Some(sender),
+            encoder_thread: Some(encoder_thread),
+            stats: CaptureStats::default(),
+        })
+    }
+
+    pub fn size(&self) -> PhysicalSize<u32> {
+        self.size
+    }
+
+    pub fn stats(&self) -> CaptureStats {
+        self.stats
+    }
+
+    /// Returns `true` once the max frames have been handed to the encoder.
+    pub fn is_finished(&self) -> bool {
+        self.max_frames <= self.stats.frames
+    }
+
+    /// Hands the frames that have been read back to the encoder, in order. It should be called once per frame,
+    /// after polling the device.
+    pub fn collect(&mut self) {
+        while let Some(&index) = self.pending.front() {
+            let staging_buffer = &self.staging_buffers[index];
+
+            match staging_buffer.state.load(Ordering::Acquire) {
+                READBACK_MAPPED => {}
+                READBACK_MAPPING => break,
+                _ => {
+                    // The map has failed.
+                    self.pending.pop_front();
+                    self.stats.dropped_frames += 1;
+                    continue;
+                }
+            }
+
+            let pixels = {
+                let data = staging_buffer.buffer.slice(..).get_mapped_range();
+                unpad_rows(
+                    &data,
+                    self.size.width * 4,
+                    self.padded_bytes_per_row,
+                    self.size.height,
+                )
+            };
+            staging_buffer.buffer.unmap();
+            staging_buffer.state.store(READBACK_IDLE, Ordering::Release);
+            self.pending.pop_front();
+
+            let frame = CapturedFrame {
+                width: self.size.width,
+                height: self.size.height,
+                pixels,
+                is_bgra: self.is_bgra,
+            };
+
+            match self.sender.as_ref().map(|sender| sender.try_send(frame)) {
+                Some(Ok(())) => self.stats.frames += 1,
+                Some(Err(TrySendError::Full(_))) | None => self.stats.dropped_frames += 1,
+                Some(Err(TrySendError::Disconnected(_))) => {
+                    // The encoder has failed and logged why; no more frames are taken.
+                    self.sender = None;
+                    self.stats.dropped_frames += 1;
+                }
+            }
+        }
+    }
+
+    /// Copies the texture into a staging buffer if a frame is due. It must be called before finishing the
+    /// encoder of the frame, and the texture must be of the size of the capture.
+    pub fn copy_frame(&mut self, encoder: &mut CommandEncoder, texture: &Texture, now: Instant) {
+        let in_flight = self.pending.len() as u32 + self.copying.is_some() as u32;
+
+        if self.max_frames <= self.stats.frames + in_flight || !self.pacer.is_due(now) {
+            return;
+        }
+
+        let index = match (0..self.staging_buffers.len()).find(|index| {
+            !self.pending.contains(index)
+                && self.staging_buffers[*index].state.load(Ordering::Acquire) == READBACK_IDLE
+        }) {
+            Some(index) => index,
+            None => {
+                self.stats.dropped_frames += 1;
+                return;
+            }
+        };
+
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            ImageCopyBuffer {
+                buffer: &self.staging_buffers[index].buffer,
+                layout: ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(self.padded_bytes_per_row),
+                    rows_per_image: Some(self.size.height),
+                },
+            },
+            Extent3d {
+                width: self.size.width,
+                height: self.size.height,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.copying = Some(index);
+    }
+
+    /// Requests to read back the frame copied in [`copy_frame`](Self::copy_frame). It must be called after
+    /// submitting the frame.
+    pub fn finish_frame(&mut self) {
+        let index = match self.copying.take() {
+            Some(index) => index,
+            None => return,
+        };
+        let state = self.staging_buffers[index].state.clone();
+        state.store(READBACK_MAPPING, Ordering::Release);
+        self.staging_buffers[index]
+            .buffer
+            .slice(..)
+            .map_async(MapMode::Read, move |result| {
+                state.store(
+                    if result.is_ok() {
+                        READBACK_MAPPED
+                    } else {
+                        READBACK_IDLE
+                    },
+                    Ordering::Release,
+                );
+            });
+        self.pending.push_back(index);
+    }
+
+    /// Waits for the readbacks in flight and the encoder, and returns the final stats. Blocks until the queued
+    /// frames are written.
+    pub fn stop(mut self, gfx_ctx: &GfxContext) -> CaptureStats {
+        if !self.pending.is_empty() {
+            gfx_ctx.device.poll(wgpu::Maintain::Wait);
+            self.collect();
+        }
+
+        self.sender = None;
+
+        if let Some(encoder_thread) = self.encoder_thread.take() {
+            if encoder_thread.join().is_err() {
+                log_warn!("the frame capture encoder has panicked");
+            }
+        }
+
+        self.stats
+    }
+}
+
+struct StagingBuffer {
+    buffer: Buffer,
+    state: Arc<AtomicU8>,
+}
+
+struct CapturedFrame {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+    is_bgra: bool,
+}
+
+impl CapturedFrame {
+    fn into_image(mut self) -> RgbaImage {
+        if self.is_bgra {
+            for pixel in self.pixels.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+
+        RgbaImage::from_raw(self.width, self.height, self.pixels).unwrap()
+    }
+}
+
+/// Decides which engine frames are captured to keep the capture rate.
+#[derive(Debug, Clone, Copy)]
+struct CapturePacer {
+    interval: Duration,
+    next: Option<Instant>,
+}
+
+impl CapturePacer {
+    fn new(fps: u32) -> Self {
+        Self {
+            interval: Duration::from_secs(1) / fps,
+            next: None,
+        }
+    }
+
+    fn is_due(&mut self, now: Instant) -> bool {
+        if self.next.is_some_and(|next| now < next) {
+            return false;
+        }
+
+        let next = self.next.unwrap_or(now) + self.interval;
+        // Starts over from now after a hitch, instead of catching up with a burst of frames.
+        self.next = Some(if next <= now {
+            now + self.interval
+        } else {
+            next
+        });
+        true
+    }
+}
+
+/// Removes the padding at the end of the rows, which texture copies align to
+/// [`COPY_BYTES_PER_ROW_ALIGNMENT`].
+fn unpad_rows(data: &[u8], bytes_per_row: u32, padded_bytes_per_row: u32, rows: u32) -> Vec<u8> {
+    let mut pixels = Vec::with_capacity((bytes_per_row * rows) as usize);
+
+    for row in data
+        .chunks(padded_bytes_per_row as usize)
+        .take(rows as usize)
+    {
+        pixels.extend_from_slice(&row[..bytes_per_row as usize]);
+    }
+
+    pixels
+}
+
+fn encode_frames(receiver: Receiver<CapturedFrame>, dir: PathBuf, format: CaptureFormat, fps: u32) {
+    let result = match format {
+        CaptureFormat::Png => write_pngs(&receiver, &dir),
+        CaptureFormat::Gif { quantize } => write_gif(&receiver, &dir, quantize, fps),
+    };
+
+    match result {
+        Ok(count) => log_info!("wrote {} captured frames into {}", count, dir.display()),
+        Err(err) => log_warn!("failed to write captured frames: {}", err),
+    }
+}
+
+fn write_pngs(receiver: &Receiver<CapturedFrame>, dir: &std::path::Path) -> ImageResult<u32> {
+    let mut count = 0;
+
+    for frame in receiver {
+        frame
+            .into_image()
+            .save(dir.join(format!("frame_{:05}.png", count)))?;
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+fn write_gif(
+    receiver: &Receiver<CapturedFrame>,
+    dir: &std::path::Path,
+    quantize: bool,
+    fps: u32,
+) -> ImageResult<u32> {
+    let file = BufWriter::new(File::create(dir.join("capture.gif"))?);
+    let mut encoder = GifEncoder::new_with_speed(file, if quantize { 1 } else { 10 });
+    encoder.set_repeat(Repeat::Infinite)?;
+
+    let delay = Delay::from_numer_denom_ms(1000, fps);
+    let mut count = 0;
+
+    for frame in receiver {
+        encoder.encode_frame(Frame::from_parts(frame.into_image(), 0, 0, delay))?;
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pacer_skips_frames_to_keep_the_rate() {
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+
+        // Engine frames every 10 ms, captured at 25 fps.
+        let mut pacer = CapturePacer::new(25);
+        let due = Vec::from_iter((0..10).filter(|frame| pacer.is_due(at(frame * 10))));
+        assert_eq!(due, [0, 4, 8]);
+
+        // A hitch is not caught up with a burst.
+        assert!(pacer.is_due(at(500)));
+        assert!(!pacer.is_due(at(510)));
+        assert!(pacer.is_due(at(540)));
+    }
+
+    #[test]
+    fn padding_is_removed_from_rows() {
+        let data = [1, 2, 3, 4, 0, 0, 5, 6, 7, 8, 0, 0, 9, 9, 9, 9, 0, 0];
+        assert_eq!(unpad_rows(&data, 4, 6, 2), [1, 2, 3, 4, 5, 6, 7, 8]);
+
+        let frame = CapturedFrame {
+            width: 1,
+            height: 2,
+            pixels: vec![1, 2, 3, 4, 5, 6, 7, 8],
+            is_bgra: true,
+        };
+        assert_eq!(frame.into_image().into_raw(), [3, 2, 1, 4, 7, 6, 5, 8]);
+    }
+}
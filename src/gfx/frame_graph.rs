@@ -0,0 +1,454 @@
+use std::ops::Range;
+use thiserror::Error;
+
+/// A texture used by the passes of a [`FrameGraph`]. It only names the texture; the texture itself is owned
+/// by whoever executes the passes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FrameGraphTexture(u32);
+
+/// How a pass begins with a texture it writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameGraphLoadOp {
+    /// The previous contents are discarded.
+    Clear,
+    /// The previous contents are drawn over.
+    Load,
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum FrameGraphError {
+    #[error("pass `{pass}` reads `{texture}`, but no pass writes it")]
+    MissingProducer { pass: String, texture: String },
+    #[error("pass `{pass}` reads `{texture}` before pass `{writer}` writes it")]
+    ReadBeforeWrite {
+        pass: String,
+        texture: String,
+        writer: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Access {
+    Read,
+    Write,
+    Clear,
+}
+
+struct TextureNode {
+    name: String,
+    is_imported: bool,
+}
+
+struct PassNode<P> {
+    name: String,
+    payload: P,
+    accesses: Vec<(FrameGraphTexture, Access)>,
+    has_side_effects: bool,
+}
+
+/// Orders the passes of a frame by the textures they read and write, instead of by hand.
+///
+/// Passes that write the same texture run in the order they are added, and passes that read a texture run after
+/// all of its writers. Passes whose outputs nothing uses are culled, and each pass is told whether to clear or
+/// load the textures it writes, and whether to store them.
+pub struct FrameGraph<P> {
+    textures: Vec<TextureNode>,
+    passes: Vec<PassNode<P>>,
+}
+
+impl<P> FrameGraph<P> {
+    pub fn new() -> Self {
+        Self {
+            textures: Vec::new(),
+            passes: Vec::new(),
+        }
+    }
+
+    /// Adds a texture that lives outside of the frame, such as the surface. Its contents exist before the first
+    /// pass and are kept after the last one, so the passes that write it are never culled.
+    pub fn import_texture(&mut self, name: impl Into<String>) -> FrameGraphTexture {
+        self.add_texture(name.into(), true)
+    }
+
+    /// Adds a texture that only lives within the frame, such as a shadow map. It must be written before read.
+    pub fn create_texture(&mut self, name: impl Into<String>) -> FrameGraphTexture {
+        self.add_texture(name.into(), false)
+    }
+
+    /// Adds a pass. The payload is handed back with the compiled pass, e.g. to find what to draw.
+    pub fn add_pass(
+        &mut self,
+        name: impl Into<String>,
+        payload: P,
+    ) -> FrameGraphPassBuilder<'_, P> {
+        self.passes.push(PassNode {
+            name: name.into(),
+            payload,
+            accesses: Vec::new(),
+            has_side_effects: false,
+        });
+
+        let pass = self.passes.len() - 1;
+        FrameGraphPassBuilder { graph: self, pass }
+    }
+
+    fn add_texture(&mut self, name: String, is_imported: bool) -> FrameGraphTexture {
+        self.textures.push(TextureNode { name, is_imported });
+        FrameGraphTexture(self.textures.len() as u32 - 1)
+    }
+
+    /// Sorts and culls the passes.
+    pub fn build(self) -> Result<CompiledFrameGraph<P>, FrameGraphError> {
+        let pass_count = self.passes.len();
+        // The writer that runs before each write of each pass, and the last writer of each texture.
+        let mut previous_writers = vec![Vec::new(); pass_count];
+        let mut last_writers = vec![None; self.textures.len()];
+
+        for (pass_index, pass) in self.passes.iter().enumerate() {
+            for &(texture, access) in &pass.accesses {
+                if access != Access::Read {
+                    let last_writer = &mut last_writers[texture.0 as usize];
+                    previous_writers[pass_index].push((texture, access, *last_writer));
+                    *last_writer = Some(pass_index);
+                }
+            }
+        }
+
+        // The passes each pass depends on, and whether the dependency needs the contents it produced.
+        let mut dependencies = vec![Vec::new(); pass_count];
+
+        for (pass_index, pass) in self.passes.iter().enumerate() {
+            for &(texture, access) in &pass.accesses {
+                if access != Access::Read {
+                    continue;
+                }
+
+                let texture_node = &self.textures[texture.0 as usize];
+
+                match last_writers[texture.0 as usize] {
+                    Some(writer) => dependencies[pass_index].push((writer, texture, true)),
+                    None if texture_node.is_imported => {}
+                    None => {
+                        return Err(FrameGraphError::MissingProducer {
+                            pass: pass.name.clone(),
+                            texture: texture_node.name.clone(),
+                        })
+                    }
+                }
+            }
+
+            for &(texture, access, previous_writer) in &previous_writers[pass_index] {
+                if let Some(writer) = previous_writer {
+                    dependencies[pass_index].push((writer, texture, access == Access::Write));
+                }
+            }
+        }
+
+        let order = self.sort(&dependencies)?;
+
+        let mut is_retained = self
+            .passes
+            .iter()
+            .map(|pass| {
+                pass.has_side_effects
+                    || pass.accesses.iter().any(|&(texture, access)| {
+                        access != Access::Read && self.textures[texture.0 as usize].is_imported
+                    })
+            })
+            .collect::<Vec<_>>();
+        // Dependencies always run earlier, so a reversed walk retains them before they are visited.
+        let mut stored = vec![Vec::new(); pass_count];
+
+        for &pass_index in order.iter().rev() {
+            if !is_retained[pass_index] {
+                continue;
+            }
+
+            for &(dependency, texture, needs_contents) in &dependencies[pass_index] {
+                if needs_contents {
+                    is_retained[dependency] = true;
+                    stored[dependency].push(texture);
+                }
+            }
+        }
+
+        let mut passes = self.passes.into_iter().map(Some).collect::<Vec<_>>();
+        let mut compiled_passes = Vec::with_capacity(pass_count);
+        let mut lifetimes = vec![None::<Range<usize>>; self.textures.len()];
+
+        for pass_index in order {
+            if !is_retained[pass_index] {
+                continue;
+            }
+
+            let pass = passes[pass_index].take().unwrap();
+            let compiled_index = compiled_passes.len();
+            let mut attachments = Vec::new();
+
+            for &(texture, access, previous_writer) in &previous_writers[pass_index] {
+                let texture_node = &self.textures[texture.0 as usize];
+                let has_contents = texture_node.is_imported
+                    || previous_writer.is_some_and(|writer| is_retained[writer]);
+                let load_op = if access == Access::Write && has_contents {
+                    FrameGraphLoadOp::Load
+                } else {
+                    FrameGraphLoadOp::Clear
+                };
+                let store = texture_node.is_imported || stored[pass_index].contains(&texture);
+
+                attachments.push(CompiledAttachment {
+                    texture,
+                    load_op,
+                    store,
+                });
+            }
+
+            for &(texture, _) in &pass.accesses {
+                let lifetime = &mut lifetimes[texture.0 as usize];
+                *lifetime = Some(match lifetime.take() {
+                    Some(lifetime) => lifetime.start..compiled_index + 1,
+                    None => compiled_index..compiled_index + 1,
+                });
+            }
+
+            compiled_passes.push(CompiledPass {
+                name: pass.name,
+                payload: pass.payload,
+                attachments,
+            });
+        }
+
+        Ok(CompiledFrameGraph {
+            passes: compiled_passes,
+            culled_pass_count: pass_count - is_retained.iter().filter(|&&kept| kept).count(),
+            lifetimes,
+        })
+    }
+
+    /// Sorts the passes topologically, preferring the order they are added in.
+    fn sort(
+        &self,
+        dependencies: &[Vec<(usize, FrameGraphTexture, bool)>],
+    ) -> Result<Vec<usize>, FrameGraphError> {
+        let pass_count = self.passes.len();
+        let mut is_sorted = vec![false; pass_count];
+        let mut order = Vec::with_capacity(pass_count);
+
+        while order.len() < pass_count {
+            let next = (0..pass_count).find(|&pass_index| {
+                !is_sorted[pass_index]
+                    && dependencies[pass_index]
+                        .iter()
+                        .all(|&(dependency, _, _)| is_sorted[dependency])
+            });
+
+            match next {
+                Some(pass_index) => {
+                    is_sorted[pass_index] = true;
+                    order.push(pass_index);
+                }
+                None => return Err(self.find_read_before_write(&is_sorted, dependencies)),
+            }
+        }
+
+        Ok(order)
+    }
+
+    /// Finds a read that cannot be ordered after its writer. Writes depend only on the passes added before them,
+    /// so one of the remaining passes must read a texture that a pass added after it writes.
+    fn find_read_before_write(
+        &self,
+        is_sorted: &[bool],
+        dependencies: &[Vec<(usize, FrameGraphTexture, bool)>],
+    ) -> FrameGraphError {
+        let (pass_index, writer, texture) = (0..self.passes.len())
+            .filter(|&pass_index| !is_sorted[pass_index])
+            .find_map(|pass_index| {
+                dependencies[pass_index]
+                    .iter()
+                    .find(|&&(dependency, _, _)| !is_sorted[dependency] && pass_index <= dependency)
+                    .map(|&(dependency, texture, _)| (pass_index, dependency, texture))
+            })
+            .unwrap();
+
+        FrameGraphError::ReadBeforeWrite {
+            pass: self.passes[pass_index].name.clone(),
+            texture: self.textures[texture.0 as usize].name.clone(),
+            writer: self.passes[writer].name.clone(),
+        }
+    }
+}
+
+impl<P> Default for FrameGraph<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Declares the textures a pass uses. See [`FrameGraph::add_pass`].
+pub struct FrameGraphPassBuilder<'g, P> {
+    graph: &'g mut FrameGraph<P>,
+    pass: usize,
+}
+
+impl<'g, P> FrameGraphPassBuilder<'g, P> {
+    /// Samples the texture, after all of its writers.
+    pub fn read(&mut self, texture: FrameGraphTexture) -> &mut Self {
+        self.access(texture, Access::Read)
+    }
+
+    /// Draws over the texture, keeping what the previous writers drew.
+    pub fn write(&mut self, texture: FrameGraphTexture) -> &mut Self {
+        self.access(texture, Access::Write)
+    }
+
+    /// Overwrites the whole texture, discarding what the previous writers drew.
+    pub fn clear(&mut self, texture: FrameGraphTexture) -> &mut Self {
+        self.access(texture, Access::Clear)
+    }
+
+    /// Keeps the pass even if nothing uses its outputs, e.g. if it reads back to the CPU.
+    pub fn has_side_effects(&mut self) -> &mut Self {
+        self.graph.passes[self.pass].has_side_effects = true;
+        self
+    }
+
+    fn access(&mut self, texture: FrameGraphTexture, access: Access) -> &mut Self {
+        self.graph.passes[self.pass]
+            .accesses
+            .push((texture, access));
+        self
+    }
+}
+
+/// The passes of a [`FrameGraph`] in the order to execute them, without the culled ones.
+pub struct CompiledFrameGraph<P> {
+    passes: Vec<CompiledPass<P>>,
+    culled_pass_count: usize,
+    lifetimes: Vec<Option<Range<usize>>>,
+}
+
+impl<P> CompiledFrameGraph<P> {
+    pub fn passes(&self) -> &[CompiledPass<P>] {
+        &self.passes
+    }
+
+    pub fn culled_pass_count(&self) -> usize {
+        self.culled_pass_count
+    }
+
+    /// Returns the range of the passes that use the texture, or `None` if no pass uses it. Transient textures whose
+    /// ranges do not overlap may share the same memory.
+    pub fn lifetime(&self, texture: FrameGraphTexture) -> Option<Range<usize>> {
+        self.lifetimes[texture.0 as usize].clone()
+    }
+}
+
+pub struct CompiledPass<P> {
+    name: String,
+    payload: P,
+    attachments: Vec<CompiledAttachment>,
+}
+
+struct CompiledAttachment {
+    texture: FrameGraphTexture,
+    load_op: FrameGraphLoadOp,
+    store: bool,
+}
+
+impl<P> CompiledPass<P> {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn payload(&self) -> &P {
+        &self.payload
+    }
+
+    /// Returns how the pass begins with the texture, or `None` if the pass does not write it.
+    pub fn load_op(&self, texture: FrameGraphTexture) -> Option<FrameGraphLoadOp> {
+        self.attachment(texture)
+            .map(|attachment| attachment.load_op)
+    }
+
+    /// Returns `true` if a later pass uses what the pass writes to the texture, or the texture is imported.
+    pub fn store(&self, texture: FrameGraphTexture) -> bool {
+        self.attachment(texture)
+            .is_some_and(|attachment| attachment.store)
+    }
+
+    fn attachment(&self, texture: FrameGraphTexture) -> Option<&CompiledAttachment> {
+        self.attachments
+            .iter()
+            .find(|attachment| attachment.texture == texture)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names<P>(graph: &CompiledFrameGraph<P>) -> Vec<&str> {
+        graph.passes().iter().map(|pass| pass.name()).collect()
+    }
+
+    #[test]
+    fn sorts_culls_and_inserts_load_ops() {
+        let mut graph = FrameGraph::new();
+        let surface = graph.import_texture("surface");
+        let shadow_map = graph.create_texture("shadow map");
+        let unused = graph.create_texture("unused");
+
+        graph.add_pass("scene", ()).clear(surface).read(shadow_map);
+        graph.add_pass("ui", ()).write(surface);
+        graph.add_pass("shadow", ()).clear(shadow_map);
+        graph.add_pass("debug", ()).write(unused);
+
+        let graph = graph.build().unwrap();
+        assert_eq!(names(&graph), ["shadow", "scene", "ui"]);
+        assert_eq!(graph.culled_pass_count(), 1);
+
+        let [shadow, scene, ui] = graph.passes() else {
+            unreachable!()
+        };
+        assert_eq!(shadow.load_op(shadow_map), Some(FrameGraphLoadOp::Clear));
+        assert!(shadow.store(shadow_map));
+        assert_eq!(scene.load_op(surface), Some(FrameGraphLoadOp::Clear));
+        assert_eq!(scene.load_op(shadow_map), None);
+        assert_eq!(ui.load_op(surface), Some(FrameGraphLoadOp::Load));
+        assert!(ui.store(surface));
+        assert_eq!(graph.lifetime(shadow_map), Some(0..2));
+        assert_eq!(graph.lifetime(unused), None);
+    }
+
+    #[test]
+    fn reports_invalid_reads() {
+        let mut graph = FrameGraph::new();
+        let surface = graph.import_texture("surface");
+        let shadow_map = graph.create_texture("shadow map");
+        graph.add_pass("scene", ()).write(surface).read(shadow_map);
+
+        assert_eq!(
+            graph.build().err(),
+            Some(FrameGraphError::MissingProducer {
+                pass: "scene".to_owned(),
+                texture: "shadow map".to_owned(),
+            })
+        );
+
+        let mut graph = FrameGraph::new();
+        let surface = graph.import_texture("surface");
+        let bloom = graph.create_texture("bloom");
+        graph.add_pass("post", ()).write(bloom).read(surface);
+        graph.add_pass("scene", ()).write(surface).read(bloom);
+
+        assert_eq!(
+            graph.build().err(),
+            Some(FrameGraphError::ReadBeforeWrite {
+                pass: "post".to_owned(),
+                texture: "surface".to_owned(),
+                writer: "scene".to_owned(),
+            })
+        );
+    }
+}
@@ -0,0 +1,32 @@
+use super::FrameBufferAllocatorStats;
+use std::time::Duration;
+
+/// Statistics of a rendered frame.
+#[derive(Debug, Default, Clone)]
+pub struct FrameStats {
+    /// GPU time of each render pass, in submission order. It is empty if GPU profiling is not supported.
+    /// The times are measured asynchronously, so they lag behind the current frame by a few frames.
+    pub gpu_pass_times: Vec<Duration>,
+    /// The number of draw calls of the last rendered frame.
+    pub draw_calls: u32,
+    /// The number of pipelines and bind groups set by the draw calls of the last rendered frame. States shared
+    /// with the previous draw call are not set again.
+    pub state_changes: u32,
+    /// The number of triangles of the last rendered frame, including all instances.
+    pub triangles: u64,
+    /// The number of render passes of the last rendered frame, one per active camera.
+    pub render_passes: u32,
+    /// The usage of the buffers allocated for the last rendered frame, such as instance data.
+    pub frame_buffers: FrameBufferAllocatorStats,
+}
+
+impl FrameStats {
+    /// Returns the total GPU time of all render passes, or `None` if GPU profiling is not supported.
+    pub fn gpu_time(&self) -> Option<Duration> {
+        if self.gpu_pass_times.is_empty() {
+            return None;
+        }
+
+        Some(self.gpu_pass_times.iter().sum())
+    }
+}
@@ -0,0 +1,52 @@
+use fontdue::Metrics;
+
+/// Generates a coverage bitmap that is shifted right by the given subpixel offset in range `[0, 1)`.
+/// The bitmap is one pixel wider than the rasterized glyph to hold the shifted coverage,
+/// and flipped vertically like the signed distance fields.
+pub fn generate_subpixel_bitmap(metrics: &Metrics, rasterized: &[u8], subpixel: f32) -> Vec<u8> {
+    let width = metrics.width + 1;
+    let height = metrics.height;
+    let mut bitmap = vec![0; width * height];
+
+    for y in 0..height {
+        let row = &rasterized[y * metrics.width..(y + 1) * metrics.width];
+        let v_flip_offset = (height - y - 1) * width;
+
+        for x in 0..width {
+            let current = row.get(x).copied().unwrap_or(0) as f32;
+            let previous = if x == 0 { 0f32 } else { row[x - 1] as f32 };
+            bitmap[v_flip_offset + x] =
+                (current * (1f32 - subpixel) + previous * subpixel).round() as u8;
+        }
+    }
+
+    bitmap
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(width: usize, height: usize) -> Metrics {
+        Metrics {
+            width,
+            height,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn subpixel_bitmap_without_offset() {
+        let bitmap = generate_subpixel_bitmap(&metrics(2, 2), &[10, 20, 30, 40], 0.0);
+
+        // Rows are flipped, and the extra column is empty.
+        assert_eq!(bitmap, vec![30, 40, 0, 10, 20, 0]);
+    }
+
+    #[test]
+    fn subpixel_bitmap_with_offset() {
+        let bitmap = generate_subpixel_bitmap(&metrics(2, 1), &[200, 100], 0.25);
+
+        assert_eq!(bitmap, vec![150, 125, 25]);
+    }
+}
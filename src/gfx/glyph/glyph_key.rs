@@ -0,0 +1,39 @@
+use crate::gfx::Font;
+use fontdue::layout::GlyphRasterConfig;
+
+/// The number of horizontal subpixel variants that are rasterized for bitmap glyphs.
+pub const GLYPH_SUBPIXEL_VARIANTS: u8 = 4;
+
+/// How a glyph is rasterized into the atlas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GlyphRasterMode {
+    /// A signed distance field, rasterized at the sdf font size of the font.
+    Sdf,
+    /// A coverage bitmap, shifted right by `subpixel / GLYPH_SUBPIXEL_VARIANTS` pixels.
+    Bitmap { subpixel: u8 },
+}
+
+/// Identifies a rasterized glyph in the atlas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GlyphKey {
+    pub raster: GlyphRasterConfig,
+    pub mode: GlyphRasterMode,
+}
+
+impl GlyphKey {
+    /// Creates a key of the given character.
+    /// Signed distance fields do not depend on the font size, so all sizes share the same key.
+    pub fn new(font: &Font, c: char, font_size: f32, mode: GlyphRasterMode) -> Self {
+        Self {
+            raster: GlyphRasterConfig {
+                glyph_index: font.data.lookup_glyph_index(c),
+                px: match mode {
+                    GlyphRasterMode::Sdf => font.sdf_font_size,
+                    GlyphRasterMode::Bitmap { .. } => font_size,
+                },
+                font_hash: font.data.file_hash(),
+            },
+            mode,
+        }
+    }
+}
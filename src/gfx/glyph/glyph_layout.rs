@@ -1,36 +1,47 @@
-use super::GlyphLayoutConfig;
-use crate::{gfx::Font, math::Vec2, ui::UISize};
-use fontdue::layout::{GlyphRasterConfig, HorizontalAlign, VerticalAlign};
+use super::{
+    GlyphKey, GlyphLayoutConfig, GlyphRasterMode, TextRenderMode, GLYPH_SUBPIXEL_VARIANTS,
+};
+use crate::{
+    gfx::{Font, FontHandle},
+    math::Vec2,
+    ui::UISize,
+};
+use fontdue::layout::{HorizontalAlign, VerticalAlign, WrapStyle};
+use std::ops::Range;
 
 pub struct GlyphLayoutElement {
     pub size: Vec2,
     pub offset: Vec2,
-    pub key: GlyphRasterConfig,
+    pub key: GlyphKey,
+    /// The index of the font that supplied the glyph, in the fonts given to the layout.
+    pub font_index: usize,
 }
 
 // TODO: Add vertical align: baseline.
+/// Lays out the given characters. The first font is the primary font, and the rest are fallbacks
+/// that supply the glyphs missing in the primary font. Lines are laid out by the primary font, and
+/// wrapped at the width of the given size.
 pub fn compute_glyph_layout(
-    font: &Font,
+    fonts: &[FontHandle],
     font_size: f32,
     size: UISize,
     config: &GlyphLayoutConfig,
-    mut chars: impl Iterator<Item = char>,
+    mode: TextRenderMode,
+    chars: impl Iterator<Item = char>,
 ) -> Vec<GlyphLayoutElement> {
-    let pixel_ratio = font_size / font.sdf_font_size;
-    let inset = pixel_ratio * font.sdf_inset as f32;
+    let raster_mode = mode.raster_mode();
 
-    let mut lines = Vec::with_capacity(4);
-
-    loop {
-        let line = compute_glyph_layout_line(font, font_size, inset, &mut chars);
-
-        if line.elements.is_empty() {
-            break;
-        }
-
-        lines.push(line);
+    if fonts.is_empty() {
+        return Vec::new();
     }
 
+    let chars = collect_chars(config, chars);
+    let mut lines = Vec::from_iter(
+        break_lines(fonts, font_size, config, Some(size.width), &chars)
+            .into_iter()
+            .map(|range| compute_glyph_layout_line(fonts, font_size, raster_mode, &chars[range])),
+    );
+
     let total_height = font_size * lines.len() as f32;
     let vertical_offset = match config.vertical_align {
         VerticalAlign::Top => size.height - total_height,
@@ -52,22 +63,238 @@ pub fn compute_glyph_layout(
         for element in line.elements.iter_mut() {
             element.offset.x += horizontal_offset;
             element.offset.y += vertical_offset;
+
+            if let GlyphRasterMode::Bitmap { .. } = raster_mode {
+                snap_to_subpixel(element);
+            }
         }
     }
 
     lines.into_iter().flat_map(|line| line.elements).collect()
 }
 
+/// Lays out the characters as [`compute_glyph_layout`] does, but in the physical pixels of a screen of the given
+/// scale factor, so that bitmap glyphs are rasterized at the pixel size they are shown at and stay crisp. The sizes
+/// and offsets of the glyphs are returned in logical pixels, as `font_size` and `size` are given.
+pub fn compute_scaled_glyph_layout(
+    fonts: &[FontHandle],
+    font_size: f32,
+    size: UISize,
+    scale_factor: f32,
+    config: &GlyphLayoutConfig,
+    mode: TextRenderMode,
+    chars: impl Iterator<Item = char>,
+) -> Vec<GlyphLayoutElement> {
+    let scale_factor = if 0f32 < scale_factor {
+        scale_factor
+    } else {
+        1f32
+    };
+    let mut elements = compute_glyph_layout(
+        fonts,
+        font_size * scale_factor,
+        UISize::from_vec2(size.to_vec2() * scale_factor),
+        config,
+        mode,
+        chars,
+    );
+
+    for element in &mut elements {
+        element.size /= scale_factor;
+        element.offset /= scale_factor;
+    }
+
+    elements
+}
+
+/// Returns the size of the given characters when laid out by [`compute_glyph_layout`], wrapping lines
+/// at `max_width` if given. Nothing is rasterized.
+pub fn measure_glyph_layout(
+    fonts: &[FontHandle],
+    font_size: f32,
+    config: &GlyphLayoutConfig,
+    max_width: Option<f32>,
+    chars: impl Iterator<Item = char>,
+) -> Vec2 {
+    if fonts.is_empty() {
+        return Vec2::ZERO;
+    }
+
+    let chars = collect_chars(config, chars);
+    let lines = break_lines(fonts, font_size, config, max_width, &chars);
+    let width = lines
+        .iter()
+        .map(|range| line_width(fonts, font_size, &chars[range.clone()]))
+        .fold(0f32, f32::max);
+
+    Vec2::new(width, font_size * lines.len() as f32)
+}
+
+/// Collects the characters to lay out. Newlines are laid out as spaces unless hard breaks are wrapped.
+fn collect_chars(config: &GlyphLayoutConfig, chars: impl Iterator<Item = char>) -> Vec<char> {
+    if config.wrap_hard_breaks {
+        return Vec::from_iter(chars);
+    }
+
+    Vec::from_iter(chars.map(|c| if c == '\n' { ' ' } else { c }))
+}
+
+/// Breaks the characters into lines and returns their ranges. Lines are broken at newlines, and wrapped
+/// by the wrap style where they would be wider than `max_width`; a width that is not positive does not wrap.
+/// Whitespace at the end of lines is trimmed.
+fn break_lines(
+    fonts: &[FontHandle],
+    font_size: f32,
+    config: &GlyphLayoutConfig,
+    max_width: Option<f32>,
+    chars: &[char],
+) -> Vec<Range<usize>> {
+    let max_width = max_width.filter(|&max_width| 0f32 < max_width);
+    let mut lines = Vec::with_capacity(4);
+
+    if chars.is_empty() {
+        return lines;
+    }
+
+    let mut start = 0;
+    let mut width = 0f32;
+    let mut prev = None;
+    let mut is_blank = true;
+    // The start of the last word in the line that follows another, where the line can be wrapped.
+    let mut word_start = None;
+    let mut index = 0;
+
+    while index < chars.len() {
+        let c = chars[index];
+
+        if c == '\n' {
+            lines.push(trim_line(chars, start..index));
+            index += 1;
+            (start, width, prev, is_blank, word_start) = (index, 0f32, None, true, None);
+            continue;
+        }
+
+        let (font_index, kern) = select_glyph_font_and_kern(fonts, font_size, prev, c);
+        let advance = kern + fonts[font_index].data.metrics(c, font_size).advance_width;
+        let is_whitespace = c.is_whitespace();
+
+        // Whitespace hangs over the end of lines instead of wrapping them.
+        if !is_whitespace
+            && start < index
+            && max_width.is_some_and(|max_width| max_width < width + advance)
+        {
+            let wrap_at = match config.wrap_style {
+                WrapStyle::Word => word_start.unwrap_or(index),
+                WrapStyle::Letter => index,
+            };
+            lines.push(trim_line(chars, start..wrap_at));
+            // The wrapped word is measured again, as it is no longer kerned against the previous line.
+            index = wrap_at;
+            (start, width, prev, is_blank, word_start) = (index, 0f32, None, true, None);
+            continue;
+        }
+
+        if !is_whitespace {
+            if !is_blank && chars[index - 1].is_whitespace() {
+                word_start = Some(index);
+            }
+
+            is_blank = false;
+        }
+
+        width += advance;
+        prev = Some((c, font_index));
+        index += 1;
+    }
+
+    lines.push(trim_line(chars, start..chars.len()));
+    lines
+}
+
+fn trim_line(chars: &[char], range: Range<usize>) -> Range<usize> {
+    let len = chars[range.clone()]
+        .iter()
+        .rposition(|c| !c.is_whitespace())
+        .map_or(0, |index| index + 1);
+
+    range.start..range.start + len
+}
+
+/// Returns the width of the line, which matches the width of its layout.
+fn line_width(fonts: &[FontHandle], font_size: f32, chars: &[char]) -> f32 {
+    let mut prev = None;
+    let mut width = 0f32;
+
+    for &c in chars {
+        let (font_index, kern) = select_glyph_font_and_kern(fonts, font_size, prev, c);
+        width += kern + fonts[font_index].data.metrics(c, font_size).advance_width;
+        prev = Some((c, font_index));
+    }
+
+    width
+}
+
+/// Snaps the glyph to pixels, selecting the subpixel variant from the fractional horizontal position.
+fn snap_to_subpixel(element: &mut GlyphLayoutElement) {
+    let mut x = element.offset.x.floor();
+    let mut subpixel = ((element.offset.x - x) * GLYPH_SUBPIXEL_VARIANTS as f32).round() as u8;
+
+    if subpixel == GLYPH_SUBPIXEL_VARIANTS {
+        x += 1f32;
+        subpixel = 0;
+    }
+
+    element.offset = Vec2::new(x, element.offset.y.round());
+    element.key.mode = GlyphRasterMode::Bitmap { subpixel };
+}
+
 struct GlyphLineLayout {
     pub width: f32,
     pub elements: Vec<GlyphLayoutElement>,
 }
 
+/// Returns the index of the first font that has a glyph of the given character.
+/// Falls back to the primary font, which renders its .notdef glyph, if no font has the glyph.
+pub fn select_glyph_font(fonts: &[FontHandle], c: char) -> usize {
+    select_glyph_font_by(fonts.len(), |index| fonts[index].data.lookup_glyph_index(c))
+}
+
+fn select_glyph_font_by(font_count: usize, lookup_glyph_index: impl Fn(usize) -> u16) -> usize {
+    // Index 0 is the .notdef glyph.
+    (0..font_count)
+        .find(|&index| lookup_glyph_index(index) != 0)
+        .unwrap_or(0)
+}
+
+/// Returns the font of the character and its kerning against the previous character of the line.
+fn select_glyph_font_and_kern(
+    fonts: &[FontHandle],
+    font_size: f32,
+    prev: Option<(char, usize)>,
+    c: char,
+) -> (usize, f32) {
+    let font_index = select_glyph_font(fonts, c);
+    // Kerning is only defined between glyphs of the same font.
+    let kern = prev
+        .filter(|&(_, prev_font_index)| prev_font_index == font_index)
+        .and_then(|(prev, _)| fonts[font_index].data.horizontal_kern(prev, c, font_size))
+        .unwrap_or(0.0f32);
+
+    (font_index, kern)
+}
+
+fn glyph_inset(font: &Font, font_size: f32, raster_mode: GlyphRasterMode) -> f32 {
+    match raster_mode {
+        GlyphRasterMode::Sdf => font_size / font.sdf_font_size * font.sdf_inset as f32,
+        GlyphRasterMode::Bitmap { .. } => 0f32,
+    }
+}
+
 fn compute_glyph_layout_line(
-    font: &Font,
+    fonts: &[FontHandle],
     font_size: f32,
-    inset: f32,
-    chars: &mut impl Iterator<Item = char>,
+    raster_mode: GlyphRasterMode,
+    chars: &[char],
 ) -> GlyphLineLayout {
     let mut prev = None;
     let mut acc_width = 0.0f32;
@@ -76,32 +303,31 @@ fn compute_glyph_layout_line(
     let mut acc_horizontal_offset = 0f32;
     let mut elements = Vec::new();
 
-    for c in chars {
-        if c == '\n' {
-            break;
-        }
-
+    for &c in chars {
+        let (font_index, kern) = select_glyph_font_and_kern(fonts, font_size, prev, c);
+        let font = &fonts[font_index];
+        let inset = glyph_inset(font, font_size, raster_mode);
         let metrics = font.data.metrics(c, font_size);
-        let kern = prev
-            .and_then(|prev| font.data.horizontal_kern(prev, c, font_size))
-            .unwrap_or(0.0f32);
 
         let offset = Vec2::new(
             -inset + metrics.xmin as f32 + kern + acc_horizontal_offset,
             -inset + metrics.ymin as f32,
         );
-        let size = Vec2::new(
-            metrics.width as f32 + inset * 2f32,
-            metrics.height as f32 + inset * 2f32,
-        );
+        let size = match raster_mode {
+            GlyphRasterMode::Sdf => Vec2::new(
+                metrics.width as f32 + inset * 2f32,
+                metrics.height as f32 + inset * 2f32,
+            ),
+            // Bitmaps have an extra column to hold the subpixel offset.
+            GlyphRasterMode::Bitmap { .. } => {
+                Vec2::new(metrics.width as f32 + 1f32, metrics.height as f32)
+            }
+        };
         elements.push(GlyphLayoutElement {
             size,
             offset,
-            key: GlyphRasterConfig {
-                glyph_index: font.data.lookup_glyph_index(c),
-                px: font_size,
-                font_hash: font.data.file_hash(),
-            },
+            key: GlyphKey::new(font, c, font_size, raster_mode),
+            font_index,
         });
 
         acc_width += kern + metrics.advance_width;
@@ -109,7 +335,7 @@ fn compute_glyph_layout_line(
         // acc_height_max = acc_height_max.max(metrics.ymin as f32 + metrics.height as f32);
         acc_horizontal_offset += kern + metrics.advance_width;
 
-        prev = Some(c);
+        prev = Some((c, font_index));
     }
 
     GlyphLineLayout {
@@ -117,3 +343,232 @@ fn compute_glyph_layout_line(
         elements,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fontdue::{layout::GlyphRasterConfig, FontSettings};
+
+    fn noto_sans() -> FontHandle {
+        let data = include_bytes!("../../../r3d-editor/assets/fonts/NotoSans-Regular.ttf");
+        let font = fontdue::Font::from_bytes(data.as_slice(), FontSettings::default()).unwrap();
+        FontHandle::new(Font::with_default(font))
+    }
+
+    fn config(
+        horizontal_align: HorizontalAlign,
+        vertical_align: VerticalAlign,
+        wrap_style: WrapStyle,
+    ) -> GlyphLayoutConfig {
+        GlyphLayoutConfig::new(horizontal_align, vertical_align, wrap_style, true)
+    }
+
+    /// Lays out the text in the given size, aligned to the left-bottom and to the right-top corners.
+    /// The layouts match where the lines are as wide and as many as the size.
+    fn layout_in_corners(
+        fonts: &[FontHandle],
+        font_size: f32,
+        mode: TextRenderMode,
+        wrap_style: WrapStyle,
+        size: Vec2,
+        text: &str,
+    ) -> (Vec<GlyphLayoutElement>, Vec<GlyphLayoutElement>) {
+        let layout = |horizontal_align, vertical_align| {
+            compute_glyph_layout(
+                fonts,
+                font_size,
+                UISize::from_vec2(size),
+                &config(horizontal_align, vertical_align, wrap_style),
+                mode,
+                text.chars(),
+            )
+        };
+
+        (
+            layout(HorizontalAlign::Left, VerticalAlign::Bottom),
+            layout(HorizontalAlign::Right, VerticalAlign::Top),
+        )
+    }
+
+    fn element(x: f32, y: f32) -> GlyphLayoutElement {
+        GlyphLayoutElement {
+            size: Vec2::new(10.0, 10.0),
+            offset: Vec2::new(x, y),
+            key: GlyphKey {
+                raster: GlyphRasterConfig {
+                    glyph_index: 1,
+                    px: 16.0,
+                    font_hash: 0,
+                },
+                mode: GlyphRasterMode::Bitmap { subpixel: 0 },
+            },
+            font_index: 0,
+        }
+    }
+
+    #[test]
+    fn snap_to_subpixel_selects_variant() {
+        let mut glyph = element(10.3, 4.6);
+        snap_to_subpixel(&mut glyph);
+
+        assert_eq!(glyph.offset, Vec2::new(10.0, 5.0));
+        assert_eq!(glyph.key.mode, GlyphRasterMode::Bitmap { subpixel: 1 });
+
+        let mut glyph = element(-1.5, 0.0);
+        snap_to_subpixel(&mut glyph);
+
+        assert_eq!(glyph.offset, Vec2::new(-2.0, 0.0));
+        assert_eq!(glyph.key.mode, GlyphRasterMode::Bitmap { subpixel: 2 });
+    }
+
+    #[test]
+    fn snap_to_subpixel_carries_to_next_pixel() {
+        let mut glyph = element(10.9, 0.0);
+        snap_to_subpixel(&mut glyph);
+
+        assert_eq!(glyph.offset, Vec2::new(11.0, 0.0));
+        assert_eq!(glyph.key.mode, GlyphRasterMode::Bitmap { subpixel: 0 });
+    }
+
+    #[test]
+    fn select_glyph_font_walks_fallbacks() {
+        // The primary font covers Latin, and the fallback covers kana.
+        let lookup = |c: char| {
+            move |index: usize| match (index, c) {
+                (0, 'A'..='z') => c as u16,
+                (1, '\u{3040}'..='\u{30ff}') => c as u16,
+                _ => 0,
+            }
+        };
+        let selected = "Aあ★"
+            .chars()
+            .map(|c| select_glyph_font_by(2, lookup(c)))
+            .collect::<Vec<_>>();
+
+        // No font has the symbol, so the .notdef glyph of the primary font is used.
+        assert_eq!(selected, vec![0, 1, 0]);
+    }
+
+    #[test]
+    fn measured_size_fits_layout() {
+        let font = noto_sans();
+        let cases = [
+            (vec![font.clone()], 16f32, TextRenderMode::Bitmap),
+            (
+                vec![font.clone(), font.clone()],
+                48f32,
+                TextRenderMode::Sdf { px_range: 1.0 },
+            ),
+        ];
+        let texts = [
+            "Hello, world!",
+            "AVAST ye\nkerned  lines \nof text",
+            "a\n\nb",
+        ];
+        let default_config = GlyphLayoutConfig::default();
+
+        for (fonts, font_size, mode) in &cases {
+            for text in texts {
+                let size =
+                    measure_glyph_layout(fonts, *font_size, &default_config, None, text.chars());
+                let line_count = text.lines().count() as f32;
+                assert_eq!(size.y, font_size * line_count, "{:?}", text);
+
+                let (left_bottom, right_top) =
+                    layout_in_corners(fonts, *font_size, *mode, WrapStyle::Word, size, text);
+                assert_eq!(left_bottom.len(), right_top.len());
+
+                // The widest line is flush with both sides, and the lines fill the height.
+                let shifts = Vec::from_iter(
+                    left_bottom
+                        .iter()
+                        .zip(&right_top)
+                        .map(|(left, right)| right.offset - left.offset),
+                );
+                assert!(shifts
+                    .iter()
+                    .all(|shift| 0f32 <= shift.x && shift.y == 0f32));
+                assert!(shifts.iter().any(|shift| shift.x == 0f32), "{:?}", text);
+            }
+        }
+    }
+
+    #[test]
+    fn measured_size_keeps_wrapped_lines() {
+        let fonts = [noto_sans()];
+        let font_size = 20f32;
+        let measure = |wrap_style, max_width, text: &str| {
+            measure_glyph_layout(
+                &fonts,
+                font_size,
+                &config(HorizontalAlign::Left, VerticalAlign::Top, wrap_style),
+                max_width,
+                text.chars(),
+            )
+        };
+        let word_width = measure(WrapStyle::Word, None, "wwww").x;
+
+        for (wrap_style, text, line_count) in [
+            (WrapStyle::Word, "wwww ii  wwww", 3f32),
+            (WrapStyle::Letter, "wwwwww", 2f32),
+        ] {
+            let size = measure(wrap_style, Some(word_width), text);
+            assert_eq!(size, Vec2::new(word_width, font_size * line_count));
+            // Laid out in the measured size, the text is wrapped into the same lines.
+            let (left_bottom, right_top) = layout_in_corners(
+                &fonts,
+                font_size,
+                TextRenderMode::Bitmap,
+                wrap_style,
+                size,
+                text,
+            );
+            assert!(left_bottom
+                .iter()
+                .zip(&right_top)
+                .all(|(left, right)| left.offset.y == right.offset.y));
+        }
+
+        // Newlines are laid out as spaces unless hard breaks are wrapped.
+        let no_hard_breaks = GlyphLayoutConfig::new(
+            HorizontalAlign::Left,
+            VerticalAlign::Top,
+            WrapStyle::Word,
+            false,
+        );
+        let size = measure_glyph_layout(&fonts, font_size, &no_hard_breaks, None, "a\nb".chars());
+        assert_eq!(size, measure(WrapStyle::Word, None, "a b"));
+    }
+
+    #[test]
+    fn scaled_layout_rasterizes_at_physical_pixel_size() {
+        let fonts = [noto_sans()];
+        let layout = |scale_factor| {
+            compute_scaled_glyph_layout(
+                &fonts,
+                16f32,
+                UISize::from_vec2(Vec2::new(200f32, 40f32)),
+                scale_factor,
+                &config(
+                    HorizontalAlign::Left,
+                    VerticalAlign::Bottom,
+                    WrapStyle::Word,
+                ),
+                TextRenderMode::Bitmap,
+                "Hello".chars(),
+            )
+        };
+        let (logical, physical) = (layout(1f32), layout(2f32));
+
+        assert_eq!(logical.len(), physical.len());
+        assert!(logical.iter().all(|glyph| glyph.key.raster.px == 16f32));
+        assert!(physical.iter().all(|glyph| glyph.key.raster.px == 32f32));
+
+        // The glyphs take the same logical space, up to the rounding of the rasterized metrics and the extra
+        // subpixel column, which is half as wide in logical pixels.
+        for (logical, physical) in logical.iter().zip(&physical) {
+            assert!((logical.offset.x - physical.offset.x).abs() <= 1f32);
+            assert!((logical.size.x - physical.size.x).abs() <= 1.5f32);
+        }
+    }
+}
@@ -1,91 +1,294 @@
-use super::{generate_sdf, GlyphSprite, GlyphSpriteHandle, GlyphTexture};
-use crate::{
-    gfx::{BindGroupLayoutCache, Font, FontHandle, GfxContextHandle},
-    use_context,
+use super::{
+    generate_sdf, generate_subpixel_bitmap, GlyphKey, GlyphRasterMode, GlyphSprite,
+    GlyphSpriteHandle, GlyphTexture, TextRenderMode, GLYPH_SUBPIXEL_VARIANTS,
 };
-use fontdue::layout::GlyphRasterConfig;
+use crate::gfx::{BindGroupLayoutCache, FontHandle, GfxContextHandle};
 use std::collections::HashMap;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GlyphAtlasConfig {
+    /// The width and height of each atlas page in texels.
+    pub page_size: u16,
+    /// The number of pages to be allocated before evicting glyphs.
+    /// More pages are allocated if there is no glyph to evict.
+    pub max_pages: usize,
+    /// Glyphs that have not been used for this number of frames can be evicted. It must be at least 1.
+    pub eviction_frames: u64,
+}
+
+impl Default for GlyphAtlasConfig {
+    fn default() -> Self {
+        Self {
+            page_size: 2048,
+            max_pages: 4,
+            eviction_frames: 120,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GlyphAtlasStats {
+    pub page_count: usize,
+    pub page_size: u16,
+    pub glyph_count: usize,
+    /// The number of texels occupied by glyphs, over all pages.
+    pub used_texels: u64,
+    /// The number of texels of all pages.
+    pub total_texels: u64,
+    /// The number of glyphs evicted so far.
+    pub evicted_glyph_count: u64,
+}
+
+impl GlyphAtlasStats {
+    /// Returns the ratio of the occupied texels in range `[0, 1]`.
+    pub fn occupancy(&self) -> f32 {
+        if self.total_texels == 0 {
+            return 0.0;
+        }
+
+        self.used_texels as f32 / self.total_texels as f32
+    }
+}
+
+struct GlyphEntry {
+    sprite: GlyphSpriteHandle,
+    last_used_frame: u64,
+}
+
 pub struct GlyphManager {
     gfx_ctx: GfxContextHandle,
-    glyphs: HashMap<GlyphRasterConfig, GlyphSpriteHandle>,
-    glyph_textures: HashMap<*const Font, Vec<GlyphTexture>>,
+    config: GlyphAtlasConfig,
+    frame: u64,
+    generation: u64,
+    evicted_glyph_count: u64,
+    glyphs: HashMap<GlyphKey, GlyphEntry>,
+    pages: Vec<GlyphTexture>,
 }
 
 impl GlyphManager {
-    pub fn new(gfx_ctx: GfxContextHandle) -> Self {
+    pub fn new(gfx_ctx: GfxContextHandle, config: GlyphAtlasConfig) -> Self {
         Self {
             gfx_ctx,
+            config,
+            frame: 0,
+            generation: 0,
+            evicted_glyph_count: 0,
             glyphs: HashMap::new(),
-            glyph_textures: HashMap::new(),
+            pages: Vec::with_capacity(config.max_pages),
         }
     }
 
-    pub fn glyph(
+    pub fn config(&self) -> &GlyphAtlasConfig {
+        &self.config
+    }
+
+    /// Returns a number that changes whenever glyphs are evicted.
+    /// Glyph sprites obtained before the change may refer to areas reused by other glyphs,
+    /// so they must be obtained again.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn stats(&self) -> GlyphAtlasStats {
+        let page_size = self.config.page_size as u64;
+
+        GlyphAtlasStats {
+            page_count: self.pages.len(),
+            page_size: self.config.page_size,
+            glyph_count: self.glyphs.len(),
+            used_texels: self
+                .pages
+                .iter()
+                .map(|page| page.allocator().used_texels())
+                .sum(),
+            total_texels: self.pages.len() as u64 * page_size * page_size,
+            evicted_glyph_count: self.evicted_glyph_count,
+        }
+    }
+
+    /// Advances the frame counter that is used to track the last usage of glyphs.
+    pub fn next_frame(&mut self) {
+        self.frame += 1;
+    }
+
+    /// Marks the given glyph as used in the current frame.
+    /// Returns `false` if the glyph is not in the atlas, e.g. it has been evicted.
+    pub fn touch(&mut self, glyph: &GlyphKey) -> bool {
+        match self.glyphs.get_mut(glyph) {
+            Some(entry) => {
+                entry.last_used_frame = self.frame;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Rasterizes the glyphs of the given characters up front, so that they are ready to be rendered.
+    /// All subpixel variants are rasterized in the bitmap mode.
+    pub fn preload(
         &mut self,
         bind_group_layout_cache: &mut BindGroupLayoutCache,
         font: &FontHandle,
-        glyph: GlyphRasterConfig,
-    ) -> GlyphSpriteHandle {
-        if !self.glyphs.contains_key(&glyph) {
-            let (metrics, rasterized) = font
-                .data
-                .rasterize_indexed(glyph.glyph_index as _, font.sdf_font_size);
-            let sdf = generate_sdf(
-                &metrics,
-                &rasterized,
-                font.sdf_inset,
-                font.sdf_radius,
-                font.sdf_cutoff,
-            );
-            let glyph_textures = self
-                .glyph_textures
-                .entry(font.as_ptr())
-                .or_insert_with(|| Vec::with_capacity(2));
-
-            for glyph_texture in glyph_textures.iter_mut() {
-                if let Some(mapping) = glyph_texture.glyph(
-                    &self.gfx_ctx.queue,
-                    (metrics.width + 2 * font.sdf_inset) as u16,
-                    (metrics.height + 2 * font.sdf_inset) as u16,
-                    &sdf,
-                ) {
-                    self.glyphs.insert(
-                        glyph,
-                        GlyphSpriteHandle::new(GlyphSprite::new(
-                            glyph_texture.texture_bind_group().clone(),
-                            glyph_texture.sampler_bind_group().clone(),
-                            glyph_texture.texture().clone(),
-                            mapping,
-                        )),
-                    );
-                    return self.glyphs.get(&glyph).unwrap().clone();
+        font_size: f32,
+        mode: TextRenderMode,
+        chars: &str,
+    ) {
+        for c in chars.chars() {
+            if c == '\n' {
+                continue;
+            }
+
+            match mode.raster_mode() {
+                GlyphRasterMode::Sdf => {
+                    let key = GlyphKey::new(font, c, font_size, GlyphRasterMode::Sdf);
+                    self.glyph(bind_group_layout_cache, font, key);
+                }
+                GlyphRasterMode::Bitmap { .. } => {
+                    for subpixel in 0..GLYPH_SUBPIXEL_VARIANTS {
+                        let key =
+                            GlyphKey::new(font, c, font_size, GlyphRasterMode::Bitmap { subpixel });
+                        self.glyph(bind_group_layout_cache, font, key);
+                    }
                 }
             }
+        }
+    }
+
+    pub fn glyph(
+        &mut self,
+        bind_group_layout_cache: &mut BindGroupLayoutCache,
+        font: &FontHandle,
+        glyph: GlyphKey,
+    ) -> GlyphSpriteHandle {
+        if let Some(entry) = self.glyphs.get_mut(&glyph) {
+            entry.last_used_frame = self.frame;
+            return entry.sprite.clone();
+        }
 
-            let ctx = use_context();
-            let mut glyph_texture =
-                GlyphTexture::new(&ctx.gfx_ctx.device, bind_group_layout_cache, font.clone());
-            let mapping = glyph_texture
-                .glyph(
-                    &self.gfx_ctx.queue,
-                    (metrics.width + 2 * font.sdf_inset) as u16,
-                    (metrics.height + 2 * font.sdf_inset) as u16,
-                    &sdf,
+        let (width, height, data) = match glyph.mode {
+            GlyphRasterMode::Sdf => {
+                let (metrics, rasterized) = font
+                    .data
+                    .rasterize_indexed(glyph.raster.glyph_index as _, font.sdf_font_size);
+                let sdf = generate_sdf(
+                    &metrics,
+                    &rasterized,
+                    font.sdf_inset,
+                    font.sdf_radius,
+                    font.sdf_cutoff,
+                );
+                (
+                    metrics.width + 2 * font.sdf_inset,
+                    metrics.height + 2 * font.sdf_inset,
+                    sdf,
                 )
-                .unwrap();
-            self.glyphs.insert(
-                glyph,
-                GlyphSpriteHandle::new(GlyphSprite::new(
-                    glyph_texture.texture_bind_group().clone(),
-                    glyph_texture.sampler_bind_group().clone(),
-                    glyph_texture.texture().clone(),
+            }
+            GlyphRasterMode::Bitmap { subpixel } => {
+                let (metrics, rasterized) = font
+                    .data
+                    .rasterize_indexed(glyph.raster.glyph_index as _, glyph.raster.px);
+                let bitmap = generate_subpixel_bitmap(
+                    &metrics,
+                    &rasterized,
+                    subpixel as f32 / GLYPH_SUBPIXEL_VARIANTS as f32,
+                );
+                (metrics.width + 1, metrics.height, bitmap)
+            }
+        };
+        let sprite = self.allocate(bind_group_layout_cache, width as u16, height as u16, &data);
+
+        self.glyphs.insert(
+            glyph,
+            GlyphEntry {
+                sprite: sprite.clone(),
+                last_used_frame: self.frame,
+            },
+        );
+        sprite
+    }
+
+    fn allocate(
+        &mut self,
+        bind_group_layout_cache: &mut BindGroupLayoutCache,
+        width: u16,
+        height: u16,
+        data: &[u8],
+    ) -> GlyphSpriteHandle {
+        if let Some(sprite) = self.allocate_in_pages(width, height, data) {
+            return sprite;
+        }
+
+        if self.config.max_pages <= self.pages.len() && self.evict() {
+            if let Some(sprite) = self.allocate_in_pages(width, height, data) {
+                return sprite;
+            }
+        }
+
+        let mut page = GlyphTexture::new(
+            &self.gfx_ctx.device,
+            bind_group_layout_cache,
+            self.config.page_size,
+        );
+        let mapping = page
+            .glyph(&self.gfx_ctx.queue, width, height, data)
+            .unwrap();
+        let sprite = GlyphSpriteHandle::new(GlyphSprite::new(
+            page.texture_bind_group().clone(),
+            page.sampler_bind_group().clone(),
+            page.texture().clone(),
+            self.pages.len(),
+            mapping,
+        ));
+        self.pages.push(page);
+        sprite
+    }
+
+    fn allocate_in_pages(
+        &mut self,
+        width: u16,
+        height: u16,
+        data: &[u8],
+    ) -> Option<GlyphSpriteHandle> {
+        for (index, page) in self.pages.iter_mut().enumerate() {
+            if let Some(mapping) = page.glyph(&self.gfx_ctx.queue, width, height, data) {
+                return Some(GlyphSpriteHandle::new(GlyphSprite::new(
+                    page.texture_bind_group().clone(),
+                    page.sampler_bind_group().clone(),
+                    page.texture().clone(),
+                    index,
                     mapping,
-                )),
-            );
-            glyph_textures.push(glyph_texture);
+                )));
+            }
+        }
+
+        None
+    }
+
+    /// Evicts all glyphs that have not been used for the configured number of frames.
+    /// Returns `true` if any glyph has been evicted.
+    fn evict(&mut self) -> bool {
+        let eviction_frames = self.config.eviction_frames.max(1);
+        let frame = self.frame;
+        let pages = &mut self.pages;
+        let glyph_count = self.glyphs.len();
+
+        self.glyphs.retain(|_, entry| {
+            if frame - entry.last_used_frame < eviction_frames {
+                return true;
+            }
+
+            pages[entry.sprite.page()].deallocate(entry.sprite.mapping());
+            false
+        });
+
+        let evicted_glyph_count = glyph_count - self.glyphs.len();
+
+        if evicted_glyph_count == 0 {
+            return false;
         }
 
-        self.glyphs.get(&glyph).unwrap().clone()
+        self.evicted_glyph_count += evicted_glyph_count as u64;
+        self.generation += 1;
+        true
     }
 }
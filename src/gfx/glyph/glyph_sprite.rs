@@ -8,6 +8,7 @@ pub struct GlyphSprite {
     texture_bind_group: Arc<BindGroup>,
     sampler_bind_group: Arc<BindGroup>,
     texture: TextureHandle,
+    page: usize,
     mapping: SpriteTexelMapping,
 }
 
@@ -16,12 +17,14 @@ impl GlyphSprite {
         texture_bind_group: Arc<BindGroup>,
         sampler_bind_group: Arc<BindGroup>,
         texture: TextureHandle,
+        page: usize,
         mapping: SpriteTexelMapping,
     ) -> Self {
         Self {
             texture_bind_group,
             sampler_bind_group,
             texture,
+            page,
             mapping,
         }
     }
@@ -38,6 +41,11 @@ impl GlyphSprite {
         &self.texture
     }
 
+    /// Returns the index of the atlas page that contains this glyph.
+    pub fn page(&self) -> usize {
+        self.page
+    }
+
     pub fn mapping(&self) -> SpriteTexelMapping {
         self.mapping
     }
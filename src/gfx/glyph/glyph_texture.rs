@@ -1,4 +1,4 @@
-use crate::gfx::{BindGroupLayoutCache, FontHandle, SpriteTexelMapping, Texture, TextureHandle};
+use crate::gfx::{BindGroupLayoutCache, SpriteTexelMapping, Texture, TextureHandle};
 use std::{cmp::max, sync::Arc};
 use wgpu::{
     BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayoutEntry, BindingResource,
@@ -11,19 +11,16 @@ pub struct GlyphTexture {
     texture_bind_group: Arc<BindGroup>,
     sampler_bind_group: Arc<BindGroup>,
     texture: TextureHandle,
-    font: FontHandle,
-    offset_x: u16,
-    offset_y: u16,
-    line_height: u16,
+    allocator: GlyphAtlasAllocator,
 }
 
 impl GlyphTexture {
     pub fn new(
         device: &Device,
         bind_group_layout_cache: &mut BindGroupLayoutCache,
-        font: FontHandle,
+        size: u16,
     ) -> Self {
-        let texture = Texture::create_empty(2048u16, 2048u16, TextureFormat::R8Unorm, device);
+        let texture = Texture::create_empty(size, size, TextureFormat::R8Unorm, device);
         let texture_bind_group_layout =
             bind_group_layout_cache.create_layout(vec![BindGroupLayoutEntry {
                 binding: 0,
@@ -67,10 +64,7 @@ impl GlyphTexture {
             texture_bind_group,
             sampler_bind_group,
             texture: TextureHandle::new(texture),
-            font,
-            offset_x: 0,
-            offset_y: 0,
-            line_height: 0,
+            allocator: GlyphAtlasAllocator::new(size),
         }
     }
 
@@ -82,68 +76,244 @@ impl GlyphTexture {
         &self.sampler_bind_group
     }
 
-    pub fn font(&self) -> &FontHandle {
-        &self.font
-    }
-
     pub fn texture(&self) -> &TextureHandle {
         &self.texture
     }
 
+    pub fn allocator(&self) -> &GlyphAtlasAllocator {
+        &self.allocator
+    }
+
+    /// Allocates an area for the given glyph data and uploads it. Returns `None` if the page is full.
     pub fn glyph(
         &mut self,
         queue: &Queue,
-        sdf_width: u16,
-        sdf_height: u16,
-        sdf: &[u8],
+        width: u16,
+        height: u16,
+        data: &[u8],
     ) -> Option<SpriteTexelMapping> {
-        if 2048 < self.offset_y + sdf_height {
-            return None;
-        }
+        let mapping = self.allocator.allocate(width, height)?;
 
-        if 2048 < self.offset_x + sdf_width {
-            self.offset_x = 0;
-            self.offset_y += self.line_height;
-            self.line_height = sdf_height;
-
-            if 2048 < self.offset_y + sdf_height {
-                return None;
-            }
+        if width == 0 || height == 0 {
+            return Some(mapping);
         }
 
-        let mapping = SpriteTexelMapping::new(
-            self.offset_x as _,
-            (self.offset_x + sdf_width) as _,
-            self.offset_y as _,
-            (self.offset_y + sdf_height) as _,
-        );
         queue.write_texture(
             ImageCopyTexture {
                 texture: &self.texture.texture,
                 mip_level: 0,
                 origin: Origin3d {
-                    x: self.offset_x as u32,
-                    y: self.offset_y as u32,
+                    x: mapping.x_min as u32,
+                    y: mapping.y_min as u32,
                     z: 0,
                 },
                 aspect: TextureAspect::All,
             },
-            &sdf,
+            data,
             ImageDataLayout {
                 offset: 0,
-                bytes_per_row: Some(sdf_width as u32),
-                rows_per_image: Some(sdf_height as u32),
+                bytes_per_row: Some(width as u32),
+                rows_per_image: Some(height as u32),
             },
             Extent3d {
-                width: sdf_width as u32,
-                height: sdf_height as u32,
+                width: width as u32,
+                height: height as u32,
                 ..Default::default()
             },
         );
 
-        self.offset_x += sdf_width;
-        self.line_height = max(self.line_height, sdf_height);
+        Some(mapping)
+    }
+
+    /// Releases the area of an evicted glyph so that it can be reused.
+    pub fn deallocate(&mut self, mapping: SpriteTexelMapping) {
+        self.allocator.deallocate(mapping);
+    }
+}
 
+/// Packs glyphs into a square page row by row. Areas of deallocated glyphs are kept in a free list
+/// and reused by later glyphs that fit into them.
+#[derive(Debug, Clone)]
+pub struct GlyphAtlasAllocator {
+    size: u16,
+    offset_x: u16,
+    offset_y: u16,
+    line_height: u16,
+    free_rects: Vec<SpriteTexelMapping>,
+    used_texels: u64,
+}
+
+impl GlyphAtlasAllocator {
+    pub fn new(size: u16) -> Self {
+        Self {
+            size,
+            offset_x: 0,
+            offset_y: 0,
+            line_height: 0,
+            free_rects: Vec::new(),
+            used_texels: 0,
+        }
+    }
+
+    pub fn size(&self) -> u16 {
+        self.size
+    }
+
+    /// Returns the number of texels occupied by allocated glyphs.
+    pub fn used_texels(&self) -> u64 {
+        self.used_texels
+    }
+
+    pub fn allocate(&mut self, width: u16, height: u16) -> Option<SpriteTexelMapping> {
+        let mapping = self
+            .allocate_from_free_rects(width, height)
+            .or_else(|| self.allocate_from_rows(width, height))?;
+        self.used_texels += width as u64 * height as u64;
         Some(mapping)
     }
+
+    pub fn deallocate(&mut self, mapping: SpriteTexelMapping) {
+        self.used_texels -= mapping.width() as u64 * mapping.height() as u64;
+        self.free_rects.push(mapping);
+    }
+
+    fn allocate_from_free_rects(&mut self, width: u16, height: u16) -> Option<SpriteTexelMapping> {
+        let index = self
+            .free_rects
+            .iter()
+            .enumerate()
+            .filter(|(_, rect)| width <= rect.width() && height <= rect.height())
+            .min_by_key(|(_, rect)| rect.width() as u32 * rect.height() as u32)
+            .map(|(index, _)| index)?;
+        let rect = self.free_rects.swap_remove(index);
+        let mapping = SpriteTexelMapping::new(
+            rect.x_min,
+            rect.x_min + width,
+            rect.y_min,
+            rect.y_min + height,
+        );
+
+        // Splits the remaining area into the right and the bottom side, so no area is lost.
+        if mapping.x_max < rect.x_max {
+            self.free_rects.push(SpriteTexelMapping::new(
+                mapping.x_max,
+                rect.x_max,
+                rect.y_min,
+                rect.y_max,
+            ));
+        }
+
+        if mapping.y_max < rect.y_max {
+            self.free_rects.push(SpriteTexelMapping::new(
+                rect.x_min,
+                mapping.x_max,
+                mapping.y_max,
+                rect.y_max,
+            ));
+        }
+
+        Some(mapping)
+    }
+
+    fn allocate_from_rows(&mut self, width: u16, height: u16) -> Option<SpriteTexelMapping> {
+        let size = self.size as u32;
+
+        if size < width as u32 {
+            return None;
+        }
+
+        if size < self.offset_x as u32 + width as u32 {
+            if size < self.offset_y as u32 + self.line_height as u32 + height as u32 {
+                return None;
+            }
+
+            self.offset_x = 0;
+            self.offset_y += self.line_height;
+            self.line_height = 0;
+        }
+
+        if size < self.offset_y as u32 + height as u32 {
+            return None;
+        }
+
+        let mapping = SpriteTexelMapping::new(
+            self.offset_x,
+            self.offset_x + width,
+            self.offset_y,
+            self.offset_y + height,
+        );
+
+        self.offset_x += width;
+        self.line_height = max(self.line_height, height);
+
+        Some(mapping)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allocator_packs_rows() {
+        let mut allocator = GlyphAtlasAllocator::new(100);
+
+        assert_eq!(
+            allocator.allocate(60, 30),
+            Some(SpriteTexelMapping::new(0, 60, 0, 30))
+        );
+        assert_eq!(
+            allocator.allocate(30, 20),
+            Some(SpriteTexelMapping::new(60, 90, 0, 20))
+        );
+        // The next glyph does not fit into the first row.
+        assert_eq!(
+            allocator.allocate(20, 40),
+            Some(SpriteTexelMapping::new(0, 20, 30, 70))
+        );
+        assert_eq!(
+            allocator.allocate(20, 40),
+            Some(SpriteTexelMapping::new(20, 40, 30, 70))
+        );
+        assert_eq!(allocator.used_texels(), 60 * 30 + 30 * 20 + 20 * 40 * 2);
+    }
+
+    #[test]
+    fn allocator_rejects_when_full() {
+        let mut allocator = GlyphAtlasAllocator::new(100);
+
+        assert_eq!(allocator.allocate(101, 10), None);
+        assert!(allocator.allocate(100, 60).is_some());
+        assert_eq!(allocator.allocate(100, 60), None);
+        assert!(allocator.allocate(100, 40).is_some());
+        assert_eq!(allocator.allocate(1, 1), None);
+    }
+
+    #[test]
+    fn allocator_reuses_deallocated_area() {
+        let mut allocator = GlyphAtlasAllocator::new(100);
+        let first = allocator.allocate(50, 50).unwrap();
+        allocator.allocate(50, 50).unwrap();
+        allocator.allocate(100, 50).unwrap();
+
+        assert_eq!(allocator.allocate(10, 10), None);
+
+        allocator.deallocate(first);
+        assert_eq!(allocator.used_texels(), 50 * 50 + 100 * 50);
+
+        assert_eq!(
+            allocator.allocate(30, 40),
+            Some(SpriteTexelMapping::new(0, 30, 0, 40))
+        );
+        // The remaining area of the deallocated glyph is still available.
+        assert_eq!(
+            allocator.allocate(20, 50),
+            Some(SpriteTexelMapping::new(30, 50, 0, 50))
+        );
+        assert_eq!(
+            allocator.allocate(30, 10),
+            Some(SpriteTexelMapping::new(0, 30, 40, 50))
+        );
+        assert_eq!(allocator.allocate(1, 1), None);
+    }
 }
@@ -1,13 +1,19 @@
+mod bitmap_gen;
+mod glyph_key;
 mod glyph_layout;
 mod glyph_layout_config;
 mod glyph_manager;
 mod glyph_sprite;
 mod glyph_texture;
 mod sdf_gen;
+mod text_render_mode;
 
+pub use bitmap_gen::*;
+pub use glyph_key::*;
 pub use glyph_layout::*;
 pub use glyph_layout_config::*;
 pub use glyph_manager::*;
 pub use glyph_sprite::*;
 pub use glyph_texture::*;
 pub use sdf_gen::*;
+pub use text_render_mode::*;
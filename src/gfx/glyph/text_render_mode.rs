@@ -0,0 +1,30 @@
+use super::GlyphRasterMode;
+
+/// Specifies how texts are rasterized and rendered.
+/// The material of the renderer must be built from a shader that matches the mode,
+/// e.g. [`BUILT_IN_SHADER_UI_TEXT_SDF`](crate::gfx::BUILT_IN_SHADER_UI_TEXT_SDF) or
+/// [`BUILT_IN_SHADER_UI_TEXT_BITMAP`](crate::gfx::BUILT_IN_SHADER_UI_TEXT_BITMAP).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextRenderMode {
+    /// Renders coverage bitmaps rasterized at the font size. Glyphs are snapped to pixels vertically
+    /// and placed at subpixel positions horizontally. It looks sharp at small sizes, but not when scaled.
+    Bitmap,
+    /// Renders signed distance fields, which can be scaled arbitrarily.
+    /// `px_range` is the width of the anti-aliased edge in screen pixels.
+    Sdf { px_range: f32 },
+}
+
+impl TextRenderMode {
+    pub fn raster_mode(self) -> GlyphRasterMode {
+        match self {
+            TextRenderMode::Bitmap => GlyphRasterMode::Bitmap { subpixel: 0 },
+            TextRenderMode::Sdf { .. } => GlyphRasterMode::Sdf,
+        }
+    }
+}
+
+impl Default for TextRenderMode {
+    fn default() -> Self {
+        Self::Sdf { px_range: 1.0 }
+    }
+}
@@ -0,0 +1,264 @@
+use std::{
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use wgpu::{
+    Buffer, BufferAddress, BufferDescriptor, BufferUsages, CommandEncoder, Device, Features,
+    MapMode, QuerySet, QuerySetDescriptor, QueryType, Queue, QUERY_SIZE,
+};
+
+/// The maximum number of render passes that can be measured in a frame.
+const MAX_PASS_COUNT: u32 = 64;
+
+const READBACK_IDLE: u8 = 0;
+const READBACK_MAPPING: u8 = 1;
+const READBACK_MAPPED: u8 = 2;
+
+/// Measures GPU time of render passes by writing timestamps before and after each pass.
+/// Results are read back asynchronously, so a frame is not measured while the previous results
+/// are being read back.
+pub struct GpuProfiler {
+    query_set: QuerySet,
+    resolve_buffer: Buffer,
+    readback_buffer: Buffer,
+    readback_state: Arc<AtomicU8>,
+    readback_pass_count: u32,
+    timestamp_period: f32,
+    is_recording: bool,
+    pass_count: u32,
+}
+
+impl GpuProfiler {
+    /// Creates a profiler, or returns `None` if the device does not support timestamp queries.
+    pub fn new(device: &Device, queue: &Queue) -> Option<Self> {
+        if !device.features().contains(Features::TIMESTAMP_QUERY) {
+            return None;
+        }
+
+        let query_count = MAX_PASS_COUNT * 2;
+        let buffer_size = (query_count * QUERY_SIZE) as BufferAddress;
+        let query_set = device.create_query_set(&QuerySetDescriptor {
+            label: Some("gpu profiler query set"),
+            ty: QueryType::Timestamp,
+            count: query_count,
+        });
+        let resolve_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("gpu profiler resolve buffer"),
+            size: buffer_size,
+            usage: BufferUsages::QUERY_RESOLVE | BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("gpu profiler readback buffer"),
+            size: buffer_size,
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        Some(Self {
+            query_set,
+            resolve_buffer,
+            readback_buffer,
+            readback_state: Arc::new(AtomicU8::new(READBACK_IDLE)),
+            readback_pass_count: 0,
+            timestamp_period: queue.get_timestamp_period(),
+            is_recording: false,
+            pass_count: 0,
+        })
+    }
+
+    /// Starts measuring a new frame. Returns the pass times of a previous frame if they are ready.
+    pub fn begin_frame(&mut self) -> Option<Vec<Duration>> {
+        let pass_times = self.collect();
+
+        self.is_recording = self.readback_state.load(Ordering::Acquire) == READBACK_IDLE;
+        self.pass_count = 0;
+
+        pass_times
+    }
+
+    /// Writes the timestamp of the beginning of a pass. Returns the index of the pass if it is measured.
+    pub fn begin_pass(&mut self, encoder: &mut CommandEncoder) -> Option<u32> {
+        if !self.is_recording || MAX_PASS_COUNT <= self.pass_count {
+            return None;
+        }
+
+        let index = self.pass_count;
+        self.pass_count += 1;
+        encoder.write_timestamp(&self.query_set, index * 2);
+        Some(index)
+    }
+
+    /// Writes the timestamp of the end of the pass.
+    pub fn end_pass(&mut self, encoder: &mut CommandEncoder, index: Option<u32>) {
+        if let Some(index) = index {
+            encoder.write_timestamp(&self.query_set, index * 2 + 1);
+        }
+    }
+
+    /// Resolves the timestamps of the frame into the readback buffer.
+    pub fn resolve(&mut self, encoder: &mut CommandEncoder) {
+        if !self.is_recording || self.pass_count == 0 {
+            return;
+        }
+
+        let query_count = self.pass_count * 2;
+        encoder.resolve_query_set(&self.query_set, 0..query_count, &self.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(
+            &self.resolve_buffer,
+            0,
+            &self.readback_buffer,
+            0,
+            (query_count * QUERY_SIZE) as BufferAddress,
+        );
+    }
+
+    /// Requests to read back the resolved timestamps. It must be called after submitting the frame.
+    pub fn finish_frame(&mut self) {
+        if !self.is_recording || self.pass_count == 0 {
+            return;
+        }
+
+        let readback_state = self.readback_state.clone();
+        readback_state.store(READBACK_MAPPING, Ordering::Release);
+        self.readback_pass_count = self.pass_count;
+        self.readback_buffer
+            .slice(..(self.pass_count * 2 * QUERY_SIZE) as BufferAddress)
+            .map_async(MapMode::Read, move |result| {
+                readback_state.store(
+                    if result.is_ok() {
+                        READBACK_MAPPED
+                    } else {
+                        READBACK_IDLE
+                    },
+                    Ordering::Release,
+                );
+            });
+        self.is_recording = false;
+    }
+
+    fn collect(&mut self) -> Option<Vec<Duration>> {
+        if self.readback_state.load(Ordering::Acquire) != READBACK_MAPPED {
+            return None;
+        }
+
+        let size = (self.readback_pass_count * 2 * QUERY_SIZE) as BufferAddress;
+        let pass_times = {
+            let data = self.readback_buffer.slice(..size).get_mapped_range();
+            let timestamps = data
+                .chunks_exact(QUERY_SIZE as usize)
+                .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+                .collect::<Vec<_>>();
+            timestamps_to_durations(&timestamps, self.timestamp_period)
+        };
+
+        self.readback_buffer.unmap();
+        self.readback_state.store(READBACK_IDLE, Ordering::Release);
+
+        Some(pass_times)
+    }
+}
+
+/// Converts pairs of begin and end timestamps into durations.
+/// The period is the number of nanoseconds per timestamp tick.
+pub fn timestamps_to_durations(timestamps: &[u64], period: f32) -> Vec<Duration> {
+    timestamps
+        .chunks_exact(2)
+        .map(|pair| {
+            let ticks = pair[1].saturating_sub(pair[0]);
+            Duration::from_nanos((ticks as f64 * period as f64) as u64)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gfx::{GfxContext, GfxContextOptions, GfxFeatures};
+    use pollster::FutureExt;
+    use winit::dpi::PhysicalSize;
+
+    #[test]
+    fn timestamps_to_durations_pairs() {
+        let durations = timestamps_to_durations(&[100, 300, 1000, 1500], 2.0);
+
+        assert_eq!(
+            durations,
+            vec![Duration::from_nanos(400), Duration::from_nanos(1000)]
+        );
+    }
+
+    #[test]
+    fn timestamps_to_durations_never_negative() {
+        // Some drivers may report timestamps out of order.
+        let durations = timestamps_to_durations(&[500, 400], 1.0);
+
+        assert_eq!(durations, vec![Duration::ZERO]);
+    }
+
+    #[test]
+    fn pass_times_are_read_back_after_the_frame() {
+        let options = GfxContextOptions {
+            allow_fallback_adapter: true,
+            features: GfxFeatures {
+                features: Features::TIMESTAMP_QUERY,
+                limits: None,
+            },
+        };
+        let gfx_ctx = match GfxContext::new_headless(PhysicalSize::new(4, 4), &options).block_on() {
+            Ok(gfx_ctx) => gfx_ctx,
+            Err(err) => {
+                eprintln!("skipped: no adapter with timestamp queries found: {}", err);
+                return;
+            }
+        };
+        let mut profiler = GpuProfiler::new(&gfx_ctx.device, &gfx_ctx.queue).unwrap();
+        let texture = gfx_ctx.device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size: wgpu::Extent3d {
+                width: 4,
+                height: 4,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&Default::default());
+
+        assert_eq!(profiler.begin_frame(), None);
+
+        let mut encoder = gfx_ctx.device.create_command_encoder(&Default::default());
+        let index = profiler.begin_pass(&mut encoder);
+        assert_eq!(index, Some(0));
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: None,
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::RED),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+        profiler.end_pass(&mut encoder, index);
+        profiler.resolve(&mut encoder);
+        gfx_ctx.queue.submit(std::iter::once(encoder.finish()));
+        profiler.finish_frame();
+
+        gfx_ctx.device.poll(wgpu::Maintain::Wait);
+
+        let pass_times = profiler.begin_frame().unwrap();
+        assert_eq!(pass_times.len(), 1);
+        // The readback buffer is unmapped again, so the next frame is measured.
+        assert!(profiler.is_recording);
+    }
+}
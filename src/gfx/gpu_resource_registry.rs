@@ -0,0 +1,274 @@
+use std::{collections::BTreeMap, fmt::Write};
+
+/// The number of frames in a row that the count of a kind must grow before it is reported as a possible leak.
+pub const DEFAULT_GROWTH_FRAMES: u32 = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum GpuResourceKind {
+    Buffer,
+    Texture,
+    BindGroup,
+    Pipeline,
+}
+
+impl GpuResourceKind {
+    pub const ALL: [Self; 4] = [Self::Buffer, Self::Texture, Self::BindGroup, Self::Pipeline];
+}
+
+#[derive(Debug, Clone)]
+pub struct GpuResourceInfo {
+    pub kind: GpuResourceKind,
+    pub label: Option<String>,
+    /// The size in bytes, or zero if unknown.
+    pub size: u64,
+    /// Where the resource was created, if backtraces are captured.
+    pub backtrace: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct GrowthHistory {
+    last_count: usize,
+    streak: u32,
+}
+
+/// Keeps the GPU resources that are alive, to find leaks. Resources are registered by the engine's wrappers
+/// through [`GpuResourceTracker`]s while the `gpu-resource-tracking` feature is enabled.
+pub struct GpuResourceRegistry {
+    next_id: u64,
+    live: BTreeMap<u64, GpuResourceInfo>,
+    capture_backtraces: bool,
+    growth_frames: u32,
+    histories: [GrowthHistory; GpuResourceKind::ALL.len()],
+}
+
+impl GpuResourceRegistry {
+    pub const fn new() -> Self {
+        Self {
+            next_id: 0,
+            live: BTreeMap::new(),
+            capture_backtraces: false,
+            growth_frames: DEFAULT_GROWTH_FRAMES,
+            histories: [GrowthHistory {
+                last_count: 0,
+                streak: 0,
+            }; GpuResourceKind::ALL.len()],
+        }
+    }
+
+    /// Captures the backtrace of every resource created from now on. It is slow, so enable it only to find
+    /// where a leak comes from.
+    pub fn set_capture_backtraces(&mut self, capture_backtraces: bool) {
+        self.capture_backtraces = capture_backtraces;
+    }
+
+    /// Sets how many frames in a row the count of a kind must grow before [`end_frame`](Self::end_frame)
+    /// reports it.
+    pub fn set_growth_frames(&mut self, growth_frames: u32) {
+        self.growth_frames = growth_frames.max(1);
+    }
+
+    pub fn track(&mut self, kind: GpuResourceKind, label: Option<&str>, size: u64) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.live.insert(
+            id,
+            GpuResourceInfo {
+                kind,
+                label: label.map(str::to_owned),
+                size,
+                backtrace: self
+                    .capture_backtraces
+                    .then(|| std::backtrace::Backtrace::force_capture().to_string()),
+            },
+        );
+        id
+    }
+
+    pub fn untrack(&mut self, id: u64) {
+        self.live.remove(&id);
+    }
+
+    pub fn live_resources(&self) -> impl Iterator<Item = &GpuResourceInfo> {
+        self.live.values()
+    }
+
+    pub fn live_count(&self, kind: GpuResourceKind) -> usize {
+        self.live_resources()
+            .filter(|info| info.kind == kind)
+            .count()
+    }
+
+    pub fn live_size(&self, kind: GpuResourceKind) -> u64 {
+        self.live_resources()
+            .filter(|info| info.kind == kind)
+            .map(|info| info.size)
+            .sum()
+    }
+
+    /// Records the counts of the frame, and returns the kinds whose counts have grown on every one of the last
+    /// frames. A kind that keeps growing is reported again after as many frames.
+    pub fn end_frame(&mut self) -> Vec<GpuResourceKind> {
+        let mut grown = Vec::new();
+
+        for (index, kind) in GpuResourceKind::ALL.into_iter().enumerate() {
+            let count = self.live_count(kind);
+            let history = &mut self.histories[index];
+
+            if history.last_count < count {
+                history.streak += 1;
+            } else {
+                history.streak = 0;
+            }
+
+            history.last_count = count;
+
+            if self.growth_frames <= history.streak {
+                history.streak = 0;
+                grown.push(kind);
+            }
+        }
+
+        grown
+    }
+
+    /// Returns a report of the live resources: the count and the size of each kind, followed by every resource.
+    pub fn dump_live_resources(&self) -> String {
+        let mut report = String::new();
+
+        for kind in GpuResourceKind::ALL {
+            let _ = writeln!(
+                report,
+                "{:?}: {} live, {} KiB",
+                kind,
+                self.live_count(kind),
+                self.live_size(kind) / 1024
+            );
+        }
+
+        for (id, info) in &self.live {
+            let _ = writeln!(
+                report,
+                "#{} {:?} `{}` {} bytes",
+                id,
+                info.kind,
+                info.label.as_deref().unwrap_or("unlabeled"),
+                info.size
+            );
+
+            if let Some(backtrace) = &info.backtrace {
+                let _ = writeln!(report, "{}", backtrace);
+            }
+        }
+
+        report
+    }
+}
+
+impl Default for GpuResourceRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "gpu-resource-tracking")]
+static GPU_RESOURCE_REGISTRY: parking_lot::Mutex<GpuResourceRegistry> =
+    parking_lot::const_mutex(GpuResourceRegistry::new());
+
+/// Returns the registry that the [`GpuResourceTracker`]s report to.
+#[cfg(feature = "gpu-resource-tracking")]
+pub fn gpu_resource_registry() -> parking_lot::MutexGuard<'static, GpuResourceRegistry> {
+    GPU_RESOURCE_REGISTRY.lock()
+}
+
+/// Registers a GPU resource while it is alive. Wrappers keep one next to the resource they own; clones share the
+/// registration. Without the `gpu-resource-tracking` feature, it is empty and does nothing.
+#[derive(Debug, Clone, Default)]
+pub struct GpuResourceTracker {
+    #[cfg(feature = "gpu-resource-tracking")]
+    _registration: Option<std::sync::Arc<Registration>>,
+}
+
+impl GpuResourceTracker {
+    pub fn new(kind: GpuResourceKind, label: Option<&str>, size: u64) -> Self {
+        #[cfg(feature = "gpu-resource-tracking")]
+        {
+            let id = gpu_resource_registry().track(kind, label, size);
+            Self {
+                _registration: Some(std::sync::Arc::new(Registration(id))),
+            }
+        }
+
+        #[cfg(not(feature = "gpu-resource-tracking"))]
+        {
+            let _ = (kind, label, size);
+            Self {}
+        }
+    }
+
+    /// Registers the texture with the size of its first mip level.
+    pub fn texture(texture: &wgpu::Texture, label: Option<&str>) -> Self {
+        let extent = texture.size();
+        let format = texture.format();
+        let (block_width, block_height) = format.block_dimensions();
+        let size = (extent.width.div_ceil(block_width) * extent.height.div_ceil(block_height))
+            as u64
+            * extent.depth_or_array_layers as u64
+            * format.block_size(None).unwrap_or(0) as u64;
+        Self::new(GpuResourceKind::Texture, label, size)
+    }
+
+    pub fn buffer(buffer: &wgpu::Buffer, label: Option<&str>) -> Self {
+        Self::new(GpuResourceKind::Buffer, label, buffer.size())
+    }
+}
+
+#[cfg(feature = "gpu-resource-tracking")]
+#[derive(Debug)]
+struct Registration(u64);
+
+#[cfg(feature = "gpu-resource-tracking")]
+impl Drop for Registration {
+    fn drop(&mut self) {
+        gpu_resource_registry().untrack(self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_live_resources_and_growth() {
+        let mut registry = GpuResourceRegistry::new();
+        registry.set_growth_frames(3);
+
+        let texture = registry.track(GpuResourceKind::Texture, Some("albedo"), 4096);
+        let buffer = registry.track(GpuResourceKind::Buffer, None, 256);
+        registry.track(GpuResourceKind::Buffer, Some("uniforms"), 64);
+        assert_eq!(registry.live_count(GpuResourceKind::Buffer), 2);
+        assert_eq!(registry.live_size(GpuResourceKind::Buffer), 320);
+
+        registry.untrack(buffer);
+        registry.untrack(texture);
+        assert_eq!(registry.live_count(GpuResourceKind::Buffer), 1);
+        assert_eq!(registry.live_count(GpuResourceKind::Texture), 0);
+
+        let report = registry.dump_live_resources();
+        assert!(report.contains("Buffer: 1 live, 0 KiB"));
+        assert!(report.contains("Buffer `uniforms` 64 bytes"));
+        assert!(!report.contains("albedo"));
+
+        // Bind groups grow on three frames in a row.
+        for _ in 0..2 {
+            registry.track(GpuResourceKind::BindGroup, None, 0);
+            assert!(registry.end_frame().is_empty());
+        }
+        registry.track(GpuResourceKind::BindGroup, None, 0);
+        assert_eq!(registry.end_frame(), [GpuResourceKind::BindGroup]);
+
+        // A frame without growth resets the streak.
+        assert!(registry.end_frame().is_empty());
+        registry.track(GpuResourceKind::BindGroup, None, 0);
+        assert!(registry.end_frame().is_empty());
+    }
+}
@@ -0,0 +1,349 @@
+use super::{
+    BindGroupEntryResource, InstanceProperty, Material, PerInstancePropertyValue,
+    UniformPropertyValue,
+};
+use crate::gfx::{GpuResourceKind, GpuResourceTracker};
+use std::{collections::HashMap, sync::Arc};
+use wgpu::{
+    util::{BufferInitDescriptor, DeviceExt},
+    BindGroup, BindGroupDescriptor, BindGroupEntry, Buffer, BufferAddress, BufferUsages, Device,
+    Queue,
+};
+
+/// Per-renderer overrides of material properties. It lets a renderer tint itself without cloning
+/// or changing the shared material.
+///
+/// Per-instance overrides are written into the instance data of the renderer. Uniform overrides are
+/// uploaded into buffers owned by the block, which replace the buffers of the material in the bind groups
+/// of this renderer only. Names that the material does not have are ignored.
+#[derive(Debug, Default, Clone)]
+pub struct MaterialPropertyBlock {
+    instance_properties: HashMap<String, PerInstancePropertyValue>,
+    uniform_properties: HashMap<String, UniformPropertyValue>,
+    uniform_buffers: HashMap<usize, Arc<Buffer>>,
+    bind_groups: Vec<OverriddenBindGroup>,
+    is_dirty: bool,
+}
+
+#[derive(Debug, Clone)]
+struct OverriddenBindGroup {
+    group: u32,
+    /// The resources of the material that the bind group was built from.
+    sources: Vec<Option<BindGroupEntryResource>>,
+    bind_group: Arc<BindGroup>,
+    _tracker: GpuResourceTracker,
+}
+
+impl MaterialPropertyBlock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instance_properties.is_empty() && self.uniform_properties.is_empty()
+    }
+
+    /// Overrides the property with the name, whether the material has it as a per-instance property or
+    /// as a uniform property.
+    pub fn set<T>(&mut self, name: impl Into<String>, value: T)
+    where
+        T: Clone + Into<PerInstancePropertyValue> + Into<UniformPropertyValue>,
+    {
+        let name = name.into();
+        self.set_per_instance(name.clone(), value.clone());
+        self.set_uniform(name, value);
+    }
+
+    pub fn set_per_instance(
+        &mut self,
+        name: impl Into<String>,
+        value: impl Into<PerInstancePropertyValue>,
+    ) {
+        self.instance_properties.insert(name.into(), value.into());
+    }
+
+    pub fn set_uniform(&mut self, name: impl Into<String>, value: impl Into<UniformPropertyValue>) {
+        self.uniform_properties.insert(name.into(), value.into());
+        self.is_dirty = true;
+    }
+
+    /// Returns the per-instance override of the property with the name, if any.
+    pub fn per_instance(&self, name: &str) -> Option<&PerInstancePropertyValue> {
+        self.instance_properties.get(name)
+    }
+
+    /// Removes the override of the property with the name, so that the value of the material is used.
+    pub fn remove(&mut self, name: &str) {
+        self.instance_properties.remove(name);
+
+        if self.uniform_properties.remove(name).is_some() {
+            self.is_dirty = true;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.instance_properties.clear();
+        self.uniform_properties.clear();
+        self.is_dirty = true;
+    }
+
+    /// Returns the per-instance overrides that match the properties of the material, with their offsets
+    /// in the instance data. Overrides of a different format than the property are skipped.
+    pub fn instance_overrides<'a>(
+        &'a self,
+        properties: &'a HashMap<String, InstanceProperty>,
+    ) -> impl Iterator<Item = (BufferAddress, &'a PerInstancePropertyValue)> {
+        self.instance_properties
+            .iter()
+            .filter_map(|(name, value)| match properties.get(name) {
+                Some(property) if property.format == value.to_vertex_format() => {
+                    Some((property.offset, value))
+                }
+                _ => None,
+            })
+    }
+
+    /// Returns the bind groups that replace the bind groups of the material, with their group indices.
+    pub fn bind_groups(&self) -> impl Iterator<Item = (u32, &Arc<BindGroup>)> {
+        self.bind_groups
+            .iter()
+            .map(|bind_group| (bind_group.group, &bind_group.bind_group))
+    }
+
+    /// Uploads the uniform overrides on top of the current uniform values of the material and rebuilds
+    /// the bind groups if needed. It must be called after the material has been flushed.
+    pub fn prepare(&mut self, material: &Material, device: &Device, queue: &Queue) {
+        if self.uniform_properties.is_empty() {
+            self.uniform_buffers.clear();
+            self.bind_groups.clear();
+            self.is_dirty = false;
+            return;
+        }
+
+        // The uniform values of the material may have been changed, so the blocks are uploaded every time.
+        let mut blocks = HashMap::new();
+
+        for (name, value) in &self.uniform_properties {
+            let property = match material.uniform_properties.get(name) {
+                Some(property) if property.ty == value.ty() => property,
+                _ => continue,
+            };
+            blocks
+                .entry(property.block_index)
+                .or_insert_with(|| material.uniform_blocks[property.block_index].block.clone())
+                .write(property.offset, value);
+        }
+
+        if blocks.len() != self.uniform_buffers.len()
+            || !blocks
+                .keys()
+                .all(|index| self.uniform_buffers.contains_key(index))
+        {
+            self.uniform_buffers.clear();
+            self.is_dirty = true;
+        }
+
+        for (&index, block) in &blocks {
+            match self.uniform_buffers.get(&index) {
+                Some(buffer) => queue.write_buffer(buffer, 0, block.data()),
+                None => {
+                    let buffer = device.create_buffer_init(&BufferInitDescriptor {
+                        label: None,
+                        contents: block.data(),
+                        usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+                    });
+                    self.uniform_buffers.insert(index, Arc::new(buffer));
+                }
+            }
+        }
+
+        let replacements = HashMap::<_, _>::from_iter(self.uniform_buffers.iter().filter_map(
+            |(&index, buffer)| {
+                let holder = &material.uniform_blocks[index];
+                material
+                    .bind_properties
+                    .get(&holder.key)
+                    .map(|bind_index| (*bind_index, buffer.clone()))
+            },
+        ));
+        let mut group_indices = Vec::from_iter(replacements.keys().map(|index| index.group_index));
+        group_indices.sort_unstable();
+        group_indices.dedup();
+
+        // Rebuilds the bind groups only if the overrides or the bind groups of the material have changed.
+        let is_up_to_date = !self.is_dirty
+            && self.bind_groups.len() == group_indices.len()
+            && group_indices
+                .iter()
+                .zip(&self.bind_groups)
+                .all(|(&index, bind_group)| {
+                    let holder = &material.bind_group_holders[index];
+                    holder.group == bind_group.group
+                        && holder.entries.len() == bind_group.sources.len()
+                        && holder
+                            .entries
+                            .iter()
+                            .zip(&bind_group.sources)
+                            .all(|(entry, source)| is_same_resource(&entry.resource, source))
+                });
+
+        if is_up_to_date {
+            return;
+        }
+
+        self.bind_groups.clear();
+        self.is_dirty = false;
+
+        for group_index in group_indices {
+            let holder = &material.bind_group_holders[group_index];
+            let layout = match (
+                holder.bind_group.as_ref(),
+                material.shader.bind_group_layouts.get(&holder.group),
+            ) {
+                (Some(_), Some(layout)) => layout,
+                // The material is not ready yet; it is retried on the next call.
+                _ => {
+                    self.is_dirty = true;
+                    continue;
+                }
+            };
+            let resources = Vec::from_iter(holder.entries.iter().enumerate().map(
+                |(entry_index, entry)| {
+                    let replacement = replacements.iter().find(|(index, _)| {
+                        index.group_index == group_index && index.entry_index == entry_index
+                    });
+
+                    match replacement {
+                        Some((_, buffer)) => BindGroupEntryResource::Buffer {
+                            buffer: buffer.clone(),
+                            offset: 0,
+                            size: None,
+                        },
+                        None => entry.resource.clone().unwrap(),
+                    }
+                },
+            ));
+            let builders = Vec::from_iter(
+                resources
+                    .iter()
+                    .map(|resource| resource.as_binding_resource_builder()),
+            );
+            let entries = Vec::from_iter(holder.entries.iter().zip(&builders).map(
+                |(entry, builder)| BindGroupEntry {
+                    binding: entry.binding,
+                    resource: builder.as_binding_resource(),
+                },
+            ));
+
+            self.bind_groups.push(OverriddenBindGroup {
+                group: holder.group,
+                sources: Vec::from_iter(holder.entries.iter().map(|entry| entry.resource.clone())),
+                bind_group: Arc::new(device.create_bind_group(&BindGroupDescriptor {
+                    label: None,
+                    layout: layout.as_ref(),
+                    entries: &entries,
+                })),
+                _tracker: GpuResourceTracker::new(GpuResourceKind::BindGroup, None, 0),
+            });
+        }
+    }
+}
+
+/// Compares the resources by identity, since the resources of wgpu cannot be compared by value.
+fn is_same_resource(
+    lhs: &Option<BindGroupEntryResource>,
+    rhs: &Option<BindGroupEntryResource>,
+) -> bool {
+    match (lhs, rhs) {
+        (None, None) => true,
+        (
+            Some(BindGroupEntryResource::Buffer {
+                buffer: lhs_buffer,
+                offset: lhs_offset,
+                size: lhs_size,
+            }),
+            Some(BindGroupEntryResource::Buffer {
+                buffer: rhs_buffer,
+                offset: rhs_offset,
+                size: rhs_size,
+            }),
+        ) => {
+            Arc::ptr_eq(lhs_buffer, rhs_buffer) && lhs_offset == rhs_offset && lhs_size == rhs_size
+        }
+        (
+            Some(BindGroupEntryResource::Sampler { sampler: lhs }),
+            Some(BindGroupEntryResource::Sampler { sampler: rhs }),
+        ) => Arc::ptr_eq(lhs, rhs),
+        (
+            Some(BindGroupEntryResource::TextureView { texture_view: lhs }),
+            Some(BindGroupEntryResource::TextureView { texture_view: rhs }),
+        ) => Arc::ptr_eq(lhs, rhs),
+        (
+            Some(BindGroupEntryResource::TextureViewArray { texture_views: lhs }),
+            Some(BindGroupEntryResource::TextureViewArray { texture_views: rhs }),
+        ) => lhs.len() == rhs.len() && lhs.iter().zip(rhs).all(|(lhs, rhs)| Arc::ptr_eq(lhs, rhs)),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wgpu::VertexFormat;
+    use zerocopy::AsBytes;
+
+    fn encode(
+        properties: &HashMap<String, InstanceProperty>,
+        block: &MaterialPropertyBlock,
+    ) -> Vec<u8> {
+        let mut data = vec![0u8; 32];
+
+        for property in properties.values() {
+            if let Some(value) = &property.value {
+                let offset = property.offset as usize;
+                data[offset..offset + value.as_bytes().len()].copy_from_slice(value.as_bytes());
+            }
+        }
+
+        for (offset, value) in block.instance_overrides(properties) {
+            let offset = offset as usize;
+            data[offset..offset + value.as_bytes().len()].copy_from_slice(value.as_bytes());
+        }
+
+        data
+    }
+
+    #[test]
+    fn instance_overrides_do_not_touch_material() {
+        let properties = HashMap::from_iter([(
+            "tint".to_owned(),
+            InstanceProperty {
+                format: VertexFormat::Float32x4,
+                offset: 16,
+                value: Some(PerInstancePropertyValue::Float32x4([1.0, 1.0, 1.0, 1.0])),
+            },
+        )]);
+
+        let mut red = MaterialPropertyBlock::new();
+        red.set("tint", [1.0f32, 0.0, 0.0, 1.0]);
+        // Unknown names and mismatching formats are ignored.
+        red.set("unknown", 1.0f32);
+        let mut blue = MaterialPropertyBlock::new();
+        blue.set_per_instance("tint", [0.0f32, 0.0, 1.0, 1.0]);
+        let mut mismatch = MaterialPropertyBlock::new();
+        mismatch.set("tint", 0.5f32);
+
+        let material = encode(&properties, &MaterialPropertyBlock::new());
+        let red = encode(&properties, &red);
+        let blue = encode(&properties, &blue);
+
+        assert_eq!(&red[16..], [1.0f32, 0.0, 0.0, 1.0].as_bytes());
+        assert_eq!(&blue[16..], [0.0f32, 0.0, 1.0, 1.0].as_bytes());
+        assert_ne!(red, blue);
+        assert_eq!(encode(&properties, &mismatch), material);
+        assert_eq!(
+            properties["tint"].value,
+            Some(PerInstancePropertyValue::Float32x4([1.0, 1.0, 1.0, 1.0]))
+        );
+    }
+}
@@ -1,32 +1,146 @@
+use crate::{
+    gfx::{GpuResourceKind, GpuResourceTracker},
+    math::{Vec2, Vec3, Vec4},
+};
+use asset::assets::{
+    MaterialBindingKey, MaterialBindingValue, MaterialInstancePropKey, MaterialInstancePropValue,
+    MaterialPreset, MaterialTopology,
+};
 use codegen::HandleMut;
-use std::{collections::HashMap, num::NonZeroU32, sync::Arc};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Display,
+    num::NonZeroU32,
+    sync::Arc,
+};
+use thiserror::Error;
 use wgpu::{
+    util::{BufferInitDescriptor, DeviceExt},
     BindGroup, BindGroupDescriptor, BindGroupEntry, BindingResource, BindingType, Buffer,
-    BufferAddress, BufferBinding, BufferSize, Device, Sampler, TextureView, VertexFormat,
-    VertexStepMode,
+    BufferAddress, BufferBinding, BufferSize, BufferUsages, Device, PrimitiveTopology, Queue,
+    Sampler, TextureView, VertexFormat, VertexStepMode,
 };
 use zerocopy::AsBytes;
 
 mod bind_group_layout_cache;
+mod material_property_block;
 mod pipeline_cache;
 mod pipeline_layout_cache;
 mod shader;
 mod shader_reflection;
+mod uniform_block;
 
+// The material states are shared with material assets.
+pub use asset::assets::{MaterialBlendMode, MaterialCullMode, MaterialFrontFace};
 pub use bind_group_layout_cache::*;
+pub use material_property_block::*;
 pub use pipeline_cache::*;
 pub use pipeline_layout_cache::*;
 pub use shader::*;
 pub use shader_reflection::*;
+pub use uniform_block::*;
+
+/// How a material tests and writes the depth.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MaterialDepthMode {
+    /// Tests against and writes the depth, as opaque surfaces do.
+    ReadWrite,
+    /// Tests against the depth without writing it, e.g. for transparent surfaces.
+    ReadOnly,
+    /// Neither tests nor writes the depth, so that it is drawn over the scene, e.g. for gizmos.
+    /// Mesh renderers of such materials are drawn after the others.
+    Always,
+}
+
+/// Where a bind group of a draw comes from. See [`Material::bind_group_sources`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindGroupSource {
+    CameraTransform,
+    ScreenSize,
+    /// The bind group that the renderer provides for the semantic binding.
+    Renderer(SemanticShaderBindingKey),
+    /// The bind group of the material at the index of [`Material::bind_group_holders`], unless a property block
+    /// overrides it.
+    Material(usize),
+}
+
+/// Why a property of a [`Material`] could not be set.
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum MaterialPropertyError {
+    #[error("unknown per-instance property `{name}`{}", did_you_mean(.suggestions))]
+    UnknownProperty {
+        name: String,
+        /// The known names that are close to the name, closest first.
+        suggestions: Vec<String>,
+    },
+    #[error("unknown binding `{key}`{}", did_you_mean(.suggestions))]
+    UnknownBinding {
+        key: BindingPropKey,
+        /// The known names that are close to the name of the key, closest first.
+        suggestions: Vec<String>,
+    },
+    #[error("per-instance property `{name}` expects {expected:?}, but {provided:?} is provided")]
+    FormatMismatch {
+        name: String,
+        expected: VertexFormat,
+        provided: VertexFormat,
+    },
+    #[error("binding `{key}` expects {expected:?} (count: {count:?}), but {provided} is provided")]
+    BindingTypeMismatch {
+        key: BindingPropKey,
+        expected: BindingType,
+        count: Option<NonZeroU32>,
+        /// The kind of the provided resource, e.g. `a sampler`.
+        provided: &'static str,
+    },
+}
+
+/// Why a [`Material`] could not be created from a [`MaterialPreset`].
+#[derive(Error, Debug)]
+pub enum MaterialPresetError {
+    #[error("failed to create the shader of the preset: {0}")]
+    Shader(#[from] ShaderInspectionError),
+    #[error("failed to set a property of the preset: {0}")]
+    Property(#[from] MaterialPropertyError),
+}
+
+fn did_you_mean(suggestions: &[String]) -> String {
+    if suggestions.is_empty() {
+        return String::new();
+    }
+
+    format!("; did you mean `{}`?", suggestions.join("`, `"))
+}
 
 #[derive(HandleMut)]
 pub struct Material {
     pub shader: ShaderHandle,
     pub pipeline_layout: CachedPipelineLayout,
     pub semantic_inputs: HashMap<SemanticShaderInputKey, SemanticInputData>,
+    /// The per-instance entries of `semantic_inputs`, sorted by offset. It is derived from the shader,
+    /// so encoding the instances of a frame does not walk the map.
+    instance_semantic_inputs: Vec<(SemanticShaderInputKey, SemanticInputData)>,
+    /// Where the bind groups of a draw come from. It is derived from the shader, so recording a draw does not
+    /// walk the reflected bindings.
+    bind_group_sources: Vec<(u32, BindGroupSource)>,
     pub bind_properties: HashMap<BindingPropKey, BindGroupIndex>,
     pub bind_group_holders: Vec<BindGroupHolder>,
     pub instance_properties: HashMap<String, InstanceProperty>,
+    pub uniform_blocks: Vec<UniformBlockHolder>,
+    pub uniform_properties: HashMap<String, UniformProperty>,
+    /// The primitives that the vertices form, e.g. line lists for debug drawing. It overrides the topology of
+    /// the renderer, and defaults to triangle lists.
+    pub topology: PrimitiveTopology,
+    /// How the output is blended into the render target. It overrides the blending of the semantic outputs,
+    /// which is alpha blending, if set.
+    pub blend_mode: Option<MaterialBlendMode>,
+    /// The faces of triangles that are culled. It overrides the cull mode of the renderer if set.
+    pub cull_mode: Option<MaterialCullMode>,
+    /// The winding of the front faces of triangles, e.g. clockwise for PMX models. It overrides the winding
+    /// of the renderer if set.
+    pub front_face: Option<MaterialFrontFace>,
+    /// How the depth is tested and written. It overrides the depth state of the renderer if set.
+    pub depth_mode: Option<MaterialDepthMode>,
 }
 
 impl Material {
@@ -73,6 +187,14 @@ impl Material {
                         }),
                 ),
         );
+        let mut instance_semantic_inputs = Vec::from_iter(
+            semantic_inputs
+                .iter()
+                .filter(|(_, input_data)| input_data.step_mode == VertexStepMode::Instance)
+                .map(|(&key, input_data)| (key, input_data.clone())),
+        );
+        instance_semantic_inputs.sort_unstable_by_key(|(_, input_data)| input_data.offset);
+
         let bind_properties = HashMap::from_iter(
             shader
                 .bind_group_layouts
@@ -114,6 +236,7 @@ impl Material {
                     is_dirty: false,
                     group: *group,
                     bind_group: None,
+                    tracker: Default::default(),
                     entries: Vec::from_iter(layout.key().entries.iter().map(|entry| {
                         BindGroupEntryHolder {
                             binding: entry.binding,
@@ -142,6 +265,54 @@ impl Material {
                 }),
         );
 
+        // Non-semantic uniform structs, e.g. `material_params`, are owned by the material.
+        let uniform_bindings = Vec::from_iter(
+            shader
+                .reflected_shader
+                .bindings
+                .iter()
+                .filter(|element| element.semantic_binding.is_none())
+                .filter_map(|element| match &element.kind {
+                    ReflectedShaderBindingElementKind::Buffer { size, members }
+                        if !members.is_empty() =>
+                    {
+                        Some((element, size.get(), members))
+                    }
+                    _ => None,
+                }),
+        );
+        let uniform_blocks =
+            Vec::from_iter(
+                uniform_bindings
+                    .iter()
+                    .map(|(element, size, _)| UniformBlockHolder {
+                        key: BindingPropKey::StringKey(element.name.clone()),
+                        block: UniformBlock::new(*size),
+                        buffer: None,
+                        tracker: Default::default(),
+                    }),
+            );
+        let uniform_properties = HashMap::from_iter(uniform_bindings.iter().enumerate().flat_map(
+            |(block_index, (_, _, members))| {
+                members.iter().map(move |member| {
+                    (
+                        member.name.clone(),
+                        UniformProperty {
+                            block_index,
+                            offset: member.offset,
+                            ty: member.ty,
+                        },
+                    )
+                })
+            },
+        ));
+
+        let bind_group_sources = collect_bind_group_sources(
+            &shader.reflected_shader.bindings,
+            &bind_properties,
+            &bind_group_holders,
+        );
+
         let mut bind_group_layouts = Vec::from_iter(
             shader
                 .bind_group_layouts
@@ -158,55 +329,230 @@ impl Material {
             shader,
             pipeline_layout,
             semantic_inputs,
+            instance_semantic_inputs,
+            bind_group_sources,
             bind_properties,
             bind_group_holders,
             instance_properties: per_instance_properties,
+            uniform_blocks,
+            uniform_properties,
+            topology: PrimitiveTopology::TriangleList,
+            blend_mode: None,
+            cull_mode: None,
+            front_face: None,
+            depth_mode: None,
+        }
+    }
+
+    /// Creates a material of the preset of a material asset. The shader is created for the source and the
+    /// defines of the preset, the bindings of the preset are bound, and its per-instance properties are set as the
+    /// defaults of the instances. The states of the preset override the ones of the renderer.
+    pub fn from_preset(
+        preset: &MaterialPreset,
+        shader_mgr: &ShaderManager,
+        bind_group_layout_cache: &mut BindGroupLayoutCache,
+        pipeline_layout_cache: &mut PipelineLayoutCache,
+    ) -> Result<Self, MaterialPresetError> {
+        let defines = BTreeSet::from_iter(preset.shader.defines().iter().cloned());
+        let shader = shader_mgr.create_shader_variant(
+            bind_group_layout_cache,
+            preset.shader.source(),
+            &defines,
+        )?;
+        let mut material = Self::new(shader, pipeline_layout_cache);
+
+        for prop in &preset.binding_props {
+            let key = match &prop.key {
+                MaterialBindingKey::Semantic(key) => {
+                    BindingPropKey::SemanticKey(SemanticShaderBindingKey::new(key.get().get()))
+                }
+                MaterialBindingKey::Named(name) => BindingPropKey::StringKey(name.clone()),
+            };
+            material.set_bind_property(&key, prop.value.clone())?;
+        }
+
+        for prop in &preset.instance_props {
+            let name = match &prop.key {
+                MaterialInstancePropKey::Semantic(key) => {
+                    let key = SemanticShaderInputKey::new(key.get().get());
+                    match shader_mgr.get_semantic_input(key) {
+                        Some(input) => input.name.to_owned(),
+                        None => {
+                            return Err(MaterialPropertyError::UnknownProperty {
+                                name: format!("semantic input #{}", key.get()),
+                                suggestions: Vec::new(),
+                            }
+                            .into());
+                        }
+                    }
+                }
+                MaterialInstancePropKey::Named(name) => name.clone(),
+            };
+            material.set_per_instance_property(name, prop.value.clone())?;
         }
+
+        material.topology = match preset.topology {
+            MaterialTopology::TriangleList => PrimitiveTopology::TriangleList,
+            MaterialTopology::LineList => PrimitiveTopology::LineList,
+            MaterialTopology::PointList => PrimitiveTopology::PointList,
+        };
+        material.blend_mode = Some(preset.blend_mode);
+        material.cull_mode = Some(preset.cull_mode);
+        material.front_face = Some(preset.front_face);
+
+        Ok(material)
+    }
+
+    /// Returns the semantic inputs that are fed per instance, sorted by offset.
+    pub fn instance_semantic_inputs(&self) -> &[(SemanticShaderInputKey, SemanticInputData)] {
+        &self.instance_semantic_inputs
     }
 
+    /// Returns where the bind groups of a draw come from, by group. They are set in order, so a later source of a
+    /// group replaces an earlier one if it is present.
+    pub fn bind_group_sources(&self) -> &[(u32, BindGroupSource)] {
+        &self.bind_group_sources
+    }
+
+    /// Returns the names of the per-instance properties, sorted.
+    pub fn property_names(&self) -> Vec<&str> {
+        let mut names = Vec::from_iter(self.instance_properties.keys().map(String::as_str));
+        names.sort_unstable();
+        names
+    }
+
+    /// Returns the names of the bindings of the shader that the material owns, sorted. Semantic bindings are
+    /// excluded, since the renderers provide them.
+    pub fn binding_names(&self) -> Vec<&str> {
+        let mut names = Vec::from_iter(self.bind_properties.keys().filter_map(|key| match key {
+            BindingPropKey::StringKey(name) => Some(name.as_str()),
+            BindingPropKey::SemanticKey(_) => None,
+        }));
+        names.sort_unstable();
+        names
+    }
+
+    /// Binds the resource to the binding of the key. The bind group is created again by
+    /// [`Material::update_bind_group`].
     pub fn set_bind_property(
         &mut self,
         key: &BindingPropKey,
         resource: impl Into<BindGroupEntryResource>,
-    ) -> bool {
-        let index = if let Some(index) = self.bind_properties.get(key) {
-            *index
-        } else {
-            return false;
-        };
-        let bind_group_holder = &mut self.bind_group_holders[index.group_index];
-        let entry_holder = &mut bind_group_holder.entries[index.entry_index];
+    ) -> Result<(), MaterialPropertyError> {
         let resource = resource.into();
+        let index = find_bind_property(
+            &self.bind_properties,
+            &self.bind_group_holders,
+            key,
+            &resource,
+        )?;
+        let bind_group_holder = &mut self.bind_group_holders[index.group_index];
 
-        if !resource.is_match(entry_holder.binding_ty, entry_holder.count) {
-            return false;
-        }
-
-        entry_holder.resource = Some(resource);
+        bind_group_holder.entries[index.entry_index].resource = Some(resource);
         bind_group_holder.is_dirty = true;
-        true
+        Ok(())
+    }
+
+    /// Same as [`Material::set_bind_property`], but only tells whether the resource is bound.
+    pub fn try_set_bind_property(
+        &mut self,
+        key: &BindingPropKey,
+        resource: impl Into<BindGroupEntryResource>,
+    ) -> bool {
+        self.set_bind_property(key, resource).is_ok()
     }
 
     pub fn set_per_instance_property(
         &mut self,
         name: impl AsRef<str>,
         value: impl Into<PerInstancePropertyValue>,
+    ) -> Result<(), MaterialPropertyError> {
+        let name = name.as_ref();
+        let value = value.into();
+
+        check_per_instance_property(&self.instance_properties, name, &value)?;
+
+        if let Some(property) = self.instance_properties.get_mut(name) {
+            property.value = Some(value);
+        }
+
+        Ok(())
+    }
+
+    /// Same as [`Material::set_per_instance_property`], but only tells whether the value is set.
+    pub fn try_set_per_instance_property(
+        &mut self,
+        name: impl AsRef<str>,
+        value: impl Into<PerInstancePropertyValue>,
     ) -> bool {
-        let property = if let Some(property) = self.instance_properties.get_mut(name.as_ref()) {
+        self.set_per_instance_property(name, value).is_ok()
+    }
+
+    /// Sets a member of a uniform struct owned by the material, e.g. a member of `material_params`.
+    /// The value is uploaded by [`Material::flush_uniforms`].
+    pub fn set_uniform_property(
+        &mut self,
+        name: impl AsRef<str>,
+        value: impl Into<UniformPropertyValue>,
+    ) -> bool {
+        let property = if let Some(property) = self.uniform_properties.get(name.as_ref()) {
             property
         } else {
             return false;
         };
         let value = value.into();
 
-        if value.to_vertex_format() != property.format {
+        if value.ty() != property.ty {
             return false;
         }
 
-        property.value = Some(value);
+        self.uniform_blocks[property.block_index]
+            .block
+            .write(property.offset, &value);
         true
     }
 
+    /// Uploads the uniform properties written since the last flush.
+    /// Uniform buffers are created and bound on the first flush, so it must be called before
+    /// [`Material::update_bind_group`].
+    pub fn flush_uniforms(&mut self, device: &Device, queue: &Queue) {
+        for index in 0..self.uniform_blocks.len() {
+            let holder = &mut self.uniform_blocks[index];
+            let dirty_range = if let Some(dirty_range) = holder.block.take_dirty_range() {
+                dirty_range
+            } else {
+                continue;
+            };
+
+            if let Some(buffer) = &holder.buffer {
+                queue.write_buffer(
+                    buffer,
+                    dirty_range.start as BufferAddress,
+                    &holder.block.data()[dirty_range],
+                );
+                continue;
+            }
+
+            let buffer = Arc::new(device.create_buffer_init(&BufferInitDescriptor {
+                label: None,
+                contents: holder.block.data(),
+                usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            }));
+            holder.buffer = Some(buffer.clone());
+            holder.tracker = GpuResourceTracker::buffer(&buffer, None);
+
+            let key = holder.key.clone();
+            self.try_set_bind_property(
+                &key,
+                BindGroupEntryResource::Buffer {
+                    buffer,
+                    offset: 0,
+                    size: None,
+                },
+            );
+        }
+    }
+
     pub fn update_bind_group(&mut self, device: &Device) {
         for bind_group_holder in &mut self.bind_group_holders {
             if !bind_group_holder.is_dirty {
@@ -221,6 +567,7 @@ impl Material {
                 .any(|entry| entry.resource.is_none())
             {
                 bind_group_holder.bind_group = None;
+                bind_group_holder.tracker = Default::default();
                 continue;
             }
 
@@ -252,6 +599,8 @@ impl Material {
                 layout: layout.as_ref(),
                 entries: &entries,
             }));
+            bind_group_holder.tracker =
+                GpuResourceTracker::new(GpuResourceKind::BindGroup, None, 0);
         }
     }
 }
@@ -262,6 +611,15 @@ pub enum BindingPropKey {
     StringKey(String),
 }
 
+impl Display for BindingPropKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BindingPropKey::SemanticKey(key) => write!(f, "semantic binding #{}", key.get()),
+            BindingPropKey::StringKey(name) => write!(f, "{}", name),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct SemanticInputData {
     pub step_mode: VertexStepMode,
@@ -281,6 +639,8 @@ pub struct BindGroupHolder {
     pub is_dirty: bool,
     pub group: u32,
     pub bind_group: Option<BindGroup>,
+    /// Registers the bind group while it is alive.
+    pub tracker: GpuResourceTracker,
     pub entries: Vec<BindGroupEntryHolder>,
 }
 
@@ -310,6 +670,27 @@ pub enum BindGroupEntryResource {
     },
 }
 
+impl From<MaterialBindingValue> for BindGroupEntryResource {
+    fn from(value: MaterialBindingValue) -> Self {
+        match value {
+            MaterialBindingValue::Buffer {
+                buffer,
+                offset,
+                size,
+            } => Self::Buffer {
+                buffer,
+                offset,
+                size,
+            },
+            MaterialBindingValue::TextureView { view } => Self::TextureView { texture_view: view },
+            MaterialBindingValue::TextureViewArray { views } => Self::TextureViewArray {
+                texture_views: views,
+            },
+            MaterialBindingValue::Sampler { sampler } => Self::Sampler { sampler },
+        }
+    }
+}
+
 impl BindGroupEntryResource {
     pub fn is_match(&self, binding_ty: BindingType, count: Option<NonZeroU32>) -> bool {
         match (self, binding_ty) {
@@ -340,6 +721,16 @@ impl BindGroupEntryResource {
         }
     }
 
+    /// Describes the kind of the resource for error messages, e.g. `a sampler`.
+    pub fn kind_name(&self) -> &'static str {
+        match self {
+            BindGroupEntryResource::Buffer { .. } => "a buffer",
+            BindGroupEntryResource::Sampler { .. } => "a sampler",
+            BindGroupEntryResource::TextureView { .. } => "a texture view",
+            BindGroupEntryResource::TextureViewArray { .. } => "a texture view array",
+        }
+    }
+
     pub fn as_binding_resource_builder(&self) -> BindGroupEntryResourceBindingResourceBuilder {
         match self {
             BindGroupEntryResource::Buffer {
@@ -391,6 +782,22 @@ impl<'a> BindGroupEntryResourceBindingResourceBuilder<'a> {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct UniformBlockHolder {
+    pub key: BindingPropKey,
+    pub block: UniformBlock,
+    pub buffer: Option<Arc<Buffer>>,
+    /// Registers the buffer while it is alive.
+    pub tracker: GpuResourceTracker,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UniformProperty {
+    pub block_index: usize,
+    pub offset: BufferAddress,
+    pub ty: UniformPropertyType,
+}
+
 #[derive(Debug, Clone)]
 pub struct InstanceProperty {
     pub format: VertexFormat,
@@ -509,3 +916,615 @@ impl PerInstancePropertyValue {
         }
     }
 }
+
+impl From<f32> for PerInstancePropertyValue {
+    fn from(value: f32) -> Self {
+        Self::Float32([value])
+    }
+}
+
+impl From<[f32; 2]> for PerInstancePropertyValue {
+    fn from(value: [f32; 2]) -> Self {
+        Self::Float32x2(value)
+    }
+}
+
+impl From<[f32; 3]> for PerInstancePropertyValue {
+    fn from(value: [f32; 3]) -> Self {
+        Self::Float32x3(value)
+    }
+}
+
+impl From<[f32; 4]> for PerInstancePropertyValue {
+    fn from(value: [f32; 4]) -> Self {
+        Self::Float32x4(value)
+    }
+}
+
+impl From<Vec2> for PerInstancePropertyValue {
+    fn from(value: Vec2) -> Self {
+        Self::Float32x2([value.x, value.y])
+    }
+}
+
+impl From<Vec3> for PerInstancePropertyValue {
+    fn from(value: Vec3) -> Self {
+        Self::Float32x3([value.x, value.y, value.z])
+    }
+}
+
+impl From<Vec4> for PerInstancePropertyValue {
+    fn from(value: Vec4) -> Self {
+        Self::Float32x4([value.x, value.y, value.z, value.w])
+    }
+}
+
+impl From<MaterialInstancePropValue> for PerInstancePropertyValue {
+    fn from(value: MaterialInstancePropValue) -> Self {
+        match value {
+            MaterialInstancePropValue::Uint8x2(inner) => Self::Uint8x2(inner),
+            MaterialInstancePropValue::Uint8x4(inner) => Self::Uint8x4(inner),
+            MaterialInstancePropValue::Sint8x2(inner) => Self::Sint8x2(inner),
+            MaterialInstancePropValue::Sint8x4(inner) => Self::Sint8x4(inner),
+            MaterialInstancePropValue::Unorm8x2(inner) => Self::Unorm8x2(inner),
+            MaterialInstancePropValue::Unorm8x4(inner) => Self::Unorm8x4(inner),
+            MaterialInstancePropValue::Snorm8x2(inner) => Self::Snorm8x2(inner),
+            MaterialInstancePropValue::Snorm8x4(inner) => Self::Snorm8x4(inner),
+            MaterialInstancePropValue::Uint16x2(inner) => Self::Uint16x2(inner),
+            MaterialInstancePropValue::Uint16x4(inner) => Self::Uint16x4(inner),
+            MaterialInstancePropValue::Sint16x2(inner) => Self::Sint16x2(inner),
+            MaterialInstancePropValue::Sint16x4(inner) => Self::Sint16x4(inner),
+            MaterialInstancePropValue::Unorm16x2(inner) => Self::Unorm16x2(inner),
+            MaterialInstancePropValue::Unorm16x4(inner) => Self::Unorm16x4(inner),
+            MaterialInstancePropValue::Snorm16x2(inner) => Self::Snorm16x2(inner),
+            MaterialInstancePropValue::Snorm16x4(inner) => Self::Snorm16x4(inner),
+            MaterialInstancePropValue::Float32(inner) => Self::Float32(inner),
+            MaterialInstancePropValue::Float32x2(inner) => Self::Float32x2(inner),
+            MaterialInstancePropValue::Float32x3(inner) => Self::Float32x3(inner),
+            MaterialInstancePropValue::Float32x4(inner) => Self::Float32x4(inner),
+            MaterialInstancePropValue::Uint32(inner) => Self::Uint32(inner),
+            MaterialInstancePropValue::Uint32x2(inner) => Self::Uint32x2(inner),
+            MaterialInstancePropValue::Uint32x3(inner) => Self::Uint32x3(inner),
+            MaterialInstancePropValue::Uint32x4(inner) => Self::Uint32x4(inner),
+            MaterialInstancePropValue::Sint32(inner) => Self::Sint32(inner),
+            MaterialInstancePropValue::Sint32x2(inner) => Self::Sint32x2(inner),
+            MaterialInstancePropValue::Sint32x3(inner) => Self::Sint32x3(inner),
+            MaterialInstancePropValue::Sint32x4(inner) => Self::Sint32x4(inner),
+            MaterialInstancePropValue::Float64(inner) => Self::Float64(inner),
+            MaterialInstancePropValue::Float64x2(inner) => Self::Float64x2(inner),
+            MaterialInstancePropValue::Float64x3(inner) => Self::Float64x3(inner),
+            MaterialInstancePropValue::Float64x4(inner) => Self::Float64x4(inner),
+        }
+    }
+}
+
+/// Lists the semantic bindings in the order of reflection, then the bind groups of the material that have any
+/// property. Each bind group is listed once, although it may be referred to by many bindings.
+fn collect_bind_group_sources(
+    bindings: &[ReflectedShaderBindingElement],
+    bind_properties: &HashMap<BindingPropKey, BindGroupIndex>,
+    bind_group_holders: &[BindGroupHolder],
+) -> Vec<(u32, BindGroupSource)> {
+    let mut sources = Vec::new();
+
+    for binding in bindings {
+        let source = match binding.semantic_binding {
+            Some(semantic_bindings::KEY_CAMERA_TRANSFORM) => BindGroupSource::CameraTransform,
+            Some(semantic_bindings::KEY_SCREEN_SIZE) => BindGroupSource::ScreenSize,
+            Some(key) => BindGroupSource::Renderer(key),
+            None => continue,
+        };
+
+        if !sources.contains(&(binding.group, source)) {
+            sources.push((binding.group, source));
+        }
+    }
+
+    let mut group_indices = Vec::from_iter(bind_properties.values().map(|index| index.group_index));
+    group_indices.sort_unstable();
+    group_indices.dedup();
+
+    sources.extend(group_indices.into_iter().map(|group_index| {
+        (
+            bind_group_holders[group_index].group,
+            BindGroupSource::Material(group_index),
+        )
+    }));
+
+    sources
+}
+
+fn find_bind_property(
+    bind_properties: &HashMap<BindingPropKey, BindGroupIndex>,
+    bind_group_holders: &[BindGroupHolder],
+    key: &BindingPropKey,
+    resource: &BindGroupEntryResource,
+) -> Result<BindGroupIndex, MaterialPropertyError> {
+    let index = if let Some(index) = bind_properties.get(key) {
+        *index
+    } else {
+        let suggestions = match key {
+            BindingPropKey::StringKey(name) => suggest_names(
+                name,
+                bind_properties.keys().filter_map(|key| match key {
+                    BindingPropKey::StringKey(name) => Some(name.as_str()),
+                    BindingPropKey::SemanticKey(_) => None,
+                }),
+            ),
+            BindingPropKey::SemanticKey(_) => Vec::new(),
+        };
+
+        return Err(MaterialPropertyError::UnknownBinding {
+            key: key.clone(),
+            suggestions,
+        });
+    };
+    let entry_holder = &bind_group_holders[index.group_index].entries[index.entry_index];
+
+    if !resource.is_match(entry_holder.binding_ty, entry_holder.count) {
+        return Err(MaterialPropertyError::BindingTypeMismatch {
+            key: key.clone(),
+            expected: entry_holder.binding_ty,
+            count: entry_holder.count,
+            provided: resource.kind_name(),
+        });
+    }
+
+    Ok(index)
+}
+
+fn check_per_instance_property(
+    instance_properties: &HashMap<String, InstanceProperty>,
+    name: &str,
+    value: &PerInstancePropertyValue,
+) -> Result<(), MaterialPropertyError> {
+    let property = if let Some(property) = instance_properties.get(name) {
+        property
+    } else {
+        return Err(MaterialPropertyError::UnknownProperty {
+            name: name.to_owned(),
+            suggestions: suggest_names(name, instance_properties.keys().map(String::as_str)),
+        });
+    };
+    let provided = value.to_vertex_format();
+
+    if provided != property.format {
+        return Err(MaterialPropertyError::FormatMismatch {
+            name: name.to_owned(),
+            expected: property.format,
+            provided,
+        });
+    }
+
+    Ok(())
+}
+
+/// Returns up to 3 of the known names that are a few edits away from the name, ignoring the case, closest first.
+fn suggest_names<'a>(name: &str, known_names: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let name = name.to_lowercase();
+    let max_distance = usize::max(2, name.chars().count() / 3);
+    let mut suggestions = Vec::from_iter(known_names.filter_map(|known_name| {
+        let distance = edit_distance(&name, &known_name.to_lowercase());
+        (distance <= max_distance).then_some((distance, known_name))
+    }));
+    suggestions.sort_unstable();
+
+    Vec::from_iter(
+        suggestions
+            .into_iter()
+            .take(3)
+            .map(|(_, known_name)| known_name.to_owned()),
+    )
+}
+
+/// Computes the Levenshtein distance between the strings, by chars.
+fn edit_distance(lhs: &str, rhs: &str) -> usize {
+    let rhs = Vec::from_iter(rhs.chars());
+    let mut row = Vec::from_iter(0..=rhs.len());
+
+    for (lhs_index, lhs_char) in lhs.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = lhs_index + 1;
+
+        for (rhs_index, &rhs_char) in rhs.iter().enumerate() {
+            let substitution = diagonal + usize::from(lhs_char != rhs_char);
+            diagonal = row[rhs_index + 1];
+            row[rhs_index + 1] = substitution.min(row[rhs_index] + 1).min(diagonal + 1);
+        }
+    }
+
+    row[rhs.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wgpu::SamplerBindingType;
+
+    fn binding(
+        name: &str,
+        group: u32,
+        semantic_binding: Option<SemanticShaderBindingKey>,
+    ) -> ReflectedShaderBindingElement {
+        ReflectedShaderBindingElement {
+            semantic_binding,
+            name: name.to_owned(),
+            group,
+            binding: 0,
+            kind: ReflectedShaderBindingElementKind::Sampler {
+                binding_type: SamplerBindingType::Filtering,
+            },
+        }
+    }
+
+    fn holder(group: u32) -> BindGroupHolder {
+        BindGroupHolder {
+            is_dirty: false,
+            group,
+            bind_group: None,
+            tracker: Default::default(),
+            entries: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn bind_group_sources_are_listed_once_per_group() {
+        let sprite_key = SemanticShaderBindingKey::new(100);
+        let bindings = [
+            binding(
+                "camera_transform",
+                0,
+                Some(semantic_bindings::KEY_CAMERA_TRANSFORM),
+            ),
+            binding("sprite_texture", 2, Some(sprite_key)),
+            binding("sprite_sampler", 2, Some(sprite_key)),
+            binding("albedo", 1, None),
+            binding("albedo_sampler", 1, None),
+        ];
+        let bind_properties = HashMap::from_iter([
+            (
+                BindingPropKey::SemanticKey(semantic_bindings::KEY_CAMERA_TRANSFORM),
+                BindGroupIndex {
+                    group_index: 1,
+                    entry_index: 0,
+                },
+            ),
+            (
+                BindingPropKey::StringKey("albedo".to_owned()),
+                BindGroupIndex {
+                    group_index: 0,
+                    entry_index: 0,
+                },
+            ),
+            (
+                BindingPropKey::StringKey("albedo_sampler".to_owned()),
+                BindGroupIndex {
+                    group_index: 0,
+                    entry_index: 1,
+                },
+            ),
+        ]);
+        let bind_group_holders = [holder(1), holder(0)];
+
+        assert_eq!(
+            collect_bind_group_sources(&bindings, &bind_properties, &bind_group_holders),
+            [
+                (0, BindGroupSource::CameraTransform),
+                (2, BindGroupSource::Renderer(sprite_key)),
+                (1, BindGroupSource::Material(0)),
+                (0, BindGroupSource::Material(1)),
+            ]
+        );
+    }
+
+    /// The per-instance properties and bindings that the material of this shader would own.
+    ///
+    /// ```wgsl
+    /// struct InstanceInput { @location(0) tint: vec4<f32>, @location(1) uv_offset: vec2<f32> }
+    /// @group(1) @binding(0) var albedo: texture_2d<f32>;
+    /// @group(1) @binding(1) var albedo_sampler: sampler;
+    /// ```
+    fn reflected_properties() -> (
+        HashMap<String, InstanceProperty>,
+        HashMap<BindingPropKey, BindGroupIndex>,
+        Vec<BindGroupHolder>,
+    ) {
+        let instance_properties = HashMap::from_iter([
+            (
+                "tint".to_owned(),
+                InstanceProperty {
+                    format: VertexFormat::Float32x4,
+                    offset: 0,
+                    value: None,
+                },
+            ),
+            (
+                "uv_offset".to_owned(),
+                InstanceProperty {
+                    format: VertexFormat::Float32x2,
+                    offset: 16,
+                    value: None,
+                },
+            ),
+        ]);
+        let bind_properties =
+            HashMap::from_iter(["albedo", "albedo_sampler"].into_iter().enumerate().map(
+                |(entry_index, name)| {
+                    (
+                        BindingPropKey::StringKey(name.to_owned()),
+                        BindGroupIndex {
+                            group_index: 0,
+                            entry_index,
+                        },
+                    )
+                },
+            ));
+        let mut bind_group_holder = holder(1);
+        bind_group_holder.entries = vec![
+            BindGroupEntryHolder {
+                binding: 0,
+                binding_ty: BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+                resource: None,
+            },
+            BindGroupEntryHolder {
+                binding: 1,
+                binding_ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                count: None,
+                resource: None,
+            },
+        ];
+
+        (
+            instance_properties,
+            bind_properties,
+            vec![bind_group_holder],
+        )
+    }
+
+    #[test]
+    fn property_errors_describe_the_mismatch() {
+        let (instance_properties, bind_properties, bind_group_holders) = reflected_properties();
+
+        assert_eq!(
+            check_per_instance_property(&instance_properties, "tint", &Vec4::ONE.into()),
+            Ok(())
+        );
+        assert_eq!(
+            check_per_instance_property(&instance_properties, "tnit", &Vec4::ONE.into()),
+            Err(MaterialPropertyError::UnknownProperty {
+                name: "tnit".to_owned(),
+                suggestions: vec!["tint".to_owned()],
+            })
+        );
+        assert_eq!(
+            check_per_instance_property(&instance_properties, "tint", &Vec3::ONE.into()),
+            Err(MaterialPropertyError::FormatMismatch {
+                name: "tint".to_owned(),
+                expected: VertexFormat::Float32x4,
+                provided: VertexFormat::Float32x3,
+            })
+        );
+
+        let resource = BindGroupEntryResource::TextureViewArray {
+            texture_views: Vec::new(),
+        };
+        let err = find_bind_property(
+            &bind_properties,
+            &bind_group_holders,
+            &BindingPropKey::StringKey("Albedo_Sampler".to_owned()),
+            &resource,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            MaterialPropertyError::UnknownBinding {
+                key: BindingPropKey::StringKey("Albedo_Sampler".to_owned()),
+                suggestions: vec!["albedo_sampler".to_owned()],
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "unknown binding `Albedo_Sampler`; did you mean `albedo_sampler`?"
+        );
+        assert_eq!(
+            find_bind_property(
+                &bind_properties,
+                &bind_group_holders,
+                &BindingPropKey::StringKey("albedo_sampler".to_owned()),
+                &resource,
+            ),
+            Err(MaterialPropertyError::BindingTypeMismatch {
+                key: BindingPropKey::StringKey("albedo_sampler".to_owned()),
+                expected: BindingType::Sampler(SamplerBindingType::Filtering),
+                count: None,
+                provided: "a texture view array",
+            })
+        );
+    }
+
+    struct TestShader {
+        key: asset::AssetKey,
+        handle: asset::GfxShaderModule,
+        source: String,
+        defines: Vec<String>,
+    }
+
+    impl asset::Asset for TestShader {
+        fn key(&self) -> &asset::AssetKey {
+            &self.key
+        }
+
+        fn as_typed(self: Arc<Self>) -> asset::TypedAsset {
+            asset::TypedAsset::Shader(self)
+        }
+    }
+
+    impl asset::assets::ShaderAsset for TestShader {
+        fn handle(&self) -> &asset::GfxShaderModule {
+            &self.handle
+        }
+
+        fn reflection(&self) -> &asset::assets::ShaderReflection {
+            unimplemented!("the runtime reflects the source on its own")
+        }
+
+        fn source(&self) -> &str {
+            &self.source
+        }
+
+        fn defines(&self) -> &[String] {
+            &self.defines
+        }
+    }
+
+    #[test]
+    fn material_is_created_from_preset() {
+        let gfx_ctx = match crate::gfx::test_gfx_ctx(winit::dpi::PhysicalSize::new(4, 4)) {
+            Some(gfx_ctx) => gfx_ctx,
+            None => {
+                eprintln!("skipped: no adapter found");
+                return;
+            }
+        };
+        let source = r#"
+@group(0) @binding(0) var albedo: texture_2d<f32>;
+@group(0) @binding(1) var albedo_sampler: sampler;
+
+struct InstanceInput {
+  @location(0) tint: vec4<f32>,
+#ifdef HAS_UV_OFFSET
+  @location(1) uv_offset: vec2<f32>,
+#endif
+};
+
+struct VertexInput {
+  @location(2) position: vec3<f32>,
+};
+
+struct VertexOutput {
+  @builtin(position) position: vec4<f32>,
+  @location(0) color: vec4<f32>,
+};
+
+@vertex
+fn vs_main(instance: InstanceInput, vertex: VertexInput) -> VertexOutput {
+  var out: VertexOutput;
+  out.position = vec4<f32>(vertex.position, 1.0);
+  out.color = instance.tint;
+  return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+  return in.color * textureSample(albedo, albedo_sampler, vec2<f32>(0.5, 0.5));
+}
+"#;
+        let device = &gfx_ctx.device;
+        let defines = vec!["HAS_UV_OFFSET".to_owned()];
+        let specialized = asset::assets::specialize_shader_source(
+            source,
+            &BTreeSet::from_iter(defines.iter().cloned()),
+        )
+        .unwrap();
+        let shader = Arc::new(TestShader {
+            key: asset::AssetKey::Path("test.wgsl".to_owned()),
+            handle: Arc::new(device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: None,
+                source: wgpu::ShaderSource::Wgsl(specialized.into()),
+            })),
+            source: source.to_owned(),
+            defines,
+        });
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size: wgpu::Extent3d {
+                width: 1,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let preset = MaterialPreset {
+            shader,
+            binding_props: vec![
+                asset::assets::MaterialBindingProp {
+                    key: MaterialBindingKey::Named("albedo".to_owned()),
+                    value: MaterialBindingValue::TextureView {
+                        view: Arc::new(texture.create_view(&Default::default())),
+                    },
+                },
+                asset::assets::MaterialBindingProp {
+                    key: MaterialBindingKey::Named("albedo_sampler".to_owned()),
+                    value: MaterialBindingValue::Sampler {
+                        sampler: Arc::new(device.create_sampler(&Default::default())),
+                    },
+                },
+            ],
+            instance_props: vec![
+                asset::assets::MaterialInstanceProp {
+                    key: MaterialInstancePropKey::Named("tint".to_owned()),
+                    value: MaterialInstancePropValue::Float32x4([1.0, 0.5, 0.25, 1.0]),
+                },
+                asset::assets::MaterialInstanceProp {
+                    key: MaterialInstancePropKey::Named("uv_offset".to_owned()),
+                    value: MaterialInstancePropValue::Float32x2([0.5, 0.0]),
+                },
+            ],
+            blend_mode: MaterialBlendMode::Alpha,
+            cull_mode: MaterialCullMode::None,
+            front_face: MaterialFrontFace::Ccw,
+            topology: MaterialTopology::LineList,
+        };
+        let shader_mgr = ShaderManager::new(gfx_ctx.clone());
+        let mut bind_group_layout_cache = BindGroupLayoutCache::new(gfx_ctx.clone());
+        let mut pipeline_layout_cache = PipelineLayoutCache::new(gfx_ctx.clone());
+
+        let mut material = Material::from_preset(
+            &preset,
+            &shader_mgr,
+            &mut bind_group_layout_cache,
+            &mut pipeline_layout_cache,
+        )
+        .unwrap();
+
+        let index = material.bind_properties[&BindingPropKey::StringKey("albedo".to_owned())];
+        assert!(matches!(
+            material.bind_group_holders[index.group_index].entries[index.entry_index].resource,
+            Some(BindGroupEntryResource::TextureView { .. })
+        ));
+        let index =
+            material.bind_properties[&BindingPropKey::StringKey("albedo_sampler".to_owned())];
+        assert!(matches!(
+            material.bind_group_holders[index.group_index].entries[index.entry_index].resource,
+            Some(BindGroupEntryResource::Sampler { .. })
+        ));
+        assert_eq!(
+            material.instance_properties["tint"].value,
+            Some(PerInstancePropertyValue::Float32x4([1.0, 0.5, 0.25, 1.0]))
+        );
+        assert_eq!(
+            material.instance_properties["uv_offset"].value,
+            Some(PerInstancePropertyValue::Float32x2([0.5, 0.0]))
+        );
+        assert_eq!(material.topology, PrimitiveTopology::LineList);
+        assert_eq!(material.blend_mode, Some(MaterialBlendMode::Alpha));
+        assert_eq!(material.cull_mode, Some(MaterialCullMode::None));
+        assert_eq!(material.front_face, Some(MaterialFrontFace::Ccw));
+
+        // The bindings of the preset are enough to create the bind group.
+        material.update_bind_group(device);
+        assert!(material.bind_group_holders[index.group_index]
+            .bind_group
+            .is_some());
+    }
+}
@@ -1,15 +1,29 @@
-use super::{CachedPipelineLayout, ShaderHandle, ShaderManager};
-use crate::gfx::GfxContextHandle;
+use super::{CachedPipelineLayout, Material, ShaderHandle, ShaderManager};
+use crate::gfx::{GfxContextHandle, GpuResourceKind, GpuResourceTracker};
+use asset::assets::MaterialBlendMode;
 use std::{
     collections::HashMap,
     hash::Hash,
     sync::{Arc, Weak},
 };
 use wgpu::{
-    BufferAddress, DepthStencilState, Device, FragmentState, PrimitiveState, RenderPipeline,
-    RenderPipelineDescriptor, VertexAttribute, VertexBufferLayout, VertexState, VertexStepMode,
+    BlendComponent, BlendFactor, BlendOperation, BlendState, BufferAddress, ColorTargetState,
+    DepthStencilState, Device, FragmentState, MultisampleState, PrimitiveState, RenderPipeline,
+    RenderPipelineDescriptor, TextureFormat, VertexAttribute, VertexBufferLayout, VertexState,
+    VertexStepMode,
 };
 
+/// The attachments that pipelines render to. A pipeline is only valid for the target it was created for, so
+/// the same material gets a pipeline per target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RenderTargetState {
+    /// The format of every color target, overriding the one of the semantic outputs.
+    pub color_format: TextureFormat,
+    /// The format of the depth stencil attachment. Depth stencil states of renderers are ignored if `None`.
+    pub depth_format: Option<TextureFormat>,
+    pub sample_count: u32,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct BufferLayout {
     pub array_stride: BufferAddress,
@@ -24,6 +38,9 @@ pub struct PipelineKey {
     pub buffer_layouts: Vec<BufferLayout>,
     pub primitive: PrimitiveState,
     pub depth_stencil: Option<DepthStencilState>,
+    /// The blending of the material. The blending of the semantic outputs is used if `None`.
+    pub blend_mode: Option<MaterialBlendMode>,
+    pub target: RenderTargetState,
 }
 
 impl PipelineKey {
@@ -47,11 +64,27 @@ impl PipelineKey {
             let target = output.semantic_output.and_then(|key| {
                 shader_mgr
                     .get_semantic_output(key)
-                    .map(|output| output.target.clone())
+                    .map(|output| ColorTargetState {
+                        format: self.target.color_format,
+                        blend: match self.blend_mode {
+                            Some(blend_mode) => blend_state(blend_mode),
+                            None => output.target.blend,
+                        },
+                        ..output.target.clone()
+                    })
             });
             targets[output.location as usize] = target;
         }
 
+        let depth_stencil = self.target.depth_format.and_then(|format| {
+            self.depth_stencil
+                .clone()
+                .map(|depth_stencil| DepthStencilState {
+                    format,
+                    ..depth_stencil
+                })
+        });
+
         device.create_render_pipeline(&RenderPipelineDescriptor {
             label: None,
             layout: Some(self.layout.as_ref()),
@@ -61,8 +94,11 @@ impl PipelineKey {
                 buffers: &buffers,
             },
             primitive: self.primitive,
-            depth_stencil: self.depth_stencil.clone(),
-            multisample: Default::default(),
+            depth_stencil,
+            multisample: MultisampleState {
+                count: self.target.sample_count,
+                ..Default::default()
+            },
             fragment: Some(FragmentState {
                 module: &self.shader.shader_module,
                 entry_point: &self.shader.reflected_shader.fragment_entry_point_name,
@@ -73,20 +109,27 @@ impl PipelineKey {
     }
 }
 
+#[derive(Debug)]
+struct TrackedPipeline {
+    pipeline: RenderPipeline,
+    _tracker: GpuResourceTracker,
+}
+
 #[derive(Debug, Clone)]
 pub struct CachedPipeline {
-    pipeline: Arc<RenderPipeline>,
+    pipeline: Arc<TrackedPipeline>,
 }
 
 impl CachedPipeline {
-    pub fn new(pipeline: Arc<RenderPipeline>) -> Self {
-        Self { pipeline }
+    /// Returns a key that is equal for the same pipeline, e.g. to group draws by pipeline.
+    pub fn id(&self) -> usize {
+        Arc::as_ptr(&self.pipeline) as usize
     }
 }
 
 impl AsRef<RenderPipeline> for CachedPipeline {
     fn as_ref(&self) -> &RenderPipeline {
-        &self.pipeline
+        &self.pipeline.pipeline
     }
 }
 
@@ -106,41 +149,249 @@ impl Hash for CachedPipeline {
 
 pub struct PipelineCache {
     gfx_ctx: GfxContextHandle,
-    caches: HashMap<PipelineKey, Weak<RenderPipeline>>,
+    target: RenderTargetState,
+    caches: HashMap<PipelineKey, Weak<TrackedPipeline>>,
 }
 
 impl PipelineCache {
-    pub fn new(gfx_ctx: GfxContextHandle) -> Self {
+    pub fn new(gfx_ctx: GfxContextHandle, target: RenderTargetState) -> Self {
         Self {
             gfx_ctx,
+            target,
             caches: HashMap::new(),
         }
     }
 
+    /// Returns the target that pipelines are created for.
+    pub fn target(&self) -> RenderTargetState {
+        self.target
+    }
+
+    /// Sets the target that pipelines are created for. Pipelines of other targets are kept while in use,
+    /// so that switching back and forth does not rebuild them.
+    pub fn set_target(&mut self, target: RenderTargetState) {
+        if self.target == target {
+            return;
+        }
+
+        self.target = target;
+        self.caches
+            .retain(|_, pipeline| pipeline.strong_count() != 0);
+    }
+
     pub fn create_pipeline(
         &mut self,
         shader_mgr: &ShaderManager,
-        layout: CachedPipelineLayout,
-        shader: ShaderHandle,
+        material: &Material,
         buffer_layouts: Vec<BufferLayout>,
         primitive: PrimitiveState,
         depth_stencil: Option<DepthStencilState>,
     ) -> CachedPipeline {
         let key = PipelineKey {
-            layout,
-            shader,
+            layout: material.pipeline_layout.clone(),
+            shader: material.shader.clone(),
             buffer_layouts,
             primitive,
             depth_stencil,
+            blend_mode: material.blend_mode,
+            target: self.target,
         };
 
-        if let Some(pipeline) = self.caches.get(&key).and_then(|weak| weak.upgrade()) {
-            return CachedPipeline::new(pipeline);
-        }
+        let pipeline = obtain_cached(&mut self.caches, key, |key| TrackedPipeline {
+            pipeline: key.create_pipeline(&self.gfx_ctx.device, shader_mgr),
+            _tracker: GpuResourceTracker::new(GpuResourceKind::Pipeline, None, 0),
+        });
+        CachedPipeline { pipeline }
+    }
+}
 
-        let pipeline = Arc::new(key.create_pipeline(&self.gfx_ctx.device, shader_mgr));
-        self.caches.insert(key, Arc::downgrade(&pipeline));
+/// Returns the blend state of the color targets for the blend mode. Opaque outputs replace the target.
+fn blend_state(blend_mode: MaterialBlendMode) -> Option<BlendState> {
+    match blend_mode {
+        MaterialBlendMode::Opaque => None,
+        MaterialBlendMode::Alpha => Some(BlendState::ALPHA_BLENDING),
+        MaterialBlendMode::Additive => Some(BlendState {
+            color: BlendComponent {
+                src_factor: BlendFactor::SrcAlpha,
+                dst_factor: BlendFactor::One,
+                operation: BlendOperation::Add,
+            },
+            alpha: BlendComponent {
+                src_factor: BlendFactor::Zero,
+                dst_factor: BlendFactor::One,
+                operation: BlendOperation::Add,
+            },
+        }),
+        MaterialBlendMode::PremultipliedAlpha => Some(BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+    }
+}
 
-        CachedPipeline::new(pipeline)
+fn obtain_cached<K, V>(
+    caches: &mut HashMap<K, Weak<V>>,
+    key: K,
+    create: impl FnOnce(&K) -> V,
+) -> Arc<V>
+where
+    K: Eq + Hash,
+{
+    if let Some(value) = caches.get(&key).and_then(|weak| weak.upgrade()) {
+        return value;
+    }
+
+    let value = Arc::new(create(&key));
+    caches.insert(key, Arc::downgrade(&value));
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gfx::{render_test_triangles, test_gfx_ctx};
+    use winit::dpi::PhysicalSize;
+
+    #[test]
+    fn pipelines_are_cached_per_render_target() {
+        // Creating real pipelines needs a device, so shaders are stood in by their names.
+        let target = |sample_count| RenderTargetState {
+            color_format: TextureFormat::Bgra8Unorm,
+            depth_format: Some(TextureFormat::Depth32Float),
+            sample_count,
+        };
+        let mut caches = HashMap::new();
+        let mut created = 0;
+        let mut obtain = |caches: &mut HashMap<_, _>, sample_count| {
+            obtain_cached(caches, ("standard", target(sample_count)), |_| {
+                created += 1;
+                created
+            })
+        };
+
+        let single = obtain(&mut caches, 1);
+        let multi = obtain(&mut caches, 4);
+        assert_ne!(single, multi);
+        assert_eq!(caches.len(), 2);
+
+        // Pipelines are reused while alive.
+        assert!(Arc::ptr_eq(&obtain(&mut caches, 4), &multi));
+        assert!(Arc::ptr_eq(&obtain(&mut caches, 1), &single));
+    }
+
+    /// Blends a color into the target as the color target state does.
+    fn blend(state: Option<BlendState>, src: [f32; 4], dst: [f32; 4]) -> [f32; 4] {
+        let state = if let Some(state) = state {
+            state
+        } else {
+            return src;
+        };
+        let factor = |factor: BlendFactor, channel: usize| match factor {
+            BlendFactor::Zero => 0.0,
+            BlendFactor::One => 1.0,
+            BlendFactor::Src => src[channel],
+            BlendFactor::OneMinusSrc => 1.0 - src[channel],
+            BlendFactor::SrcAlpha => src[3],
+            BlendFactor::OneMinusSrcAlpha => 1.0 - src[3],
+            BlendFactor::Dst => dst[channel],
+            BlendFactor::OneMinusDst => 1.0 - dst[channel],
+            BlendFactor::DstAlpha => dst[3],
+            BlendFactor::OneMinusDstAlpha => 1.0 - dst[3],
+            BlendFactor::SrcAlphaSaturated if channel == 3 => 1.0,
+            BlendFactor::SrcAlphaSaturated => src[3].min(1.0 - dst[3]),
+            // The renderer never sets the blend constant, so it is the default of transparent black.
+            BlendFactor::Constant => 0.0,
+            BlendFactor::OneMinusConstant => 1.0,
+        };
+        let component = |component: BlendComponent, channel: usize| {
+            let src_term = src[channel] * factor(component.src_factor, channel);
+            let dst_term = dst[channel] * factor(component.dst_factor, channel);
+
+            // The factors are ignored by the min and max operations.
+            match component.operation {
+                BlendOperation::Add => src_term + dst_term,
+                BlendOperation::Subtract => src_term - dst_term,
+                BlendOperation::ReverseSubtract => dst_term - src_term,
+                BlendOperation::Min => src[channel].min(dst[channel]),
+                BlendOperation::Max => src[channel].max(dst[channel]),
+            }
+        };
+
+        [
+            component(state.color, 0),
+            component(state.color, 1),
+            component(state.color, 2),
+            component(state.alpha, 3),
+        ]
+    }
+
+    #[test]
+    fn half_transparent_quad_over_red() {
+        let red = [1.0, 0.0, 0.0, 1.0];
+        let blue = [0.0, 0.0, 1.0, 0.5];
+        let premultiplied_blue = [0.0, 0.0, 0.5, 0.5];
+
+        assert_eq!(
+            blend(blend_state(MaterialBlendMode::Opaque), blue, red),
+            blue
+        );
+        assert_eq!(
+            blend(blend_state(MaterialBlendMode::Alpha), blue, red),
+            [0.5, 0.0, 0.5, 1.0]
+        );
+        assert_eq!(
+            blend(
+                blend_state(MaterialBlendMode::PremultipliedAlpha),
+                premultiplied_blue,
+                red
+            ),
+            [0.5, 0.0, 0.5, 1.0]
+        );
+        assert_eq!(
+            blend(blend_state(MaterialBlendMode::Additive), blue, red),
+            [1.0, 0.0, 0.5, 1.0]
+        );
+    }
+
+    #[test]
+    fn half_transparent_quad_over_red_is_blended_by_gpu() {
+        let gfx_ctx = match test_gfx_ctx(PhysicalSize::new(4, 4)) {
+            Some(gfx_ctx) => gfx_ctx,
+            None => {
+                eprintln!("skipped: no adapter found");
+                return;
+            }
+        };
+        let red = [1.0, 0.0, 0.0, 1.0];
+        let blue = [0.0, 0.0, 1.0, 0.5];
+        let premultiplied_blue = [0.0, 0.0, 0.5, 0.5];
+
+        for (blend_mode, color) in [
+            (MaterialBlendMode::Opaque, blue),
+            (MaterialBlendMode::Alpha, blue),
+            (MaterialBlendMode::PremultipliedAlpha, premultiplied_blue),
+            (MaterialBlendMode::Additive, blue),
+        ] {
+            let state = blend_state(blend_mode);
+            let expected = blend(state, color, red).map(|value| (value * 255.0).round() as u8);
+            // A triangle covering the target.
+            let pixel = render_test_triangles(
+                &gfx_ctx,
+                &[[[-1.0, -1.0], [3.0, -1.0], [-1.0, 3.0]]],
+                color,
+                wgpu::Color::RED,
+                PrimitiveState::default(),
+                state,
+            )[0];
+
+            // The GPU may round the 8-bit channels either way.
+            assert!(
+                pixel
+                    .iter()
+                    .zip(expected)
+                    .all(|(&actual, expected)| actual.abs_diff(expected) <= 1),
+                "{:?}: {:?} != {:?}",
+                blend_mode,
+                pixel,
+                expected
+            );
+        }
     }
 }
@@ -1,9 +1,11 @@
 use super::{inspect_shader, BindGroupLayoutCache, CachedBindGroupLayout, ShaderInspectionError};
 use crate::gfx::{GfxContextHandle, ReflectedShader};
+use asset::assets::specialize_shader_source;
 use codegen::Handle;
 use std::{
     borrow::Cow,
-    collections::{hash_map::Entry, HashMap},
+    cell::RefCell,
+    collections::{hash_map::Entry, BTreeSet, HashMap},
     num::NonZeroU32,
 };
 use wgpu::{
@@ -155,6 +157,27 @@ pub mod semantic_inputs {
         format: VertexFormat::Float32x4,
         step_mode: VertexStepMode::Instance,
     };
+    pub const KEY_SPRITE_ORIGIN: SemanticShaderInputKey = SemanticShaderInputKey::new(206);
+    pub const SPRITE_ORIGIN: SemanticShaderInput = SemanticShaderInput {
+        key: KEY_SPRITE_ORIGIN,
+        name: "sprite_origin",
+        format: VertexFormat::Float32x3,
+        step_mode: VertexStepMode::Instance,
+    };
+    pub const KEY_SPRITE_AXIS_X: SemanticShaderInputKey = SemanticShaderInputKey::new(207);
+    pub const SPRITE_AXIS_X: SemanticShaderInput = SemanticShaderInput {
+        key: KEY_SPRITE_AXIS_X,
+        name: "sprite_axis_x",
+        format: VertexFormat::Float32x3,
+        step_mode: VertexStepMode::Instance,
+    };
+    pub const KEY_SPRITE_AXIS_Y: SemanticShaderInputKey = SemanticShaderInputKey::new(208);
+    pub const SPRITE_AXIS_Y: SemanticShaderInput = SemanticShaderInput {
+        key: KEY_SPRITE_AXIS_Y,
+        name: "sprite_axis_y",
+        format: VertexFormat::Float32x3,
+        step_mode: VertexStepMode::Instance,
+    };
 
     pub const KEY_GLYPH_THICKNESS: SemanticShaderInputKey = SemanticShaderInputKey::new(301);
     pub const GLYPH_THICKNESS: SemanticShaderInput = SemanticShaderInput {
@@ -170,6 +193,27 @@ pub mod semantic_inputs {
         format: VertexFormat::Float32,
         step_mode: VertexStepMode::Instance,
     };
+    pub const KEY_GLYPH_PX_RANGE: SemanticShaderInputKey = SemanticShaderInputKey::new(303);
+    pub const GLYPH_PX_RANGE: SemanticShaderInput = SemanticShaderInput {
+        key: KEY_GLYPH_PX_RANGE,
+        name: "glyph_px_range",
+        format: VertexFormat::Float32,
+        step_mode: VertexStepMode::Instance,
+    };
+    pub const KEY_GLYPH_OUTLINE_COLOR: SemanticShaderInputKey = SemanticShaderInputKey::new(304);
+    pub const GLYPH_OUTLINE_COLOR: SemanticShaderInput = SemanticShaderInput {
+        key: KEY_GLYPH_OUTLINE_COLOR,
+        name: "glyph_outline_color",
+        format: VertexFormat::Float32x4,
+        step_mode: VertexStepMode::Instance,
+    };
+    pub const KEY_GLYPH_OUTLINE_WIDTH: SemanticShaderInputKey = SemanticShaderInputKey::new(305);
+    pub const GLYPH_OUTLINE_WIDTH: SemanticShaderInput = SemanticShaderInput {
+        key: KEY_GLYPH_OUTLINE_WIDTH,
+        name: "glyph_outline_width",
+        format: VertexFormat::Float32,
+        step_mode: VertexStepMode::Instance,
+    };
 }
 
 pub mod semantic_outputs {
@@ -267,6 +311,9 @@ pub struct ShaderManager {
     bindings: HashMap<SemanticShaderBindingKey, SemanticShaderBinding>,
     inputs: HashMap<SemanticShaderInputKey, SemanticShaderInput>,
     outputs: HashMap<SemanticShaderOutputKey, SemanticShaderOutput>,
+    /// The shaders created by [`create_shader_variant`](Self::create_shader_variant), keyed by the source
+    /// and the define set.
+    variants: RefCell<HashMap<(String, BTreeSet<String>), ShaderHandle>>,
 }
 
 impl ShaderManager {
@@ -279,6 +326,7 @@ impl ShaderManager {
             bindings: HashMap::new(),
             inputs: HashMap::new(),
             outputs: HashMap::new(),
+            variants: RefCell::new(HashMap::new()),
         };
 
         this.register_binding(semantic_bindings::CAMERA_TRANSFORM);
@@ -298,8 +346,14 @@ impl ShaderManager {
         this.register_input(semantic_inputs::SPRITE_UV_MIN);
         this.register_input(semantic_inputs::SPRITE_UV_MAX);
         this.register_input(semantic_inputs::SPRITE_COLOR);
+        this.register_input(semantic_inputs::SPRITE_ORIGIN);
+        this.register_input(semantic_inputs::SPRITE_AXIS_X);
+        this.register_input(semantic_inputs::SPRITE_AXIS_Y);
         this.register_input(semantic_inputs::GLYPH_THICKNESS);
         this.register_input(semantic_inputs::GLYPH_SMOOTHNESS);
+        this.register_input(semantic_inputs::GLYPH_PX_RANGE);
+        this.register_input(semantic_inputs::GLYPH_OUTLINE_COLOR);
+        this.register_input(semantic_inputs::GLYPH_OUTLINE_WIDTH);
 
         this.register_output(semantic_outputs::COLOR);
 
@@ -361,6 +415,28 @@ impl ShaderManager {
         Ok(self.build_shader(bind_group_layout_cache, shader_module, reflected_shader))
     }
 
+    /// Creates a shader from the source specialized for the defines, or returns the one created before for the
+    /// same source and defines. Each variant is a distinct shader, so it gets its own pipelines.
+    /// See [`specialize_shader_source`] for the directives.
+    pub fn create_shader_variant(
+        &self,
+        bind_group_layout_cache: &mut BindGroupLayoutCache,
+        source: impl AsRef<str>,
+        defines: &BTreeSet<String>,
+    ) -> Result<ShaderHandle, ShaderInspectionError> {
+        let key = (source.as_ref().to_owned(), defines.clone());
+
+        if let Some(shader) = self.variants.borrow().get(&key) {
+            return Ok(shader.clone());
+        }
+
+        let specialized = specialize_shader_source(&key.0, defines)?;
+        let shader = self.create_shader(bind_group_layout_cache, specialized)?;
+        self.variants.borrow_mut().insert(key, shader.clone());
+
+        Ok(shader)
+    }
+
     fn compile_shader(
         &self,
         source: impl AsRef<str>,
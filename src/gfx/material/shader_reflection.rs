@@ -1,7 +1,8 @@
 use super::{
     shader::{SemanticShaderInputKey, ShaderManager},
-    SemanticShaderBindingKey, SemanticShaderOutputKey,
+    SemanticShaderBindingKey, SemanticShaderOutputKey, UniformPropertyType,
 };
+use asset::assets::ShaderSpecializationError;
 use naga::{
     front::wgsl::{parse_str, ParseError},
     AddressSpace, ArraySize, Binding, Function, ImageClass, ImageDimension, Module, ScalarKind,
@@ -17,6 +18,8 @@ use wgpu::{
 
 #[derive(Error, Debug)]
 pub enum ShaderInspectionError {
+    #[error("failed to specialize shader source: {0}")]
+    SpecializationError(#[from] ShaderSpecializationError),
     #[error("failed to parse shader source: {0}")]
     ParseError(#[from] ParseError),
     #[error("no vertex entry point found")]
@@ -50,7 +53,7 @@ impl From<&ReflectedShaderBindingElement> for BindGroupLayoutEntry {
             binding: value.binding,
             visibility: ShaderStages::VERTEX_FRAGMENT,
             ty: match &value.kind {
-                ReflectedShaderBindingElementKind::Buffer { size } => BindingType::Buffer {
+                ReflectedShaderBindingElementKind::Buffer { size, .. } => BindingType::Buffer {
                     ty: BufferBindingType::Uniform,
                     has_dynamic_offset: false,
                     min_binding_size: Some(*size),
@@ -84,6 +87,8 @@ impl From<&ReflectedShaderBindingElement> for BindGroupLayoutEntry {
 pub enum ReflectedShaderBindingElementKind {
     Buffer {
         size: NonZeroU64,
+        /// The members of the buffer if it is a struct. Only scalars, vectors and matrices are listed.
+        members: Vec<ReflectedShaderUniformMember>,
     },
     Texture {
         sample_type: TextureSampleType,
@@ -96,6 +101,13 @@ pub enum ReflectedShaderBindingElementKind {
     },
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ReflectedShaderUniformMember {
+    pub name: String,
+    pub offset: BufferAddress,
+    pub ty: UniformPropertyType,
+}
+
 #[derive(Debug, Clone)]
 pub struct ReflectedShaderInput {
     pub step_mode: VertexStepMode,
@@ -245,7 +257,7 @@ fn reflect_globals(
                     BindingType::Buffer {
                         min_binding_size, ..
                     },
-                    ReflectedShaderBindingElementKind::Buffer { size },
+                    ReflectedShaderBindingElementKind::Buffer { size, .. },
                 ) if *min_binding_size == Some(*size) => {}
                 (
                     BindingType::Texture {
@@ -488,11 +500,13 @@ fn shader_ty_to_binding_element_kind(
     match &ty.inner {
         TypeInner::Scalar { width, .. } => Some(ReflectedShaderBindingElementKind::Buffer {
             size: unsafe { NonZeroU64::new_unchecked(aligned_size(*width as u64, 16)) },
+            members: Vec::new(),
         }),
         TypeInner::Vector { size, width, .. } => Some(ReflectedShaderBindingElementKind::Buffer {
             size: unsafe {
                 NonZeroU64::new_unchecked(aligned_size(*size as u64 * *width as u64, 16))
             },
+            members: Vec::new(),
         }),
         TypeInner::Matrix {
             columns,
@@ -504,6 +518,7 @@ fn shader_ty_to_binding_element_kind(
                     aligned_size(*columns as u64 * *width as u64, 16) * *rows as u64,
                 )
             },
+            members: Vec::new(),
         }),
         TypeInner::Array { size, stride, .. } => {
             let size = if let Some(size) = parse_array_size(*size) {
@@ -522,10 +537,12 @@ fn shader_ty_to_binding_element_kind(
                         } * size as u64,
                     )
                 },
+                members: Vec::new(),
             })
         }
         TypeInner::Struct { span, .. } => Some(ReflectedShaderBindingElementKind::Buffer {
             size: unsafe { NonZeroU64::new_unchecked(*span as u64) },
+            members: reflect_uniform_members(module, ty),
         }),
         TypeInner::Image { dim, class, .. } => {
             let (sample_type, multisampled) = match *class {
@@ -605,6 +622,24 @@ fn shader_ty_to_binding_element_kind(
     }
 }
 
+/// Lists the members of a uniform struct with their offsets, which follow the layout rules of the
+/// uniform address space. Members of other types, e.g. nested structs and arrays, are skipped.
+pub fn reflect_uniform_members(module: &Module, ty: &Type) -> Vec<ReflectedShaderUniformMember> {
+    let members = if let TypeInner::Struct { members, .. } = &ty.inner {
+        members
+    } else {
+        return Vec::new();
+    };
+
+    Vec::from_iter(members.iter().filter_map(|member| {
+        Some(ReflectedShaderUniformMember {
+            name: member.name.clone()?,
+            offset: member.offset as BufferAddress,
+            ty: UniformPropertyType::from_shader_ty(&module.types[member.ty].inner)?,
+        })
+    }))
+}
+
 fn shader_ty_to_vertex_format(ty: &Type) -> Option<VertexFormat> {
     match &ty.inner {
         TypeInner::Scalar { kind, width } => match (*kind, *width) {
@@ -0,0 +1,302 @@
+use crate::math::{Mat4, Vec2, Vec3, Vec4};
+use naga::{ScalarKind, TypeInner, VectorSize};
+use std::ops::Range;
+use wgpu::BufferAddress;
+use zerocopy::AsBytes;
+
+/// The type of a member of a uniform block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UniformPropertyType {
+    Float32,
+    Float32x2,
+    Float32x3,
+    Float32x4,
+    Uint32,
+    Uint32x2,
+    Uint32x3,
+    Uint32x4,
+    Sint32,
+    Sint32x2,
+    Sint32x3,
+    Sint32x4,
+    Mat2x2,
+    Mat3x3,
+    Mat4x4,
+}
+
+impl UniformPropertyType {
+    pub fn from_shader_ty(ty: &TypeInner) -> Option<Self> {
+        match *ty {
+            TypeInner::Scalar { kind, width: 4 } => match kind {
+                ScalarKind::Float => Some(Self::Float32),
+                ScalarKind::Uint => Some(Self::Uint32),
+                ScalarKind::Sint => Some(Self::Sint32),
+                ScalarKind::Bool => None,
+            },
+            TypeInner::Vector {
+                size,
+                kind,
+                width: 4,
+            } => match (size, kind) {
+                (VectorSize::Bi, ScalarKind::Float) => Some(Self::Float32x2),
+                (VectorSize::Tri, ScalarKind::Float) => Some(Self::Float32x3),
+                (VectorSize::Quad, ScalarKind::Float) => Some(Self::Float32x4),
+                (VectorSize::Bi, ScalarKind::Uint) => Some(Self::Uint32x2),
+                (VectorSize::Tri, ScalarKind::Uint) => Some(Self::Uint32x3),
+                (VectorSize::Quad, ScalarKind::Uint) => Some(Self::Uint32x4),
+                (VectorSize::Bi, ScalarKind::Sint) => Some(Self::Sint32x2),
+                (VectorSize::Tri, ScalarKind::Sint) => Some(Self::Sint32x3),
+                (VectorSize::Quad, ScalarKind::Sint) => Some(Self::Sint32x4),
+                _ => None,
+            },
+            TypeInner::Matrix {
+                columns,
+                rows,
+                width: 4,
+            } => match (columns, rows) {
+                (VectorSize::Bi, VectorSize::Bi) => Some(Self::Mat2x2),
+                (VectorSize::Tri, VectorSize::Tri) => Some(Self::Mat3x3),
+                (VectorSize::Quad, VectorSize::Quad) => Some(Self::Mat4x4),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns the number of columns and the stride between them in bytes.
+    /// Columns of matrices are aligned as vectors, so a column of `mat3x3` is padded to 16 bytes.
+    fn columns(self) -> (usize, usize) {
+        match self {
+            Self::Mat2x2 => (2, 8),
+            Self::Mat3x3 => (3, 16),
+            Self::Mat4x4 => (4, 16),
+            _ => (1, 0),
+        }
+    }
+}
+
+/// A value of a member of a uniform block. Matrices are in column-major order.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub enum UniformPropertyValue {
+    Float32([f32; 1]),
+    Float32x2([f32; 2]),
+    Float32x3([f32; 3]),
+    Float32x4([f32; 4]),
+    Uint32([u32; 1]),
+    Uint32x2([u32; 2]),
+    Uint32x3([u32; 3]),
+    Uint32x4([u32; 4]),
+    Sint32([i32; 1]),
+    Sint32x2([i32; 2]),
+    Sint32x3([i32; 3]),
+    Sint32x4([i32; 4]),
+    Mat2x2([f32; 4]),
+    Mat3x3([f32; 9]),
+    Mat4x4([f32; 16]),
+}
+
+impl UniformPropertyValue {
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Self::Float32(inner) => inner.as_bytes(),
+            Self::Float32x2(inner) => inner.as_bytes(),
+            Self::Float32x3(inner) => inner.as_bytes(),
+            Self::Float32x4(inner) => inner.as_bytes(),
+            Self::Uint32(inner) => inner.as_bytes(),
+            Self::Uint32x2(inner) => inner.as_bytes(),
+            Self::Uint32x3(inner) => inner.as_bytes(),
+            Self::Uint32x4(inner) => inner.as_bytes(),
+            Self::Sint32(inner) => inner.as_bytes(),
+            Self::Sint32x2(inner) => inner.as_bytes(),
+            Self::Sint32x3(inner) => inner.as_bytes(),
+            Self::Sint32x4(inner) => inner.as_bytes(),
+            Self::Mat2x2(inner) => inner.as_bytes(),
+            Self::Mat3x3(inner) => inner.as_bytes(),
+            Self::Mat4x4(inner) => inner.as_bytes(),
+        }
+    }
+
+    pub fn ty(&self) -> UniformPropertyType {
+        match self {
+            Self::Float32(_) => UniformPropertyType::Float32,
+            Self::Float32x2(_) => UniformPropertyType::Float32x2,
+            Self::Float32x3(_) => UniformPropertyType::Float32x3,
+            Self::Float32x4(_) => UniformPropertyType::Float32x4,
+            Self::Uint32(_) => UniformPropertyType::Uint32,
+            Self::Uint32x2(_) => UniformPropertyType::Uint32x2,
+            Self::Uint32x3(_) => UniformPropertyType::Uint32x3,
+            Self::Uint32x4(_) => UniformPropertyType::Uint32x4,
+            Self::Sint32(_) => UniformPropertyType::Sint32,
+            Self::Sint32x2(_) => UniformPropertyType::Sint32x2,
+            Self::Sint32x3(_) => UniformPropertyType::Sint32x3,
+            Self::Sint32x4(_) => UniformPropertyType::Sint32x4,
+            Self::Mat2x2(_) => UniformPropertyType::Mat2x2,
+            Self::Mat3x3(_) => UniformPropertyType::Mat3x3,
+            Self::Mat4x4(_) => UniformPropertyType::Mat4x4,
+        }
+    }
+}
+
+impl From<f32> for UniformPropertyValue {
+    fn from(value: f32) -> Self {
+        Self::Float32([value])
+    }
+}
+
+impl From<[f32; 2]> for UniformPropertyValue {
+    fn from(value: [f32; 2]) -> Self {
+        Self::Float32x2(value)
+    }
+}
+
+impl From<[f32; 3]> for UniformPropertyValue {
+    fn from(value: [f32; 3]) -> Self {
+        Self::Float32x3(value)
+    }
+}
+
+impl From<[f32; 4]> for UniformPropertyValue {
+    fn from(value: [f32; 4]) -> Self {
+        Self::Float32x4(value)
+    }
+}
+
+impl From<Vec2> for UniformPropertyValue {
+    fn from(value: Vec2) -> Self {
+        Self::Float32x2([value.x, value.y])
+    }
+}
+
+impl From<Vec3> for UniformPropertyValue {
+    fn from(value: Vec3) -> Self {
+        Self::Float32x3([value.x, value.y, value.z])
+    }
+}
+
+impl From<Vec4> for UniformPropertyValue {
+    fn from(value: Vec4) -> Self {
+        Self::Float32x4([value.x, value.y, value.z, value.w])
+    }
+}
+
+impl From<Mat4> for UniformPropertyValue {
+    fn from(value: Mat4) -> Self {
+        // The elements are uploaded as they are, same as the other matrices, e.g. the camera matrix.
+        Self::Mat4x4(value.elements)
+    }
+}
+
+/// A CPU shadow of a uniform buffer. It tracks the range written since the last flush.
+#[derive(Debug, Clone)]
+pub struct UniformBlock {
+    data: Vec<u8>,
+    dirty_range: Option<Range<usize>>,
+}
+
+impl UniformBlock {
+    /// Creates a zero-filled block. The whole block is dirty, so that it is uploaded at least once.
+    pub fn new(size: BufferAddress) -> Self {
+        let size = size as usize;
+
+        Self {
+            data: vec![0; size],
+            dirty_range: Some(0..size),
+        }
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Writes the value at the given offset, laying out matrix columns with their padding.
+    pub fn write(&mut self, offset: BufferAddress, value: &UniformPropertyValue) {
+        let offset = offset as usize;
+        let bytes = value.as_bytes();
+        let (columns, stride) = value.ty().columns();
+        let column_size = bytes.len() / columns;
+        let end = offset + stride * (columns - 1) + column_size;
+
+        for (index, column) in bytes.chunks_exact(column_size).enumerate() {
+            let column_offset = offset + stride * index;
+            self.data[column_offset..column_offset + column_size].copy_from_slice(column);
+        }
+
+        self.dirty_range = Some(match self.dirty_range.take() {
+            Some(range) => range.start.min(offset)..range.end.max(end),
+            None => offset..end,
+        });
+    }
+
+    /// Returns the range written since the last call, and clears it.
+    pub fn take_dirty_range(&mut self) -> Option<Range<usize>> {
+        self.dirty_range.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gfx::reflect_uniform_members;
+    use naga::front::wgsl::parse_str;
+
+    const SOURCE: &str = r#"
+struct MaterialParams {
+    roughness: f32,
+    tint: vec3<f32>,
+    metallic: f32,
+    normal_matrix: mat3x3<f32>,
+    model: mat4x4<f32>,
+    flags: u32,
+}
+
+@group(1) @binding(0) var<uniform> material_params: MaterialParams;
+"#;
+
+    #[test]
+    fn reflect_uniform_member_offsets() {
+        let module = parse_str(SOURCE).unwrap();
+        let (_, global) = module.global_variables.iter().next().unwrap();
+        let members = reflect_uniform_members(&module, &module.types[global.ty]);
+        let layout = Vec::from_iter(
+            members
+                .iter()
+                .map(|member| (member.name.as_str(), member.offset, member.ty)),
+        );
+
+        assert_eq!(
+            layout,
+            vec![
+                ("roughness", 0, UniformPropertyType::Float32),
+                ("tint", 16, UniformPropertyType::Float32x3),
+                ("metallic", 28, UniformPropertyType::Float32),
+                ("normal_matrix", 32, UniformPropertyType::Mat3x3),
+                ("model", 80, UniformPropertyType::Mat4x4),
+                ("flags", 144, UniformPropertyType::Uint32),
+            ]
+        );
+    }
+
+    #[test]
+    fn write_pads_matrix_columns() {
+        let mut block = UniformBlock::new(160);
+        assert_eq!(block.take_dirty_range(), Some(0..160));
+
+        block.write(28, &UniformPropertyValue::from(0.5));
+        block.write(
+            32,
+            &UniformPropertyValue::Mat3x3([1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0]),
+        );
+        assert_eq!(block.take_dirty_range(), Some(28..76));
+        assert_eq!(block.take_dirty_range(), None);
+
+        let floats = Vec::from_iter(
+            block.data()[28..80]
+                .chunks_exact(4)
+                .map(|bytes| f32::from_ne_bytes(bytes.try_into().unwrap())),
+        );
+        assert_eq!(
+            floats,
+            vec![0.5, 1.0, 2.0, 3.0, 0.0, 4.0, 5.0, 6.0, 0.0, 7.0, 8.0, 9.0, 0.0]
+        );
+    }
+}
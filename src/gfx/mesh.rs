@@ -1,7 +1,394 @@
+use super::GenericBufferAllocation;
+use crate::math::{Vec2, Vec3, AABB};
+use asset::{AssetCategory, AssetUsage};
 use codegen::Handle;
+use parking_lot::Mutex;
+#[cfg(feature = "assimp")]
 use russimp::mesh::Mesh as RussimpMesh;
+use std::{
+    mem::size_of,
+    sync::{mpsc, Arc, OnceLock, Weak},
+};
+use thiserror::Error;
+use wgpu::{
+    util::{BufferInitDescriptor, DeviceExt},
+    Buffer, BufferAsyncError, BufferDescriptor, BufferSize, BufferUsages, CommandEncoderDescriptor,
+    Device, Maintain, MapMode, Queue,
+};
+use zerocopy::AsBytes;
+
+/// The number of floats of a vertex in the vertex buffer of a mesh: the position, the normal and the uv.
+const VERTEX_FLOATS: usize = 3 + 3 + 2;
+
+/// What a mesh does with its CPU data once it has been uploaded to the GPU. The data is kept until the upload
+/// regardless, and the GPU copy can always be [read back](Mesh::readback).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MeshCpuDataPolicy {
+    /// Keeps the data, e.g. for picking, baking or editing.
+    #[default]
+    Retain,
+    /// Drops the data right after the upload.
+    Drop,
+    /// Keeps the data for the given number of frames after the upload, e.g. for the systems that read new
+    /// meshes once.
+    DropAfterFrames(u32),
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MeshPrimitive {
+    /// Every three indices form a triangle.
+    #[default]
+    Triangles,
+    /// Every two indices form a line segment, drawn with a material of
+    /// [`PrimitiveTopology::LineList`](wgpu::PrimitiveTopology::LineList).
+    Lines,
+}
+
+impl MeshPrimitive {
+    pub fn index_count(self) -> usize {
+        match self {
+            MeshPrimitive::Triangles => 3,
+            MeshPrimitive::Lines => 2,
+        }
+    }
+}
+
+/// The vertices of a mesh on the CPU. The normals and uvs are per vertex, like the positions.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MeshCpuData {
+    pub positions: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+    pub uvs: Vec<Vec2>,
+    pub indices: Vec<u32>,
+    pub primitive: MeshPrimitive,
+}
+
+impl MeshCpuData {
+    pub fn positions(&self) -> &[Vec3] {
+        &self.positions
+    }
+
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    /// Returns the indices of each triangle or line segment.
+    pub fn faces(&self) -> std::slice::ChunksExact<'_, u32> {
+        self.indices.chunks_exact(self.primitive.index_count())
+    }
+
+    /// Returns the bytes that the data takes.
+    pub fn byte_size(&self) -> u64 {
+        (self.positions.len() * size_of::<Vec3>()
+            + self.normals.len() * size_of::<Vec3>()
+            + self.uvs.len() * size_of::<Vec2>()
+            + self.indices.len() * size_of::<u32>()) as u64
+    }
+
+    /// Returns the vertices of the vertex buffer, one per index.
+    fn interleaved(&self) -> Vec<f32> {
+        let mut vertices = Vec::with_capacity(self.indices.len() * VERTEX_FLOATS);
+
+        for &index in &self.indices {
+            let index = index as usize;
+            let position = self.positions[index];
+            let normal = self.normals.get(index).copied().unwrap_or_default();
+            let uv = self.uvs.get(index).copied().unwrap_or_default();
+            vertices.extend([
+                position.x, position.y, position.z, normal.x, normal.y, normal.z, uv.x, uv.y,
+            ]);
+        }
+
+        vertices
+    }
+}
+
+/// Converts the faces of the primitive of the first face; faces of other sizes are skipped. Only the first uv
+/// channel is kept.
+#[cfg(feature = "assimp")]
+impl From<RussimpMesh> for MeshCpuData {
+    fn from(mesh: RussimpMesh) -> Self {
+        let to_vec3 = |vector: &russimp::Vector3D| Vec3::new(vector.x, vector.y, vector.z);
+        let primitive = match mesh.faces.first() {
+            Some(face) if face.0.len() == 2 => MeshPrimitive::Lines,
+            _ => MeshPrimitive::Triangles,
+        };
+        let uvs = match mesh.texture_coords.first() {
+            Some(Some(uvs)) => Vec::from_iter(uvs.iter().map(|uv| Vec2::new(uv.x, uv.y))),
+            _ => Vec::new(),
+        };
+
+        Self {
+            positions: Vec::from_iter(mesh.vertices.iter().map(to_vec3)),
+            normals: Vec::from_iter(mesh.normals.iter().map(to_vec3)),
+            uvs,
+            indices: Vec::from_iter(
+                mesh.faces
+                    .iter()
+                    .filter(|face| face.0.len() == primitive.index_count())
+                    .flat_map(|face| face.0.iter().copied()),
+            ),
+            primitive,
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum MeshReadbackError {
+    #[error("the mesh has not been uploaded")]
+    NotUploaded,
+    #[error("failed to map the staging buffer: {0}")]
+    Map(#[from] BufferAsyncError),
+}
+
+/// The CPU data of a mesh, accounted for in the [`Mesh`](AssetCategory::Mesh) asset usage while retained.
+type CpuDataSlot = Mutex<Option<(Arc<MeshCpuData>, AssetUsage)>>;
+
+struct MeshGpuData {
+    vertex_buffer: GenericBufferAllocation<Buffer>,
+    vertex_count: u32,
+}
 
 #[derive(Handle)]
 pub struct Mesh {
-    pub data: RussimpMesh,
+    cpu_data: Arc<CpuDataSlot>,
+    policy: MeshCpuDataPolicy,
+    primitive: MeshPrimitive,
+    gpu_data: OnceLock<Option<MeshGpuData>>,
+    local_aabb: Option<AABB>,
+}
+
+impl Mesh {
+    /// Creates a mesh that retains its CPU data.
+    pub fn new(data: impl Into<MeshCpuData>) -> Self {
+        Self::with_policy(data, MeshCpuDataPolicy::Retain)
+    }
+
+    pub fn with_policy(data: impl Into<MeshCpuData>, policy: MeshCpuDataPolicy) -> Self {
+        let data = data.into();
+        let local_aabb = AABB::from_points(data.positions.iter().copied());
+        let usage = AssetUsage::new(AssetCategory::Mesh, data.byte_size());
+
+        Self {
+            primitive: data.primitive,
+            cpu_data: Arc::new(Mutex::new(Some((Arc::new(data), usage)))),
+            policy,
+            gpu_data: OnceLock::new(),
+            local_aabb,
+        }
+    }
+
+    pub fn policy(&self) -> MeshCpuDataPolicy {
+        self.policy
+    }
+
+    pub fn primitive(&self) -> MeshPrimitive {
+        self.primitive
+    }
+
+    /// Returns the CPU data, or `None` if it has been dropped by the [policy](MeshCpuDataPolicy). Systems that
+    /// need the triangles may fall back to the [bounds](Self::local_aabb) then; precise picking does not hit the
+    /// mesh at all.
+    pub fn cpu_data(&self) -> Option<Arc<MeshCpuData>> {
+        self.cpu_data.lock().as_ref().map(|(data, _)| data.clone())
+    }
+
+    /// Returns the local-space AABB of the vertices, or `None` if the mesh has no vertex. It is kept after the
+    /// CPU data is dropped.
+    pub fn local_aabb(&self) -> Option<AABB> {
+        self.local_aabb
+    }
+
+    /// Returns the vertex buffer, uploading the mesh on the first call. Each vertex is a position, a normal and
+    /// a uv, with a vertex per index. Returns `None` if the mesh has no vertex.
+    pub fn vertex_buffer(&self, device: &Device) -> Option<(GenericBufferAllocation<Buffer>, u32)> {
+        self.gpu_data
+            .get_or_init(|| self.upload(device))
+            .as_ref()
+            .map(|gpu_data| (gpu_data.vertex_buffer.clone(), gpu_data.vertex_count))
+    }
+
+    fn upload(&self, device: &Device) -> Option<MeshGpuData> {
+        let data = self.cpu_data()?;
+        let vertices = data.interleaved();
+        let size = BufferSize::new((vertices.len() * size_of::<f32>()) as u64)?;
+        let vertex_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: None,
+            contents: vertices.as_bytes(),
+            usage: BufferUsages::VERTEX | BufferUsages::COPY_SRC,
+        });
+
+        self.apply_policy();
+
+        Some(MeshGpuData {
+            vertex_buffer: GenericBufferAllocation::new(vertex_buffer, 0, size),
+            vertex_count: (vertices.len() / VERTEX_FLOATS) as u32,
+        })
+    }
+
+    /// Drops the CPU data now or schedules it to be dropped, once the mesh has been uploaded.
+    fn apply_policy(&self) {
+        match self.policy {
+            MeshCpuDataPolicy::Retain => {}
+            MeshCpuDataPolicy::Drop => *self.cpu_data.lock() = None,
+            MeshCpuDataPolicy::DropAfterFrames(frames) => {
+                EXPIRING_CPU_DATA.lock().push(ExpiringCpuData {
+                    slot: Arc::downgrade(&self.cpu_data),
+                    frames,
+                })
+            }
+        }
+    }
+
+    /// Returns the CPU data, reading it back from the vertex buffer if it has been dropped, e.g. for an editor
+    /// operation. The read back data has a vertex per index. Blocks until the GPU has copied the buffer.
+    pub fn readback(
+        &self,
+        device: &Device,
+        queue: &Queue,
+    ) -> Result<MeshCpuData, MeshReadbackError> {
+        if let Some(data) = self.cpu_data() {
+            return Ok(MeshCpuData::clone(&data));
+        }
+
+        let gpu_data = match self.gpu_data.get() {
+            Some(Some(gpu_data)) => gpu_data,
+            _ => return Err(MeshReadbackError::NotUploaded),
+        };
+        let size = gpu_data.vertex_buffer.size().get();
+        let staging_buffer = device.create_buffer(&BufferDescriptor {
+            label: None,
+            size,
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor { label: None });
+        encoder.copy_buffer_to_buffer(
+            gpu_data.vertex_buffer.buffer(),
+            gpu_data.vertex_buffer.offset(),
+            &staging_buffer,
+            0,
+            size,
+        );
+        queue.submit(Some(encoder.finish()));
+
+        let (sender, receiver) = mpsc::channel();
+        staging_buffer
+            .slice(..)
+            .map_async(MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+        device.poll(Maintain::Wait);
+        receiver
+            .recv()
+            .unwrap_or(Err(BufferAsyncError))
+            .map_err(MeshReadbackError::Map)?;
+
+        let data = {
+            let bytes = staging_buffer.slice(..).get_mapped_range();
+            let vertices = Vec::from_iter(
+                bytes
+                    .chunks_exact(size_of::<f32>())
+                    .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap())),
+            );
+            deinterleave(&vertices, self.primitive)
+        };
+        staging_buffer.unmap();
+
+        Ok(data)
+    }
+}
+
+/// Splits the vertices of a vertex buffer into CPU data, with an index per vertex.
+fn deinterleave(vertices: &[f32], primitive: MeshPrimitive) -> MeshCpuData {
+    let mut data = MeshCpuData {
+        primitive,
+        ..Default::default()
+    };
+
+    for (index, vertex) in vertices.chunks_exact(VERTEX_FLOATS).enumerate() {
+        data.positions
+            .push(Vec3::new(vertex[0], vertex[1], vertex[2]));
+        data.normals
+            .push(Vec3::new(vertex[3], vertex[4], vertex[5]));
+        data.uvs.push(Vec2::new(vertex[6], vertex[7]));
+        data.indices.push(index as u32);
+    }
+
+    data
+}
+
+struct ExpiringCpuData {
+    slot: Weak<CpuDataSlot>,
+    frames: u32,
+}
+
+/// The CPU data of the meshes with [`MeshCpuDataPolicy::DropAfterFrames`] that has not been dropped yet.
+static EXPIRING_CPU_DATA: Mutex<Vec<ExpiringCpuData>> = parking_lot::const_mutex(Vec::new());
+
+/// Counts a frame down for the CPU data scheduled to be dropped, and drops the data whose frames have passed.
+/// The engine calls it once per frame.
+pub(crate) fn expire_mesh_cpu_data() {
+    EXPIRING_CPU_DATA.lock().retain_mut(|expiring| {
+        let slot = if let Some(slot) = expiring.slot.upgrade() {
+            slot
+        } else {
+            return false;
+        };
+
+        if expiring.frames == 0 {
+            *slot.lock() = None;
+            return false;
+        }
+
+        expiring.frames -= 1;
+        true
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cpu_data_is_dropped_after_the_frames() {
+        let mesh = Mesh::with_policy(
+            Mesh::quad().cpu_data().unwrap().as_ref().clone(),
+            MeshCpuDataPolicy::DropAfterFrames(2),
+        );
+        let aabb = mesh.local_aabb();
+
+        // Nothing is dropped until the mesh is uploaded.
+        expire_mesh_cpu_data();
+        assert!(mesh.cpu_data().is_some());
+
+        mesh.apply_policy();
+        expire_mesh_cpu_data();
+        expire_mesh_cpu_data();
+        assert_eq!(mesh.cpu_data().unwrap().indices().len(), 6);
+
+        expire_mesh_cpu_data();
+        assert!(mesh.cpu_data().is_none());
+        assert_eq!(mesh.local_aabb(), aabb);
+
+        let mesh = Mesh::with_policy(MeshCpuData::default(), MeshCpuDataPolicy::Drop);
+        mesh.apply_policy();
+        assert!(mesh.cpu_data().is_none());
+    }
+
+    #[test]
+    fn readback_restores_the_vertices() {
+        let data = Mesh::quad().cpu_data().unwrap().as_ref().clone();
+        let read_back = deinterleave(&data.interleaved(), data.primitive);
+
+        assert_eq!(read_back.indices.len(), data.indices.len());
+
+        for (read_back_index, &index) in data.indices.iter().enumerate() {
+            let index = index as usize;
+            assert_eq!(read_back.positions[read_back_index], data.positions[index]);
+            assert_eq!(read_back.normals[read_back_index], data.normals[index]);
+            assert_eq!(read_back.uvs[read_back_index], data.uvs[index]);
+        }
+    }
 }
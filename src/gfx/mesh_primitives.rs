@@ -0,0 +1,435 @@
+use super::{Mesh, MeshCpuData, MeshPrimitive};
+use crate::math::{Vec2, Vec3};
+use asset::assets::{ModelSource, VertexAttributeKind, VertexIndexType};
+use std::f32::consts::{FRAC_PI_2, PI};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum MeshFromSourceError {
+    #[error("sub mesh {0} not found")]
+    SubMeshNotFound(usize),
+    #[error("sub mesh has no position attribute")]
+    NoPosition,
+    #[error("vertex buffer is too small")]
+    InvalidVertexBuffer,
+    #[error("index buffer is invalid")]
+    InvalidIndexBuffer,
+}
+
+/// Generated meshes use a right-handed, y-up space. Triangles are counter-clockwise when seen
+/// from the side the normals point to, and the origin of the uv is at the top-left corner.
+impl Mesh {
+    /// Creates a 1x1 quad on the XY plane, facing +Z.
+    pub fn quad() -> Self {
+        let mut geometry = MeshGeometry::default();
+        geometry.push_face(Vec3::BACKWARD, Vec3::RIGHT, Vec3::UP, 0.5);
+        geometry.into_mesh()
+    }
+
+    /// Creates a cube centered at the origin.
+    pub fn cube(size: f32) -> Self {
+        let half = size * 0.5;
+        let mut geometry = MeshGeometry::default();
+
+        for (normal, right, up) in [
+            (Vec3::BACKWARD, Vec3::RIGHT, Vec3::UP),
+            (Vec3::FORWARD, Vec3::LEFT, Vec3::UP),
+            (Vec3::RIGHT, Vec3::FORWARD, Vec3::UP),
+            (Vec3::LEFT, Vec3::BACKWARD, Vec3::UP),
+            (Vec3::UP, Vec3::RIGHT, Vec3::FORWARD),
+            (Vec3::DOWN, Vec3::RIGHT, Vec3::BACKWARD),
+        ] {
+            geometry.push_face(normal, right, up, half);
+        }
+
+        geometry.into_mesh()
+    }
+
+    /// Creates a square plane on the XZ plane, facing +Y.
+    /// Each side is divided into the given number of cells; at least 1.
+    pub fn plane(size: f32, subdivisions: u32) -> Self {
+        let subdivisions = subdivisions.max(1);
+        let mut geometry = MeshGeometry::default();
+
+        for row in 0..=subdivisions {
+            for column in 0..=subdivisions {
+                let uv = Vec2::new(
+                    column as f32 / subdivisions as f32,
+                    row as f32 / subdivisions as f32,
+                );
+                geometry.push_vertex(
+                    Vec3::new((uv.x - 0.5) * size, 0.0, (uv.y - 0.5) * size),
+                    Vec3::UP,
+                    uv,
+                );
+            }
+        }
+
+        geometry.push_grid(0, subdivisions, subdivisions, false, false);
+        geometry.into_mesh()
+    }
+
+    /// Creates a sphere centered at the origin.
+    /// There must be at least 2 rings and 3 sectors; smaller values are raised to the minimum.
+    pub fn uv_sphere(radius: f32, rings: u32, sectors: u32) -> Self {
+        let rings = rings.max(2);
+        let sectors = sectors.max(3);
+        let mut geometry = MeshGeometry::default();
+
+        for ring in 0..=rings {
+            let v = ring as f32 / rings as f32;
+            geometry.push_latitude(radius, v * PI, 0.0, v, sectors);
+        }
+
+        geometry.push_grid(0, sectors, rings, true, true);
+        geometry.into_mesh()
+    }
+
+    /// Creates a capsule centered at the origin, along the Y axis.
+    /// The height includes the hemispheres, so it is at least twice the radius.
+    /// Each hemisphere has the given number of rings; at least 1. There must be at least 3 sectors.
+    pub fn capsule(radius: f32, height: f32, rings: u32, sectors: u32) -> Self {
+        let rings = rings.max(1);
+        let sectors = sectors.max(3);
+        let half_cylinder = (height * 0.5 - radius).max(0.0);
+        let total_height = 2.0 * (half_cylinder + radius);
+        let mut geometry = MeshGeometry::default();
+
+        // The equator is duplicated, so that the cylinder lies between the hemispheres.
+        for (offset, phi_base) in [(half_cylinder, 0.0), (-half_cylinder, FRAC_PI_2)] {
+            for ring in 0..=rings {
+                let phi = phi_base + ring as f32 / rings as f32 * FRAC_PI_2;
+                let y = radius * phi.cos() + offset;
+                let v = (total_height * 0.5 - y) / total_height;
+                geometry.push_latitude(radius, phi, offset, v, sectors);
+            }
+        }
+
+        geometry.push_grid(0, sectors, 2 * rings + 1, true, true);
+        geometry.into_mesh()
+    }
+
+    /// Creates a mesh of line segments, e.g. for debug drawing. Its faces are the segments, so it must be
+    /// drawn with a material of [`PrimitiveTopology::LineList`](wgpu::PrimitiveTopology::LineList).
+    pub fn lines(segments: impl IntoIterator<Item = (Vec3, Vec3)>) -> Self {
+        let mut geometry = MeshGeometry::default();
+
+        for (start, end) in segments {
+            let base = geometry.positions.len() as u32;
+            geometry.push_vertex(start, Vec3::ZERO, Vec2::ZERO);
+            geometry.push_vertex(end, Vec3::ZERO, Vec2::ZERO);
+            geometry.indices.extend([base, base + 1]);
+        }
+
+        geometry.into_mesh_of(MeshPrimitive::Lines)
+    }
+
+    /// Creates a mesh from a sub mesh of the given model.
+    /// Missing normals and uvs are filled with zeros.
+    pub fn from_model_source(
+        source: &ModelSource,
+        sub_mesh_index: usize,
+    ) -> Result<Self, MeshFromSourceError> {
+        let sub_mesh = source
+            .meshes
+            .get(sub_mesh_index)
+            .ok_or(MeshFromSourceError::SubMeshNotFound(sub_mesh_index))?;
+        let vertex_count = sub_mesh.vertex_count as usize;
+        let stride = sub_mesh
+            .vertex_buffer
+            .len()
+            .checked_div(vertex_count)
+            .unwrap_or(0);
+
+        let read_attribute = |kind: VertexAttributeKind, components: usize| {
+            let attribute = sub_mesh
+                .vertex_attributes
+                .iter()
+                .find(|attribute| attribute.kind == kind)?;
+            let offset = attribute.offset as usize;
+
+            if stride < offset + components * 4 {
+                return Some(Err(MeshFromSourceError::InvalidVertexBuffer));
+            }
+
+            Some(Ok(Vec::from_iter((0..vertex_count).map(|index| {
+                let base = index * stride + offset;
+                let mut value = [0f32; 3];
+
+                for (component, value) in value.iter_mut().enumerate().take(components) {
+                    let at = base + component * 4;
+                    *value =
+                        f32::from_le_bytes(sub_mesh.vertex_buffer[at..at + 4].try_into().unwrap());
+                }
+
+                Vec3::new(value[0], value[1], value[2])
+            }))))
+        };
+
+        let positions = read_attribute(VertexAttributeKind::Position, 3)
+            .ok_or(MeshFromSourceError::NoPosition)??;
+        let normals = read_attribute(VertexAttributeKind::Normal, 3)
+            .transpose()?
+            .unwrap_or_else(|| vec![Vec3::ZERO; vertex_count]);
+        let uvs = read_attribute(VertexAttributeKind::TexCoord { index: 0 }, 2)
+            .transpose()?
+            .map(|uvs| Vec::from_iter(uvs.into_iter().map(|uv| Vec2::new(uv.x, uv.y))))
+            .unwrap_or_else(|| vec![Vec2::ZERO; vertex_count]);
+
+        let index_size = match sub_mesh.index_type {
+            VertexIndexType::U8 => 1,
+            VertexIndexType::U16 => 2,
+            VertexIndexType::U32 => 4,
+        };
+        let indices = Vec::from_iter(sub_mesh.index_buffer.chunks_exact(index_size).map(|bytes| {
+            match sub_mesh.index_type {
+                VertexIndexType::U8 => bytes[0] as u32,
+                VertexIndexType::U16 => u16::from_le_bytes([bytes[0], bytes[1]]) as u32,
+                VertexIndexType::U32 => u32::from_le_bytes(bytes.try_into().unwrap()),
+            }
+        }));
+
+        if sub_mesh.index_buffer.len() % (index_size * 3) != 0
+            || indices.iter().any(|&index| vertex_count <= index as usize)
+        {
+            return Err(MeshFromSourceError::InvalidIndexBuffer);
+        }
+
+        Ok(Self::new(MeshCpuData {
+            positions,
+            normals,
+            uvs,
+            indices,
+            primitive: MeshPrimitive::Triangles,
+        }))
+    }
+}
+
+#[derive(Default)]
+struct MeshGeometry {
+    positions: Vec<Vec3>,
+    normals: Vec<Vec3>,
+    uvs: Vec<Vec2>,
+    indices: Vec<u32>,
+}
+
+impl MeshGeometry {
+    fn push_vertex(&mut self, position: Vec3, normal: Vec3, uv: Vec2) {
+        self.positions.push(position);
+        self.normals.push(normal);
+        self.uvs.push(uv);
+    }
+
+    /// Pushes a square face. The right and up directions must satisfy `right x up = normal`.
+    fn push_face(&mut self, normal: Vec3, right: Vec3, up: Vec3, half: f32) {
+        let base = self.positions.len() as u32;
+        let center = normal * half;
+
+        for (x, y) in [(-1.0, 1.0), (1.0, 1.0), (-1.0, -1.0), (1.0, -1.0)] {
+            self.push_vertex(
+                center + right * (x * half) + up * (y * half),
+                normal,
+                Vec2::new((x + 1.0) * 0.5, (1.0 - y) * 0.5),
+            );
+        }
+
+        self.push_grid(base, 1, 1, false, false);
+    }
+
+    /// Pushes a ring of vertices around the Y axis, at the given polar angle from +Y.
+    /// The first and the last vertices are at the same position, with different uvs.
+    fn push_latitude(&mut self, radius: f32, phi: f32, offset: f32, v: f32, sectors: u32) {
+        let (sin_phi, cos_phi) = phi.sin_cos();
+
+        for sector in 0..=sectors {
+            let u = sector as f32 / sectors as f32;
+            let (sin_theta, cos_theta) = (u * 2.0 * PI).sin_cos();
+            let normal = Vec3::new(sin_phi * sin_theta, cos_phi, sin_phi * cos_theta);
+            self.push_vertex(
+                normal * radius + Vec3::new(0.0, offset, 0.0),
+                normal,
+                Vec2::new(u, v),
+            );
+        }
+    }
+
+    /// Connects a grid of `(columns + 1) x (rows + 1)` vertices, stored row by row from the top-left.
+    /// Rows that collapse into a single point at the top or the bottom emit a triangle per cell.
+    fn push_grid(&mut self, base: u32, columns: u32, rows: u32, top_pole: bool, bottom_pole: bool) {
+        let stride = columns + 1;
+
+        for row in 0..rows {
+            for column in 0..columns {
+                let top_left = base + row * stride + column;
+                let bottom_left = top_left + stride;
+                let bottom_right = bottom_left + 1;
+                let top_right = top_left + 1;
+
+                if !(bottom_pole && row == rows - 1) {
+                    self.indices.extend([top_left, bottom_left, bottom_right]);
+                }
+
+                if !(top_pole && row == 0) {
+                    self.indices.extend([top_left, bottom_right, top_right]);
+                }
+            }
+        }
+    }
+
+    fn into_mesh(self) -> Mesh {
+        self.into_mesh_of(MeshPrimitive::Triangles)
+    }
+
+    fn into_mesh_of(self, primitive: MeshPrimitive) -> Mesh {
+        Mesh::new(MeshCpuData {
+            positions: self.positions,
+            normals: self.normals,
+            uvs: self.uvs,
+            indices: self.indices,
+            primitive,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use asset::assets::{MeshAABB, MeshSource, VertexAttribute};
+
+    fn counts(mesh: &Mesh) -> (usize, usize) {
+        let data = mesh.cpu_data().unwrap();
+        (data.positions.len(), data.indices.len())
+    }
+
+    /// Checks that every non-degenerate triangle is counter-clockwise when seen from its normals.
+    fn assert_winding(mesh: &Mesh) {
+        let data = mesh.cpu_data().unwrap();
+
+        for face in data.faces() {
+            let [a, b, c] = [0, 1, 2].map(|i| face[i] as usize);
+            let [pa, pb, pc] = [a, b, c].map(|i| data.positions[i]);
+            let cross = Vec3::cross(pb - pa, pc - pa);
+
+            if cross.len() < 1e-6 {
+                continue;
+            }
+
+            let normal = data.normals[a] + data.normals[b] + data.normals[c];
+            assert!(0.0 < Vec3::dot(cross, normal));
+        }
+    }
+
+    #[test]
+    fn primitive_counts() {
+        assert_eq!(counts(&Mesh::quad()), (4, 6));
+        assert_eq!(counts(&Mesh::cube(1.0)), (24, 36));
+        assert_eq!(counts(&Mesh::plane(1.0, 4)), (25, 96));
+        assert_eq!(counts(&Mesh::uv_sphere(1.0, 8, 16)), (9 * 17, 6 * 16 * 7));
+        assert_eq!(
+            counts(&Mesh::capsule(0.5, 2.0, 4, 16)),
+            (10 * 17, 6 * 16 * 8)
+        );
+    }
+
+    #[test]
+    fn lines_have_a_face_per_segment() {
+        let mesh = Mesh::lines([
+            (Vec3::ZERO, Vec3::RIGHT),
+            (Vec3::ZERO, Vec3::new(0.0, 2.0, 0.0)),
+        ]);
+        let data = mesh.cpu_data().unwrap();
+        assert_eq!(data.positions.len(), 4);
+        assert_eq!(data.primitive, MeshPrimitive::Lines);
+        assert_eq!(
+            Vec::from_iter(data.faces().map(|face| face.to_vec())),
+            vec![vec![0, 1], vec![2, 3]]
+        );
+
+        let aabb = mesh.local_aabb().unwrap();
+        assert_eq!(aabb.max, Vec3::new(1.0, 2.0, 0.0));
+    }
+
+    #[test]
+    fn primitive_winding_matches_normals() {
+        for mesh in [
+            Mesh::quad(),
+            Mesh::cube(2.0),
+            Mesh::plane(2.0, 3),
+            Mesh::uv_sphere(1.0, 6, 8),
+            Mesh::capsule(0.5, 3.0, 3, 8),
+        ] {
+            assert_winding(&mesh);
+        }
+    }
+
+    #[test]
+    fn primitive_aabb() {
+        let aabb = Mesh::cube(2.0).local_aabb().unwrap();
+        assert_eq!(aabb.min, Vec3::new(-1.0, -1.0, -1.0));
+        assert_eq!(aabb.max, Vec3::new(1.0, 1.0, 1.0));
+
+        let aabb = Mesh::capsule(0.5, 3.0, 3, 8).local_aabb().unwrap();
+        assert!((aabb.min.y + 1.5).abs() < 1e-5);
+        assert!((aabb.max.y - 1.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn from_model_source_reads_sub_mesh() {
+        let mut vertex_buffer = Vec::new();
+
+        for vertex in [
+            [0f32, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0],
+            [1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0],
+            [0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+        ] {
+            for value in vertex {
+                vertex_buffer.extend(value.to_le_bytes());
+            }
+        }
+
+        let source = ModelSource {
+            root_node_index: None,
+            nodes: vec![],
+            meshes: vec![MeshSource {
+                index: 0,
+                aabb: MeshAABB {
+                    min: [0.0; 3],
+                    max: [1.0, 1.0, 0.0],
+                },
+                index_type: VertexIndexType::U16,
+                index_buffer: vec![0, 0, 1, 0, 2, 0],
+                vertex_attributes: vec![
+                    VertexAttribute {
+                        offset: 0,
+                        kind: VertexAttributeKind::Position,
+                    },
+                    VertexAttribute {
+                        offset: 12,
+                        kind: VertexAttributeKind::Normal,
+                    },
+                    VertexAttribute {
+                        offset: 24,
+                        kind: VertexAttributeKind::TexCoord { index: 0 },
+                    },
+                ],
+                vertex_buffer,
+                vertex_count: 3,
+                material: None,
+            }],
+        };
+
+        let mesh = Mesh::from_model_source(&source, 0).unwrap();
+        assert_eq!(counts(&mesh), (3, 3));
+        let data = mesh.cpu_data().unwrap();
+        assert_eq!(data.indices, vec![0, 1, 2]);
+        assert_eq!(data.positions[1], Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(data.normals[2], Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(data.uvs[1], Vec2::new(1.0, 1.0));
+        assert_winding(&mesh);
+
+        assert!(matches!(
+            Mesh::from_model_source(&source, 1),
+            Err(MeshFromSourceError::SubMeshNotFound(1))
+        ));
+    }
+}
@@ -1,43 +1,89 @@
 use codegen::Handle;
 use itertools::Itertools;
+use logging::log_warn;
+#[cfg(not(target_arch = "wasm32"))]
+use pollster::FutureExt;
 use std::cell::RefCell;
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    future::Future,
+    pin::pin,
+    task::{Context, Poll, Waker},
+};
 use thiserror::Error;
 use wgpu::{
-    Adapter, Backend, Backends, CompositeAlphaMode, CreateSurfaceError, Device, DeviceDescriptor,
-    DeviceType, Features, Instance, InstanceDescriptor, PresentMode, Queue, RequestDeviceError,
-    Surface, SurfaceConfiguration, TextureFormat, TextureUsages,
+    Adapter, AdapterInfo, CompositeAlphaMode, CreateSurfaceError, Device, DeviceDescriptor,
+    DeviceType, ErrorFilter, Features, Instance, InstanceDescriptor, Limits, PowerPreference,
+    PresentMode, Queue, RequestAdapterOptions, RequestDeviceError, Surface, SurfaceConfiguration,
+    TextureFormat, TextureUsages,
 };
+#[cfg(not(target_arch = "wasm32"))]
+use wgpu::{Backend, Backends};
 use winit::{dpi::PhysicalSize, window::Window};
 
 mod built_in_shader_manager;
+mod bvh;
 mod camera;
 mod color;
 mod depth_stencil;
 mod font;
+mod frame_capture;
+mod frame_graph;
+mod frame_stats;
 mod glyph;
+mod gpu_profiler;
+mod gpu_resource_registry;
 mod material;
 mod mesh;
+mod mesh_primitives;
 mod nine_patch;
+mod particle_emitter;
+mod picking;
+mod reflection_probe;
 mod render_mgr;
 mod renderer;
 mod screen_mgr;
+mod secondary_window;
+mod skybox_renderer;
 mod sprite;
+mod sprite_animation;
+mod standard_material;
+#[cfg(feature = "text-mesh")]
+mod text_mesh;
 mod texture;
+mod viewport_clearer;
 
 pub use built_in_shader_manager::*;
+pub use bvh::*;
 pub use camera::*;
 pub use color::*;
 pub use depth_stencil::*;
 pub use font::*;
+pub use frame_capture::*;
+pub use frame_graph::*;
+pub use frame_stats::*;
 pub use glyph::*;
+pub use gpu_profiler::*;
+pub use gpu_resource_registry::*;
 pub use material::*;
 pub use mesh::*;
+pub use mesh_primitives::*;
 pub use nine_patch::*;
+pub use particle_emitter::*;
+pub use picking::*;
+pub use reflection_probe::*;
 pub use render_mgr::*;
 pub use renderer::*;
 pub use screen_mgr::*;
+pub use secondary_window::*;
+pub use skybox_renderer::*;
 pub use sprite::*;
+pub use sprite_animation::*;
+pub use standard_material::*;
+#[cfg(feature = "text-mesh")]
+pub use text_mesh::*;
 pub use texture::*;
+pub use viewport_clearer::*;
 
 #[derive(Error, Debug)]
 pub enum GfxContextCreationError {
@@ -45,61 +91,239 @@ pub enum GfxContextCreationError {
     CreateSurfaceError(#[from] CreateSurfaceError),
     #[error("no adapter found")]
     AdapterNotFound,
+    #[error("the adapter does not support the requested features: {}", feature_names(*.0))]
+    UnsupportedFeatures(Features),
+    #[error("the adapter does not reach the requested limits: {}", .0.join(", "))]
+    UnsupportedLimits(Vec<String>),
     #[error("failed to obtain device")]
     RequestDeviceError(#[from] RequestDeviceError),
 }
 
+fn feature_names(features: Features) -> String {
+    features.iter_names().map(|(name, _)| name).join(", ")
+}
+
+/// The device features and limits that the application needs on top of the ones the engine requests, e.g. texture
+/// compression or multi draw indirect.
+#[derive(Debug, Default, Clone)]
+pub struct GfxFeatures {
+    /// Features that the device is created with. The context fails to be created if the adapter lacks any of them.
+    pub features: Features,
+    /// Limits that the device is created with instead of the engine's, or `None` to keep the engine's.
+    pub limits: Option<Limits>,
+}
+
+impl GfxFeatures {
+    /// Checks that the adapter supports the requested features and limits, listing the ones it does not.
+    pub fn validate(
+        &self,
+        adapter_features: Features,
+        adapter_limits: &Limits,
+    ) -> Result<(), GfxContextCreationError> {
+        let unsupported = self.features - adapter_features;
+
+        if !unsupported.is_empty() {
+            return Err(GfxContextCreationError::UnsupportedFeatures(unsupported));
+        }
+
+        if let Some(limits) = &self.limits {
+            let mut unsupported = Vec::new();
+            limits.check_limits_with_fail_fn(adapter_limits, false, |name, requested, allowed| {
+                unsupported.push(format!(
+                    "{} (requested {}, allowed {})",
+                    name, requested, allowed
+                ))
+            });
+
+            if !unsupported.is_empty() {
+                return Err(GfxContextCreationError::UnsupportedLimits(unsupported));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A device error captured by [`GfxContext::capture_errors`].
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum GfxError {
+    #[error("gfx out of memory")]
+    OutOfMemory,
+    #[error("gfx validation error: {0}")]
+    Validation(String),
+    #[error("gfx device lost")]
+    DeviceLost,
+}
+
+impl From<wgpu::Error> for GfxError {
+    fn from(err: wgpu::Error) -> Self {
+        match err {
+            wgpu::Error::OutOfMemory { .. } => Self::OutOfMemory,
+            // wgpu reports the operations on a lost device as validation errors caused by the loss.
+            wgpu::Error::Validation {
+                source,
+                description,
+            } if description.contains(DEVICE_LOST_MESSAGE) || is_device_lost(&*source) => {
+                Self::DeviceLost
+            }
+            wgpu::Error::Validation { description, .. } => Self::Validation(description),
+        }
+    }
+}
+
+/// Takes the error of the popped scope without waiting if it has been reported already.
+#[cfg(not(target_arch = "wasm32"))]
+fn poll_error_scope(scope: impl Future<Output = Option<wgpu::Error>>) -> Option<wgpu::Error> {
+    let mut scope = pin!(scope);
+
+    match scope.as_mut().poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(err) => err,
+        Poll::Pending if cfg!(debug_assertions) => scope.block_on(),
+        Poll::Pending => {
+            log_warn!("skipped the device errors of a frame that were not reported in time");
+            None
+        }
+    }
+}
+
+const DEVICE_LOST_MESSAGE: &str = "device is lost";
+
+fn is_device_lost(err: &(dyn std::error::Error + 'static)) -> bool {
+    let mut source = Some(err);
+
+    while let Some(err) = source {
+        if err.to_string().contains(DEVICE_LOST_MESSAGE) {
+            return true;
+        }
+
+        source = err.source();
+    }
+
+    false
+}
+
+/// How the [`GfxContext`] selects the adapter and creates the device.
+#[derive(Debug, Default, Clone)]
+pub struct GfxContextOptions {
+    /// Whether to fall back to a software adapter if no GPU is found, e.g. on CI or remote machines.
+    /// It is slow, and a warning is logged.
+    pub allow_fallback_adapter: bool,
+    /// Device features and limits to request on top of the engine's. The context fails to be created if they are
+    /// not supported.
+    pub features: GfxFeatures,
+}
+
 #[derive(Handle)]
 pub struct GfxContext {
     pub instance: Instance,
+    pub adapter_info: AdapterInfo,
     pub device: Device,
     pub queue: Queue,
-    pub surface: Surface,
+    /// The surface of the window, or `None` for contexts created by [`GfxContext::new_headless`].
+    pub surface: Option<Surface>,
+    /// The configuration of the surface. Headless contexts keep it to describe the format and size of frames.
     pub surface_config: RefCell<SurfaceConfiguration>,
 }
 
 impl GfxContext {
-    pub async fn new(window: &Window) -> Result<Self, GfxContextCreationError> {
+    /// Creates the context for the window. See [`GfxContextOptions`] for the adapter and the device.
+    pub async fn new(
+        window: &Window,
+        options: &GfxContextOptions,
+    ) -> Result<Self, GfxContextCreationError> {
         let instance = Instance::new(InstanceDescriptor::default());
         let surface = unsafe { instance.create_surface(window) }?;
-        let adapters = instance
-            .enumerate_adapters(Backends::all())
-            .collect::<Vec<_>>();
-        let adapter = if let Some(adapter_index) = select_adapter(&surface, &adapters) {
-            &adapters[adapter_index]
+
+        Self::create(instance, Some(surface), window.inner_size(), options).await
+    }
+
+    /// Creates a context without a window, which renders into textures only, e.g. for tests and offscreen tools.
+    pub async fn new_headless(
+        size: PhysicalSize<u32>,
+        options: &GfxContextOptions,
+    ) -> Result<Self, GfxContextCreationError> {
+        let instance = Instance::new(InstanceDescriptor::default());
+
+        Self::create(instance, None, size, options).await
+    }
+
+    async fn create(
+        instance: Instance,
+        surface: Option<Surface>,
+        size: PhysicalSize<u32>,
+        options: &GfxContextOptions,
+    ) -> Result<Self, GfxContextCreationError> {
+        let adapter = match request_adapter(&instance, surface.as_ref()).await {
+            Some(adapter) => adapter,
+            None if options.allow_fallback_adapter => {
+                request_fallback_adapter(&instance, surface.as_ref())
+                    .await
+                    .ok_or(GfxContextCreationError::AdapterNotFound)?
+            }
+            None => return Err(GfxContextCreationError::AdapterNotFound),
+        };
+        let adapter_info = adapter.get_info();
+
+        if adapter_info.device_type == DeviceType::Cpu {
+            log_warn!(
+                "no GPU adapter found; falling back to the software adapter `{}` ({:?}), which is slow",
+                adapter_info.name,
+                adapter_info.backend
+            );
+        }
+
+        let requested = &options.features;
+        requested.validate(adapter.features(), &adapter.limits())?;
+
+        // Fallback adapters may not reach the default limits, so they get the lowest ones the engine runs with.
+        let limits = if let Some(limits) = &requested.limits {
+            limits.clone()
+        } else if cfg!(target_arch = "wasm32") {
+            Limits::downlevel_webgl2_defaults()
+        } else if options.allow_fallback_adapter
+            && !Limits::default().check_limits(&adapter.limits())
+        {
+            Limits::downlevel_defaults()
         } else {
-            return Err(GfxContextCreationError::AdapterNotFound);
+            Limits::default()
         };
 
         let (device, queue) = adapter
             .request_device(
                 &DeviceDescriptor {
                     label: None,
-                    features: Features::CLEAR_TEXTURE,
-                    limits: if cfg!(target_arch = "wasm32") {
-                        wgpu::Limits::downlevel_webgl2_defaults()
-                    } else {
-                        wgpu::Limits::default()
-                    },
+                    // Timestamp queries are optional; GPU profiling is disabled without them.
+                    features: Features::CLEAR_TEXTURE
+                        | requested.features
+                        | (adapter.features() & Features::TIMESTAMP_QUERY),
+                    limits,
                 },
                 None,
             )
             .await?;
 
-        let window_inner_size = window.inner_size();
+        // Frames can only be captured if the surface can be copied from.
+        let capture_usage = match &surface {
+            Some(surface) => surface.get_capabilities(&adapter).usages & TextureUsages::COPY_SRC,
+            None => TextureUsages::COPY_SRC,
+        };
         let surface_config = RefCell::new(SurfaceConfiguration {
-            usage: TextureUsages::RENDER_ATTACHMENT,
+            usage: TextureUsages::RENDER_ATTACHMENT | capture_usage,
             format: TextureFormat::Bgra8Unorm,
-            width: window_inner_size.width,
-            height: window_inner_size.height,
+            width: size.width,
+            height: size.height,
             present_mode: PresentMode::Fifo,
             alpha_mode: CompositeAlphaMode::Auto,
             view_formats: vec![TextureFormat::Bgra8Unorm],
         });
-        surface.configure(&device, &surface_config.borrow());
+
+        if let Some(surface) = &surface {
+            surface.configure(&device, &surface_config.borrow());
+        }
 
         Ok(GfxContext {
             instance,
+            adapter_info,
             device,
             queue,
             surface,
@@ -107,49 +331,428 @@ impl GfxContext {
         })
     }
 
+    /// Runs the given function, capturing device errors raised by the gfx calls in it.
+    /// Without this, device errors are reported to the uncaptured error handler of the device,
+    /// which panics by default. Validation errors take precedence over out of memory errors.
+    ///
+    /// The native backends report the errors of a scope as soon as it is popped, so the scopes are polled without
+    /// waiting. If a backend does not report them right away, debug builds wait for them, while release builds
+    /// skip them rather than stalling the frame.
+    ///
+    /// On the web, the scopes cannot be waited for without blocking the browser, so the errors are logged once
+    /// they are reported instead, and `Ok` is always returned.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn capture_errors<R>(&self, f: impl FnOnce() -> R) -> Result<R, GfxError> {
+        self.device.push_error_scope(ErrorFilter::OutOfMemory);
+        self.device.push_error_scope(ErrorFilter::Validation);
+
+        let r = f();
+
+        // Scopes must be popped in reverse order, even if the first one has an error.
+        let validation_error = poll_error_scope(self.device.pop_error_scope());
+        let out_of_memory_error = poll_error_scope(self.device.pop_error_scope());
+
+        match validation_error.or(out_of_memory_error) {
+            Some(err) => Err(err.into()),
+            None => Ok(r),
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn capture_errors<R>(&self, f: impl FnOnce() -> R) -> Result<R, GfxError> {
+        self.device.push_error_scope(ErrorFilter::OutOfMemory);
+        self.device.push_error_scope(ErrorFilter::Validation);
+
+        let r = f();
+
+        let validation_error = self.device.pop_error_scope();
+        let out_of_memory_error = self.device.pop_error_scope();
+
+        wasm_bindgen_futures::spawn_local(async move {
+            let validation_error = validation_error.await;
+            let out_of_memory_error = out_of_memory_error.await;
+
+            if let Some(err) = validation_error.or(out_of_memory_error) {
+                logging::log_error!("{}", GfxError::from(err));
+            }
+        });
+
+        Ok(r)
+    }
+
     pub fn resize(&self, size: PhysicalSize<u32>) {
         let mut surface_config = self.surface_config.borrow_mut();
         surface_config.width = size.width;
         surface_config.height = size.height;
-        self.surface.configure(&self.device, &surface_config);
+
+        if let Some(surface) = &self.surface {
+            surface.configure(&self.device, &surface_config);
+        }
+    }
+
+    /// Configures the surface again with the current configuration, e.g. after it is lost or outdated.
+    pub fn reconfigure_surface(&self) {
+        if let Some(surface) = &self.surface {
+            surface.configure(&self.device, &self.surface_config.borrow());
+        }
     }
 }
 
-fn select_adapter(surface: &Surface, adapters: impl AsRef<[Adapter]>) -> Option<usize> {
-    let adapters = adapters
+/// Selects the best adapter that can present to the surface, or the best adapter at all without one.
+#[cfg(not(target_arch = "wasm32"))]
+async fn request_adapter(instance: &Instance, surface: Option<&Surface>) -> Option<Adapter> {
+    let mut adapters = instance
+        .enumerate_adapters(Backends::all())
+        .collect::<Vec<_>>();
+    let adapter_index = select_adapter(surface, &adapters)?;
+    Some(adapters.swap_remove(adapter_index))
+}
+
+/// Browsers expose a single adapter, which cannot be enumerated.
+#[cfg(target_arch = "wasm32")]
+async fn request_adapter(instance: &Instance, surface: Option<&Surface>) -> Option<Adapter> {
+    instance
+        .request_adapter(&RequestAdapterOptions {
+            power_preference: PowerPreference::HighPerformance,
+            force_fallback_adapter: false,
+            compatible_surface: surface,
+        })
+        .await
+}
+
+/// Requests the fallback adapter of the platform, which is a software renderer if there is one.
+async fn request_fallback_adapter(
+    instance: &Instance,
+    surface: Option<&Surface>,
+) -> Option<Adapter> {
+    instance
+        .request_adapter(&RequestAdapterOptions {
+            power_preference: PowerPreference::default(),
+            force_fallback_adapter: true,
+            compatible_surface: surface,
+        })
+        .await
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn select_adapter(surface: Option<&Surface>, adapters: impl AsRef<[Adapter]>) -> Option<usize> {
+    let is_compatible = |adapter: &Adapter| {
+        surface.is_none_or(|surface| !surface.get_capabilities(adapter).formats.is_empty())
+    };
+
+    adapters
         .as_ref()
         .iter()
-        .filter(|adapter| !surface.get_capabilities(adapter).formats.is_empty())
-        .collect::<Vec<_>>();
+        .enumerate()
+        .filter(|(_, adapter)| is_compatible(adapter))
+        .max_by_key(|(_, adapter)| adapter_score(&adapter.get_info()))
+        .map(|(index, _)| index)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn adapter_score(info: &AdapterInfo) -> i32 {
+    let device_score = match info.device_type {
+        DeviceType::IntegratedGpu => 10,
+        DeviceType::DiscreteGpu => 20,
+        DeviceType::Cpu => -10,
+        _ => 0,
+    };
+    let backend_score = match info.backend {
+        // The Vulkan is available with other backends simultaneously on some platforms.
+        // Because the dedicated backends are preferred over the Vulkan, we set the score of the Vulkan slightly lower than others.
+        Backend::Metal => 2,
+        Backend::Dx12 => 2,
+        Backend::Vulkan => 1,
+        _ => 0,
+    };
+    device_score + backend_score
+}
+
+/// Creates a headless context for tests, falling back to a software adapter, or returns `None` if the machine has
+/// no adapter at all.
+#[cfg(test)]
+pub(crate) fn test_gfx_ctx(size: PhysicalSize<u32>) -> Option<GfxContextHandle> {
+    let options = GfxContextOptions {
+        allow_fallback_adapter: true,
+        ..Default::default()
+    };
+
+    GfxContext::new_headless(size, &options)
+        .block_on()
+        .ok()
+        .map(GfxContextHandle::new)
+}
+
+/// Reads back the pixels of an [`Rgba8Unorm`](TextureFormat::Rgba8Unorm) texture for tests, row by row. The
+/// texture must be created with [`TextureUsages::COPY_SRC`].
+#[cfg(test)]
+pub(crate) fn read_texture_rgba8(gfx_ctx: &GfxContext, texture: &wgpu::Texture) -> Vec<[u8; 4]> {
+    let (width, height) = (texture.width(), texture.height());
+    let padded_bytes_per_row = (width * 4).div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+        * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    let buffer = gfx_ctx.device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: (padded_bytes_per_row * height) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+    let mut encoder = gfx_ctx.device.create_command_encoder(&Default::default());
+    encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_bytes_per_row),
+                rows_per_image: Some(height),
+            },
+        },
+        texture.size(),
+    );
+    gfx_ctx.queue.submit(std::iter::once(encoder.finish()));
+
+    buffer
+        .slice(..)
+        .map_async(wgpu::MapMode::Read, |result| result.unwrap());
+    gfx_ctx.device.poll(wgpu::Maintain::Wait);
+
+    let bytes = buffer.slice(..).get_mapped_range();
+    bytes
+        .chunks(padded_bytes_per_row as usize)
+        .flat_map(|row| row[..width as usize * 4].chunks(4))
+        .map(|pixel| [pixel[0], pixel[1], pixel[2], pixel[3]])
+        .collect()
+}
+
+/// Draws the triangles, given in normalized device coordinates, in the color over the clear color of a 4x4 target
+/// for tests, and reads back its pixels.
+#[cfg(test)]
+pub(crate) fn render_test_triangles(
+    gfx_ctx: &GfxContext,
+    triangles: &[[[f32; 2]; 3]],
+    color: [f32; 4],
+    clear_color: wgpu::Color,
+    primitive: wgpu::PrimitiveState,
+    blend: Option<wgpu::BlendState>,
+) -> Vec<[u8; 4]> {
+    let device = &gfx_ctx.device;
+    let positions = triangles
+        .iter()
+        .flatten()
+        .map(|[x, y]| format!("vec2<f32>({:?}, {:?})", x, y))
+        .join(", ");
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: None,
+        source: wgpu::ShaderSource::Wgsl(
+            format!(
+                r#"
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {{
+    var positions = array<vec2<f32>, {}>({});
+    return vec4<f32>(positions[index], 0.0, 1.0);
+}}
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {{
+    return vec4<f32>({:?}, {:?}, {:?}, {:?});
+}}
+"#,
+                triangles.len() * 3,
+                positions,
+                color[0],
+                color[1],
+                color[2],
+                color[3]
+            )
+            .into(),
+        ),
+    });
+    let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: None,
+        layout: None,
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: "vs_main",
+            buffers: &[],
+        },
+        primitive,
+        depth_stencil: None,
+        multisample: Default::default(),
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: "fs_main",
+            targets: &[Some(wgpu::ColorTargetState {
+                format: TextureFormat::Rgba8Unorm,
+                blend,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        multiview: None,
+    });
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: None,
+        size: wgpu::Extent3d {
+            width: 4,
+            height: 4,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: TextureFormat::Rgba8Unorm,
+        usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&Default::default());
 
-    if adapters.is_empty() {
-        return None;
+    let mut encoder = device.create_command_encoder(&Default::default());
+    {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: None,
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(clear_color),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+        render_pass.set_pipeline(&pipeline);
+        render_pass.draw(0..triangles.len() as u32 * 3, 0..1);
     }
+    gfx_ctx.queue.submit(std::iter::once(encoder.finish()));
+
+    read_texture_rgba8(gfx_ctx, &texture)
+}
 
-    let mut scores = adapters.iter().map(|_| 0).collect::<Vec<_>>();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    for (index, adapter) in adapters.iter().enumerate() {
-        if surface.get_capabilities(adapter).formats.is_empty() {
-            continue;
-        }
+    #[test]
+    fn gfx_error_from_wgpu_error() {
+        let err = GfxError::from(wgpu::Error::Validation {
+            source: Box::new(std::fmt::Error),
+            description: "buffer binding is out of bounds".to_owned(),
+        });
+        assert!(
+            matches!(err, GfxError::Validation(description) if description == "buffer binding is out of bounds")
+        );
+
+        let err = GfxError::from(wgpu::Error::OutOfMemory {
+            source: Box::new(std::fmt::Error),
+        });
+        assert!(matches!(err, GfxError::OutOfMemory));
+
+        let err = GfxError::from(wgpu::Error::Validation {
+            source: Box::new(std::io::Error::other("Parent device is lost")),
+            description: "Validation Error".to_owned(),
+        });
+        assert!(matches!(err, GfxError::DeviceLost));
+    }
+
+    #[test]
+    fn unsupported_features_are_listed() {
+        let requested = GfxFeatures {
+            features: Features::TEXTURE_COMPRESSION_BC | Features::MULTI_DRAW_INDIRECT,
+            limits: None,
+        };
+        let err = requested
+            .validate(Features::MULTI_DRAW_INDIRECT, &Limits::default())
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            GfxContextCreationError::UnsupportedFeatures(features)
+                if features == Features::TEXTURE_COMPRESSION_BC
+        ));
+        assert_eq!(
+            err.to_string(),
+            "the adapter does not support the requested features: TEXTURE_COMPRESSION_BC"
+        );
 
-        let info = adapter.get_info();
-        let device_score = match info.device_type {
-            DeviceType::IntegratedGpu => 10,
-            DeviceType::DiscreteGpu => 20,
-            DeviceType::Cpu => -10,
-            _ => 0,
+        let requested = GfxFeatures {
+            features: Features::empty(),
+            limits: Some(Limits {
+                max_texture_dimension_2d: 16384,
+                ..Limits::default()
+            }),
         };
-        let backend_score = match info.backend {
-            // The Vulkan is available with other backends simultaneously on some platforms.
-            // Because the dedicated backends are preferred over the Vulkan, we set the score of the Vulkan slightly lower than others.
-            Backend::Metal => 2,
-            Backend::Dx12 => 2,
-            Backend::Vulkan => 1,
-            _ => 0,
+        let err = requested
+            .validate(Features::all(), &Limits::default())
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "the adapter does not reach the requested limits: max_texture_dimension_2d (requested 16384, allowed 8192)"
+        );
+        assert!(requested
+            .validate(Features::empty(), &requested.limits.clone().unwrap())
+            .is_ok());
+    }
+
+    #[test]
+    fn fallback_adapter_is_software_adapter() {
+        let instance = Instance::new(InstanceDescriptor::default());
+        let adapter = match request_fallback_adapter(&instance, None).block_on() {
+            Some(adapter) => adapter,
+            None => {
+                eprintln!("skipped: the platform has no fallback adapter");
+                return;
+            }
         };
-        scores[index] += device_score + backend_score;
+
+        assert_eq!(adapter.get_info().device_type, DeviceType::Cpu);
     }
 
-    scores.iter().position_max()
+    #[test]
+    fn headless_context_is_created_with_fallback_adapter() {
+        let gfx_ctx = match test_gfx_ctx(PhysicalSize::new(64, 32)) {
+            Some(gfx_ctx) => gfx_ctx,
+            None => {
+                eprintln!("skipped: no adapter found");
+                return;
+            }
+        };
+
+        assert!(gfx_ctx.surface.is_none());
+        let surface_config = gfx_ctx.surface_config.borrow();
+        assert_eq!((surface_config.width, surface_config.height), (64, 32));
+        assert!(surface_config
+            .usage
+            .contains(TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC));
+    }
+
+    #[test]
+    fn oversized_buffer_is_captured_as_validation_error() {
+        let gfx_ctx = match test_gfx_ctx(PhysicalSize::new(64, 32)) {
+            Some(gfx_ctx) => gfx_ctx,
+            None => {
+                eprintln!("skipped: no adapter found");
+                return;
+            }
+        };
+
+        let max_buffer_size = gfx_ctx.device.limits().max_buffer_size;
+        let result = gfx_ctx.capture_errors(|| {
+            gfx_ctx.device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size: max_buffer_size + wgpu::COPY_BUFFER_ALIGNMENT,
+                usage: wgpu::BufferUsages::UNIFORM,
+                mapped_at_creation: false,
+            })
+        });
+        assert!(matches!(result, Err(GfxError::Validation(_))));
+
+        // The device is still usable after the error.
+        let result = gfx_ctx.capture_errors(|| {
+            gfx_ctx.device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size: 16,
+                usage: wgpu::BufferUsages::UNIFORM,
+                mapped_at_creation: false,
+            })
+        });
+        assert!(result.is_ok());
+    }
 }
@@ -1,19 +1,38 @@
-use super::TextureHandle;
+use super::{TextureHandle, WeakTextureHandle};
 use codegen::Handle;
+use thiserror::Error;
 
+/// A nine-patch region of a texture. It refers to the texture weakly, since textures usually own
+/// their nine-patches and a strong handle would keep both alive forever.
 #[derive(Handle)]
 pub struct NinePatch {
-    texture: TextureHandle,
+    texture: WeakTextureHandle,
+    texture_width: u16,
+    texture_height: u16,
     mapping: NinePatchTexelMapping,
 }
 
 impl NinePatch {
-    pub fn new(texture: TextureHandle, mapping: NinePatchTexelMapping) -> Self {
-        Self { texture, mapping }
+    pub fn new(texture: &TextureHandle, mapping: NinePatchTexelMapping) -> Self {
+        Self {
+            texture: texture.downgrade(),
+            texture_width: texture.width,
+            texture_height: texture.height,
+            mapping,
+        }
     }
 
-    pub fn texture(&self) -> &TextureHandle {
-        &self.texture
+    /// Returns the texture, or `None` if it has been dropped.
+    pub fn texture(&self) -> Option<TextureHandle> {
+        self.texture.upgrade()
+    }
+
+    pub fn texture_width(&self) -> u16 {
+        self.texture_width
+    }
+
+    pub fn texture_height(&self) -> u16 {
+        self.texture_height
     }
 
     pub fn mapping(&self) -> NinePatchTexelMapping {
@@ -21,6 +40,18 @@ impl NinePatch {
     }
 }
 
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NinePatchBorderError {
+    #[error(
+        "the left and right borders ({left} + {right} px) exceed the texture width ({width} px)"
+    )]
+    ExceedsWidth { left: u16, right: u16, width: u16 },
+    #[error(
+        "the top and bottom borders ({top} + {bottom} px) exceed the texture height ({height} px)"
+    )]
+    ExceedsHeight { top: u16, bottom: u16, height: u16 },
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct NinePatchTexelMapping {
     pub x_min: u16,
@@ -56,6 +87,44 @@ impl NinePatchTexelMapping {
         }
     }
 
+    /// Computes the mapping that covers the whole texture from the thicknesses of the borders in pixels, as
+    /// nine-patches are usually authored. The top border is at `y_max`, the side that is drawn on top.
+    pub fn from_borders(
+        texture_width: u16,
+        texture_height: u16,
+        left: u16,
+        right: u16,
+        top: u16,
+        bottom: u16,
+    ) -> Result<Self, NinePatchBorderError> {
+        if texture_width < left || texture_width - left < right {
+            return Err(NinePatchBorderError::ExceedsWidth {
+                left,
+                right,
+                width: texture_width,
+            });
+        }
+
+        if texture_height < top || texture_height - top < bottom {
+            return Err(NinePatchBorderError::ExceedsHeight {
+                top,
+                bottom,
+                height: texture_height,
+            });
+        }
+
+        Ok(Self::new(
+            0,
+            left,
+            texture_width - right,
+            texture_width,
+            0,
+            bottom,
+            texture_height - top,
+            texture_height,
+        ))
+    }
+
     pub fn min(self) -> (u16, u16) {
         (self.x_min, self.y_min)
     }
@@ -80,3 +149,32 @@ impl NinePatchTexelMapping {
         u16::abs_diff(self.y_mid_bottom, self.y_mid_top)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn borders_are_measured_from_the_edges() {
+        assert_eq!(
+            NinePatchTexelMapping::from_borders(64, 64, 10, 10, 10, 10),
+            Ok(NinePatchTexelMapping::new(0, 10, 54, 64, 0, 10, 54, 64))
+        );
+        assert_eq!(
+            NinePatchTexelMapping::from_borders(64, 64, 40, 30, 10, 10),
+            Err(NinePatchBorderError::ExceedsWidth {
+                left: 40,
+                right: 30,
+                width: 64,
+            })
+        );
+        assert_eq!(
+            NinePatchTexelMapping::from_borders(64, 32, 10, 10, 20, 20),
+            Err(NinePatchBorderError::ExceedsHeight {
+                top: 20,
+                bottom: 20,
+                height: 32,
+            })
+        );
+    }
+}
@@ -0,0 +1,333 @@
+use super::{
+    Color, PipelineCache, ShaderManager, SpriteDraw, SpriteInstance, SpriteRenderer,
+    SpriteSortAxis, SpriteView,
+};
+use crate::{
+    math::{Mat4, Vec2, Vec3, Vec4},
+    util::Rng,
+};
+use codegen::Component;
+use specs::prelude::*;
+
+/// A range that the values of spawned particles are drawn from uniformly.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParticleRange<T> {
+    pub min: T,
+    pub max: T,
+}
+
+impl<T> ParticleRange<T>
+where
+    T: Copy,
+{
+    pub fn new(min: T, max: T) -> Self {
+        Self { min, max }
+    }
+
+    pub fn constant(value: T) -> Self {
+        Self {
+            min: value,
+            max: value,
+        }
+    }
+}
+
+impl ParticleRange<f32> {
+    pub fn sample(&self, rng: &mut Rng) -> f32 {
+        self.min + (self.max - self.min) * rng.next_f32()
+    }
+}
+
+impl ParticleRange<Vec3> {
+    /// Draws each component independently, so that the values fill the box between `min` and `max`.
+    pub fn sample(&self, rng: &mut Rng) -> Vec3 {
+        Vec3::new(
+            ParticleRange::new(self.min.x, self.max.x).sample(rng),
+            ParticleRange::new(self.min.y, self.max.y).sample(rng),
+            ParticleRange::new(self.min.z, self.max.z).sample(rng),
+        )
+    }
+}
+
+impl ParticleRange<Color> {
+    /// Draws a color on the gradient between `min` and `max`.
+    pub fn sample(&self, rng: &mut Rng) -> Color {
+        Color::lerp(self.min, self.max, rng.next_f32())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParticleEmitterConfig {
+    /// The number of particles spawned per second while emitting.
+    pub rate: f32,
+    /// The number of live particles that no more particles are spawned beyond.
+    pub max_particles: usize,
+    /// The seconds that particles live for.
+    pub lifetime: ParticleRange<f32>,
+    /// The initial velocity of particles, in the space of the emitter object.
+    pub velocity: ParticleRange<Vec3>,
+    /// The acceleration applied to particles, in world space.
+    pub gravity: Vec3,
+    /// The width and the height of the quads of particles, in world units.
+    pub size: ParticleRange<f32>,
+    pub color: ParticleRange<Color>,
+}
+
+impl Default for ParticleEmitterConfig {
+    fn default() -> Self {
+        Self {
+            rate: 10.0,
+            max_particles: 1000,
+            lifetime: ParticleRange::constant(1.0),
+            velocity: ParticleRange::constant(Vec3::ZERO),
+            gravity: Vec3::ZERO,
+            size: ParticleRange::constant(0.1),
+            color: ParticleRange::constant(Color::white()),
+        }
+    }
+}
+
+/// A live particle, in world space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Particle {
+    pub position: Vec3,
+    pub velocity: Vec3,
+    pub size: f32,
+    pub color: Color,
+    /// The seconds since the particle has been spawned.
+    pub age: f32,
+    pub lifetime: f32,
+}
+
+/// Spawns particles from the position of its object and moves them on the CPU every update, on the game time.
+/// Particles are drawn as camera-facing quads of the sprite of [`renderer`](Self::renderer), sorted and batched
+/// with the other sprites, so that the whole emitter is drawn in one instanced draw call.
+#[derive(Component)]
+#[storage(HashMapStorage)]
+#[auto_register]
+pub struct ParticleEmitter {
+    config: ParticleEmitterConfig,
+    is_emitting: bool,
+    /// The fraction of a particle that is carried over to the next update.
+    spawn_accumulator: f32,
+    particles: Vec<Particle>,
+    rng: Rng,
+    renderer: SpriteRenderer,
+}
+
+impl ParticleEmitter {
+    /// Creates an emitter that draws the values of particles from the given generator, e.g. a
+    /// [`fork`](Rng::fork) of the global one to keep the particles reproducible.
+    pub fn new(config: ParticleEmitterConfig, rng: Rng) -> Self {
+        Self {
+            config,
+            is_emitting: true,
+            spawn_accumulator: 0.0,
+            particles: Vec::new(),
+            rng,
+            renderer: SpriteRenderer::new(),
+        }
+    }
+
+    pub fn config(&self) -> &ParticleEmitterConfig {
+        &self.config
+    }
+
+    pub fn config_mut(&mut self) -> &mut ParticleEmitterConfig {
+        &mut self.config
+    }
+
+    pub fn is_emitting(&self) -> bool {
+        self.is_emitting
+    }
+
+    /// Starts or stops spawning particles. The live particles keep moving until they die.
+    pub fn set_emitting(&mut self, is_emitting: bool) {
+        self.is_emitting = is_emitting;
+    }
+
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+
+    /// Returns the renderer that particles are drawn with. Set its material, sprite, mask and sorting layer;
+    /// its color tints every particle.
+    pub fn renderer(&self) -> &SpriteRenderer {
+        &self.renderer
+    }
+
+    pub fn renderer_mut(&mut self) -> &mut SpriteRenderer {
+        &mut self.renderer
+    }
+
+    /// Removes every live particle.
+    pub fn clear(&mut self) {
+        self.particles.clear();
+    }
+
+    /// Spawns the given number of particles at once, regardless of the rate, up to the maximum.
+    pub fn burst(&mut self, count: usize, matrix: &Mat4) {
+        for _ in 0..count {
+            if self.config.max_particles <= self.particles.len() {
+                break;
+            }
+
+            self.spawn(matrix);
+        }
+    }
+
+    /// Advances the live particles by `delta_time` seconds, removes the expired ones and spawns new ones from the
+    /// object matrix of the emitter.
+    pub fn update(&mut self, delta_time: f32, matrix: &Mat4) {
+        let gravity = self.config.gravity;
+
+        self.particles.retain_mut(|particle| {
+            particle.age += delta_time;
+
+            if particle.lifetime <= particle.age {
+                return false;
+            }
+
+            particle.velocity += gravity * delta_time;
+            particle.position += particle.velocity * delta_time;
+            true
+        });
+
+        if !self.is_emitting {
+            self.spawn_accumulator = 0.0;
+            return;
+        }
+
+        self.spawn_accumulator += self.config.rate * delta_time;
+
+        while 1.0 <= self.spawn_accumulator {
+            self.spawn_accumulator -= 1.0;
+
+            if self.config.max_particles <= self.particles.len() {
+                continue;
+            }
+
+            self.spawn(matrix);
+        }
+    }
+
+    fn spawn(&mut self, matrix: &Mat4) {
+        let velocity = self.config.velocity.sample(&mut self.rng);
+
+        self.particles.push(Particle {
+            position: Vec3::from(matrix.row(3)),
+            velocity: Vec3::from(Vec4::from_vec3(velocity, 0.0) * matrix),
+            size: self.config.size.sample(&mut self.rng),
+            color: self.config.color.sample(&mut self.rng),
+            age: 0.0,
+            lifetime: self.config.lifetime.sample(&mut self.rng),
+        });
+    }
+
+    /// Prepares a sprite draw for every live particle, as seen from the view. Nothing is drawn if the renderer
+    /// is not ready.
+    pub fn draw(
+        &mut self,
+        view: &SpriteView,
+        sort_axis: SpriteSortAxis,
+        shader_mgr: &ShaderManager,
+        pipeline_cache: &mut PipelineCache,
+        mut f: impl FnMut(SpriteDraw),
+    ) {
+        if self.particles.is_empty() {
+            return;
+        }
+
+        let template = if let Some(draw) = self.renderer.draw(
+            &Mat4::identity(),
+            view,
+            sort_axis,
+            shader_mgr,
+            pipeline_cache,
+        ) {
+            draw
+        } else {
+            return;
+        };
+        let axis = match sort_axis {
+            SpriteSortAxis::CameraForward => view.forward,
+            SpriteSortAxis::World(axis) => axis,
+        };
+
+        for particle in &self.particles {
+            let mut draw = template.clone();
+            draw.instance = SpriteInstance {
+                origin: particle.position,
+                axis_x: view.right,
+                axis_y: view.up,
+                size: Vec2::new(particle.size, particle.size),
+                offset: Vec2::new(-0.5 * particle.size, -0.5 * particle.size),
+                color: template.instance.color * particle.color,
+                ..template.instance
+            };
+            draw.sort_key.depth = Vec3::dot(particle.position, axis);
+            f(draw);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn live_particles_follow_rate_and_lifetime() {
+        let mut emitter = ParticleEmitter::new(
+            ParticleEmitterConfig {
+                rate: 8.0,
+                lifetime: ParticleRange::constant(1.0),
+                velocity: ParticleRange::new(Vec3::new(-1.0, 1.0, -1.0), Vec3::new(1.0, 2.0, 1.0)),
+                ..Default::default()
+            },
+            Rng::new(7),
+        );
+        let matrix = Mat4::translation(Vec3::new(1.0, 2.0, 3.0));
+        let delta_time = 0.125;
+
+        for _ in 0..4 {
+            emitter.update(delta_time, &matrix);
+        }
+        assert_eq!(emitter.particles().len(), 4);
+
+        for _ in 4..24 {
+            emitter.update(delta_time, &matrix);
+        }
+        // 3 seconds at 8 per second spawn 24 particles, of which the 16 spawned in the first 2 seconds expired.
+        assert_eq!(emitter.particles().len(), 24 - 16);
+        assert!(emitter
+            .particles()
+            .iter()
+            .all(|particle| particle.age < particle.lifetime && 2.0 <= particle.position.y));
+
+        emitter.set_emitting(false);
+        for _ in 0..8 {
+            emitter.update(delta_time, &matrix);
+        }
+        assert!(emitter.particles().is_empty());
+    }
+
+    #[test]
+    fn particles_stop_at_the_maximum() {
+        let mut emitter = ParticleEmitter::new(
+            ParticleEmitterConfig {
+                rate: 100.0,
+                max_particles: 10,
+                lifetime: ParticleRange::constant(10.0),
+                ..Default::default()
+            },
+            Rng::new(7),
+        );
+
+        emitter.update(1.0, &Mat4::identity());
+        assert_eq!(emitter.particles().len(), 10);
+
+        emitter.clear();
+        emitter.burst(15, &Mat4::identity());
+        assert_eq!(emitter.particles().len(), 10);
+    }
+}
@@ -0,0 +1,367 @@
+use super::{Camera, Mesh, MeshPrimitive, MeshRenderer};
+use crate::{
+    math::{Mat4, Ray, Vec2, Vec3, Vec4, AABB},
+    object::{
+        is_object_hidden_for_camera, Object, ObjectHandle, ObjectHierarchy, ObjectId,
+        ObjectVisibility,
+    },
+    use_context,
+};
+use codegen::Component;
+use specs::prelude::*;
+
+/// Marks an object as a target of mouse events in the 3D scene. See [`UIEventManager::set_pick_camera`].
+///
+/// [`UIEventManager::set_pick_camera`]: crate::ui::UIEventManager::set_pick_camera
+#[derive(Debug, Default, Clone, Copy, Component)]
+#[storage(NullStorage)]
+#[auto_register]
+pub struct Pickable;
+
+/// The closest object hit by a pick.
+#[derive(Clone)]
+pub struct PickHit {
+    pub object: ObjectHandle,
+    /// The distance from the ray origin to the hit point.
+    pub distance: f32,
+    /// The world-space hit point.
+    pub point: Vec3,
+    /// The world-space normal of the surface at the hit point, facing the ray origin.
+    pub normal: Vec3,
+}
+
+/// Finds objects with a [`MeshRenderer`] under the mouse or along a ray.
+/// Candidates are found in the [renderer BVH](crate::Context::renderer_bvh), so objects created in this frame are
+/// not found until the next frame. Inactive and hidden objects are not found, as they are not rendered. Each mesh
+/// is tested against its world-space AABB first, then against its triangles for precision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ObjectPicker {
+    /// Only renderers that share a bit with the mask are considered.
+    pub mask: u32,
+    /// Whether only objects with a [`Pickable`] component are considered.
+    pub is_pickable_only: bool,
+    /// Whether hits are refined against the triangles of the mesh. If `false`, the AABB hit is reported.
+    /// If `true`, meshes without triangles are never hit, e.g. line meshes and meshes that have dropped their
+    /// [CPU data](super::MeshCpuDataPolicy).
+    pub is_precise: bool,
+}
+
+impl ObjectPicker {
+    pub fn new() -> Self {
+        Self {
+            mask: 0xFFFF_FFFF,
+            is_pickable_only: false,
+            is_precise: true,
+        }
+    }
+
+    /// Finds the closest active object under the given screen position, as seen by the given camera object.
+    /// The screen position must be in logical pixels, with the origin at the top-left corner of the screen.
+    /// Only renderers that are visible to the camera (by mask) are considered, and objects hidden from the camera
+    /// are not found.
+    pub fn pick(&self, screen_position: Vec2, camera: &ObjectHandle) -> Option<PickHit> {
+        let (ray, mask) = {
+            let ctx = use_context();
+            let world = ctx.world();
+            let cameras = world.read_component::<Camera>();
+            let camera_component = cameras.get(camera.entity)?;
+            let object_mgr = ctx.object_mgr();
+            let camera_matrix = object_mgr.object_hierarchy().matrix(camera.object_id);
+            let screen_mgr = ctx.screen_mgr();
+            let (origin, direction) = camera_component.screen_to_ray(
+                camera_matrix,
+                screen_position,
+                screen_mgr.logical_size(),
+            );
+
+            (Ray::new(origin, direction), camera_component.mask)
+        };
+
+        Self {
+            mask: self.mask & mask,
+            ..*self
+        }
+        .pick_ray_from(&ray, Some(camera.entity))
+    }
+
+    /// Finds the closest active object hit by the given world-space ray.
+    pub fn pick_ray(&self, ray: &Ray) -> Option<PickHit> {
+        self.pick_ray_from(ray, None)
+    }
+
+    /// Finds the closest active object hit by the ray, skipping the objects hidden from the camera if any.
+    fn pick_ray_from(&self, ray: &Ray, camera: Option<Entity>) -> Option<PickHit> {
+        let ctx = use_context();
+        let world = ctx.world();
+        let objects = world.read_component::<Object>();
+        let visibilities = world.read_component::<ObjectVisibility>();
+        let mesh_renderers = world.read_component::<MeshRenderer>();
+        let pickables = world.read_component::<Pickable>();
+        let object_mgr = ctx.object_mgr();
+        let object_hierarchy = object_mgr.object_hierarchy();
+
+        let mut candidates = Vec::new();
+        ctx.renderer_bvh().query_ray(ray, &mut candidates);
+
+        let mut closest: Option<(Entity, ObjectId, MeshHit)> = None;
+
+        for entity in candidates {
+            let (object, mesh_renderer) = match (objects.get(entity), mesh_renderers.get(entity)) {
+                (Some(object), Some(mesh_renderer)) => (object, mesh_renderer),
+                _ => continue,
+            };
+            let object_id = object.object_id();
+
+            if !is_object_pickable(&object_hierarchy, &visibilities, object_id, camera) {
+                continue;
+            }
+
+            if mesh_renderer.mask() & self.mask == 0 {
+                continue;
+            }
+
+            if self.is_pickable_only && !pickables.contains(entity) {
+                continue;
+            }
+
+            let mesh = if let Some(mesh) = mesh_renderer.mesh() {
+                mesh
+            } else {
+                continue;
+            };
+
+            if let Some(hit) = pick_mesh(
+                ray,
+                mesh,
+                object_hierarchy.matrix(object_id),
+                self.is_precise,
+            ) {
+                if closest
+                    .as_ref()
+                    .is_none_or(|(_, _, closest)| hit.distance < closest.distance)
+                {
+                    closest = Some((entity, object_id, hit));
+                }
+            }
+        }
+
+        closest.map(|(entity, object_id, hit)| PickHit {
+            object: ObjectHandle::new(ctx.clone(), entity, object_id),
+            distance: hit.distance,
+            point: ray.at(hit.distance),
+            normal: hit.normal,
+        })
+    }
+}
+
+impl Default for ObjectPicker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Finds the closest active object with a [`MeshRenderer`] that is hit by the given ray.
+/// Returns the object and the distance from the ray origin to the hit point. See [`ObjectPicker`] for details.
+pub fn pick(ray_origin: Vec3, ray_dir: Vec3) -> Option<(ObjectId, f32)> {
+    ObjectPicker::new()
+        .pick_ray(&Ray::new(ray_origin, ray_dir))
+        .map(|hit| (hit.object.object_id, hit.distance))
+}
+
+/// Finds the closest active object under the given screen position, as seen by the given camera object.
+/// See [`ObjectPicker::pick`] for details.
+pub fn pick_screen(camera: &ObjectHandle, screen_position: Vec2) -> Option<(ObjectId, f32)> {
+    ObjectPicker::new()
+        .pick(screen_position, camera)
+        .map(|hit| (hit.object.object_id, hit.distance))
+}
+
+/// Returns `true` if the object is active and visible, and not hidden from the camera if any.
+fn is_object_pickable(
+    hierarchy: &ObjectHierarchy,
+    visibilities: &ReadStorage<ObjectVisibility>,
+    object: ObjectId,
+    camera: Option<Entity>,
+) -> bool {
+    match camera {
+        Some(camera) => !is_object_hidden_for_camera(hierarchy, visibilities, object, camera),
+        None => hierarchy.is_active(object) && hierarchy.is_visible(object),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct MeshHit {
+    distance: f32,
+    normal: Vec3,
+}
+
+fn pick_mesh(ray: &Ray, mesh: &Mesh, matrix: &Mat4, is_precise: bool) -> Option<MeshHit> {
+    let world_aabb = mesh.local_aabb()?.transformed(matrix);
+    let aabb_distance = ray.intersect_aabb(&world_aabb)?;
+
+    if !is_precise {
+        return Some(aabb_hit(ray, &world_aabb, aabb_distance));
+    }
+
+    // Without the triangles, e.g. after the CPU data of the mesh has been dropped, there is nothing to hit.
+    let data = match mesh.cpu_data() {
+        Some(data) if data.primitive == MeshPrimitive::Triangles => data,
+        _ => return None,
+    };
+
+    // Test triangles in local space. The direction is intentionally not re-normalized,
+    // so the distances found in local space are equal to the distances in world space.
+    let inverse_matrix = matrix.inversed();
+    let local_ray = Ray {
+        origin: Vec3::from(Vec4::from_vec3(ray.origin, 1.0) * &inverse_matrix),
+        direction: Vec3::from(Vec4::from_vec3(ray.direction, 0.0) * &inverse_matrix),
+    };
+
+    let vertex = |index: u32| data.positions[index as usize];
+    let to_world = |direction: Vec3| Vec3::from(Vec4::from_vec3(direction, 0.0) * matrix);
+
+    let (distance, a, b, c) = data
+        .faces()
+        .filter_map(|face| {
+            let (a, b, c) = (vertex(face[0]), vertex(face[1]), vertex(face[2]));
+            local_ray
+                .intersect_triangle(a, b, c)
+                .map(|distance| (distance, a, b, c))
+        })
+        .min_by(|lhs, rhs| lhs.0.total_cmp(&rhs.0))?;
+
+    // Edges are transformed rather than the normal, so that non-uniform scales are handled.
+    let normal = Vec3::cross(to_world(b - a), to_world(c - a)).normalized();
+    let normal = if Vec3::dot(normal, ray.direction) > 0.0 {
+        -normal
+    } else {
+        normal
+    };
+
+    Some(MeshHit { distance, normal })
+}
+
+/// Returns the hit on the AABB, with the normal of the face of the box that the hit point is closest to.
+fn aabb_hit(ray: &Ray, world_aabb: &AABB, distance: f32) -> MeshHit {
+    let offset = ray.at(distance) - world_aabb.center();
+    let extents = world_aabb.extents();
+    let gap = Vec3::new(
+        offset.x.abs() - extents.x,
+        offset.y.abs() - extents.y,
+        offset.z.abs() - extents.z,
+    );
+    let normal = if gap.x >= gap.y && gap.x >= gap.z {
+        Vec3::new(offset.x.signum(), 0.0, 0.0)
+    } else if gap.y >= gap.z {
+        Vec3::new(0.0, offset.y.signum(), 0.0)
+    } else {
+        Vec3::new(0.0, 0.0, offset.z.signum())
+    };
+
+    MeshHit { distance, normal }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{gfx::MeshCpuDataPolicy, math::Quat};
+
+    fn close(lhs: Vec3, rhs: Vec3) -> bool {
+        Vec3::distance(lhs, rhs) < 1e-4
+    }
+
+    #[test]
+    fn pick_cube_center() {
+        let cube = Mesh::cube(2.0);
+        let matrix = Mat4::translation(Vec3::new(0.0, 0.0, -5.0));
+        let ray = Ray::new(Vec3::ZERO, Vec3::FORWARD);
+
+        let hit = pick_mesh(&ray, &cube, &matrix, true).unwrap();
+        assert!((hit.distance - 4.0).abs() < 1e-4);
+        assert!(close(ray.at(hit.distance), Vec3::new(0.0, 0.0, -4.0)));
+        assert!(close(hit.normal, Vec3::BACKWARD));
+    }
+
+    #[test]
+    fn pick_cube_outside_silhouette() {
+        // Rotated around the view axis, the cube is a diamond inside its square world AABB.
+        let cube = Mesh::cube(2.0);
+        let matrix = Mat4::rotation(Quat::from_axis_angle(
+            Vec3::BACKWARD,
+            std::f32::consts::FRAC_PI_4,
+        )) * Mat4::translation(Vec3::new(0.0, 0.0, -5.0));
+        let ray = Ray::new(Vec3::new(1.2, 1.2, 0.0), Vec3::FORWARD);
+
+        assert!(ray
+            .intersect_aabb(&cube.local_aabb().unwrap().transformed(&matrix))
+            .is_some());
+        assert!(pick_mesh(&ray, &cube, &matrix, false).is_some());
+        assert!(pick_mesh(&ray, &cube, &matrix, true).is_none());
+    }
+
+    #[test]
+    fn precise_pick_misses_meshes_without_triangles() {
+        let matrix = Mat4::translation(Vec3::new(0.0, 0.0, -5.0));
+        let ray = Ray::new(Vec3::ZERO, Vec3::FORWARD);
+        let lines = Mesh::lines([(Vec3::new(-1.0, -1.0, 0.0), Vec3::new(1.0, 1.0, 0.0))]);
+
+        assert!(pick_mesh(&ray, &lines, &matrix, false).is_some());
+        assert!(pick_mesh(&ray, &lines, &matrix, true).is_none());
+
+        let gfx_ctx = match crate::gfx::test_gfx_ctx(winit::dpi::PhysicalSize::new(4, 4)) {
+            Some(gfx_ctx) => gfx_ctx,
+            None => {
+                eprintln!("skipped: no adapter found");
+                return;
+            }
+        };
+        let cube = Mesh::with_policy(
+            Mesh::cube(2.0).cpu_data().unwrap().as_ref().clone(),
+            MeshCpuDataPolicy::Drop,
+        );
+        cube.vertex_buffer(&gfx_ctx.device).unwrap();
+
+        assert!(cube.cpu_data().is_none());
+        assert!(pick_mesh(&ray, &cube, &matrix, false).is_some());
+        assert!(pick_mesh(&ray, &cube, &matrix, true).is_none());
+    }
+
+    #[test]
+    fn inactive_and_hidden_objects_are_not_pickable() {
+        let mut world = World::new();
+        world.register::<ObjectVisibility>();
+        let camera = world.create_entity().build();
+        let mut hierarchy = ObjectHierarchy::new();
+        let entities = (0..4)
+            .map(|id| {
+                let entity = world.create_entity().build();
+                hierarchy.add(ObjectId::from_u32(id), entity);
+                entity
+            })
+            .collect::<Vec<_>>();
+
+        hierarchy.set_active(ObjectId::from_u32(1), false);
+        hierarchy.set_visible(ObjectId::from_u32(2), false);
+
+        let mut visibility = ObjectVisibility::new();
+        visibility.set_hidden_for_camera(camera, true);
+        world
+            .write_component::<ObjectVisibility>()
+            .insert(entities[3], visibility)
+            .unwrap();
+
+        let visibilities = world.read_component::<ObjectVisibility>();
+        let is_pickable = |id, camera| {
+            is_object_pickable(&hierarchy, &visibilities, ObjectId::from_u32(id), camera)
+        };
+
+        assert!(is_pickable(0, None));
+        assert!(is_pickable(0, Some(camera)));
+        assert!(!is_pickable(1, None));
+        assert!(!is_pickable(1, Some(camera)));
+        assert!(!is_pickable(2, None));
+        assert!(!is_pickable(2, Some(camera)));
+        assert!(is_pickable(3, None));
+        assert!(!is_pickable(3, Some(camera)));
+    }
+}
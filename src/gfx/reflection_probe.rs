@@ -0,0 +1,364 @@
+use super::{
+    depth_stencil::create_texture_and_view, Camera, CameraClearMode,
+    CameraPerspectiveProjectionAspect, CameraProjection, GfxContextHandle, GpuResourceTracker,
+    RenderManager, Texture, TextureHandle,
+};
+use crate::math::{Mat4, Vec2, Vec3};
+use codegen::Component;
+use specs::prelude::*;
+use std::{f32::consts::FRAC_PI_2, sync::Arc};
+use wgpu::{TextureView, TextureViewDescriptor, TextureViewDimension};
+use winit::dpi::PhysicalSize;
+
+/// The directions that the faces look at and their up vectors, in the order of the layers of a cubemap.
+///
+/// The layers of a cubemap follow a left-handed convention, so the faces rendered in the right-handed world are
+/// stored as if the z axis were flipped: the layer of `+z` holds what is seen towards `-z`, and vice versa.
+const FACES: [(Vec3, Vec3); 6] = [
+    (Vec3::RIGHT, Vec3::UP),
+    (Vec3::LEFT, Vec3::UP),
+    (Vec3::UP, Vec3::BACKWARD),
+    (Vec3::DOWN, Vec3::FORWARD),
+    (Vec3::FORWARD, Vec3::UP),
+    (Vec3::BACKWARD, Vec3::UP),
+];
+
+/// When a [`ReflectionProbe`] captures the scene. A capture renders the scene six times, so it is expensive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReflectionProbeSchedule {
+    /// Once, in the first frame after the probe is added, e.g. at scene load.
+    Once,
+    /// In the first frame, then every given number of frames.
+    EveryFrames(u32),
+    /// Only when [`ReflectionProbe::request_capture`] is called.
+    Manual,
+}
+
+pub struct ReflectionProbeConfig {
+    /// The width and height of each face in pixels.
+    pub size: u16,
+    /// The objects that the probe captures, like [`Camera::mask`].
+    pub mask: u32,
+    pub clear_mode: CameraClearMode,
+    pub near: f32,
+    pub far: f32,
+    pub schedule: ReflectionProbeSchedule,
+    pub intensity: f32,
+}
+
+/// A cubemap that the standard shader reflects. See [`StandardMaterialTextures::environment`] and
+/// [`RenderManager::set_global_environment`].
+///
+/// [`StandardMaterialTextures::environment`]: super::StandardMaterialTextures::environment
+#[derive(Clone)]
+pub struct Environment {
+    pub cubemap: TextureHandle,
+    pub intensity: f32,
+    is_captured: bool,
+}
+
+impl Environment {
+    /// Reflects the cubemap as it is, e.g. the one of a [`CameraClearMode::Skybox`], so that it can be used as
+    /// the global environment without any probe.
+    pub fn from_cubemap(cubemap: TextureHandle, intensity: f32) -> Self {
+        Self {
+            cubemap,
+            intensity,
+            is_captured: false,
+        }
+    }
+
+    /// Returns the sign that the shader multiplies the z axis of lookups by; captured cubemaps are stored with it
+    /// flipped. See [`FACES`].
+    pub fn z_sign(&self) -> f32 {
+        if self.is_captured {
+            -1.0
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Captures the scene around its object into a cubemap, which shiny materials of the standard shader reflect.
+/// The faces are rendered by the render system like cameras, in the frames its
+/// [`schedule`](ReflectionProbe::schedule) picks, before the cameras of the frame.
+#[derive(Component)]
+#[storage(HashMapStorage)]
+#[auto_register]
+pub struct ReflectionProbe {
+    pub schedule: ReflectionProbeSchedule,
+    pub intensity: f32,
+    size: u16,
+    cubemap: TextureHandle,
+    face_views: Vec<Arc<TextureView>>,
+    depth_stencil_view: Option<TextureView>,
+    _depth_stencil_tracker: GpuResourceTracker,
+    /// The cameras of the faces, in the order of the layers.
+    face_cameras: Vec<Camera>,
+    face_matrices: Vec<Mat4>,
+    /// The frames since the last capture, or `None` if it has never captured.
+    frames_since_capture: Option<u32>,
+    is_capture_requested: bool,
+    is_capturing: bool,
+}
+
+impl ReflectionProbe {
+    /// Creates the cubemap and the depth stencil buffer of the probe, in the formats of the main surface so that
+    /// the same pipelines draw into both.
+    pub fn new(
+        config: ReflectionProbeConfig,
+        gfx_ctx: GfxContextHandle,
+        render_mgr: &mut RenderManager,
+    ) -> Self {
+        let format = gfx_ctx.surface_config.borrow().format;
+        let cubemap = TextureHandle::new(Texture::create_cubemap_target(
+            config.size,
+            format,
+            &gfx_ctx.device,
+        ));
+        let face_views = Vec::from_iter((0..FACES.len() as u32).map(|layer| {
+            Arc::new(cubemap.texture.create_view(&TextureViewDescriptor {
+                label: Some("reflection probe face"),
+                dimension: Some(TextureViewDimension::D2),
+                base_array_layer: layer,
+                array_layer_count: Some(1),
+                ..Default::default()
+            }))
+        }));
+        let (depth_stencil_texture, depth_stencil_view) = create_texture_and_view(
+            &gfx_ctx.device,
+            render_mgr.depth_stencil_mode(),
+            PhysicalSize::new(config.size as u32, config.size as u32),
+        );
+        let depth_stencil_tracker = depth_stencil_texture
+            .map_or_else(Default::default, |texture| {
+                GpuResourceTracker::texture(&texture, Some("reflection probe depth stencil"))
+            });
+        let face_cameras = Vec::from_iter(FACES.iter().map(|_| {
+            Camera::new(
+                config.mask,
+                0,
+                config.clear_mode.clone(),
+                CameraProjection::perspective(
+                    FRAC_PI_2,
+                    CameraPerspectiveProjectionAspect::Fixed(1.0),
+                    config.near,
+                    config.far,
+                ),
+                &gfx_ctx.device,
+                render_mgr.bind_group_layout_cache(),
+            )
+        }));
+
+        Self {
+            schedule: config.schedule,
+            intensity: config.intensity,
+            size: config.size,
+            cubemap,
+            face_views,
+            depth_stencil_view,
+            _depth_stencil_tracker: depth_stencil_tracker,
+            face_cameras,
+            face_matrices: face_matrices(Vec3::ZERO),
+            frames_since_capture: None,
+            is_capture_requested: false,
+            is_capturing: false,
+        }
+    }
+
+    pub fn size(&self) -> u16 {
+        self.size
+    }
+
+    pub fn cubemap(&self) -> &TextureHandle {
+        &self.cubemap
+    }
+
+    /// Returns the cubemap as an environment to reflect, with the intensity of the probe.
+    pub fn environment(&self) -> Environment {
+        Environment {
+            cubemap: self.cubemap.clone(),
+            intensity: self.intensity,
+            is_captured: true,
+        }
+    }
+
+    /// Captures the scene in the next frame, whatever the schedule is.
+    pub fn request_capture(&mut self) {
+        self.is_capture_requested = true;
+    }
+
+    /// Returns `true` if the probe has been captured at least once.
+    pub fn is_captured(&self) -> bool {
+        self.frames_since_capture.is_some()
+    }
+
+    /// Decides whether the probe captures in this frame, and places the cameras of the faces at the position if so.
+    /// It must be called once per frame by the render system.
+    pub(crate) fn begin_frame(&mut self, position: Vec3, queue: &wgpu::Queue) -> bool {
+        self.is_capturing = is_capture_due(
+            self.schedule,
+            self.frames_since_capture,
+            std::mem::take(&mut self.is_capture_requested),
+        );
+
+        if !self.is_capturing {
+            self.frames_since_capture = self.frames_since_capture.map(|frames| frames + 1);
+            return false;
+        }
+
+        self.frames_since_capture = Some(0);
+        self.face_matrices = face_matrices(position);
+
+        let size = Vec2::new(self.size as f32, self.size as f32);
+
+        for (camera, matrix) in self.face_cameras.iter().zip(&self.face_matrices) {
+            camera.update_buffer(size, queue, matrix);
+        }
+
+        true
+    }
+
+    /// Returns `true` if the probe captures in this frame.
+    pub(crate) fn is_capturing(&self) -> bool {
+        self.is_capturing
+    }
+
+    /// Returns the camera, its transform matrix and the texture view of each face.
+    pub(crate) fn faces(&self) -> impl Iterator<Item = (&Camera, &Mat4, &Arc<TextureView>)> {
+        self.face_cameras
+            .iter()
+            .zip(&self.face_matrices)
+            .zip(&self.face_views)
+            .map(|((camera, matrix), view)| (camera, matrix, view))
+    }
+
+    pub fn depth_stencil_view(&self) -> Option<&TextureView> {
+        self.depth_stencil_view.as_ref()
+    }
+}
+
+fn is_capture_due(
+    schedule: ReflectionProbeSchedule,
+    frames_since_capture: Option<u32>,
+    is_capture_requested: bool,
+) -> bool {
+    if is_capture_requested {
+        return true;
+    }
+
+    match (schedule, frames_since_capture) {
+        (ReflectionProbeSchedule::Manual, _) => false,
+        (_, None) => true,
+        (ReflectionProbeSchedule::Once, Some(_)) => false,
+        (ReflectionProbeSchedule::EveryFrames(frames), Some(frames_since_capture)) => {
+            frames <= frames_since_capture + 1
+        }
+    }
+}
+
+fn face_matrices(position: Vec3) -> Vec<Mat4> {
+    Vec::from_iter(
+        FACES
+            .iter()
+            .map(|&(direction, up)| Mat4::look_at(position, position + direction, up)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Vec4;
+
+    #[test]
+    fn captures_follow_the_schedule() {
+        let captures = |schedule, requests: &[u32]| {
+            let mut frames_since_capture = None;
+            Vec::from_iter((0..7).filter(|frame| {
+                let is_due =
+                    is_capture_due(schedule, frames_since_capture, requests.contains(frame));
+                frames_since_capture = if is_due {
+                    Some(0)
+                } else {
+                    frames_since_capture.map(|frames| frames + 1)
+                };
+                is_due
+            }))
+        };
+
+        assert_eq!(captures(ReflectionProbeSchedule::Once, &[]), [0]);
+        assert_eq!(captures(ReflectionProbeSchedule::Once, &[4]), [0, 4]);
+        assert_eq!(
+            captures(ReflectionProbeSchedule::EveryFrames(3), &[]),
+            [0, 3, 6]
+        );
+        assert_eq!(
+            captures(ReflectionProbeSchedule::EveryFrames(3), &[1]),
+            [0, 1, 4]
+        );
+        assert!(captures(ReflectionProbeSchedule::Manual, &[]).is_empty());
+        assert_eq!(captures(ReflectionProbeSchedule::Manual, &[2, 5]), [2, 5]);
+    }
+
+    /// Returns the layer and the texture coordinates that a cubemap lookup of the direction samples.
+    fn cubemap_lookup(direction: Vec3) -> (usize, Vec2) {
+        let Vec3 { x, y, z } = direction;
+        let (layer, sc, tc, ma) = if z.abs() <= x.abs() && y.abs() <= x.abs() {
+            if 0.0 < x {
+                (0, -z, -y, x)
+            } else {
+                (1, z, -y, -x)
+            }
+        } else if z.abs() <= y.abs() {
+            if 0.0 < y {
+                (2, x, z, y)
+            } else {
+                (3, x, -z, -y)
+            }
+        } else if 0.0 < z {
+            (4, x, -y, z)
+        } else {
+            (5, -x, -y, -z)
+        };
+
+        (layer, Vec2::new(sc / ma * 0.5 + 0.5, tc / ma * 0.5 + 0.5))
+    }
+
+    #[test]
+    fn faces_are_rendered_where_flipped_lookups_sample() {
+        let projection = CameraProjection::perspective(
+            FRAC_PI_2,
+            CameraPerspectiveProjectionAspect::Fixed(1.0),
+            0.1,
+            10.0,
+        )
+        .as_matrix_with_screen_size(Vec2::new(1.0, 1.0));
+        let position = Vec3::new(1.0, 2.0, 3.0);
+        let directions = [
+            Vec3::new(1.0, 0.3, -0.5),
+            Vec3::new(-1.0, -0.2, 0.4),
+            Vec3::new(0.3, 1.0, 0.6),
+            Vec3::new(-0.7, -1.0, 0.2),
+            Vec3::new(0.4, -0.6, 1.0),
+            Vec3::new(-0.1, 0.5, -1.0),
+        ];
+
+        for direction in directions {
+            let (layer, uv) = cubemap_lookup(Vec3::new(direction.x, direction.y, -direction.z));
+            let matrix = &face_matrices(position)[layer];
+            let clip = Vec4::from_vec3(position + direction, 1.0)
+                * &(matrix.inversed() * projection.clone());
+            // Texture coordinates grow downwards, while normalized device coordinates grow upwards.
+            let rendered = Vec2::new(clip.x / clip.w * 0.5 + 0.5, 0.5 - clip.y / clip.w * 0.5);
+
+            assert!(
+                (rendered - uv).len() < 1e-4,
+                "{:?} is rendered at {:?} of layer {}, but sampled at {:?}",
+                direction,
+                rendered,
+                layer,
+                uv
+            );
+        }
+    }
+}
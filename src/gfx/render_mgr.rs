@@ -1,15 +1,22 @@
 use super::{
-    build_rendering_command, BindGroupLayoutCache, CameraClearMode, DepthStencil, DepthStencilMode,
-    FrameBufferAllocator, GenericBufferAllocation, GfxContextHandle, PipelineCache,
-    PipelineLayoutCache, Renderer, RenderingCommand,
+    build_rendering_command, BindGroupLayoutCache, CameraClearMode, CaptureConfig, CaptureStats,
+    DepthStencil, DepthStencilMode, Environment, FrameBufferAllocator, FrameCapture,
+    FrameCaptureError, FrameStats, GenericBufferAllocation, GfxContextHandle, GpuProfiler,
+    PhysicalViewport, PipelineCache, PipelineLayoutCache, PreparedSkybox, RenderTargetState,
+    Renderer, RenderingCommand, SkyboxRenderer, ViewportClearer,
 };
-use crate::object::{ObjectHierarchy, ObjectId};
+use crate::{
+    math::Mat4,
+    object::{ObjectHierarchy, ObjectId},
+    time::Instant,
+};
+use logging::log_warn;
 use std::mem::size_of;
 use wgpu::{
     util::{BufferInitDescriptor, DeviceExt},
-    Buffer, BufferSize, BufferUsages, Color, CommandBuffer, CommandEncoder,
-    CommandEncoderDescriptor, LoadOp, Operations, RenderPass, RenderPassColorAttachment,
-    RenderPassDepthStencilAttachment, SurfaceError, TextureView,
+    Buffer, BufferSize, BufferUsages, CommandBuffer, CommandEncoder, CommandEncoderDescriptor,
+    Maintain, Operations, RenderPass, RenderPassColorAttachment, RenderPassDepthStencilAttachment,
+    SurfaceError, Texture, TextureView,
 };
 use winit::dpi::PhysicalSize;
 use zerocopy::AsBytes;
@@ -22,6 +29,12 @@ pub struct RenderManager {
     pipeline_cache: PipelineCache,
     frame_buffer_allocator: FrameBufferAllocator,
     standard_ui_vertex_buffer: GenericBufferAllocation<Buffer>,
+    viewport_clearer: ViewportClearer,
+    skybox_renderer: SkyboxRenderer,
+    gpu_profiler: Option<GpuProfiler>,
+    frame_capture: Option<FrameCapture>,
+    global_environment: Option<Environment>,
+    frame_stats: FrameStats,
 }
 
 impl RenderManager {
@@ -33,8 +46,26 @@ impl RenderManager {
         let depth_stencil = DepthStencil::new(gfx_ctx.clone(), depth_stencil_mode, size).unwrap();
         let bind_group_layout_cache = BindGroupLayoutCache::new(gfx_ctx.clone());
         let pipeline_layout_cache = PipelineLayoutCache::new(gfx_ctx.clone());
-        let pipeline_cache = PipelineCache::new(gfx_ctx.clone());
+        let pipeline_cache = PipelineCache::new(
+            gfx_ctx.clone(),
+            RenderTargetState {
+                color_format: gfx_ctx.surface_config.borrow().format,
+                depth_format: depth_stencil_mode.as_texture_format(),
+                sample_count: 1,
+            },
+        );
         let frame_buffer_allocator = FrameBufferAllocator::new(gfx_ctx.clone());
+        let viewport_clearer = ViewportClearer::new(
+            &gfx_ctx.device,
+            gfx_ctx.surface_config.borrow().format,
+            depth_stencil_mode,
+        );
+        let skybox_renderer = SkyboxRenderer::new(
+            &gfx_ctx.device,
+            gfx_ctx.surface_config.borrow().format,
+            depth_stencil_mode,
+        );
+        let gpu_profiler = GpuProfiler::new(&gfx_ctx.device, &gfx_ctx.queue);
 
         // Since ui elements are always left-bottom based, positions must in range [0, 1].
         let standard_ui_vertices = vec![
@@ -63,6 +94,12 @@ impl RenderManager {
             pipeline_cache,
             frame_buffer_allocator,
             standard_ui_vertex_buffer,
+            viewport_clearer,
+            skybox_renderer,
+            gpu_profiler,
+            frame_capture: None,
+            global_environment: None,
+            frame_stats: FrameStats::default(),
         }
     }
 
@@ -86,8 +123,135 @@ impl RenderManager {
         &self.standard_ui_vertex_buffer
     }
 
+    /// Returns the environment that materials of the standard shader reflect unless they have their own.
+    pub fn global_environment(&self) -> Option<&Environment> {
+        self.global_environment.as_ref()
+    }
+
+    /// Sets the environment that materials of the standard shader created from now on reflect, e.g. the cubemap of
+    /// a [`ReflectionProbe`](super::ReflectionProbe) or of the skybox.
+    pub fn set_global_environment(&mut self, environment: Option<Environment>) {
+        self.global_environment = environment;
+    }
+
+    /// Returns the statistics of the last measured frame.
+    pub fn frame_stats(&self) -> &FrameStats {
+        &self.frame_stats
+    }
+
+    /// Returns `true` if GPU time of render passes is measured.
+    pub fn is_gpu_profiling_supported(&self) -> bool {
+        self.gpu_profiler.is_some()
+    }
+
     pub fn resize(&mut self, size: PhysicalSize<u32>) {
         self.depth_stencil.resize(size);
+
+        if self
+            .frame_capture
+            .as_ref()
+            .is_some_and(|frame_capture| frame_capture.size() != size)
+        {
+            log_warn!("stopped capturing frames since the window has been resized");
+            self.stop_capture();
+        }
+    }
+
+    /// Starts recording the frames of the main surface, stopping the current capture if any. The capture stops
+    /// by itself after [`CaptureConfig::max_frames`], or once the window is resized.
+    pub fn start_capture(&mut self, config: CaptureConfig) -> Result<(), FrameCaptureError> {
+        self.stop_capture();
+        self.frame_capture = Some(FrameCapture::start(&self.gfx_ctx, config)?);
+        Ok(())
+    }
+
+    /// Stops the capture, and returns its stats if there was one. Blocks until the captured frames are written.
+    pub fn stop_capture(&mut self) -> Option<CaptureStats> {
+        let stats = self.frame_capture.take()?.stop(&self.gfx_ctx);
+
+        if stats.dropped_frames != 0 {
+            log_warn!(
+                "dropped {} of {} captured frames; the readbacks or the encoder could not keep up",
+                stats.dropped_frames,
+                stats.frames + stats.dropped_frames
+            );
+        }
+
+        Some(stats)
+    }
+
+    /// Returns the stats of the current capture, if any.
+    pub fn capture_stats(&self) -> Option<CaptureStats> {
+        self.frame_capture.as_ref().map(FrameCapture::stats)
+    }
+
+    /// Copies the surface texture of the frame for the current capture, if a frame is due. It must be called
+    /// before finishing the encoder.
+    pub fn capture_frame(&mut self, encoder: &mut CommandEncoder, surface_texture: &Texture) {
+        if let Some(frame_capture) = self.frame_capture.as_mut() {
+            frame_capture.copy_frame(encoder, surface_texture, Instant::now());
+        }
+    }
+
+    /// Starts a new frame, updating the frame stats with the results of a previous frame if available.
+    pub fn begin_frame(&mut self) {
+        self.skybox_renderer.begin_frame();
+
+        if self.gpu_profiler.is_some() || self.frame_capture.is_some() {
+            // Drives the readback of the timestamps and the captured frames without blocking.
+            self.gfx_ctx.device.poll(Maintain::Poll);
+        }
+
+        if let Some(frame_capture) = self.frame_capture.as_mut() {
+            frame_capture.collect();
+
+            if frame_capture.is_finished() {
+                self.stop_capture();
+            }
+        }
+
+        if let Some(gpu_pass_times) = self
+            .gpu_profiler
+            .as_mut()
+            .and_then(|gpu_profiler| gpu_profiler.begin_frame())
+        {
+            self.frame_stats.gpu_pass_times = gpu_pass_times;
+        }
+    }
+
+    /// Records the draw statistics of the frame being rendered. It must be called once per frame.
+    pub fn record_draw_stats(
+        &mut self,
+        draw_calls: u32,
+        triangles: u64,
+        render_passes: u32,
+        state_changes: u32,
+    ) {
+        self.frame_stats.draw_calls = draw_calls;
+        self.frame_stats.state_changes = state_changes;
+        self.frame_stats.triangles = triangles;
+        self.frame_stats.render_passes = render_passes;
+    }
+
+    /// Marks the beginning of a render pass to be measured. It must be called before beginning the pass.
+    pub fn begin_pass_timestamp(&mut self, encoder: &mut CommandEncoder) -> Option<u32> {
+        self.gpu_profiler
+            .as_mut()
+            .and_then(|gpu_profiler| gpu_profiler.begin_pass(encoder))
+    }
+
+    /// Marks the end of a render pass to be measured. It must be called after ending the pass.
+    pub fn end_pass_timestamp(&mut self, encoder: &mut CommandEncoder, index: Option<u32>) {
+        if let Some(gpu_profiler) = self.gpu_profiler.as_mut() {
+            gpu_profiler.end_pass(encoder, index);
+        }
+    }
+
+    /// Resolves the measured timestamps. It must be called before finishing the encoder.
+    pub fn resolve_timestamps(&mut self, encoder: &mut CommandEncoder) {
+        if let Some(gpu_profiler) = self.gpu_profiler.as_mut() {
+            gpu_profiler.resolve(encoder);
+        }
     }
 
     pub fn create_encoder(&self) -> CommandEncoder {
@@ -96,76 +260,142 @@ impl RenderManager {
             .create_command_encoder(&CommandEncoderDescriptor { label: None })
     }
 
+    /// Begins a render pass into the surface and the depth stencil buffer, if any. The operations are decided by
+    /// the [`FrameGraph`](super::FrameGraph) of the frame.
     pub fn begin_frame_buffer_render_pass<'e>(
         &'e self,
         encoder: &'e mut CommandEncoder,
         surface_texture_view: &'e TextureView,
-        clear_mode: &CameraClearMode,
+        color_ops: Operations<wgpu::Color>,
+        depth_ops: Operations<f32>,
+        stencil_ops: Operations<u32>,
     ) -> Result<RenderPass<'e>, SurfaceError> {
-        let render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        Ok(Self::begin_render_pass(
+            encoder,
+            surface_texture_view,
+            self.depth_stencil_view(),
+            color_ops,
+            depth_ops,
+            stencil_ops,
+        ))
+    }
+
+    /// Begins a render pass into the given attachments, e.g. of a [`SecondaryWindow`](super::SecondaryWindow).
+    /// The depth stencil view must be of [`depth_stencil_mode`](Self::depth_stencil_mode).
+    pub fn begin_render_pass<'e>(
+        encoder: &'e mut CommandEncoder,
+        color_view: &'e TextureView,
+        depth_stencil_view: Option<&'e TextureView>,
+        color_ops: Operations<wgpu::Color>,
+        depth_ops: Operations<f32>,
+        stencil_ops: Operations<u32>,
+    ) -> RenderPass<'e> {
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: None,
             color_attachments: &[Some(RenderPassColorAttachment {
-                view: &surface_texture_view,
+                view: color_view,
                 resolve_target: None,
-                ops: Operations {
-                    load: match clear_mode {
-                        CameraClearMode::Keep => LoadOp::Load,
-                        CameraClearMode::All { color, .. } => LoadOp::Clear(Color {
-                            r: color.r as f64,
-                            g: color.g as f64,
-                            b: color.b as f64,
-                            a: color.a as f64,
-                        }),
-                        CameraClearMode::DepthOnly { .. } => LoadOp::Load,
-                    },
-                    store: true,
-                },
+                ops: color_ops,
             })],
-            depth_stencil_attachment: self.depth_stencil.texture_view().map(|view| {
+            depth_stencil_attachment: depth_stencil_view.map(|view| {
                 RenderPassDepthStencilAttachment {
                     view,
-                    depth_ops: Some(Operations {
-                        load: match clear_mode {
-                            CameraClearMode::Keep => LoadOp::Load,
-                            CameraClearMode::All { depth, .. } => LoadOp::Clear(*depth),
-                            CameraClearMode::DepthOnly { depth, .. } => LoadOp::Clear(*depth),
-                        },
-                        store: true,
-                    }),
-                    stencil_ops: Some(Operations {
-                        load: match clear_mode {
-                            CameraClearMode::Keep => LoadOp::Load,
-                            CameraClearMode::All { stencil, .. } => LoadOp::Clear(*stencil),
-                            CameraClearMode::DepthOnly { stencil, .. } => LoadOp::Clear(*stencil),
-                        },
-                        store: true,
-                    }),
+                    depth_ops: Some(depth_ops),
+                    stencil_ops: Some(stencil_ops),
                 }
             }),
-        });
-        Ok(render_pass)
+        })
     }
 
-    /// Constructs a rendering command for the given object by encoding per-instance data into a buffer.
+    pub fn depth_stencil_mode(&self) -> DepthStencilMode {
+        self.depth_stencil.mode()
+    }
+
+    pub fn depth_stencil_view(&self) -> Option<&TextureView> {
+        self.depth_stencil.texture_view()
+    }
+
+    /// Returns `true` if there is a depth stencil buffer to render into.
+    pub fn has_depth_stencil(&self) -> bool {
+        self.depth_stencil.texture_view().is_some()
+    }
+
+    /// Sets the viewport of the given render pass and clears only the area of it.
+    /// The render pass should load the attachments to preserve the rest of the frame buffer.
+    pub fn clear_viewport<'e>(
+        &'e self,
+        render_pass: &mut RenderPass<'e>,
+        clear_mode: &CameraClearMode,
+        viewport: PhysicalViewport,
+    ) {
+        self.viewport_clearer
+            .clear(render_pass, clear_mode, viewport);
+    }
+
+    /// Prepares the skybox of the camera if it is cleared with [`CameraClearMode::Skybox`].
+    /// It must be called before beginning the render pass of the camera.
+    pub fn prepare_skybox(
+        &mut self,
+        clear_mode: &CameraClearMode,
+        transform_matrix: &Mat4,
+        view_projection: &Mat4,
+    ) -> Option<PreparedSkybox> {
+        match clear_mode {
+            CameraClearMode::Skybox { cubemap, .. } => Some(self.skybox_renderer.prepare(
+                &self.gfx_ctx.device,
+                &self.gfx_ctx.queue,
+                cubemap,
+                transform_matrix,
+                view_projection,
+            )),
+            _ => None,
+        }
+    }
+
+    /// Draws the prepared skybox into the current viewport. It must be called before drawing any geometry.
+    pub fn draw_skybox<'e>(&'e self, render_pass: &mut RenderPass<'e>, skybox: PreparedSkybox) {
+        self.skybox_renderer.draw(render_pass, skybox);
+    }
+
+    /// Constructs a rendering command for the given object by encoding per-instance data into a buffer,
+    /// and pushes it into `commands`.
     pub fn build_rendering_command<'r>(
         &mut self,
         object_id: ObjectId,
         object_hierarchy: &ObjectHierarchy,
         renderer: &'r dyn Renderer,
-    ) -> RenderingCommand<'r> {
+        commands: &mut Vec<RenderingCommand<'r>>,
+    ) {
         build_rendering_command(
             object_id,
             object_hierarchy,
             renderer,
             &mut self.frame_buffer_allocator,
+            commands,
         )
     }
 
-    pub fn finish_frame(&mut self, command_buffers: Vec<CommandBuffer>) {
-        self.gfx_ctx.queue.submit(
-            std::iter::once(self.frame_buffer_allocator.finish())
-                .chain(command_buffers.into_iter()),
-        );
+    pub fn finish_frame(&mut self, command_buffers: impl IntoIterator<Item = CommandBuffer>) {
+        self.gfx_ctx
+            .queue
+            .submit(std::iter::once(self.frame_buffer_allocator.finish()).chain(command_buffers));
         self.frame_buffer_allocator.recall();
+        self.frame_stats.frame_buffers = self.frame_buffer_allocator.stats();
+
+        if let Some(gpu_profiler) = self.gpu_profiler.as_mut() {
+            gpu_profiler.finish_frame();
+        }
+
+        if let Some(frame_capture) = self.frame_capture.as_mut() {
+            frame_capture.finish_frame();
+        }
+
+        #[cfg(feature = "gpu-resource-tracking")]
+        for kind in super::gpu_resource_registry().end_frame() {
+            log_warn!(
+                "the count of live {:?} resources keeps growing; it may be a leak",
+                kind
+            );
+        }
     }
 }
@@ -1,4 +1,7 @@
-use super::{GenericBufferAllocation, GenericBufferPool, HostBuffer};
+use super::{
+    GenericBufferAllocation, GenericBufferPool, GenericBufferPoolConfig, GenericBufferPoolStats,
+    HostBuffer,
+};
 use crate::gfx::GfxContextHandle;
 use std::mem::replace;
 use wgpu::{
@@ -6,6 +9,13 @@ use wgpu::{
     CommandEncoderDescriptor, Device,
 };
 
+/// Usage statistics of a [`FrameBufferAllocator`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FrameBufferAllocatorStats {
+    pub host: GenericBufferPoolStats,
+    pub device: GenericBufferPoolStats,
+}
+
 /// A buffer allocator that can be used to allocate buffers for a single frame.
 pub struct FrameBufferAllocator {
     gfx_context: GfxContextHandle,
@@ -20,15 +30,30 @@ impl FrameBufferAllocator {
     pub const PAGE_SIZE: BufferSize = unsafe { BufferSize::new_unchecked(1 * 1024 * 1024) };
 
     pub fn new(gfx_context: GfxContextHandle) -> FrameBufferAllocator {
+        Self::with_config(gfx_context, GenericBufferPoolConfig::new(Self::PAGE_SIZE))
+    }
+
+    /// Creates an allocator whose host and device pools use the given config.
+    pub fn with_config(
+        gfx_context: GfxContextHandle,
+        config: GenericBufferPoolConfig,
+    ) -> FrameBufferAllocator {
         Self {
-            staging_belt: StagingBelt::new(Self::PAGE_SIZE.get()),
+            staging_belt: StagingBelt::new(config.page_size.get()),
             staging_belt_encoder: create_staging_belt_encoder(&gfx_context.device),
-            host_buffer_list: GenericBufferPool::new(Self::PAGE_SIZE),
-            device_buffer_list: GenericBufferPool::new(Self::PAGE_SIZE),
+            host_buffer_list: GenericBufferPool::with_config(config),
+            device_buffer_list: GenericBufferPool::with_config(config),
             gfx_context,
         }
     }
 
+    pub fn stats(&self) -> FrameBufferAllocatorStats {
+        FrameBufferAllocatorStats {
+            host: self.host_buffer_list.stats(),
+            device: self.device_buffer_list.stats(),
+        }
+    }
+
     pub fn alloc_staging_buffer(
         &mut self,
         size: BufferAddress,
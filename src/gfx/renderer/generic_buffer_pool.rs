@@ -1,5 +1,6 @@
-use std::{cmp::Ordering, sync::Arc};
-use wgpu::{Buffer, BufferAddress, BufferSize, BufferSlice, Device};
+use crate::gfx::{GpuResourceKind, GpuResourceTracker};
+use std::{cmp::Ordering, collections::VecDeque, sync::Arc};
+use wgpu::{Buffer, BufferAddress, BufferSize, BufferSlice, Device, COPY_BUFFER_ALIGNMENT};
 
 /// Represents a buffer that can be used to allocate sub buffers from.
 pub trait GenericBuffer
@@ -134,6 +135,7 @@ pub struct GenericBufferPage<T> {
     size: BufferSize,
     /// The amount of bytes that are already allocated in this page.
     allocated: BufferAddress,
+    _tracker: GpuResourceTracker,
 }
 
 impl<T> GenericBufferPage<T>
@@ -141,10 +143,19 @@ where
     T: GenericBuffer,
 {
     pub fn new(device: &Device, size: BufferSize) -> Self {
+        Self::with_buffer(T::allocate(device, size), size)
+    }
+
+    pub fn with_buffer(buffer: Arc<T>, size: BufferSize) -> Self {
         Self {
-            buffer: T::allocate(device, size),
+            buffer,
             size,
             allocated: 0,
+            _tracker: GpuResourceTracker::new(
+                GpuResourceKind::Buffer,
+                Some("buffer pool page"),
+                size.get(),
+            ),
         }
     }
 
@@ -153,12 +164,13 @@ where
         self.size.get() - self.allocated
     }
 
-    /// Allocates a new sub buffer from this page.
-    pub fn allocate(&mut self, size: BufferSize) -> GenericBufferAllocation<T> {
-        debug_assert!(size.get() <= self.available_size());
+    /// Allocates a new sub buffer from this page. The page is consumed by `aligned_size`, which keeps
+    /// the offset of the next allocation aligned.
+    pub fn allocate(&mut self, size: BufferSize, aligned_size: u64) -> GenericBufferAllocation<T> {
+        debug_assert!(aligned_size <= self.available_size());
 
         let offset = self.allocated;
-        self.allocated += size.get();
+        self.allocated += aligned_size;
 
         GenericBufferAllocation {
             buffer: self.buffer.clone(),
@@ -168,15 +180,58 @@ where
     }
 }
 
+/// Configures how a [`GenericBufferPool`] allocates and releases its pages.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GenericBufferPoolConfig {
+    /// The size of a single page. Allocations larger than it get a page of their own.
+    pub page_size: BufferSize,
+    /// The alignment of the offsets of allocations, e.g. 256 for uniform buffers. It must be a power of two.
+    pub alignment: BufferAddress,
+    /// The number of frames whose peak usage decides whether to shrink. Zero disables shrinking.
+    pub shrink_window: usize,
+    /// Pages are released if the peak usage over the window is below this fraction of the capacity.
+    pub shrink_threshold: f32,
+}
+
+impl GenericBufferPoolConfig {
+    pub fn new(page_size: BufferSize) -> Self {
+        Self {
+            page_size,
+            alignment: COPY_BUFFER_ALIGNMENT,
+            shrink_window: 300,
+            shrink_threshold: 0.5,
+        }
+    }
+}
+
+/// Usage statistics of a [`GenericBufferPool`], in bytes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GenericBufferPoolStats {
+    /// The total size of the pages.
+    pub capacity: u64,
+    /// The number of pages.
+    pub pages: usize,
+    /// The bytes allocated in the last recalled frame.
+    pub last_frame_usage: u64,
+    /// The highest usage of a frame since the pool was created.
+    pub high_water_mark: u64,
+    /// The bytes released by shrinking since the pool was created.
+    pub released: u64,
+}
+
 /// Holds a list of buffers of type `T` and allocates sub buffers from them.
 pub struct GenericBufferPool<T>
 where
     T: GenericBuffer,
 {
-    /// The size of a single page in the buffer list.
-    page_size: BufferSize,
+    config: GenericBufferPoolConfig,
     /// A list of buffers. It is guaranteed that the buffers are always sorted by size in ascending order.
     pages: Vec<GenericBufferPage<T>>,
+    /// The bytes allocated in the current frame.
+    usage: u64,
+    /// The usage of the last frames, up to the shrink window.
+    usage_history: VecDeque<u64>,
+    stats: GenericBufferPoolStats,
 }
 
 impl<T> GenericBufferPool<T>
@@ -184,32 +239,88 @@ where
     T: GenericBuffer,
 {
     pub fn new(page_size: BufferSize) -> Self {
+        Self::with_config(GenericBufferPoolConfig::new(page_size))
+    }
+
+    pub fn with_config(config: GenericBufferPoolConfig) -> Self {
+        debug_assert!(config.alignment.is_power_of_two());
+
         Self {
-            page_size,
+            config,
             pages: Vec::new(),
+            usage: 0,
+            usage_history: VecDeque::with_capacity(config.shrink_window),
+            stats: GenericBufferPoolStats::default(),
         }
     }
 
-    /// Mark all pages as unused.
+    pub fn config(&self) -> &GenericBufferPoolConfig {
+        &self.config
+    }
+
+    pub fn stats(&self) -> GenericBufferPoolStats {
+        self.stats
+    }
+
+    /// Mark all pages as unused, ending the frame. Pages are released if the pool has been mostly unused
+    /// for the shrink window.
+    ///
+    /// Released pages are dropped rather than destroyed; wgpu keeps the buffers alive until the submitted
+    /// work that uses them completes, so it must be called after the frame has been submitted.
     pub fn recall(&mut self) {
-        // TODO: Drop some pages to prevent memory leaks.
         for page in &mut self.pages {
             page.allocated = 0;
         }
+
+        // Pages were sorted by the available size, which is now the size of each page.
+        self.pages.sort_by_key(|page| page.size);
+
+        self.stats.last_frame_usage = self.usage;
+        self.stats.high_water_mark = self.stats.high_water_mark.max(self.usage);
+        self.usage = 0;
+
+        if self.config.shrink_window != 0 {
+            if self.usage_history.len() == self.config.shrink_window {
+                self.usage_history.pop_front();
+            }
+
+            self.usage_history.push_back(self.stats.last_frame_usage);
+
+            if self.usage_history.len() == self.config.shrink_window {
+                self.shrink();
+            }
+        }
+
+        self.update_capacity();
     }
 
     /// Allocates a new buffer with the given size. It may allocate a new page if no page with enough space is available.
     pub fn allocate(&mut self, device: &Device, size: BufferSize) -> GenericBufferAllocation<T> {
+        self.allocate_with(size, |size| T::allocate(device, size))
+    }
+
+    fn allocate_with(
+        &mut self,
+        size: BufferSize,
+        new_buffer: impl FnOnce(BufferSize) -> Arc<T>,
+    ) -> GenericBufferAllocation<T> {
+        let aligned_size = align_to(size.get(), self.config.alignment);
         let result = self.pages.binary_search_by(|page| {
             let available_size = page.available_size();
-            let size = size.get();
-            available_size.cmp(&size)
+            available_size.cmp(&aligned_size)
         });
         let index = match result {
             Ok(index) => index,
             Err(index) => {
                 if index == self.pages.len() {
-                    self.append_page(device, self.page_size.max(size))
+                    let page_size = self
+                        .config
+                        .page_size
+                        .max(unsafe { BufferSize::new_unchecked(aligned_size) });
+                    self.append_page(GenericBufferPage::with_buffer(
+                        new_buffer(page_size),
+                        page_size,
+                    ))
                 } else {
                     index
                 }
@@ -217,7 +328,8 @@ where
         };
 
         let mut updated_page = self.pages.remove(index);
-        let allocation = updated_page.allocate(size);
+        let allocation = updated_page.allocate(size, aligned_size);
+        self.usage += aligned_size;
 
         let new_page_index = self
             .pages
@@ -236,12 +348,12 @@ where
     }
 
     /// Appends a new page to the buffer list. Returns the index of the new page.
-    fn append_page(&mut self, device: &Device, page_size: BufferSize) -> usize {
+    fn append_page(&mut self, page: GenericBufferPage<T>) -> usize {
         let index = self
             .pages
-            .binary_search_by(|page| {
+            .binary_search_by(|item| {
                 // We never return `Equal` here, because we want to insert the new page in correct order.
-                if page.available_size() < page_size.get() {
+                if item.available_size() < page.available_size() {
                     Ordering::Less
                 } else {
                     Ordering::Greater
@@ -250,9 +362,118 @@ where
             .err()
             .unwrap();
 
-        let page = GenericBufferPage::new(device, page_size);
         self.pages.insert(index, page);
+        self.update_capacity();
 
         index
     }
+
+    /// Releases the largest pages while the rest can still hold the peak usage of the window.
+    fn shrink(&mut self) {
+        let capacity = self.stats.capacity;
+        let peak_usage = self.usage_history.iter().copied().max().unwrap_or(0);
+
+        if capacity as f64 * self.config.shrink_threshold as f64 <= peak_usage as f64 {
+            return;
+        }
+
+        let mut remaining = capacity;
+
+        // Pages are sorted by size, since they are all unused now.
+        while let Some(page) = self.pages.last() {
+            let size = page.size.get();
+
+            if remaining - size < peak_usage {
+                break;
+            }
+
+            self.pages.pop();
+            remaining -= size;
+            self.stats.released += size;
+        }
+
+        // Waits for another window before shrinking again.
+        self.usage_history.clear();
+    }
+
+    fn update_capacity(&mut self) {
+        self.stats.capacity = self.pages.iter().map(|page| page.size.get()).sum();
+        self.stats.pages = self.pages.len();
+    }
+}
+
+fn align_to(size: u64, alignment: u64) -> u64 {
+    (size + alignment - 1) & !(alignment - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct MockBuffer;
+
+    impl GenericBuffer for MockBuffer {
+        fn allocate(_: &Device, _: BufferSize) -> Arc<Self> {
+            Arc::new(Self)
+        }
+    }
+
+    fn run_frame(pool: &mut GenericBufferPool<MockBuffer>, sizes: &[u64]) -> Vec<BufferAddress> {
+        let offsets = sizes
+            .iter()
+            .map(|&size| {
+                pool.allocate_with(BufferSize::new(size).unwrap(), |_| Arc::new(MockBuffer))
+                    .offset()
+            })
+            .collect();
+        pool.recall();
+        offsets
+    }
+
+    #[test]
+    fn pool_grows_for_bursts_and_shrinks_after_window() {
+        let mut pool = GenericBufferPool::with_config(GenericBufferPoolConfig {
+            page_size: BufferSize::new(1024).unwrap(),
+            alignment: 256,
+            shrink_window: 4,
+            shrink_threshold: 0.5,
+        });
+
+        // Offsets are aligned, and a page holds four aligned allocations.
+        assert_eq!(
+            run_frame(&mut pool, &[100, 4, 256, 1]),
+            vec![0, 256, 512, 768]
+        );
+        assert_eq!(pool.stats().capacity, 1024);
+        assert_eq!(pool.stats().last_frame_usage, 1024);
+
+        // A burst grows the pool, including a page of its own for an oversized allocation.
+        run_frame(&mut pool, &[1024, 1024, 4000]);
+        let stats = pool.stats();
+        assert_eq!(stats.pages, 3);
+        assert_eq!(stats.capacity, 1024 * 2 + 4096);
+        assert_eq!(stats.high_water_mark, 1024 * 2 + 4096);
+
+        // The burst is still in the window, so nothing is released yet.
+        run_frame(&mut pool, &[512]);
+        run_frame(&mut pool, &[512]);
+        assert_eq!(pool.stats().pages, 3);
+
+        // The window now holds only small frames; the largest pages are released.
+        run_frame(&mut pool, &[512]);
+        run_frame(&mut pool, &[512]);
+        run_frame(&mut pool, &[512]);
+        run_frame(&mut pool, &[512]);
+        let stats = pool.stats();
+        assert_eq!(stats.pages, 1);
+        assert_eq!(stats.capacity, 1024);
+        assert_eq!(stats.released, 1024 + 4096);
+        assert_eq!(stats.high_water_mark, 1024 * 2 + 4096);
+
+        // A steady usage above the threshold keeps the pages.
+        for _ in 0..8 {
+            run_frame(&mut pool, &[1024, 1024]);
+        }
+        assert_eq!(pool.stats().capacity, 2048);
+    }
 }
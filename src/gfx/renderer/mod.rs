@@ -1,11 +1,10 @@
 use super::{
-    semantic_bindings,
     semantic_inputs::{self},
-    CachedPipeline, Material,
+    BindGroupSource, CachedPipeline, Material, MaterialPropertyBlock,
 };
 use crate::object::{ObjectHierarchy, ObjectId};
 use parking_lot::RwLockReadGuard;
-use wgpu::{BindGroup, Buffer, BufferAddress, RenderPass, VertexStepMode};
+use wgpu::{BindGroup, Buffer, BufferAddress, RenderPass};
 use zerocopy::AsBytes;
 
 mod device_buffer;
@@ -32,47 +31,43 @@ pub struct RenderingCommand<'r> {
     pub bind_group_provider: &'r dyn BindGroupProvider,
     pub vertex_buffer_provider: &'r dyn VertexBufferProvider,
     pub instance_buffer: Option<GenericBufferAllocation<Buffer>>,
+    /// The overrides whose bind groups replace the bind groups of the material, by group index.
+    pub property_block: Option<&'r MaterialPropertyBlock>,
 }
 
 impl<'r> RenderingCommand<'r> {
-    /// Records a render pass for this rendering command.
+    /// Records a render pass for this rendering command. Only the states that differ from the previous command
+    /// recorded with the same `state` are set.
     pub fn render(
         &'r self,
         render_pass: &mut RenderPass<'r>,
+        state: &mut RenderPassState<'r>,
         camera_transform_bind_group: &'r BindGroup,
         screen_size_bind_group: &'r BindGroup,
     ) {
-        render_pass.set_pipeline(self.pipeline.as_ref());
+        state.set_pipeline(render_pass, &self.pipeline);
 
-        for binding in &self.material.shader.reflected_shader.bindings {
-            let key = if let Some(key) = binding.semantic_binding {
-                key
-            } else {
-                continue;
-            };
-
-            match key {
-                semantic_bindings::KEY_CAMERA_TRANSFORM => {
-                    render_pass.set_bind_group(binding.group, camera_transform_bind_group, &[]);
-                }
-                semantic_bindings::KEY_SCREEN_SIZE => {
-                    render_pass.set_bind_group(binding.group, screen_size_bind_group, &[]);
-                }
-                _ => {
-                    // TODO: Since this bind group is required, we should notify the user if it's not present.
-                    if let Some(bind_group) = self.bind_group_provider.bind_group(0, key) {
-                        render_pass.set_bind_group(binding.group, &bind_group, &[]);
-                    }
-                }
-            }
-        }
+        let material: &'r Material = &self.material;
 
-        for bind_group_index in self.material.bind_properties.values() {
-            let bind_group_holder = &self.material.bind_group_holders[bind_group_index.group_index];
+        for &(group, source) in material.bind_group_sources() {
+            let bind_group = match source {
+                BindGroupSource::CameraTransform => Some(camera_transform_bind_group),
+                BindGroupSource::ScreenSize => Some(screen_size_bind_group),
+                // TODO: Since this bind group is required, we should notify the user if it's not present.
+                BindGroupSource::Renderer(key) => self.bind_group_provider.bind_group(0, key),
+                BindGroupSource::Material(group_index) => self
+                    .property_block
+                    .and_then(|property_block| {
+                        property_block
+                            .bind_groups()
+                            .find(|(property_group, _)| *property_group == group)
+                    })
+                    .map(|(_, bind_group)| bind_group.as_ref())
+                    .or(material.bind_group_holders[group_index].bind_group.as_ref()),
+            };
 
-            // TODO: Since this bind group is required, we should notify the user if it's not present.
-            if let Some(bind_group) = bind_group_holder.bind_group.as_ref() {
-                render_pass.set_bind_group(bind_group_holder.group, bind_group, &[]);
+            if let Some(bind_group) = bind_group {
+                state.set_bind_group(render_pass, group, bind_group);
             }
         }
 
@@ -119,72 +114,168 @@ impl<'r> RenderingCommand<'r> {
     }
 }
 
-/// Constructs a rendering command for the given object by encoding per-instance data into a buffer.
+/// The state changes of a render pass that [`RenderPassState`] skips when they would not change anything. It is
+/// implemented by [`RenderPass`], and can be implemented by stand-ins that need no device, e.g. in benchmarks.
+pub trait RenderPassStateTarget<'r, P, B> {
+    fn set_pipeline(&mut self, pipeline: &'r P);
+    fn set_bind_group(&mut self, group: u32, bind_group: &'r B);
+}
+
+impl<'r> RenderPassStateTarget<'r, CachedPipeline, BindGroup> for RenderPass<'r> {
+    fn set_pipeline(&mut self, pipeline: &'r CachedPipeline) {
+        RenderPass::set_pipeline(self, pipeline.as_ref());
+    }
+
+    fn set_bind_group(&mut self, group: u32, bind_group: &'r BindGroup) {
+        RenderPass::set_bind_group(self, group, bind_group, &[]);
+    }
+}
+
+/// The pipeline and the bind groups set on a render pass by the commands recorded so far. Bind groups stay bound
+/// across pipelines, so a bind group that is set already is not set again.
+pub struct RenderPassState<'r, P = CachedPipeline, B = BindGroup> {
+    pipeline: Option<&'r P>,
+    bind_groups: Vec<Option<&'r B>>,
+    state_changes: u32,
+}
+
+impl<'r, P, B> Default for RenderPassState<'r, P, B> {
+    fn default() -> Self {
+        Self {
+            pipeline: None,
+            bind_groups: Vec::new(),
+            state_changes: 0,
+        }
+    }
+}
+
+impl<'r, P: PartialEq, B> RenderPassState<'r, P, B> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns the number of pipelines and bind groups that have been set.
+    pub fn state_changes(&self) -> u32 {
+        self.state_changes
+    }
+
+    pub fn set_pipeline(
+        &mut self,
+        render_pass: &mut impl RenderPassStateTarget<'r, P, B>,
+        pipeline: &'r P,
+    ) {
+        if self.pipeline == Some(pipeline) {
+            return;
+        }
+
+        render_pass.set_pipeline(pipeline);
+        self.pipeline = Some(pipeline);
+        self.state_changes += 1;
+    }
+
+    pub fn set_bind_group(
+        &mut self,
+        render_pass: &mut impl RenderPassStateTarget<'r, P, B>,
+        group: u32,
+        bind_group: &'r B,
+    ) {
+        let index = group as usize;
+
+        if self.bind_groups.len() <= index {
+            self.bind_groups.resize(index + 1, None);
+        }
+
+        if self.bind_groups[index].is_some_and(|current| std::ptr::eq(current, bind_group)) {
+            return;
+        }
+
+        render_pass.set_bind_group(group, bind_group);
+        self.bind_groups[index] = Some(bind_group);
+        self.state_changes += 1;
+    }
+}
+
+/// Constructs a rendering command for the given object by encoding per-instance data into a buffer,
+/// and pushes it into `commands`. The caller owns `commands`, so that it can be reused across frames.
 pub fn build_rendering_command<'r>(
     object_id: ObjectId,
     object_hierarchy: &ObjectHierarchy,
     renderer: &'r dyn Renderer,
     frame_buffer_allocator: &mut FrameBufferAllocator,
-) -> RenderingCommand<'r> {
+    commands: &mut Vec<RenderingCommand<'r>>,
+) {
     let matrix = object_hierarchy.matrix(object_id);
     let material = renderer.material();
 
+    let property_block = renderer.property_block();
+
     let instance_count = renderer.instance_count();
     let instance_data_provider = renderer.instance_data_provider();
-    let per_instance_buffer = frame_buffer_allocator.alloc_staging_buffer(
-        material.shader.reflected_shader.per_instance_input.stride
-            * instance_count as BufferAddress,
-    );
-
-    for instance in 0..instance_count {
-        let per_instance_buffer = per_instance_buffer.slice(
-            material.shader.reflected_shader.per_instance_input.stride * instance as BufferAddress,
-            material.shader.reflected_shader.per_instance_input.stride,
-        );
-
-        for (&key, input_data) in &material.semantic_inputs {
-            if input_data.step_mode != VertexStepMode::Instance {
-                continue;
-            }
+    let per_instance_size = material.shader.reflected_shader.per_instance_input.stride
+        * instance_count as BufferAddress;
+    // Even an empty staging buffer is allocated, so commands without per-instance data skip it.
+    let per_instance_buffer = if per_instance_size == 0 {
+        None
+    } else {
+        let per_instance_buffer = frame_buffer_allocator.alloc_staging_buffer(per_instance_size);
 
-            let size = material.shader.reflected_shader.per_instance_input.elements
-                [input_data.index]
-                .attribute
-                .format
-                .size();
-            let allocation = &mut per_instance_buffer.slice(input_data.offset, size);
+        for instance in 0..instance_count {
+            let per_instance_buffer = per_instance_buffer.slice(
+                material.shader.reflected_shader.per_instance_input.stride
+                    * instance as BufferAddress,
+                material.shader.reflected_shader.per_instance_input.stride,
+            );
 
-            match key {
-                semantic_inputs::KEY_TRANSFORM_ROW_0 => {
-                    allocation.copy_from_slice(matrix.row(0).as_bytes())
-                }
-                semantic_inputs::KEY_TRANSFORM_ROW_1 => {
-                    allocation.copy_from_slice(matrix.row(1).as_bytes())
-                }
-                semantic_inputs::KEY_TRANSFORM_ROW_2 => {
-                    allocation.copy_from_slice(matrix.row(2).as_bytes())
-                }
-                semantic_inputs::KEY_TRANSFORM_ROW_3 => {
-                    allocation.copy_from_slice(matrix.row(3).as_bytes())
+            for (key, input_data) in material.instance_semantic_inputs() {
+                let size = material.shader.reflected_shader.per_instance_input.elements
+                    [input_data.index]
+                    .attribute
+                    .format
+                    .size();
+                let allocation = &mut per_instance_buffer.slice(input_data.offset, size);
+
+                match *key {
+                    semantic_inputs::KEY_TRANSFORM_ROW_0 => {
+                        allocation.copy_from_slice(matrix.row(0).as_bytes())
+                    }
+                    semantic_inputs::KEY_TRANSFORM_ROW_1 => {
+                        allocation.copy_from_slice(matrix.row(1).as_bytes())
+                    }
+                    semantic_inputs::KEY_TRANSFORM_ROW_2 => {
+                        allocation.copy_from_slice(matrix.row(2).as_bytes())
+                    }
+                    semantic_inputs::KEY_TRANSFORM_ROW_3 => {
+                        allocation.copy_from_slice(matrix.row(3).as_bytes())
+                    }
+                    _ => {
+                        instance_data_provider.copy_per_instance_data(instance, *key, allocation);
+                    }
                 }
-                _ => {
-                    instance_data_provider.copy_per_instance_data(instance, key, allocation);
+            }
+
+            for property in material.instance_properties.values() {
+                if let Some(value) = &property.value {
+                    per_instance_buffer
+                        .slice(property.offset, value.to_vertex_format().size())
+                        .copy_from_slice(value.as_bytes());
                 }
             }
-        }
 
-        for property in material.instance_properties.values() {
-            if let Some(value) = &property.value {
-                per_instance_buffer
-                    .slice(property.offset, value.to_vertex_format().size())
-                    .copy_from_slice(value.as_bytes());
+            if let Some(property_block) = property_block {
+                for (offset, value) in
+                    property_block.instance_overrides(&material.instance_properties)
+                {
+                    per_instance_buffer
+                        .slice(offset, value.to_vertex_format().size())
+                        .copy_from_slice(value.as_bytes());
+                }
             }
         }
-    }
 
-    let per_instance_buffer = frame_buffer_allocator.commit_staging_buffer(per_instance_buffer);
+        frame_buffer_allocator.commit_staging_buffer(per_instance_buffer)
+    };
 
-    RenderingCommand {
+    commands.push(RenderingCommand {
         pipeline: renderer.pipeline(),
         material,
         instance_count,
@@ -192,5 +283,6 @@ pub fn build_rendering_command<'r>(
         bind_group_provider: renderer.bind_group_provider(),
         vertex_buffer_provider: renderer.vertex_buffer_provider(),
         instance_buffer: per_instance_buffer,
-    }
+        property_block,
+    });
 }
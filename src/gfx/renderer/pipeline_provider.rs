@@ -1,11 +1,23 @@
 use super::RendererVertexBufferLayout;
-use crate::gfx::{BufferLayout, CachedPipeline, MaterialHandle, PipelineCache, ShaderManager};
-use wgpu::{DepthStencilState, PrimitiveState, VertexAttribute, VertexStepMode};
+use crate::gfx::{
+    BufferLayout, CachedPipeline, MaterialDepthMode, MaterialHandle, PipelineCache,
+    RenderTargetState, ShaderManager,
+};
+use asset::assets::{MaterialBlendMode, MaterialCullMode, MaterialFrontFace};
+use wgpu::{
+    CompareFunction, DepthStencilState, Face, FrontFace, PrimitiveState, PrimitiveTopology,
+    VertexAttribute, VertexStepMode,
+};
 
 // TODO: Should we make buffer layouts and states to be shared across all renderer instances?
 pub struct PipelineProvider {
     is_dirty: bool,
     pipeline: Option<CachedPipeline>,
+    /// The target and the states, with the overrides of the material, that `pipeline` was created for.
+    target: Option<RenderTargetState>,
+    pipeline_primitive: Option<PrimitiveState>,
+    pipeline_blend_mode: Option<MaterialBlendMode>,
+    pipeline_depth_mode: Option<MaterialDepthMode>,
     material: Option<MaterialHandle>,
     buffer_layouts: Vec<RendererVertexBufferLayout>,
     primitive: Option<PrimitiveState>,
@@ -17,6 +29,10 @@ impl PipelineProvider {
         Self {
             is_dirty: true,
             pipeline: None,
+            target: None,
+            pipeline_primitive: None,
+            pipeline_blend_mode: None,
+            pipeline_depth_mode: None,
             material: None,
             buffer_layouts: Vec::new(),
             primitive: None,
@@ -53,27 +69,37 @@ impl PipelineProvider {
         shader_mgr: &ShaderManager,
         pipeline_cache: &mut PipelineCache,
     ) -> Option<CachedPipeline> {
-        if !self.is_dirty {
-            if let Some(pipeline) = self.pipeline.clone() {
-                return Some(pipeline);
-            }
-        }
-
         let material = if let Some(material) = &self.material {
             material.read()
         } else {
             return None;
         };
 
-        if self.buffer_layouts.len() == 0 {
+        let primitive = if let Some(primitive) = self.primitive {
+            material_primitive(
+                primitive,
+                material.topology,
+                material.cull_mode,
+                material.front_face,
+            )
+        } else {
             return None;
+        };
+
+        if !self.is_dirty
+            && self.target == Some(pipeline_cache.target())
+            && self.pipeline_primitive == Some(primitive)
+            && self.pipeline_blend_mode == material.blend_mode
+            && self.pipeline_depth_mode == material.depth_mode
+        {
+            if let Some(pipeline) = self.pipeline.clone() {
+                return Some(pipeline);
+            }
         }
 
-        let primitive = if let Some(primitive) = self.primitive.clone() {
-            primitive
-        } else {
+        if self.buffer_layouts.len() == 0 {
             return None;
-        };
+        }
         let mut buffer_layouts =
             Vec::from_iter(self.buffer_layouts.iter().map(|layout| BufferLayout {
                 array_stride: layout.array_stride,
@@ -122,16 +148,244 @@ impl PipelineProvider {
 
         let pipeline = pipeline_cache.create_pipeline(
             shader_mgr,
-            material.pipeline_layout.clone(),
-            material.shader.clone(),
+            &material,
             buffer_layouts,
             primitive,
-            self.depth_stencil.clone(),
+            material_depth_stencil(self.depth_stencil.clone(), material.depth_mode),
         );
 
         self.is_dirty = false;
         self.pipeline = Some(pipeline.clone());
+        self.target = Some(pipeline_cache.target());
+        self.pipeline_primitive = Some(primitive);
+        self.pipeline_blend_mode = material.blend_mode;
+        self.pipeline_depth_mode = material.depth_mode;
 
         Some(pipeline)
     }
 }
+
+/// Returns the primitive state of the renderer with the overrides of the material.
+fn material_primitive(
+    primitive: PrimitiveState,
+    topology: PrimitiveTopology,
+    cull_mode: Option<MaterialCullMode>,
+    front_face: Option<MaterialFrontFace>,
+) -> PrimitiveState {
+    let primitive = PrimitiveState {
+        cull_mode: match cull_mode {
+            Some(MaterialCullMode::None) => None,
+            Some(MaterialCullMode::Front) => Some(Face::Front),
+            Some(MaterialCullMode::Back) => Some(Face::Back),
+            None => primitive.cull_mode,
+        },
+        front_face: match front_face {
+            Some(MaterialFrontFace::Cw) => FrontFace::Cw,
+            Some(MaterialFrontFace::Ccw) => FrontFace::Ccw,
+            None => primitive.front_face,
+        },
+        ..primitive
+    };
+
+    primitive_with_topology(primitive, topology)
+}
+
+/// Returns the depth stencil state of the renderer with the depth mode of the material.
+fn material_depth_stencil(
+    depth_stencil: Option<DepthStencilState>,
+    depth_mode: Option<MaterialDepthMode>,
+) -> Option<DepthStencilState> {
+    let depth_stencil = depth_stencil?;
+    let (depth_write_enabled, depth_compare) = match depth_mode {
+        Some(MaterialDepthMode::ReadWrite) => (true, CompareFunction::Less),
+        Some(MaterialDepthMode::ReadOnly) => (false, CompareFunction::Less),
+        Some(MaterialDepthMode::Always) => (false, CompareFunction::Always),
+        None => return Some(depth_stencil),
+    };
+
+    Some(DepthStencilState {
+        depth_write_enabled,
+        depth_compare,
+        ..depth_stencil
+    })
+}
+
+/// Returns the primitive state with the topology of the material. Strip index formats and
+/// culling only apply to triangles, so they are cleared for lines and points.
+fn primitive_with_topology(
+    primitive: PrimitiveState,
+    topology: PrimitiveTopology,
+) -> PrimitiveState {
+    if primitive.topology == topology {
+        return primitive;
+    }
+
+    PrimitiveState {
+        topology,
+        strip_index_format: None,
+        cull_mode: if topology == PrimitiveTopology::TriangleList {
+            primitive.cull_mode
+        } else {
+            None
+        },
+        ..primitive
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gfx::{render_test_triangles, test_gfx_ctx};
+    use wgpu::PolygonMode;
+    use winit::dpi::PhysicalSize;
+
+    /// Returns whether the rasterizer culls the triangle, given its vertices in normalized device coordinates.
+    fn is_culled(primitive: &PrimitiveState, triangle: [[f32; 2]; 3]) -> bool {
+        let [a, b, c] = triangle;
+        let area = (b[0] - a[0]) * (c[1] - a[1]) - (c[0] - a[0]) * (b[1] - a[1]);
+        let is_front = (0.0 < area) == (primitive.front_face == FrontFace::Ccw);
+
+        match primitive.cull_mode {
+            Some(Face::Front) => is_front,
+            Some(Face::Back) => !is_front,
+            None => false,
+        }
+    }
+
+    #[test]
+    fn line_list_material_draws_lines() {
+        let primitive = PrimitiveState {
+            topology: PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: FrontFace::Ccw,
+            cull_mode: Some(Face::Back),
+            unclipped_depth: false,
+            polygon_mode: PolygonMode::Fill,
+            conservative: false,
+        };
+
+        let lines = primitive_with_topology(primitive, PrimitiveTopology::LineList);
+        assert_eq!(lines.topology, PrimitiveTopology::LineList);
+        assert_eq!(lines.cull_mode, None);
+        assert_eq!(lines.front_face, FrontFace::Ccw);
+
+        let points = primitive_with_topology(primitive, PrimitiveTopology::PointList);
+        assert_eq!(points.topology, PrimitiveTopology::PointList);
+
+        assert_eq!(
+            primitive_with_topology(primitive, PrimitiveTopology::TriangleList),
+            primitive
+        );
+    }
+
+    #[test]
+    fn back_face_culled_quad_is_hidden_from_behind() {
+        // Sprites are wound counter-clockwise and not culled.
+        let primitive = PrimitiveState {
+            topology: PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: FrontFace::Ccw,
+            cull_mode: None,
+            unclipped_depth: false,
+            polygon_mode: PolygonMode::Fill,
+            conservative: false,
+        };
+        // A quad wound clockwise as in PMX models, and the same quad viewed from behind.
+        let front = [
+            [[-1.0, -1.0], [-1.0, 1.0], [1.0, 1.0]],
+            [[-1.0, -1.0], [1.0, 1.0], [1.0, -1.0]],
+        ];
+        let behind = front.map(|triangle| triangle.map(|[x, y]| [-x, y]));
+        let visible_triangles = |primitive: &PrimitiveState, quad: &[[[f32; 2]; 3]; 2]| {
+            quad.iter()
+                .filter(|&&triangle| !is_culled(primitive, triangle))
+                .count()
+        };
+
+        let culled = material_primitive(
+            primitive,
+            PrimitiveTopology::TriangleList,
+            Some(MaterialCullMode::Back),
+            Some(MaterialFrontFace::Cw),
+        );
+        assert_eq!(culled.cull_mode, Some(Face::Back));
+        assert_eq!(culled.front_face, FrontFace::Cw);
+        assert_eq!(visible_triangles(&culled, &front), 2);
+        assert_eq!(visible_triangles(&culled, &behind), 0);
+
+        let double_sided = material_primitive(
+            primitive,
+            PrimitiveTopology::TriangleList,
+            Some(MaterialCullMode::None),
+            Some(MaterialFrontFace::Cw),
+        );
+        assert_eq!(visible_triangles(&double_sided, &front), 2);
+        assert_eq!(visible_triangles(&double_sided, &behind), 2);
+
+        // Without overrides, the state of the renderer is kept.
+        assert_eq!(
+            material_primitive(primitive, PrimitiveTopology::TriangleList, None, None),
+            primitive
+        );
+    }
+
+    #[test]
+    fn back_face_culled_quad_is_hidden_from_behind_on_gpu() {
+        let gfx_ctx = match test_gfx_ctx(PhysicalSize::new(4, 4)) {
+            Some(gfx_ctx) => gfx_ctx,
+            None => {
+                eprintln!("skipped: no adapter found");
+                return;
+            }
+        };
+        let primitive = PrimitiveState {
+            topology: PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: FrontFace::Ccw,
+            cull_mode: None,
+            unclipped_depth: false,
+            polygon_mode: PolygonMode::Fill,
+            conservative: false,
+        };
+        // A quad covering the target, wound clockwise as in PMX models, and the same quad viewed from behind.
+        let front = [
+            [[-1.0, -1.0], [-1.0, 1.0], [1.0, 1.0]],
+            [[-1.0, -1.0], [1.0, 1.0], [1.0, -1.0]],
+        ];
+        let behind = front.map(|triangle| triangle.map(|[x, y]| [-x, y]));
+        let green = [0, 255, 0, 255];
+        let red = [255, 0, 0, 255];
+        let render = |primitive: PrimitiveState, quad: &[[[f32; 2]; 3]]| {
+            render_test_triangles(
+                &gfx_ctx,
+                quad,
+                [0.0, 1.0, 0.0, 1.0],
+                wgpu::Color::RED,
+                primitive,
+                None,
+            )
+        };
+
+        let culled = material_primitive(
+            primitive,
+            PrimitiveTopology::TriangleList,
+            Some(MaterialCullMode::Back),
+            Some(MaterialFrontFace::Cw),
+        );
+        assert!(render(culled, &front).iter().all(|&pixel| pixel == green));
+        assert!(render(culled, &behind).iter().all(|&pixel| pixel == red));
+
+        let double_sided = material_primitive(
+            primitive,
+            PrimitiveTopology::TriangleList,
+            Some(MaterialCullMode::None),
+            Some(MaterialFrontFace::Cw),
+        );
+        assert!(render(double_sided, &front)
+            .iter()
+            .all(|&pixel| pixel == green));
+        assert!(render(double_sided, &behind)
+            .iter()
+            .all(|&pixel| pixel == green));
+    }
+}
@@ -1,5 +1,8 @@
 use super::{GenericBufferAllocation, HostBuffer};
-use crate::gfx::{CachedPipeline, Material, SemanticShaderBindingKey, SemanticShaderInputKey};
+use crate::gfx::{
+    CachedPipeline, Material, MaterialPropertyBlock, SemanticShaderBindingKey,
+    SemanticShaderInputKey,
+};
 use parking_lot::RwLockReadGuard;
 use wgpu::{BindGroup, Buffer, BufferAddress};
 
@@ -29,6 +32,11 @@ pub trait Renderer {
     fn vertex_buffer_provider(&self) -> &dyn VertexBufferProvider;
 
     fn instance_data_provider(&self) -> &dyn InstanceDataProvider;
+
+    /// Returns the overrides of the material properties for this renderer.
+    fn property_block(&self) -> Option<&MaterialPropertyBlock> {
+        None
+    }
 }
 
 pub trait BindGroupProvider {
@@ -1,27 +1,34 @@
-use crate::gfx::{
-    semantic_inputs::{self, KEY_NORMAL, KEY_POSITION, KEY_UV},
-    BindGroupProvider, CachedPipeline, GenericBufferAllocation, HostBuffer, InstanceDataProvider,
-    Material, MaterialHandle, MeshHandle, PipelineCache, PipelineProvider, Renderer,
-    RendererVertexBufferAttribute, RendererVertexBufferLayout, SemanticShaderBindingKey,
-    SemanticShaderInputKey, ShaderManager, VertexBuffer, VertexBufferProvider,
+use crate::{
+    gfx::{
+        semantic_inputs::{self, KEY_NORMAL, KEY_POSITION, KEY_UV},
+        BindGroupProvider, CachedPipeline, GenericBufferAllocation, HostBuffer,
+        InstanceDataProvider, Material, MaterialHandle, MaterialPropertyBlock, MeshHandle,
+        PipelineCache, PipelineProvider, Renderer, RendererVertexBufferAttribute,
+        RendererVertexBufferLayout, SemanticShaderBindingKey, SemanticShaderInputKey,
+        ShaderManager, VertexBuffer, VertexBufferProvider,
+    },
+    math::{Mat4, AABB},
 };
+use codegen::Component;
 use parking_lot::RwLockReadGuard;
-use specs::{prelude::*, Component};
-use std::mem::size_of;
+use specs::prelude::*;
+use std::{mem::size_of, sync::Arc};
 use wgpu::{
-    util::{BufferInitDescriptor, DeviceExt},
-    BindGroup, Buffer, BufferAddress, BufferSize, BufferUsages, CompareFunction, DepthStencilState,
-    Device, Face, FrontFace, PolygonMode, PrimitiveState, PrimitiveTopology, TextureFormat,
+    BindGroup, Buffer, BufferAddress, CompareFunction, DepthStencilState, Device, Face, FrontFace,
+    PolygonMode, PrimitiveState, PrimitiveTopology, Queue, TextureFormat,
 };
-use zerocopy::AsBytes;
 
 #[derive(Component)]
 #[storage(HashMapStorage)]
+#[auto_register]
 pub struct MeshRenderer {
     mask: u32,
     pipeline_provider: PipelineProvider,
     mesh: Option<MeshHandle>,
     vertex_buffer: Option<GenericBufferAllocation<Buffer>>,
+    /// The number of vertices in the buffer. Faces are not always triangles, e.g. in line meshes.
+    vertex_count: u32,
+    property_block: Arc<MaterialPropertyBlock>,
 }
 
 impl MeshRenderer {
@@ -67,6 +74,8 @@ impl MeshRenderer {
             pipeline_provider,
             mesh: None,
             vertex_buffer: None,
+            vertex_count: 0,
+            property_block: Arc::new(MaterialPropertyBlock::new()),
         }
     }
 
@@ -74,53 +83,66 @@ impl MeshRenderer {
         self.mask
     }
 
+    pub fn mesh(&self) -> Option<&MeshHandle> {
+        self.mesh.as_ref()
+    }
+
+    /// Returns the local-space AABB of the mesh, or `None` if there is no mesh.
+    pub fn local_aabb(&self) -> Option<AABB> {
+        self.mesh.as_ref().and_then(|mesh| mesh.local_aabb())
+    }
+
+    /// Returns the world-space AABB of the mesh, transformed by the given object matrix.
+    pub fn world_aabb(&self, matrix: &Mat4) -> Option<AABB> {
+        self.local_aabb().map(|aabb| aabb.transformed(matrix))
+    }
+
     pub fn set_mask(&mut self, mask: u32) {
         self.mask = mask;
     }
 
-    pub fn set_material(&mut self, material: MaterialHandle) {
-        self.pipeline_provider.set_material(material);
+    pub fn property_block(&self) -> &MaterialPropertyBlock {
+        &self.property_block
     }
 
-    pub fn set_mesh(&mut self, mesh: MeshHandle, device: &Device) {
-        if mesh.data.vertices.is_empty() {
-            self.mesh = None;
-            self.vertex_buffer = None;
+    /// Returns the overrides of the material properties for this renderer, e.g. to tint only this object.
+    pub fn property_block_mut(&mut self) -> &mut MaterialPropertyBlock {
+        Arc::make_mut(&mut self.property_block)
+    }
+
+    /// Uploads the uniform overrides of the property block. The render system calls it every frame.
+    pub fn prepare_property_block(&mut self, device: &Device, queue: &Queue) {
+        if self.property_block.is_empty() && self.property_block.bind_groups().next().is_none() {
             return;
         }
 
-        self.mesh = Some(mesh.clone());
-
-        let mut vertices = Vec::with_capacity(mesh.data.faces.len() * 3 * (3 + 3 + 2));
-        let uvs = mesh.data.texture_coords[0].as_ref().unwrap();
+        let material = if let Some(material) = self.pipeline_provider.material() {
+            material.clone()
+        } else {
+            return;
+        };
 
-        for face in &mesh.data.faces {
-            for &face_index in &face.0 {
-                let vertex = &mesh.data.vertices[face_index as usize];
-                vertices.push(vertex.x);
-                vertices.push(vertex.y);
-                vertices.push(vertex.z);
+        Arc::make_mut(&mut self.property_block).prepare(&material.read(), device, queue);
+    }
 
-                let normal = &mesh.data.normals[face_index as usize];
-                vertices.push(normal.x);
-                vertices.push(normal.y);
-                vertices.push(normal.z);
+    pub fn set_material(&mut self, material: MaterialHandle) {
+        self.pipeline_provider.set_material(material);
+    }
 
-                let uv = &uvs[face_index as usize];
-                vertices.push(uv.x);
-                vertices.push(uv.y);
-            }
-        }
+    /// Draws the given mesh, uploading it if it has not been uploaded yet.
+    pub fn set_mesh(&mut self, mesh: MeshHandle, device: &Device) {
+        let (vertex_buffer, vertex_count) = if let Some(vertex_buffer) = mesh.vertex_buffer(device)
+        {
+            vertex_buffer
+        } else {
+            self.mesh = None;
+            self.vertex_buffer = None;
+            return;
+        };
 
-        self.vertex_buffer = Some(GenericBufferAllocation::new(
-            device.create_buffer_init(&BufferInitDescriptor {
-                label: None,
-                contents: vertices.as_bytes(),
-                usage: BufferUsages::VERTEX,
-            }),
-            0,
-            BufferSize::new((size_of::<f32>() * vertices.len()) as u64).unwrap(),
-        ));
+        self.mesh = Some(mesh);
+        self.vertex_buffer = Some(vertex_buffer);
+        self.vertex_count = vertex_count;
     }
 
     pub fn sub_renderer(
@@ -133,15 +155,15 @@ impl MeshRenderer {
             .obtain_pipeline(shader_mgr, pipeline_cache)?;
         let material = self.pipeline_provider.material().cloned()?;
         let vertex_buffer = self.vertex_buffer.clone()?;
-        let mesh = self.mesh.as_ref()?;
 
         Some(MeshSubRenderer {
             pipeline,
             material,
-            vertex_count: mesh.data.faces.len() as u32 * 3,
+            vertex_count: self.vertex_count,
             bind_group_provider: MeshRendererBindGroupProvider,
             vertex_buffer_provider: MeshRendererVertexBufferProvider { vertex_buffer },
             instance_data_provider: MeshRendererInstanceDataProvider,
+            property_block: self.property_block.clone(),
         })
     }
 }
@@ -153,6 +175,7 @@ pub struct MeshSubRenderer {
     bind_group_provider: MeshRendererBindGroupProvider,
     vertex_buffer_provider: MeshRendererVertexBufferProvider,
     instance_data_provider: MeshRendererInstanceDataProvider,
+    property_block: Arc<MaterialPropertyBlock>,
 }
 
 impl Renderer for MeshSubRenderer {
@@ -183,6 +206,10 @@ impl Renderer for MeshSubRenderer {
     fn instance_data_provider(&self) -> &dyn InstanceDataProvider {
         &self.instance_data_provider
     }
+
+    fn property_block(&self) -> Option<&MaterialPropertyBlock> {
+        Some(&self.property_block)
+    }
 }
 
 struct MeshRendererBindGroupProvider;
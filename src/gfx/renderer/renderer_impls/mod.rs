@@ -1,7 +1,9 @@
 mod mesh_renderer;
+mod sprite_renderer;
 mod ui_element_renderer;
 mod ui_text_renderer;
 
 pub use mesh_renderer::*;
+pub use sprite_renderer::*;
 pub use ui_element_renderer::*;
 pub use ui_text_renderer::*;
@@ -0,0 +1,748 @@
+use crate::{
+    gfx::{
+        semantic_bindings,
+        semantic_inputs::{self, KEY_POSITION},
+        BindGroupLayoutCache, BindGroupProvider, CachedPipeline, Color, GenericBufferAllocation,
+        HostBuffer, InstanceDataProvider, Material, MaterialHandle, MaterialPropertyBlock,
+        PipelineCache, PipelineProvider, Renderer, RendererVertexBufferAttribute,
+        RendererVertexBufferLayout, SemanticShaderBindingKey, SemanticShaderInputKey,
+        ShaderManager, SpriteHandle, SpriteTexelMapping, VertexBuffer, VertexBufferProvider,
+    },
+    math::{Mat4, Vec2, Vec3, Vec4},
+};
+use codegen::Component;
+use parking_lot::RwLockReadGuard;
+use specs::prelude::*;
+use std::{cmp::Ordering, mem::size_of, sync::Arc};
+use wgpu::{
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayoutEntry, BindingResource,
+    BindingType, Buffer, BufferAddress, CompareFunction, DepthStencilState, Device, FrontFace,
+    PolygonMode, PrimitiveState, PrimitiveTopology, Queue, SamplerBindingType, ShaderStages,
+    TextureFormat, TextureSampleType, TextureViewDimension,
+};
+use zerocopy::AsBytes;
+
+/// How a [`SpriteRenderer`] orients its quad.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SpriteFacing {
+    /// Lies on the XY plane of the object.
+    Plane,
+    /// Faces the camera, keeping the position and the scale of the object.
+    Camera,
+}
+
+/// The axis that sprites of the same sorting layer and order are sorted along. Sprites further along
+/// the axis are drawn first, so that the nearer ones are drawn over them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpriteSortAxis {
+    /// The forward direction of the camera, which draws sprites back to front.
+    CameraForward,
+    /// A world-space axis, e.g. [`Vec3::UP`] for top-down scenes where higher sprites are behind.
+    World(Vec3),
+}
+
+/// The camera that sprites are drawn for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpriteView {
+    pub position: Vec3,
+    pub right: Vec3,
+    pub up: Vec3,
+    pub forward: Vec3,
+}
+
+impl SpriteView {
+    /// Builds the view from the transform matrix of the camera object.
+    pub fn from_matrix(matrix: &Mat4) -> Self {
+        let axis = |axis: Vec3| Vec3::from(Vec4::from_vec3(axis, 0.0) * matrix).normalized();
+
+        Self {
+            position: Vec3::from(matrix.row(3)),
+            right: axis(Vec3::RIGHT),
+            up: axis(Vec3::UP),
+            forward: axis(Vec3::FORWARD),
+        }
+    }
+}
+
+/// The position of a sprite in the drawing order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpriteSortKey {
+    pub sorting_layer: i32,
+    pub order: i32,
+    /// The position along the sort axis.
+    pub depth: f32,
+}
+
+impl SpriteSortKey {
+    /// Compares in drawing order: by sorting layer, then by order, then the deepest first.
+    pub fn drawing_order(&self, other: &Self) -> Ordering {
+        self.sorting_layer
+            .cmp(&other.sorting_layer)
+            .then(self.order.cmp(&other.order))
+            .then(other.depth.total_cmp(&self.depth))
+    }
+}
+
+/// A quad of a sprite in world space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpriteInstance {
+    pub origin: Vec3,
+    pub axis_x: Vec3,
+    pub axis_y: Vec3,
+    pub size: Vec2,
+    /// The offset of the bottom-left corner from the origin, along the axes.
+    pub offset: Vec2,
+    pub uv_min: Vec2,
+    pub uv_max: Vec2,
+    pub color: Color,
+}
+
+impl SpriteInstance {
+    /// Returns the world-space corners of the quad.
+    pub fn corners(&self) -> [Vec3; 4] {
+        let corner = |x: f32, y: f32| {
+            self.origin
+                + self.axis_x * (self.offset.x + self.size.x * x)
+                + self.axis_y * (self.offset.y + self.size.y * y)
+        };
+        [
+            corner(0.0, 0.0),
+            corner(1.0, 0.0),
+            corner(1.0, 1.0),
+            corner(0.0, 1.0),
+        ]
+    }
+
+    /// Returns `false` if the quad lies entirely outside of the frustum of the view-projection matrix.
+    /// Only the side planes are tested; the depth range is left to the clipper.
+    pub fn is_visible(&self, view_projection: &Mat4) -> bool {
+        let clip = self
+            .corners()
+            .map(|corner| Vec4::from_vec3(corner, 1.0) * view_projection);
+        let is_outside = |f: fn(&Vec4) -> bool| clip.iter().all(f);
+
+        !(is_outside(|p| p.x < -p.w)
+            || is_outside(|p| p.w < p.x)
+            || is_outside(|p| p.y < -p.w)
+            || is_outside(|p| p.w < p.y))
+    }
+}
+
+/// Draws a sprite in world space, sorted among the other sprites by its sorting layer and order.
+/// Sprites that share the material, the texture and an empty property block are drawn in one batch.
+#[derive(Component)]
+#[storage(HashMapStorage)]
+#[auto_register]
+pub struct SpriteRenderer {
+    mask: u32,
+    color: Color,
+    flip_x: bool,
+    flip_y: bool,
+    pivot: Vec2,
+    sorting_layer: i32,
+    order: i32,
+    facing: SpriteFacing,
+    pixels_per_unit: f32,
+    pixel_snap: bool,
+    pipeline_provider: PipelineProvider,
+    sprite: Option<SpriteHandle>,
+    mapping: Option<SpriteTexelMapping>,
+    sprite_texture_bind_group: Option<Arc<BindGroup>>,
+    sprite_sampler_bind_group: Option<Arc<BindGroup>>,
+    property_block: Arc<MaterialPropertyBlock>,
+}
+
+impl SpriteRenderer {
+    pub fn new() -> Self {
+        let mut pipeline_provider = PipelineProvider::new();
+
+        pipeline_provider.set_buffer_layouts(vec![RendererVertexBufferLayout {
+            array_stride: size_of::<[f32; 3]>() as BufferAddress,
+            attributes: vec![RendererVertexBufferAttribute {
+                key: KEY_POSITION,
+                offset: 0,
+            }],
+        }]);
+        // Flipped and camera-facing sprites may show either side.
+        pipeline_provider.set_primitive(PrimitiveState {
+            topology: PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: FrontFace::Ccw,
+            cull_mode: None,
+            unclipped_depth: false,
+            polygon_mode: PolygonMode::Fill,
+            conservative: false,
+        });
+        // Sprites are sorted instead of depth-written, but still hidden behind opaque meshes.
+        pipeline_provider.set_depth_stencil(Some(DepthStencilState {
+            format: TextureFormat::Depth32Float,
+            depth_write_enabled: false,
+            depth_compare: CompareFunction::LessEqual,
+            stencil: Default::default(),
+            bias: Default::default(),
+        }));
+
+        Self {
+            mask: 0xFFFF_FFFF,
+            color: Color::white(),
+            flip_x: false,
+            flip_y: false,
+            pivot: Vec2::new(0.5, 0.5),
+            sorting_layer: 0,
+            order: 0,
+            facing: SpriteFacing::Plane,
+            pixels_per_unit: 100.0,
+            pixel_snap: false,
+            pipeline_provider,
+            sprite: None,
+            mapping: None,
+            sprite_texture_bind_group: None,
+            sprite_sampler_bind_group: None,
+            property_block: Arc::new(MaterialPropertyBlock::new()),
+        }
+    }
+
+    pub fn mask(&self) -> u32 {
+        self.mask
+    }
+
+    pub fn set_mask(&mut self, mask: u32) {
+        self.mask = mask;
+    }
+
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    pub fn set_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    pub fn flip_x(&self) -> bool {
+        self.flip_x
+    }
+
+    pub fn set_flip_x(&mut self, flip_x: bool) {
+        self.flip_x = flip_x;
+    }
+
+    pub fn flip_y(&self) -> bool {
+        self.flip_y
+    }
+
+    pub fn set_flip_y(&mut self, flip_y: bool) {
+        self.flip_y = flip_y;
+    }
+
+    /// Returns the point of the sprite that sits on the object, in range `[0, 1]` from the bottom-left corner.
+    pub fn pivot(&self) -> Vec2 {
+        self.pivot
+    }
+
+    pub fn set_pivot(&mut self, pivot: Vec2) {
+        self.pivot = pivot;
+    }
+
+    pub fn sorting_layer(&self) -> i32 {
+        self.sorting_layer
+    }
+
+    /// Sets the layer of the sprite. Sprites of higher layers are drawn over those of lower layers.
+    pub fn set_sorting_layer(&mut self, sorting_layer: i32) {
+        self.sorting_layer = sorting_layer;
+    }
+
+    pub fn order(&self) -> i32 {
+        self.order
+    }
+
+    /// Sets the order of the sprite within its layer. Sprites of higher orders are drawn over the others.
+    pub fn set_order(&mut self, order: i32) {
+        self.order = order;
+    }
+
+    pub fn facing(&self) -> SpriteFacing {
+        self.facing
+    }
+
+    pub fn set_facing(&mut self, facing: SpriteFacing) {
+        self.facing = facing;
+    }
+
+    /// Returns the number of sprite pixels that span a world unit.
+    pub fn pixels_per_unit(&self) -> f32 {
+        self.pixels_per_unit
+    }
+
+    pub fn set_pixels_per_unit(&mut self, pixels_per_unit: f32) {
+        self.pixels_per_unit = pixels_per_unit;
+    }
+
+    pub fn pixel_snap(&self) -> bool {
+        self.pixel_snap
+    }
+
+    /// Snaps the sprite to the pixel grid of the camera, measured in pixels-per-unit, to keep pixel art crisp.
+    pub fn set_pixel_snap(&mut self, pixel_snap: bool) {
+        self.pixel_snap = pixel_snap;
+    }
+
+    pub fn sprite(&self) -> Option<&SpriteHandle> {
+        self.sprite.as_ref()
+    }
+
+    /// Returns the texel mapping that is drawn, or `None` if there is no sprite.
+    pub fn mapping(&self) -> Option<SpriteTexelMapping> {
+        self.mapping
+            .or_else(|| self.sprite.as_ref().map(|sprite| sprite.mapping()))
+    }
+
+    /// Draws the given texel mapping of the texture of the sprite instead of the mapping of the sprite, e.g. a
+    /// frame of a sprite sheet. `None` draws the mapping of the sprite again.
+    pub fn set_mapping(&mut self, mapping: Option<SpriteTexelMapping>) {
+        self.mapping = mapping;
+    }
+
+    pub fn property_block(&self) -> &MaterialPropertyBlock {
+        &self.property_block
+    }
+
+    /// Returns the overrides of the material properties for this renderer. Sprites with overrides are
+    /// not batched with the others.
+    pub fn property_block_mut(&mut self) -> &mut MaterialPropertyBlock {
+        Arc::make_mut(&mut self.property_block)
+    }
+
+    /// Uploads the uniform overrides of the property block. The render system calls it every frame.
+    pub fn prepare_property_block(&mut self, device: &Device, queue: &Queue) {
+        if self.property_block.is_empty() && self.property_block.bind_groups().next().is_none() {
+            return;
+        }
+
+        let material = if let Some(material) = self.pipeline_provider.material() {
+            material.clone()
+        } else {
+            return;
+        };
+
+        Arc::make_mut(&mut self.property_block).prepare(&material.read(), device, queue);
+    }
+
+    pub fn set_material(&mut self, material: MaterialHandle) {
+        self.pipeline_provider.set_material(material);
+    }
+
+    pub fn set_sprite(
+        &mut self,
+        sprite: SpriteHandle,
+        device: &Device,
+        bind_group_layout_cache: &mut BindGroupLayoutCache,
+    ) {
+        let texture = sprite.texture();
+        let sprite_texture_bind_group_layout =
+            bind_group_layout_cache.create_layout(vec![BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::VERTEX_FRAGMENT,
+                ty: BindingType::Texture {
+                    sample_type: TextureSampleType::Float { filterable: true },
+                    view_dimension: TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            }]);
+        let sprite_sampler_bind_group_layout =
+            bind_group_layout_cache.create_layout(vec![BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::VERTEX_FRAGMENT,
+                ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                count: None,
+            }]);
+
+        self.sprite_texture_bind_group =
+            Some(Arc::new(device.create_bind_group(&BindGroupDescriptor {
+                label: None,
+                layout: sprite_texture_bind_group_layout.as_ref(),
+                entries: &[BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(&texture.view),
+                }],
+            })));
+        self.sprite_sampler_bind_group =
+            Some(Arc::new(device.create_bind_group(&BindGroupDescriptor {
+                label: None,
+                layout: sprite_sampler_bind_group_layout.as_ref(),
+                entries: &[BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::Sampler(&texture.sampler),
+                }],
+            })));
+        self.sprite = Some(sprite);
+    }
+
+    /// Computes the quad of the sprite for the object matrix, as seen from the view.
+    pub fn instance(&self, matrix: &Mat4, view: &SpriteView) -> Option<SpriteInstance> {
+        let sprite = self.sprite.as_ref()?;
+        let texture = sprite.texture();
+        let mapping = self.mapping.unwrap_or_else(|| sprite.mapping());
+
+        let size = Vec2::new(
+            mapping.width() as f32 / self.pixels_per_unit,
+            mapping.height() as f32 / self.pixels_per_unit,
+        );
+        let mut offset = Vec2::new(-self.pivot.x * size.x, -self.pivot.y * size.y);
+        let mut origin = Vec3::from(matrix.row(3));
+        let (axis_x, axis_y) = match self.facing {
+            SpriteFacing::Plane => (Vec3::from(matrix.row(0)), Vec3::from(matrix.row(1))),
+            SpriteFacing::Camera => (
+                view.right * Vec3::from(matrix.row(0)).len(),
+                view.up * Vec3::from(matrix.row(1)).len(),
+            ),
+        };
+
+        if self.pixel_snap {
+            origin = snap_to_pixel_grid(origin, view, self.pixels_per_unit);
+            offset = Vec2::new(
+                (offset.x * self.pixels_per_unit).round() / self.pixels_per_unit,
+                (offset.y * self.pixels_per_unit).round() / self.pixels_per_unit,
+            );
+        }
+
+        let texel_width_half = 0.5 / texture.width as f32;
+        let texel_height_half = 0.5 / texture.height as f32;
+        let mut uv_min = Vec2::new(
+            mapping.x_min as f32 / texture.width as f32 + texel_width_half,
+            mapping.y_min as f32 / texture.height as f32 + texel_height_half,
+        );
+        let mut uv_max = Vec2::new(
+            mapping.x_max as f32 / texture.width as f32 - texel_width_half,
+            mapping.y_max as f32 / texture.height as f32 - texel_height_half,
+        );
+
+        if self.flip_x {
+            std::mem::swap(&mut uv_min.x, &mut uv_max.x);
+        }
+
+        if self.flip_y {
+            std::mem::swap(&mut uv_min.y, &mut uv_max.y);
+        }
+
+        Some(SpriteInstance {
+            origin,
+            axis_x,
+            axis_y,
+            size,
+            offset,
+            uv_min,
+            uv_max,
+            color: self.color,
+        })
+    }
+
+    /// Prepares the sprite to be sorted and batched for the camera. Returns `None` if the sprite is not
+    /// ready to be drawn.
+    pub fn draw(
+        &mut self,
+        matrix: &Mat4,
+        view: &SpriteView,
+        sort_axis: SpriteSortAxis,
+        shader_mgr: &ShaderManager,
+        pipeline_cache: &mut PipelineCache,
+    ) -> Option<SpriteDraw> {
+        let pipeline = self
+            .pipeline_provider
+            .obtain_pipeline(shader_mgr, pipeline_cache)?;
+        let material = self.pipeline_provider.material().cloned()?;
+        let sprite_texture_bind_group = self.sprite_texture_bind_group.clone()?;
+        let sprite_sampler_bind_group = self.sprite_sampler_bind_group.clone()?;
+        let instance = self.instance(matrix, view)?;
+        let axis = match sort_axis {
+            SpriteSortAxis::CameraForward => view.forward,
+            SpriteSortAxis::World(axis) => axis,
+        };
+
+        Some(SpriteDraw {
+            sort_key: SpriteSortKey {
+                sorting_layer: self.sorting_layer,
+                order: self.order,
+                depth: Vec3::dot(instance.origin, axis),
+            },
+            instance,
+            pipeline,
+            material,
+            bind_group_provider: SpriteRendererBindGroupProvider {
+                sprite_texture_bind_group,
+                sprite_sampler_bind_group,
+            },
+            property_block: self.property_block.clone(),
+        })
+    }
+}
+
+impl Default for SpriteRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Moves the origin onto the pixel grid of the view, keeping its distance along the view direction.
+fn snap_to_pixel_grid(origin: Vec3, view: &SpriteView, pixels_per_unit: f32) -> Vec3 {
+    let delta = origin - view.position;
+    let x = Vec3::dot(delta, view.right);
+    let y = Vec3::dot(delta, view.up);
+    let snap = |value: f32| (value * pixels_per_unit).round() / pixels_per_unit;
+
+    origin + view.right * (snap(x) - x) + view.up * (snap(y) - y)
+}
+
+/// A sprite prepared by [`SpriteRenderer::draw`], waiting to be sorted into a [`SpriteBatch`].
+#[derive(Clone)]
+pub struct SpriteDraw {
+    pub sort_key: SpriteSortKey,
+    pub instance: SpriteInstance,
+    pipeline: CachedPipeline,
+    material: MaterialHandle,
+    bind_group_provider: SpriteRendererBindGroupProvider,
+    property_block: Arc<MaterialPropertyBlock>,
+}
+
+/// Consecutive sprites in drawing order that are drawn with one instanced draw call.
+pub struct SpriteBatch {
+    pipeline: CachedPipeline,
+    material: MaterialHandle,
+    bind_group_provider: SpriteRendererBindGroupProvider,
+    vertex_buffer_provider: SpriteRendererVertexBufferProvider,
+    instance_data_provider: SpriteBatchInstanceDataProvider,
+    property_block: Arc<MaterialPropertyBlock>,
+}
+
+impl SpriteBatch {
+    /// Starts a batch with the sprite. The given vector is cleared and reused to hold the instances.
+    pub fn new(
+        draw: SpriteDraw,
+        standard_ui_vertex_buffer: &GenericBufferAllocation<Buffer>,
+        mut instances: Vec<SpriteInstance>,
+    ) -> Self {
+        instances.clear();
+        instances.push(draw.instance);
+
+        Self {
+            pipeline: draw.pipeline,
+            material: draw.material,
+            bind_group_provider: draw.bind_group_provider,
+            vertex_buffer_provider: SpriteRendererVertexBufferProvider {
+                vertex_buffer: standard_ui_vertex_buffer.clone(),
+            },
+            instance_data_provider: SpriteBatchInstanceDataProvider { instances },
+            property_block: draw.property_block,
+        }
+    }
+
+    /// Returns `true` if the sprite can be drawn in the same draw call as the batch.
+    pub fn can_merge(&self, draw: &SpriteDraw) -> bool {
+        let is_property_block_shared = Arc::ptr_eq(&self.property_block, &draw.property_block)
+            || (self.property_block.is_empty() && draw.property_block.is_empty());
+
+        self.pipeline == draw.pipeline
+            && self.material == draw.material
+            && Arc::ptr_eq(
+                &self.bind_group_provider.sprite_texture_bind_group,
+                &draw.bind_group_provider.sprite_texture_bind_group,
+            )
+            && Arc::ptr_eq(
+                &self.bind_group_provider.sprite_sampler_bind_group,
+                &draw.bind_group_provider.sprite_sampler_bind_group,
+            )
+            && is_property_block_shared
+    }
+
+    pub fn push(&mut self, instance: SpriteInstance) {
+        self.instance_data_provider.instances.push(instance);
+    }
+
+    /// Gives back the storage of the instances, to be reused by the next batch.
+    pub fn into_instances(self) -> Vec<SpriteInstance> {
+        self.instance_data_provider.instances
+    }
+}
+
+impl Renderer for SpriteBatch {
+    fn pipeline(&self) -> CachedPipeline {
+        self.pipeline.clone()
+    }
+
+    fn material(&self) -> RwLockReadGuard<'_, Material> {
+        self.material.read()
+    }
+
+    fn instance_count(&self) -> u32 {
+        self.instance_data_provider.instances.len() as u32
+    }
+
+    fn vertex_count(&self) -> u32 {
+        6
+    }
+
+    fn bind_group_provider(&self) -> &dyn BindGroupProvider {
+        &self.bind_group_provider
+    }
+
+    fn vertex_buffer_provider(&self) -> &dyn VertexBufferProvider {
+        &self.vertex_buffer_provider
+    }
+
+    fn instance_data_provider(&self) -> &dyn InstanceDataProvider {
+        &self.instance_data_provider
+    }
+
+    fn property_block(&self) -> Option<&MaterialPropertyBlock> {
+        Some(&self.property_block)
+    }
+}
+
+#[derive(Clone)]
+struct SpriteRendererBindGroupProvider {
+    sprite_texture_bind_group: Arc<BindGroup>,
+    sprite_sampler_bind_group: Arc<BindGroup>,
+}
+
+impl BindGroupProvider for SpriteRendererBindGroupProvider {
+    fn bind_group(&self, _instance: u32, key: SemanticShaderBindingKey) -> Option<&BindGroup> {
+        match key {
+            semantic_bindings::KEY_SPRITE_TEXTURE => Some(&self.sprite_texture_bind_group),
+            semantic_bindings::KEY_SPRITE_SAMPLER => Some(&self.sprite_sampler_bind_group),
+            _ => None,
+        }
+    }
+}
+
+struct SpriteRendererVertexBufferProvider {
+    vertex_buffer: GenericBufferAllocation<Buffer>,
+}
+
+impl VertexBufferProvider for SpriteRendererVertexBufferProvider {
+    fn vertex_buffer_count(&self) -> u32 {
+        1
+    }
+
+    fn vertex_buffer(&self, key: SemanticShaderInputKey) -> Option<VertexBuffer<'_>> {
+        match key {
+            semantic_inputs::KEY_POSITION => Some(VertexBuffer {
+                slot: 0,
+                buffer: &self.vertex_buffer,
+            }),
+            _ => None,
+        }
+    }
+}
+
+struct SpriteBatchInstanceDataProvider {
+    instances: Vec<SpriteInstance>,
+}
+
+impl InstanceDataProvider for SpriteBatchInstanceDataProvider {
+    fn copy_per_instance_data(
+        &self,
+        instance: u32,
+        key: SemanticShaderInputKey,
+        buffer: &mut GenericBufferAllocation<HostBuffer>,
+    ) {
+        let instance = if let Some(instance) = self.instances.get(instance as usize) {
+            instance
+        } else {
+            return;
+        };
+
+        match key {
+            semantic_inputs::KEY_SPRITE_ORIGIN => {
+                buffer.copy_from_slice(instance.origin.as_bytes());
+            }
+            semantic_inputs::KEY_SPRITE_AXIS_X => {
+                buffer.copy_from_slice(instance.axis_x.as_bytes());
+            }
+            semantic_inputs::KEY_SPRITE_AXIS_Y => {
+                buffer.copy_from_slice(instance.axis_y.as_bytes());
+            }
+            semantic_inputs::KEY_SPRITE_SIZE => {
+                buffer.copy_from_slice(instance.size.as_bytes());
+            }
+            semantic_inputs::KEY_SPRITE_OFFSET => {
+                buffer.copy_from_slice(instance.offset.as_bytes());
+            }
+            semantic_inputs::KEY_SPRITE_UV_MIN => {
+                buffer.copy_from_slice(instance.uv_min.as_bytes());
+            }
+            semantic_inputs::KEY_SPRITE_UV_MAX => {
+                buffer.copy_from_slice(instance.uv_max.as_bytes());
+            }
+            semantic_inputs::KEY_SPRITE_COLOR => {
+                let color = instance.color;
+                buffer.copy_from_slice([color.r, color.g, color.b, color.a].as_bytes());
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(sorting_layer: i32, order: i32, depth: f32) -> SpriteSortKey {
+        SpriteSortKey {
+            sorting_layer,
+            order,
+            depth,
+        }
+    }
+
+    #[test]
+    fn sprites_sort_by_layer_order_then_depth() {
+        let mut keys = vec![
+            key(1, 0, 0.0),
+            key(0, 1, 5.0),
+            key(0, 0, 1.0),
+            key(0, 0, 3.0),
+            key(-1, 9, -9.0),
+        ];
+        keys.sort_by(SpriteSortKey::drawing_order);
+
+        assert_eq!(
+            keys,
+            vec![
+                key(-1, 9, -9.0),
+                // The deeper sprite of the same layer and order is drawn first.
+                key(0, 0, 3.0),
+                key(0, 0, 1.0),
+                key(0, 1, 5.0),
+                key(1, 0, 0.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn pixel_snap_and_culling() {
+        let view = SpriteView::from_matrix(&Mat4::identity());
+        let snapped = snap_to_pixel_grid(Vec3::new(0.123, -0.456, -2.0), &view, 10.0);
+        assert!((snapped.x - 0.1).abs() < 1e-5);
+        assert!((snapped.y + 0.5).abs() < 1e-5);
+        assert_eq!(snapped.z, -2.0);
+
+        let instance = |origin: Vec3| SpriteInstance {
+            origin,
+            axis_x: Vec3::RIGHT,
+            axis_y: Vec3::UP,
+            size: Vec2::new(1.0, 1.0),
+            offset: Vec2::new(-0.5, -0.5),
+            uv_min: Vec2::new(0.0, 0.0),
+            uv_max: Vec2::new(1.0, 1.0),
+            color: Color::white(),
+        };
+        let view_projection = Mat4::orthographic(-5.0, 5.0, -5.0, 5.0, 0.0, 10.0);
+
+        assert!(instance(Vec3::new(0.0, 0.0, -1.0)).is_visible(&view_projection));
+        // Sprites that only overlap the edge are kept.
+        assert!(instance(Vec3::new(5.4, 0.0, -1.0)).is_visible(&view_projection));
+        assert!(!instance(Vec3::new(5.6, 0.0, -1.0)).is_visible(&view_projection));
+        assert!(!instance(Vec3::new(0.0, -6.0, -1.0)).is_visible(&view_projection));
+    }
+}
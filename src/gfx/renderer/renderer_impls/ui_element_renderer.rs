@@ -3,21 +3,24 @@ use crate::{
         semantic_bindings,
         semantic_inputs::{self, KEY_POSITION},
         BindGroupLayoutCache, BindGroupProvider, CachedPipeline, Color, GenericBufferAllocation,
-        HostBuffer, InstanceDataProvider, Material, MaterialHandle, NinePatchHandle, PipelineCache,
-        PipelineProvider, Renderer, RendererVertexBufferAttribute, RendererVertexBufferLayout,
-        SemanticShaderBindingKey, SemanticShaderInputKey, ShaderManager, SpriteHandle,
-        TextureHandle, VertexBuffer, VertexBufferProvider,
+        HostBuffer, InstanceDataProvider, Material, MaterialHandle, MaterialPropertyBlock,
+        NinePatchHandle, PipelineCache, PipelineProvider, Renderer, RendererVertexBufferAttribute,
+        RendererVertexBufferLayout, SemanticShaderBindingKey, SemanticShaderInputKey,
+        ShaderManager, SpriteHandle, SpriteTexelMapping, TextureHandle, VertexBuffer,
+        VertexBufferProvider,
     },
+    math::Vec2,
     ui::UISize,
 };
+use codegen::Component;
 use parking_lot::RwLockReadGuard;
-use specs::{prelude::*, Component};
+use specs::prelude::*;
 use std::{mem::size_of, sync::Arc};
 use wgpu::{
     BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayoutEntry, BindingResource,
     BindingType, Buffer, BufferAddress, CompareFunction, DepthStencilState, Device, Face,
-    FrontFace, PolygonMode, PrimitiveState, PrimitiveTopology, SamplerBindingType, ShaderStages,
-    TextureFormat, TextureSampleType, TextureViewDimension,
+    FrontFace, PolygonMode, PrimitiveState, PrimitiveTopology, Queue, SamplerBindingType,
+    ShaderStages, TextureFormat, TextureSampleType, TextureViewDimension,
 };
 use zerocopy::AsBytes;
 
@@ -36,9 +39,10 @@ impl UIElementSprite {
         Self::NinePatch(nine_patch)
     }
 
-    pub fn texture(&self) -> &TextureHandle {
+    /// Returns the texture, or `None` if the texture of the nine-patch has been dropped.
+    pub fn texture(&self) -> Option<TextureHandle> {
         match self {
-            UIElementSprite::Sprite(sprite) => sprite.texture(),
+            UIElementSprite::Sprite(sprite) => Some(sprite.texture().clone()),
             UIElementSprite::NinePatch(nine_patch) => nine_patch.texture(),
         }
     }
@@ -46,13 +50,16 @@ impl UIElementSprite {
 
 #[derive(Component)]
 #[storage(HashMapStorage)]
+#[auto_register]
 pub struct UIElementRenderer {
     mask: u32,
     color: Color,
     pipeline_provider: PipelineProvider,
     sprite: Option<UIElementSprite>,
+    mapping: Option<SpriteTexelMapping>,
     sprite_texture_bind_group: Option<Arc<BindGroup>>,
     sprite_sampler_bind_group: Option<Arc<BindGroup>>,
+    property_block: Arc<MaterialPropertyBlock>,
 }
 
 impl UIElementRenderer {
@@ -88,8 +95,10 @@ impl UIElementRenderer {
             color: Color::white(),
             pipeline_provider,
             sprite: None,
+            mapping: None,
             sprite_texture_bind_group: None,
             sprite_sampler_bind_group: None,
+            property_block: Arc::new(MaterialPropertyBlock::new()),
         }
     }
 
@@ -109,6 +118,51 @@ impl UIElementRenderer {
         self.color = color;
     }
 
+    /// Returns the texel mapping that overrides the mapping of the sprite, if any.
+    pub fn mapping(&self) -> Option<SpriteTexelMapping> {
+        self.mapping
+    }
+
+    /// Draws the given texel mapping of the texture of the sprite instead of the mapping of the sprite, e.g. a
+    /// frame of a sprite sheet. `None` draws the mapping of the sprite again. Nine-patches ignore it.
+    pub fn set_mapping(&mut self, mapping: Option<SpriteTexelMapping>) {
+        self.mapping = mapping;
+    }
+
+    /// Returns the size of the drawn texels of the sprite, or `None` for nine-patches, which have no natural size.
+    pub fn content_size(&self) -> Option<Vec2> {
+        let mapping = match &self.sprite {
+            Some(UIElementSprite::Sprite(sprite)) => self.mapping.unwrap_or(sprite.mapping()),
+            _ => return None,
+        };
+
+        Some(Vec2::new(mapping.width() as f32, mapping.height() as f32))
+    }
+
+    pub fn property_block(&self) -> &MaterialPropertyBlock {
+        &self.property_block
+    }
+
+    /// Returns the overrides of the material properties for this renderer, e.g. to tint only this object.
+    pub fn property_block_mut(&mut self) -> &mut MaterialPropertyBlock {
+        Arc::make_mut(&mut self.property_block)
+    }
+
+    /// Uploads the uniform overrides of the property block. The render system calls it every frame.
+    pub fn prepare_property_block(&mut self, device: &Device, queue: &Queue) {
+        if self.property_block.is_empty() && self.property_block.bind_groups().next().is_none() {
+            return;
+        }
+
+        let material = if let Some(material) = self.pipeline_provider.material() {
+            material.clone()
+        } else {
+            return;
+        };
+
+        Arc::make_mut(&mut self.property_block).prepare(&material.read(), device, queue);
+    }
+
     pub fn set_material(&mut self, material: MaterialHandle) {
         self.pipeline_provider.set_material(material);
     }
@@ -119,6 +173,14 @@ impl UIElementRenderer {
         device: &Device,
         bind_group_layout_cache: &mut BindGroupLayoutCache,
     ) {
+        let texture = if let Some(texture) = sprite.texture() {
+            texture
+        } else {
+            self.sprite = None;
+            self.sprite_texture_bind_group = None;
+            self.sprite_sampler_bind_group = None;
+            return;
+        };
         let sprite_texture_bind_group_layout =
             bind_group_layout_cache.create_layout(vec![BindGroupLayoutEntry {
                 binding: 0,
@@ -144,7 +206,7 @@ impl UIElementRenderer {
                 layout: sprite_texture_bind_group_layout.as_ref(),
                 entries: &[BindGroupEntry {
                     binding: 0,
-                    resource: BindingResource::TextureView(&sprite.texture().view),
+                    resource: BindingResource::TextureView(&texture.view),
                 }],
             })));
         self.sprite_sampler_bind_group =
@@ -153,15 +215,18 @@ impl UIElementRenderer {
                 layout: sprite_sampler_bind_group_layout.as_ref(),
                 entries: &[BindGroupEntry {
                     binding: 0,
-                    resource: BindingResource::Sampler(&sprite.texture().sampler),
+                    resource: BindingResource::Sampler(&texture.sampler),
                 }],
             })));
         self.sprite = Some(sprite);
     }
 
+    /// Returns the sub renderer of the element, with its color multiplied by `tint`, e.g. the
+    /// [effective color](crate::ui::effective_ui_color) of its element.
     pub fn sub_renderer(
         &mut self,
         size: UISize,
+        tint: Color,
         standard_ui_vertex_buffer: &GenericBufferAllocation<Buffer>,
         shader_mgr: &ShaderManager,
         pipeline_cache: &mut PipelineCache,
@@ -190,9 +255,11 @@ impl UIElementRenderer {
             },
             instance_data_provider: UIElementRendererInstanceDataProvider {
                 sprite,
+                mapping: self.mapping,
                 size,
-                color: self.color,
+                color: self.color * tint,
             },
+            property_block: self.property_block.clone(),
         })
     }
 }
@@ -204,6 +271,7 @@ pub struct UIElementSubRenderer {
     bind_group_provider: UIElementRendererBindGroupProvider,
     vertex_buffer_provider: UIElementRendererVertexBufferProvider,
     instance_data_provider: UIElementRendererInstanceDataProvider,
+    property_block: Arc<MaterialPropertyBlock>,
 }
 
 impl Renderer for UIElementSubRenderer {
@@ -234,6 +302,10 @@ impl Renderer for UIElementSubRenderer {
     fn instance_data_provider(&self) -> &dyn InstanceDataProvider {
         &self.instance_data_provider
     }
+
+    fn property_block(&self) -> Option<&MaterialPropertyBlock> {
+        Some(&self.property_block)
+    }
 }
 
 struct UIElementRendererBindGroupProvider {
@@ -273,6 +345,7 @@ impl VertexBufferProvider for UIElementRendererVertexBufferProvider {
 
 struct UIElementRendererInstanceDataProvider {
     sprite: UIElementSprite,
+    mapping: Option<SpriteTexelMapping>,
     size: UISize,
     color: Color,
 }
@@ -304,7 +377,7 @@ impl InstanceDataProvider for UIElementRendererInstanceDataProvider {
                     UIElementSprite::Sprite(sprite) => {
                         let texel_width_half = 0.5 / sprite.texture().width as f32;
                         let texel_height_half = 0.5 / sprite.texture().height as f32;
-                        let mapping = sprite.mapping();
+                        let mapping = self.mapping.unwrap_or_else(|| sprite.mapping());
                         [
                             mapping.x_min as f32 / sprite.texture().width as f32 + texel_width_half,
                             mapping.y_min as f32 / sprite.texture().height as f32
@@ -312,8 +385,8 @@ impl InstanceDataProvider for UIElementRendererInstanceDataProvider {
                         ]
                     }
                     UIElementSprite::NinePatch(nine_patch) => {
-                        let texel_width_half = 0.5 / nine_patch.texture().width as f32;
-                        let texel_height_half = 0.5 / nine_patch.texture().height as f32;
+                        let texel_width_half = 0.5 / nine_patch.texture_width() as f32;
+                        let texel_height_half = 0.5 / nine_patch.texture_height() as f32;
                         let x = match instance {
                             0 | 3 | 6 => nine_patch.mapping().x_min,
                             1 | 4 | 7 => nine_patch.mapping().x_mid_left,
@@ -327,8 +400,8 @@ impl InstanceDataProvider for UIElementRendererInstanceDataProvider {
                             _ => return,
                         };
                         [
-                            x as f32 / nine_patch.texture().width as f32 + texel_width_half,
-                            y as f32 / nine_patch.texture().height as f32 + texel_height_half,
+                            x as f32 / nine_patch.texture_width() as f32 + texel_width_half,
+                            y as f32 / nine_patch.texture_height() as f32 + texel_height_half,
                         ]
                     }
                 };
@@ -339,7 +412,7 @@ impl InstanceDataProvider for UIElementRendererInstanceDataProvider {
                     UIElementSprite::Sprite(sprite) => {
                         let texel_width_half = 0.5 / sprite.texture().width as f32;
                         let texel_height_half = 0.5 / sprite.texture().height as f32;
-                        let mapping = sprite.mapping();
+                        let mapping = self.mapping.unwrap_or_else(|| sprite.mapping());
                         [
                             mapping.x_max as f32 / sprite.texture().width as f32 - texel_width_half,
                             mapping.y_max as f32 / sprite.texture().height as f32
@@ -347,8 +420,8 @@ impl InstanceDataProvider for UIElementRendererInstanceDataProvider {
                         ]
                     }
                     UIElementSprite::NinePatch(nine_patch) => {
-                        let texel_width_half = 0.5 / nine_patch.texture().width as f32;
-                        let texel_height_half = 0.5 / nine_patch.texture().height as f32;
+                        let texel_width_half = 0.5 / nine_patch.texture_width() as f32;
+                        let texel_height_half = 0.5 / nine_patch.texture_height() as f32;
                         let x = match instance {
                             0 | 3 | 6 => nine_patch.mapping().x_mid_left,
                             1 | 4 | 7 => nine_patch.mapping().x_mid_right,
@@ -362,8 +435,8 @@ impl InstanceDataProvider for UIElementRendererInstanceDataProvider {
                             _ => return,
                         };
                         [
-                            x as f32 / nine_patch.texture().width as f32 - texel_width_half,
-                            y as f32 / nine_patch.texture().height as f32 - texel_height_half,
+                            x as f32 / nine_patch.texture_width() as f32 - texel_width_half,
+                            y as f32 / nine_patch.texture_height() as f32 - texel_height_half,
                         ]
                     }
                 };
@@ -1,20 +1,21 @@
 use crate::{
     gfx::{
-        compute_glyph_layout, semantic_bindings,
+        compute_scaled_glyph_layout, measure_glyph_layout, semantic_bindings,
         semantic_inputs::{self, KEY_POSITION},
         BindGroupLayoutCache, BindGroupProvider, CachedPipeline, Color, FontHandle,
-        GenericBufferAllocation, GlyphLayoutConfig, GlyphManager, GlyphSpriteHandle, HostBuffer,
-        InstanceDataProvider, Material, MaterialHandle, PipelineCache, PipelineProvider, Renderer,
-        RendererVertexBufferAttribute, RendererVertexBufferLayout, SemanticShaderBindingKey,
-        SemanticShaderInputKey, ShaderManager, VertexBuffer, VertexBufferProvider,
+        GenericBufferAllocation, GlyphKey, GlyphLayoutConfig, GlyphManager, GlyphSpriteHandle,
+        HostBuffer, InstanceDataProvider, Material, MaterialHandle, PipelineCache,
+        PipelineProvider, Renderer, RendererVertexBufferAttribute, RendererVertexBufferLayout,
+        SemanticShaderBindingKey, SemanticShaderInputKey, ShaderManager, TextRenderMode,
+        VertexBuffer, VertexBufferProvider,
     },
     math::Vec2,
     ui::UISize,
 };
-use itertools::Itertools;
+use codegen::Component;
 use parking_lot::RwLockReadGuard;
-use specs::{prelude::*, Component};
-use std::{mem::size_of, sync::Arc};
+use specs::prelude::*;
+use std::{mem::size_of, ops::Range, sync::Arc};
 use wgpu::{
     BindGroup, Buffer, BufferAddress, CompareFunction, DepthStencilState, Face, FrontFace,
     PolygonMode, PrimitiveState, PrimitiveTopology, TextureFormat,
@@ -25,22 +26,33 @@ use zerocopy::AsBytes;
 struct Glyph {
     pub size: Vec2,
     pub offset: Vec2,
+    pub key: GlyphKey,
     pub sprite: GlyphSpriteHandle,
 }
 
 #[derive(Component)]
 #[storage(HashMapStorage)]
+#[auto_register]
 pub struct UITextRenderer {
     mask: u32,
     color: Color,
     font_size: f32,
     thickness: f32,
     smoothness: f32,
+    render_mode: TextRenderMode,
+    outline_color: Color,
+    outline_width: f32,
     pipeline_provider: PipelineProvider,
     font: Option<FontHandle>,
+    fallback_fonts: Vec<FontHandle>,
     text: Option<String>,
-    glyphs: Vec<Glyph>,
+    /// Shared with the sub renderers of the current frame, so that they do not copy the glyphs.
+    glyphs: Arc<Vec<Glyph>>,
+    glyph_generation: u64,
+    /// The scale factor of the screen that the glyphs have been rasterized for.
+    scale_factor: f32,
     layout_config: GlyphLayoutConfig,
+    layout_generation: u64,
     is_dirty: bool,
 }
 
@@ -78,11 +90,18 @@ impl UITextRenderer {
             font_size: 16f32,
             thickness: 0.5f32,
             smoothness: 16f32 / 1000f32,
+            render_mode: TextRenderMode::default(),
+            outline_color: Color::black(),
+            outline_width: 0f32,
             pipeline_provider,
             font: None,
+            fallback_fonts: Vec::new(),
             text: None,
-            glyphs: Vec::new(),
+            glyphs: Arc::new(Vec::new()),
+            glyph_generation: 0,
+            scale_factor: 1f32,
             layout_config: Default::default(),
+            layout_generation: 0,
             is_dirty: true,
         }
     }
@@ -107,21 +126,43 @@ impl UITextRenderer {
         self.smoothness
     }
 
+    pub fn render_mode(&self) -> TextRenderMode {
+        self.render_mode
+    }
+
+    pub fn outline_color(&self) -> Color {
+        self.outline_color
+    }
+
+    pub fn outline_width(&self) -> f32 {
+        self.outline_width
+    }
+
     pub fn font(&self) -> Option<&FontHandle> {
         self.font.as_ref()
     }
 
+    pub fn fallback_fonts(&self) -> &[FontHandle] {
+        &self.fallback_fonts
+    }
+
     pub fn text(&self) -> Option<&String> {
         self.text.as_ref()
     }
 
+    /// Returns a number that changes whenever the layout of the text may change, i.e. when the text,
+    /// the fonts, the font size, the render mode or the layout config changes.
+    pub fn layout_generation(&self) -> u64 {
+        self.layout_generation
+    }
+
     pub fn config(&self) -> &GlyphLayoutConfig {
         &self.layout_config
     }
 
     pub fn with_config<R>(&mut self, f: impl FnOnce(&mut GlyphLayoutConfig) -> R) -> R {
         let r = f(&mut self.layout_config);
-        self.is_dirty = true;
+        self.mark_dirty();
         r
     }
 
@@ -135,7 +176,7 @@ impl UITextRenderer {
 
     pub fn set_font_size(&mut self, font_size: f32) {
         self.font_size = font_size;
-        self.is_dirty = true;
+        self.mark_dirty();
     }
 
     /// Sets the font size and recommended values for thickness and smoothness.
@@ -143,7 +184,7 @@ impl UITextRenderer {
         self.font_size = font_size;
         self.thickness = 0.5f32;
         self.smoothness = font_size / 1000f32;
-        self.is_dirty = true;
+        self.mark_dirty();
     }
 
     /// Sets the thickness of the glyph outlines.
@@ -158,84 +199,148 @@ impl UITextRenderer {
         self.smoothness = smoothness;
     }
 
+    /// Sets the render mode. The material should be changed together to match the mode.
+    pub fn set_render_mode(&mut self, render_mode: TextRenderMode) {
+        self.render_mode = render_mode;
+        self.mark_dirty();
+    }
+
+    /// Sets the color of the glyph outlines. Only used in the sdf render mode.
+    pub fn set_outline_color(&mut self, outline_color: Color) {
+        self.outline_color = outline_color;
+    }
+
+    /// Sets the width of the glyph outlines, in the same unit as the thickness.
+    /// Only used in the sdf render mode; zero disables the outlines.
+    pub fn set_outline_width(&mut self, outline_width: f32) {
+        self.outline_width = outline_width;
+    }
+
     pub fn set_material(&mut self, material: MaterialHandle) {
         self.pipeline_provider.set_material(material);
     }
 
     pub fn set_font(&mut self, font: FontHandle) {
         self.font = Some(font);
-        self.is_dirty = true;
+        self.mark_dirty();
+    }
+
+    /// Sets the primary font and the fallback fonts. Glyphs missing in the primary font are taken
+    /// from the first fallback font that has them, in order.
+    pub fn set_fonts(&mut self, primary: FontHandle, fallbacks: Vec<FontHandle>) {
+        self.font = Some(primary);
+        self.fallback_fonts = fallbacks;
+        self.mark_dirty();
     }
 
     pub fn set_text(&mut self, text: String) {
         self.text = Some(text);
-        self.is_dirty = true;
+        self.mark_dirty();
+    }
+
+    /// Returns the size that the text takes when laid out, wrapping lines at `max_width` if given.
+    /// Nothing is rasterized, so it can be called before the text is rendered.
+    pub fn measure(&self, max_width: Option<f32>) -> Vec2 {
+        match (&self.font, &self.text) {
+            (Some(font), Some(text)) => measure_glyph_layout(
+                &self.fonts(font),
+                self.font_size,
+                &self.layout_config,
+                max_width,
+                text.chars(),
+            ),
+            _ => Vec2::ZERO,
+        }
     }
 
-    pub fn sub_renderers<'a>(
-        &'a mut self,
+    /// Returns a sub renderer for each run of glyphs that share a texture, with the color multiplied by `tint`.
+    /// The glyphs are rasterized again when the scale factor of the screen changes, so that they match its pixel
+    /// density.
+    pub fn sub_renderers(
+        &mut self,
         is_dirty: bool,
         size: UISize,
+        scale_factor: f32,
+        tint: Color,
         standard_ui_vertex_buffer: &GenericBufferAllocation<Buffer>,
         shader_mgr: &ShaderManager,
         glyph_mgr: &mut GlyphManager,
         pipeline_cache: &mut PipelineCache,
         bind_group_layout_cache: &mut BindGroupLayoutCache,
-    ) -> Option<Vec<UITextSubRenderer>> {
-        self.update_glyphs(is_dirty, size, glyph_mgr, bind_group_layout_cache);
+    ) -> Option<impl Iterator<Item = UITextSubRenderer>> {
+        self.update_glyphs(
+            is_dirty || self.scale_factor != scale_factor,
+            size,
+            scale_factor,
+            glyph_mgr,
+            bind_group_layout_cache,
+        );
 
         let pipeline = self
             .pipeline_provider
             .obtain_pipeline(shader_mgr, pipeline_cache)?;
         let material = self.pipeline_provider.material().cloned()?;
-
-        let groups = self
-            .glyphs
-            .iter()
-            .group_by(|&glyph| Arc::as_ptr(glyph.sprite.texture_bind_group()));
-
-        Some(Vec::from_iter(groups.into_iter().filter_map(
-            |(_, group)| {
-                let glyphs = Vec::from_iter(group.cloned());
-
-                let first = if let Some(first) = glyphs.first() {
-                    first
-                } else {
-                    return None;
-                };
-                let glyph_texture_bind_group = first.sprite.texture_bind_group().clone();
-                let glyph_sampler_bind_group = first.sprite.sampler_bind_group().clone();
-
-                Some(UITextSubRenderer {
-                    pipeline: pipeline.clone(),
-                    material: material.clone(),
-                    instance_count: glyphs.len() as u32,
-                    bind_group_provider: UITextRendererBindGroupProvider {
-                        glyph_texture_bind_group,
-                        glyph_sampler_bind_group,
-                    },
-                    vertex_buffer_provider: UITextRendererVertexBufferProvider {
-                        vertex_buffer: standard_ui_vertex_buffer.clone(),
-                    },
-                    instance_data_provider: UITextRendererInstanceDataProvider {
-                        glyphs,
-                        color: self.color,
-                        thickness: self.thickness,
-                        smoothness: self.smoothness,
-                    },
+        let vertex_buffer = standard_ui_vertex_buffer.clone();
+        let glyphs = self.glyphs.clone();
+        let color = self.color * tint;
+        let thickness = self.thickness;
+        let smoothness = self.smoothness;
+        let render_mode = self.render_mode;
+        let outline_color = self.outline_color;
+        let outline_width = self.outline_width;
+        let mut start = 0;
+
+        Some(std::iter::from_fn(move || {
+            let first = glyphs.get(start)?;
+            let texture_bind_group = Arc::as_ptr(first.sprite.texture_bind_group());
+            let end = glyphs[start..]
+                .iter()
+                .position(|glyph| {
+                    Arc::as_ptr(glyph.sprite.texture_bind_group()) != texture_bind_group
                 })
-            },
-        )))
+                .map_or(glyphs.len(), |count| start + count);
+            let range = start..end;
+            start = end;
+
+            Some(UITextSubRenderer {
+                pipeline: pipeline.clone(),
+                material: material.clone(),
+                instance_count: range.len() as u32,
+                bind_group_provider: UITextRendererBindGroupProvider {
+                    glyph_texture_bind_group: first.sprite.texture_bind_group().clone(),
+                    glyph_sampler_bind_group: first.sprite.sampler_bind_group().clone(),
+                },
+                vertex_buffer_provider: UITextRendererVertexBufferProvider {
+                    vertex_buffer: vertex_buffer.clone(),
+                },
+                instance_data_provider: UITextRendererInstanceDataProvider {
+                    glyphs: glyphs.clone(),
+                    range,
+                    color,
+                    thickness,
+                    smoothness,
+                    render_mode,
+                    outline_color,
+                    outline_width,
+                },
+            })
+        }))
     }
 
     fn update_glyphs(
         &mut self,
         is_dirty: bool,
         size: UISize,
+        scale_factor: f32,
         glyph_mgr: &mut GlyphManager,
         bind_group_layout_cache: &mut BindGroupLayoutCache,
     ) {
-        if !self.is_dirty && !is_dirty {
+        // Glyphs may have been evicted from the atlas; they should be obtained again to be re-rasterized.
+        if !self.is_dirty && !is_dirty && self.glyph_generation == glyph_mgr.generation() {
+            for glyph in self.glyphs.iter() {
+                glyph_mgr.touch(&glyph.key);
+            }
+
             return;
         }
 
@@ -244,28 +349,50 @@ impl UITextRenderer {
             _ => return,
         };
 
-        self.glyphs.clear();
+        let fonts = self.fonts(font);
+
+        // The sub renderers of the previous frame are gone by now, so the glyphs are not copied.
+        let glyphs = Arc::make_mut(&mut self.glyphs);
+        glyphs.clear();
 
-        for glyph in compute_glyph_layout(
-            font,
+        for glyph in compute_scaled_glyph_layout(
+            &fonts,
             self.font_size,
             size,
+            scale_factor,
             &self.layout_config,
+            self.render_mode,
             text.chars(),
         ) {
-            self.glyphs.push(Glyph {
+            glyphs.push(Glyph {
                 size: glyph.size,
                 offset: glyph.offset,
+                key: glyph.key,
                 sprite: glyph_mgr
-                    .glyph(bind_group_layout_cache, font, glyph.key)
+                    .glyph(bind_group_layout_cache, &fonts[glyph.font_index], glyph.key)
                     .clone(),
             });
         }
 
-        self.glyphs
-            .sort_unstable_by_key(|glyph| Arc::as_ptr(glyph.sprite.texture_bind_group()));
+        glyphs.sort_unstable_by_key(|glyph| Arc::as_ptr(glyph.sprite.texture_bind_group()));
+        self.glyph_generation = glyph_mgr.generation();
+        self.scale_factor = scale_factor;
         self.is_dirty = false;
     }
+
+    /// Returns the primary font followed by the fallback fonts.
+    fn fonts(&self, primary: &FontHandle) -> Vec<FontHandle> {
+        Vec::from_iter(
+            std::iter::once(primary)
+                .chain(self.fallback_fonts.iter())
+                .cloned(),
+        )
+    }
+
+    fn mark_dirty(&mut self) {
+        self.is_dirty = true;
+        self.layout_generation += 1;
+    }
 }
 
 pub struct UITextSubRenderer {
@@ -343,10 +470,29 @@ impl VertexBufferProvider for UITextRendererVertexBufferProvider {
 }
 
 struct UITextRendererInstanceDataProvider {
-    glyphs: Vec<Glyph>,
+    glyphs: Arc<Vec<Glyph>>,
+    /// The run of glyphs that this sub renderer draws.
+    range: Range<usize>,
     color: Color,
     thickness: f32,
     smoothness: f32,
+    render_mode: TextRenderMode,
+    outline_color: Color,
+    outline_width: f32,
+}
+
+impl UITextRendererInstanceDataProvider {
+    /// Returns the half texel size that insets the uv of signed distance fields.
+    /// Bitmaps are rendered pixel-aligned, so their uv must not be inset.
+    fn uv_inset(&self, glyph: &Glyph) -> (f32, f32) {
+        match self.render_mode {
+            TextRenderMode::Bitmap => (0f32, 0f32),
+            TextRenderMode::Sdf { .. } => (
+                0.5 / glyph.sprite.texture().width as f32,
+                0.5 / glyph.sprite.texture().height as f32,
+            ),
+        }
+    }
 }
 
 impl InstanceDataProvider for UITextRendererInstanceDataProvider {
@@ -358,17 +504,16 @@ impl InstanceDataProvider for UITextRendererInstanceDataProvider {
     ) {
         match key {
             semantic_inputs::KEY_SPRITE_SIZE => {
-                let glyph = &self.glyphs[instance as usize];
+                let glyph = &self.glyphs[self.range.start + instance as usize];
                 buffer.copy_from_slice([glyph.size.x, glyph.size.y].as_bytes());
             }
             semantic_inputs::KEY_SPRITE_OFFSET => {
-                let glyph = &self.glyphs[instance as usize];
+                let glyph = &self.glyphs[self.range.start + instance as usize];
                 buffer.copy_from_slice([glyph.offset.x, glyph.offset.y].as_bytes());
             }
             semantic_inputs::KEY_SPRITE_UV_MIN => {
-                let glyph = &self.glyphs[instance as usize];
-                let texel_width_half = 0.5 / glyph.sprite.texture().width as f32;
-                let texel_height_half = 0.5 / glyph.sprite.texture().height as f32;
+                let glyph = &self.glyphs[self.range.start + instance as usize];
+                let (texel_width_half, texel_height_half) = self.uv_inset(glyph);
                 let mapping = glyph.sprite.mapping();
                 buffer.copy_from_slice(
                     [
@@ -381,9 +526,8 @@ impl InstanceDataProvider for UITextRendererInstanceDataProvider {
                 );
             }
             semantic_inputs::KEY_SPRITE_UV_MAX => {
-                let glyph = &self.glyphs[instance as usize];
-                let texel_width_half = 0.5 / glyph.sprite.texture().width as f32;
-                let texel_height_half = 0.5 / glyph.sprite.texture().height as f32;
+                let glyph = &self.glyphs[self.range.start + instance as usize];
+                let (texel_width_half, texel_height_half) = self.uv_inset(glyph);
                 let mapping = glyph.sprite.mapping();
                 buffer.copy_from_slice(
                     [
@@ -406,6 +550,27 @@ impl InstanceDataProvider for UITextRendererInstanceDataProvider {
             semantic_inputs::KEY_GLYPH_SMOOTHNESS => {
                 buffer.copy_from_slice([self.smoothness].as_bytes());
             }
+            semantic_inputs::KEY_GLYPH_PX_RANGE => {
+                let px_range = match self.render_mode {
+                    TextRenderMode::Bitmap => 1f32,
+                    TextRenderMode::Sdf { px_range } => px_range,
+                };
+                buffer.copy_from_slice([px_range].as_bytes());
+            }
+            semantic_inputs::KEY_GLYPH_OUTLINE_COLOR => {
+                buffer.copy_from_slice(
+                    [
+                        self.outline_color.r,
+                        self.outline_color.g,
+                        self.outline_color.b,
+                        self.outline_color.a,
+                    ]
+                    .as_bytes(),
+                );
+            }
+            semantic_inputs::KEY_GLYPH_OUTLINE_WIDTH => {
+                buffer.copy_from_slice([self.outline_width].as_bytes());
+            }
             _ => {}
         }
     }
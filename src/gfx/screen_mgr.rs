@@ -1,10 +1,43 @@
+use crate::math::Vec2;
 use winit::dpi::PhysicalSize;
 
+/// Insets of the screen area that can be covered by the system, e.g. notches or rounded corners.
+/// All values are in logical pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SafeAreaInsets {
+    pub left: f32,
+    pub right: f32,
+    pub top: f32,
+    pub bottom: f32,
+}
+
+impl SafeAreaInsets {
+    pub fn new(left: f32, right: f32, top: f32, bottom: f32) -> Self {
+        Self {
+            left,
+            right,
+            top,
+            bottom,
+        }
+    }
+
+    pub fn zero() -> Self {
+        Self {
+            left: 0f32,
+            right: 0f32,
+            top: 0f32,
+            bottom: 0f32,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct ScreenManager {
     width: f64,
     height: f64,
     scale_factor: f64,
+    refresh_rate: f32,
+    safe_area_insets: SafeAreaInsets,
     is_dirty: bool,
 }
 
@@ -14,6 +47,8 @@ impl ScreenManager {
             width: width as _,
             height: height as _,
             scale_factor: 1f64,
+            refresh_rate: 60f32,
+            safe_area_insets: SafeAreaInsets::zero(),
             is_dirty: true,
         }
     }
@@ -30,6 +65,17 @@ impl ScreenManager {
         self.scale_factor
     }
 
+    /// Returns the refresh rate of the monitor that the window is on, in hertz.
+    pub fn refresh_rate(&self) -> f32 {
+        self.refresh_rate
+    }
+
+    /// Returns the safe area insets of the window.
+    /// Desktop platforms have no unsafe area, so it is zero unless it has been set manually.
+    pub fn safe_area_insets(&self) -> SafeAreaInsets {
+        self.safe_area_insets
+    }
+
     pub fn is_dirty(&self) -> bool {
         self.is_dirty
     }
@@ -42,6 +88,31 @@ impl ScreenManager {
         self.height * self.scale_factor
     }
 
+    pub fn logical_size(&self) -> Vec2 {
+        Vec2::new(self.width as f32, self.height as f32)
+    }
+
+    pub fn physical_size(&self) -> Vec2 {
+        self.logical_to_physical(self.logical_size())
+    }
+
+    pub fn logical_to_physical(&self, point: Vec2) -> Vec2 {
+        point * self.scale_factor as f32
+    }
+
+    pub fn physical_to_logical(&self, point: Vec2) -> Vec2 {
+        point / self.scale_factor as f32
+    }
+
+    /// Returns the width divided by the height. It is 1 if the screen has no height, e.g. while minimized.
+    pub fn aspect_ratio(&self) -> f32 {
+        if self.height <= 0f64 {
+            return 1f32;
+        }
+
+        (self.width / self.height) as f32
+    }
+
     pub fn update_size(&mut self, inner_size: PhysicalSize<u32>) {
         let size = inner_size.to_logical(self.scale_factor);
         self.width = size.width;
@@ -57,7 +128,48 @@ impl ScreenManager {
         self.is_dirty = true;
     }
 
+    pub fn update_refresh_rate(&mut self, refresh_rate: f32) {
+        self.refresh_rate = refresh_rate;
+    }
+
+    pub fn set_safe_area_insets(&mut self, insets: SafeAreaInsets) {
+        if self.safe_area_insets == insets {
+            return;
+        }
+
+        self.safe_area_insets = insets;
+        self.is_dirty = true;
+    }
+
     pub fn reset_dirty(&mut self) {
         self.is_dirty = false;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conversions_follow_resizes() {
+        let mut screen_mgr = ScreenManager::new(800, 600);
+        screen_mgr.update_scale_factor(2f64, PhysicalSize::new(1600, 1200));
+        assert_eq!(screen_mgr.logical_size(), Vec2::new(800f32, 600f32));
+        assert_eq!(screen_mgr.physical_size(), Vec2::new(1600f32, 1200f32));
+
+        screen_mgr.update_size(PhysicalSize::new(1000, 500));
+        assert_eq!(screen_mgr.logical_size(), Vec2::new(500f32, 250f32));
+        assert_eq!(screen_mgr.aspect_ratio(), 2f32);
+        assert_eq!(
+            screen_mgr.physical_to_logical(Vec2::new(300f32, 100f32)),
+            Vec2::new(150f32, 50f32)
+        );
+        assert_eq!(
+            screen_mgr.logical_to_physical(Vec2::new(150f32, 50f32)),
+            Vec2::new(300f32, 100f32)
+        );
+
+        screen_mgr.update_size(PhysicalSize::new(1000, 0));
+        assert_eq!(screen_mgr.aspect_ratio(), 1f32);
+    }
+}
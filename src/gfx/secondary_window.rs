@@ -0,0 +1,308 @@
+use super::{DepthStencil, DepthStencilMode, GfxContextHandle};
+use std::collections::HashMap;
+use thiserror::Error;
+use wgpu::{
+    CreateSurfaceError, Surface, SurfaceConfiguration, SurfaceError, SurfaceTexture, TextureView,
+};
+use winit::{
+    dpi::PhysicalSize,
+    event::WindowEvent,
+    window::{Window, WindowId},
+};
+
+#[derive(Error, Debug)]
+pub enum SecondaryWindowCreationError {
+    #[error("winit os error: {0}")]
+    WinitOsError(#[from] winit::error::OsError),
+    #[error("failed to create surface")]
+    CreateSurfaceError(#[from] CreateSurfaceError),
+}
+
+/// A window besides the main one, e.g. for tools or an inspector. It has its own surface and depth stencil buffer,
+/// which the cameras targeting it with [`CameraTarget::Window`](super::CameraTarget::Window) draw into.
+pub struct SecondaryWindow {
+    gfx_ctx: GfxContextHandle,
+    // The surface refers to the window, so it is declared before it to be dropped before it.
+    surface: Surface,
+    surface_config: SurfaceConfiguration,
+    is_surface_outdated: bool,
+    depth_stencil_mode: DepthStencilMode,
+    depth_stencil: Option<DepthStencil>,
+    window: Window,
+}
+
+impl SecondaryWindow {
+    /// Creates a surface for the window. It has the format of the main surface, so that the same pipelines can
+    /// draw into both.
+    pub fn new(
+        gfx_ctx: GfxContextHandle,
+        window: Window,
+        depth_stencil_mode: DepthStencilMode,
+    ) -> Result<Self, CreateSurfaceError> {
+        let surface = unsafe { gfx_ctx.instance.create_surface(&window) }?;
+        let size = window.inner_size();
+        let surface_config = SurfaceConfiguration {
+            width: size.width,
+            height: size.height,
+            ..gfx_ctx.surface_config.borrow().clone()
+        };
+        let depth_stencil = DepthStencil::new(gfx_ctx.clone(), depth_stencil_mode, size);
+
+        Ok(Self {
+            gfx_ctx,
+            surface,
+            surface_config,
+            is_surface_outdated: true,
+            depth_stencil_mode,
+            depth_stencil,
+            window,
+        })
+    }
+
+    pub fn id(&self) -> WindowId {
+        self.window.id()
+    }
+
+    pub fn window(&self) -> &Window {
+        &self.window
+    }
+
+    pub fn size(&self) -> PhysicalSize<u32> {
+        PhysicalSize::new(self.surface_config.width, self.surface_config.height)
+    }
+
+    /// Resizes the depth stencil buffer. The surface is configured again before the next frame.
+    pub fn resize(&mut self, size: PhysicalSize<u32>) {
+        if self.size() == size {
+            return;
+        }
+
+        self.surface_config.width = size.width;
+        self.surface_config.height = size.height;
+        self.is_surface_outdated = true;
+
+        match &mut self.depth_stencil {
+            Some(depth_stencil) => depth_stencil.resize(size),
+            None => {
+                self.depth_stencil =
+                    DepthStencil::new(self.gfx_ctx.clone(), self.depth_stencil_mode, size);
+            }
+        }
+    }
+
+    /// Returns the texture to draw the frame into, or `None` if the window is minimized or the frame should be
+    /// skipped.
+    pub fn acquire_texture(&mut self) -> Result<Option<SurfaceTexture>, SurfaceError> {
+        if self.surface_config.width == 0 || self.surface_config.height == 0 {
+            return Ok(None);
+        }
+
+        if self.is_surface_outdated {
+            self.surface
+                .configure(&self.gfx_ctx.device, &self.surface_config);
+            self.is_surface_outdated = false;
+        }
+
+        match self.surface.get_current_texture() {
+            Ok(texture) => Ok(Some(texture)),
+            Err(SurfaceError::Lost | SurfaceError::Outdated) => {
+                self.is_surface_outdated = true;
+                Ok(None)
+            }
+            Err(SurfaceError::Timeout) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn depth_stencil_view(&self) -> Option<&TextureView> {
+        self.depth_stencil
+            .as_ref()
+            .and_then(|depth_stencil| depth_stencil.texture_view())
+    }
+}
+
+/// A window kept by the [`SecondaryWindowManager`]. It only has to know its id and size, so the routing of the
+/// manager does not need a real window.
+pub trait ManagedWindow {
+    fn id(&self) -> WindowId;
+    fn size(&self) -> PhysicalSize<u32>;
+    fn resize(&mut self, size: PhysicalSize<u32>);
+}
+
+impl ManagedWindow for SecondaryWindow {
+    fn id(&self) -> WindowId {
+        SecondaryWindow::id(self)
+    }
+
+    fn size(&self) -> PhysicalSize<u32> {
+        SecondaryWindow::size(self)
+    }
+
+    fn resize(&mut self, size: PhysicalSize<u32>) {
+        SecondaryWindow::resize(self, size)
+    }
+}
+
+/// Keeps the secondary windows by their ids. The engine loop routes the resize and close events of the windows
+/// here, while the events of the main window go to the rest of the context.
+pub struct SecondaryWindowManager<W = SecondaryWindow> {
+    windows: HashMap<WindowId, W>,
+}
+
+impl<W> Default for SecondaryWindowManager<W> {
+    fn default() -> Self {
+        Self {
+            windows: HashMap::new(),
+        }
+    }
+}
+
+impl<W: ManagedWindow> SecondaryWindowManager<W> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn insert(&mut self, window: W) -> WindowId {
+        let id = window.id();
+        self.windows.insert(id, window);
+        id
+    }
+
+    /// Removes the window, which closes it once dropped.
+    pub fn remove(&mut self, id: WindowId) -> Option<W> {
+        self.windows.remove(&id)
+    }
+
+    pub fn contains(&self, id: WindowId) -> bool {
+        self.windows.contains_key(&id)
+    }
+
+    pub fn get(&self, id: WindowId) -> Option<&W> {
+        self.windows.get(&id)
+    }
+
+    pub fn get_mut(&mut self, id: WindowId) -> Option<&mut W> {
+        self.windows.get_mut(&id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &W> {
+        self.windows.values()
+    }
+
+    pub fn size(&self, id: WindowId) -> Option<PhysicalSize<u32>> {
+        self.get(id).map(W::size)
+    }
+
+    /// Resizes the window of the id. Returns `false` if the manager does not keep it, e.g. for the main window.
+    pub fn resize(&mut self, id: WindowId, size: PhysicalSize<u32>) -> bool {
+        match self.get_mut(id) {
+            Some(window) => {
+                window.resize(size);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Resizes the window of the event, or removes it if it is asked to close. Returns the removed window, which
+    /// closes once dropped. Other events are ignored.
+    pub fn handle_window_event(&mut self, id: WindowId, event: &WindowEvent) -> Option<W> {
+        match event {
+            WindowEvent::Resized(size) => {
+                self.resize(id, *size);
+            }
+            WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
+                self.resize(id, **new_inner_size);
+            }
+            WindowEvent::CloseRequested => return self.remove(id),
+            _ => {}
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestWindow {
+        id: WindowId,
+        size: PhysicalSize<u32>,
+    }
+
+    impl TestWindow {
+        fn new(id: u64, width: u32, height: u32) -> Self {
+            Self {
+                id: WindowId::from(id),
+                size: PhysicalSize::new(width, height),
+            }
+        }
+    }
+
+    impl ManagedWindow for TestWindow {
+        fn id(&self) -> WindowId {
+            self.id
+        }
+
+        fn size(&self) -> PhysicalSize<u32> {
+            self.size
+        }
+
+        fn resize(&mut self, size: PhysicalSize<u32>) {
+            self.size = size;
+        }
+    }
+
+    #[test]
+    fn resize_is_routed_to_the_window_of_the_id() {
+        let mut secondary_window_mgr = SecondaryWindowManager::new();
+        let first = secondary_window_mgr.insert(TestWindow::new(1, 200, 100));
+        let second = secondary_window_mgr.insert(TestWindow::new(2, 200, 100));
+
+        assert!(secondary_window_mgr.resize(first, PhysicalSize::new(320, 240)));
+        assert_eq!(
+            secondary_window_mgr.size(first),
+            Some(PhysicalSize::new(320, 240))
+        );
+        assert_eq!(
+            secondary_window_mgr.size(second),
+            Some(PhysicalSize::new(200, 100))
+        );
+
+        // The main window is not kept by the manager.
+        let main = WindowId::from(0);
+        assert!(!secondary_window_mgr.resize(main, PhysicalSize::new(64, 64)));
+        assert_eq!(secondary_window_mgr.size(main), None);
+    }
+
+    #[test]
+    fn window_events_resize_and_close_their_window() {
+        let mut secondary_window_mgr = SecondaryWindowManager::new();
+        let id = secondary_window_mgr.insert(TestWindow::new(1, 200, 100));
+
+        let resized = WindowEvent::Resized(PhysicalSize::new(320, 240));
+        assert!(secondary_window_mgr
+            .handle_window_event(id, &resized)
+            .is_none());
+        assert_eq!(
+            secondary_window_mgr.size(id),
+            Some(PhysicalSize::new(320, 240))
+        );
+
+        let mut new_inner_size = PhysicalSize::new(640, 480);
+        let scale_factor_changed = WindowEvent::ScaleFactorChanged {
+            scale_factor: 2.0,
+            new_inner_size: &mut new_inner_size,
+        };
+        secondary_window_mgr.handle_window_event(id, &scale_factor_changed);
+        assert_eq!(
+            secondary_window_mgr.size(id),
+            Some(PhysicalSize::new(640, 480))
+        );
+
+        let closed = secondary_window_mgr.handle_window_event(id, &WindowEvent::CloseRequested);
+        assert_eq!(closed.map(|window| window.id), Some(id));
+        assert!(!secondary_window_mgr.contains(id));
+    }
+}
@@ -0,0 +1,209 @@
+use super::{DepthStencilMode, Texture};
+use crate::math::{Mat4, Vec3, Vec4};
+use std::{mem::size_of, sync::Arc};
+use wgpu::{
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingResource, BindingType, Buffer, BufferAddress, BufferBindingType,
+    BufferDescriptor, BufferSize, BufferUsages, ColorTargetState, ColorWrites, CompareFunction,
+    DepthBiasState, DepthStencilState, Device, FragmentState, MultisampleState,
+    PipelineLayoutDescriptor, PrimitiveState, Queue, RenderPass, RenderPipeline,
+    RenderPipelineDescriptor, SamplerBindingType, ShaderModuleDescriptor, ShaderSource,
+    ShaderStages, StencilState, TextureFormat, TextureSampleType, TextureView,
+    TextureViewDimension, VertexState,
+};
+use zerocopy::AsBytes;
+
+/// The inverse view-projection matrix followed by the forward direction of the camera.
+const VIEW_SIZE: usize = size_of::<[f32; 4 * 4 + 4]>();
+
+/// A skybox prepared for the current frame by [`SkyboxRenderer::prepare`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PreparedSkybox(usize);
+
+struct SkyboxSlot {
+    buffer: Buffer,
+    texture_view: Arc<TextureView>,
+    bind_group: BindGroup,
+}
+
+/// Draws the cubemap of [`CameraClearMode::Skybox`](super::CameraClearMode::Skybox) as the background,
+/// by drawing a triangle that covers the current viewport. It neither tests nor writes the depth.
+pub struct SkyboxRenderer {
+    bind_group_layout: BindGroupLayout,
+    pipeline: RenderPipeline,
+    slots: Vec<SkyboxSlot>,
+    used_slots: usize,
+}
+
+impl SkyboxRenderer {
+    pub fn new(
+        device: &Device,
+        color_format: TextureFormat,
+        depth_stencil_mode: DepthStencilMode,
+    ) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("skybox bind group layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: BufferSize::new(VIEW_SIZE as u64),
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::Cube,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let pipeline =
+            create_pipeline(device, &bind_group_layout, color_format, depth_stencil_mode);
+
+        Self {
+            bind_group_layout,
+            pipeline,
+            slots: Vec::new(),
+            used_slots: 0,
+        }
+    }
+
+    /// Releases the skyboxes prepared for the previous frame. It must be called once per frame.
+    pub fn begin_frame(&mut self) {
+        self.used_slots = 0;
+    }
+
+    /// Uploads the view of the camera to draw the cubemap with. Each camera of a frame needs its own call,
+    /// since the uploads are not visible until the frame is submitted.
+    pub fn prepare(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        cubemap: &Texture,
+        transform_matrix: &Mat4,
+        view_projection: &Mat4,
+    ) -> PreparedSkybox {
+        let index = self.used_slots;
+        self.used_slots += 1;
+
+        if self.slots.len() <= index {
+            let buffer = device.create_buffer(&BufferDescriptor {
+                label: Some("skybox view buffer"),
+                size: VIEW_SIZE as BufferAddress,
+                usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            let bind_group = self.create_bind_group(device, &buffer, cubemap);
+            self.slots.push(SkyboxSlot {
+                buffer,
+                texture_view: cubemap.view.clone(),
+                bind_group,
+            });
+        } else if !Arc::ptr_eq(&self.slots[index].texture_view, &cubemap.view) {
+            let bind_group = self.create_bind_group(device, &self.slots[index].buffer, cubemap);
+            let slot = &mut self.slots[index];
+            slot.texture_view = cubemap.view.clone();
+            slot.bind_group = bind_group;
+        }
+
+        let forward = Vec4::from_vec3(Vec3::FORWARD, 0.0) * transform_matrix;
+        let mut view = [0f32; 4 * 4 + 4];
+        view[..16].copy_from_slice(&view_projection.inversed().elements);
+        view[16..].copy_from_slice(&[forward.x, forward.y, forward.z, 0.0]);
+        queue.write_buffer(&self.slots[index].buffer, 0, view.as_bytes());
+
+        PreparedSkybox(index)
+    }
+
+    fn create_bind_group(&self, device: &Device, buffer: &Buffer, cubemap: &Texture) -> BindGroup {
+        device.create_bind_group(&BindGroupDescriptor {
+            label: Some("skybox bind group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::TextureView(&cubemap.view),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: BindingResource::Sampler(&cubemap.sampler),
+                },
+            ],
+        })
+    }
+
+    /// Draws the prepared skybox into the current viewport.
+    pub fn draw<'r>(&'r self, render_pass: &mut RenderPass<'r>, skybox: PreparedSkybox) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.slots[skybox.0].bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+fn create_pipeline(
+    device: &Device,
+    bind_group_layout: &BindGroupLayout,
+    color_format: TextureFormat,
+    depth_stencil_mode: DepthStencilMode,
+) -> RenderPipeline {
+    let shader_module = device.create_shader_module(ShaderModuleDescriptor {
+        label: Some("skybox shader"),
+        source: ShaderSource::Wgsl(include_str!("./built_in_shaders/skybox.wgsl").into()),
+    });
+    let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+        label: Some("skybox pipeline layout"),
+        bind_group_layouts: &[bind_group_layout],
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&RenderPipelineDescriptor {
+        label: Some("skybox pipeline"),
+        layout: Some(&layout),
+        vertex: VertexState {
+            module: &shader_module,
+            entry_point: "vs_main",
+            buffers: &[],
+        },
+        primitive: PrimitiveState::default(),
+        // The depth is cleared separately by the clear mode, so the skybox leaves it untouched.
+        depth_stencil: depth_stencil_mode
+            .as_texture_format()
+            .map(|format| DepthStencilState {
+                format,
+                depth_write_enabled: false,
+                depth_compare: CompareFunction::Always,
+                stencil: StencilState::default(),
+                bias: DepthBiasState::default(),
+            }),
+        multisample: MultisampleState::default(),
+        fragment: Some(FragmentState {
+            module: &shader_module,
+            entry_point: "fs_main",
+            targets: &[Some(ColorTargetState {
+                format: color_format,
+                blend: None,
+                write_mask: ColorWrites::ALL,
+            })],
+        }),
+        multiview: None,
+    })
+}
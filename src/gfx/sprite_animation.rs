@@ -0,0 +1,164 @@
+use super::SpriteTexelMapping;
+use codegen::Component;
+use specs::prelude::*;
+
+/// Plays the frames of a sprite sheet on the [`SpriteRenderer`](super::SpriteRenderer) or the
+/// [`UIElementRenderer`](super::UIElementRenderer) of its object, by overriding the texel mapping of their sprite.
+/// The frames are texel rects in the texture of that sprite.
+///
+/// Sprites advance on the game time, so that they pause with the game; UI elements advance on the UI time.
+#[derive(Component, Debug, Clone, PartialEq)]
+#[storage(HashMapStorage)]
+#[auto_register]
+pub struct SpriteAnimation {
+    frames: Vec<SpriteTexelMapping>,
+    fps: f32,
+    is_looping: bool,
+    is_playing: bool,
+    /// The seconds since the first frame.
+    time: f32,
+}
+
+impl SpriteAnimation {
+    /// Creates a looping animation that plays from the first frame.
+    pub fn new(frames: Vec<SpriteTexelMapping>, fps: f32) -> Self {
+        Self {
+            frames,
+            fps,
+            is_looping: true,
+            is_playing: true,
+            time: 0.0,
+        }
+    }
+
+    pub fn frames(&self) -> &[SpriteTexelMapping] {
+        &self.frames
+    }
+
+    pub fn set_frames(&mut self, frames: Vec<SpriteTexelMapping>) {
+        self.frames = frames;
+        self.time = 0.0;
+    }
+
+    pub fn fps(&self) -> f32 {
+        self.fps
+    }
+
+    pub fn set_fps(&mut self, fps: f32) {
+        self.fps = fps;
+    }
+
+    pub fn is_looping(&self) -> bool {
+        self.is_looping
+    }
+
+    /// Sets whether the animation starts over after the last frame, instead of holding it.
+    pub fn set_looping(&mut self, is_looping: bool) {
+        self.is_looping = is_looping;
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.is_playing
+    }
+
+    /// Returns whether a non-looping animation has reached its last frame.
+    pub fn is_finished(&self) -> bool {
+        !self.is_looping && self.frames.len() <= self.frame_count()
+    }
+
+    /// Plays the animation from the first frame.
+    pub fn play(&mut self) {
+        self.is_playing = true;
+        self.time = 0.0;
+    }
+
+    pub fn pause(&mut self) {
+        self.is_playing = false;
+    }
+
+    pub fn resume(&mut self) {
+        self.is_playing = true;
+    }
+
+    /// Returns the index of the frame that is shown.
+    pub fn frame_index(&self) -> usize {
+        if self.frames.is_empty() {
+            return 0;
+        }
+
+        let count = self.frame_count();
+
+        if self.is_looping {
+            count % self.frames.len()
+        } else {
+            count.min(self.frames.len() - 1)
+        }
+    }
+
+    /// Returns the texel mapping of the frame that is shown, or `None` if there are no frames.
+    pub fn mapping(&self) -> Option<SpriteTexelMapping> {
+        self.frames.get(self.frame_index()).copied()
+    }
+
+    /// Advances the animation by `delta_time` seconds. Returns whether the shown frame has changed.
+    pub fn advance(&mut self, delta_time: f32) -> bool {
+        if !self.is_playing || self.is_finished() {
+            return false;
+        }
+
+        let frame_index = self.frame_index();
+        self.time += delta_time;
+
+        if self.is_looping && 0.0 < self.fps && !self.frames.is_empty() {
+            // Keeps the time small, so that long-running animations do not lose precision.
+            self.time %= self.frames.len() as f32 / self.fps;
+        }
+
+        frame_index != self.frame_index()
+    }
+
+    /// Returns the number of frames that have fully elapsed.
+    fn frame_count(&self) -> usize {
+        if self.fps <= 0.0 {
+            return 0;
+        }
+
+        (self.time * self.fps) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames() -> Vec<SpriteTexelMapping> {
+        (0..3)
+            .map(|index| SpriteTexelMapping::new(index * 16, index * 16 + 16, 0, 16))
+            .collect()
+    }
+
+    #[test]
+    fn frames_advance_with_the_fps() {
+        let mut animation = SpriteAnimation::new(frames(), 10.0);
+
+        // 0.25 seconds at 10 fps are 2.5 frames, so the third frame is shown.
+        for _ in 0..5 {
+            animation.advance(0.05);
+        }
+        assert_eq!(animation.frame_index(), 2);
+        assert_eq!(
+            animation.mapping(),
+            Some(SpriteTexelMapping::new(32, 48, 0, 16))
+        );
+
+        // It wraps around to the first frame after the third.
+        assert!(animation.advance(0.1));
+        assert_eq!(animation.frame_index(), 0);
+
+        let mut animation = SpriteAnimation::new(frames(), 10.0);
+        animation.set_looping(false);
+        animation.advance(1.0);
+        assert_eq!(animation.frame_index(), 2);
+        assert!(animation.is_finished());
+    }
+}
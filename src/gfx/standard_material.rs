@@ -0,0 +1,131 @@
+use super::{
+    BindGroupEntryResource, BindingPropKey, Environment, Material, StandardShaderFeatures,
+    TextureHandle,
+};
+use crate::use_context;
+use logging::log_warn;
+
+/// The textures of a material of the standard shader. The variant of the shader is selected by the textures
+/// that are set, e.g. a normal map selects [`StandardShaderFeatures::has_normal_map`].
+#[derive(Clone)]
+pub struct StandardMaterialTextures {
+    pub base_color: TextureHandle,
+    pub normal: Option<TextureHandle>,
+    pub emissive: Option<TextureHandle>,
+    /// The depth of the scene as seen by the light. Its sampler must be a comparison sampler, and the
+    /// `light_view_projection` of the material must be the one it is rendered with.
+    pub shadow_map: Option<TextureHandle>,
+    /// The cubemap to reflect. Without it, the [global environment](super::RenderManager::global_environment) is
+    /// reflected if there is one.
+    pub environment: Option<Environment>,
+}
+
+impl StandardMaterialTextures {
+    pub fn new(base_color: TextureHandle) -> Self {
+        Self {
+            base_color,
+            normal: None,
+            emissive: None,
+            shadow_map: None,
+            environment: None,
+        }
+    }
+
+    /// Returns the features that the textures select. Skinning is not selected by textures.
+    pub fn features(&self) -> StandardShaderFeatures {
+        StandardShaderFeatures {
+            has_normal_map: self.normal.is_some(),
+            has_emissive: self.emissive.is_some(),
+            skinned: false,
+            receive_shadows: self.shadow_map.is_some(),
+            has_environment: self.environment.is_some(),
+        }
+    }
+
+    /// Creates a material of the variant that the textures select, with the textures bound.
+    ///
+    /// The surface is a white dielectric of roughness 0.5, lit by a white light from above and a dim ambient
+    /// light. Since there is no light in the scene, the light and the camera position are properties of the
+    /// material: `light_direction`, `light_color`, `ambient_color` and `camera_position`. Properties must be
+    /// flushed with [`Material::flush_uniforms`] and [`Material::update_bind_group`] before rendering.
+    ///
+    /// The environment is bound when the material is created, so materials created before the global environment
+    /// is set do not reflect it. Captures of a probe update its cubemap in place, so they are reflected.
+    pub fn create_material(&self, skinned: bool) -> Material {
+        let ctx = use_context();
+        let environment = self
+            .environment
+            .clone()
+            .or_else(|| ctx.render_mgr().global_environment().cloned());
+        let shader = ctx.built_in_shader_mgr().standard_shader(
+            ctx.shader_mgr(),
+            ctx.render_mgr_mut().bind_group_layout_cache(),
+            StandardShaderFeatures {
+                skinned,
+                has_environment: environment.is_some(),
+                ..self.features()
+            },
+        );
+        let mut material = Material::new(shader, ctx.render_mgr_mut().pipeline_layout_cache());
+
+        let textures = [
+            (
+                "base_color_texture",
+                "base_color_sampler",
+                Some(&self.base_color),
+            ),
+            ("normal_texture", "normal_sampler", self.normal.as_ref()),
+            (
+                "emissive_texture",
+                "emissive_sampler",
+                self.emissive.as_ref(),
+            ),
+            ("shadow_map", "shadow_sampler", self.shadow_map.as_ref()),
+            (
+                "environment_map",
+                "environment_sampler",
+                environment.as_ref().map(|environment| &environment.cubemap),
+            ),
+        ];
+
+        for (texture_name, sampler_name, texture) in textures {
+            let texture = if let Some(texture) = texture {
+                texture
+            } else {
+                continue;
+            };
+
+            let results = [
+                material.set_bind_property(
+                    &BindingPropKey::StringKey(texture_name.to_owned()),
+                    BindGroupEntryResource::TextureView {
+                        texture_view: texture.view.clone(),
+                    },
+                ),
+                material.set_bind_property(
+                    &BindingPropKey::StringKey(sampler_name.to_owned()),
+                    BindGroupEntryResource::Sampler {
+                        sampler: texture.sampler.clone(),
+                    },
+                ),
+            ];
+
+            for err in results.into_iter().filter_map(Result::err) {
+                log_warn!("failed to bind a texture of the standard material: {}", err);
+            }
+        }
+
+        material.set_uniform_property("base_color", [1.0, 1.0, 1.0, 1.0]);
+        material.set_uniform_property("roughness", 0.5);
+        material.set_uniform_property("light_direction", [-0.4, -1.0, -0.6]);
+        material.set_uniform_property("light_color", [1.0, 1.0, 1.0]);
+        material.set_uniform_property("ambient_color", [0.1, 0.1, 0.1]);
+
+        if let Some(environment) = &environment {
+            material.set_uniform_property("environment_intensity", environment.intensity);
+            material.set_uniform_property("environment_z_sign", environment.z_sign());
+        }
+
+        material
+    }
+}
@@ -0,0 +1,492 @@
+use super::{Mesh, MeshCpuData, MeshPrimitive};
+use crate::math::{orient_polygon, triangulate_polygon, Vec2, Vec3};
+use codegen::{Component, Handle};
+use fontdue::layout::HorizontalAlign;
+use parking_lot::Mutex;
+use specs::prelude::*;
+use std::{collections::HashMap, sync::Arc};
+use thiserror::Error;
+use ttf_parser::{FaceParsingError, GlyphId, OutlineBuilder};
+
+/// Side edges meeting at a smaller angle than this are shaded smoothly, e.g. along curves.
+const SMOOTH_ANGLE_COS: f32 = 0.866;
+
+#[derive(Error, Debug)]
+pub enum TextMeshFontError {
+    #[error("failed to parse the font: {0}")]
+    Parse(#[from] FaceParsingError),
+}
+
+/// A font whose glyph outlines are turned into meshes, for text placed in the scene with [`TextMeshRenderer`].
+///
+/// Each glyph is triangulated once, when it is first used, and is kept for every string drawn with the font.
+#[derive(Handle)]
+pub struct TextMeshFont {
+    data: Vec<u8>,
+    units_per_em: f32,
+    line_height: f32,
+    glyphs: Mutex<HashMap<u16, Arc<GlyphOutline>>>,
+}
+
+impl TextMeshFont {
+    /// Loads a TrueType or OpenType font.
+    pub fn from_bytes(data: impl Into<Vec<u8>>) -> Result<Self, TextMeshFontError> {
+        let data = data.into();
+        let face = ttf_parser::Face::from_slice(&data, 0)?;
+        let units_per_em = face.units_per_em() as f32;
+        let line_height = (face.ascender() - face.descender() + face.line_gap()) as f32;
+
+        Ok(Self {
+            data,
+            units_per_em,
+            line_height,
+            glyphs: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Returns the number of glyphs that have been triangulated so far.
+    pub fn cached_glyph_count(&self) -> usize {
+        self.glyphs.lock().len()
+    }
+
+    fn face(&self) -> ttf_parser::Face<'_> {
+        // The data has been parsed once already.
+        ttf_parser::Face::from_slice(&self.data, 0).unwrap()
+    }
+
+    fn glyph(&self, face: &ttf_parser::Face, glyph_id: GlyphId) -> Arc<GlyphOutline> {
+        self.glyphs
+            .lock()
+            .entry(glyph_id.0)
+            .or_insert_with(|| Arc::new(GlyphOutline::new(face, glyph_id, self.units_per_em)))
+            .clone()
+    }
+
+    /// Lays out the text, returning the glyphs and their offsets in font units.
+    fn layout(&self, text: &str, align: HorizontalAlign) -> Vec<(Arc<GlyphOutline>, Vec2)> {
+        let face = self.face();
+        let kern = face.tables().kern;
+        let mut placements = Vec::with_capacity(text.len());
+
+        for (line_index, line) in text.lines().enumerate() {
+            let line_start = placements.len();
+            let mut x = 0.0;
+            let mut prev = None;
+
+            for c in line.chars() {
+                let glyph_id = face.glyph_index(c).unwrap_or(GlyphId(0));
+
+                if let (Some(prev), Some(kern)) = (prev, kern) {
+                    x += kern
+                        .subtables
+                        .into_iter()
+                        .filter(|subtable| subtable.horizontal)
+                        .find_map(|subtable| subtable.glyphs_kerning(prev, glyph_id))
+                        .unwrap_or(0) as f32;
+                }
+
+                let glyph = self.glyph(&face, glyph_id);
+                let advance = face.glyph_hor_advance(glyph_id).unwrap_or(0) as f32;
+
+                if !glyph.triangles.is_empty() {
+                    placements.push((glyph, Vec2::new(x, -(line_index as f32) * self.line_height)));
+                }
+
+                x += advance;
+                prev = Some(glyph_id);
+            }
+
+            let offset = match align {
+                HorizontalAlign::Left => 0.0,
+                HorizontalAlign::Center => -x * 0.5,
+                HorizontalAlign::Right => -x,
+            };
+
+            for (_, position) in &mut placements[line_start..] {
+                position.x += offset;
+            }
+        }
+
+        placements
+    }
+}
+
+/// The outline of a glyph, in em units, with the interior on the left of the edges.
+struct GlyphOutline {
+    contours: Vec<Vec<Vec2>>,
+    triangles: Vec<[u32; 3]>,
+}
+
+impl GlyphOutline {
+    fn new(face: &ttf_parser::Face, glyph_id: GlyphId, units_per_em: f32) -> Self {
+        let mut builder = OutlineCollector {
+            // Curves are split into segments about 2% of the em long.
+            tolerance: units_per_em * 0.02,
+            contours: Vec::new(),
+            current: Vec::new(),
+        };
+        face.outline_glyph(glyph_id, &mut builder);
+        builder.close();
+
+        let mut contours = builder.contours;
+
+        for contour in &mut contours {
+            for point in contour.iter_mut() {
+                *point *= 1.0 / units_per_em;
+            }
+        }
+
+        orient_polygon(&mut contours);
+        let triangles = triangulate_polygon(&contours);
+
+        Self {
+            contours,
+            triangles,
+        }
+    }
+}
+
+struct OutlineCollector {
+    tolerance: f32,
+    contours: Vec<Vec<Vec2>>,
+    current: Vec<Vec2>,
+}
+
+impl OutlineCollector {
+    fn last(&self) -> Vec2 {
+        self.current.last().copied().unwrap_or_default()
+    }
+
+    fn segment_count(&self, length: f32) -> u32 {
+        ((length / self.tolerance).ceil() as u32).clamp(1, 16)
+    }
+}
+
+impl OutlineBuilder for OutlineCollector {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.close();
+        self.current.push(Vec2::new(x, y));
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.current.push(Vec2::new(x, y));
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let from = self.last();
+        let control = Vec2::new(x1, y1);
+        let to = Vec2::new(x, y);
+        let count = self.segment_count(Vec2::distance(from, control) + Vec2::distance(control, to));
+
+        for segment in 1..=count {
+            let t = segment as f32 / count as f32;
+            let s = 1.0 - t;
+            self.current
+                .push(from * (s * s) + control * (2.0 * s * t) + to * (t * t));
+        }
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let from = self.last();
+        let control1 = Vec2::new(x1, y1);
+        let control2 = Vec2::new(x2, y2);
+        let to = Vec2::new(x, y);
+        let count = self.segment_count(
+            Vec2::distance(from, control1)
+                + Vec2::distance(control1, control2)
+                + Vec2::distance(control2, to),
+        );
+
+        for segment in 1..=count {
+            let t = segment as f32 / count as f32;
+            let s = 1.0 - t;
+            self.current.push(
+                from * (s * s * s)
+                    + control1 * (3.0 * s * s * t)
+                    + control2 * (3.0 * s * t * t)
+                    + to * (t * t * t),
+            );
+        }
+    }
+
+    fn close(&mut self) {
+        let mut contour = std::mem::take(&mut self.current);
+        contour.dedup();
+
+        if 1 < contour.len() && contour.first() == contour.last() {
+            contour.pop();
+        }
+
+        if 3 <= contour.len() {
+            self.contours.push(contour);
+        }
+    }
+}
+
+/// Draws text in the scene, e.g. for a title screen. It generates the mesh of the [`MeshRenderer`] of the same
+/// object, which draws it with its material.
+///
+/// The text faces +Z, and starts at the origin on the baseline of its first line. Lines are separated by `\n`.
+/// With a depth, the glyphs are extruded towards -Z and get sides; otherwise they are flat.
+///
+/// [`MeshRenderer`]: super::MeshRenderer
+#[derive(Component)]
+#[storage(HashMapStorage)]
+#[auto_register]
+pub struct TextMeshRenderer {
+    font: TextMeshFontHandle,
+    text: String,
+    size: f32,
+    depth: f32,
+    horizontal_align: HorizontalAlign,
+    is_dirty: bool,
+}
+
+impl TextMeshRenderer {
+    /// Creates a flat text of the given size, which is the height of the em.
+    pub fn new(font: TextMeshFontHandle, text: impl Into<String>, size: f32) -> Self {
+        Self {
+            font,
+            text: text.into(),
+            size,
+            depth: 0.0,
+            horizontal_align: HorizontalAlign::Left,
+            is_dirty: true,
+        }
+    }
+
+    pub fn font(&self) -> &TextMeshFontHandle {
+        &self.font
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn size(&self) -> f32 {
+        self.size
+    }
+
+    pub fn depth(&self) -> f32 {
+        self.depth
+    }
+
+    pub fn horizontal_align(&self) -> HorizontalAlign {
+        self.horizontal_align
+    }
+
+    pub fn set_font(&mut self, font: TextMeshFontHandle) {
+        self.font = font;
+        self.is_dirty = true;
+    }
+
+    pub fn set_text(&mut self, text: impl Into<String>) {
+        let text = text.into();
+
+        if self.text != text {
+            self.text = text;
+            self.is_dirty = true;
+        }
+    }
+
+    pub fn set_size(&mut self, size: f32) {
+        self.size = size;
+        self.is_dirty = true;
+    }
+
+    /// Sets how far the glyphs are extruded; zero makes them flat.
+    pub fn set_depth(&mut self, depth: f32) {
+        self.depth = depth.max(0.0);
+        self.is_dirty = true;
+    }
+
+    pub fn set_horizontal_align(&mut self, horizontal_align: HorizontalAlign) {
+        self.horizontal_align = horizontal_align;
+        self.is_dirty = true;
+    }
+
+    /// Returns the mesh of the text if it has changed since the last call.
+    pub(crate) fn take_mesh(&mut self) -> Option<Mesh> {
+        if !self.is_dirty {
+            return None;
+        }
+
+        self.is_dirty = false;
+        Some(self.build_mesh())
+    }
+
+    /// Builds the mesh of the text from the cached glyphs, placing a copy of each glyph at each of its characters.
+    pub fn build_mesh(&self) -> Mesh {
+        let mut geometry = TextGeometry::default();
+        let half_depth = self.depth * 0.5;
+
+        for (glyph, offset) in self.font.layout(&self.text, self.horizontal_align) {
+            let offset = offset * (1.0 / self.font.units_per_em);
+            let points = Vec::from_iter(
+                glyph
+                    .contours
+                    .iter()
+                    .flatten()
+                    .map(|&point| (point + offset) * self.size),
+            );
+
+            geometry.push_cap(&points, &glyph.triangles, half_depth, false);
+
+            if 0.0 < self.depth {
+                geometry.push_cap(&points, &glyph.triangles, -half_depth, true);
+
+                let mut base = 0;
+
+                for contour in &glyph.contours {
+                    geometry.push_sides(&points[base..base + contour.len()], half_depth, self.size);
+                    base += contour.len();
+                }
+            }
+        }
+
+        geometry.into_mesh()
+    }
+}
+
+#[derive(Default)]
+struct TextGeometry {
+    positions: Vec<Vec3>,
+    normals: Vec<Vec3>,
+    uvs: Vec<Vec2>,
+    indices: Vec<u32>,
+}
+
+impl TextGeometry {
+    fn push_vertex(&mut self, position: Vec3, normal: Vec3, uv: Vec2) {
+        self.positions.push(position);
+        self.normals.push(normal);
+        self.uvs.push(uv);
+    }
+
+    /// Pushes the front or the back of a glyph. The uvs follow the positions, with V growing downwards.
+    fn push_cap(&mut self, points: &[Vec2], triangles: &[[u32; 3]], z: f32, is_back: bool) {
+        let base = self.positions.len() as u32;
+        let normal = if is_back {
+            Vec3::FORWARD
+        } else {
+            Vec3::BACKWARD
+        };
+
+        for &point in points {
+            self.push_vertex(
+                Vec3::new(point.x, point.y, z),
+                normal,
+                Vec2::new(point.x, -point.y),
+            );
+        }
+
+        for &[a, b, c] in triangles {
+            if is_back {
+                self.indices.extend([base + a, base + c, base + b]);
+            } else {
+                self.indices.extend([base + a, base + b, base + c]);
+            }
+        }
+    }
+
+    /// Pushes a quad per edge of the contour, facing away from the interior. The uvs run along the contour.
+    fn push_sides(&mut self, contour: &[Vec2], half_depth: f32, size: f32) {
+        let count = contour.len();
+        let edge_normal = |index: usize| {
+            let direction = contour[(index + 1) % count] - contour[index];
+            Vec3::new(direction.y, -direction.x, 0.0).normalized()
+        };
+        let smooth = |lhs: Vec3, rhs: Vec3| {
+            if SMOOTH_ANGLE_COS <= Vec3::dot(lhs, rhs) {
+                (lhs + rhs).normalized()
+            } else {
+                rhs
+            }
+        };
+        let mut u = 0.0;
+
+        for index in 0..count {
+            let start = contour[index];
+            let end = contour[(index + 1) % count];
+            let normal = edge_normal(index);
+            let start_normal = smooth(edge_normal((index + count - 1) % count), normal);
+            let end_normal = smooth(edge_normal((index + 1) % count), normal);
+            let next_u = u + Vec2::distance(start, end) / size;
+            let base = self.positions.len() as u32;
+
+            for (point, normal, u) in [(start, start_normal, u), (end, end_normal, next_u)] {
+                self.push_vertex(
+                    Vec3::new(point.x, point.y, half_depth),
+                    normal,
+                    Vec2::new(u, 0.0),
+                );
+                self.push_vertex(
+                    Vec3::new(point.x, point.y, -half_depth),
+                    normal,
+                    Vec2::new(u, 1.0),
+                );
+            }
+
+            self.indices
+                .extend([base, base + 1, base + 3, base, base + 3, base + 2]);
+            u = next_u;
+        }
+    }
+
+    fn into_mesh(self) -> Mesh {
+        Mesh::new(MeshCpuData {
+            positions: self.positions,
+            normals: self.normals,
+            uvs: self.uvs,
+            indices: self.indices,
+            primitive: MeshPrimitive::Triangles,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn noto_sans() -> TextMeshFontHandle {
+        let data = include_bytes!("../../r3d-editor/assets/fonts/NotoSans-Regular.ttf");
+        TextMeshFontHandle::new(TextMeshFont::from_bytes(data.as_slice()).unwrap())
+    }
+
+    #[test]
+    fn extruded_text_is_closed_and_outward_facing() {
+        let font = noto_sans();
+        let mut renderer = TextMeshRenderer::new(font.clone(), "R3D", 2.0);
+        renderer.set_depth(0.5);
+        let mesh = renderer.take_mesh().unwrap();
+        assert!(renderer.take_mesh().is_none());
+        assert_eq!(font.cached_glyph_count(), 3);
+
+        // Every triangle faces the way its normals point.
+        let data = mesh.cpu_data().unwrap();
+        let mut area = 0.0;
+
+        for face in data.faces() {
+            let [a, b, c] = [0, 1, 2].map(|i| face[i] as usize);
+            let [pa, pb, pc] = [a, b, c].map(|i| data.positions[i]);
+            let cross = Vec3::cross(pb - pa, pc - pa);
+            let normal = data.normals[a];
+            assert!(-1e-6 <= Vec3::dot(cross, normal));
+
+            if 0.0 < normal.z {
+                area += cross.len() * 0.5;
+            }
+        }
+
+        assert!(0.0 < area);
+
+        let aabb = mesh.local_aabb().unwrap();
+        assert!((aabb.max.z - 0.25).abs() < 1e-5);
+        assert!((aabb.min.z + 0.25).abs() < 1e-5);
+
+        // Changing the text only triangulates the new glyphs.
+        renderer.set_text("R3D 3D");
+        renderer.take_mesh().unwrap();
+        assert_eq!(font.cached_glyph_count(), 4);
+    }
+}
@@ -1,18 +1,32 @@
+use super::GpuResourceTracker;
 use codegen::Handle;
 use image::{DynamicImage, GenericImageView};
 use std::sync::Arc;
+use thiserror::Error;
 use wgpu::{
     util::DeviceExt, AddressMode, Device, Extent3d, FilterMode, Queue, Sampler, SamplerDescriptor,
     TextureDescriptor, TextureDimension, TextureFormat, TextureUsages, TextureView,
+    TextureViewDescriptor, TextureViewDimension,
 };
 
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CubemapError {
+    #[error("faces of a cubemap must be square and have the same size")]
+    FaceSizeMismatch,
+    #[error("a cubemap strip must be six square faces stacked vertically")]
+    InvalidStrip,
+}
+
 #[derive(Handle)]
 pub struct Texture {
     pub texture: Arc<wgpu::Texture>,
     pub view: Arc<TextureView>,
     pub sampler: Arc<Sampler>,
+    /// The dimension of the view; [`TextureViewDimension::Cube`] for cubemaps.
+    pub view_dimension: TextureViewDimension,
     pub width: u16,
     pub height: u16,
+    _tracker: GpuResourceTracker,
 }
 
 impl Texture {
@@ -59,14 +73,162 @@ impl Texture {
         });
 
         Self {
+            _tracker: GpuResourceTracker::texture(&texture, None),
             texture: texture.into(),
             view: view.into(),
             sampler: sampler.into(),
+            view_dimension: TextureViewDimension::D2,
             width: width as u16,
             height: height as u16,
         }
     }
 
+    /// Creates a cubemap from six square faces of the same size, in order of `+x`, `-x`, `+y`, `-y`, `+z` and `-z`.
+    pub fn cubemap_from_images(
+        format: TextureFormat,
+        faces: [&DynamicImage; 6],
+        device: &Device,
+        queue: &Queue,
+    ) -> Result<Self, CubemapError> {
+        let size = faces[0].width();
+
+        if faces.iter().any(|face| face.dimensions() != (size, size)) {
+            return Err(CubemapError::FaceSizeMismatch);
+        }
+
+        let data = faces
+            .iter()
+            .flat_map(|face| face.as_bytes())
+            .copied()
+            .collect::<Vec<_>>();
+        Ok(Self::create_cubemap(format, size, &data, device, queue))
+    }
+
+    /// Creates a cubemap from an image of six square faces stacked vertically, in the order of
+    /// [`cubemap_from_images`](Self::cubemap_from_images).
+    pub fn cubemap_from_strip(
+        format: TextureFormat,
+        image: &DynamicImage,
+        device: &Device,
+        queue: &Queue,
+    ) -> Result<Self, CubemapError> {
+        let (width, height) = image.dimensions();
+
+        if width == 0 || height != width * 6 {
+            return Err(CubemapError::InvalidStrip);
+        }
+
+        // The rows of the faces are contiguous, so the strip is already laid out layer by layer.
+        Ok(Self::create_cubemap(
+            format,
+            width,
+            image.as_bytes(),
+            device,
+            queue,
+        ))
+    }
+
+    fn create_cubemap(
+        format: TextureFormat,
+        size: u32,
+        data: &[u8],
+        device: &Device,
+        queue: &Queue,
+    ) -> Self {
+        let texture = device.create_texture_with_data(
+            queue,
+            &TextureDescriptor {
+                label: None,
+                size: Extent3d {
+                    width: size,
+                    height: size,
+                    depth_or_array_layers: 6,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format,
+                usage: TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING,
+                view_formats: &[format],
+            },
+            data,
+        );
+        let view = texture.create_view(&TextureViewDescriptor {
+            dimension: Some(TextureViewDimension::Cube),
+            ..Default::default()
+        });
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: None,
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            address_mode_w: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            mipmap_filter: FilterMode::Linear,
+            lod_min_clamp: 0.0,
+            lod_max_clamp: 32.0,
+            compare: None,
+            anisotropy_clamp: 1,
+            border_color: None,
+        });
+
+        Self {
+            _tracker: GpuResourceTracker::texture(&texture, None),
+            texture: texture.into(),
+            view: view.into(),
+            sampler: sampler.into(),
+            view_dimension: TextureViewDimension::Cube,
+            width: size as u16,
+            height: size as u16,
+        }
+    }
+
+    /// Creates a cubemap that is rendered into layer by layer, e.g. by a [`ReflectionProbe`](super::ReflectionProbe).
+    pub fn create_cubemap_target(size: u16, format: TextureFormat, device: &Device) -> Self {
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some("cubemap target"),
+            size: Extent3d {
+                width: size as _,
+                height: size as _,
+                depth_or_array_layers: 6,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[format],
+        });
+        let view = texture.create_view(&TextureViewDescriptor {
+            dimension: Some(TextureViewDimension::Cube),
+            ..Default::default()
+        });
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: None,
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            address_mode_w: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            mipmap_filter: FilterMode::Linear,
+            lod_min_clamp: 0.0,
+            lod_max_clamp: 32.0,
+            compare: None,
+            anisotropy_clamp: 1,
+            border_color: None,
+        });
+
+        Self {
+            _tracker: GpuResourceTracker::texture(&texture, Some("cubemap target")),
+            texture: texture.into(),
+            view: view.into(),
+            sampler: sampler.into(),
+            view_dimension: TextureViewDimension::Cube,
+            width: size,
+            height: size,
+        }
+    }
+
     pub fn create_empty(width: u16, height: u16, format: TextureFormat, device: &Device) -> Self {
         let texture_extent = Extent3d {
             width: width as _,
@@ -100,9 +262,11 @@ impl Texture {
         });
 
         Self {
+            _tracker: GpuResourceTracker::texture(&texture, None),
             texture: texture.into(),
             view: view.into(),
             sampler: sampler.into(),
+            view_dimension: TextureViewDimension::D2,
             width,
             height,
         }
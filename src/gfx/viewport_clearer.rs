@@ -0,0 +1,298 @@
+use super::{CameraClearMode, DepthStencilMode};
+use wgpu::{
+    BlendComponent, BlendFactor, BlendOperation, BlendState, ColorTargetState, ColorWrites,
+    CompareFunction, DepthBiasState, DepthStencilState, Device, FragmentState, MultisampleState,
+    PipelineLayoutDescriptor, PrimitiveState, RenderPass, RenderPipeline, RenderPipelineDescriptor,
+    ShaderModuleDescriptor, ShaderSource, StencilFaceState, StencilOperation, StencilState,
+    TextureFormat, VertexState,
+};
+
+const SHADER_SOURCE: &str = r#"
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 1.0, 1.0);
+}
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0, 1.0, 1.0, 1.0);
+}
+"#;
+
+/// A viewport in physical pixels, with the origin at the top-left corner of the surface.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhysicalViewport {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// Clears a part of the frame buffer by drawing a triangle that covers the current viewport.
+/// Load operations of a render pass always clear the whole attachment, so cameras that render into
+/// a part of the screen use this instead.
+pub struct ViewportClearer {
+    clear_all_pipeline: RenderPipeline,
+    clear_depth_only_pipeline: Option<RenderPipeline>,
+}
+
+impl ViewportClearer {
+    pub fn new(
+        device: &Device,
+        color_format: TextureFormat,
+        depth_stencil_mode: DepthStencilMode,
+    ) -> Self {
+        let clear_all_pipeline =
+            create_pipeline(device, color_format, depth_stencil_mode, ColorWrites::ALL);
+        let clear_depth_only_pipeline = depth_stencil_mode.as_texture_format().map(|_| {
+            create_pipeline(
+                device,
+                color_format,
+                depth_stencil_mode,
+                ColorWrites::empty(),
+            )
+        });
+
+        Self {
+            clear_all_pipeline,
+            clear_depth_only_pipeline,
+        }
+    }
+
+    /// Clears the given viewport as specified by the clear mode, and leaves the viewport set.
+    pub fn clear<'r>(
+        &'r self,
+        render_pass: &mut RenderPass<'r>,
+        clear_mode: &CameraClearMode,
+        viewport: PhysicalViewport,
+    ) {
+        let (pipeline, depth, stencil) = match clear_mode {
+            CameraClearMode::Keep => {
+                render_pass.set_viewport(
+                    viewport.x,
+                    viewport.y,
+                    viewport.width,
+                    viewport.height,
+                    0.0,
+                    1.0,
+                );
+                return;
+            }
+            CameraClearMode::All {
+                color,
+                depth,
+                stencil,
+            } => {
+                render_pass.set_blend_constant(wgpu::Color {
+                    r: color.r as f64,
+                    g: color.g as f64,
+                    b: color.b as f64,
+                    a: color.a as f64,
+                });
+                (&self.clear_all_pipeline, *depth, *stencil)
+            }
+            // The skybox is drawn over the viewport afterwards, so only the depth and stencil are cleared.
+            CameraClearMode::DepthOnly { depth, stencil }
+            | CameraClearMode::Skybox { depth, stencil, .. } => {
+                match self.clear_depth_only_pipeline.as_ref() {
+                    Some(pipeline) => (pipeline, *depth, *stencil),
+                    None => {
+                        render_pass.set_viewport(
+                            viewport.x,
+                            viewport.y,
+                            viewport.width,
+                            viewport.height,
+                            0.0,
+                            1.0,
+                        );
+                        return;
+                    }
+                }
+            }
+        };
+
+        // The triangle is placed at the far plane, so the depth range of the viewport decides the depth value.
+        render_pass.set_viewport(
+            viewport.x,
+            viewport.y,
+            viewport.width,
+            viewport.height,
+            depth,
+            depth,
+        );
+        render_pass.set_stencil_reference(stencil);
+        render_pass.set_pipeline(pipeline);
+        render_pass.draw(0..3, 0..1);
+
+        render_pass.set_viewport(
+            viewport.x,
+            viewport.y,
+            viewport.width,
+            viewport.height,
+            0.0,
+            1.0,
+        );
+    }
+}
+
+fn create_pipeline(
+    device: &Device,
+    color_format: TextureFormat,
+    depth_stencil_mode: DepthStencilMode,
+    write_mask: ColorWrites,
+) -> RenderPipeline {
+    let shader_module = device.create_shader_module(ShaderModuleDescriptor {
+        label: Some("viewport clear shader"),
+        source: ShaderSource::Wgsl(SHADER_SOURCE.into()),
+    });
+    let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+        label: Some("viewport clear pipeline layout"),
+        bind_group_layouts: &[],
+        push_constant_ranges: &[],
+    });
+    // The output color is replaced by the blend constant, which holds the clear color.
+    let blend_component = BlendComponent {
+        src_factor: BlendFactor::Constant,
+        dst_factor: BlendFactor::Zero,
+        operation: BlendOperation::Add,
+    };
+    let stencil_face = StencilFaceState {
+        compare: CompareFunction::Always,
+        fail_op: StencilOperation::Replace,
+        depth_fail_op: StencilOperation::Replace,
+        pass_op: StencilOperation::Replace,
+    };
+
+    device.create_render_pipeline(&RenderPipelineDescriptor {
+        label: Some("viewport clear pipeline"),
+        layout: Some(&layout),
+        vertex: VertexState {
+            module: &shader_module,
+            entry_point: "vs_main",
+            buffers: &[],
+        },
+        primitive: PrimitiveState::default(),
+        depth_stencil: depth_stencil_mode
+            .as_texture_format()
+            .map(|format| DepthStencilState {
+                format,
+                depth_write_enabled: true,
+                depth_compare: CompareFunction::Always,
+                stencil: match depth_stencil_mode {
+                    DepthStencilMode::DepthStencil => StencilState {
+                        front: stencil_face,
+                        back: stencil_face,
+                        read_mask: 0xFF,
+                        write_mask: 0xFF,
+                    },
+                    _ => StencilState::default(),
+                },
+                bias: DepthBiasState::default(),
+            }),
+        multisample: MultisampleState::default(),
+        fragment: Some(FragmentState {
+            module: &shader_module,
+            entry_point: "fs_main",
+            targets: &[Some(ColorTargetState {
+                format: color_format,
+                blend: Some(BlendState {
+                    color: blend_component,
+                    alpha: blend_component,
+                }),
+                write_mask,
+            })],
+        }),
+        multiview: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gfx::{read_texture_rgba8, test_gfx_ctx, CameraViewport, Color};
+    use winit::dpi::PhysicalSize;
+
+    #[test]
+    fn cameras_clear_their_own_half_of_the_target() {
+        let gfx_ctx = match test_gfx_ctx(PhysicalSize::new(8, 4)) {
+            Some(gfx_ctx) => gfx_ctx,
+            None => {
+                eprintln!("skipped: no adapter found");
+                return;
+            }
+        };
+        let (width, height) = (8, 4);
+        let texture = gfx_ctx.device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&Default::default());
+        let clearer = ViewportClearer::new(
+            &gfx_ctx.device,
+            TextureFormat::Rgba8Unorm,
+            DepthStencilMode::None,
+        );
+        let cameras = [
+            (CameraViewport::new(0.0, 0.0, 0.5, 1.0), Color::red()),
+            (CameraViewport::new(0.5, 0.0, 0.5, 1.0), Color::blue()),
+        ];
+
+        // Each camera renders in its own pass, loading what the previous cameras rendered.
+        let mut encoder = gfx_ctx.device.create_command_encoder(&Default::default());
+
+        for (viewport, color) in cameras {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+            clearer.clear(
+                &mut render_pass,
+                &CameraClearMode::All {
+                    color,
+                    depth: 1.0,
+                    stencil: 0,
+                },
+                viewport.to_physical(width as f32, height as f32),
+            );
+        }
+
+        gfx_ctx.queue.submit(std::iter::once(encoder.finish()));
+
+        let pixels = read_texture_rgba8(&gfx_ctx, &texture);
+
+        for (index, pixel) in pixels.into_iter().enumerate() {
+            let x = index as u32 % width;
+            let expected = if x < width / 2 {
+                [255, 0, 0, 255]
+            } else {
+                [0, 0, 255, 255]
+            };
+            assert_eq!(
+                pixel,
+                expected,
+                "pixel {} of row {}",
+                x,
+                index as u32 / width
+            );
+        }
+    }
+}
@@ -2,6 +2,129 @@ use crate::input::{InputDevice, RawInput, RawInputEventDispatcher};
 use std::collections::HashMap;
 use winit::event::{ElementState, KeyboardInput, VirtualKeyCode};
 
+/// The names of the keyboard inputs, in the order of [`Keyboard::inputs`](InputDevice::inputs).
+pub const KEY_NAMES: [&str; 119] = [
+    "1",
+    "2",
+    "3",
+    "4",
+    "5",
+    "6",
+    "7",
+    "8",
+    "9",
+    "0",
+    "a",
+    "b",
+    "c",
+    "d",
+    "e",
+    "f",
+    "g",
+    "h",
+    "i",
+    "j",
+    "k",
+    "l",
+    "m",
+    "n",
+    "o",
+    "p",
+    "q",
+    "r",
+    "s",
+    "t",
+    "u",
+    "v",
+    "w",
+    "x",
+    "y",
+    "z",
+    "escape",
+    "f1",
+    "f2",
+    "f3",
+    "f4",
+    "f5",
+    "f6",
+    "f7",
+    "f8",
+    "f9",
+    "f10",
+    "f11",
+    "f12",
+    "f13",
+    "f14",
+    "f15",
+    "f16",
+    "f17",
+    "f18",
+    "f19",
+    "f20",
+    "f21",
+    "f22",
+    "f23",
+    "f24",
+    "printscreen",
+    "scrolllock",
+    "pause",
+    "insert",
+    "home",
+    "delete",
+    "end",
+    "pagedown",
+    "pageup",
+    "left",
+    "up",
+    "right",
+    "down",
+    "backspace",
+    "enter",
+    "space",
+    "numlock",
+    "numpad0",
+    "numpad1",
+    "numpad2",
+    "numpad3",
+    "numpad4",
+    "numpad5",
+    "numpad6",
+    "numpad7",
+    "numpad8",
+    "numpad9",
+    "numpadadd",
+    "numpaddivide",
+    "numpaddecimal",
+    "numpadcomma",
+    "numpadenter",
+    "numpadequal",
+    "numpadmultiply",
+    "numpadsubtract",
+    "asterisk",
+    "at",
+    "backslash",
+    "colon",
+    "comma",
+    "equal",
+    "grave",
+    "alt:l",
+    "bracket:l",
+    "control:l",
+    "shift:l",
+    "os:l",
+    "minus",
+    "period",
+    "plus",
+    "alt:r",
+    "bracket:r",
+    "control:r",
+    "shift:r",
+    "os:r",
+    "semicolon",
+    "slash",
+    "tab",
+];
+
 pub struct Keyboard {
     inputs: Vec<RawInput>,
     input_names: HashMap<String, usize>,
@@ -10,126 +133,10 @@ pub struct Keyboard {
 
 impl Keyboard {
     pub fn new() -> Self {
-        let inputs = vec![
-            RawInput::new("1"),
-            RawInput::new("2"),
-            RawInput::new("3"),
-            RawInput::new("4"),
-            RawInput::new("5"),
-            RawInput::new("6"),
-            RawInput::new("7"),
-            RawInput::new("8"),
-            RawInput::new("9"),
-            RawInput::new("0"),
-            RawInput::new("a"),
-            RawInput::new("b"),
-            RawInput::new("c"),
-            RawInput::new("d"),
-            RawInput::new("e"),
-            RawInput::new("f"),
-            RawInput::new("g"),
-            RawInput::new("h"),
-            RawInput::new("i"),
-            RawInput::new("j"),
-            RawInput::new("k"),
-            RawInput::new("l"),
-            RawInput::new("m"),
-            RawInput::new("n"),
-            RawInput::new("o"),
-            RawInput::new("p"),
-            RawInput::new("q"),
-            RawInput::new("r"),
-            RawInput::new("s"),
-            RawInput::new("t"),
-            RawInput::new("u"),
-            RawInput::new("v"),
-            RawInput::new("w"),
-            RawInput::new("x"),
-            RawInput::new("y"),
-            RawInput::new("z"),
-            RawInput::new("escape"),
-            RawInput::new("f1"),
-            RawInput::new("f2"),
-            RawInput::new("f3"),
-            RawInput::new("f4"),
-            RawInput::new("f5"),
-            RawInput::new("f6"),
-            RawInput::new("f7"),
-            RawInput::new("f8"),
-            RawInput::new("f9"),
-            RawInput::new("f10"),
-            RawInput::new("f11"),
-            RawInput::new("f12"),
-            RawInput::new("f13"),
-            RawInput::new("f14"),
-            RawInput::new("f15"),
-            RawInput::new("f16"),
-            RawInput::new("f17"),
-            RawInput::new("f18"),
-            RawInput::new("f19"),
-            RawInput::new("f20"),
-            RawInput::new("f21"),
-            RawInput::new("f22"),
-            RawInput::new("f23"),
-            RawInput::new("f24"),
-            RawInput::new("printscreen"),
-            RawInput::new("scrolllock"),
-            RawInput::new("pause"),
-            RawInput::new("insert"),
-            RawInput::new("home"),
-            RawInput::new("delete"),
-            RawInput::new("end"),
-            RawInput::new("pagedown"),
-            RawInput::new("pageup"),
-            RawInput::new("left"),
-            RawInput::new("up"),
-            RawInput::new("right"),
-            RawInput::new("down"),
-            RawInput::new("backspace"),
-            RawInput::new("enter"),
-            RawInput::new("space"),
-            RawInput::new("numlock"),
-            RawInput::new("numpad0"),
-            RawInput::new("numpad1"),
-            RawInput::new("numpad2"),
-            RawInput::new("numpad3"),
-            RawInput::new("numpad4"),
-            RawInput::new("numpad5"),
-            RawInput::new("numpad6"),
-            RawInput::new("numpad7"),
-            RawInput::new("numpad8"),
-            RawInput::new("numpad9"),
-            RawInput::new("numpadadd"),
-            RawInput::new("numpaddivide"),
-            RawInput::new("numpaddecimal"),
-            RawInput::new("numpadcomma"),
-            RawInput::new("numpadenter"),
-            RawInput::new("numpadequal"),
-            RawInput::new("numpadmultiply"),
-            RawInput::new("numpadsubtract"),
-            RawInput::new("asterisk"),
-            RawInput::new("at"),
-            RawInput::new("backslash"),
-            RawInput::new("colon"),
-            RawInput::new("comma"),
-            RawInput::new("equal"),
-            RawInput::new("grave"),
-            RawInput::new("alt:l"),
-            RawInput::new("bracket:l"),
-            RawInput::new("control:l"),
-            RawInput::new("shift:l"),
-            RawInput::new("os:l"),
-            RawInput::new("minus"),
-            RawInput::new("plus"),
-            RawInput::new("alt:r"),
-            RawInput::new("bracket:r"),
-            RawInput::new("control:r"),
-            RawInput::new("shift:r"),
-            RawInput::new("os:r"),
-            RawInput::new("semicolon"),
-            RawInput::new("slash"),
-            RawInput::new("tab"),
-        ];
+        let inputs = KEY_NAMES
+            .iter()
+            .map(|&name| RawInput::new(name))
+            .collect::<Vec<_>>();
         let input_names = inputs
             .iter()
             .enumerate()
@@ -323,7 +330,7 @@ fn virtual_keycode_into_raw_input_name(virtual_keycode: VirtualKeyCode) -> Optio
         VirtualKeyCode::NextTrack => None,
         VirtualKeyCode::NoConvert => None,
         VirtualKeyCode::OEM102 => None,
-        VirtualKeyCode::Period => None,
+        VirtualKeyCode::Period => Some("period"),
         VirtualKeyCode::PlayPause => None,
         VirtualKeyCode::Plus => Some("plus"),
         VirtualKeyCode::Power => None,
@@ -1,8 +1,11 @@
-use crate::input::{InputDevice, RawInput, RawInputEventDispatcher};
+use crate::{
+    input::{InputDevice, RawInput, RawInputEventDispatcher},
+    math::Vec2,
+};
 use std::collections::HashMap;
 use winit::{
     dpi::PhysicalPosition,
-    event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent},
+    event::{DeviceEvent, ElementState, MouseButton, MouseScrollDelta, WindowEvent},
 };
 
 pub enum MouseWindowEvent {
@@ -16,6 +19,9 @@ pub enum MouseWindowEvent {
         state: ElementState,
         button: MouseButton,
     },
+    MouseMotion {
+        delta: (f64, f64),
+    },
 }
 
 pub struct Mouse {
@@ -31,6 +37,8 @@ impl Mouse {
             RawInput::new("y"),
             RawInput::new("delta:x"),
             RawInput::new("delta:y"),
+            RawInput::new("motion:x"),
+            RawInput::new("motion:y"),
             RawInput::new("scroll:x"),
             RawInput::new("scroll:y"),
             RawInput::new("button:left"),
@@ -70,6 +78,36 @@ impl Mouse {
             _ => {}
         }
     }
+
+    /// Returns the cursor position in physical pixels, as of the last poll.
+    pub fn position(&self) -> Vec2 {
+        Vec2::new(self.value("x"), self.value("y"))
+    }
+
+    /// Returns how far the cursor moved in the last frame, in physical pixels. It stays zero while the cursor
+    /// is locked; use [`motion`](Self::motion) instead.
+    pub fn delta(&self) -> Vec2 {
+        Vec2::new(self.value("delta:x"), self.value("delta:y"))
+    }
+
+    /// Returns the raw motion of the mouse in the last frame, accumulated from the device events. It is
+    /// independent of the cursor position, so it keeps reporting while the cursor is locked or at the edge of
+    /// the screen.
+    pub fn motion(&self) -> Vec2 {
+        Vec2::new(self.value("motion:x"), self.value("motion:y"))
+    }
+
+    fn value(&self, name: &str) -> f32 {
+        self.inputs[self.input_names[name]].value
+    }
+
+    /// Handles raw mouse motion. Unlike the cursor delta, it keeps reporting while the cursor is locked.
+    pub fn handle_device_event(&mut self, event: &DeviceEvent) {
+        if let DeviceEvent::MouseMotion { delta } = event {
+            self.window_event_queue
+                .push(MouseWindowEvent::MouseMotion { delta: *delta });
+        }
+    }
 }
 
 impl InputDevice for Mouse {
@@ -89,6 +127,8 @@ impl InputDevice for Mouse {
         let mut is_delta_changed = false
             || self.inputs[self.input_names["delta:x"]].value != 0.0
             || self.inputs[self.input_names["delta:y"]].value != 0.0;
+        let mut is_motion_changed = self.inputs[self.input_names["motion:x"]].value != 0.0
+            || self.inputs[self.input_names["motion:y"]].value != 0.0;
         let mut is_scroll_changed = false
             || self.inputs[self.input_names["scroll:x"]].value != 0.0
             || self.inputs[self.input_names["scroll:y"]].value != 0.0;
@@ -97,6 +137,9 @@ impl InputDevice for Mouse {
         self.inputs[self.input_names["delta:x"]].value = 0.0;
         self.inputs[self.input_names["delta:y"]].value = 0.0;
 
+        self.inputs[self.input_names["motion:x"]].value = 0.0;
+        self.inputs[self.input_names["motion:y"]].value = 0.0;
+
         self.inputs[self.input_names["scroll:x"]].value = 0.0;
         self.inputs[self.input_names["scroll:y"]].value = 0.0;
 
@@ -152,6 +195,12 @@ impl InputDevice for Mouse {
 
                     dispatcher.dispatch(&self.inputs[button_index]);
                 }
+                MouseWindowEvent::MouseMotion { delta } => {
+                    self.inputs[self.input_names["motion:x"]].value += delta.0 as f32;
+                    self.inputs[self.input_names["motion:y"]].value += delta.1 as f32;
+
+                    is_motion_changed = true;
+                }
             }
         }
 
@@ -160,9 +209,45 @@ impl InputDevice for Mouse {
             dispatcher.dispatch(&self.inputs[self.input_names["delta:y"]]);
         }
 
+        if is_motion_changed {
+            dispatcher.dispatch(&self.inputs[self.input_names["motion:x"]]);
+            dispatcher.dispatch(&self.inputs[self.input_names["motion:y"]]);
+        }
+
         if is_scroll_changed {
             dispatcher.dispatch(&self.inputs[self.input_names["scroll:x"]]);
             dispatcher.dispatch(&self.inputs[self.input_names["scroll:y"]]);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn motion_accumulates_independent_of_position() {
+        let mut mouse = Mouse::new();
+        let mut dispatcher = RawInputEventDispatcher::new();
+
+        mouse
+            .window_event_queue
+            .push(MouseWindowEvent::CursorMoved {
+                position: PhysicalPosition::new(100.0, 50.0),
+            });
+        mouse.poll(&mut dispatcher);
+
+        // A locked cursor stays in place while the device keeps reporting motion.
+        for delta in [(3.0, -1.0), (4.5, 2.0), (-0.5, 1.0)] {
+            mouse.handle_device_event(&DeviceEvent::MouseMotion { delta });
+        }
+        mouse.poll(&mut dispatcher);
+
+        assert_eq!(mouse.position(), Vec2::new(100.0, 50.0));
+        assert_eq!(mouse.delta(), Vec2::ZERO);
+        assert_eq!(mouse.motion(), Vec2::new(7.0, 2.0));
+
+        mouse.poll(&mut dispatcher);
+        assert_eq!(mouse.motion(), Vec2::ZERO);
+    }
+}
@@ -1,53 +1,86 @@
+// Lets `codegen` derives refer to this crate as `::r3d`, both inside and outside of it.
+extern crate self as r3d;
+
 use self::{
     ecs_system::{
         render::RenderSystem, update_camera_transform_buffer::UpdateCameraTransformBufferSystem,
+        update_renderer_bvh::UpdateRendererBvh,
     },
     gfx::{
-        Camera, DepthStencilMode, GfxContext, GfxContextCreationError, GfxContextHandle,
-        RenderManager, ScreenManager, ShaderManager,
+        Bvh, CaptureConfig, CaptureStats, DepthStencilMode, FrameCaptureError, GfxContext,
+        GfxContextCreationError, GfxContextHandle, GfxContextOptions, GfxError, GlyphAtlasConfig,
+        RenderManager, ScreenManager, SecondaryWindow, SecondaryWindowCreationError,
+        SecondaryWindowManager, ShaderManager,
     },
-    time::TimeManager,
-    vsync::TargetFrameInterval,
+    time::{Instant, TimeManager},
+    util::{Rng, RngManager},
+    vsync::{AdaptiveLoop, FramePacing, TargetFrameInterval},
 };
 use codegen::Handle;
 use ecs_system::{
-    make_ui_scaler_dirty::MakeUIScalerDirty, update_ui_element::UpdateUIElement,
-    update_ui_raycast_grid::UpdateUIRaycastGrid, update_ui_scaler::UpdateUIScaler,
+    make_ui_scaler_dirty::{mark_ui_scalers_dirty, MakeUIScalerDirty},
+    update_camera_controllers::UpdateCameraControllers,
+    update_particle_emitters::UpdateParticleEmitters,
+    update_sprite_animations::UpdateSpriteAnimations,
+    update_tweens::UpdateTweens,
+    update_ui_element::UpdateUIElement,
+    update_ui_localized_text::UpdateUILocalizedText,
+    update_ui_progress_bar::UpdateUIProgressBar,
+    update_ui_raycast_grid::UpdateUIRaycastGrid,
+    update_ui_scaler::UpdateUIScaler,
 };
 use event::{event_types, EventManager};
-use gfx::{BuiltInShaderManager, GlyphManager, MeshRenderer, UIElementRenderer, UITextRenderer};
+use frame_snapshot::FrameSnapshot;
+use gfx::{BuiltInShaderManager, GlyphManager};
 use input::InputManager;
+use localization::LocalizationManager;
+use logging::{log_error, log_warn, transports::ConsoleTransport, Logger};
 use math::Vec2;
-use object::{Object, ObjectManager};
+use object::ObjectManager;
 use object_event::ObjectEventManager;
+use scheduler::Scheduler;
+use serde::{Deserialize, Serialize};
+use settings::{SettingsManager, WindowSettings};
 use specs::prelude::*;
 use std::{
-    cell::{Ref, RefCell, RefMut},
+    cell::{Cell, Ref, RefCell, RefMut},
     mem::MaybeUninit,
     num::NonZeroU32,
-    time::Instant,
+    sync::Arc,
 };
 use thiserror::Error;
-use transform::Transform;
-use ui::{UIElement, UIEventManager, UIRaycastManager, UIScaler, UISize};
+use tween::TweenManager;
+use ui::{UIEventManager, UIRaycastManager};
 use wgpu::MaintainBase;
 use winit::{
     dpi::{LogicalSize, PhysicalSize},
-    event::{Event, WindowEvent},
-    event_loop::{ControlFlow, EventLoop},
-    window::{Window, WindowBuilder},
+    error::ExternalError,
+    event::{DeviceEvent, ElementState, Event, MouseButton, WindowEvent},
+    event_loop::{ControlFlow, EventLoop, EventLoopWindowTarget},
+    window::{CursorGrabMode, Fullscreen, Window, WindowBuilder, WindowId},
 };
 
 pub mod asset;
+pub mod camera_controller;
+pub mod component_registry;
+pub mod debug_overlay;
 pub mod ecs_system;
 pub mod event;
+pub mod frame_snapshot;
 pub mod gfx;
 pub mod input;
+pub mod localization;
 pub mod math;
 pub mod object;
 pub mod object_event;
+pub mod scene;
+pub mod scheduler;
+#[cfg(feature = "scripting")]
+pub mod script;
+pub mod settings;
 pub mod time;
 pub mod transform;
+pub mod tween;
 pub mod ui;
 pub mod util;
 pub mod vsync;
@@ -55,9 +88,17 @@ pub mod vsync;
 // re-exports.
 pub use fontdue;
 pub use image;
+#[doc(hidden)]
+pub use inventory;
+pub use logging;
+#[cfg(feature = "scripting")]
+pub use mlua;
+#[cfg(feature = "assimp")]
 pub use russimp;
 pub use specs;
 pub use wgpu;
+pub use winit;
+pub use winit::window::CursorIcon;
 
 static mut CONTEXT: MaybeUninit<ContextHandle> = MaybeUninit::uninit();
 
@@ -78,30 +119,52 @@ pub struct Context {
     object_mgr: RefCell<ObjectManager>,
     screen_mgr: RefCell<ScreenManager>,
     render_mgr: RefCell<RenderManager>,
+    secondary_window_mgr: RefCell<SecondaryWindowManager>,
     glyph_mgr: RefCell<GlyphManager>,
+    renderer_bvh: RefCell<Bvh>,
     shader_mgr: ShaderManager,
     built_in_shader_mgr: BuiltInShaderManager,
     ui_raycast_mgr: RefCell<UIRaycastManager>,
     ui_event_mgr: RefCell<UIEventManager>,
     time_mgr: RefCell<TimeManager>,
+    tween_mgr: RefCell<TweenManager>,
     input_mgr: RefCell<InputManager>,
+    frame: Cell<FrameSnapshot>,
+    localization_mgr: RefCell<LocalizationManager>,
+    rng: RefCell<Rng>,
+    rng_mgr: RefCell<RngManager>,
+    settings_mgr: RefCell<SettingsManager>,
     event_mgr: EventManager,
+    scheduler: Scheduler,
+    #[cfg(feature = "scripting")]
+    script_mgr: script::ScriptManager,
     object_event_mgr: ObjectEventManager,
 }
 
 impl Context {
-    pub fn new(window: Window, gfx_ctx: GfxContext, screen_width: u32, screen_height: u32) -> Self {
+    pub fn new(
+        window: Window,
+        gfx_ctx: GfxContext,
+        screen_width: u32,
+        screen_height: u32,
+        glyph_atlas_config: GlyphAtlasConfig,
+        rng_seed: Option<u64>,
+        settings_mgr: SettingsManager,
+    ) -> Self {
         let gfx_ctx = GfxContextHandle::new(gfx_ctx);
         let world = World::new().into();
         let object_mgr = ObjectManager::new().into();
-        let screen_mgr = ScreenManager::new(screen_width, screen_height).into();
+        let screen_mgr: RefCell<ScreenManager> =
+            ScreenManager::new(screen_width, screen_height).into();
         let render_mgr: RefCell<RenderManager> = RenderManager::new(
             gfx_ctx.clone(),
             PhysicalSize::new(screen_width, screen_height),
             DepthStencilMode::DepthOnly,
         )
         .into();
-        let glyph_mgr = GlyphManager::new(gfx_ctx.clone()).into();
+        let secondary_window_mgr = SecondaryWindowManager::new().into();
+        let glyph_mgr = GlyphManager::new(gfx_ctx.clone(), glyph_atlas_config).into();
+        let renderer_bvh = Bvh::new().into();
         let shader_mgr = ShaderManager::new(gfx_ctx.clone());
         let mut built_in_shader_mgr = BuiltInShaderManager::new();
         built_in_shader_mgr.init(
@@ -110,9 +173,24 @@ impl Context {
         );
         let ui_raycast_mgr = UIRaycastManager::new().into();
         let ui_event_mgr = UIEventManager::new().into();
-        let time_mgr = TimeManager::new().into();
-        let input_mgr = InputManager::new().into();
+        let time_mgr: RefCell<TimeManager> = TimeManager::new().into();
+        let tween_mgr = TweenManager::new().into();
+        let input_mgr: RefCell<InputManager> = InputManager::new().into();
+        let frame = FrameSnapshot::capture(
+            0,
+            &time_mgr.borrow(),
+            &screen_mgr.borrow(),
+            &input_mgr.borrow(),
+        )
+        .into();
+        let localization_mgr = LocalizationManager::new().into();
+        let rng_seed = rng_seed.unwrap_or_else(|| Rng::from_time().seed());
+        let rng = Rng::new(rng_seed).into();
+        let rng_mgr = RngManager::new(rng_seed).into();
         let event_mgr = EventManager::new();
+        let scheduler = Scheduler::new();
+        #[cfg(feature = "scripting")]
+        let script_mgr = script::ScriptManager::new();
         let object_event_mgr = ObjectEventManager::new();
 
         Self {
@@ -122,22 +200,76 @@ impl Context {
             object_mgr,
             screen_mgr,
             render_mgr,
+            secondary_window_mgr,
             glyph_mgr,
+            renderer_bvh,
             shader_mgr,
             built_in_shader_mgr: built_in_shader_mgr.into(),
             ui_raycast_mgr,
             ui_event_mgr,
             time_mgr,
+            tween_mgr,
             input_mgr,
+            frame,
+            localization_mgr,
+            rng,
+            rng_mgr,
+            settings_mgr: settings_mgr.into(),
             event_mgr,
+            scheduler,
+            #[cfg(feature = "scripting")]
+            script_mgr,
             object_event_mgr,
         }
     }
 
+    /// Returns the underlying window. Prefer the methods of the context, which do not change with winit.
     pub fn window(&self) -> &Window {
         &self.window
     }
 
+    pub fn set_title(&self, title: &str) {
+        self.window.set_title(title);
+    }
+
+    pub fn is_fullscreen(&self) -> bool {
+        self.window.fullscreen().is_some()
+    }
+
+    /// Makes the window borderless fullscreen on its current monitor, or windowed again.
+    pub fn set_fullscreen(&self, fullscreen: bool) {
+        self.window
+            .set_fullscreen(fullscreen.then_some(Fullscreen::Borderless(None)));
+    }
+
+    /// Requests a frame to be rendered, e.g. after a change while the engine waits for events in
+    /// [`EngineLoopMode::Wait`]. In [`EngineLoopMode::Adaptive`], it also makes the engine poll again.
+    pub fn request_redraw(&self) {
+        self.window.request_redraw();
+    }
+
+    /// Sets the cursor icon of the window. Elements with [`UICursor`](ui::UICursor) override it while hovered.
+    pub fn set_cursor_icon(&self, icon: CursorIcon) {
+        self.window.set_cursor_icon(icon);
+    }
+
+    /// Grabs the cursor so that it cannot leave the window, e.g. for first-person controls. Read the raw motion
+    /// with [`Mouse::motion`](input::Mouse::motion) meanwhile, since the cursor position stops changing.
+    /// Platforms that cannot lock the cursor in place confine it to the window instead.
+    pub fn set_cursor_grab(&self, grab: bool) -> Result<(), ExternalError> {
+        if !grab {
+            return self.window.set_cursor_grab(CursorGrabMode::None);
+        }
+
+        self.window
+            .set_cursor_grab(CursorGrabMode::Locked)
+            .or_else(|_| self.window.set_cursor_grab(CursorGrabMode::Confined))
+    }
+
+    pub fn set_cursor_visible(&self, visible: bool) {
+        self.window.set_cursor_visible(visible);
+    }
+
     pub fn gfx_ctx(&self) -> &GfxContextHandle {
         &self.gfx_ctx
     }
@@ -174,6 +306,26 @@ impl Context {
         self.render_mgr.borrow_mut()
     }
 
+    /// Starts recording the frames of the window into a directory, e.g. to capture a short clip of a bug.
+    /// See [`RenderManager::start_capture`].
+    pub fn start_capture(&self, config: CaptureConfig) -> Result<(), FrameCaptureError> {
+        self.render_mgr_mut().start_capture(config)
+    }
+
+    /// Stops the capture, and returns how many frames it has written and dropped.
+    pub fn stop_capture(&self) -> Option<CaptureStats> {
+        self.render_mgr_mut().stop_capture()
+    }
+
+    /// Returns the windows created by [`Engine::create_secondary_window`].
+    pub fn secondary_window_mgr(&self) -> Ref<SecondaryWindowManager> {
+        self.secondary_window_mgr.borrow()
+    }
+
+    pub fn secondary_window_mgr_mut(&self) -> RefMut<SecondaryWindowManager> {
+        self.secondary_window_mgr.borrow_mut()
+    }
+
     pub fn glyph_mgr(&self) -> Ref<GlyphManager> {
         self.glyph_mgr.borrow()
     }
@@ -182,6 +334,16 @@ impl Context {
         self.glyph_mgr.borrow_mut()
     }
 
+    /// Returns the bounding volume hierarchy over the world-space AABBs of the mesh renderers.
+    /// It's updated once per frame, after the object matrices.
+    pub fn renderer_bvh(&self) -> Ref<Bvh> {
+        self.renderer_bvh.borrow()
+    }
+
+    pub fn renderer_bvh_mut(&self) -> RefMut<Bvh> {
+        self.renderer_bvh.borrow_mut()
+    }
+
     pub fn shader_mgr(&self) -> &ShaderManager {
         &self.shader_mgr
     }
@@ -214,6 +376,14 @@ impl Context {
         self.time_mgr.borrow_mut()
     }
 
+    pub fn tween_mgr(&self) -> Ref<TweenManager> {
+        self.tween_mgr.borrow()
+    }
+
+    pub fn tween_mgr_mut(&self) -> RefMut<TweenManager> {
+        self.tween_mgr.borrow_mut()
+    }
+
     pub fn input_mgr(&self) -> Ref<InputManager> {
         self.input_mgr.borrow()
     }
@@ -222,10 +392,73 @@ impl Context {
         self.input_mgr.borrow_mut()
     }
 
+    /// Returns the time, screen and input state of the current frame. Unlike the managers, it can be read
+    /// anywhere without borrowing; see [`FrameSnapshot`] for when it is captured.
+    pub fn frame(&self) -> FrameSnapshot {
+        self.frame.get()
+    }
+
+    /// Captures the snapshot of the next frame, once its clocks are updated and its input is polled.
+    pub(crate) fn capture_frame(&self) {
+        let frame_index = self.frame.get().time.frame_index + 1;
+        self.frame.set(FrameSnapshot::capture(
+            frame_index,
+            &self.time_mgr(),
+            &self.screen_mgr(),
+            &self.input_mgr(),
+        ));
+    }
+
+    pub fn localization_mgr(&self) -> Ref<LocalizationManager> {
+        self.localization_mgr.borrow()
+    }
+
+    pub fn localization_mgr_mut(&self) -> RefMut<LocalizationManager> {
+        self.localization_mgr.borrow_mut()
+    }
+
+    /// Returns the global random number generator. Systems that need their own sequence should
+    /// [`fork`](Rng::fork) it once, so that they do not affect each other.
+    pub fn rng(&self) -> Ref<Rng> {
+        self.rng.borrow()
+    }
+
+    pub fn rng_mut(&self) -> RefMut<Rng> {
+        self.rng.borrow_mut()
+    }
+
+    /// Returns the named deterministic streams, e.g. `rng_mgr_mut().rng("gameplay")`. They share the run seed of
+    /// the global generator; record [`RngManager::run_seed`] alongside an input recording to replay it.
+    pub fn rng_mgr(&self) -> Ref<RngManager> {
+        self.rng_mgr.borrow()
+    }
+
+    pub fn rng_mgr_mut(&self) -> RefMut<RngManager> {
+        self.rng_mgr.borrow_mut()
+    }
+
+    /// Returns the settings that persist across runs. See [`EngineConfig::settings_app_name`].
+    pub fn settings_mgr(&self) -> Ref<SettingsManager> {
+        self.settings_mgr.borrow()
+    }
+
+    pub fn settings_mgr_mut(&self) -> RefMut<SettingsManager> {
+        self.settings_mgr.borrow_mut()
+    }
+
     pub fn event_mgr(&self) -> &EventManager {
         &self.event_mgr
     }
 
+    pub fn scheduler(&self) -> &Scheduler {
+        &self.scheduler
+    }
+
+    #[cfg(feature = "scripting")]
+    pub fn script_mgr(&self) -> &script::ScriptManager {
+        &self.script_mgr
+    }
+
     pub fn object_event_mgr(&self) -> &ObjectEventManager {
         &self.object_event_mgr
     }
@@ -237,41 +470,80 @@ pub struct Engine {
 }
 
 impl Engine {
+    /// Creates the engine. It installs a global logger wired with a console transport, unless one
+    /// has already been installed.
     pub async fn new(config: EngineConfig) -> Result<Self, EngineInitError> {
+        if logging::global().is_none() {
+            let mut logger = Logger::new();
+            logger.wire(Arc::new(ConsoleTransport::new()));
+            // Another thread may have installed one in the meantime; it is kept then.
+            logging::set_global(logger).ok();
+        }
+
+        let settings_mgr = match &config.settings_app_name {
+            Some(app_name) => SettingsManager::for_app(app_name),
+            None => SettingsManager::in_memory(),
+        };
+        let saved_window = settings_mgr.window().filter(|window| {
+            config.restore_window_state && window.width != 0 && window.height != 0
+        });
+
         let event_loop = EventLoop::new();
-        let window = WindowBuilder::new()
+        let mut window_builder = WindowBuilder::new()
             .with_visible(false)
             .with_title(config.title)
-            .with_resizable(config.resizable)
-            .with_inner_size(LogicalSize::new(config.width, config.height))
-            .build(&event_loop)
-            .unwrap();
-        let gfx_ctx = GfxContext::new(&window).await?;
-        let ctx = ContextHandle::new(Context::new(window, gfx_ctx, config.width, config.height));
+            .with_resizable(config.resizable);
+
+        window_builder = match saved_window {
+            Some(saved_window) => {
+                let monitors = event_loop
+                    .available_monitors()
+                    .map(|monitor| (monitor.position(), monitor.size()));
+
+                if saved_window.is_on_monitors(monitors) {
+                    window_builder = window_builder.with_position(saved_window.position());
+                }
+
+                window_builder
+                    .with_inner_size(saved_window.size())
+                    .with_maximized(saved_window.is_maximized)
+                    .with_fullscreen(
+                        saved_window
+                            .is_fullscreen
+                            .then_some(Fullscreen::Borderless(None)),
+                    )
+            }
+            None => window_builder.with_inner_size(LogicalSize::new(config.width, config.height)),
+        };
+
+        let window = window_builder.build(&event_loop).unwrap();
+        #[cfg(target_arch = "wasm32")]
+        attach_canvas(&window);
+        let scale_factor = window.scale_factor();
+        let physical_size = match saved_window {
+            Some(saved_window) => saved_window.size(),
+            None => LogicalSize::new(config.width, config.height).to_physical(scale_factor),
+        };
+        let logical_size = physical_size.to_logical::<u32>(scale_factor);
+        let gfx_ctx = GfxContext::new(&window, &config.gfx).await?;
+        let ctx = ContextHandle::new(Context::new(
+            window,
+            gfx_ctx,
+            logical_size.width,
+            logical_size.height,
+            config.glyph_atlas,
+            config.rng_seed,
+            settings_mgr,
+        ));
 
         unsafe {
             CONTEXT.write(ctx.clone());
         }
 
-        {
-            let mut world = ctx.world_mut();
-            world.register::<Object>();
-            world.register::<Transform>();
-
-            world.register::<Camera>();
-            world.register::<MeshRenderer>();
-            world.register::<UIElementRenderer>();
-            world.register::<UITextRenderer>();
-
-            world.register::<UISize>();
-            world.register::<UIScaler>();
-            world.register::<UIElement>();
-        }
+        // Components are registered through `#[auto_register]`, including the built-in ones.
+        component_registry::register_components(&mut ctx.world_mut());
 
         {
-            let scale_factor = ctx.window.scale_factor();
-            let physical_size =
-                LogicalSize::new(config.width, config.height).to_physical(scale_factor);
             let mut screen_mgr = ctx.screen_mgr_mut();
             screen_mgr.update_scale_factor(scale_factor, physical_size);
             ctx.gfx_ctx().resize(physical_size);
@@ -284,17 +556,53 @@ impl Engine {
         self.ctx.clone()
     }
 
+    /// Opens another window with its own surface, e.g. for tools. Cameras render into it with
+    /// [`CameraTarget::Window`](gfx::CameraTarget::Window). It can only be created before [`run`](Self::run),
+    /// and is removed once the user closes it; see [`SecondaryWindowClosed`](event_types::SecondaryWindowClosed).
+    /// Input is still read from the main window only.
+    pub fn create_secondary_window(
+        &self,
+        title: &str,
+        width: u32,
+        height: u32,
+    ) -> Result<WindowId, SecondaryWindowCreationError> {
+        let window = WindowBuilder::new()
+            .with_title(title)
+            .with_inner_size(LogicalSize::new(width, height))
+            .build(&self.event_loop)?;
+        let depth_stencil_mode = self.ctx.render_mgr().depth_stencil_mode();
+        let window = SecondaryWindow::new(self.ctx.gfx_ctx().clone(), window, depth_stencil_mode)?;
+        Ok(self.ctx.secondary_window_mgr_mut().insert(window))
+    }
+
+    /// Runs the engine loop. On the web, the browser drives the loop instead, so this returns right away while
+    /// the loop keeps running.
+    /// If a device error is captured while rendering a frame, it is logged and
+    /// [`GfxErrorCaptured`](event_types::GfxErrorCaptured) is dispatched. If the device is lost instead,
+    /// [`DeviceLost`](event_types::DeviceLost) is dispatched and the loop exits.
     pub fn run(
         self,
         loop_mode: EngineLoopMode,
         target_fps: EngineTargetFps,
     ) -> Result<(), EngineExecError> {
+        let mut update_tweens = UpdateTweens::new(self.ctx.clone());
+        let mut update_sprite_animations = UpdateSpriteAnimations::new(self.ctx.clone());
         let mut make_ui_scaler_dirty = MakeUIScalerDirty::new(self.ctx.clone());
         let mut update_ui_scaler = UpdateUIScaler::new(self.ctx.clone());
+        let mut update_ui_progress_bar = UpdateUIProgressBar::new(self.ctx.clone());
+        let mut update_ui_localized_text = UpdateUILocalizedText::new(self.ctx.clone());
         let mut update_ui_element = UpdateUIElement::new(self.ctx.clone());
+        let mut update_camera_controllers = UpdateCameraControllers::new(self.ctx.clone());
+        let mut update_particle_emitters = UpdateParticleEmitters::new(self.ctx.clone());
         let mut update_ui_raycast_grid = UpdateUIRaycastGrid::new(self.ctx.clone());
+        #[cfg(feature = "text-mesh")]
+        let mut update_text_meshes =
+            ecs_system::update_text_meshes::UpdateTextMeshes::new(self.ctx.clone());
+        let mut update_renderer_bvh = UpdateRendererBvh::new(self.ctx.clone());
         let mut update_camera_transform_buffer_system =
             UpdateCameraTransformBufferSystem::new(self.ctx.clone());
+        #[cfg(feature = "scripting")]
+        let mut update_scripts = ecs_system::update_scripts::UpdateScripts::new(self.ctx.clone());
         let mut render_system = RenderSystem::new(
             &self.ctx.gfx_ctx.device,
             self.ctx.render_mgr_mut().bind_group_layout_cache(),
@@ -304,6 +612,7 @@ impl Engine {
 
         let window_id = self.ctx.window.id();
         let mut window_occluded = false;
+        let mut is_device_lost = false;
         let mut target_frame_interval = TargetFrameInterval::new(
             match target_fps {
                 EngineTargetFps::VSync => None,
@@ -312,24 +621,44 @@ impl Engine {
             },
             self.ctx.window(),
         );
+        self.ctx
+            .screen_mgr_mut()
+            .update_refresh_rate(target_frame_interval.refresh_rate());
         let mut last_frame_time = Instant::now();
-
-        self.event_loop.run(move |event, _, control_flow| {
-            *control_flow = match loop_mode {
-                EngineLoopMode::Wait => ControlFlow::Wait,
-                EngineLoopMode::Poll => ControlFlow::Poll,
+        let mut adaptive_loop = match loop_mode {
+            EngineLoopMode::Adaptive { idle_frames } => Some(AdaptiveLoop::new(idle_frames)),
+            EngineLoopMode::Poll | EngineLoopMode::Wait => None,
+        };
+
+        let event_handler = move |event: Event<()>,
+                                  _: &EventLoopWindowTarget<()>,
+                                  control_flow: &mut ControlFlow| {
+            let is_polling = match &adaptive_loop {
+                Some(adaptive_loop) => adaptive_loop.is_polling(),
+                None => loop_mode == EngineLoopMode::Poll,
+            };
+            *control_flow = if is_polling {
+                ControlFlow::Poll
+            } else {
+                ControlFlow::Wait
             };
 
             match event {
                 Event::MainEventsCleared => {
-                    if loop_mode == EngineLoopMode::Wait {
+                    if !is_polling {
                         return;
                     }
 
                     let now = Instant::now();
 
-                    if now - last_frame_time < target_frame_interval.interval() {
-                        return;
+                    match target_frame_interval.pace(last_frame_time, now) {
+                        FramePacing::Ready => {}
+                        FramePacing::WaitUntil(until) => {
+                            // Sleeps instead of spinning; input events still wake the loop up.
+                            *control_flow = ControlFlow::WaitUntil(until);
+                            return;
+                        }
+                        FramePacing::Spin => return,
                     }
 
                     last_frame_time = now;
@@ -339,44 +668,85 @@ impl Engine {
                         time_mgr.update();
                     }
 
+                    dispatch_pause_events(&self.ctx);
+                    dispatch_locale_changed(&self.ctx);
+                    dispatch_hierarchy_changed(&self.ctx);
+                    self.ctx.settings_mgr_mut().update();
+
                     {
                         let mut input_mgr = self.ctx.input_mgr_mut();
                         input_mgr.poll();
                     }
+                    self.ctx.capture_frame();
+                    gfx::expire_mesh_cpu_data();
+
+                    let time = self.ctx.frame().time;
+                    self.ctx
+                        .scheduler()
+                        .tick(time.delta_time, time.unscaled_delta_time);
 
                     self.ctx.event_mgr().dispatch(&event_types::Update);
 
+                    #[cfg(feature = "scripting")]
+                    update_scripts.run_now(&self.ctx.world());
+
+                    update_tweens.run_now(&self.ctx.world());
+                    update_tweens.run_completion_callbacks();
+                    update_sprite_animations.run_now(&self.ctx.world());
+
                     make_ui_scaler_dirty.run_now(&self.ctx.world());
                     update_ui_scaler.run_now(&self.ctx.world());
+                    update_ui_progress_bar.run_now(&self.ctx.world());
+                    update_ui_localized_text.run_now(&self.ctx.world());
                     update_ui_element.run_now(&self.ctx.world());
                     update_ui_raycast_grid.run_now(&self.ctx.world());
 
                     self.ctx.ui_event_mgr_mut().handle_mouse_move();
+                    update_camera_controllers.run_now(&self.ctx.world());
 
-                    {
-                        let world = self.ctx.world();
-                        let mut object_mgr = self.ctx.object_mgr_mut();
-                        let object_hierarchy = object_mgr.object_hierarchy_mut();
-
-                        object_hierarchy.copy_dirty_to_current_frame();
+                    transform::update_object_matrices(
+                        &self.ctx.world(),
+                        self.ctx.object_mgr_mut().object_hierarchy_mut(),
+                        self.ctx.time_mgr().fixed_alpha(),
+                    );
+                    update_particle_emitters.run_now(&self.ctx.world());
 
-                        let transforms = world.read_component::<Transform>();
-                        object_hierarchy.update_object_matrices(|entity| transforms.get(entity));
-                    }
+                    #[cfg(feature = "text-mesh")]
+                    update_text_meshes.run_now(&self.ctx.world());
+                    update_renderer_bvh.run_now(&self.ctx.world());
 
                     self.ctx.event_mgr().dispatch(&event_types::LateUpdate);
 
-                    if window_occluded {
+                    if let Some(adaptive_loop) = &mut adaptive_loop {
+                        adaptive_loop.end_frame(self.ctx.tween_mgr().has_running());
+                    }
+
+                    if window_occluded || is_device_lost {
                         return;
                     }
 
-                    update_camera_transform_buffer_system.run_now(&self.ctx.world());
-                    render_system.run_now(&self.ctx.world());
+                    let result = self.ctx.gfx_ctx().capture_errors(|| {
+                        update_camera_transform_buffer_system.run_now(&self.ctx.world());
+                        render_system.run_now(&self.ctx.world());
+                    });
+
+                    match result {
+                        Ok(()) => {}
+                        Err(GfxError::DeviceLost) => {
+                            is_device_lost = true;
+                            handle_device_lost(&self.ctx, control_flow);
+                        }
+                        Err(err) => handle_gfx_error(&self.ctx, err),
+                    }
 
                     return;
                 }
                 Event::RedrawRequested(id) if id == window_id => {
-                    if loop_mode == EngineLoopMode::Poll {
+                    if let Some(adaptive_loop) = &mut adaptive_loop {
+                        adaptive_loop.record_activity();
+                    }
+
+                    if is_polling {
                         return;
                     }
 
@@ -385,35 +755,76 @@ impl Engine {
                         time_mgr.update();
                     }
 
+                    dispatch_pause_events(&self.ctx);
+                    dispatch_locale_changed(&self.ctx);
+                    dispatch_hierarchy_changed(&self.ctx);
+                    self.ctx.settings_mgr_mut().update();
+
                     {
                         let mut input_mgr = self.ctx.input_mgr_mut();
                         input_mgr.poll();
                     }
+                    self.ctx.capture_frame();
+                    gfx::expire_mesh_cpu_data();
+
+                    let time = self.ctx.frame().time;
+                    self.ctx
+                        .scheduler()
+                        .tick(time.delta_time, time.unscaled_delta_time);
 
                     self.ctx.event_mgr().dispatch(&event_types::Update);
 
+                    #[cfg(feature = "scripting")]
+                    update_scripts.run_now(&self.ctx.world());
+
+                    update_tweens.run_now(&self.ctx.world());
+                    update_tweens.run_completion_callbacks();
+                    update_sprite_animations.run_now(&self.ctx.world());
+
                     make_ui_scaler_dirty.run_now(&self.ctx.world());
                     update_ui_scaler.run_now(&self.ctx.world());
+                    update_ui_progress_bar.run_now(&self.ctx.world());
+                    update_ui_localized_text.run_now(&self.ctx.world());
                     update_ui_element.run_now(&self.ctx.world());
                     update_ui_raycast_grid.run_now(&self.ctx.world());
 
                     self.ctx.ui_event_mgr_mut().handle_mouse_move();
+                    update_camera_controllers.run_now(&self.ctx.world());
 
-                    {
-                        let world = self.ctx.world();
-                        let mut object_mgr = self.ctx.object_mgr_mut();
-                        let object_hierarchy = object_mgr.object_hierarchy_mut();
+                    transform::update_object_matrices(
+                        &self.ctx.world(),
+                        self.ctx.object_mgr_mut().object_hierarchy_mut(),
+                        self.ctx.time_mgr().fixed_alpha(),
+                    );
+                    update_particle_emitters.run_now(&self.ctx.world());
+
+                    #[cfg(feature = "text-mesh")]
+                    update_text_meshes.run_now(&self.ctx.world());
+                    update_renderer_bvh.run_now(&self.ctx.world());
 
-                        object_hierarchy.copy_dirty_to_current_frame();
+                    self.ctx.event_mgr().dispatch(&event_types::LateUpdate);
 
-                        let transforms = world.read_component::<Transform>();
-                        object_hierarchy.update_object_matrices(|entity| transforms.get(entity));
+                    if let Some(adaptive_loop) = &mut adaptive_loop {
+                        adaptive_loop.end_frame(self.ctx.tween_mgr().has_running());
                     }
 
-                    self.ctx.event_mgr().dispatch(&event_types::LateUpdate);
+                    if is_device_lost {
+                        return;
+                    }
 
-                    update_camera_transform_buffer_system.run_now(&self.ctx.world());
-                    render_system.run_now(&self.ctx.world());
+                    let result = self.ctx.gfx_ctx().capture_errors(|| {
+                        update_camera_transform_buffer_system.run_now(&self.ctx.world());
+                        render_system.run_now(&self.ctx.world());
+                    });
+
+                    match result {
+                        Ok(()) => {}
+                        Err(GfxError::DeviceLost) => {
+                            is_device_lost = true;
+                            handle_device_lost(&self.ctx, control_flow);
+                        }
+                        Err(err) => handle_gfx_error(&self.ctx, err),
+                    }
 
                     return;
                 }
@@ -433,6 +844,7 @@ impl Engine {
                         .input_mgr_mut()
                         .keyboard_mut()
                         .handle_window_event(input);
+                    record_activity(&self.ctx, &mut adaptive_loop);
 
                     return;
                 }
@@ -460,13 +872,14 @@ impl Engine {
                         .input_mgr_mut()
                         .mouse_mut()
                         .handle_window_event(&event);
+                    record_activity(&self.ctx, &mut adaptive_loop);
 
                     if let WindowEvent::CursorMoved { position, .. } = &event {
-                        let position =
-                            position.to_logical::<f32>(self.ctx.screen_mgr().scale_factor());
-                        self.ctx
-                            .ui_event_mgr_mut()
-                            .update_mouse_position(Vec2::new(position.x, position.y));
+                        let position = self
+                            .ctx
+                            .screen_mgr()
+                            .physical_to_logical(Vec2::new(position.x as f32, position.y as f32));
+                        self.ctx.ui_event_mgr_mut().update_mouse_position(position);
                     }
 
                     return;
@@ -479,6 +892,21 @@ impl Engine {
                         .input_mgr_mut()
                         .mouse_mut()
                         .handle_window_event(&event);
+                    record_activity(&self.ctx, &mut adaptive_loop);
+
+                    if let WindowEvent::MouseInput {
+                        state,
+                        button: MouseButton::Left,
+                        ..
+                    } = &event
+                    {
+                        let mut ui_event_mgr = self.ctx.ui_event_mgr_mut();
+
+                        match state {
+                            ElementState::Pressed => ui_event_mgr.handle_mouse_down(),
+                            ElementState::Released => ui_event_mgr.handle_mouse_up(),
+                        }
+                    }
 
                     return;
                 }
@@ -490,6 +918,7 @@ impl Engine {
                         .input_mgr_mut()
                         .mouse_mut()
                         .handle_window_event(&event);
+                    record_activity(&self.ctx, &mut adaptive_loop);
 
                     return;
                 }
@@ -498,6 +927,8 @@ impl Engine {
                     window_id: id,
                 } if id == window_id => {
                     self.ctx.screen_mgr_mut().update_size(inner_size);
+                    store_window_state(&self.ctx);
+                    record_activity(&self.ctx, &mut adaptive_loop);
 
                     if inner_size.width == 0 || inner_size.height == 0 {
                         window_occluded = true;
@@ -509,6 +940,7 @@ impl Engine {
                     self.ctx.gfx_ctx().device.poll(MaintainBase::Wait);
                     self.ctx.gfx_ctx().resize(inner_size);
                     self.ctx.render_mgr_mut().resize(inner_size);
+                    dispatch_screen_resized(&self.ctx);
 
                     return;
                 }
@@ -521,9 +953,23 @@ impl Engine {
                     window_id: id,
                 } if id == window_id => {
                     target_frame_interval.update_window(&self.ctx.window);
+                    self.ctx
+                        .screen_mgr_mut()
+                        .update_refresh_rate(target_frame_interval.refresh_rate());
                     self.ctx
                         .screen_mgr_mut()
                         .update_scale_factor(scale_factor, *new_inner_size);
+                    // The layout of the UI depends on the logical size, so it is resolved again in the next
+                    // update even if nothing else dirties it. Texts are rasterized again at the new pixel density.
+                    {
+                        let world = self.ctx.world();
+                        mark_ui_scalers_dirty(
+                            &world.read_storage(),
+                            &world.read_storage(),
+                            self.ctx.object_mgr_mut().object_hierarchy_mut(),
+                        );
+                    }
+                    record_activity(&self.ctx, &mut adaptive_loop);
 
                     if new_inner_size.width == 0 || new_inner_size.height == 0 {
                         window_occluded = true;
@@ -534,6 +980,21 @@ impl Engine {
 
                     self.ctx.gfx_ctx().resize(*new_inner_size);
                     self.ctx.render_mgr_mut().resize(*new_inner_size);
+                    dispatch_screen_resized(&self.ctx);
+
+                    return;
+                }
+                Event::WindowEvent {
+                    event: WindowEvent::Moved(_),
+                    window_id: id,
+                } if id == window_id => {
+                    if target_frame_interval.handle_window_moved(&self.ctx.window) {
+                        self.ctx
+                            .screen_mgr_mut()
+                            .update_refresh_rate(target_frame_interval.refresh_rate());
+                    }
+
+                    store_window_state(&self.ctx);
 
                     return;
                 }
@@ -541,21 +1002,224 @@ impl Engine {
                     event: WindowEvent::CloseRequested,
                     window_id: id,
                 } if id == window_id => {
-                    *control_flow = ControlFlow::Exit;
+                    exit(&self.ctx, control_flow);
+
+                    return;
+                }
+                Event::WindowEvent {
+                    event,
+                    window_id: id,
+                } if id != window_id => {
+                    let closed = self
+                        .ctx
+                        .secondary_window_mgr_mut()
+                        .handle_window_event(id, &event);
+
+                    // The window is dropped after the handlers, which can borrow the manager again.
+                    if closed.is_some() {
+                        self.ctx
+                            .event_mgr()
+                            .dispatch(&event_types::SecondaryWindowClosed { window_id: id });
+                    }
+
+                    return;
+                }
+                Event::DeviceEvent {
+                    event: event @ DeviceEvent::MouseMotion { .. },
+                    ..
+                } => {
+                    self.ctx
+                        .input_mgr_mut()
+                        .mouse_mut()
+                        .handle_device_event(&event);
 
                     return;
                 }
                 _ => return,
             }
-        })
+        };
+
+        run_event_loop(self.event_loop, event_handler)
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+fn run_event_loop<F>(event_loop: EventLoop<()>, event_handler: F) -> Result<(), EngineExecError>
+where
+    F: 'static + FnMut(Event<()>, &EventLoopWindowTarget<()>, &mut ControlFlow),
+{
+    event_loop.run(event_handler)
+}
+
+/// Blocking in the browser would freeze the page, so the loop is spawned and the handler is called from the
+/// browser's event loop.
+#[cfg(target_arch = "wasm32")]
+fn run_event_loop<F>(event_loop: EventLoop<()>, event_handler: F) -> Result<(), EngineExecError>
+where
+    F: 'static + FnMut(Event<()>, &EventLoopWindowTarget<()>, &mut ControlFlow),
+{
+    use winit::platform::web::EventLoopExtWebSys;
+
+    event_loop.spawn(event_handler);
+    Ok(())
+}
+
+/// Appends the canvas that winit creates for the window to the page, which is where the engine renders on the web.
+#[cfg(target_arch = "wasm32")]
+fn attach_canvas(window: &Window) {
+    use winit::platform::web::WindowExtWebSys;
+
+    let body = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.body());
+
+    match body {
+        Some(body) => {
+            if let Err(err) = body.append_child(&window.canvas()) {
+                log_warn!("failed to append the canvas to the page: {:?}", err);
+            }
+        }
+        None => log_warn!("no document body to append the canvas to"),
+    }
+}
+
+/// Keeps [`EngineLoopMode::Adaptive`] polling after input, and wakes it up with a redraw if it has been waiting.
+/// Raw mouse motion is not counted, since it is reported while the window is in the background as well.
+fn record_activity(ctx: &Context, adaptive_loop: &mut Option<AdaptiveLoop>) {
+    if let Some(adaptive_loop) = adaptive_loop {
+        if adaptive_loop.record_activity() {
+            ctx.request_redraw();
+        }
+    }
+}
+
+fn dispatch_pause_events(ctx: &Context) {
+    let events = ctx.time_mgr_mut().take_pause_events();
+
+    for is_paused in events {
+        if is_paused {
+            ctx.event_mgr().dispatch(&event_types::Paused);
+        } else {
+            ctx.event_mgr().dispatch(&event_types::Resumed);
+        }
+    }
+}
+
+fn dispatch_locale_changed(ctx: &Context) {
+    let locale = {
+        let mut localization_mgr = ctx.localization_mgr_mut();
+
+        if !localization_mgr.take_locale_changed() {
+            return;
+        }
+
+        localization_mgr.locale().to_owned()
+    };
+
+    ctx.event_mgr()
+        .dispatch(&event_types::LocaleChanged { locale });
+}
+
+fn dispatch_hierarchy_changed(ctx: &Context) {
+    if !ctx.object_mgr_mut().take_hierarchy_changed() {
+        return;
+    }
+
+    ctx.event_mgr().dispatch(&event_types::HierarchyChanged);
+}
+
+/// Stores the window state and the settings, and stops the engine loop.
+fn exit(ctx: &Context, control_flow: &mut ControlFlow) {
+    store_window_state(ctx);
+
+    if let Err(err) = ctx.settings_mgr_mut().save() {
+        log_warn!("failed to save settings: {}", err);
+    }
+
+    *control_flow = ControlFlow::Exit;
+}
+
+/// Lets the handlers release what they hold, then exits cleanly, since the resources of a lost device cannot
+/// be used anymore.
+fn handle_device_lost(ctx: &Context, control_flow: &mut ControlFlow) {
+    log_warn!("the gfx device is lost; exiting");
+    ctx.event_mgr().dispatch(&event_types::DeviceLost);
+    exit(ctx, control_flow);
+}
+
+/// Reports a device error of a frame. The frame may be incomplete, but the device can still be used, so the loop
+/// goes on.
+fn handle_gfx_error(ctx: &Context, err: GfxError) {
+    log_error!("{}", EngineExecError::from(err.clone()));
+    ctx.event_mgr()
+        .dispatch(&event_types::GfxErrorCaptured { error: err });
+}
+
+/// Records the window geometry in the settings. The size and position are kept as they were while the window
+/// is maximized, fullscreen or minimized, so that it is restored to them once it is not.
+fn store_window_state(ctx: &Context) {
+    let window = ctx.window();
+    let is_maximized = window.is_maximized();
+    let is_fullscreen = window.fullscreen().is_some();
+    let size = window.inner_size();
+    let geometry = match window.outer_position() {
+        Ok(position) if !is_maximized && !is_fullscreen && size.width != 0 && size.height != 0 => {
+            Some((position, size))
+        }
+        _ => None,
+    };
+    let mut settings_mgr = ctx.settings_mgr_mut();
+    let window_settings = match (geometry, settings_mgr.window()) {
+        (Some((position, size)), _) => WindowSettings {
+            x: position.x,
+            y: position.y,
+            width: size.width,
+            height: size.height,
+            is_maximized,
+            is_fullscreen,
+        },
+        (None, Some(saved)) => WindowSettings {
+            is_maximized,
+            is_fullscreen,
+            ..saved
+        },
+        (None, None) => return,
+    };
+
+    if let Err(err) = settings_mgr.set_window(window_settings) {
+        log_warn!("failed to store the window state: {}", err);
+    }
+}
+
+fn dispatch_screen_resized(ctx: &Context) {
+    let event = {
+        let screen_mgr = ctx.screen_mgr();
+        event_types::ScreenResized {
+            logical: screen_mgr.logical_size(),
+            physical: screen_mgr.physical_size(),
+            scale_factor: screen_mgr.scale_factor(),
+        }
+    };
+
+    ctx.event_mgr().dispatch(&event);
+}
+
 pub struct EngineConfig {
     pub title: String,
     pub resizable: bool,
     pub width: u32,
     pub height: u32,
+    pub glyph_atlas: GlyphAtlasConfig,
+    /// The seed of the global random number generator and the run seed of the [`RngManager`]. It is seeded from
+    /// the current time if `None`.
+    pub rng_seed: Option<u64>,
+    /// The name of the directory that settings are saved in, under the platform config directory. The settings
+    /// are kept in memory if `None`.
+    pub settings_app_name: Option<String>,
+    /// Whether to restore the window geometry of the last run, instead of `width` and `height`.
+    pub restore_window_state: bool,
+    /// How the adapter is selected and the device is created, e.g. to fall back to a software adapter.
+    pub gfx: GfxContextOptions,
 }
 
 #[derive(Error, Debug)]
@@ -574,12 +1238,19 @@ pub enum EngineInitError {
 pub enum EngineExecError {
     #[error("gfx surface error: {0}")]
     SurfaceError(#[from] wgpu::SurfaceError),
+    #[error("gfx error: {0}")]
+    GfxError(#[from] GfxError),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum EngineLoopMode {
     Poll,
     Wait,
+    /// Polls while there is input, running tweens or requested redraws, and waits for events once the engine has
+    /// been idle for `idle_frames` frames.
+    Adaptive {
+        idle_frames: u32,
+    },
 }
 
 impl Default for EngineLoopMode {
@@ -588,8 +1259,10 @@ impl Default for EngineLoopMode {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
 pub enum EngineTargetFps {
+    #[serde(rename = "vsync")]
     VSync,
     MilliHertz(NonZeroU32),
     Unlimited,
@@ -600,3 +1273,18 @@ impl Default for EngineTargetFps {
         Self::VSync
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_methods_are_exposed_without_winit() {
+        // Creating a window needs a display, so only the signatures are checked.
+        let _: fn(&Context, &str) = Context::set_title;
+        let _: fn(&Context) -> bool = Context::is_fullscreen;
+        let _: fn(&Context, bool) = Context::set_fullscreen;
+        let _: fn(&Context, crate::CursorIcon) = Context::set_cursor_icon;
+        let _: fn(&Context) = Context::request_redraw;
+    }
+}
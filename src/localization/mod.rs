@@ -0,0 +1,293 @@
+use crate::{
+    asset::{GfxBridgeImpl, PipelineGfxBridgeImpl},
+    ContextHandle,
+};
+use asset::{assets::StringTable, AssetKey};
+use asset_loader::{asset_loaders::RuntimeAssetLoader, AssetDatabase, AssetLoadError, AssetLoader};
+
+/// The locale that is used until [`LocalizationManager::set_default_locale`] is called.
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// Translates the keys of localized strings into the texts of the current locale.
+///
+/// Texts are looked up in the string tables of the current locale, then in those of the default locale. Keys
+/// that are in neither are shown as `⟦key⟧`, so that missing texts stand out. When the locale changes or a
+/// table of the current or default locale is added, a [`LocaleChanged`](crate::event::event_types::LocaleChanged)
+/// event is dispatched at the start of the next frame, and [`UILocalizedText`](crate::ui::UILocalizedText)s
+/// are updated.
+///
+/// Adding a table with the key of a previous one replaces it, so that hot reloading a table is a matter of
+/// adding it again, e.g. from [`AssetCache::subscribe`](asset::AssetCache::subscribe).
+pub struct LocalizationManager {
+    locale: String,
+    default_locale: String,
+    /// Later tables take precedence over earlier ones of the same locale.
+    tables: Vec<StringTable>,
+    is_locale_changed: bool,
+}
+
+impl LocalizationManager {
+    pub fn new() -> Self {
+        Self {
+            locale: DEFAULT_LOCALE.to_owned(),
+            default_locale: DEFAULT_LOCALE.to_owned(),
+            tables: Vec::new(),
+            is_locale_changed: false,
+        }
+    }
+
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    pub fn set_locale(&mut self, locale: impl Into<String>) {
+        let locale = locale.into();
+
+        if self.locale != locale {
+            self.locale = locale;
+            self.is_locale_changed = true;
+        }
+    }
+
+    pub fn default_locale(&self) -> &str {
+        &self.default_locale
+    }
+
+    /// Sets the locale whose texts are used when the current locale lacks them.
+    pub fn set_default_locale(&mut self, locale: impl Into<String>) {
+        let locale = locale.into();
+
+        if self.default_locale != locale {
+            self.default_locale = locale;
+            self.is_locale_changed = true;
+        }
+    }
+
+    pub fn tables(&self) -> &[StringTable] {
+        &self.tables
+    }
+
+    /// Adds the table, replacing the one with the same key if any.
+    pub fn add_table(&mut self, table: StringTable) {
+        if self.is_in_use(table.locale()) {
+            self.is_locale_changed = true;
+        }
+
+        match self
+            .tables
+            .iter_mut()
+            .find(|existing| existing.key() == table.key())
+        {
+            Some(existing) => *existing = table,
+            None => self.tables.push(table),
+        }
+    }
+
+    pub fn remove_table(&mut self, key: &AssetKey) -> Option<StringTable> {
+        let index = self.tables.iter().position(|table| table.key() == key)?;
+        let table = self.tables.remove(index);
+
+        if self.is_in_use(table.locale()) {
+            self.is_locale_changed = true;
+        }
+
+        Some(table)
+    }
+
+    /// Returns the text of the key.
+    pub fn tr(&self, key: &str) -> String {
+        self.tr_args(key, &[])
+    }
+
+    /// Returns the text of the key, replacing each `{name}` in it with the value of the argument.
+    /// Plural texts are selected by the `count` argument.
+    pub fn tr_args(&self, key: &str, args: &[(&str, &str)]) -> String {
+        let string = [&self.locale, &self.default_locale]
+            .into_iter()
+            .find_map(|locale| {
+                self.tables
+                    .iter()
+                    .rev()
+                    .filter(|table| table.locale() == locale)
+                    .find_map(|table| table.get(key))
+            });
+        let string = match string {
+            Some(string) => string,
+            None => return format!("⟦{}⟧", key),
+        };
+        let count = args
+            .iter()
+            .find(|(name, _)| *name == "count")
+            .and_then(|(_, value)| value.trim().parse::<f64>().ok());
+
+        format_text(string.select(count), args)
+    }
+
+    pub(crate) fn take_locale_changed(&mut self) -> bool {
+        std::mem::take(&mut self.is_locale_changed)
+    }
+
+    fn is_in_use(&self, locale: &str) -> bool {
+        locale == self.locale || locale == self.default_locale
+    }
+}
+
+impl Default for LocalizationManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Replaces each `{name}` in the text with the value of the argument. Placeholders without an argument
+/// are kept as they are.
+fn format_text(text: &str, args: &[(&str, &str)]) -> String {
+    let mut formatted = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('{') {
+        formatted.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let value = rest.find('}').and_then(|end| {
+            let name = &rest[1..end];
+            let (_, value) = args.iter().find(|(arg, _)| *arg == name)?;
+            Some((end, value))
+        });
+
+        match value {
+            Some((end, value)) => {
+                formatted.push_str(value);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                formatted.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    formatted.push_str(rest);
+    formatted
+}
+
+/// Loads a string table from the given path through the asset pipeline, without registering it to a database.
+pub fn load_string_table(
+    ctx: &ContextHandle,
+    path: impl Into<String>,
+) -> Result<StringTable, AssetLoadError> {
+    let loader = RuntimeAssetLoader::new(
+        GfxBridgeImpl::new(ctx.clone()),
+        PipelineGfxBridgeImpl::new(ctx.clone()),
+    );
+    let asset = loader.load_asset(&AssetKey::Path(path.into()), &AssetDatabase::new(""))?;
+
+    // The type of the asset is deduced from the extension.
+    match asset.as_string_table() {
+        Some(table) => Ok(table.clone()),
+        None => Err(AssetLoadError::LoadError(asset::AssetLoadError::Other(
+            format!("not a string table: {}", asset.ty()),
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use asset::{
+        assets::{LocalizedString, StringTableAsset},
+        Asset, TypedAsset,
+    };
+    use std::{collections::BTreeMap, sync::Arc};
+
+    struct TestTable {
+        key: AssetKey,
+        locale: &'static str,
+        entries: BTreeMap<String, LocalizedString>,
+    }
+
+    impl Asset for TestTable {
+        fn key(&self) -> &AssetKey {
+            &self.key
+        }
+
+        fn as_typed(self: Arc<Self>) -> TypedAsset {
+            TypedAsset::StringTable(self)
+        }
+    }
+
+    impl StringTableAsset for TestTable {
+        fn locale(&self) -> &str {
+            self.locale
+        }
+
+        fn entries(&self) -> &BTreeMap<String, LocalizedString> {
+            &self.entries
+        }
+    }
+
+    fn table(path: &str, locale: &'static str, entries: &[(&str, LocalizedString)]) -> StringTable {
+        Arc::new(TestTable {
+            key: AssetKey::Path(path.to_owned()),
+            locale,
+            entries: BTreeMap::from_iter(
+                entries
+                    .iter()
+                    .map(|(key, string)| (key.to_string(), string.clone())),
+            ),
+        })
+    }
+
+    #[test]
+    fn tr_falls_back_to_the_default_locale() {
+        let text = |text: &str| LocalizedString::Text(text.to_owned());
+        let mut localization = LocalizationManager::new();
+        localization.add_table(table(
+            "en.lang",
+            "en",
+            &[
+                ("title", text("Adventure")),
+                ("greeting", text("Hello, {name}! {unknown}")),
+                (
+                    "apples",
+                    LocalizedString::Plural {
+                        one: Some("{count} apple".to_owned()),
+                        other: "{count} apples".to_owned(),
+                    },
+                ),
+            ],
+        ));
+        localization.add_table(table("ko.lang", "ko", &[("title", text("모험"))]));
+        assert!(localization.take_locale_changed());
+
+        assert_eq!(localization.tr("title"), "Adventure");
+        assert_eq!(
+            localization.tr_args("greeting", &[("name", "Mina")]),
+            "Hello, Mina! {unknown}"
+        );
+        assert_eq!(localization.tr_args("apples", &[("count", "1")]), "1 apple");
+        assert_eq!(
+            localization.tr_args("apples", &[("count", "3")]),
+            "3 apples"
+        );
+        assert_eq!(localization.tr("apples"), "{count} apples");
+        assert_eq!(localization.tr("missing"), "⟦missing⟧");
+
+        localization.set_locale("ko");
+        assert!(localization.take_locale_changed());
+        assert_eq!(localization.tr("title"), "모험");
+        assert_eq!(
+            localization.tr_args("greeting", &[("name", "Mina")]),
+            "Hello, Mina! {unknown}"
+        );
+
+        // Reloading a table replaces it.
+        localization.add_table(table("ko.lang", "ko", &[("title", text("대모험"))]));
+        assert!(localization.take_locale_changed());
+        assert_eq!(localization.tables().len(), 2);
+        assert_eq!(localization.tr("title"), "대모험");
+
+        // Tables of other locales do not affect the texts.
+        localization.add_table(table("ja.lang", "ja", &[("title", text("冒険"))]));
+        assert!(!localization.take_locale_changed());
+    }
+}
@@ -0,0 +1,162 @@
+use super::{Mat4, Vec3, Vec4};
+use std::fmt::Display;
+
+/// Axis-aligned bounding box.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AABB {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl AABB {
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min, max }
+    }
+
+    /// Returns the smallest AABB that contains all the given points, or `None` if there is no point.
+    pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+
+        Some(points.fold(Self::new(first, first), |aabb, point| Self {
+            min: Vec3::min(aabb.min, point),
+            max: Vec3::max(aabb.max, point),
+        }))
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn size(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    pub fn extents(&self) -> Vec3 {
+        self.size() * 0.5
+    }
+
+    pub fn corners(&self) -> [Vec3; 8] {
+        [
+            Vec3::new(self.min.x, self.min.y, self.min.z),
+            Vec3::new(self.max.x, self.min.y, self.min.z),
+            Vec3::new(self.min.x, self.max.y, self.min.z),
+            Vec3::new(self.max.x, self.max.y, self.min.z),
+            Vec3::new(self.min.x, self.min.y, self.max.z),
+            Vec3::new(self.max.x, self.min.y, self.max.z),
+            Vec3::new(self.min.x, self.max.y, self.max.z),
+            Vec3::new(self.max.x, self.max.y, self.max.z),
+        ]
+    }
+
+    pub fn contains(&self, point: Vec3) -> bool {
+        self.min.x <= point.x
+            && point.x <= self.max.x
+            && self.min.y <= point.y
+            && point.y <= self.max.y
+            && self.min.z <= point.z
+            && point.z <= self.max.z
+    }
+
+    /// Returns `true` if the other AABB lies entirely inside this AABB.
+    pub fn contains_aabb(&self, other: &Self) -> bool {
+        self.min.x <= other.min.x
+            && other.max.x <= self.max.x
+            && self.min.y <= other.min.y
+            && other.max.y <= self.max.y
+            && self.min.z <= other.min.z
+            && other.max.z <= self.max.z
+    }
+
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.min.x <= other.max.x
+            && other.min.x <= self.max.x
+            && self.min.y <= other.max.y
+            && other.min.y <= self.max.y
+            && self.min.z <= other.max.z
+            && other.min.z <= self.max.z
+    }
+
+    pub fn union(lhs: &Self, rhs: &Self) -> Self {
+        Self {
+            min: Vec3::min(lhs.min, rhs.min),
+            max: Vec3::max(lhs.max, rhs.max),
+        }
+    }
+
+    /// Returns the AABB grown by the given margin on every side.
+    pub fn expanded(&self, margin: f32) -> Self {
+        let margin = Vec3::new(margin, margin, margin);
+        Self {
+            min: self.min - margin,
+            max: self.max + margin,
+        }
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        let size = self.size();
+        2.0 * (size.x * size.y + size.y * size.z + size.z * size.x)
+    }
+
+    /// Returns the AABB that contains this AABB after being transformed by the given matrix.
+    /// All 8 corners are transformed, so the result stays conservative under rotation.
+    pub fn transformed(&self, matrix: &Mat4) -> Self {
+        Self::from_points(
+            self.corners()
+                .into_iter()
+                .map(|corner| Vec3::from(Vec4::from_vec3(corner, 1.0) * matrix)),
+        )
+        .unwrap()
+    }
+}
+
+impl Display for AABB {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "AABB(min={}, max={})", self.min, self.max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Quat;
+
+    fn equals_vec3(a: Vec3, b: Vec3) -> bool {
+        (a - b).len() <= 1e-5
+    }
+
+    #[test]
+    fn transformed_rotated_unit_cube() {
+        let aabb = AABB::new(Vec3::new(-0.5, -0.5, -0.5), Vec3::new(0.5, 0.5, 0.5));
+        let matrix = Mat4::rotation(Quat::from_axis_angle(
+            Vec3::new(0.0, 1.0, 0.0),
+            45f32.to_radians(),
+        ));
+        let transformed = aabb.transformed(&matrix);
+        let half_diagonal = 0.5 * 2f32.sqrt();
+
+        // The diagonal of the face lies on the X and Z axes after the rotation.
+        assert!(equals_vec3(
+            transformed.min,
+            Vec3::new(-half_diagonal, -0.5, -half_diagonal)
+        ));
+        assert!(equals_vec3(
+            transformed.max,
+            Vec3::new(half_diagonal, 0.5, half_diagonal)
+        ));
+    }
+
+    #[test]
+    fn transformed_translated_and_scaled() {
+        let aabb = AABB::new(Vec3::new(-0.5, -0.5, -0.5), Vec3::new(0.5, 0.5, 0.5));
+        let matrix = Mat4::srt(
+            Vec3::new(1.0, 2.0, 3.0),
+            Quat::from_eular(0.0, 0.0, 0.0),
+            Vec3::new(2.0, 4.0, 6.0),
+        );
+        let transformed = aabb.transformed(&matrix);
+
+        assert!(equals_vec3(transformed.min, Vec3::new(0.0, 0.0, 0.0)));
+        assert!(equals_vec3(transformed.max, Vec3::new(2.0, 4.0, 6.0)));
+    }
+}
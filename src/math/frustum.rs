@@ -0,0 +1,110 @@
+use super::{Mat4, Vec3, Vec4, AABB};
+
+/// The six planes of a view volume, extracted from a view-projection matrix.
+/// Each plane is stored as `(normal, distance)` in a [`Vec4`], with the normal facing inside.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    pub planes: [Vec4; 6],
+}
+
+impl Frustum {
+    /// Extracts the planes from the given view-projection matrix, that transforms world space into clip space.
+    pub fn from_view_projection(view_projection: &Mat4) -> Self {
+        let x = view_projection.column(0);
+        let y = view_projection.column(1);
+        let z = view_projection.column(2);
+        let w = view_projection.column(3);
+
+        // The near plane is taken as `-w <= z`, which also covers the `0 <= z` depth range.
+        Self {
+            planes: [w + x, w - x, w + y, w - y, w + z, w - z].map(normalize_plane),
+        }
+    }
+
+    /// Returns `false` if the AABB lies entirely outside of any plane.
+    /// It may return `true` for some AABBs that are near the corners but outside.
+    pub fn intersects_aabb(&self, aabb: &AABB) -> bool {
+        self.planes.iter().all(|plane| {
+            // The corner that lies furthest along the normal.
+            let corner = Vec3::new(
+                if 0.0 <= plane.x {
+                    aabb.max.x
+                } else {
+                    aabb.min.x
+                },
+                if 0.0 <= plane.y {
+                    aabb.max.y
+                } else {
+                    aabb.min.y
+                },
+                if 0.0 <= plane.z {
+                    aabb.max.z
+                } else {
+                    aabb.min.z
+                },
+            );
+            0.0 <= Vec4::dot(*plane, Vec4::from_vec3(corner, 1.0))
+        })
+    }
+
+    /// Returns `true` if the AABB lies entirely inside all planes.
+    pub fn contains_aabb(&self, aabb: &AABB) -> bool {
+        self.planes.iter().all(|plane| {
+            // The corner that lies furthest against the normal.
+            let corner = Vec3::new(
+                if 0.0 <= plane.x {
+                    aabb.min.x
+                } else {
+                    aabb.max.x
+                },
+                if 0.0 <= plane.y {
+                    aabb.min.y
+                } else {
+                    aabb.max.y
+                },
+                if 0.0 <= plane.z {
+                    aabb.min.z
+                } else {
+                    aabb.max.z
+                },
+            );
+            0.0 <= Vec4::dot(*plane, Vec4::from_vec3(corner, 1.0))
+        })
+    }
+}
+
+fn normalize_plane(plane: Vec4) -> Vec4 {
+    let len = Vec3::from(plane).len();
+
+    if len <= f32::EPSILON {
+        plane
+    } else {
+        plane / len
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn culls_aabbs_outside_of_perspective() {
+        let view_projection = Mat4::perspective(90f32.to_radians(), 1.0, 0.1, 100.0);
+        let frustum = Frustum::from_view_projection(&view_projection);
+        let unit = |center: Vec3| {
+            AABB::new(
+                center - Vec3::new(0.5, 0.5, 0.5),
+                center + Vec3::new(0.5, 0.5, 0.5),
+            )
+        };
+
+        assert!(frustum.intersects_aabb(&unit(Vec3::new(0.0, 0.0, -10.0))));
+        assert!(frustum.intersects_aabb(&unit(Vec3::new(9.0, 0.0, -10.0))));
+        assert!(!frustum.intersects_aabb(&unit(Vec3::new(12.0, 0.0, -10.0))));
+        assert!(!frustum.intersects_aabb(&unit(Vec3::new(0.0, 0.0, 10.0))));
+        assert!(!frustum.intersects_aabb(&unit(Vec3::new(0.0, 0.0, -200.0))));
+
+        assert!(frustum.contains_aabb(&unit(Vec3::new(0.0, 0.0, -10.0))));
+        assert!(!frustum.contains_aabb(&unit(Vec3::new(10.0, 0.0, -10.0))));
+    }
+}
@@ -1,11 +1,19 @@
+mod aabb;
+mod frustum;
 mod mat4;
+mod polygon;
 mod quat;
+mod ray;
 mod vec2;
 mod vec3;
 mod vec4;
 
+pub use aabb::*;
+pub use frustum::*;
 pub use mat4::*;
+pub use polygon::*;
 pub use quat::*;
+pub use ray::*;
 pub use vec2::*;
 pub use vec3::*;
 pub use vec4::*;
@@ -0,0 +1,337 @@
+use super::Vec2;
+
+/// Reverses the contours so that outers are counter-clockwise and holes are clockwise, i.e. the interior is on
+/// the left of every edge. Holes are found as in [`triangulate_polygon`].
+pub fn orient_polygon(contours: &mut [Vec<Vec2>]) {
+    let depths = contour_depths(contours);
+
+    for (contour, depth) in contours.iter_mut().zip(depths) {
+        if 3 <= contour.len() && (0.0 < contour_area(contour)) == (depth % 2 == 1) {
+            contour.reverse();
+        }
+    }
+}
+
+/// Triangulates polygons with holes by ear clipping, e.g. the outlines of glyphs.
+///
+/// The contours may be wound either way. A contour inside an odd number of other contours is a hole of the
+/// smallest one around it, and is bridged into it before clipping. Returns the triangles as indices into the
+/// points of all contours in order, counter-clockwise. Contours with less than 3 points are ignored.
+pub fn triangulate_polygon(contours: &[Vec<Vec2>]) -> Vec<[u32; 3]> {
+    let depths = contour_depths(contours);
+    let points = contours.concat();
+    let mut base = 0;
+    let mut outers = Vec::new();
+    let mut holes = Vec::new();
+
+    for (index, contour) in contours.iter().enumerate() {
+        let mut ring = Vec::from_iter(base..base + contour.len() as u32);
+        base += contour.len() as u32;
+
+        if contour.len() < 3 {
+            continue;
+        }
+
+        let is_hole = depths[index] % 2 == 1;
+
+        // Outers are counter-clockwise and holes are clockwise, so that the interior is always on the left.
+        if (0.0 < contour_area(contour)) == is_hole {
+            ring.reverse();
+        }
+
+        if is_hole {
+            holes.push((index, ring));
+        } else {
+            outers.push((index, ring, Vec::new()));
+        }
+    }
+
+    for (index, hole) in holes {
+        let point = contours[index][0];
+        let parent = outers
+            .iter_mut()
+            .filter(|(outer, _, _)| {
+                depths[*outer] + 1 == depths[index] && is_point_in_contour(point, &contours[*outer])
+            })
+            .min_by(|(lhs, _, _), (rhs, _, _)| {
+                contour_area(&contours[*lhs])
+                    .abs()
+                    .total_cmp(&contour_area(&contours[*rhs]).abs())
+            });
+
+        if let Some((_, _, outer_holes)) = parent {
+            outer_holes.push(hole);
+        }
+    }
+
+    let mut triangles = Vec::new();
+
+    for (_, mut ring, mut holes) in outers {
+        // Bridging the rightmost holes first keeps the bridges from crossing each other.
+        holes.sort_by(|lhs, rhs| max_x(rhs, &points).total_cmp(&max_x(lhs, &points)));
+
+        for hole in holes {
+            bridge_hole(&mut ring, &hole, &points);
+        }
+
+        clip_ears(ring, &points, &mut triangles);
+    }
+
+    triangles
+}
+
+fn cross(origin: Vec2, a: Vec2, b: Vec2) -> f32 {
+    (a.x - origin.x) * (b.y - origin.y) - (a.y - origin.y) * (b.x - origin.x)
+}
+
+/// Returns the signed area of the contour, which is positive if it is counter-clockwise.
+fn contour_area(contour: &[Vec2]) -> f32 {
+    let mut area = 0.0;
+
+    for (index, &current) in contour.iter().enumerate() {
+        let next = contour[(index + 1) % contour.len()];
+        area += current.x * next.y - next.x * current.y;
+    }
+
+    area * 0.5
+}
+
+/// Returns the number of contours around each contour.
+fn contour_depths(contours: &[Vec<Vec2>]) -> Vec<usize> {
+    Vec::from_iter(contours.iter().enumerate().map(|(index, contour)| {
+        if contour.len() < 3 {
+            return 0;
+        }
+
+        contours
+            .iter()
+            .enumerate()
+            .filter(|&(other, other_contour)| {
+                other != index
+                    && 3 <= other_contour.len()
+                    && is_point_in_contour(contour[0], other_contour)
+            })
+            .count()
+    }))
+}
+
+fn max_x(ring: &[u32], points: &[Vec2]) -> f32 {
+    ring.iter()
+        .map(|&index| points[index as usize].x)
+        .fold(f32::MIN, f32::max)
+}
+
+fn is_point_in_contour(point: Vec2, contour: &[Vec2]) -> bool {
+    let mut is_inside = false;
+
+    for (index, &a) in contour.iter().enumerate() {
+        let b = contour[(index + 1) % contour.len()];
+
+        if (point.y < a.y) != (point.y < b.y)
+            && point.x < a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x)
+        {
+            is_inside = !is_inside;
+        }
+    }
+
+    is_inside
+}
+
+/// Returns `true` if the point is inside of the triangle or on its edges, whichever way it is wound.
+fn is_point_in_triangle(point: Vec2, a: Vec2, b: Vec2, c: Vec2) -> bool {
+    let ab = cross(a, b, point);
+    let bc = cross(b, c, point);
+    let ca = cross(c, a, point);
+    let has_negative = ab < 0.0 || bc < 0.0 || ca < 0.0;
+    let has_positive = 0.0 < ab || 0.0 < bc || 0.0 < ca;
+    !(has_negative && has_positive)
+}
+
+/// Cuts the outer ring open from a vertex visible from the rightmost vertex of the hole, and walks around the
+/// hole through the cut, making a single ring.
+fn bridge_hole(ring: &mut Vec<u32>, hole: &[u32], points: &[Vec2]) {
+    let (hole_start, hole_point) = hole
+        .iter()
+        .enumerate()
+        .map(|(position, &index)| (position, points[index as usize]))
+        .max_by(|(_, lhs), (_, rhs)| lhs.x.total_cmp(&rhs.x))
+        .unwrap();
+
+    // Casts a ray to +X and finds the closest edge it hits.
+    let mut closest = None;
+
+    for position in 0..ring.len() {
+        let a = points[ring[position] as usize];
+        let b = points[ring[(position + 1) % ring.len()] as usize];
+
+        if a.y == b.y || (hole_point.y < a.y) == (hole_point.y < b.y) && hole_point.y != b.y {
+            continue;
+        }
+
+        let x = a.x + (hole_point.y - a.y) / (b.y - a.y) * (b.x - a.x);
+
+        if hole_point.x <= x && closest.is_none_or(|(closest_x, _)| x < closest_x) {
+            closest = Some((x, position));
+        }
+    }
+
+    let (hit_x, edge) = if let Some(closest) = closest {
+        closest
+    } else {
+        return;
+    };
+    let hit = Vec2::new(hit_x, hole_point.y);
+    let edge_end = (edge + 1) % ring.len();
+    let mut bridge = if points[ring[edge_end] as usize].x < points[ring[edge] as usize].x {
+        edge
+    } else {
+        edge_end
+    };
+    let bridge_point = points[ring[bridge] as usize];
+
+    // A reflex vertex inside the triangle of the hit may block the view; the one closest to the ray is visible.
+    if bridge_point != hit {
+        let mut best = None;
+
+        for position in 0..ring.len() {
+            let point = points[ring[position] as usize];
+            let prev = points[ring[(position + ring.len() - 1) % ring.len()] as usize];
+            let next = points[ring[(position + 1) % ring.len()] as usize];
+
+            if position == bridge
+                || 0.0 < cross(prev, point, next)
+                || !is_point_in_triangle(point, hole_point, hit, bridge_point)
+            {
+                continue;
+            }
+
+            let offset = point - hole_point;
+            let key = (
+                offset.y.abs() / offset.x.max(f32::EPSILON),
+                offset.len_square(),
+            );
+
+            if best.is_none_or(|(_, best_key)| key < best_key) {
+                best = Some((position, key));
+            }
+        }
+
+        if let Some((position, _)) = best {
+            bridge = position;
+        }
+    }
+
+    let mut bridged = Vec::with_capacity(ring.len() + hole.len() + 2);
+    bridged.extend_from_slice(&ring[..=bridge]);
+    bridged.extend(
+        hole[hole_start..]
+            .iter()
+            .chain(&hole[..=hole_start])
+            .copied(),
+    );
+    bridged.extend_from_slice(&ring[bridge..]);
+    *ring = bridged;
+}
+
+fn clip_ears(mut ring: Vec<u32>, points: &[Vec2], triangles: &mut Vec<[u32; 3]>) {
+    let mut position = 0;
+    let mut misses = 0;
+
+    while 3 < ring.len() {
+        let count = ring.len();
+        position %= count;
+
+        let prev = ring[(position + count - 1) % count];
+        let current = ring[position];
+        let next = ring[(position + 1) % count];
+        let [a, b, c] = [prev, current, next].map(|index| points[index as usize]);
+        let area = cross(a, b, c);
+
+        if area == 0.0 {
+            // Collinear, so the vertex adds nothing.
+            ring.remove(position);
+            misses = 0;
+            continue;
+        }
+
+        // Once no ear is left, e.g. in self-intersecting outlines, clips anyway so that the loop ends.
+        let is_ear = 0.0 < area
+            && ring.iter().all(|&index| {
+                let point = points[index as usize];
+                point == a || point == b || point == c || !is_point_in_triangle(point, a, b, c)
+            });
+
+        if is_ear || count < misses {
+            triangles.push([prev, current, next]);
+            ring.remove(position);
+            misses = 0;
+        } else {
+            position += 1;
+            misses += 1;
+        }
+    }
+
+    if let [a, b, c] = ring[..] {
+        if 0.0 < cross(points[a as usize], points[b as usize], points[c as usize]) {
+            triangles.push([a, b, c]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(min: f32, max: f32) -> Vec<Vec2> {
+        vec![
+            Vec2::new(min, min),
+            Vec2::new(max, min),
+            Vec2::new(max, max),
+            Vec2::new(min, max),
+        ]
+    }
+
+    fn area(contours: &[Vec<Vec2>], triangles: &[[u32; 3]]) -> f32 {
+        let points = contours.concat();
+        triangles
+            .iter()
+            .map(|&[a, b, c]| {
+                let area = cross(points[a as usize], points[b as usize], points[c as usize]) * 0.5;
+                assert!(0.0 < area);
+                area
+            })
+            .sum()
+    }
+
+    #[test]
+    fn triangulates_concave_polygons() {
+        // An L shape, wound clockwise.
+        let contours = vec![vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(0.0, 2.0),
+            Vec2::new(1.0, 2.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(2.0, 1.0),
+            Vec2::new(2.0, 0.0),
+        ]];
+        let triangles = triangulate_polygon(&contours);
+        assert_eq!(triangles.len(), 4);
+        assert!((area(&contours, &triangles) - 3.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn bridges_holes() {
+        let mut oriented = vec![square(0.0, 3.0), square(1.0, 2.0)];
+        orient_polygon(&mut oriented);
+        assert!(0.0 < contour_area(&oriented[0]));
+        assert!(contour_area(&oriented[1]) < 0.0);
+
+        // Two squares with a hole each, the second wound the other way around.
+        let mut outer = square(4.0, 8.0);
+        outer.reverse();
+        let contours = vec![square(0.0, 3.0), square(1.0, 2.0), outer, square(5.0, 7.0)];
+        let triangles = triangulate_polygon(&contours);
+        assert_eq!(triangles.len(), 16);
+        assert!((area(&contours, &triangles) - (9.0 - 1.0 + 16.0 - 4.0)).abs() < 1e-4);
+    }
+}
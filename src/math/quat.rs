@@ -97,6 +97,58 @@ impl Quat {
         quat.normalized()
     }
 
+    /// Returns the rotation that turns `Vec3::FORWARD` towards the direction, keeping `Vec3::UP` as close to
+    /// the given up vector as possible. The direction must not be parallel to the up vector.
+    pub fn look_rotation(direction: Vec3, up: Vec3) -> Self {
+        Self::from_mat4(&Mat4::look_at(Vec3::ZERO, direction, up))
+    }
+
+    pub fn dot(lhs: Self, rhs: Self) -> f32 {
+        lhs.x * rhs.x + lhs.y * rhs.y + lhs.z * rhs.z + lhs.w * rhs.w
+    }
+
+    /// Returns the angle of the rotation from one quaternion to the other in radians, in range `[0, π]`.
+    /// Both quaternions are expected to be normalized.
+    pub fn angle_between(lhs: Self, rhs: Self) -> f32 {
+        // `q` and `-q` represent the same rotation, so the sign of the dot product is ignored.
+        let dot = Self::dot(lhs, rhs).abs().min(1.0);
+        2.0 * dot.acos()
+    }
+
+    /// Interpolates spherically between the rotations along the shortest path. `t` is clamped to `[0, 1]`.
+    pub fn slerp(from: Self, to: Self, t: f32) -> Self {
+        Self::slerp_unclamped(from, to, t.clamp(0.0, 1.0))
+    }
+
+    /// Same as [`Quat::slerp`], but `t` may go out of `[0, 1]` to overshoot. Both quaternions are expected
+    /// to be normalized.
+    pub fn slerp_unclamped(from: Self, to: Self, t: f32) -> Self {
+        let mut dot = Self::dot(from, to);
+        let to = if dot < 0.0 {
+            dot = -dot;
+            -to
+        } else {
+            to
+        };
+
+        // Nearly identical rotations are interpolated linearly, since the sine below goes to zero.
+        let (from_weight, to_weight) = if 0.9995 < dot {
+            (1.0 - t, t)
+        } else {
+            let angle = dot.acos();
+            let sin = angle.sin();
+            (((1.0 - t) * angle).sin() / sin, (t * angle).sin() / sin)
+        };
+
+        Self {
+            x: from.x * from_weight + to.x * to_weight,
+            y: from.y * from_weight + to.y * to_weight,
+            z: from.z * from_weight + to.z * to_weight,
+            w: from.w * from_weight + to.w * to_weight,
+        }
+        .normalized()
+    }
+
     pub fn normalize(&mut self) -> &mut Self {
         let len = self.x * self.x + self.y * self.y + self.z * self.z + self.w * self.w;
         if len != 1.0 && len != 0.0 {
@@ -274,3 +326,62 @@ impl Display for Quat {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn angle_between_rotations() {
+        use std::f32::consts::{FRAC_PI_2, FRAC_PI_3};
+
+        let from = Quat::from_axis_angle(Vec3::UP, FRAC_PI_3);
+        let to = Quat::from_axis_angle(Vec3::UP, FRAC_PI_3 + FRAC_PI_2);
+
+        assert!((Quat::angle_between(from, to) - FRAC_PI_2).abs() < 1e-5);
+        // The negated quaternion represents the same rotation.
+        let negated = Quat {
+            x: -from.x,
+            y: -from.y,
+            z: -from.z,
+            w: -from.w,
+        };
+        assert!(Quat::angle_between(from, negated) < 1e-3);
+        assert_eq!(Quat::angle_between(Quat::IDENTITY, Quat::IDENTITY), 0.0);
+    }
+
+    #[test]
+    fn slerp_takes_shortest_path() {
+        use std::f32::consts::FRAC_PI_2;
+
+        let from = Quat::from_axis_angle(Vec3::UP, 0.0);
+        let to = Quat::from_axis_angle(Vec3::UP, FRAC_PI_2);
+        let half = Quat::slerp(from, to, 0.5);
+
+        assert!((Quat::angle_between(from, half) - FRAC_PI_2 * 0.5).abs() < 1e-4);
+        assert!((Quat::angle_between(half, to) - FRAC_PI_2 * 0.5).abs() < 1e-4);
+        // The negated target is the same rotation, so the path must not go the long way around.
+        let half = Quat::slerp(from, -to, 0.5);
+        assert!((Quat::angle_between(from, half) - FRAC_PI_2 * 0.5).abs() < 1e-4);
+        assert!(Quat::angle_between(Quat::slerp(from, to, 1.0), to) < 1e-3);
+    }
+
+    #[test]
+    fn look_rotation_faces_direction() {
+        for direction in [
+            Vec3::FORWARD,
+            Vec3::BACKWARD,
+            Vec3::RIGHT,
+            Vec3::new(1.0, 2.0, -3.0).normalized(),
+        ] {
+            let rotation = Quat::look_rotation(direction, Vec3::UP);
+            let forward = rotation * Vec3::FORWARD;
+            let up = rotation * Vec3::UP;
+
+            assert!(Vec3::distance(forward, direction) < 1e-4);
+            // The rotated up stays in the plane of the direction and the world up, without rolling.
+            assert!(Vec3::dot(Vec3::cross(direction, Vec3::UP), up).abs() < 1e-4);
+            assert!(up.y > 0.0);
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use super::{Vec3, AABB};
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray {
+    pub origin: Vec3,
+    pub direction: Vec3,
+}
+
+impl Ray {
+    /// Creates a new ray. The direction will be normalized.
+    pub fn new(origin: Vec3, direction: Vec3) -> Self {
+        Self {
+            origin,
+            direction: direction.normalized(),
+        }
+    }
+
+    /// Returns the point at the given distance along the ray.
+    pub fn at(&self, distance: f32) -> Vec3 {
+        self.origin + self.direction * distance
+    }
+
+    /// Returns the distance to the nearest intersection with the given AABB, if any.
+    /// Returns zero if the origin is inside the AABB.
+    pub fn intersect_aabb(&self, aabb: &AABB) -> Option<f32> {
+        let inv_direction = Vec3::recip(self.direction);
+        let t0 = (aabb.min - self.origin) * inv_direction;
+        let t1 = (aabb.max - self.origin) * inv_direction;
+        let t_min = Vec3::min(t0, t1);
+        let t_max = Vec3::max(t0, t1);
+
+        let enter = t_min.x.max(t_min.y).max(t_min.z);
+        let exit = t_max.x.min(t_max.y).min(t_max.z);
+
+        if exit < 0.0 || exit < enter {
+            return None;
+        }
+
+        Some(enter.max(0.0))
+    }
+
+    /// Returns the distance to the intersection with the given triangle, if any.
+    /// Both faces of the triangle are considered. The direction does not need to be normalized, e.g. for a ray
+    /// transformed into the local space of a mesh; the distance is then in units of the direction.
+    pub fn intersect_triangle(&self, a: Vec3, b: Vec3, c: Vec3) -> Option<f32> {
+        // Möller–Trumbore intersection algorithm.
+        let edge_ab = b - a;
+        let edge_ac = c - a;
+        let p = Vec3::cross(self.direction, edge_ac);
+        let det = Vec3::dot(edge_ab, p);
+
+        // The determinant scales with the lengths of the direction and the edges, so the threshold does too;
+        // otherwise hits on small or far scaled triangles would be rejected.
+        if det.abs() <= f32::EPSILON * self.direction.len() * edge_ab.len() * edge_ac.len() {
+            return None;
+        }
+
+        let inv_det = det.recip();
+        let s = self.origin - a;
+        let u = Vec3::dot(s, p) * inv_det;
+
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let q = Vec3::cross(s, edge_ab);
+        let v = Vec3::dot(self.direction, q) * inv_det;
+
+        if v < 0.0 || 1.0 < u + v {
+            return None;
+        }
+
+        let distance = Vec3::dot(edge_ac, q) * inv_det;
+
+        if distance < f32::EPSILON {
+            return None;
+        }
+
+        Some(distance)
+    }
+}
+
+impl Display for Ray {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Ray(origin={}, direction={})",
+            self.origin, self.direction
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn equals_float(a: f32, b: f32) -> bool {
+        (a - b).abs() <= 1e-5
+    }
+
+    fn unit_cube() -> AABB {
+        AABB::new(Vec3::new(-0.5, -0.5, -0.5), Vec3::new(0.5, 0.5, 0.5))
+    }
+
+    #[test]
+    fn ray_aabb_hit() {
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::FORWARD);
+        let distance = ray.intersect_aabb(&unit_cube()).unwrap();
+
+        assert!(equals_float(distance, 4.5));
+    }
+
+    #[test]
+    fn ray_aabb_miss() {
+        let ray = Ray::new(Vec3::new(2.0, 0.0, 5.0), Vec3::FORWARD);
+        assert!(ray.intersect_aabb(&unit_cube()).is_none());
+
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::BACKWARD);
+        assert!(ray.intersect_aabb(&unit_cube()).is_none());
+    }
+
+    #[test]
+    fn ray_aabb_diagonal() {
+        let ray = Ray::new(Vec3::new(-2.0, -2.0, -2.0), Vec3::ONE);
+        let distance = ray.intersect_aabb(&unit_cube()).unwrap();
+
+        assert!(equals_float(distance, 1.5 * 3f32.sqrt()));
+    }
+
+    #[test]
+    fn ray_aabb_inside() {
+        let ray = Ray::new(Vec3::ZERO, Vec3::UP);
+        let distance = ray.intersect_aabb(&unit_cube()).unwrap();
+
+        assert!(equals_float(distance, 0.0));
+    }
+
+    #[test]
+    fn ray_aabb_parallel_to_face() {
+        let ray = Ray::new(Vec3::new(0.25, 5.0, 0.0), Vec3::DOWN);
+        assert!(equals_float(ray.intersect_aabb(&unit_cube()).unwrap(), 4.5));
+
+        let ray = Ray::new(Vec3::new(0.75, 5.0, 0.0), Vec3::DOWN);
+        assert!(ray.intersect_aabb(&unit_cube()).is_none());
+    }
+
+    #[test]
+    fn ray_triangle_hit() {
+        let a = Vec3::new(-1.0, -1.0, 0.0);
+        let b = Vec3::new(1.0, -1.0, 0.0);
+        let c = Vec3::new(0.0, 1.0, 0.0);
+
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 3.0), Vec3::FORWARD);
+        assert!(equals_float(ray.intersect_triangle(a, b, c).unwrap(), 3.0));
+
+        // The back face must be hit as well.
+        let ray = Ray::new(Vec3::new(0.0, 0.0, -3.0), Vec3::BACKWARD);
+        assert!(equals_float(ray.intersect_triangle(a, b, c).unwrap(), 3.0));
+    }
+
+    #[test]
+    fn ray_triangle_miss() {
+        let a = Vec3::new(-1.0, -1.0, 0.0);
+        let b = Vec3::new(1.0, -1.0, 0.0);
+        let c = Vec3::new(0.0, 1.0, 0.0);
+
+        // Outside of the triangle.
+        let ray = Ray::new(Vec3::new(0.9, 0.9, 3.0), Vec3::FORWARD);
+        assert!(ray.intersect_triangle(a, b, c).is_none());
+
+        // Behind the origin.
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 3.0), Vec3::BACKWARD);
+        assert!(ray.intersect_triangle(a, b, c).is_none());
+
+        // Parallel to the triangle.
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 1.0), Vec3::RIGHT);
+        assert!(ray.intersect_triangle(a, b, c).is_none());
+
+        // Degenerate.
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 3.0), Vec3::FORWARD);
+        assert!(ray.intersect_triangle(a, b, a).is_none());
+    }
+
+    #[test]
+    fn ray_triangle_hit_at_non_unit_scales() {
+        let (a, b, c) = (
+            Vec3::new(-1.0, -1.0, 0.0),
+            Vec3::new(1.0, -1.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+        );
+
+        // A tiny triangle, e.g. of a mesh scaled down.
+        let scale = 1e-4;
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 3.0 * scale), Vec3::FORWARD);
+        let distance = ray
+            .intersect_triangle(a * scale, b * scale, c * scale)
+            .unwrap();
+        assert!(equals_float(distance / scale, 3.0));
+
+        // A short direction, e.g. of a ray transformed into the local space of a mesh scaled up.
+        let scale = 1e-8;
+        let ray = Ray {
+            origin: Vec3::new(0.0, 0.0, 3.0),
+            direction: Vec3::FORWARD * scale,
+        };
+        let distance = ray.intersect_triangle(a, b, c).unwrap();
+        assert!(equals_float(distance * scale, 3.0));
+    }
+}
@@ -38,6 +38,17 @@ impl Vec2 {
         }
     }
 
+    pub fn from_array(array: [f32; 2]) -> Self {
+        Self {
+            x: array[0],
+            y: array[1],
+        }
+    }
+
+    pub fn to_array(self) -> [f32; 2] {
+        [self.x, self.y]
+    }
+
     pub fn len(self) -> f32 {
         self.len_square().sqrt()
     }
@@ -66,8 +77,13 @@ impl Vec2 {
         lhs.x * rhs.x + lhs.y * rhs.y
     }
 
+    /// Projects the vector onto the given axis, which does not need to be normalized.
     pub fn project(lhs: Self, normal: Self) -> Self {
-        normal * Self::projected_len(lhs, normal)
+        let len_square = normal.len_square();
+        if len_square < f32::EPSILON {
+            return Self::ZERO;
+        }
+        normal * (Self::dot(lhs, normal) / len_square)
     }
 
     pub fn projected_len(lhs: Self, normal: Self) -> f32 {
@@ -248,6 +264,14 @@ impl Vec2 {
         }
     }
 
+    /// Clamps each component into range `[min, max]` of the corresponding component.
+    pub fn clamp(lhs: Self, min: Self, max: Self) -> Self {
+        Self {
+            x: lhs.x.clamp(min.x, max.x),
+            y: lhs.y.clamp(min.y, max.y),
+        }
+    }
+
     pub fn recip(lhs: Self) -> Self {
         Self {
             x: lhs.x.recip(),
@@ -274,6 +298,18 @@ impl From<Vec4> for Vec2 {
     }
 }
 
+impl From<[f32; 2]> for Vec2 {
+    fn from(value: [f32; 2]) -> Self {
+        Self::from_array(value)
+    }
+}
+
+impl From<Vec2> for [f32; 2] {
+    fn from(value: Vec2) -> Self {
+        value.to_array()
+    }
+}
+
 impl Add for Vec2 {
     type Output = Self;
 
@@ -409,3 +445,25 @@ impl Display for Vec2 {
         write!(f, "Vec2(x={}, y={})", self.x, self.y)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn project_onto_axis() {
+        let projected = Vec2::project(Vec2::new(3.0, 4.0), Vec2::new(2.0, 0.0));
+        assert_eq!(projected, Vec2::new(3.0, 0.0));
+        assert_eq!(Vec2::project(Vec2::ONE, Vec2::ZERO), Vec2::ZERO);
+    }
+
+    #[test]
+    fn clamp_componentwise() {
+        let clamped = Vec2::clamp(Vec2::new(-2.0, 0.5), Vec2::ZERO, Vec2::ONE);
+        assert_eq!(clamped, Vec2::new(0.0, 0.5));
+        assert_eq!(
+            Vec2::clamp(Vec2::new(0.5, 3.0), Vec2::ZERO, Vec2::ONE),
+            Vec2::new(0.5, 1.0)
+        );
+    }
+}
@@ -75,6 +75,22 @@ impl Vec3 {
         }
     }
 
+    pub fn from_array(array: [f32; 3]) -> Self {
+        Self {
+            x: array[0],
+            y: array[1],
+            z: array[2],
+        }
+    }
+
+    pub fn to_array(self) -> [f32; 3] {
+        [self.x, self.y, self.z]
+    }
+
+    pub fn xy(self) -> Vec2 {
+        Vec2::new(self.x, self.y)
+    }
+
     pub fn len(self) -> f32 {
         self.len_square().sqrt()
     }
@@ -111,8 +127,13 @@ impl Vec3 {
         }
     }
 
+    /// Projects the vector onto the given axis, which does not need to be normalized.
     pub fn project(lhs: Self, normal: Self) -> Self {
-        normal * Self::projected_len(lhs, normal)
+        let len_square = normal.len_square();
+        if len_square < f32::EPSILON {
+            return Self::ZERO;
+        }
+        normal * (Self::dot(lhs, normal) / len_square)
     }
 
     pub fn projected_len(lhs: Self, normal: Self) -> f32 {
@@ -120,7 +141,18 @@ impl Vec3 {
     }
 
     pub fn angle(from: Self, to: Self) -> f32 {
-        (Self::dot(from, to) / (from.len() * to.len())).acos()
+        Self::angle_between(from, to)
+    }
+
+    /// Returns the unsigned angle between two vectors in radians, in range `[0, π]`.
+    /// Returns zero if any of the vectors has zero length.
+    pub fn angle_between(lhs: Self, rhs: Self) -> f32 {
+        let len = (lhs.len_square() * rhs.len_square()).sqrt();
+        if len < f32::EPSILON {
+            return 0.0;
+        }
+        // Rounding errors can push the cosine slightly out of range, which makes `acos` return NaN.
+        (Self::dot(lhs, rhs) / len).clamp(-1.0, 1.0).acos()
     }
 
     pub fn angle_signed(from: Self, to: Self, normal: Self) -> f32 {
@@ -302,6 +334,15 @@ impl Vec3 {
         }
     }
 
+    /// Clamps each component into range `[min, max]` of the corresponding component.
+    pub fn clamp(lhs: Self, min: Self, max: Self) -> Self {
+        Self {
+            x: lhs.x.clamp(min.x, max.x),
+            y: lhs.y.clamp(min.y, max.y),
+            z: lhs.z.clamp(min.z, max.z),
+        }
+    }
+
     pub fn recip(lhs: Self) -> Self {
         Self {
             x: lhs.x.recip(),
@@ -323,6 +364,18 @@ impl From<Vec4> for Vec3 {
     }
 }
 
+impl From<[f32; 3]> for Vec3 {
+    fn from(value: [f32; 3]) -> Self {
+        Self::from_array(value)
+    }
+}
+
+impl From<Vec3> for [f32; 3] {
+    fn from(value: Vec3) -> Self {
+        value.to_array()
+    }
+}
+
 impl Add for Vec3 {
     type Output = Self;
 
@@ -472,3 +525,79 @@ impl Display for Vec3 {
         write!(f, "Vec3(x={}, y={}, z={})", self.x, self.y, self.z)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reflect_off_plane() {
+        let reflected = Vec3::reflect(Vec3::new(1.0, -1.0, 0.0), Vec3::UP);
+        assert_eq!(reflected, Vec3::new(1.0, 1.0, 0.0));
+
+        // The normal does not need to be normalized.
+        let reflected = Vec3::reflect(Vec3::new(1.0, -1.0, 0.0), Vec3::new(0.0, 2.0, 0.0));
+        assert_eq!(reflected, Vec3::new(1.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn project_onto_axis() {
+        let projected = Vec3::project(Vec3::new(3.0, 4.0, 5.0), Vec3::new(2.0, 0.0, 0.0));
+        assert_eq!(projected, Vec3::new(3.0, 0.0, 0.0));
+        assert_eq!(Vec3::project(Vec3::ONE, Vec3::ZERO), Vec3::ZERO);
+    }
+
+    #[test]
+    fn lerp_endpoints_and_midpoint() {
+        let from = Vec3::new(-2.0, 0.0, 4.0);
+        let to = Vec3::new(2.0, 4.0, 8.0);
+
+        assert_eq!(Vec3::lerp(from, to, 0.0), from);
+        assert_eq!(Vec3::lerp(from, to, 1.0), to);
+        assert_eq!(Vec3::lerp(from, to, 0.5), Vec3::new(0.0, 2.0, 6.0));
+        assert_eq!(Vec3::lerp(from, to, 2.0), to);
+    }
+
+    #[test]
+    fn clamp_componentwise() {
+        let clamped = Vec3::clamp(Vec3::new(-2.0, 0.5, 3.0), Vec3::ZERO, Vec3::ONE);
+        assert_eq!(clamped, Vec3::new(0.0, 0.5, 1.0));
+        assert_eq!(Vec3::distance(Vec3::ZERO, Vec3::new(0.0, 3.0, 4.0)), 5.0);
+    }
+
+    #[test]
+    fn angle_between_vectors() {
+        use std::f32::consts::{FRAC_PI_2, PI};
+
+        assert_eq!(Vec3::angle_between(Vec3::RIGHT, Vec3::UP), FRAC_PI_2);
+        assert_eq!(
+            Vec3::angle_between(Vec3::new(1.0, 2.0, 3.0), Vec3::new(2.0, 4.0, 6.0)),
+            0.0
+        );
+        assert_eq!(
+            Vec3::angle_between(Vec3::new(1.0, 2.0, 3.0), Vec3::new(-2.0, -4.0, -6.0)),
+            PI
+        );
+        assert_eq!(Vec3::angle_between(Vec3::ZERO, Vec3::UP), 0.0);
+    }
+
+    #[test]
+    fn swizzle_and_array_round_trip() {
+        assert_eq!(
+            Vec4::new(1.0, 2.0, 3.0, 4.0).xyz(),
+            Vec3::new(1.0, 2.0, 3.0)
+        );
+        assert_eq!(Vec4::new(1.0, 2.0, 3.0, 4.0).xy(), Vec2::new(1.0, 2.0));
+        assert_eq!(Vec3::new(1.0, 2.0, 3.0).xy(), Vec2::new(1.0, 2.0));
+
+        assert_eq!(Vec2::from([1.0, 2.0]).to_array(), [1.0, 2.0]);
+        assert_eq!(
+            <[f32; 3]>::from(Vec3::from_array([1.0, 2.0, 3.0])),
+            [1.0, 2.0, 3.0]
+        );
+        assert_eq!(
+            Vec4::from([1.0, 2.0, 3.0, 4.0]),
+            Vec4::new(1.0, 2.0, 3.0, 4.0)
+        );
+    }
+}
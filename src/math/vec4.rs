@@ -86,6 +86,27 @@ impl Vec4 {
         }
     }
 
+    pub fn from_array(array: [f32; 4]) -> Self {
+        Self {
+            x: array[0],
+            y: array[1],
+            z: array[2],
+            w: array[3],
+        }
+    }
+
+    pub fn to_array(self) -> [f32; 4] {
+        [self.x, self.y, self.z, self.w]
+    }
+
+    pub fn xy(self) -> Vec2 {
+        Vec2::new(self.x, self.y)
+    }
+
+    pub fn xyz(self) -> Vec3 {
+        Vec3::new(self.x, self.y, self.z)
+    }
+
     pub fn len(self) -> f32 {
         self.len_square().sqrt()
     }
@@ -114,8 +135,13 @@ impl Vec4 {
         lhs.x * rhs.x + lhs.y * rhs.y + lhs.z * rhs.z + lhs.w * rhs.w
     }
 
+    /// Projects the vector onto the given axis, which does not need to be normalized.
     pub fn project(lhs: Self, normal: Self) -> Self {
-        normal * Self::projected_len(lhs, normal)
+        let len_square = normal.len_square();
+        if len_square < f32::EPSILON {
+            return Self::ZERO;
+        }
+        normal * (Self::dot(lhs, normal) / len_square)
     }
 
     pub fn projected_len(lhs: Self, normal: Self) -> f32 {
@@ -282,6 +308,16 @@ impl Vec4 {
         }
     }
 
+    /// Clamps each component into range `[min, max]` of the corresponding component.
+    pub fn clamp(lhs: Self, min: Self, max: Self) -> Self {
+        Self {
+            x: lhs.x.clamp(min.x, max.x),
+            y: lhs.y.clamp(min.y, max.y),
+            z: lhs.z.clamp(min.z, max.z),
+            w: lhs.w.clamp(min.w, max.w),
+        }
+    }
+
     pub fn recip(lhs: Self) -> Self {
         Self {
             x: lhs.x.recip(),
@@ -298,6 +334,18 @@ impl Default for Vec4 {
     }
 }
 
+impl From<[f32; 4]> for Vec4 {
+    fn from(value: [f32; 4]) -> Self {
+        Self::from_array(value)
+    }
+}
+
+impl From<Vec4> for [f32; 4] {
+    fn from(value: Vec4) -> Self {
+        value.to_array()
+    }
+}
+
 impl Add for Vec4 {
     type Output = Self;
 
@@ -465,3 +513,21 @@ impl Display for Vec4 {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn project_onto_axis() {
+        let projected = Vec4::project(Vec4::new(3.0, 4.0, 5.0, 6.0), Vec4::new(0.0, 0.0, 0.0, 2.0));
+        assert_eq!(projected, Vec4::new(0.0, 0.0, 0.0, 6.0));
+        assert_eq!(Vec4::project(Vec4::ONE, Vec4::ZERO), Vec4::ZERO);
+    }
+
+    #[test]
+    fn clamp_componentwise() {
+        let clamped = Vec4::clamp(Vec4::new(-2.0, 0.5, 3.0, 1.0), Vec4::ZERO, Vec4::ONE);
+        assert_eq!(clamped, Vec4::new(0.0, 0.5, 1.0, 1.0));
+    }
+}
@@ -12,12 +12,21 @@ pub type ComponentTypeId = NonZeroU32;
 pub type Storage<T> = SlotMap<RefCell<T>>;
 
 trait ComponentSubStorage: Downcast {
+    fn get_component_dyn_mut(&self, id: usize) -> Option<RefMut<dyn Component>>;
     fn remove_component_untyped(&mut self, id: usize);
 }
 
 impl_downcast!(ComponentSubStorage);
 
 impl<T: Component> ComponentSubStorage for SlotMap<RefCell<T>> {
+    fn get_component_dyn_mut(&self, id: usize) -> Option<RefMut<dyn Component>> {
+        self.get(id).map(|cell| {
+            RefMut::map(cell.borrow_mut(), |component| {
+                component as &mut dyn Component
+            })
+        })
+    }
+
     fn remove_component_untyped(&mut self, id: usize) {
         self.deallocate(id);
     }
@@ -71,6 +80,13 @@ impl ComponentStorage {
             .map(|cell| cell.borrow_mut())
     }
 
+    /// Gets the component without knowing its type, e.g. to invoke its lifecycle hooks.
+    pub fn get_component_dyn_mut(&self, id: ComponentId) -> Option<RefMut<dyn Component>> {
+        self.storages
+            .get(&id.type_id)?
+            .get_component_dyn_mut(id.component_id as usize)
+    }
+
     pub fn add_component<T: Component>(&mut self, component: T) -> ComponentId {
         let type_id = match self.type_type_id_map.entry(TypeId::of::<T>()) {
             Entry::Occupied(entry) => *entry.get(),
@@ -112,6 +128,16 @@ impl ComponentStorage {
             .deallocate(id.component_id as usize);
     }
 
+    /// Removes the component and returns it, e.g. to move it to another object. Returns `None` if there is no
+    /// such component of the type.
+    pub fn take_component<T: Component>(&mut self, id: ComponentId) -> Option<T> {
+        self.storages
+            .get_mut(&id.type_id)?
+            .downcast_mut::<Storage<T>>()?
+            .take(id.component_id as usize)
+            .map(RefCell::into_inner)
+    }
+
     pub fn remove_component_untyped(&mut self, id: ComponentId) {
         let storage = if let Some(stoage) = self.storages.get_mut(&id.type_id) {
             stoage
@@ -246,4 +272,34 @@ mod tests {
         assert!(storage.get_component::<TestComponentA>(quux).is_none());
         assert!(storage.get_component::<TestComponentB>(quux).is_none());
     }
+
+    #[test]
+    fn take_component_returns_the_removed_value() {
+        let mut storage = ComponentStorage::new();
+
+        let foo = storage.add_component(TestComponentA { value: "foo" });
+        let bar = storage.add_component(TestComponentA { value: "bar" });
+
+        assert!(storage.take_component::<TestComponentB>(foo).is_none());
+        assert_eq!(
+            storage
+                .take_component::<TestComponentA>(foo)
+                .map(|component| component.value),
+            Some("foo")
+        );
+        assert!(storage.get_component::<TestComponentA>(foo).is_none());
+        assert!(storage.take_component::<TestComponentA>(foo).is_none());
+        assert_eq!(
+            storage.get_component::<TestComponentA>(bar).unwrap().value,
+            "bar"
+        );
+
+        // The type keeps its id, so the freed slot is reused by the same type.
+        let baz = storage.add_component(TestComponentA { value: "baz" });
+        assert_eq!(baz.type_id(), foo.type_id());
+        assert_eq!(
+            storage.get_component::<TestComponentA>(baz).unwrap().value,
+            "baz"
+        );
+    }
 }
@@ -1,4 +1,5 @@
-use specs::{prelude::*, Component};
+use codegen::Component;
+use specs::prelude::*;
 
 mod component_storage;
 mod handle;
@@ -9,7 +10,9 @@ mod object_id;
 mod object_id_allocator;
 mod object_manager;
 mod object_name_registry;
+mod object_pool;
 mod object_storage;
+mod object_visibility;
 
 pub use component_storage::*;
 pub use handle::*;
@@ -20,10 +23,13 @@ pub use object_id::*;
 pub use object_id_allocator::*;
 pub use object_manager::*;
 pub use object_name_registry::*;
+pub use object_pool::*;
 pub use object_storage::*;
+pub use object_visibility::*;
 
 #[derive(Debug, Clone, Copy, Component)]
 #[storage(VecStorage)]
+#[auto_register]
 pub struct Object {
     entity: Entity,
     object_id: ObjectId,
@@ -118,11 +124,13 @@ pub mod new {
                 .and_then(|id| storage.get_component_mut(*id))
         }
 
+        /// Adds the component and invokes [`Component::init`] on it.
         pub fn add_component(
             &mut self,
             storage: &mut ComponentStorage,
-            component: impl Component,
+            mut component: impl Component,
         ) -> ComponentId {
+            component.init();
             let id = storage.add_component(component);
             self.component_ids.push(id);
             id
@@ -132,13 +140,15 @@ pub mod new {
             &mut self,
             storage: &mut ComponentStorage,
             index: usize,
-            component: impl Component,
+            mut component: impl Component,
         ) -> ComponentId {
+            component.init();
             let id = storage.add_component(component);
             self.component_ids.insert(index, id);
             id
         }
 
+        /// Invokes [`Component::fin`] on the component and removes it. The component is dropped after `fin`.
         pub fn remove_component<T: Component>(
             &mut self,
             storage: &mut ComponentStorage,
@@ -168,10 +178,41 @@ pub mod new {
                 return;
             };
 
+            if let Some(mut component) = storage.get_component_mut::<T>(id) {
+                component.fin();
+            }
+
             storage.remove_component::<T>(id);
             self.component_ids.swap_remove(index);
         }
 
+        /// Removes the component without knowing its type, like [`Self::remove_component`].
+        pub fn remove_component_untyped(
+            &mut self,
+            storage: &mut ComponentStorage,
+            id: ComponentId,
+        ) {
+            let index = if let Some(index) = self
+                .component_ids
+                .iter()
+                .position(|component| *component == id)
+            {
+                index
+            } else {
+                return;
+            };
+
+            fin_and_remove_untyped(storage, id);
+            self.component_ids.swap_remove(index);
+        }
+
+        /// Removes every component of the object, invoking [`Component::fin`] on each.
+        pub fn remove_all_components(&mut self, storage: &mut ComponentStorage) {
+            for id in self.component_ids.drain(..) {
+                fin_and_remove_untyped(storage, id);
+            }
+        }
+
         pub fn remove_components_of_type<T: Component>(&mut self, storage: &mut ComponentStorage) {
             let type_id = if let Some(type_id) = storage.get_type_id::<T>() {
                 type_id
@@ -193,12 +234,24 @@ pub mod new {
                 .collect::<Vec<_>>();
 
             for (index, id) in ids.into_iter().rev() {
+                if let Some(mut component) = storage.get_component_mut::<T>(id) {
+                    component.fin();
+                }
+
                 storage.remove_component::<T>(id);
                 self.component_ids.swap_remove(index);
             }
         }
     }
 
+    fn fin_and_remove_untyped(storage: &mut ComponentStorage, id: ComponentId) {
+        if let Some(mut component) = storage.get_component_dyn_mut(id) {
+            component.fin();
+        }
+
+        storage.remove_component_untyped(id);
+    }
+
     pub trait Component: 'static {
         fn new(id: ObjectId) -> Self
         where
@@ -220,6 +273,7 @@ pub mod new {
 
         use super::{Component, Object};
         use crate::object::{component_storage, ObjectId, ObjectIdAllocator};
+        use std::{cell::RefCell, rc::Rc};
 
         struct TestComponent {
             value: i32,
@@ -244,6 +298,66 @@ pub mod new {
             }
         }
 
+        struct LifecycleComponent {
+            events: Rc<RefCell<Vec<&'static str>>>,
+        }
+
+        impl Component for LifecycleComponent {
+            fn new(_id: ObjectId) -> Self
+            where
+                Self: Sized,
+            {
+                Self {
+                    events: Default::default(),
+                }
+            }
+
+            fn init(&mut self) {
+                self.events.borrow_mut().push("init");
+            }
+
+            fn fin(&mut self) {
+                self.events.borrow_mut().push("fin");
+            }
+        }
+
+        impl Drop for LifecycleComponent {
+            fn drop(&mut self) {
+                self.events.borrow_mut().push("drop");
+            }
+        }
+
+        #[test]
+        fn init_and_fin_are_invoked_on_add_and_remove() {
+            let mut component_storage = ComponentStorage::new();
+            let mut object = Object::new();
+            let events = Rc::new(RefCell::new(Vec::new()));
+            let component = || LifecycleComponent {
+                events: events.clone(),
+            };
+
+            let id = object.add_component(&mut component_storage, component());
+            assert_eq!(*events.borrow(), ["init"]);
+
+            object.remove_component::<LifecycleComponent>(&mut component_storage, id);
+            assert_eq!(*events.borrow(), ["init", "fin", "drop"]);
+            assert!(object.component_ids().is_empty());
+
+            events.borrow_mut().clear();
+            let id = object.add_component_at(&mut component_storage, 0, component());
+            object.remove_component_untyped(&mut component_storage, id);
+            assert_eq!(*events.borrow(), ["init", "fin", "drop"]);
+
+            events.borrow_mut().clear();
+            object.add_component(&mut component_storage, component());
+            object.add_component(&mut component_storage, component());
+            object.remove_all_components(&mut component_storage);
+            assert_eq!(
+                *events.borrow(),
+                ["init", "init", "fin", "drop", "fin", "drop"]
+            );
+        }
+
         // #[test]
         // fn test() {
         //     let mut component_storage = ComponentStorage::new();
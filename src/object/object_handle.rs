@@ -1,6 +1,6 @@
-use super::{ObjectComponent, ObjectId};
+use super::{ObjectComponent, ObjectId, ObjectVisibility};
 use crate::ContextHandle;
-use specs::Entity;
+use specs::{Entity, WorldExt};
 use std::hash::{Hash, Hasher};
 
 #[derive(Clone)]
@@ -101,6 +101,38 @@ impl ObjectHandle {
             .set_active(self.object_id, active);
     }
 
+    pub fn is_visible(&self) -> bool {
+        self.ctx
+            .object_mgr()
+            .object_hierarchy()
+            .is_visible(self.object_id)
+    }
+
+    /// Shows or hides the object and its children from every camera.
+    pub fn set_visible(&self, visible: bool) {
+        self.ctx
+            .object_mgr_mut()
+            .object_hierarchy_mut()
+            .set_visible(self.object_id, visible);
+        self.with_visibility(|visibility| visibility.set_hidden(!visible));
+    }
+
+    /// Shows or hides the object and its children from the given camera object.
+    pub fn set_visible_for_camera(&self, camera: &Self, visible: bool) {
+        self.with_visibility(|visibility| {
+            visibility.set_hidden_for_camera(camera.entity, !visible)
+        });
+    }
+
+    fn with_visibility(&self, f: impl FnOnce(&mut ObjectVisibility)) {
+        let world = self.ctx.world();
+        let mut visibilities = world.write_component::<ObjectVisibility>();
+
+        if let Ok(entry) = visibilities.entry(self.entity) {
+            f(entry.or_insert_with(ObjectVisibility::new));
+        }
+    }
+
     pub fn set_name(&self, name: impl Into<Option<String>>) {
         self.ctx
             .object_mgr_mut()
@@ -115,6 +147,18 @@ impl ObjectHandle {
             .set_parent(self.object_id, parent.into().map(|parent| parent.object_id));
     }
 
+    /// Creates a copy of the object and all of its children. See [`ObjectManager::instantiate`].
+    ///
+    /// [`ObjectManager::instantiate`]: super::ObjectManager::instantiate
+    pub fn instantiate(&self) -> Self {
+        let object_id = self
+            .ctx
+            .object_mgr_mut()
+            .instantiate(&mut self.ctx.world_mut(), self.object_id);
+        let entity = self.ctx.object_mgr().object_hierarchy().entity(object_id);
+        Self::new(self.ctx.clone(), entity, object_id)
+    }
+
     pub fn remove(&self) {
         self.ctx.object_mgr_mut().remove_object(self);
     }
@@ -1,5 +1,5 @@
 use super::ObjectId;
-use crate::{math::Mat4, transform::Transform};
+use crate::math::Mat4;
 use bitvec::prelude::*;
 use specs::prelude::*;
 use std::{cmp::Ordering, ops::Range};
@@ -94,6 +94,20 @@ impl<'a> Iterator for ObjectSiblingIter<'a> {
     }
 }
 
+/// A change of the active state of an object, either by its own flag or by the flags of its parents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ObjectActiveTransition {
+    pub object: ObjectId,
+    pub is_active: bool,
+}
+
+/// A flag that is inherited from the parents, e.g. an object is active only if all of its parents are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InheritedFlag {
+    Active,
+    Visible,
+}
+
 /// This represents a hierarchy of objects. It is used to store the parent-child relationships and keep track of the object order.
 #[derive(Debug)]
 pub struct ObjectHierarchy {
@@ -104,10 +118,13 @@ pub struct ObjectHierarchy {
     object_current_frame_dirties: BitVec,
     object_actives: BitVec,
     object_active_selfs: BitVec,
+    object_visibles: BitVec,
+    object_visible_selfs: BitVec,
     // unordered
     object_spans: Vec<ObjectSpan>,
     object_parents: Vec<Vec<ObjectId>>,
     object_matrices: Vec<Mat4>,
+    is_structure_changed: bool,
 }
 
 impl ObjectHierarchy {
@@ -147,6 +164,15 @@ impl ObjectHierarchy {
         self.object_active_selfs[self.object_spans[object.get() as usize].index as usize]
     }
 
+    /// Returns `false` if the object or any of its parents is hidden.
+    pub fn is_visible(&self, object: ObjectId) -> bool {
+        self.object_visibles[self.object_spans[object.get() as usize].index as usize]
+    }
+
+    pub fn is_visible_self(&self, object: ObjectId) -> bool {
+        self.object_visible_selfs[self.object_spans[object.get() as usize].index as usize]
+    }
+
     pub fn parent(&self, object: ObjectId) -> Option<ObjectId> {
         self.object_parents[object.get() as usize].first().copied()
     }
@@ -206,43 +232,25 @@ impl ObjectHierarchy {
             .copy_from_bitslice(&self.object_dirties);
     }
 
-    pub fn set_active(&mut self, object: ObjectId, is_active: bool) {
-        self.object_active_selfs
-            .set(object.get() as usize, is_active);
-
-        let is_parent_active = match self.parent(object) {
-            Some(parent) => self.is_active(parent),
-            _ => true,
-        };
-
-        if is_active && is_parent_active {
-            let children = self.children(object);
-            let mut flags: BitVec = BitVec::with_capacity(children.len() + 1);
-
-            flags.push(true);
-
-            let base_index = self.object_spans[object.get() as usize].index;
+    /// Sets the own active flag of the object. Returns the objects whose active state changed, i.e. the object and
+    /// the children that are not deactivated by themselves, in the order of hierarchy.
+    pub fn set_active(&mut self, object: ObjectId, is_active: bool) -> Vec<ObjectActiveTransition> {
+        let index = self.object_spans[object.get() as usize].index as usize;
+        self.object_active_selfs.set(index, is_active);
+        self.propagate_active_flag(object)
+    }
 
-            for &child in children {
-                let is_parent_active = match self.parent(child) {
-                    Some(parent) => {
-                        let parent_index = self.object_spans[parent.get() as usize].index;
-                        let index = parent_index - base_index;
-                        flags[index as usize]
-                    }
-                    None => true,
-                };
-                flags.push(is_parent_active && self.is_active_self(child));
-            }
+    /// Shows or hides the object and its children. Unlike [`Self::set_active`], it only affects rendering.
+    /// Transforms are not marked dirty.
+    pub fn set_visible(&mut self, object: ObjectId, is_visible: bool) {
+        let index = self.object_spans[object.get() as usize].index as usize;
+        self.object_visible_selfs.set(index, is_visible);
+        self.propagate_flag(object, InheritedFlag::Visible);
+    }
 
-            self.object_actives.as_mut_bitslice()
-                [self.object_spans[object.get() as usize].to_range()]
-            .copy_from_bitslice(&flags);
-        } else {
-            self.object_actives.as_mut_bitslice()
-                [self.object_spans[object.get() as usize].to_range()]
-            .fill(false);
-        }
+    /// Returns `true` once after objects have been added, removed or re-parented.
+    pub(crate) fn take_structure_changed(&mut self) -> bool {
+        std::mem::take(&mut self.is_structure_changed)
     }
 
     pub fn reset_dirties(&mut self) {
@@ -275,6 +283,9 @@ impl ObjectHierarchy {
         self.object_current_frame_dirties.push(true);
         self.object_actives.push(true);
         self.object_active_selfs.push(true);
+        self.object_visibles.push(true);
+        self.object_visible_selfs.push(true);
+        self.is_structure_changed = true;
     }
 
     /// Removes the given object and its children. Returns the removed objects in the order of hierarchy.
@@ -282,6 +293,7 @@ impl ObjectHierarchy {
         let object_usize = object.get() as usize;
         let span = self.object_spans[object_usize];
         let to_be_removed = self.object_entities[span.to_range()].to_vec();
+        self.is_structure_changed = true;
 
         // Remove the object and its children from its parents.
         for &parent in &self.object_parents[object_usize] {
@@ -344,12 +356,34 @@ impl ObjectHierarchy {
         self.object_active_selfs
             .truncate(self.object_active_selfs.len() - span_count);
 
+        if span_index + span_count < self.object_visibles.len() {
+            self.object_visibles
+                .copy_within(span_index + span_count.., span_index);
+        }
+
+        self.object_visibles
+            .truncate(self.object_visibles.len() - span_count);
+
+        if span_index + span_count < self.object_visible_selfs.len() {
+            self.object_visible_selfs
+                .copy_within(span_index + span_count.., span_index);
+        }
+
+        self.object_visible_selfs
+            .truncate(self.object_visible_selfs.len() - span_count);
+
         to_be_removed
     }
 
-    /// Sets the parent of the given object and re-order all objects.
-    pub fn set_parent(&mut self, object: ObjectId, parent: Option<ObjectId>) {
+    /// Sets the parent of the given object and re-order all objects. Returns the objects whose active state
+    /// changed by the flags of the new parents, like [`Self::set_active`].
+    pub fn set_parent(
+        &mut self,
+        object: ObjectId,
+        parent: Option<ObjectId>,
+    ) -> Vec<ObjectActiveTransition> {
         self.set_dirty(object);
+        self.is_structure_changed = true;
 
         let object_usize = object.get() as usize;
         let span = self.object_spans[object_usize];
@@ -406,25 +440,19 @@ impl ObjectHierarchy {
         // Set dirties.
         self.set_dirty(object);
 
-        // Update active flags.
-        self.set_active(object, self.is_active_self(object));
+        // Update inherited flags.
+        self.propagate_flag(object, InheritedFlag::Visible);
+        self.propagate_active_flag(object)
     }
 
-    /// Updates the object matrices.
-    pub fn update_object_matrices<'a>(
-        &mut self,
-        transforms: impl Fn(Entity) -> Option<&'a Transform>,
-    ) {
+    /// Updates the matrices of the dirty objects from the local matrices of their entities.
+    pub fn update_object_matrices(&mut self, local_matrices: impl Fn(Entity) -> Option<Mat4>) {
         for (&object, &entity) in self.objects.iter().zip(self.object_entities.iter()) {
             if !self.is_dirty(object) {
                 continue;
             }
 
-            let mut matrix = if let Some(transform) = transforms(entity) {
-                transform.matrix()
-            } else {
-                Mat4::identity()
-            };
+            let mut matrix = local_matrices(entity).unwrap_or_else(Mat4::identity);
 
             if let Some(parent) = self.parent(object) {
                 matrix *= self.matrix(parent);
@@ -436,6 +464,54 @@ impl ObjectHierarchy {
         self.reset_dirties();
     }
 
+    /// Recomputes the given flag of the object and its children from their own flags.
+    /// The flag of an object is set only if its own flag and the flag of its parent are set.
+    /// Returns the objects whose flag changed, with their new flags.
+    fn propagate_flag(&mut self, object: ObjectId, flag: InheritedFlag) -> Vec<(ObjectId, bool)> {
+        let span = self.object_spans[object.get() as usize];
+        let (inherited, selfs) = match flag {
+            InheritedFlag::Active => (&self.object_actives, &self.object_active_selfs),
+            InheritedFlag::Visible => (&self.object_visibles, &self.object_visible_selfs),
+        };
+        let mut flags: BitVec = BitVec::with_capacity(span.count as usize);
+
+        for &child in &self.objects[span.to_range()] {
+            let index = self.object_spans[child.get() as usize].index;
+            let is_parent_set = match self.parent(child) {
+                // Parents of the children are in the span, so their flags are already computed.
+                Some(parent) if child != object => {
+                    flags[(self.object_spans[parent.get() as usize].index - span.index) as usize]
+                }
+                Some(parent) => inherited[self.object_spans[parent.get() as usize].index as usize],
+                None => true,
+            };
+            flags.push(is_parent_set && selfs[index as usize]);
+        }
+
+        let inherited = match flag {
+            InheritedFlag::Active => &mut self.object_actives,
+            InheritedFlag::Visible => &mut self.object_visibles,
+        };
+        let changes = Vec::from_iter(
+            self.objects[span.to_range()]
+                .iter()
+                .zip(inherited[span.to_range()].iter().zip(flags.iter()))
+                .filter(|(_, (prev, next))| **prev != **next)
+                .map(|(&object, (_, next))| (object, *next)),
+        );
+        inherited.as_mut_bitslice()[span.to_range()].copy_from_bitslice(&flags);
+
+        changes
+    }
+
+    fn propagate_active_flag(&mut self, object: ObjectId) -> Vec<ObjectActiveTransition> {
+        Vec::from_iter(
+            self.propagate_flag(object, InheritedFlag::Active)
+                .into_iter()
+                .map(|(object, is_active)| ObjectActiveTransition { object, is_active }),
+        )
+    }
+
     /// Moves the given object and its children to the destination index.
     fn move_objects(&mut self, object: ObjectId, destination_index: usize) {
         let object = object.get() as usize;
@@ -526,6 +602,16 @@ impl ObjectHierarchy {
         self.object_active_selfs.copy_within(src.clone(), dest);
         self.object_active_selfs[temp_dest..temp_dest + temp.len()]
             .copy_from_bitslice(&temp_object_active_selfs);
+
+        let temp_object_visibles = self.object_visibles[temp.clone()].to_bitvec();
+        self.object_visibles.copy_within(src.clone(), dest);
+        self.object_visibles[temp_dest..temp_dest + temp.len()]
+            .copy_from_bitslice(&temp_object_visibles);
+
+        let temp_object_visible_selfs = self.object_visible_selfs[temp.clone()].to_bitvec();
+        self.object_visible_selfs.copy_within(src.clone(), dest);
+        self.object_visible_selfs[temp_dest..temp_dest + temp.len()]
+            .copy_from_bitslice(&temp_object_visible_selfs);
     }
 }
 
@@ -538,10 +624,13 @@ impl Default for ObjectHierarchy {
             object_current_frame_dirties: BitVec::with_capacity(1024),
             object_actives: BitVec::with_capacity(1024),
             object_active_selfs: BitVec::with_capacity(1024),
+            object_visibles: BitVec::with_capacity(1024),
+            object_visible_selfs: BitVec::with_capacity(1024),
 
             object_spans: Vec::with_capacity(1024),
             object_parents: Vec::with_capacity(1024),
             object_matrices: Vec::with_capacity(1024),
+            is_structure_changed: false,
         }
     }
 }
@@ -549,7 +638,7 @@ impl Default for ObjectHierarchy {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::math::Vec3;
+    use crate::{math::Vec3, transform::Transform};
     use std::collections::HashMap;
 
     fn equals_float(a: f32, b: f32) -> bool {
@@ -804,6 +893,46 @@ mod test {
         assert_eq!(hierarchy.is_active(ObjectId::from_u32(3)), true);
     }
 
+    #[test]
+    fn check_hierarchy_object_visible_flag_change_parent() {
+        let mut hierarchy = create_hierarchy(5);
+
+        hierarchy.set_parent(ObjectId::from_u32(3), Some(ObjectId::from_u32(2)));
+        hierarchy.set_parent(ObjectId::from_u32(4), Some(ObjectId::from_u32(3)));
+        hierarchy.reset_dirties();
+
+        // Hiding does not touch transforms.
+        hierarchy.set_visible(ObjectId::from_u32(2), false);
+        assert!(!hierarchy.is_dirty(ObjectId::from_u32(2)));
+        assert!(!hierarchy.is_visible(ObjectId::from_u32(2)));
+        assert!(!hierarchy.is_visible(ObjectId::from_u32(3)));
+        assert!(!hierarchy.is_visible(ObjectId::from_u32(4)));
+        assert!(hierarchy.is_visible_self(ObjectId::from_u32(3)));
+
+        // A hidden subtree stays hidden under a visible parent.
+        hierarchy.set_parent(ObjectId::from_u32(2), Some(ObjectId::from_u32(0)));
+        assert!(hierarchy.is_visible(ObjectId::from_u32(0)));
+        assert!(!hierarchy.is_visible(ObjectId::from_u32(2)));
+        assert!(!hierarchy.is_visible(ObjectId::from_u32(3)));
+        assert!(!hierarchy.is_visible(ObjectId::from_u32(4)));
+
+        // Its children become visible once they are moved out of it.
+        hierarchy.set_parent(ObjectId::from_u32(3), Some(ObjectId::from_u32(1)));
+        assert!(hierarchy.is_visible(ObjectId::from_u32(3)));
+        assert!(hierarchy.is_visible(ObjectId::from_u32(4)));
+
+        // And a visible subtree becomes hidden under a hidden parent.
+        hierarchy.set_parent(ObjectId::from_u32(1), Some(ObjectId::from_u32(2)));
+        assert!(!hierarchy.is_visible(ObjectId::from_u32(1)));
+        assert!(!hierarchy.is_visible(ObjectId::from_u32(3)));
+        assert!(!hierarchy.is_visible(ObjectId::from_u32(4)));
+
+        hierarchy.set_visible(ObjectId::from_u32(2), true);
+        for id in 0..5 {
+            assert!(hierarchy.is_visible(ObjectId::from_u32(id)));
+        }
+    }
+
     #[test]
     fn check_hierarchy_object_matrix_update_uniform_scales() {
         let mut hierarchy = create_hierarchy(4);
@@ -834,7 +963,7 @@ mod test {
             transform
         });
 
-        hierarchy.update_object_matrices(|entity| transforms.get(&entity));
+        hierarchy.update_object_matrices(|entity| transforms.get(&entity).map(Transform::matrix));
 
         assert_eq!(
             equals_mat4(
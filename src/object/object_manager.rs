@@ -1,22 +1,70 @@
 use super::{
     Object, ObjectHandle, ObjectHierarchy, ObjectId, ObjectIdAllocator, ObjectNameRegistry,
+    ObjectVisibility,
 };
-use crate::{transform::Transform, use_context};
-use specs::prelude::*;
+use crate::{
+    camera_controller::{FlyCameraController, OrbitCameraController},
+    gfx::{Camera, Pickable},
+    transform::Transform,
+    ui::{
+        UIAspectRatioFitter, UIContentSizeFitter, UICursor, UIElement, UIGridLayout,
+        UIHorizontalLayout, UILocalizedText, UIProgressBar, UISafeArea, UIScaler, UISize, UISlider,
+        UIVerticalLayout,
+    },
+    use_context,
+};
+use specs::{prelude::*, storage::MaskedStorage};
+use std::collections::HashMap;
+
+type CloneComponentFn = fn(&World, Entity, Entity);
 
 pub struct ObjectManager {
     object_hierarchy: ObjectHierarchy,
     object_name_registry: ObjectNameRegistry,
     object_id_allocator: ObjectIdAllocator,
+    component_cloners: Vec<CloneComponentFn>,
 }
 
 impl ObjectManager {
     pub fn new() -> Self {
-        Self {
+        let mut object_mgr = Self {
             object_hierarchy: ObjectHierarchy::new(),
             object_name_registry: ObjectNameRegistry::new(),
             object_id_allocator: ObjectIdAllocator::new(),
-        }
+            component_cloners: Vec::new(),
+        };
+
+        object_mgr.register_cloneable::<ObjectVisibility>();
+        object_mgr.register_cloneable::<Camera>();
+        object_mgr.register_cloneable::<Pickable>();
+        object_mgr.register_cloneable::<FlyCameraController>();
+        object_mgr.register_cloneable::<OrbitCameraController>();
+        object_mgr.register_cloneable::<UIAspectRatioFitter>();
+        object_mgr.register_cloneable::<UIContentSizeFitter>();
+        object_mgr.register_cloneable::<UICursor>();
+        object_mgr.register_cloneable::<UIElement>();
+        object_mgr.register_cloneable::<UIGridLayout>();
+        object_mgr.register_cloneable::<UIHorizontalLayout>();
+        object_mgr.register_cloneable::<UILocalizedText>();
+        object_mgr.register_cloneable::<UIProgressBar>();
+        object_mgr.register_cloneable::<UISafeArea>();
+        object_mgr.register_cloneable::<UIScaler>();
+        object_mgr.register_cloneable::<UISize>();
+        object_mgr.register_cloneable::<UISlider>();
+        object_mgr.register_cloneable::<UIVerticalLayout>();
+
+        object_mgr
+    }
+
+    /// Registers a component type to be copied by [`Self::instantiate`]. The transform is always copied; the
+    /// cloneable components of the engine are registered already. Renderers are not cloneable, as they own GPU
+    /// resources, so they have to be set up again on the copies. Ids of other objects in registered components
+    /// are copied as they are, so they keep referring to the originals.
+    pub fn register_cloneable<T>(&mut self)
+    where
+        T: Component + Clone,
+    {
+        self.component_cloners.push(clone_component::<T>);
     }
 
     pub fn object_name_registry(&self) -> &ObjectNameRegistry {
@@ -35,6 +83,13 @@ impl ObjectManager {
         &mut self.object_hierarchy
     }
 
+    /// Returns `true` once after objects have been added, removed, re-parented or renamed. See
+    /// [`HierarchyChanged`](crate::event::event_types::HierarchyChanged).
+    pub(crate) fn take_hierarchy_changed(&mut self) -> bool {
+        // Both flags must be taken, so no short-circuit here.
+        self.object_hierarchy.take_structure_changed() | self.object_name_registry.take_changed()
+    }
+
     pub fn object_handle(&self, object_id: ObjectId) -> ObjectHandle {
         ObjectHandle::new(
             use_context().clone(),
@@ -63,6 +118,20 @@ impl ObjectManager {
         name: impl Into<Option<String>>,
         transform: Option<Transform>,
     ) -> (ObjectHandle, EntityBuilder<'w>) {
+        let (object_id, builder) = self.create_object_id_builder(world, name, transform);
+        let object_handle = ObjectHandle::new(use_context().clone(), builder.entity, object_id);
+
+        (object_handle, builder)
+    }
+
+    /// Like [`Self::create_object_builder`], but returns the id of the object instead of its handle, so that it
+    /// works without a context.
+    pub(crate) fn create_object_id_builder<'w>(
+        &mut self,
+        world: &'w mut World,
+        name: impl Into<Option<String>>,
+        transform: Option<Transform>,
+    ) -> (ObjectId, EntityBuilder<'w>) {
         let object_id = self.object_id_allocator.alloc();
         let builder = world.create_entity();
         let entity = builder.entity;
@@ -70,16 +139,71 @@ impl ObjectManager {
         self.object_hierarchy.add(object_id, entity);
         self.object_name_registry.set_name(object_id, name.into());
 
-        let object_handle = ObjectHandle::new(use_context().clone(), entity, object_id);
-
         (
-            object_handle,
+            object_id,
             builder
                 .with(Object::new(entity, object_id))
                 .with(transform.unwrap_or_default()),
         )
     }
 
+    /// Creates a copy of the object and all of its children, with new ids. The names, the active states, the
+    /// transforms and the [cloneable](Self::register_cloneable) components are copied. The copy is placed under
+    /// the parent of the object. Returns the id of the copy of the object.
+    pub fn instantiate(&mut self, world: &mut World, subtree_root: ObjectId) -> ObjectId {
+        let originals = self
+            .object_hierarchy
+            .object_and_children(subtree_root)
+            .to_vec();
+        let mut copies = HashMap::with_capacity(originals.len());
+
+        // Parents precede their children, so the parent of every copy but the first one is already copied.
+        for original in originals {
+            let original_entity = self.object_hierarchy.entity(original);
+            let name = self.object_name_registry.name(original).cloned();
+            let transform = world
+                .read_component::<Transform>()
+                .get(original_entity)
+                .cloned();
+            let (copy, builder) = self.create_object_id_builder(world, name, transform);
+            let copy_entity = builder.build();
+
+            for clone in &self.component_cloners {
+                clone(world, original_entity, copy_entity);
+            }
+
+            let parent = self
+                .object_hierarchy
+                .parent(original)
+                .map(|parent| copies.get(&parent).copied().unwrap_or(parent));
+
+            if parent.is_some() {
+                self.object_hierarchy.set_parent(copy, parent);
+            }
+
+            if !self.object_hierarchy.is_active_self(original) {
+                self.object_hierarchy.set_active(copy, false);
+            }
+
+            copies.insert(original, copy);
+        }
+
+        // The built-in components that refer to objects in the subtree are pointed at the copies.
+        let remap = |object: ObjectId| copies.get(&object).copied().unwrap_or(object);
+        let copy_entities = copies
+            .values()
+            .map(|&copy| self.object_hierarchy.entity(copy))
+            .collect::<Vec<_>>();
+        modify_components::<UIProgressBar>(world, &copy_entities, |progress_bar| {
+            progress_bar.fill = remap(progress_bar.fill)
+        });
+        modify_components::<UISlider>(world, &copy_entities, |slider| {
+            slider.handle = remap(slider.handle)
+        });
+
+        copies[&subtree_root]
+    }
+
     pub fn remove_object(&mut self, handle: &ObjectHandle) {
         use_context()
             .world_mut()
@@ -96,3 +220,99 @@ impl ObjectManager {
         use_context().ui_event_mgr_mut().remove_object(handle);
     }
 }
+
+fn clone_component<T>(world: &World, from: Entity, to: Entity)
+where
+    T: Component + Clone,
+{
+    // Worlds that don't have the type registered cannot have the component anyway.
+    if !world.has_value::<MaskedStorage<T>>() {
+        return;
+    }
+
+    let mut storage = world.write_component::<T>();
+
+    if let Some(component) = storage.get(from).cloned() {
+        // The entity was just created, so the insertion cannot fail.
+        let _ = storage.insert(to, component);
+    }
+}
+
+fn modify_components<T>(world: &World, entities: &[Entity], mut f: impl FnMut(&mut T))
+where
+    T: Component,
+{
+    if !world.has_value::<MaskedStorage<T>>() {
+        return;
+    }
+
+    let mut storage = world.write_component::<T>();
+
+    for &entity in entities {
+        if let Some(component) = storage.get_mut(entity) {
+            f(component);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{Quat, Vec3};
+
+    #[test]
+    fn instantiate_copies_subtree_with_new_ids() {
+        let mut world = World::new();
+        world.register::<Object>();
+        world.register::<Transform>();
+        world.register::<Pickable>();
+
+        let mut object_mgr = ObjectManager::new();
+        let mut create = |name: &str, x: f32| {
+            let (object, builder) = object_mgr.create_object_id_builder(
+                &mut world,
+                Some(name.to_owned()),
+                Some(Transform {
+                    position: Vec3::new(x, 2.0, 3.0),
+                    rotation: Quat::from_eular(0.0, x, 0.0),
+                    scale: Vec3::new(1.0, x, 1.0),
+                }),
+            );
+            builder.with(Pickable).build();
+            object
+        };
+        let scene_root = create("scene", 0.0);
+        let root = create("root", 1.0);
+        let child = create("child", 2.0);
+
+        let hierarchy = object_mgr.object_hierarchy_mut();
+        hierarchy.set_parent(root, Some(scene_root));
+        hierarchy.set_parent(child, Some(root));
+
+        let copy = object_mgr.instantiate(&mut world, root);
+        let hierarchy = object_mgr.object_hierarchy();
+        let copy_child = hierarchy.object_and_children(copy)[1];
+
+        assert_ne!(copy, root);
+        assert_ne!(copy_child, child);
+        assert_eq!(hierarchy.parent(copy), Some(scene_root));
+        assert_eq!(hierarchy.parent(copy_child), Some(copy));
+        assert_eq!(
+            object_mgr.object_name_registry().name(copy_child),
+            Some(&"child".to_owned())
+        );
+
+        let transforms = world.read_component::<Transform>();
+        let pickables = world.read_component::<Pickable>();
+
+        for (original, copy) in [(root, copy), (child, copy_child)] {
+            let original = transforms.get(hierarchy.entity(original)).unwrap();
+            let copy_entity = hierarchy.entity(copy);
+            let copy = transforms.get(copy_entity).unwrap();
+            assert_eq!(copy.position, original.position);
+            assert_eq!(copy.rotation, original.rotation);
+            assert_eq!(copy.scale, original.scale);
+            assert!(pickables.contains(copy_entity));
+        }
+    }
+}
@@ -4,6 +4,7 @@ use std::collections::{HashMap, HashSet};
 pub struct ObjectNameRegistry {
     object_names: HashMap<ObjectId, String>,
     object_ids: HashMap<String, HashSet<ObjectId>>,
+    is_changed: bool,
 }
 
 impl ObjectNameRegistry {
@@ -11,6 +12,7 @@ impl ObjectNameRegistry {
         Self {
             object_names: HashMap::new(),
             object_ids: HashMap::new(),
+            is_changed: false,
         }
     }
 
@@ -24,6 +26,7 @@ impl ObjectNameRegistry {
 
     pub fn set_name(&mut self, object: ObjectId, name: Option<String>) {
         self.decouple(object);
+        self.is_changed = true;
 
         if let Some(name) = name {
             self.object_names.insert(object, name.clone());
@@ -34,6 +37,11 @@ impl ObjectNameRegistry {
         }
     }
 
+    /// Returns `true` once after a name has been set.
+    pub(crate) fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.is_changed)
+    }
+
     fn decouple(&mut self, object: ObjectId) {
         if let Some(name) = self.object_names.remove(&object) {
             if let Some(object_ids) = self.object_ids.get_mut(&name) {
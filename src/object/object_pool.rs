@@ -0,0 +1,309 @@
+use super::ObjectHandle;
+use crate::{transform::Transform, use_context};
+use specs::prelude::*;
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+};
+use thiserror::Error;
+
+/// What [`ObjectPool::acquire`] does when every object of the pool is in use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ObjectPoolExhaustion {
+    /// Creates another object with the factory.
+    Grow,
+    /// Takes back the object that has been in use for the longest time.
+    ReuseOldest,
+    /// Fails with [`ObjectPoolError::Exhausted`].
+    Fail,
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ObjectPoolError {
+    #[error("every object of the pool is in use")]
+    Exhausted,
+}
+
+/// Keeps objects that are spawned and despawned frequently, such as bullets and particles, alive
+/// instead of destroying them. Released objects are deactivated and kept under a hidden root.
+///
+/// Objects of the pool may still be removed outright; they are dropped from the pool once it notices.
+pub struct ObjectPool {
+    root: ObjectHandle,
+    factory: Box<dyn FnMut() -> ObjectHandle>,
+    exhaustion: ObjectPoolExhaustion,
+    slots: ObjectPoolSlots<ObjectHandle>,
+}
+
+impl ObjectPool {
+    /// Creates a pool with `capacity` objects created by the factory.
+    pub fn new(
+        capacity: usize,
+        exhaustion: ObjectPoolExhaustion,
+        factory: impl FnMut() -> ObjectHandle + 'static,
+    ) -> Self {
+        let root = {
+            let context = use_context();
+            let mut object_mgr = context.object_mgr_mut();
+            let mut world = context.world_mut();
+            let (root, builder) =
+                object_mgr.create_object_builder(&mut world, Some("object pool".to_owned()), None);
+            builder.build();
+            root
+        };
+        root.set_active(false);
+
+        let mut this = Self {
+            root,
+            factory: Box::new(factory),
+            exhaustion,
+            slots: ObjectPoolSlots::new(),
+        };
+
+        for _ in 0..capacity {
+            let object = (this.factory)();
+            this.store(&object);
+            this.slots.available.push(object);
+        }
+
+        this
+    }
+
+    /// Returns the hidden root that the released objects are kept under.
+    pub fn root(&self) -> &ObjectHandle {
+        &self.root
+    }
+
+    pub fn exhaustion(&self) -> ObjectPoolExhaustion {
+        self.exhaustion
+    }
+
+    pub fn set_exhaustion(&mut self, exhaustion: ObjectPoolExhaustion) {
+        self.exhaustion = exhaustion;
+    }
+
+    /// Returns the number of objects that are ready to be acquired.
+    pub fn available_count(&self) -> usize {
+        self.slots.available.len()
+    }
+
+    /// Returns the number of objects that have been acquired and not released yet.
+    pub fn in_use_count(&self) -> usize {
+        self.slots.in_use.len()
+    }
+
+    /// Hands out an object of the pool. It is activated, detached from the root and its transform is reset.
+    pub fn acquire(&mut self) -> Result<ObjectHandle, ObjectPoolError> {
+        let object = match self.slots.take_available(is_alive) {
+            Some(object) => object,
+            None => match self.exhaustion {
+                ObjectPoolExhaustion::Grow => (self.factory)(),
+                ObjectPoolExhaustion::ReuseOldest => self
+                    .slots
+                    .take_oldest(is_alive)
+                    .ok_or(ObjectPoolError::Exhausted)?,
+                ObjectPoolExhaustion::Fail => return Err(ObjectPoolError::Exhausted),
+            },
+        };
+
+        object.set_parent(None);
+        object.set_active(true);
+        reset_transform(&object);
+
+        self.slots.mark_in_use(object.clone());
+        Ok(object)
+    }
+
+    /// Takes the object back into the pool. It is deactivated and moved under the root.
+    /// Returns `false` if the object is not in use from this pool.
+    pub fn release(&mut self, object: &ObjectHandle) -> bool {
+        if !self.slots.release(object, is_alive) {
+            return false;
+        }
+
+        self.store(object);
+        true
+    }
+
+    /// Drops the objects that have been removed outright from the pool.
+    pub fn prune(&mut self) {
+        self.slots.prune(is_alive);
+    }
+
+    fn store(&self, object: &ObjectHandle) {
+        object.set_active(false);
+        object.set_parent(&self.root);
+    }
+}
+
+fn is_alive(object: &ObjectHandle) -> bool {
+    object.ctx.world().is_alive(object.entity)
+}
+
+fn reset_transform(object: &ObjectHandle) {
+    object
+        .ctx
+        .object_mgr_mut()
+        .object_hierarchy_mut()
+        .set_dirty(object.object_id);
+
+    let world = object.ctx.world();
+    let mut transforms = world.write_component::<Transform>();
+
+    if let Some(transform) = transforms.get_mut(object.entity) {
+        *transform = Transform::default();
+    }
+}
+
+/// The bookkeeping of [`ObjectPool`]. The acquisition order is kept for
+/// [`ObjectPoolExhaustion::ReuseOldest`]; entries of released objects are skipped lazily.
+struct ObjectPoolSlots<T> {
+    available: Vec<T>,
+    in_use: HashMap<T, u64>,
+    order: VecDeque<(T, u64)>,
+    next_serial: u64,
+}
+
+impl<T> ObjectPoolSlots<T>
+where
+    T: Clone + Eq + Hash,
+{
+    fn new() -> Self {
+        Self {
+            available: Vec::new(),
+            in_use: HashMap::new(),
+            order: VecDeque::new(),
+            next_serial: 0,
+        }
+    }
+
+    fn take_available(&mut self, is_alive: impl Fn(&T) -> bool) -> Option<T> {
+        while let Some(item) = self.available.pop() {
+            if is_alive(&item) {
+                return Some(item);
+            }
+        }
+
+        None
+    }
+
+    fn take_oldest(&mut self, is_alive: impl Fn(&T) -> bool) -> Option<T> {
+        while let Some((item, serial)) = self.order.pop_front() {
+            if self.in_use.get(&item) != Some(&serial) {
+                continue;
+            }
+
+            self.in_use.remove(&item);
+
+            if is_alive(&item) {
+                return Some(item);
+            }
+        }
+
+        None
+    }
+
+    fn mark_in_use(&mut self, item: T) {
+        let serial = self.next_serial;
+        self.next_serial += 1;
+        self.in_use.insert(item.clone(), serial);
+        self.order.push_back((item, serial));
+    }
+
+    fn release(&mut self, item: &T, is_alive: impl Fn(&T) -> bool) -> bool {
+        // The stored item is checked rather than the given one, since the id of a removed item may have been reused.
+        let (item, _) = match self.in_use.remove_entry(item) {
+            Some(entry) => entry,
+            None => return false,
+        };
+
+        if !is_alive(&item) {
+            return false;
+        }
+
+        self.available.push(item);
+
+        // Keeps the order from growing with the entries of released items.
+        if self.in_use.len() * 2 + 16 < self.order.len() {
+            let in_use = &self.in_use;
+            self.order
+                .retain(|(item, serial)| in_use.get(item) == Some(serial));
+        }
+
+        true
+    }
+
+    fn prune(&mut self, is_alive: impl Fn(&T) -> bool) {
+        self.available.retain(&is_alive);
+        self.in_use.retain(|item, _| is_alive(item));
+
+        let in_use = &self.in_use;
+        self.order
+            .retain(|(item, serial)| in_use.get(item) == Some(serial));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pool_slots_keep_memory_stable_under_churn() {
+        let mut slots = ObjectPoolSlots::new();
+        let mut next_item = 0u32;
+        let mut acquired = Vec::new();
+        let mut warm_capacities = None;
+
+        for frame in 0..100 {
+            // Releases the items kept from the previous frame, then spawns 10k items and releases
+            // all but the most recent 100.
+            for item in acquired.drain(..) {
+                assert!(slots.release(&item, |_| true));
+            }
+
+            for _ in 0..10_000 {
+                let item = slots.take_available(|_| true).unwrap_or_else(|| {
+                    next_item += 1;
+                    next_item
+                });
+                slots.mark_in_use(item);
+                acquired.push(item);
+            }
+
+            let keep = acquired.split_off(acquired.len() - 100);
+            for item in acquired.drain(..) {
+                assert!(slots.release(&item, |_| true));
+            }
+            acquired = keep;
+
+            let current = (
+                slots.available.capacity(),
+                slots.in_use.capacity(),
+                slots.order.capacity(),
+            );
+
+            // The reported capacity of a map dips with its tombstones, so the largest one seen while
+            // warming up is the bound that it must never grow past afterwards.
+            match (frame, &mut warm_capacities) {
+                (0..=10, None) => warm_capacities = Some(current),
+                (0..=10, Some((available, in_use, order))) => {
+                    *available = current.0.max(*available);
+                    *in_use = current.1.max(*in_use);
+                    *order = current.2.max(*order);
+                }
+                (_, Some((available, in_use, order))) => {
+                    assert!(current.0 <= *available && current.1 <= *in_use && current.2 <= *order);
+                }
+                (_, None) => unreachable!(),
+            }
+        }
+
+        assert_eq!(next_item, 10_000);
+        assert!(!slots.release(&0, |_| true));
+
+        // The oldest item in use is reused first, and removed items are skipped.
+        let oldest = acquired[0];
+        assert_eq!(slots.take_oldest(|item| *item != oldest), Some(acquired[1]));
+        assert!(!slots.release(&oldest, |_| true));
+    }
+}
@@ -1,17 +1,13 @@
-use self::new::ObjectId;
 use super::{
     new::{Component, Object},
-    ComponentId, ComponentStorage,
+    ComponentId, ComponentStorage, ObjectActiveTransition, ObjectHierarchy, ObjectId,
 };
-use crate::util::SlotMap;
-
-pub mod new {
-    pub type ObjectId = usize;
-}
 
+/// The components of the objects of a hierarchy. Objects are keyed by their ids in the hierarchy, so that changes
+/// of the hierarchy can be dispatched to their components.
 #[derive(Default)]
 pub struct ObjectStorage {
-    objects: SlotMap<Object>,
+    objects: Vec<Option<Object>>,
     component_storage: ComponentStorage,
 }
 
@@ -24,19 +20,24 @@ impl ObjectStorage {
         &self.component_storage
     }
 
-    pub fn add_object(&mut self) -> ObjectId {
-        self.objects.allocate(Object::new())
+    pub fn object(&self, id: ObjectId) -> Option<&Object> {
+        self.objects.get(id.get() as usize)?.as_ref()
     }
 
-    pub fn remove_object(&mut self, id: ObjectId) -> Option<()> {
-        let object = self.objects.get_mut(id)?;
+    pub fn add_object(&mut self, id: ObjectId) {
+        let index = id.get() as usize;
 
-        for id in object.component_ids() {
-            self.component_storage.remove_component_untyped(*id);
+        if self.objects.len() <= index {
+            self.objects.resize_with(index + 1, || None);
         }
 
-        self.objects.deallocate(id);
+        self.objects[index] = Some(Object::new());
+    }
 
+    /// Removes the object and its components, invoking [`Component::fin`] on each.
+    pub fn remove_object(&mut self, id: ObjectId) -> Option<()> {
+        let mut object = self.objects.get_mut(id.get() as usize)?.take()?;
+        object.remove_all_components(&mut self.component_storage);
         Some(())
     }
 
@@ -45,7 +46,7 @@ impl ObjectStorage {
         id: ObjectId,
         component: impl Component,
     ) -> Option<ComponentId> {
-        let object = self.objects.get_mut(id)?;
+        let object = self.objects.get_mut(id.get() as usize)?.as_mut()?;
         Some(object.add_component(&mut self.component_storage, component))
     }
 
@@ -55,15 +56,142 @@ impl ObjectStorage {
         index: usize,
         component: impl Component,
     ) -> Option<ComponentId> {
-        let object = self.objects.get_mut(id)?;
+        let object = self.objects.get_mut(id.get() as usize)?.as_mut()?;
         Some(object.add_component_at(&mut self.component_storage, index, component))
     }
 
     pub fn remove_component(&mut self, id: ObjectId, component_id: ComponentId) {
-        if let Some(object) = self.objects.get_mut(id) {
-            // TODO: we need a method that only removes the component from the object,
-            // but not from the component storage
-            object.remove_component(&mut self.component_storage, component_id);
+        if let Some(Some(object)) = self.objects.get_mut(id.get() as usize) {
+            object.remove_component_untyped(&mut self.component_storage, component_id);
         }
     }
+
+    /// Sets the active flag of the object in the hierarchy, and enables or disables the components of the objects
+    /// whose active state changed. Each component is notified once per change, parents first.
+    pub fn set_active(&self, hierarchy: &mut ObjectHierarchy, id: ObjectId, is_active: bool) {
+        self.dispatch_active_transitions(&hierarchy.set_active(id, is_active));
+    }
+
+    /// Sets the parent of the object in the hierarchy, and enables or disables the components of the objects whose
+    /// active state changed by the new parents, like [`Self::set_active`].
+    pub fn set_parent(
+        &self,
+        hierarchy: &mut ObjectHierarchy,
+        id: ObjectId,
+        parent: Option<ObjectId>,
+    ) {
+        self.dispatch_active_transitions(&hierarchy.set_parent(id, parent));
+    }
+
+    /// Invokes [`Component::enable`] or [`Component::disable`] on the components of the objects.
+    pub fn dispatch_active_transitions(&self, transitions: &[ObjectActiveTransition]) {
+        for transition in transitions {
+            let object = if let Some(object) = self.object(transition.object) {
+                object
+            } else {
+                continue;
+            };
+
+            for id in object.component_ids() {
+                let mut component =
+                    if let Some(component) = self.component_storage.get_component_dyn_mut(*id) {
+                        component
+                    } else {
+                        continue;
+                    };
+
+                if transition.is_active {
+                    component.enable();
+                } else {
+                    component.disable();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::{Builder, World, WorldExt};
+    use std::{cell::Cell, rc::Rc};
+
+    #[derive(Default)]
+    struct Counts {
+        enables: Cell<u32>,
+        disables: Cell<u32>,
+    }
+
+    struct CountingComponent {
+        counts: Rc<Counts>,
+    }
+
+    impl Component for CountingComponent {
+        fn new(_id: ObjectId) -> Self
+        where
+            Self: Sized,
+        {
+            Self {
+                counts: Default::default(),
+            }
+        }
+
+        fn enable(&mut self) {
+            self.counts.enables.set(self.counts.enables.get() + 1);
+        }
+
+        fn disable(&mut self) {
+            self.counts.disables.set(self.counts.disables.get() + 1);
+        }
+    }
+
+    #[test]
+    fn active_transitions_enable_and_disable_components() {
+        let mut world = World::new();
+        let mut hierarchy = ObjectHierarchy::new();
+        let mut storage = ObjectStorage::new();
+        let [root, child, other] = [0, 1, 2].map(ObjectId::from_u32);
+        let counts = [root, child, other].map(|id| {
+            let counts = Rc::new(Counts::default());
+            hierarchy.add(id, world.create_entity().build());
+            storage.add_object(id);
+            storage.add_component(
+                id,
+                CountingComponent {
+                    counts: counts.clone(),
+                },
+            );
+            counts
+        });
+        let totals = || {
+            counts
+                .each_ref()
+                .map(|counts| (counts.enables.get(), counts.disables.get()))
+        };
+
+        storage.set_parent(&mut hierarchy, child, Some(root));
+        assert_eq!(totals(), [(0, 0), (0, 0), (0, 0)]);
+
+        storage.set_active(&mut hierarchy, root, false);
+        assert_eq!(totals(), [(0, 1), (0, 1), (0, 0)]);
+
+        // Neither object changes, as the child is already inactive by its parent.
+        storage.set_active(&mut hierarchy, root, false);
+        storage.set_active(&mut hierarchy, child, false);
+        assert_eq!(totals(), [(0, 1), (0, 1), (0, 0)]);
+
+        storage.set_active(&mut hierarchy, root, true);
+        assert_eq!(totals(), [(1, 1), (0, 1), (0, 0)]);
+
+        storage.set_active(&mut hierarchy, child, true);
+        assert_eq!(totals(), [(1, 1), (1, 1), (0, 0)]);
+
+        // Moving under an inactive parent deactivates the object and its children.
+        storage.set_active(&mut hierarchy, other, false);
+        storage.set_parent(&mut hierarchy, root, Some(other));
+        assert_eq!(totals(), [(1, 2), (1, 2), (0, 1)]);
+
+        storage.set_parent(&mut hierarchy, root, None);
+        assert_eq!(totals(), [(2, 2), (2, 2), (0, 1)]);
+    }
 }
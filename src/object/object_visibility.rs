@@ -0,0 +1,66 @@
+use super::ObjectHierarchy;
+use codegen::Component;
+use smallvec::SmallVec;
+use specs::prelude::*;
+
+/// Hides an object and its children from rendering, from every camera or from specific ones.
+/// Use [`ObjectHandle::set_visible`](super::ObjectHandle::set_visible) to hide the object from every camera,
+/// so that the hierarchy is updated as well.
+#[derive(Debug, Default, Clone, Component)]
+#[storage(HashMapStorage)]
+#[auto_register]
+pub struct ObjectVisibility {
+    hidden: bool,
+    /// Entities of the camera objects that do not render the object and its children.
+    pub hidden_for_cameras: SmallVec<[Entity; 2]>,
+}
+
+impl ObjectVisibility {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        self.hidden
+    }
+
+    pub(crate) fn set_hidden(&mut self, hidden: bool) {
+        self.hidden = hidden;
+    }
+
+    pub fn is_hidden_for_camera(&self, camera: Entity) -> bool {
+        self.hidden_for_cameras.contains(&camera)
+    }
+
+    pub fn set_hidden_for_camera(&mut self, camera: Entity, hidden: bool) {
+        if hidden {
+            if !self.is_hidden_for_camera(camera) {
+                self.hidden_for_cameras.push(camera);
+            }
+        } else {
+            self.hidden_for_cameras.retain(|entity| *entity != camera);
+        }
+    }
+}
+
+/// Returns `true` if the given camera should not render the object, which is the case if the object is inactive,
+/// hidden, or hidden from the camera by itself or by any of its parents.
+pub fn is_object_hidden_for_camera(
+    hierarchy: &ObjectHierarchy,
+    visibilities: &ReadStorage<ObjectVisibility>,
+    object: super::ObjectId,
+    camera: Entity,
+) -> bool {
+    if !hierarchy.is_active(object) || !hierarchy.is_visible(object) {
+        return true;
+    }
+
+    if visibilities.is_empty() {
+        return false;
+    }
+
+    std::iter::once(object)
+        .chain(hierarchy.parents(object).iter().copied())
+        .filter_map(|object| visibilities.get(hierarchy.entity(object)))
+        .any(|visibility| visibility.is_hidden_for_camera(camera))
+}
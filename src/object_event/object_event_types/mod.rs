@@ -1,3 +1,5 @@
+use crate::math::Vec2;
+
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct MouseEnterEvent;
 
@@ -12,3 +14,29 @@ pub struct MouseDownEvent;
 
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct MouseUpEvent;
+
+/// Dispatched to an interactable object when the left mouse button is pressed on it.
+/// The position is in UI space; the origin is at the center of the screen and y-axis is up.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DragStartEvent {
+    pub position: Vec2,
+}
+
+/// Dispatched to the dragged object whenever the mouse moves while the left mouse button is held.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DragEvent {
+    pub position: Vec2,
+    pub delta: Vec2,
+}
+
+/// Dispatched to the dragged object when the left mouse button is released.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DragEndEvent {
+    pub position: Vec2,
+}
+
+/// Dispatched to a value widget, such as [`UISlider`](crate::ui::UISlider), when its value has been changed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ValueChangedEvent {
+    pub value: f32,
+}
@@ -0,0 +1,421 @@
+use crate::{
+    math::{Quat, Vec3},
+    object::{ObjectId, ObjectManager},
+    transform::Transform,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use specs::{Builder, Component, Entity, World, WorldExt};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum SceneError {
+    #[error("io error: {0}")]
+    IOError(#[from] std::io::Error),
+    #[error("failed to parse or write the scene: {0}")]
+    SerdeError(#[from] serde_json::Error),
+    #[error("component type `{0}` is not registered")]
+    UnknownComponent(String),
+    #[error("failed to serialize or deserialize component `{type_name}`: {source}")]
+    ComponentError {
+        type_name: String,
+        source: serde_json::Error,
+    },
+    #[error("object {object} refers to parent {parent}, which does not precede it")]
+    UnknownParent { object: u32, parent: u32 },
+}
+
+/// A saved scene. Objects are listed parents first, and are identified by their index in the list, which is
+/// unrelated to their [`ObjectId`]s.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Scene {
+    pub objects: Vec<SceneObject>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SceneObject {
+    pub id: u32,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub parent: Option<u32>,
+    #[serde(default = "default_active")]
+    pub is_active: bool,
+    pub transform: SceneTransform,
+    /// The components, keyed by the type names they are registered with in the [`SceneSerializer`].
+    #[serde(default)]
+    pub components: BTreeMap<String, serde_json::Value>,
+}
+
+fn default_active() -> bool {
+    true
+}
+
+/// The local transform of a [`SceneObject`].
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct SceneTransform {
+    pub position: [f32; 3],
+    pub rotation: [f32; 4],
+    pub scale: [f32; 3],
+}
+
+impl From<&Transform> for SceneTransform {
+    fn from(transform: &Transform) -> Self {
+        let Transform {
+            position,
+            rotation,
+            scale,
+        } = transform;
+
+        Self {
+            position: [position.x, position.y, position.z],
+            rotation: [rotation.x, rotation.y, rotation.z, rotation.w],
+            scale: [scale.x, scale.y, scale.z],
+        }
+    }
+}
+
+impl From<SceneTransform> for Transform {
+    fn from(transform: SceneTransform) -> Self {
+        let [px, py, pz] = transform.position;
+        let [rx, ry, rz, rw] = transform.rotation;
+        let [sx, sy, sz] = transform.scale;
+
+        Self {
+            position: Vec3::new(px, py, pz),
+            rotation: Quat {
+                x: rx,
+                y: ry,
+                z: rz,
+                w: rw,
+            },
+            scale: Vec3::new(sx, sy, sz),
+        }
+    }
+}
+
+impl Scene {
+    pub fn from_json(json: &str) -> Result<Self, SceneError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn to_json(&self) -> Result<String, SceneError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn load_from_file(path: impl AsRef<Path>) -> Result<Self, SceneError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn save_to_file(&self, path: impl AsRef<Path>) -> Result<(), SceneError> {
+        std::fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+}
+
+type SaveComponentFn = fn(&World, Entity) -> Option<Result<serde_json::Value, serde_json::Error>>;
+type LoadComponentFn = fn(&World, Entity, serde_json::Value) -> Result<(), serde_json::Error>;
+
+/// Saves objects into [`Scene`]s and loads them back. Besides the names, the hierarchy and the transforms,
+/// only the components of the registered types are saved.
+#[derive(Default)]
+pub struct SceneSerializer {
+    components: BTreeMap<String, (SaveComponentFn, LoadComponentFn)>,
+}
+
+impl SceneSerializer {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Registers a component type to be saved and loaded under the type name. The component must be registered
+    /// to the world as well.
+    pub fn register<T>(&mut self, type_name: impl Into<String>)
+    where
+        T: Component + Serialize + DeserializeOwned,
+    {
+        self.components
+            .insert(type_name.into(), (save_component::<T>, load_component::<T>));
+    }
+
+    /// Saves all objects.
+    pub fn save(&self, object_mgr: &ObjectManager, world: &World) -> Result<Scene, SceneError> {
+        let hierarchy = object_mgr.object_hierarchy();
+        let roots = hierarchy
+            .objects()
+            .iter()
+            .copied()
+            .filter(|&object| hierarchy.parent(object).is_none())
+            .collect::<Vec<_>>();
+        self.save_subtrees(roots, object_mgr, world)
+    }
+
+    /// Saves the objects and their children, e.g. to leave out the objects of an editor. The parents of the
+    /// given objects are not saved, so they become top-level objects when loaded.
+    pub fn save_subtrees(
+        &self,
+        roots: impl IntoIterator<Item = ObjectId>,
+        object_mgr: &ObjectManager,
+        world: &World,
+    ) -> Result<Scene, SceneError> {
+        let hierarchy = object_mgr.object_hierarchy();
+        let names = object_mgr.object_name_registry();
+        let transforms = world.read_component::<Transform>();
+        let mut ids = HashMap::new();
+        let mut objects = Vec::new();
+
+        for root in roots {
+            for &object in hierarchy.object_and_children(root) {
+                let id = objects.len() as u32;
+                let entity = hierarchy.entity(object);
+                let mut components = BTreeMap::new();
+
+                for (type_name, (save, _)) in &self.components {
+                    if let Some(value) = save(world, entity) {
+                        let value = value.map_err(|source| SceneError::ComponentError {
+                            type_name: type_name.clone(),
+                            source,
+                        })?;
+                        components.insert(type_name.clone(), value);
+                    }
+                }
+
+                ids.insert(object, id);
+                objects.push(SceneObject {
+                    id,
+                    name: names.name(object).cloned(),
+                    parent: hierarchy
+                        .parent(object)
+                        .and_then(|parent| ids.get(&parent).copied()),
+                    is_active: hierarchy.is_active_self(object),
+                    transform: transforms
+                        .get(entity)
+                        .map(SceneTransform::from)
+                        .unwrap_or_else(|| (&Transform::new()).into()),
+                    components,
+                });
+            }
+        }
+
+        Ok(Scene { objects })
+    }
+
+    /// Creates the objects of the scene, and returns their ids in the order of the scene.
+    pub fn load(
+        &self,
+        scene: &Scene,
+        object_mgr: &mut ObjectManager,
+        world: &mut World,
+    ) -> Result<Vec<ObjectId>, SceneError> {
+        // Fails before creating any object if a component cannot be loaded at all.
+        for object in &scene.objects {
+            if let Some(type_name) = object
+                .components
+                .keys()
+                .find(|type_name| !self.components.contains_key(*type_name))
+            {
+                return Err(SceneError::UnknownComponent(type_name.clone()));
+            }
+        }
+
+        let mut ids = HashMap::new();
+        let mut created = Vec::with_capacity(scene.objects.len());
+
+        for object in &scene.objects {
+            let parent = match object.parent {
+                Some(parent) => Some(*ids.get(&parent).ok_or(SceneError::UnknownParent {
+                    object: object.id,
+                    parent,
+                })?),
+                None => None,
+            };
+
+            let (object_id, builder) = object_mgr.create_object_id_builder(
+                world,
+                object.name.clone(),
+                Some(object.transform.into()),
+            );
+            let entity = builder.build();
+            let hierarchy = object_mgr.object_hierarchy_mut();
+
+            if parent.is_some() {
+                hierarchy.set_parent(object_id, parent);
+            }
+
+            if !object.is_active {
+                hierarchy.set_active(object_id, false);
+            }
+
+            ids.insert(object.id, object_id);
+            created.push(object_id);
+
+            for (type_name, value) in &object.components {
+                let (_, load) = &self.components[type_name];
+                load(world, entity, value.clone()).map_err(|source| {
+                    SceneError::ComponentError {
+                        type_name: type_name.clone(),
+                        source,
+                    }
+                })?;
+            }
+        }
+
+        Ok(created)
+    }
+}
+
+fn save_component<T>(
+    world: &World,
+    entity: Entity,
+) -> Option<Result<serde_json::Value, serde_json::Error>>
+where
+    T: Component + Serialize,
+{
+    world
+        .read_component::<T>()
+        .get(entity)
+        .map(serde_json::to_value)
+}
+
+fn load_component<T>(
+    world: &World,
+    entity: Entity,
+    value: serde_json::Value,
+) -> Result<(), serde_json::Error>
+where
+    T: Component + DeserializeOwned,
+{
+    let component = serde_json::from_value::<T>(value)?;
+    // The entity was just created, so the insertion cannot fail.
+    let _ = world.write_component::<T>().insert(entity, component);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object::Object;
+    use specs::VecStorage;
+
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+    struct Health {
+        current: u32,
+        max: u32,
+    }
+
+    impl Component for Health {
+        type Storage = VecStorage<Self>;
+    }
+
+    fn new_world() -> World {
+        let mut world = World::new();
+        world.register::<Object>();
+        world.register::<Transform>();
+        world.register::<Health>();
+        world
+    }
+
+    fn transform(x: f32, scale: f32) -> Option<Transform> {
+        Some(Transform {
+            position: Vec3::new(x, 1.5, -2.0),
+            rotation: Quat::from_eular(0.1, x, 0.3),
+            scale: Vec3::new(scale, scale, 1.0),
+        })
+    }
+
+    #[test]
+    fn scenes_round_trip_through_json() {
+        let mut serializer = SceneSerializer::new();
+        serializer.register::<Health>("health");
+
+        let mut world = new_world();
+        let mut object_mgr = ObjectManager::new();
+        let mut create = |name: &str, x: f32, health: Option<Health>| {
+            let (object, builder) = object_mgr.create_object_id_builder(
+                &mut world,
+                Some(name.to_owned()),
+                transform(x, 2.0),
+            );
+            match health {
+                Some(health) => builder.with(health).build(),
+                None => builder.build(),
+            };
+            object
+        };
+        create("other", 4.0, None);
+        let root = create("root", 1.0, Some(Health { current: 3, max: 5 }));
+        let child = create("child", 2.0, None);
+        let grandchild = create("grandchild", 3.0, Some(Health { current: 1, max: 1 }));
+
+        let hierarchy = object_mgr.object_hierarchy_mut();
+        hierarchy.set_parent(grandchild, Some(child));
+        hierarchy.set_parent(child, Some(root));
+        hierarchy.set_active(child, false);
+
+        let scene = serializer.save(&object_mgr, &world).unwrap();
+        let parents = scene
+            .objects
+            .iter()
+            .map(|object| (object.name.as_deref().unwrap(), object.parent))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            parents,
+            [
+                ("other", None),
+                ("root", None),
+                ("child", Some(1)),
+                ("grandchild", Some(2)),
+            ]
+        );
+        assert_eq!(
+            scene.objects[1].components["health"],
+            serde_json::json!({ "current": 3, "max": 5 })
+        );
+
+        let loaded_scene = Scene::from_json(&scene.to_json().unwrap()).unwrap();
+        assert_eq!(loaded_scene, scene);
+
+        let mut loaded_world = new_world();
+        let mut loaded_object_mgr = ObjectManager::new();
+        let loaded = serializer
+            .load(&loaded_scene, &mut loaded_object_mgr, &mut loaded_world)
+            .unwrap();
+        assert_eq!(loaded.len(), 4);
+
+        let hierarchy = loaded_object_mgr.object_hierarchy();
+        assert_eq!(hierarchy.parent(loaded[3]), Some(loaded[2]));
+        assert_eq!(hierarchy.parent(loaded[2]), Some(loaded[1]));
+        assert!(!hierarchy.is_active(loaded[3]));
+        assert_eq!(
+            loaded_world
+                .read_component::<Health>()
+                .get(hierarchy.entity(loaded[3])),
+            Some(&Health { current: 1, max: 1 })
+        );
+
+        assert_eq!(
+            serializer.save(&loaded_object_mgr, &loaded_world).unwrap(),
+            scene
+        );
+    }
+
+    #[test]
+    fn unknown_components_fail_before_loading() {
+        let scene = Scene::from_json(
+            r#"{ "objects": [{ "id": 0, "transform": { "position": [0, 0, 0], "rotation": [0, 0, 0, 1],
+            "scale": [1, 1, 1] }, "components": { "health": { "current": 1, "max": 1 } } }] }"#,
+        )
+        .unwrap();
+        let mut world = new_world();
+        let mut object_mgr = ObjectManager::new();
+
+        assert!(matches!(
+            SceneSerializer::new().load(&scene, &mut object_mgr, &mut world),
+            Err(SceneError::UnknownComponent(type_name)) if type_name == "health"
+        ));
+        assert!(object_mgr.object_hierarchy().objects().is_empty());
+    }
+}
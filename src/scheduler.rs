@@ -0,0 +1,375 @@
+use std::{
+    cell::{Cell, RefCell},
+    time::Duration,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TaskId(u64);
+
+/// Tells the scheduler whether a repeating task should keep running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ControlFlowDecision {
+    Continue,
+    Stop,
+}
+
+/// The clock that a task is scheduled on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum TaskClock {
+    Scaled,
+    Unscaled,
+}
+
+enum TaskCallback {
+    Once(Option<Box<dyn FnOnce()>>),
+    Repeat(Option<Box<dyn FnMut() -> ControlFlowDecision>>),
+}
+
+impl TaskCallback {
+    fn is_finished(&self) -> bool {
+        matches!(self, TaskCallback::Once(None) | TaskCallback::Repeat(None))
+    }
+}
+
+struct Task {
+    id: TaskId,
+    clock: TaskClock,
+    due: Duration,
+    interval: Duration,
+    callback: TaskCallback,
+}
+
+/// Runs callbacks after a delay or repeatedly, on the scaled or unscaled time of
+/// [`TimeManager`](crate::time::TimeManager). The engine ticks it right before dispatching
+/// [`Update`](crate::event::event_types::Update). Tasks can be scheduled and cancelled from within callbacks.
+///
+/// Tasks are only run when the scheduler ticks, so they are quantized to frames: a task runs on the first
+/// frame at or after its due time, never before it.
+pub struct Scheduler {
+    time: Cell<Duration>,
+    unscaled_time: Cell<Duration>,
+    next_id: Cell<u64>,
+    tasks: RefCell<Vec<Task>>,
+    added_queue: RefCell<Vec<Task>>,
+    cancelled_queue: RefCell<Vec<TaskId>>,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self {
+            time: Cell::new(Duration::ZERO),
+            unscaled_time: Cell::new(Duration::ZERO),
+            next_id: Cell::new(0),
+            tasks: Vec::new().into(),
+            added_queue: Vec::new().into(),
+            cancelled_queue: Vec::new().into(),
+        }
+    }
+
+    /// Calls the callback once, after the delay.
+    pub fn after(&self, delay: Duration, callback: impl FnOnce() + 'static) -> TaskId {
+        self.add_task(
+            TaskClock::Scaled,
+            delay,
+            delay,
+            TaskCallback::Once(Some(Box::new(callback))),
+        )
+    }
+
+    /// Same as [`Scheduler::after`], but on the unscaled time; it keeps running while the game is paused.
+    pub fn after_unscaled(&self, delay: Duration, callback: impl FnOnce() + 'static) -> TaskId {
+        self.add_task(
+            TaskClock::Unscaled,
+            delay,
+            delay,
+            TaskCallback::Once(Some(Box::new(callback))),
+        )
+    }
+
+    /// Calls the callback every interval until it returns [`ControlFlowDecision::Stop`] or is cancelled.
+    /// The first call is after one interval. If a tick spans several intervals, the callback is called once
+    /// for each of them.
+    pub fn every(
+        &self,
+        interval: Duration,
+        callback: impl FnMut() -> ControlFlowDecision + 'static,
+    ) -> TaskId {
+        self.add_task(
+            TaskClock::Scaled,
+            interval,
+            interval,
+            TaskCallback::Repeat(Some(Box::new(callback))),
+        )
+    }
+
+    /// Same as [`Scheduler::every`], but on the unscaled time; it keeps running while the game is paused.
+    pub fn every_unscaled(
+        &self,
+        interval: Duration,
+        callback: impl FnMut() -> ControlFlowDecision + 'static,
+    ) -> TaskId {
+        self.add_task(
+            TaskClock::Unscaled,
+            interval,
+            interval,
+            TaskCallback::Repeat(Some(Box::new(callback))),
+        )
+    }
+
+    /// Calls the callback once, on the next tick. A callback scheduled from within a tick runs on the tick after it.
+    pub fn next_frame(&self, callback: impl FnOnce() + 'static) -> TaskId {
+        self.add_task(
+            TaskClock::Unscaled,
+            Duration::ZERO,
+            Duration::ZERO,
+            TaskCallback::Once(Some(Box::new(callback))),
+        )
+    }
+
+    /// Cancels the task. It does nothing if the task has already finished.
+    pub fn cancel(&self, task_id: TaskId) {
+        match self.tasks.try_borrow_mut() {
+            Ok(mut tasks) => {
+                tasks.retain(|task| task.id != task_id);
+                self.added_queue
+                    .borrow_mut()
+                    .retain(|task| task.id != task_id);
+            }
+            Err(_) => {
+                self.cancelled_queue.borrow_mut().push(task_id);
+            }
+        }
+    }
+
+    /// Advances the scaled and unscaled time and calls the callbacks of the tasks that are due.
+    pub fn tick(&self, dt: Duration, unscaled_dt: Duration) {
+        let mut tasks = if let Ok(tasks) = self.tasks.try_borrow_mut() {
+            tasks
+        } else {
+            return;
+        };
+
+        let time = self.time.get() + dt;
+        self.time.set(time);
+        let unscaled_time = self.unscaled_time.get() + unscaled_dt;
+        self.unscaled_time.set(unscaled_time);
+
+        for task in tasks.iter_mut() {
+            let time = match task.clock {
+                TaskClock::Scaled => time,
+                TaskClock::Unscaled => unscaled_time,
+            };
+
+            while task.due <= time && !self.cancelled_queue.borrow().contains(&task.id) {
+                match &mut task.callback {
+                    TaskCallback::Once(callback) => {
+                        if let Some(callback) = callback.take() {
+                            callback();
+                        }
+
+                        break;
+                    }
+                    TaskCallback::Repeat(repeat) => {
+                        let decision = match repeat {
+                            Some(callback) => callback(),
+                            None => ControlFlowDecision::Stop,
+                        };
+
+                        if decision == ControlFlowDecision::Stop {
+                            *repeat = None;
+                            break;
+                        }
+                    }
+                }
+
+                task.due += task.interval;
+
+                // A zero interval would never catch up; call it once per tick instead.
+                if task.interval.is_zero() {
+                    break;
+                }
+            }
+        }
+
+        let cancelled = std::mem::take(&mut *self.cancelled_queue.borrow_mut());
+        tasks.retain(|task| !task.callback.is_finished() && !cancelled.contains(&task.id));
+
+        let mut added_queue = self.added_queue.borrow_mut();
+        added_queue.retain(|task| !cancelled.contains(&task.id));
+        tasks.extend(added_queue.drain(..));
+    }
+
+    fn add_task(
+        &self,
+        clock: TaskClock,
+        delay: Duration,
+        interval: Duration,
+        callback: TaskCallback,
+    ) -> TaskId {
+        let id = TaskId(self.next_id.get());
+        self.next_id.set(id.0 + 1);
+
+        let now = match clock {
+            TaskClock::Scaled => self.time.get(),
+            TaskClock::Unscaled => self.unscaled_time.get(),
+        };
+        let task = Task {
+            id,
+            clock,
+            due: now + delay,
+            interval,
+            callback,
+        };
+
+        match self.tasks.try_borrow_mut() {
+            Ok(mut tasks) => tasks.push(task),
+            Err(_) => self.added_queue.borrow_mut().push(task),
+        }
+
+        id
+    }
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn tasks_fire_at_the_right_times() {
+        let scheduler = Scheduler::new();
+        let once = Rc::new(Cell::new(0));
+        let repeat = Rc::new(Cell::new(0));
+
+        scheduler.after(Duration::from_secs(2), {
+            let once = once.clone();
+            move || once.set(once.get() + 1)
+        });
+        let repeat_task = scheduler.every(Duration::from_millis(500), {
+            let repeat = repeat.clone();
+            move || {
+                repeat.set(repeat.get() + 1);
+                ControlFlowDecision::Continue
+            }
+        });
+
+        let frame = Duration::from_millis(100);
+
+        for _ in 0..19 {
+            scheduler.tick(frame, frame);
+        }
+
+        assert_eq!(once.get(), 0);
+        assert_eq!(repeat.get(), 3);
+
+        scheduler.tick(frame, frame);
+        assert_eq!(once.get(), 1);
+        assert_eq!(repeat.get(), 4);
+
+        // A long frame catches up on all the missed intervals.
+        scheduler.tick(Duration::from_millis(1000), Duration::from_millis(1000));
+        assert_eq!(once.get(), 1);
+        assert_eq!(repeat.get(), 6);
+
+        scheduler.cancel(repeat_task);
+        scheduler.tick(Duration::from_secs(5), Duration::from_secs(5));
+        assert_eq!(repeat.get(), 6);
+    }
+
+    #[test]
+    fn callbacks_can_schedule_and_cancel_tasks() {
+        let scheduler = Rc::new(Scheduler::new());
+        let count = Rc::new(Cell::new(0));
+        let task = Rc::new(Cell::new(None));
+
+        task.set(Some(scheduler.every(Duration::from_secs(1), {
+            let scheduler = scheduler.clone();
+            let count = count.clone();
+            let task = task.clone();
+            move || {
+                count.set(count.get() + 1);
+
+                if count.get() == 2 {
+                    scheduler.cancel(task.get().unwrap());
+                    scheduler.after(Duration::from_secs(1), {
+                        let count = count.clone();
+                        move || count.set(count.get() + 10)
+                    });
+                }
+
+                ControlFlowDecision::Continue
+            }
+        })));
+
+        for _ in 0..5 {
+            scheduler.tick(Duration::from_secs(1), Duration::from_secs(1));
+        }
+
+        assert_eq!(count.get(), 12);
+    }
+
+    #[test]
+    fn tasks_are_quantized_to_frames_with_odd_increments() {
+        let scheduler = Rc::new(Scheduler::new());
+        let fired_at = Rc::new(RefCell::new(Vec::new()));
+        let unscaled_fired_at = Rc::new(Cell::new(None));
+        let next_frame = Rc::new(Cell::new(0));
+        let clock = Rc::new(Cell::new(Duration::ZERO));
+
+        scheduler.every(Duration::from_millis(500), {
+            let fired_at = fired_at.clone();
+            let clock = clock.clone();
+            move || {
+                fired_at.borrow_mut().push(clock.get());
+
+                if fired_at.borrow().len() == 3 {
+                    ControlFlowDecision::Stop
+                } else {
+                    ControlFlowDecision::Continue
+                }
+            }
+        });
+        scheduler.after_unscaled(Duration::from_millis(500), {
+            let unscaled_fired_at = unscaled_fired_at.clone();
+            let clock = clock.clone();
+            move || unscaled_fired_at.set(Some(clock.get()))
+        });
+        scheduler.next_frame({
+            let scheduler = scheduler.clone();
+            let next_frame = next_frame.clone();
+            move || {
+                next_frame.set(next_frame.get() + 1);
+
+                // Scheduled re-entrantly; it runs on the tick after this one.
+                scheduler.next_frame({
+                    let next_frame = next_frame.clone();
+                    move || next_frame.set(next_frame.get() + 10)
+                });
+            }
+        });
+
+        // Frames of 170ms, while the game runs at half speed.
+        let frame = Duration::from_millis(170);
+
+        for tick in 0..20 {
+            clock.set(clock.get() + frame);
+            scheduler.tick(frame / 2, frame);
+
+            match tick {
+                0 => assert_eq!(next_frame.get(), 1),
+                _ => assert_eq!(next_frame.get(), 11),
+            }
+        }
+
+        // Each call is on the first frame at or after its due time, and the task stops after three calls.
+        let millis = Vec::from_iter(fired_at.borrow().iter().map(|time| time.as_millis()));
+        assert_eq!(millis, vec![1020, 2040, 3060]);
+        assert_eq!(unscaled_fired_at.get(), Some(Duration::from_millis(510)));
+    }
+}
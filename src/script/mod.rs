@@ -0,0 +1,189 @@
+use crate::object::ObjectHandle;
+use asset::assets::Script;
+use logging::log_error;
+use mlua::{IntoLua, IntoLuaMulti, Lua, RegistryKey, Table, Value};
+use specs::Entity;
+use std::{cell::RefCell, collections::HashMap, rc::Rc, sync::Arc};
+
+mod script_bindings;
+mod script_component;
+
+pub use script_component::*;
+
+/// A script evaluated for an entity. The table is the value returned by the script.
+struct ScriptInstance {
+    script: Script,
+    table: RegistryKey,
+}
+
+/// Owns the Lua state and the script instances of all entities that have a [`ScriptComponent`].
+/// Errors raised by scripts are reported to the global logger instead of being propagated.
+pub struct ScriptManager {
+    lua: Lua,
+    instances: RefCell<HashMap<Entity, Rc<ScriptInstance>>>,
+    subscribers: RefCell<HashMap<String, Vec<Rc<RegistryKey>>>>,
+}
+
+impl ScriptManager {
+    pub fn new() -> Self {
+        let lua = Lua::new();
+        script_bindings::register_globals(&lua).unwrap();
+
+        Self {
+            lua,
+            instances: HashMap::new().into(),
+            subscribers: HashMap::new().into(),
+        }
+    }
+
+    pub fn lua(&self) -> &Lua {
+        &self.lua
+    }
+
+    /// Updates the instance of the given entity, evaluating the script and calling `on_init` first if the
+    /// entity has no instance yet or its script has been replaced.
+    pub fn update_instance(&self, entity: Entity, object: ObjectHandle, script: &Script, dt: f32) {
+        let instance = self.instances.borrow().get(&entity).cloned();
+        let instance = match instance {
+            Some(instance) if Arc::ptr_eq(&instance.script, script) => instance,
+            _ => match self.create_instance(entity, object, script) {
+                Some(instance) => instance,
+                None => return,
+            },
+        };
+
+        self.call_instance(&instance, "on_update", dt);
+    }
+
+    /// Drops the instances of the entities that no longer have a script.
+    pub fn retain_instances(&self, mut f: impl FnMut(Entity) -> bool) {
+        self.instances.borrow_mut().retain(|&entity, _| f(entity));
+    }
+
+    /// Dispatches a named event to the functions subscribed through `events.subscribe` and to `on_event` of
+    /// all instances.
+    pub fn emit<'lua>(&'lua self, name: &str, payload: impl IntoLua<'lua>) {
+        let payload = match payload.into_lua(&self.lua) {
+            Ok(payload) => payload,
+            Err(err) => {
+                self.report(&err);
+                return;
+            }
+        };
+        let subscribers = self
+            .subscribers
+            .borrow()
+            .get(name)
+            .cloned()
+            .unwrap_or_default();
+
+        for subscriber in subscribers {
+            self.call_handler(&subscriber, payload.clone());
+        }
+
+        let instances = Vec::from_iter(self.instances.borrow().values().cloned());
+
+        for instance in instances {
+            self.call_instance(&instance, "on_event", (name, payload.clone()));
+        }
+    }
+
+    /// Replaces the script of all components that use a script with the same key, e.g. when its file is
+    /// modified. The instances are re-evaluated and `on_init` is called again on the next update.
+    pub fn reload(&self, world: &specs::World, script: Script) {
+        use specs::{Join, WorldExt};
+
+        let mut components = world.write_component::<ScriptComponent>();
+
+        for component in (&mut components).join() {
+            if component.script.key() == script.key() {
+                component.script = script.clone();
+            }
+        }
+    }
+
+    pub(crate) fn add_subscriber(&self, name: String, handler: Rc<RegistryKey>) {
+        self.subscribers
+            .borrow_mut()
+            .entry(name)
+            .or_default()
+            .push(handler);
+    }
+
+    pub(crate) fn call_handler<'lua>(
+        &'lua self,
+        handler: &RegistryKey,
+        args: impl IntoLuaMulti<'lua>,
+    ) {
+        let result = self
+            .lua
+            .registry_value::<mlua::Function>(handler)
+            .and_then(|handler| handler.call::<_, ()>(args));
+
+        if let Err(err) = result {
+            self.report(&err);
+        }
+    }
+
+    fn create_instance(
+        &self,
+        entity: Entity,
+        object: ObjectHandle,
+        script: &Script,
+    ) -> Option<Rc<ScriptInstance>> {
+        let result = self
+            .lua
+            .load(script.source())
+            .set_name(format!("={}", script.name()))
+            .eval::<Table>()
+            .and_then(|table| {
+                table.set("object", object)?;
+                self.lua.create_registry_value(table)
+            });
+        let instance = match result {
+            Ok(table) => Rc::new(ScriptInstance {
+                script: script.clone(),
+                table,
+            }),
+            Err(err) => {
+                self.report(&err);
+                // Drops the previous instance, so that a broken script does not keep running its old version.
+                self.instances.borrow_mut().remove(&entity);
+                return None;
+            }
+        };
+
+        self.instances.borrow_mut().insert(entity, instance.clone());
+        self.call_instance(&instance, "on_init", ());
+
+        Some(instance)
+    }
+
+    fn call_instance<'lua>(
+        &'lua self,
+        instance: &ScriptInstance,
+        name: &str,
+        args: impl IntoLuaMulti<'lua>,
+    ) {
+        let result =
+            self.lua
+                .registry_value::<Table>(&instance.table)
+                .and_then(|table| match table.get::<_, Value>(name)? {
+                    Value::Function(function) => {
+                        let mut args = args.into_lua_multi(&self.lua)?;
+                        args.push_front(Value::Table(table));
+                        function.call::<_, ()>(args)
+                    }
+                    _ => Ok(()),
+                });
+
+        if let Err(err) = result {
+            self.report(&err);
+        }
+    }
+
+    /// Logs the error. Lua errors start with the script name and the line, e.g. `rotate.lua:3: ...`.
+    fn report(&self, err: &mlua::Error) {
+        log_error!("script error: {}", err);
+    }
+}
@@ -0,0 +1,384 @@
+use crate::{
+    event::{event_types, EventHandler},
+    gfx::Color,
+    input::{InputDevice, RawInput},
+    math::{Quat, Vec2, Vec3},
+    object::ObjectHandle,
+    transform::TransformComponent,
+    use_context,
+};
+use mlua::{
+    FromLua, Function, Lua, MetaMethod, Result as LuaResult, UserData, UserDataFields,
+    UserDataMethods, Value,
+};
+use std::rc::Rc;
+
+/// Registers all engine types and modules as globals.
+pub(super) fn register_globals(lua: &Lua) -> LuaResult<()> {
+    let globals = lua.globals();
+
+    let vec2 = lua.create_table()?;
+    vec2.set(
+        "new",
+        lua.create_function(|_, (x, y): (f32, f32)| Ok(Vec2::new(x, y)))?,
+    )?;
+    vec2.set(
+        "dot",
+        lua.create_function(|_, (lhs, rhs): (Vec2, Vec2)| Ok(Vec2::dot(lhs, rhs)))?,
+    )?;
+    globals.set("Vec2", vec2)?;
+
+    let vec3 = lua.create_table()?;
+    vec3.set(
+        "new",
+        lua.create_function(|_, (x, y, z): (f32, f32, f32)| Ok(Vec3::new(x, y, z)))?,
+    )?;
+    vec3.set(
+        "dot",
+        lua.create_function(|_, (lhs, rhs): (Vec3, Vec3)| Ok(Vec3::dot(lhs, rhs)))?,
+    )?;
+    vec3.set(
+        "cross",
+        lua.create_function(|_, (lhs, rhs): (Vec3, Vec3)| Ok(Vec3::cross(lhs, rhs)))?,
+    )?;
+    vec3.set(
+        "lerp",
+        lua.create_function(|_, (from, to, t): (Vec3, Vec3, f32)| Ok(Vec3::lerp(from, to, t)))?,
+    )?;
+    globals.set("Vec3", vec3)?;
+
+    let quat = lua.create_table()?;
+    quat.set("identity", Quat::IDENTITY)?;
+    quat.set(
+        "from_eular",
+        lua.create_function(|_, (x, y, z): (f32, f32, f32)| Ok(Quat::from_eular(x, y, z)))?,
+    )?;
+    quat.set(
+        "from_axis_angle",
+        lua.create_function(|_, (axis, angle): (Vec3, f32)| {
+            Ok(Quat::from_axis_angle(axis.normalized(), angle))
+        })?,
+    )?;
+    globals.set("Quat", quat)?;
+
+    let color = lua.create_table()?;
+    color.set(
+        "new",
+        lua.create_function(|_, (r, g, b, a): (f32, f32, f32, Option<f32>)| {
+            Ok(Color::from_rgba(r, g, b, a.unwrap_or(1.0)))
+        })?,
+    )?;
+    color.set(
+        "parse_hex",
+        lua.create_function(|_, hex: String| Color::parse_hex(hex).map_err(mlua::Error::external))?,
+    )?;
+    globals.set("Color", color)?;
+
+    let input = lua.create_table()?;
+    input.set(
+        "key",
+        lua.create_function(|_, name: String| {
+            let input_mgr = use_context().input_mgr();
+            Ok(input_value(input_mgr.keyboard().input(&name)))
+        })?,
+    )?;
+    input.set(
+        "mouse",
+        lua.create_function(|_, name: String| {
+            let input_mgr = use_context().input_mgr();
+            Ok(input_value(input_mgr.mouse().input(&name)))
+        })?,
+    )?;
+    globals.set("input", input)?;
+
+    let events = lua.create_table()?;
+    events.set("subscribe", lua.create_function(subscribe)?)?;
+    events.set(
+        "emit",
+        lua.create_function(|_, (name, payload): (String, Value)| {
+            use_context().script_mgr().emit(&name, payload);
+            Ok(())
+        })?,
+    )?;
+    globals.set("events", events)?;
+
+    let objects = lua.create_table()?;
+    objects.set(
+        "find",
+        lua.create_function(|_, name: String| Ok(use_context().object_mgr().find(&name)))?,
+    )?;
+    globals.set("objects", objects)?;
+
+    Ok(())
+}
+
+/// Returns zero for unknown inputs, so that scripts do not need to check them.
+fn input_value(input: Option<&RawInput>) -> f32 {
+    input.map_or(0.0, |input| input.value)
+}
+
+/// Subscribes a function to an event. `update` and `late_update` are forwarded from the
+/// [`EventManager`](crate::event::EventManager); the other names are emitted by scripts.
+fn subscribe(lua: &Lua, (name, handler): (String, Function)) -> LuaResult<()> {
+    let handler = Rc::new(lua.create_registry_value(handler)?);
+    let ctx = use_context();
+
+    match name.as_str() {
+        "update" => {
+            ctx.event_mgr()
+                .add_handler(EventHandler::new(move |_: &event_types::Update| {
+                    use_context().script_mgr().call_handler(&handler, ());
+                }))
+        }
+        "late_update" => {
+            ctx.event_mgr()
+                .add_handler(EventHandler::new(move |_: &event_types::LateUpdate| {
+                    use_context().script_mgr().call_handler(&handler, ());
+                }))
+        }
+        _ => ctx.script_mgr().add_subscriber(name, handler),
+    }
+
+    Ok(())
+}
+
+macro_rules! impl_from_lua_for_userdata {
+    ($ty:ty, $name:literal) => {
+        impl<'lua> FromLua<'lua> for $ty {
+            fn from_lua(value: Value<'lua>, _lua: &'lua Lua) -> LuaResult<Self> {
+                match value {
+                    Value::UserData(userdata) => Ok(userdata.borrow::<Self>()?.clone()),
+                    _ => Err(mlua::Error::FromLuaConversionError {
+                        from: value.type_name(),
+                        to: $name,
+                        message: None,
+                    }),
+                }
+            }
+        }
+    };
+}
+
+impl_from_lua_for_userdata!(Vec2, "Vec2");
+impl_from_lua_for_userdata!(Vec3, "Vec3");
+impl_from_lua_for_userdata!(Quat, "Quat");
+impl_from_lua_for_userdata!(Color, "Color");
+impl_from_lua_for_userdata!(ObjectHandle, "ObjectHandle");
+
+impl UserData for Vec2 {
+    fn add_fields<'lua, F: UserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_field_method_get("x", |_, this| Ok(this.x));
+        fields.add_field_method_get("y", |_, this| Ok(this.y));
+        fields.add_field_method_set("x", |_, this, value| {
+            this.x = value;
+            Ok(())
+        });
+        fields.add_field_method_set("y", |_, this, value| {
+            this.y = value;
+            Ok(())
+        });
+    }
+
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("len", |_, this, ()| Ok(this.len()));
+        methods.add_method("normalized", |_, this, ()| Ok(this.normalized()));
+        methods.add_meta_method(MetaMethod::Add, |_, this, rhs: Vec2| Ok(*this + rhs));
+        methods.add_meta_method(MetaMethod::Sub, |_, this, rhs: Vec2| Ok(*this - rhs));
+        methods.add_meta_method(MetaMethod::Mul, |_, this, rhs: f32| Ok(*this * rhs));
+        methods.add_meta_method(MetaMethod::Div, |_, this, rhs: f32| Ok(*this / rhs));
+        methods.add_meta_method(MetaMethod::Unm, |_, this, ()| Ok(-*this));
+        methods.add_meta_method(MetaMethod::Eq, |_, this, rhs: Vec2| Ok(*this == rhs));
+        methods.add_meta_method(MetaMethod::ToString, |_, this, ()| Ok(this.to_string()));
+    }
+}
+
+impl UserData for Vec3 {
+    fn add_fields<'lua, F: UserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_field_method_get("x", |_, this| Ok(this.x));
+        fields.add_field_method_get("y", |_, this| Ok(this.y));
+        fields.add_field_method_get("z", |_, this| Ok(this.z));
+        fields.add_field_method_set("x", |_, this, value| {
+            this.x = value;
+            Ok(())
+        });
+        fields.add_field_method_set("y", |_, this, value| {
+            this.y = value;
+            Ok(())
+        });
+        fields.add_field_method_set("z", |_, this, value| {
+            this.z = value;
+            Ok(())
+        });
+    }
+
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("len", |_, this, ()| Ok(this.len()));
+        methods.add_method("normalized", |_, this, ()| Ok(this.normalized()));
+        methods.add_meta_method(MetaMethod::Add, |_, this, rhs: Vec3| Ok(*this + rhs));
+        methods.add_meta_method(MetaMethod::Sub, |_, this, rhs: Vec3| Ok(*this - rhs));
+        methods.add_meta_method(MetaMethod::Mul, |_, this, rhs: f32| Ok(*this * rhs));
+        methods.add_meta_method(MetaMethod::Div, |_, this, rhs: f32| Ok(*this / rhs));
+        methods.add_meta_method(MetaMethod::Unm, |_, this, ()| Ok(-*this));
+        methods.add_meta_method(MetaMethod::Eq, |_, this, rhs: Vec3| Ok(*this == rhs));
+        methods.add_meta_method(MetaMethod::ToString, |_, this, ()| Ok(this.to_string()));
+    }
+}
+
+impl UserData for Quat {
+    fn add_fields<'lua, F: UserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_field_method_get("x", |_, this| Ok(this.x));
+        fields.add_field_method_get("y", |_, this| Ok(this.y));
+        fields.add_field_method_get("z", |_, this| Ok(this.z));
+        fields.add_field_method_get("w", |_, this| Ok(this.w));
+    }
+
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("normalized", |_, this, ()| Ok(this.normalized()));
+        methods.add_method("inverted", |_, this, ()| Ok(this.inverted()));
+        methods.add_method("into_eular", |_, this, ()| Ok(this.into_eular()));
+        methods.add_method("rotate", |_, this, vector: Vec3| Ok(*this * vector));
+        methods.add_meta_method(MetaMethod::Mul, |_, this, rhs: Quat| Ok(*this * rhs));
+        methods.add_meta_method(MetaMethod::Eq, |_, this, rhs: Quat| Ok(*this == rhs));
+        methods.add_meta_method(MetaMethod::ToString, |_, this, ()| Ok(this.to_string()));
+    }
+}
+
+impl UserData for Color {
+    fn add_fields<'lua, F: UserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_field_method_get("r", |_, this| Ok(this.r));
+        fields.add_field_method_get("g", |_, this| Ok(this.g));
+        fields.add_field_method_get("b", |_, this| Ok(this.b));
+        fields.add_field_method_get("a", |_, this| Ok(this.a));
+        fields.add_field_method_set("r", |_, this, value| {
+            this.r = value;
+            Ok(())
+        });
+        fields.add_field_method_set("g", |_, this, value| {
+            this.g = value;
+            Ok(())
+        });
+        fields.add_field_method_set("b", |_, this, value| {
+            this.b = value;
+            Ok(())
+        });
+        fields.add_field_method_set("a", |_, this, value| {
+            this.a = value;
+            Ok(())
+        });
+    }
+
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_meta_method(MetaMethod::Mul, |_, this, rhs: Color| Ok(*this * rhs));
+        methods.add_meta_method(MetaMethod::Eq, |_, this, rhs: Color| Ok(*this == rhs));
+        methods.add_meta_method(MetaMethod::ToString, |_, this, ()| Ok(this.to_string()));
+    }
+}
+
+impl UserData for ObjectHandle {
+    fn add_fields<'lua, F: UserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_field_method_get("name", |_, this| Ok(this.name()));
+        fields.add_field_method_get("active", |_, this| Ok(this.is_active()));
+        fields.add_field_method_get("parent", |_, this| Ok(this.parent()));
+        fields.add_field_method_get("transform", |_, this| {
+            Ok(this.component::<TransformComponent>())
+        });
+        fields.add_field_method_set("name", |_, this, name: Option<String>| {
+            this.set_name(name);
+            Ok(())
+        });
+        fields.add_field_method_set("active", |_, this, active| {
+            this.set_active(active);
+            Ok(())
+        });
+    }
+
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("children", |_, this, ()| Ok(this.direct_children()));
+        methods.add_method("remove", |_, this, ()| {
+            this.remove();
+            Ok(())
+        });
+        methods.add_meta_method(
+            MetaMethod::Eq,
+            |_, this, rhs: ObjectHandle| Ok(*this == rhs),
+        );
+    }
+}
+
+impl UserData for TransformComponent {
+    fn add_fields<'lua, F: UserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_field_method_get("position", |_, this| Ok(this.position()));
+        fields.add_field_method_get("rotation", |_, this| Ok(this.rotation()));
+        fields.add_field_method_get("scale", |_, this| Ok(this.scale()));
+        fields.add_field_method_get("world_position", |_, this| Ok(this.world_position()));
+        fields.add_field_method_get("world_rotation", |_, this| Ok(this.world_rotation()));
+        fields.add_field_method_get("world_scale", |_, this| Ok(this.world_scale()));
+        fields.add_field_method_set("position", |_, this, position| {
+            this.set_position(position);
+            Ok(())
+        });
+        fields.add_field_method_set("rotation", |_, this, rotation| {
+            this.set_rotation(rotation);
+            Ok(())
+        });
+        fields.add_field_method_set("scale", |_, this, scale| {
+            this.set_scale(scale);
+            Ok(())
+        });
+        fields.add_field_method_set("world_position", |_, this, position| {
+            this.set_world_position(position);
+            Ok(())
+        });
+        fields.add_field_method_set("world_rotation", |_, this, rotation| {
+            this.set_world_rotation(rotation);
+            Ok(())
+        });
+        fields.add_field_method_set("world_scale", |_, this, scale| {
+            this.set_world_scale(scale);
+            Ok(())
+        });
+    }
+
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("forward", |_, this, ()| Ok(this.forward()));
+        methods.add_method("right", |_, this, ()| Ok(this.right()));
+        methods.add_method("up", |_, this, ()| Ok(this.up()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn math_types_round_trip_through_lua() {
+        let lua = Lua::new();
+        register_globals(&lua).unwrap();
+
+        let vector = lua
+            .load("return (Vec3.new(1, 2, 3) + Vec3.new(1, 1, 1)) * 2")
+            .eval::<Vec3>()
+            .unwrap();
+        assert_eq!(vector, Vec3::new(4.0, 6.0, 8.0));
+
+        let rotation = lua
+            .load("return Quat.from_axis_angle(Vec3.new(0, 0, 2), math.pi)")
+            .eval::<Quat>()
+            .unwrap();
+        assert!(
+            (Quat::dot(
+                rotation,
+                Quat::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), std::f32::consts::PI)
+            ) - 1.0)
+                .abs()
+                < 1e-6
+        );
+
+        let err = lua
+            .load("local v = Vec3.new(1, 2, 3)\nreturn v + 1")
+            .set_name("=test.lua")
+            .eval::<Vec3>()
+            .unwrap_err();
+        assert!(err.to_string().contains("test.lua:2:"));
+    }
+}
@@ -0,0 +1,38 @@
+use crate::{
+    asset::{GfxBridgeImpl, PipelineGfxBridgeImpl},
+    ContextHandle,
+};
+use asset::{assets::Script, AssetKey};
+use asset_loader::{asset_loaders::RuntimeAssetLoader, AssetDatabase, AssetLoadError, AssetLoader};
+use codegen::Component;
+
+/// Runs a script for the object. The script must return a table, which may define `on_init(self)`,
+/// `on_update(self, dt)` and `on_event(self, name, payload)`. The object is available as `self.object`.
+#[derive(Clone, Component)]
+#[auto_register]
+pub struct ScriptComponent {
+    pub script: Script,
+}
+
+impl ScriptComponent {
+    pub fn new(script: Script) -> Self {
+        Self { script }
+    }
+}
+
+/// Loads a script from the given path through the asset pipeline, without registering it to a database.
+pub fn load_script(ctx: &ContextHandle, path: impl Into<String>) -> Result<Script, AssetLoadError> {
+    let loader = RuntimeAssetLoader::new(
+        GfxBridgeImpl::new(ctx.clone()),
+        PipelineGfxBridgeImpl::new(ctx.clone()),
+    );
+    let asset = loader.load_asset(&AssetKey::Path(path.into()), &AssetDatabase::new(""))?;
+
+    // The type of the asset is deduced from the extension.
+    match asset.as_script() {
+        Some(script) => Ok(script.clone()),
+        None => Err(AssetLoadError::LoadError(asset::AssetLoadError::Other(
+            format!("not a script: {}", asset.ty()),
+        ))),
+    }
+}
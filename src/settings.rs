@@ -0,0 +1,345 @@
+use crate::{time::Instant, EngineTargetFps};
+use directories::ProjectDirs;
+use logging::log_warn;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use thiserror::Error;
+use toml::{Table, Value};
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+
+/// Changes are written to disk once no other change has been made for this long.
+pub const SETTINGS_SAVE_DELAY: Duration = Duration::from_secs(1);
+/// The section of the keys owned by the engine, besides the window geometry.
+pub const ENGINE_SECTION: &str = "engine";
+/// The section of the window geometry. See [`WindowSettings`].
+pub const WINDOW_SECTION: &str = "window";
+/// The part of a window that must be on a monitor for its saved position to be restored, in physical pixels.
+const MIN_VISIBLE_WINDOW_SIZE: u32 = 64;
+
+#[derive(Error, Debug)]
+pub enum SettingsError {
+    #[error("io error: {0}")]
+    IOError(#[from] std::io::Error),
+    #[error("failed to serialize settings: {0}")]
+    SerializeError(#[from] toml::ser::Error),
+}
+
+/// The geometry of the window, in physical pixels. The position is the outer position of the window, and
+/// the size is its inner size. The position and size are those of the window when it was last neither
+/// maximized nor fullscreen.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WindowSettings {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    #[serde(default)]
+    pub is_maximized: bool,
+    #[serde(default)]
+    pub is_fullscreen: bool,
+}
+
+impl WindowSettings {
+    pub fn position(&self) -> PhysicalPosition<i32> {
+        PhysicalPosition::new(self.x, self.y)
+    }
+
+    pub fn size(&self) -> PhysicalSize<u32> {
+        PhysicalSize::new(self.width, self.height)
+    }
+
+    /// Returns whether enough of the window is on one of the monitors, given as their positions and sizes,
+    /// for the user to grab its title bar.
+    pub fn is_on_monitors(
+        &self,
+        monitors: impl IntoIterator<Item = (PhysicalPosition<i32>, PhysicalSize<u32>)>,
+    ) -> bool {
+        let min_visible = MIN_VISIBLE_WINDOW_SIZE as i64;
+        let (x, y, width) = (self.x as i64, self.y as i64, self.width as i64);
+
+        monitors.into_iter().any(|(position, size)| {
+            let (left, top) = (position.x as i64, position.y as i64);
+            let (right, bottom) = (left + size.width as i64, top + size.height as i64);
+            let visible_width = (x + width).min(right) - x.max(left);
+
+            min_visible <= visible_width && top <= y && y + min_visible <= bottom
+        })
+    }
+}
+
+/// Keeps settings that persist across runs, such as the window geometry and user preferences, in a TOML file.
+///
+/// Settings are grouped in sections: [`ENGINE_SECTION`] and [`WINDOW_SECTION`] are owned by the engine, and
+/// applications may use any other section. Changes are saved by [`update`](Self::update) once
+/// [`SETTINGS_SAVE_DELAY`] has passed without further changes; the engine calls it every frame and saves
+/// the settings when the window is closed.
+///
+/// A file that cannot be parsed is renamed aside with a `.corrupt` extension and the defaults are used.
+pub struct SettingsManager {
+    path: Option<PathBuf>,
+    table: Table,
+    changed_at: Option<Instant>,
+}
+
+impl SettingsManager {
+    /// Creates settings that are never written to disk.
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            table: Table::new(),
+            changed_at: None,
+        }
+    }
+
+    /// Loads the settings of the application from `settings.toml` in its platform config directory, e.g.
+    /// `~/.config/<app_name>` on Linux. The settings are kept in memory if there is no such directory.
+    pub fn for_app(app_name: &str) -> Self {
+        match ProjectDirs::from("", "", app_name) {
+            Some(dirs) => Self::load(dirs.config_dir().join("settings.toml")),
+            None => {
+                log_warn!(
+                    "no config directory is available for `{}`; settings will not be saved",
+                    app_name
+                );
+                Self::in_memory()
+            }
+        }
+    }
+
+    /// Loads the settings from the file, which is created on the first save if it does not exist.
+    pub fn load(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let table = match std::fs::read_to_string(&path) {
+            Ok(content) => match content.parse::<Table>() {
+                Ok(table) => table,
+                Err(err) => {
+                    let corrupt_path = path.with_extension("toml.corrupt");
+                    log_warn!(
+                        "failed to parse settings `{}`: {}; it is moved to `{}` and the defaults are used",
+                        path.display(),
+                        err,
+                        corrupt_path.display()
+                    );
+
+                    if let Err(err) = std::fs::rename(&path, &corrupt_path) {
+                        log_warn!(
+                            "failed to move settings `{}` aside: {}",
+                            path.display(),
+                            err
+                        );
+                    }
+
+                    Table::new()
+                }
+            },
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Table::new(),
+            Err(err) => {
+                log_warn!(
+                    "failed to read settings `{}`: {}; the defaults are used",
+                    path.display(),
+                    err
+                );
+                Table::new()
+            }
+        };
+
+        Self {
+            path: Some(path),
+            table,
+            changed_at: None,
+        }
+    }
+
+    /// Returns the file that the settings are saved to, if any.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Returns whether there are changes that have not been saved yet.
+    pub fn is_dirty(&self) -> bool {
+        self.changed_at.is_some()
+    }
+
+    /// Returns the value of the key, or `None` if it is missing or has a different type.
+    pub fn get<T: DeserializeOwned>(&self, section: &str, key: &str) -> Option<T> {
+        self.table
+            .get(section)?
+            .as_table()?
+            .get(key)?
+            .clone()
+            .try_into()
+            .ok()
+    }
+
+    pub fn get_or<T: DeserializeOwned>(&self, section: &str, key: &str, default: T) -> T {
+        self.get(section, key).unwrap_or(default)
+    }
+
+    /// Sets the value of the key. It is saved after [`SETTINGS_SAVE_DELAY`] unless it is unchanged.
+    pub fn set<T: Serialize>(
+        &mut self,
+        section: &str,
+        key: &str,
+        value: T,
+    ) -> Result<(), SettingsError> {
+        let value = Value::try_from(value)?;
+        let section = self
+            .table
+            .entry(section)
+            .or_insert_with(|| Value::Table(Table::new()));
+
+        if !section.is_table() {
+            *section = Value::Table(Table::new());
+        }
+
+        let section = section.as_table_mut().unwrap();
+
+        if section.get(key) != Some(&value) {
+            section.insert(key.to_owned(), value);
+            self.changed_at = Some(Instant::now());
+        }
+
+        Ok(())
+    }
+
+    pub fn remove(&mut self, section: &str, key: &str) {
+        let removed = self
+            .table
+            .get_mut(section)
+            .and_then(|section| section.as_table_mut())
+            .and_then(|section| section.remove(key));
+
+        if removed.is_some() {
+            self.changed_at = Some(Instant::now());
+        }
+    }
+
+    pub fn window(&self) -> Option<WindowSettings> {
+        self.table.get(WINDOW_SECTION)?.clone().try_into().ok()
+    }
+
+    pub fn set_window(&mut self, window: WindowSettings) -> Result<(), SettingsError> {
+        let value = Value::try_from(window)?;
+
+        if self.table.get(WINDOW_SECTION) != Some(&value) {
+            self.table.insert(WINDOW_SECTION.to_owned(), value);
+            self.changed_at = Some(Instant::now());
+        }
+
+        Ok(())
+    }
+
+    /// Returns the saved frame rate to pass to [`Engine::run`](crate::Engine::run).
+    pub fn target_fps(&self) -> Option<EngineTargetFps> {
+        self.get(ENGINE_SECTION, "target_fps")
+    }
+
+    pub fn set_target_fps(&mut self, target_fps: EngineTargetFps) -> Result<(), SettingsError> {
+        self.set(ENGINE_SECTION, "target_fps", target_fps)
+    }
+
+    /// Returns the saved multisample count. The renderer does not multisample yet; it is kept for applications
+    /// that offer the option.
+    pub fn msaa_samples(&self) -> Option<u32> {
+        self.get(ENGINE_SECTION, "msaa_samples")
+    }
+
+    pub fn set_msaa_samples(&mut self, samples: u32) -> Result<(), SettingsError> {
+        self.set(ENGINE_SECTION, "msaa_samples", samples)
+    }
+
+    /// Saves the changes once [`SETTINGS_SAVE_DELAY`] has passed since the last one. Errors are logged.
+    pub fn update(&mut self) {
+        let is_due = self
+            .changed_at
+            .is_some_and(|changed_at| SETTINGS_SAVE_DELAY <= changed_at.elapsed());
+
+        if !is_due {
+            return;
+        }
+
+        if let Err(err) = self.save() {
+            log_warn!("failed to save settings: {}", err);
+            // Retries after another delay instead of every frame.
+            self.changed_at = Some(Instant::now());
+        }
+    }
+
+    /// Saves the settings now. The file is replaced at once, so that it is never left half-written.
+    pub fn save(&mut self) -> Result<(), SettingsError> {
+        if let Some(path) = &self.path {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            let temp_path = path.with_extension("toml.tmp");
+            std::fs::write(&temp_path, toml::to_string_pretty(&self.table)?)?;
+            std::fs::rename(&temp_path, path)?;
+        }
+
+        self.changed_at = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn corrupt_settings_are_moved_aside() {
+        let dir = std::env::temp_dir().join(format!("r3d-settings-{}", std::process::id()));
+        let path = dir.join("settings.toml");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(&path, "[window\nx = ").unwrap();
+
+        let mut settings = SettingsManager::load(&path);
+        assert!(settings.window().is_none());
+        assert!(!path.exists());
+        assert!(dir.join("settings.toml.corrupt").exists());
+        assert_eq!(settings.get_or("app", "volume", 0.5), 0.5);
+
+        settings.set("app", "volume", 0.8).unwrap();
+        settings.set_target_fps(EngineTargetFps::VSync).unwrap();
+        assert!(settings.is_dirty());
+        settings.save().unwrap();
+        assert!(!settings.is_dirty());
+
+        let settings = SettingsManager::load(&path);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(settings.get("app", "volume"), Some(0.8));
+        assert_eq!(settings.get::<String>("app", "volume"), None);
+        assert_eq!(settings.target_fps(), Some(EngineTargetFps::VSync));
+    }
+
+    #[test]
+    fn window_must_be_on_a_monitor() {
+        let monitors = [
+            (PhysicalPosition::new(0, 0), PhysicalSize::new(1920, 1080)),
+            (
+                PhysicalPosition::new(1920, 0),
+                PhysicalSize::new(1280, 1024),
+            ),
+        ];
+        let window = |x, y| WindowSettings {
+            x,
+            y,
+            width: 800,
+            height: 600,
+            is_maximized: false,
+            is_fullscreen: false,
+        };
+
+        assert!(window(100, 100).is_on_monitors(monitors));
+        assert!(window(2500, 900).is_on_monitors(monitors));
+        // Only a sliver is left on the right of the second monitor.
+        assert!(!window(3180, 100).is_on_monitors(monitors));
+        // The title bar is above the monitors.
+        assert!(!window(100, -50).is_on_monitors(monitors));
+        // The monitor that it was on is disconnected.
+        assert!(!window(100, 100).is_on_monitors([]));
+    }
+}
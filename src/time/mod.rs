@@ -1,7 +1,30 @@
-use std::time::{Duration, Instant};
+use std::{collections::VecDeque, time::Duration};
 
+#[cfg(target_arch = "wasm32")]
+pub use instant::Instant;
+/// The clock of the engine. `std::time::Instant` panics on the web, so `performance.now()` is read there instead,
+/// as winit does; use this one in code that should run in the browser as well.
+#[cfg(not(target_arch = "wasm32"))]
+pub use std::time::Instant;
+
+/// The number of frames kept to compute the frame statistics.
+pub const FRAME_TIME_HISTORY_LEN: usize = 120;
+
+/// Keeps the clocks of the engine. There are two channels:
+///
+/// - The game time, [`time`](Self::time) and [`delta_time`](Self::delta_time), is scaled by the time scale
+///   and stops while paused. Use it for gameplay.
+/// - The UI time, [`ui_time`](Self::ui_time) and [`ui_delta_time`](Self::ui_delta_time), always advances with
+///   the real time. Use it for menus and UI animations that keep running while the game is paused.
 pub struct TimeManager {
     time_scale: f64,
+    is_paused: bool,
+    /// The step requested by [`step_frame`](Self::step_frame), taken by the next update.
+    pending_step: Option<Duration>,
+    /// Pauses (`true`) and resumes (`false`) that have not been dispatched as events yet.
+    pending_pause_events: Vec<bool>,
+    /// How far the rendered frame is between the previous and the current fixed step.
+    fixed_alpha: f32,
     time: Duration,
     base_time: Duration,
     delta_time: Duration,
@@ -9,6 +32,7 @@ pub struct TimeManager {
     initial_time: Instant,
     last_frame_time: Instant,
     last_scale_updated_time: Instant,
+    frame_times: VecDeque<Duration>,
 }
 
 impl TimeManager {
@@ -16,6 +40,10 @@ impl TimeManager {
         let now = Instant::now();
         Self {
             time_scale: 1.0,
+            is_paused: false,
+            pending_step: None,
+            pending_pause_events: Vec::new(),
+            fixed_alpha: 1.0,
             time: Duration::from_secs(0),
             base_time: Duration::from_secs(0),
             delta_time: Duration::from_secs(0),
@@ -23,6 +51,7 @@ impl TimeManager {
             initial_time: now,
             last_frame_time: now,
             last_scale_updated_time: now,
+            frame_times: VecDeque::with_capacity(FRAME_TIME_HISTORY_LEN),
         }
     }
 
@@ -30,6 +59,10 @@ impl TimeManager {
         self.time_scale
     }
 
+    pub fn is_paused(&self) -> bool {
+        self.is_paused
+    }
+
     pub fn time(&self) -> Duration {
         self.time + self.base_time
     }
@@ -46,22 +79,168 @@ impl TimeManager {
         self.unscaled_delta_time
     }
 
+    /// Returns the time of the UI channel, which is neither scaled nor paused.
+    pub fn ui_time(&self) -> Duration {
+        self.unscaled_time()
+    }
+
+    /// Returns the delta time of the UI channel, which is neither scaled nor paused.
+    pub fn ui_delta_time(&self) -> Duration {
+        self.unscaled_delta_time
+    }
+
+    /// Returns how far the rendered frame is between the previous and the current fixed step, in `[0, 1]`.
+    /// It is `1` unless a fixed-step loop sets it, so that the current transforms are rendered as they are.
+    pub fn fixed_alpha(&self) -> f32 {
+        self.fixed_alpha
+    }
+
+    /// Sets the interpolation factor used to render the
+    /// [`InterpolatedTransform`](crate::transform::InterpolatedTransform)s. Clamped to `[0, 1]`.
+    pub fn set_fixed_alpha(&mut self, fixed_alpha: f32) {
+        self.fixed_alpha = fixed_alpha.clamp(0.0, 1.0);
+    }
+
+    /// Returns the unscaled delta times of the recent frames, from the oldest to the newest.
+    pub fn frame_times(&self) -> &VecDeque<Duration> {
+        &self.frame_times
+    }
+
+    /// Returns the average frames per second over the recent frames.
+    pub fn fps(&self) -> f32 {
+        let total = self.frame_times.iter().sum::<Duration>().as_secs_f32();
+
+        if total <= 0.0 {
+            return 0.0;
+        }
+
+        self.frame_times.len() as f32 / total
+    }
+
     pub fn set_time_scale(&mut self, time_scale: f64) {
         self.time_scale = time_scale;
-        self.base_time += self.time;
-        self.time = Duration::from_secs(0);
-        self.last_scale_updated_time = self.last_frame_time;
+        self.rebase();
+    }
+
+    /// Stops the game time, keeping the time scale. The UI time keeps running.
+    /// [`Paused`](crate::event::event_types::Paused) is dispatched at the start of the next frame.
+    pub fn pause(&mut self) {
+        if self.is_paused {
+            return;
+        }
+
+        self.rebase();
+        self.is_paused = true;
+        self.pending_pause_events.push(true);
+    }
+
+    /// Resumes the game time from where it was paused.
+    /// [`Resumed`](crate::event::event_types::Resumed) is dispatched at the start of the next frame.
+    pub fn resume(&mut self) {
+        if !self.is_paused {
+            return;
+        }
+
+        self.rebase();
+        self.is_paused = false;
+        self.pending_step = None;
+        self.pending_pause_events.push(false);
+    }
+
+    /// Advances the game time of the next frame by exactly `dt` while paused, regardless of the time scale.
+    /// Calling it again before the next frame replaces the step. It does nothing unless paused.
+    pub fn step_frame(&mut self, dt: Duration) {
+        if self.is_paused {
+            self.pending_step = Some(dt);
+        }
+    }
+
+    pub(crate) fn take_pause_events(&mut self) -> Vec<bool> {
+        std::mem::take(&mut self.pending_pause_events)
     }
 
     pub fn update(&mut self) {
-        let now = Instant::now();
-        self.time = now
-            .duration_since(self.last_scale_updated_time)
-            .mul_f64(self.time_scale);
-        self.delta_time = now
-            .duration_since(self.last_frame_time)
-            .mul_f64(self.time_scale);
+        self.update_at(Instant::now());
+    }
+
+    fn update_at(&mut self, now: Instant) {
+        if self.is_paused {
+            self.delta_time = self.pending_step.take().unwrap_or_default();
+            self.base_time += self.delta_time;
+        } else {
+            self.time = now
+                .duration_since(self.last_scale_updated_time)
+                .mul_f64(self.time_scale);
+            self.delta_time = now
+                .duration_since(self.last_frame_time)
+                .mul_f64(self.time_scale);
+        }
+
         self.unscaled_delta_time = now.duration_since(self.last_frame_time);
         self.last_frame_time = now;
+
+        if self.frame_times.len() == FRAME_TIME_HISTORY_LEN {
+            self.frame_times.pop_front();
+        }
+
+        self.frame_times.push_back(self.unscaled_delta_time);
+    }
+
+    /// Folds the game time so far into the base time, so that it keeps counting from the last frame.
+    fn rebase(&mut self) {
+        self.base_time += self.time;
+        self.time = Duration::from_secs(0);
+        self.last_scale_updated_time = self.last_frame_time;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pause_step_and_time_scale() {
+        let mut time_mgr = TimeManager::new();
+        let start = time_mgr.last_frame_time;
+        let ms = Duration::from_millis;
+
+        time_mgr.set_time_scale(0.5);
+        time_mgr.update_at(start + ms(100));
+        assert_eq!(time_mgr.delta_time(), ms(50));
+        assert_eq!(time_mgr.time(), ms(50));
+
+        // Paused, the game time stands still while the UI time keeps running.
+        time_mgr.pause();
+        time_mgr.update_at(start + ms(200));
+        assert_eq!(time_mgr.delta_time(), Duration::ZERO);
+        assert_eq!(time_mgr.time(), ms(50));
+        assert_eq!(time_mgr.ui_delta_time(), ms(100));
+        assert_eq!(time_mgr.ui_time(), ms(200));
+
+        // A step advances one frame by exactly the step, ignoring the time scale.
+        time_mgr.step_frame(ms(16));
+        time_mgr.update_at(start + ms(300));
+        assert_eq!(time_mgr.delta_time(), ms(16));
+        assert_eq!(time_mgr.time(), ms(66));
+        time_mgr.update_at(start + ms(400));
+        assert_eq!(time_mgr.delta_time(), Duration::ZERO);
+        assert_eq!(time_mgr.time(), ms(66));
+
+        // The time scale can change while paused, and applies once resumed.
+        time_mgr.set_time_scale(2.0);
+        time_mgr.update_at(start + ms(500));
+        assert_eq!(time_mgr.time(), ms(66));
+        time_mgr.resume();
+        time_mgr.update_at(start + ms(600));
+        assert_eq!(time_mgr.delta_time(), ms(200));
+        assert_eq!(time_mgr.time(), ms(266));
+
+        // Steps are ignored unless paused, and redundant calls are not reported.
+        time_mgr.step_frame(ms(16));
+        time_mgr.resume();
+        time_mgr.update_at(start + ms(700));
+        assert_eq!(time_mgr.delta_time(), ms(200));
+        assert_eq!(time_mgr.take_pause_events(), vec![true, false]);
+        assert!(time_mgr.take_pause_events().is_empty());
     }
 }
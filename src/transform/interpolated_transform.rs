@@ -0,0 +1,55 @@
+use super::Transform;
+use crate::object::{Object, ObjectHierarchy};
+use codegen::Component;
+use specs::prelude::*;
+
+/// Renders the object between its transform of the previous fixed step and its current transform, blended
+/// by [`TimeManager::fixed_alpha`](crate::time::TimeManager::fixed_alpha).
+#[derive(Debug, Clone, Component)]
+#[storage(HashMapStorage)]
+#[auto_register]
+pub struct InterpolatedTransform {
+    /// The transform of the previous fixed step.
+    pub previous: Transform,
+}
+
+impl InterpolatedTransform {
+    pub fn new(previous: Transform) -> Self {
+        Self { previous }
+    }
+}
+
+/// Keeps the current transforms as the previous ones. Call it at the start of each fixed step, before the
+/// transforms are moved.
+pub fn store_previous_transforms(world: &World) {
+    let transforms = world.read_component::<Transform>();
+    let mut interpolated_transforms = world.write_component::<InterpolatedTransform>();
+
+    for (transform, interpolated) in (&transforms, &mut interpolated_transforms).join() {
+        interpolated.previous = transform.clone();
+    }
+}
+
+/// Updates the matrices of the dirty objects, rendering the interpolated objects at `alpha` between their
+/// previous and current transforms. They are updated every frame, as `alpha` changes without them moving.
+pub(crate) fn update_object_matrices(world: &World, hierarchy: &mut ObjectHierarchy, alpha: f32) {
+    let objects = world.read_component::<Object>();
+    let transforms = world.read_component::<Transform>();
+    let interpolated_transforms = world.read_component::<InterpolatedTransform>();
+
+    for (object, _) in (&objects, &interpolated_transforms).join() {
+        hierarchy.set_dirty(object.object_id());
+    }
+
+    hierarchy.copy_dirty_to_current_frame();
+    hierarchy.update_object_matrices(|entity| {
+        let transform = transforms.get(entity)?;
+
+        Some(match interpolated_transforms.get(entity) {
+            Some(interpolated) => {
+                Transform::interpolate(&interpolated.previous, transform, alpha).matrix()
+            }
+            None => transform.matrix(),
+        })
+    });
+}
@@ -1,3 +1,5 @@
+mod interpolated_transform;
 mod transform;
 
+pub use interpolated_transform::*;
 pub use transform::*;
@@ -2,10 +2,12 @@ use crate::{
     math::{Mat4, Quat, Vec3, Vec4},
     object::{ObjectComponent, ObjectHandle, ObjectHierarchy, ObjectId},
 };
-use specs::{prelude::*, Component};
+use codegen::Component;
+use specs::prelude::*;
 
 #[derive(Debug, Clone, Component)]
 #[storage(VecStorage)]
+#[auto_register]
 pub struct Transform {
     pub position: Vec3,
     pub rotation: Quat,
@@ -32,12 +34,27 @@ impl Transform {
         Mat4::srt(self.position, self.rotation, self.scale)
     }
 
+    /// Blends between the transforms, linearly for the position and the scale and spherically for the
+    /// rotation. `alpha` is clamped to `[0, 1]`.
+    pub fn interpolate(from: &Self, to: &Self, alpha: f32) -> Self {
+        Self {
+            position: Vec3::lerp(from.position, to.position, alpha),
+            rotation: Quat::slerp(from.rotation, to.rotation, alpha),
+            scale: Vec3::lerp(from.scale, to.scale, alpha),
+        }
+    }
+
     /// Returns the inverse transform matrix that transforms from world space to local space.
     /// This matrix does not include the parent transforms.
     pub fn inverse_matrix(&self) -> Mat4 {
         Mat4::trs(-self.position, -self.rotation, Vec3::recip(self.scale))
     }
 
+    /// Rotates the transform so that its forward points at the target. Both are in the parent space.
+    pub fn look_at(&mut self, target: Vec3, up: Vec3) {
+        self.rotation = Quat::look_rotation(target - self.position, up);
+    }
+
     /// Returns the transform matrix that transforms from local space to world space.
     /// This matrix includes the parent transforms.
     pub fn world_matrix(
@@ -396,6 +413,21 @@ impl TransformComponent {
         transforms.get_mut(self.object.entity).unwrap().scale = scale;
     }
 
+    /// Rotates the given object so that its forward points at the target, given in the parent space.
+    pub fn look_at(&self, target: Vec3, up: Vec3) {
+        let mut object_mgr = self.object.ctx.object_mgr_mut();
+        object_mgr
+            .object_hierarchy_mut()
+            .set_dirty(self.object.object_id);
+
+        let world = self.object.ctx.world();
+        let mut transforms = world.write_component::<Transform>();
+        transforms
+            .get_mut(self.object.entity)
+            .unwrap()
+            .look_at(target, up);
+    }
+
     /// Returns the world position of the given object.
     pub fn world_position(&self) -> Vec3 {
         let object_id = self.object.object_id;
@@ -551,3 +583,39 @@ impl TransformComponent {
             .down(object_id, &hierarchy, &transforms)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interpolate_blends_halfway() {
+        let from = Transform {
+            position: Vec3::new(0.0, 2.0, -4.0),
+            rotation: Quat::from_eular(0.0, 0.0, 0.0),
+            scale: Vec3::new(1.0, 1.0, 1.0),
+        };
+        let to = Transform {
+            position: Vec3::new(4.0, 2.0, 0.0),
+            rotation: Quat::from_eular(0.0, std::f32::consts::FRAC_PI_2, 0.0),
+            scale: Vec3::new(3.0, 1.0, 2.0),
+        };
+        let expected = Transform {
+            position: Vec3::new(2.0, 2.0, -2.0),
+            rotation: Quat::from_eular(0.0, std::f32::consts::FRAC_PI_4, 0.0),
+            scale: Vec3::new(2.0, 1.0, 1.5),
+        }
+        .matrix();
+
+        let matrix = Transform::interpolate(&from, &to, 0.5).matrix();
+
+        for (actual, expected) in matrix.elements.iter().zip(expected.elements.iter()) {
+            assert!(
+                (actual - expected).abs() < 1e-5,
+                "{:?} != {:?}",
+                matrix,
+                expected
+            );
+        }
+    }
+}
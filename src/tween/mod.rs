@@ -0,0 +1,5 @@
+mod object_tween;
+mod tween_manager;
+
+pub use object_tween::*;
+pub use tween_manager::*;
@@ -0,0 +1,188 @@
+use crate::{
+    gfx::Color,
+    math::{Quat, Vec3},
+    object::ObjectHandle,
+    ui::UIMargin,
+    use_context,
+    util::{Easing, Lerp},
+};
+use std::time::Duration;
+
+/// A property of an object that [`ObjectTween`] animates, with its value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TweenValue {
+    /// The local position of the [`Transform`](crate::transform::Transform).
+    Position(Vec3),
+    /// The local rotation of the [`Transform`](crate::transform::Transform).
+    Rotation(Quat),
+    /// The local scale of the [`Transform`](crate::transform::Transform).
+    Scale(Vec3),
+    /// The width of the [`UISize`](crate::ui::UISize).
+    Width(f32),
+    /// The height of the [`UISize`](crate::ui::UISize).
+    Height(f32),
+    /// The margin of the [`UIElement`](crate::ui::UIElement).
+    Margin(UIMargin),
+    /// The color of the renderers of the object. Mesh renderers get it as the `color` property of their
+    /// property block.
+    Color(Color),
+    /// The alpha of the color of the renderers of the object.
+    Alpha(f32),
+}
+
+impl TweenValue {
+    /// Interpolates towards the other value of the same property. Rotations are interpolated spherically.
+    pub fn interpolate(&self, to: &Self, t: f32) -> Self {
+        match (self, to) {
+            (Self::Position(from), Self::Position(to)) => Self::Position(Lerp::lerp(*from, *to, t)),
+            (Self::Rotation(from), Self::Rotation(to)) => {
+                Self::Rotation(Quat::slerp_unclamped(*from, *to, t))
+            }
+            (Self::Scale(from), Self::Scale(to)) => Self::Scale(Lerp::lerp(*from, *to, t)),
+            (Self::Width(from), Self::Width(to)) => Self::Width(Lerp::lerp(*from, *to, t)),
+            (Self::Height(from), Self::Height(to)) => Self::Height(Lerp::lerp(*from, *to, t)),
+            (Self::Margin(from), Self::Margin(to)) => Self::Margin(UIMargin::new(
+                Lerp::lerp(from.left, to.left, t),
+                Lerp::lerp(from.right, to.right, t),
+                Lerp::lerp(from.top, to.top, t),
+                Lerp::lerp(from.bottom, to.bottom, t),
+            )),
+            (Self::Color(from), Self::Color(to)) => Self::Color(Lerp::lerp(*from, *to, t)),
+            (Self::Alpha(from), Self::Alpha(to)) => Self::Alpha(Lerp::lerp(*from, *to, t)),
+            _ => to.clone(),
+        }
+    }
+}
+
+/// The clock that a tween runs on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum TweenClock {
+    /// The UI time of [`TimeManager`](crate::time::TimeManager), which keeps running while the game is paused.
+    UI,
+    /// The game time of [`TimeManager`](crate::time::TimeManager), which is scaled and paused.
+    Game,
+}
+
+/// Builds a tween that animates a property of an object from its current value, e.g.
+/// `ObjectTween::new(panel).position_to(Vec3::ZERO, duration).easing(Easing::CubicOut).start()`.
+///
+/// A tween animates one property; the last `*_to` call wins. Without any, it only waits for the duration.
+/// The tween is cancelled silently if the object is removed or loses the component of the property.
+/// It runs on the UI time unless [`game_time`](Self::game_time) is called.
+pub struct ObjectTween {
+    pub(crate) target: ObjectHandle,
+    pub(crate) to: Option<TweenValue>,
+    pub(crate) duration: Duration,
+    pub(crate) easing: Easing,
+    pub(crate) clock: TweenClock,
+    pub(crate) on_complete: Option<Box<dyn FnOnce()>>,
+}
+
+impl ObjectTween {
+    pub fn new(target: ObjectHandle) -> Self {
+        Self {
+            target,
+            to: None,
+            duration: Duration::ZERO,
+            easing: Easing::Linear,
+            clock: TweenClock::UI,
+            on_complete: None,
+        }
+    }
+
+    pub fn target(&self) -> &ObjectHandle {
+        &self.target
+    }
+
+    pub fn to(mut self, value: TweenValue, duration: Duration) -> Self {
+        self.to = Some(value);
+        self.duration = duration;
+        self
+    }
+
+    pub fn position_to(self, position: Vec3, duration: Duration) -> Self {
+        self.to(TweenValue::Position(position), duration)
+    }
+
+    pub fn rotation_to(self, rotation: Quat, duration: Duration) -> Self {
+        self.to(TweenValue::Rotation(rotation), duration)
+    }
+
+    pub fn scale_to(self, scale: Vec3, duration: Duration) -> Self {
+        self.to(TweenValue::Scale(scale), duration)
+    }
+
+    pub fn width_to(self, width: f32, duration: Duration) -> Self {
+        self.to(TweenValue::Width(width), duration)
+    }
+
+    pub fn height_to(self, height: f32, duration: Duration) -> Self {
+        self.to(TweenValue::Height(height), duration)
+    }
+
+    pub fn margin_to(self, margin: UIMargin, duration: Duration) -> Self {
+        self.to(TweenValue::Margin(margin), duration)
+    }
+
+    pub fn color_to(self, color: Color, duration: Duration) -> Self {
+        self.to(TweenValue::Color(color), duration)
+    }
+
+    pub fn alpha_to(self, alpha: f32, duration: Duration) -> Self {
+        self.to(TweenValue::Alpha(alpha), duration)
+    }
+
+    /// Sets the wait with no property to animate.
+    pub fn wait(mut self, duration: Duration) -> Self {
+        self.to = None;
+        self.duration = duration;
+        self
+    }
+
+    pub fn easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// Runs the tween on the game time, so that it follows the time scale and stops while the game is paused.
+    pub fn game_time(mut self) -> Self {
+        self.clock = TweenClock::Game;
+        self
+    }
+
+    /// Calls the callback once the tween has reached its end. It is not called if the tween is cancelled.
+    pub fn on_complete(mut self, callback: impl FnOnce() + 'static) -> Self {
+        self.on_complete = Some(Box::new(callback));
+        self
+    }
+
+    /// Hands the tween over to the [`TweenManager`](super::TweenManager). It starts from the value of the
+    /// property on the next update.
+    pub fn start(self) -> super::TweenId {
+        use_context().tween_mgr_mut().add(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interpolate_values_of_same_property() {
+        let from = TweenValue::Margin(UIMargin::new(0f32, 10f32, 20f32, 30f32));
+        let to = TweenValue::Margin(UIMargin::new(10f32, 10f32, 0f32, 40f32));
+        assert_eq!(
+            from.interpolate(&to, 0.5f32),
+            TweenValue::Margin(UIMargin::new(5f32, 10f32, 10f32, 35f32))
+        );
+
+        // Overshooting easings go past the end.
+        let from = TweenValue::Alpha(0f32);
+        let to = TweenValue::Alpha(1f32);
+        assert_eq!(from.interpolate(&to, 1.5f32), TweenValue::Alpha(1.5f32));
+
+        // Mismatching properties jump to the end.
+        let from = TweenValue::Width(0f32);
+        assert_eq!(from.interpolate(&to, 0.5f32), to);
+    }
+}
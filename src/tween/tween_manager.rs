@@ -0,0 +1,95 @@
+use super::{ObjectTween, TweenClock, TweenValue};
+use crate::object::ObjectHandle;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TweenId(u64);
+
+pub(crate) struct RunningTween {
+    pub id: TweenId,
+    pub tween: ObjectTween,
+    /// The value of the property when the tween has started. It is read on the first update.
+    pub from: Option<TweenValue>,
+    pub elapsed: Duration,
+}
+
+impl RunningTween {
+    /// Advances the tween and returns the eased progress.
+    pub fn advance(&mut self, delta_time: Duration, ui_delta_time: Duration) -> f32 {
+        let dt = match self.tween.clock {
+            TweenClock::UI => ui_delta_time,
+            TweenClock::Game => delta_time,
+        };
+        self.elapsed = (self.elapsed + dt).min(self.tween.duration);
+
+        let progress = if self.tween.duration.is_zero() {
+            1f32
+        } else {
+            self.elapsed.as_secs_f32() / self.tween.duration.as_secs_f32()
+        };
+        self.tween.easing.apply(progress)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.tween.duration <= self.elapsed
+    }
+}
+
+/// Keeps the tweens started with [`ObjectTween::start`]. The
+/// [`UpdateTweens`](crate::ecs_system::update_tweens::UpdateTweens) system drives them right after
+/// [`Update`](crate::event::event_types::Update), on the UI time unless they opt in to the game time.
+pub struct TweenManager {
+    next_id: u64,
+    tweens: Vec<RunningTween>,
+}
+
+impl TweenManager {
+    pub fn new() -> Self {
+        Self {
+            next_id: 0,
+            tweens: Vec::new(),
+        }
+    }
+
+    pub(crate) fn add(&mut self, tween: ObjectTween) -> TweenId {
+        let id = TweenId(self.next_id);
+        self.next_id += 1;
+        self.tweens.push(RunningTween {
+            id,
+            tween,
+            from: None,
+            elapsed: Duration::ZERO,
+        });
+        id
+    }
+
+    /// Returns `true` if any tween is running, which keeps [`EngineLoopMode::Adaptive`](crate::EngineLoopMode::Adaptive)
+    /// polling.
+    pub fn has_running(&self) -> bool {
+        !self.tweens.is_empty()
+    }
+
+    pub fn is_running(&self, id: TweenId) -> bool {
+        self.tweens.iter().any(|tween| tween.id == id)
+    }
+
+    /// Stops the tween where it is, without calling its completion callback.
+    pub fn cancel(&mut self, id: TweenId) {
+        self.tweens.retain(|tween| tween.id != id);
+    }
+
+    /// Stops every tween of the object, without calling their completion callbacks.
+    pub fn cancel_object(&mut self, object: &ObjectHandle) {
+        self.tweens.retain(|tween| &tween.tween.target != object);
+    }
+
+    pub(crate) fn tweens_mut(&mut self) -> &mut Vec<RunningTween> {
+        &mut self.tweens
+    }
+}
+
+impl Default for TweenManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,11 +1,27 @@
+mod ui_aspect_ratio_fitter;
+mod ui_content_size_fitter;
+mod ui_cursor;
 mod ui_element;
 mod ui_event_manager;
+mod ui_layout;
+mod ui_localized_text;
+mod ui_progress_bar;
 mod ui_raycast_manager;
+mod ui_safe_area;
 mod ui_scaler;
 mod ui_size;
+mod ui_slider;
 
+pub use ui_aspect_ratio_fitter::*;
+pub use ui_content_size_fitter::*;
+pub use ui_cursor::*;
 pub use ui_element::*;
 pub use ui_event_manager::*;
+pub use ui_layout::*;
+pub use ui_localized_text::*;
+pub use ui_progress_bar::*;
 pub use ui_raycast_manager::*;
+pub use ui_safe_area::*;
 pub use ui_scaler::*;
 pub use ui_size::*;
+pub use ui_slider::*;
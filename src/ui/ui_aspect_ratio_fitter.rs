@@ -0,0 +1,145 @@
+use crate::{
+    math::Vec2,
+    object::{ObjectComponent, ObjectHandle},
+};
+use codegen::Component;
+use specs::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UIAspectRatioFitMode {
+    /// Fits the element inside of the rect resolved by its anchor and margin.
+    FitInParent,
+    /// Keeps the width of the element and adjusts its height.
+    WidthControlsHeight,
+    /// Keeps the height of the element and adjusts its width.
+    HeightControlsWidth,
+}
+
+/// Preserves the aspect ratio of an element within the rect resolved by its anchor and margin.
+/// The adjusted element stays centered in the resolved rect.
+#[derive(Debug, Clone, Component)]
+#[storage(HashMapStorage)]
+#[auto_register]
+pub struct UIAspectRatioFitter {
+    /// The aspect ratio to preserve, in width / height.
+    pub ratio: f32,
+    pub mode: UIAspectRatioFitMode,
+}
+
+impl UIAspectRatioFitter {
+    pub fn new(ratio: f32, mode: UIAspectRatioFitMode) -> Self {
+        Self { ratio, mode }
+    }
+
+    /// Adjusts the given rect to preserve the aspect ratio.
+    /// The position is the left-bottom corner of the rect.
+    pub fn fit(&self, position: Vec2, size: Vec2) -> (Vec2, Vec2) {
+        if self.ratio <= 0.0 {
+            return (position, size);
+        }
+
+        let fitted_size = match self.mode {
+            UIAspectRatioFitMode::FitInParent => {
+                if self.ratio * size.y <= size.x {
+                    Vec2::new(size.y * self.ratio, size.y)
+                } else {
+                    Vec2::new(size.x, size.x / self.ratio)
+                }
+            }
+            UIAspectRatioFitMode::WidthControlsHeight => Vec2::new(size.x, size.x / self.ratio),
+            UIAspectRatioFitMode::HeightControlsWidth => Vec2::new(size.y * self.ratio, size.y),
+        };
+
+        (position + (size - fitted_size) * 0.5, fitted_size)
+    }
+}
+
+pub struct UIAspectRatioFitterComponent {
+    object: ObjectHandle,
+}
+
+impl ObjectComponent for UIAspectRatioFitterComponent {
+    type Component = UIAspectRatioFitter;
+
+    fn new(object: ObjectHandle) -> Self {
+        Self { object }
+    }
+
+    fn object(&self) -> &ObjectHandle {
+        &self.object
+    }
+}
+
+impl UIAspectRatioFitterComponent {
+    pub fn ratio(&self) -> f32 {
+        let world = self.object.ctx.world();
+        let fitters = world.read_storage::<UIAspectRatioFitter>();
+        fitters.get(self.object.entity).unwrap().ratio
+    }
+
+    pub fn mode(&self) -> UIAspectRatioFitMode {
+        let world = self.object.ctx.world();
+        let fitters = world.read_storage::<UIAspectRatioFitter>();
+        fitters.get(self.object.entity).unwrap().mode
+    }
+
+    pub fn set_ratio(&self, ratio: f32) {
+        let mut object_mgr = self.object.ctx.object_mgr_mut();
+        object_mgr
+            .object_hierarchy_mut()
+            .set_dirty(self.object.object_id);
+
+        let world = self.object.ctx.world();
+        let mut fitters = world.write_storage::<UIAspectRatioFitter>();
+        fitters.get_mut(self.object.entity).unwrap().ratio = ratio;
+    }
+
+    pub fn set_mode(&self, mode: UIAspectRatioFitMode) {
+        let mut object_mgr = self.object.ctx.object_mgr_mut();
+        object_mgr
+            .object_hierarchy_mut()
+            .set_dirty(self.object.object_id);
+
+        let world = self.object.ctx.world();
+        let mut fitters = world.write_storage::<UIAspectRatioFitter>();
+        fitters.get_mut(self.object.entity).unwrap().mode = mode;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fit_in_parent() {
+        let fitter = UIAspectRatioFitter::new(2.0, UIAspectRatioFitMode::FitInParent);
+
+        // Wider than the ratio; the width is reduced.
+        let (position, size) = fitter.fit(Vec2::new(10.0, 20.0), Vec2::new(400.0, 100.0));
+        assert_eq!(size, Vec2::new(200.0, 100.0));
+        assert_eq!(position, Vec2::new(110.0, 20.0));
+
+        // Taller than the ratio; the height is reduced.
+        let (position, size) = fitter.fit(Vec2::new(10.0, 20.0), Vec2::new(100.0, 100.0));
+        assert_eq!(size, Vec2::new(100.0, 50.0));
+        assert_eq!(position, Vec2::new(10.0, 45.0));
+    }
+
+    #[test]
+    fn width_controls_height() {
+        let fitter = UIAspectRatioFitter::new(0.5, UIAspectRatioFitMode::WidthControlsHeight);
+        let (position, size) = fitter.fit(Vec2::ZERO, Vec2::new(100.0, 100.0));
+
+        assert_eq!(size, Vec2::new(100.0, 200.0));
+        assert_eq!(position, Vec2::new(0.0, -50.0));
+    }
+
+    #[test]
+    fn height_controls_width() {
+        let fitter = UIAspectRatioFitter::new(0.5, UIAspectRatioFitMode::HeightControlsWidth);
+        let (position, size) = fitter.fit(Vec2::ZERO, Vec2::new(100.0, 100.0));
+
+        assert_eq!(size, Vec2::new(50.0, 100.0));
+        assert_eq!(position, Vec2::new(25.0, 0.0));
+    }
+}
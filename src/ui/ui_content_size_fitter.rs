@@ -0,0 +1,159 @@
+use crate::{
+    math::Vec2,
+    object::{ObjectComponent, ObjectHandle},
+};
+use codegen::Component;
+use specs::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UIContentFitMode {
+    /// Keeps the size resolved by the anchor and margin.
+    Anchored,
+    /// Sizes the element to its content. Horizontally, the content is not wrapped.
+    Unconstrained,
+}
+
+/// Sizes an element to its content, e.g. a label to its text, within the rect resolved by its anchor and
+/// margin. The content is measured by the [`UITextRenderer`](crate::gfx::UITextRenderer) of the element,
+/// wrapped at the resolved width unless the horizontal fit is unconstrained.
+/// The adjusted element stays centered in the resolved rect.
+#[derive(Debug, Clone, Component)]
+#[storage(HashMapStorage)]
+#[auto_register]
+pub struct UIContentSizeFitter {
+    pub horizontal: UIContentFitMode,
+    pub vertical: UIContentFitMode,
+}
+
+impl UIContentSizeFitter {
+    pub fn new(horizontal: UIContentFitMode, vertical: UIContentFitMode) -> Self {
+        Self {
+            horizontal,
+            vertical,
+        }
+    }
+
+    /// Adjusts the given rect to the content, which is measured by `measure` with the width to wrap at.
+    /// The position is the left-bottom corner of the rect.
+    pub fn fit(
+        &self,
+        position: Vec2,
+        size: Vec2,
+        measure: impl FnOnce(Option<f32>) -> Vec2,
+    ) -> (Vec2, Vec2) {
+        if self.horizontal == UIContentFitMode::Anchored
+            && self.vertical == UIContentFitMode::Anchored
+        {
+            return (position, size);
+        }
+
+        let content_size = match self.horizontal {
+            UIContentFitMode::Anchored => measure(Some(size.x)),
+            UIContentFitMode::Unconstrained => measure(None),
+        };
+        let fitted_size = Vec2::new(
+            match self.horizontal {
+                UIContentFitMode::Anchored => size.x,
+                UIContentFitMode::Unconstrained => content_size.x,
+            },
+            match self.vertical {
+                UIContentFitMode::Anchored => size.y,
+                UIContentFitMode::Unconstrained => content_size.y,
+            },
+        );
+
+        (position + (size - fitted_size) * 0.5, fitted_size)
+    }
+}
+
+pub struct UIContentSizeFitterComponent {
+    object: ObjectHandle,
+}
+
+impl ObjectComponent for UIContentSizeFitterComponent {
+    type Component = UIContentSizeFitter;
+
+    fn new(object: ObjectHandle) -> Self {
+        Self { object }
+    }
+
+    fn object(&self) -> &ObjectHandle {
+        &self.object
+    }
+}
+
+impl UIContentSizeFitterComponent {
+    pub fn horizontal(&self) -> UIContentFitMode {
+        let world = self.object.ctx.world();
+        let fitters = world.read_storage::<UIContentSizeFitter>();
+        fitters.get(self.object.entity).unwrap().horizontal
+    }
+
+    pub fn vertical(&self) -> UIContentFitMode {
+        let world = self.object.ctx.world();
+        let fitters = world.read_storage::<UIContentSizeFitter>();
+        fitters.get(self.object.entity).unwrap().vertical
+    }
+
+    pub fn set_horizontal(&self, horizontal: UIContentFitMode) {
+        let mut object_mgr = self.object.ctx.object_mgr_mut();
+        object_mgr
+            .object_hierarchy_mut()
+            .set_dirty(self.object.object_id);
+
+        let world = self.object.ctx.world();
+        let mut fitters = world.write_storage::<UIContentSizeFitter>();
+        fitters.get_mut(self.object.entity).unwrap().horizontal = horizontal;
+    }
+
+    pub fn set_vertical(&self, vertical: UIContentFitMode) {
+        let mut object_mgr = self.object.ctx.object_mgr_mut();
+        object_mgr
+            .object_hierarchy_mut()
+            .set_dirty(self.object.object_id);
+
+        let world = self.object.ctx.world();
+        let mut fitters = world.write_storage::<UIContentSizeFitter>();
+        fitters.get_mut(self.object.entity).unwrap().vertical = vertical;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fit_to_content() {
+        let measure = |max_width: Option<f32>| match max_width {
+            // Wrapped into two lines.
+            Some(_) => Vec2::new(80.0, 40.0),
+            None => Vec2::new(150.0, 20.0),
+        };
+        let position = Vec2::new(10.0, 20.0);
+        let size = Vec2::new(100.0, 100.0);
+
+        let fitter = UIContentSizeFitter::new(
+            UIContentFitMode::Unconstrained,
+            UIContentFitMode::Unconstrained,
+        );
+        let (position_fitted, size_fitted) = fitter.fit(position, size, measure);
+        assert_eq!(size_fitted, Vec2::new(150.0, 20.0));
+        assert_eq!(position_fitted, Vec2::new(-15.0, 60.0));
+
+        // The text is wrapped at the anchored width.
+        let fitter =
+            UIContentSizeFitter::new(UIContentFitMode::Anchored, UIContentFitMode::Unconstrained);
+        let (position_fitted, size_fitted) = fitter.fit(position, size, |max_width| {
+            assert_eq!(max_width, Some(100.0));
+            measure(max_width)
+        });
+        assert_eq!(size_fitted, Vec2::new(100.0, 40.0));
+        assert_eq!(position_fitted, Vec2::new(10.0, 50.0));
+
+        let fitter =
+            UIContentSizeFitter::new(UIContentFitMode::Anchored, UIContentFitMode::Anchored);
+        let (position_fitted, size_fitted) =
+            fitter.fit(position, size, |_| unreachable!("nothing is fitted"));
+        assert_eq!((position_fitted, size_fitted), (position, size));
+    }
+}
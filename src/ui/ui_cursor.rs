@@ -0,0 +1,106 @@
+use crate::object::ObjectId;
+use codegen::Component;
+use specs::prelude::*;
+use winit::window::CursorIcon;
+
+/// Shows the cursor icon while the pointer is over the element, and restores the default when it leaves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Component)]
+#[storage(HashMapStorage)]
+#[auto_register]
+pub struct UICursor {
+    pub icon: CursorIcon,
+}
+
+impl UICursor {
+    pub fn new(icon: CursorIcon) -> Self {
+        Self { icon }
+    }
+}
+
+/// Tracks which element owns the cursor icon. Only the owner can restore the default, so that leaving an
+/// element after entering another one does not reset the icon of the new one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct UICursorState {
+    owner: Option<ObjectId>,
+    icon: CursorIcon,
+}
+
+impl UICursorState {
+    pub fn new() -> Self {
+        Self {
+            owner: None,
+            icon: CursorIcon::Default,
+        }
+    }
+
+    /// Handles the pointer entering the object. Returns the icon to apply if it has changed.
+    pub fn enter(&mut self, object_id: ObjectId, cursor: Option<CursorIcon>) -> Option<CursorIcon> {
+        match cursor {
+            Some(icon) => {
+                self.owner = Some(object_id);
+                self.apply(icon)
+            }
+            None => {
+                self.owner = None;
+                self.apply(CursorIcon::Default)
+            }
+        }
+    }
+
+    /// Handles the pointer leaving the object. Returns the icon to apply if it has changed.
+    pub fn leave(&mut self, object_id: ObjectId) -> Option<CursorIcon> {
+        if self.owner != Some(object_id) {
+            return None;
+        }
+
+        self.owner = None;
+        self.apply(CursorIcon::Default)
+    }
+
+    fn apply(&mut self, icon: CursorIcon) -> Option<CursorIcon> {
+        if self.icon == icon {
+            return None;
+        }
+
+        self.icon = icon;
+        Some(icon)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_state_survives_enter_before_leave() {
+        let a = ObjectId::from_u32(1);
+        let b = ObjectId::from_u32(2);
+        let mut state = UICursorState::new();
+
+        assert_eq!(
+            state.enter(a, Some(CursorIcon::Text)),
+            Some(CursorIcon::Text)
+        );
+
+        // Enters B before leaving A; leaving A must not reset the icon of B.
+        assert_eq!(
+            state.enter(b, Some(CursorIcon::EwResize)),
+            Some(CursorIcon::EwResize)
+        );
+        assert_eq!(state.leave(a), None);
+        assert_eq!(state.leave(b), Some(CursorIcon::Default));
+
+        // Leaves A before entering B.
+        state.enter(a, Some(CursorIcon::Text));
+        assert_eq!(state.leave(a), Some(CursorIcon::Default));
+        assert_eq!(
+            state.enter(b, Some(CursorIcon::EwResize)),
+            Some(CursorIcon::EwResize)
+        );
+
+        // Entering an element without a cursor restores the default.
+        assert_eq!(state.enter(a, None), Some(CursorIcon::Default));
+        assert_eq!(state.leave(b), None);
+        assert_eq!(state.enter(b, None), None);
+    }
+}
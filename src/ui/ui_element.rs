@@ -1,5 +1,10 @@
-use crate::math::Vec2;
-use specs::{prelude::*, Component};
+use crate::{
+    gfx::Color,
+    math::Vec2,
+    object::{ObjectHierarchy, ObjectId},
+};
+use codegen::Component;
+use specs::prelude::*;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct UIAnchor {
@@ -62,10 +67,15 @@ impl UIMargin {
 
 #[derive(Debug, Clone, Component)]
 #[storage(HashMapStorage)]
+#[auto_register]
 pub struct UIElement {
     pub anchor: UIAnchor,
     pub margin: UIMargin,
     pub is_interactable: bool,
+    /// The opacity in range `[0, 1]`, multiplied with the opacities of the parent elements, e.g. to fade a panel.
+    pub opacity: f32,
+    /// The color that the renderers of the element and its descendants are multiplied with.
+    pub tint: Color,
 }
 
 impl UIElement {
@@ -74,8 +84,37 @@ impl UIElement {
             anchor,
             margin,
             is_interactable,
+            ..Default::default()
         }
     }
+
+    /// Returns the tint with the opacity applied to its alpha.
+    pub fn color(&self) -> Color {
+        let mut color = self.tint;
+        color.a *= self.opacity.clamp(0.0, 1.0);
+        color
+    }
+}
+
+/// Returns the color that the renderers of the object are multiplied with: the colors of its element and of the
+/// elements of its parents, multiplied together. Objects without elements do not affect it.
+pub fn effective_ui_color(
+    object: ObjectId,
+    hierarchy: &ObjectHierarchy,
+    elements: &ReadStorage<UIElement>,
+) -> Color {
+    let mut color = Color::white();
+    let mut current = Some(object);
+
+    while let Some(object) = current {
+        if let Some(element) = elements.get(hierarchy.entity(object)) {
+            color *= element.color();
+        }
+
+        current = hierarchy.parent(object);
+    }
+
+    color
 }
 
 impl Default for UIElement {
@@ -84,6 +123,41 @@ impl Default for UIElement {
             anchor: UIAnchor::full(),
             margin: UIMargin::zero(),
             is_interactable: false,
+            opacity: 1.0,
+            tint: Color::white(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opacity_multiplies_through_the_hierarchy() {
+        let mut world = World::new();
+        world.register::<UIElement>();
+
+        let mut hierarchy = ObjectHierarchy::new();
+        let opacities = [0.5, 0.5, 1.0];
+
+        for (id, &opacity) in opacities.iter().enumerate() {
+            let entity = world
+                .create_entity()
+                .with(UIElement {
+                    opacity,
+                    ..Default::default()
+                })
+                .build();
+            hierarchy.add(ObjectId::from_u32(id as u32), entity);
+        }
+
+        hierarchy.set_parent(ObjectId::from_u32(1), Some(ObjectId::from_u32(0)));
+        hierarchy.set_parent(ObjectId::from_u32(2), Some(ObjectId::from_u32(1)));
+
+        let elements = world.read_storage::<UIElement>();
+        let color = effective_ui_color(ObjectId::from_u32(2), &hierarchy, &elements);
+        assert_eq!(color.a, 0.25);
+        assert_eq!((color.r, color.g, color.b), (1.0, 1.0, 1.0));
+    }
+}
@@ -1,13 +1,25 @@
+use super::{UICursor, UICursorState};
 use crate::{
+    gfx::ObjectPicker,
     math::Vec2,
     object::ObjectHandle,
-    object_event::object_event_types::{MouseEnterEvent, MouseLeaveEvent, MouseMoveEvent},
+    object_event::object_event_types::{
+        DragEndEvent, DragEvent, DragStartEvent, MouseDownEvent, MouseEnterEvent, MouseLeaveEvent,
+        MouseMoveEvent, MouseUpEvent,
+    },
     use_context,
 };
+use specs::WorldExt;
 
 pub struct UIEventManager {
     prev_object: Option<ObjectHandle>,
+    drag_object: Option<ObjectHandle>,
+    is_prev_object_ui: bool,
+    is_drag_object_ui: bool,
+    pick_camera: Option<ObjectHandle>,
     mouse_position: Option<Vec2>,
+    drag_position: Vec2,
+    cursor_state: UICursorState,
     is_dirty: bool,
 }
 
@@ -15,14 +27,34 @@ impl UIEventManager {
     pub fn new() -> Self {
         Self {
             prev_object: None,
+            drag_object: None,
+            is_prev_object_ui: false,
+            is_drag_object_ui: false,
+            pick_camera: None,
             mouse_position: None,
+            drag_position: Vec2::ZERO,
+            cursor_state: UICursorState::new(),
             is_dirty: false,
         }
     }
 
+    /// Returns `true` if the pointer is over an interactable element or an element is being dragged.
+    /// Scene controls should ignore clicks and drags that start while it is `true`.
+    pub fn is_pointer_over_ui(&self) -> bool {
+        (self.prev_object.is_some() && self.is_prev_object_ui)
+            || (self.drag_object.is_some() && self.is_drag_object_ui)
+    }
+
+    /// Sets the camera that objects in the 3D scene are picked with. If set, objects with a
+    /// [`Pickable`](crate::gfx::Pickable) component receive the same mouse events as UI elements,
+    /// but only while no UI element is under the mouse.
+    pub fn set_pick_camera(&mut self, camera: Option<ObjectHandle>) {
+        self.pick_camera = camera;
+        self.is_dirty = true;
+    }
+
     pub fn update_mouse_position(&mut self, point: Vec2) {
-        let screen_mgr = use_context().screen_mgr();
-        let screen_size = Vec2::new(screen_mgr.width() as f32, screen_mgr.height() as f32);
+        let screen_size = use_context().screen_mgr().logical_size();
         let point = Vec2::new(
             point.x - screen_size.x * 0.5f32,
             screen_size.y * 0.5f32 - point.y,
@@ -34,18 +66,25 @@ impl UIEventManager {
     pub fn remove_object(&mut self, object: &ObjectHandle) {
         if let Some(prev_object) = self.prev_object.as_ref() {
             if prev_object == object {
+                self.leave_cursor(object);
                 self.prev_object = None;
                 self.is_dirty = true;
             }
         }
+
+        if let Some(drag_object) = self.drag_object.as_ref() {
+            if drag_object == object {
+                self.drag_object = None;
+            }
+        }
     }
 
     pub fn handle_mouse_leave(&mut self) {
-        if let Some(prev_object) = self.prev_object.as_ref() {
+        if let Some(prev_object) = self.prev_object.take() {
             use_context()
                 .object_event_mgr()
                 .dispatch(prev_object.object_id, &MouseLeaveEvent);
-            self.prev_object = None;
+            self.leave_cursor(&prev_object);
             self.is_dirty = false;
         }
     }
@@ -61,27 +100,130 @@ impl UIEventManager {
             return;
         };
 
-        let current = use_context().ui_raycast_mgr_mut().raycast(point);
         let event_mgr = use_context().object_event_mgr();
 
-        match (self.prev_object.as_ref(), current.as_ref()) {
+        if let Some(drag_object) = self.drag_object.as_ref() {
+            if point != self.drag_position {
+                event_mgr.dispatch(
+                    drag_object.object_id,
+                    &DragEvent {
+                        position: point,
+                        delta: point - self.drag_position,
+                    },
+                );
+                self.drag_position = point;
+            }
+        }
+
+        let (current, is_ui) = self.raycast(point).unzip();
+
+        let prev_object = self.prev_object.take();
+
+        match (prev_object.as_ref(), current.as_ref()) {
             (Some(prev), Some(current)) if prev == current => {
                 event_mgr.dispatch(current.object_id, &MouseMoveEvent);
             }
             (Some(prev), Some(current)) => {
                 event_mgr.dispatch(prev.object_id, &MouseLeaveEvent);
                 event_mgr.dispatch(current.object_id, &MouseEnterEvent);
+                self.enter_cursor(current);
+                self.leave_cursor(prev);
             }
             (Some(prev), None) => {
                 event_mgr.dispatch(prev.object_id, &MouseLeaveEvent);
+                self.leave_cursor(prev);
             }
             (None, Some(current)) => {
                 event_mgr.dispatch(current.object_id, &MouseEnterEvent);
+                self.enter_cursor(current);
             }
             _ => {}
         }
 
         self.prev_object = current;
+        self.is_prev_object_ui = is_ui.unwrap_or(false);
         self.is_dirty = false;
     }
+
+    /// Handles the left mouse button being pressed. The object under the mouse starts being dragged.
+    pub fn handle_mouse_down(&mut self) {
+        let point = if let Some(mouse_position) = self.mouse_position {
+            mouse_position
+        } else {
+            return;
+        };
+
+        let (current, is_ui) = if let Some(current) = self.raycast(point) {
+            current
+        } else {
+            return;
+        };
+
+        let event_mgr = use_context().object_event_mgr();
+        event_mgr.dispatch(current.object_id, &MouseDownEvent);
+        event_mgr.dispatch(current.object_id, &DragStartEvent { position: point });
+
+        self.drag_object = Some(current);
+        self.is_drag_object_ui = is_ui;
+        self.drag_position = point;
+    }
+
+    /// Handles the left mouse button being released. The dragged object, if any, stops being dragged.
+    pub fn handle_mouse_up(&mut self) {
+        let point = self.mouse_position.unwrap_or(self.drag_position);
+        let current = self.raycast(point);
+        let event_mgr = use_context().object_event_mgr();
+
+        if let Some((current, _)) = current {
+            event_mgr.dispatch(current.object_id, &MouseUpEvent);
+        }
+
+        if let Some(drag_object) = self.drag_object.take() {
+            event_mgr.dispatch(drag_object.object_id, &DragEndEvent { position: point });
+        }
+    }
+
+    /// Finds the object under the given point, in the same space as the mouse position. UI elements come first,
+    /// then pickable objects in the 3D scene. Also returns whether the object is a UI element.
+    fn raycast(&self, point: Vec2) -> Option<(ObjectHandle, bool)> {
+        let ctx = use_context();
+
+        if let Some(object) = ctx.ui_raycast_mgr_mut().raycast(point) {
+            return Some((object, true));
+        }
+
+        let camera = self.pick_camera.as_ref()?;
+        let screen_size = ctx.screen_mgr().logical_size();
+        let screen_position = Vec2::new(
+            point.x + screen_size.x * 0.5f32,
+            screen_size.y * 0.5f32 - point.y,
+        );
+        let picker = ObjectPicker {
+            is_pickable_only: true,
+            ..ObjectPicker::new()
+        };
+
+        picker
+            .pick(screen_position, camera)
+            .map(|hit| (hit.object, false))
+    }
+
+    fn enter_cursor(&mut self, object: &ObjectHandle) {
+        let ctx = use_context();
+        let cursor = ctx
+            .world()
+            .read_component::<UICursor>()
+            .get(object.entity)
+            .map(|cursor| cursor.icon);
+
+        if let Some(icon) = self.cursor_state.enter(object.object_id, cursor) {
+            ctx.set_cursor_icon(icon);
+        }
+    }
+
+    fn leave_cursor(&mut self, object: &ObjectHandle) {
+        if let Some(icon) = self.cursor_state.leave(object.object_id) {
+            use_context().set_cursor_icon(icon);
+        }
+    }
 }
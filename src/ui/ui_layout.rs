@@ -0,0 +1,193 @@
+use super::UIMargin;
+use crate::math::Vec2;
+use codegen::Component;
+use specs::prelude::*;
+
+/// Where the children of a layout are placed across its direction.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UILayoutAlignment {
+    /// Aligns the children to the top of a horizontal layout, or the left of a vertical one.
+    #[default]
+    Start,
+    Center,
+    /// Aligns the children to the bottom of a horizontal layout, or the right of a vertical one.
+    End,
+}
+
+impl UILayoutAlignment {
+    /// Returns the offset of a child of the given extent from the start of the available extent.
+    fn offset(self, available: f32, extent: f32) -> f32 {
+        match self {
+            UILayoutAlignment::Start => 0.0,
+            UILayoutAlignment::Center => (available - extent) * 0.5,
+            UILayoutAlignment::End => available - extent,
+        }
+    }
+}
+
+/// Places the direct child elements of an element in a row, from left to right in the order of the hierarchy.
+/// The children keep their [`UISize`](super::UISize), and their anchor and margin are ignored.
+#[derive(Debug, Clone, Component)]
+#[storage(HashMapStorage)]
+#[auto_register]
+pub struct UIHorizontalLayout {
+    pub padding: UIMargin,
+    /// The gap between two adjacent children.
+    pub spacing: f32,
+    pub alignment: UILayoutAlignment,
+}
+
+impl UIHorizontalLayout {
+    pub fn new(padding: UIMargin, spacing: f32, alignment: UILayoutAlignment) -> Self {
+        Self {
+            padding,
+            spacing,
+            alignment,
+        }
+    }
+
+    /// Returns the positions of the children of the given sizes in an element of the given size.
+    /// The positions are the left-bottom corners of the children.
+    pub fn arrange(&self, size: Vec2, child_sizes: &[Vec2]) -> Vec<Vec2> {
+        let top = size.y - self.padding.top;
+        let available = top - self.padding.bottom;
+        let mut x = self.padding.left;
+
+        Vec::from_iter(child_sizes.iter().map(|child_size| {
+            let position = Vec2::new(
+                x,
+                top - self.alignment.offset(available, child_size.y) - child_size.y,
+            );
+            x += child_size.x + self.spacing;
+            position
+        }))
+    }
+}
+
+/// Places the direct child elements of an element in a column, from top to bottom in the order of the hierarchy.
+/// The children keep their [`UISize`](super::UISize), and their anchor and margin are ignored.
+#[derive(Debug, Clone, Component)]
+#[storage(HashMapStorage)]
+#[auto_register]
+pub struct UIVerticalLayout {
+    pub padding: UIMargin,
+    /// The gap between two adjacent children.
+    pub spacing: f32,
+    pub alignment: UILayoutAlignment,
+}
+
+impl UIVerticalLayout {
+    pub fn new(padding: UIMargin, spacing: f32, alignment: UILayoutAlignment) -> Self {
+        Self {
+            padding,
+            spacing,
+            alignment,
+        }
+    }
+
+    /// Returns the positions of the children of the given sizes in an element of the given size.
+    /// The positions are the left-bottom corners of the children.
+    pub fn arrange(&self, size: Vec2, child_sizes: &[Vec2]) -> Vec<Vec2> {
+        let available = size.x - self.padding.left - self.padding.right;
+        let mut top = size.y - self.padding.top;
+
+        Vec::from_iter(child_sizes.iter().map(|child_size| {
+            let position = Vec2::new(
+                self.padding.left + self.alignment.offset(available, child_size.x),
+                top - child_size.y,
+            );
+            top -= child_size.y + self.spacing;
+            position
+        }))
+    }
+}
+
+/// Places the direct child elements of an element in a grid, row by row from the top-left in the order of the
+/// hierarchy. Each column is as wide as its widest child and each row as tall as its tallest child; the children
+/// keep their [`UISize`](super::UISize) and sit at the top-left of their cells. Their anchor and margin are
+/// ignored.
+#[derive(Debug, Clone, Component)]
+#[storage(HashMapStorage)]
+#[auto_register]
+pub struct UIGridLayout {
+    pub padding: UIMargin,
+    /// The horizontal gap between two columns and the vertical gap between two rows.
+    pub spacing: Vec2,
+    /// The number of columns. It is treated as 1 if 0.
+    pub columns: u32,
+}
+
+impl UIGridLayout {
+    pub fn new(padding: UIMargin, spacing: Vec2, columns: u32) -> Self {
+        Self {
+            padding,
+            spacing,
+            columns,
+        }
+    }
+
+    /// Returns the positions of the children of the given sizes in an element of the given size.
+    /// The positions are the left-bottom corners of the children.
+    pub fn arrange(&self, size: Vec2, child_sizes: &[Vec2]) -> Vec<Vec2> {
+        let columns = self.columns.max(1) as usize;
+        let mut column_widths = vec![0f32; columns.min(child_sizes.len())];
+        let mut row_heights = vec![0f32; child_sizes.len().div_ceil(columns)];
+
+        for (index, child_size) in child_sizes.iter().enumerate() {
+            let width = &mut column_widths[index % columns];
+            *width = width.max(child_size.x);
+            let height = &mut row_heights[index / columns];
+            *height = height.max(child_size.y);
+        }
+
+        let column_lefts =
+            Vec::from_iter(column_widths.iter().scan(self.padding.left, |left, width| {
+                let column_left = *left;
+                *left += width + self.spacing.x;
+                Some(column_left)
+            }));
+        let row_tops = Vec::from_iter(row_heights.iter().scan(
+            size.y - self.padding.top,
+            |top, height| {
+                let row_top = *top;
+                *top -= height + self.spacing.y;
+                Some(row_top)
+            },
+        ));
+
+        Vec::from_iter(child_sizes.iter().enumerate().map(|(index, child_size)| {
+            Vec2::new(
+                column_lefts[index % columns],
+                row_tops[index / columns] - child_size.y,
+            )
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_cells_fit_the_largest_children() {
+        let layout = UIGridLayout::new(UIMargin::new(10.0, 0.0, 20.0, 0.0), Vec2::new(4.0, 2.0), 2);
+        let positions = layout.arrange(
+            Vec2::new(200.0, 100.0),
+            &[
+                Vec2::new(30.0, 10.0),
+                Vec2::new(20.0, 15.0),
+                Vec2::new(40.0, 5.0),
+            ],
+        );
+
+        // The first column is as wide as the third child, and the first row as tall as the second child.
+        assert_eq!(
+            positions,
+            vec![
+                Vec2::new(10.0, 70.0),
+                Vec2::new(54.0, 65.0),
+                Vec2::new(10.0, 58.0),
+            ]
+        );
+    }
+}
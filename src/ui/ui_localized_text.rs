@@ -0,0 +1,71 @@
+use codegen::Component;
+use specs::prelude::*;
+
+/// Sets the text of the [`UITextRenderer`](crate::gfx::UITextRenderer) of the same object to a localized
+/// string, and sets it again whenever the locale changes. See
+/// [`LocalizationManager`](crate::localization::LocalizationManager).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Component)]
+#[storage(HashMapStorage)]
+#[auto_register]
+pub struct UILocalizedText {
+    key: String,
+    args: Vec<(String, String)>,
+    is_dirty: bool,
+}
+
+impl UILocalizedText {
+    pub fn new(key: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            args: Vec::new(),
+            is_dirty: true,
+        }
+    }
+
+    pub fn with_arg(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+        self.set_arg(name, value);
+        self
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn set_key(&mut self, key: impl Into<String>) {
+        self.key = key.into();
+        self.is_dirty = true;
+    }
+
+    pub fn args(&self) -> &[(String, String)] {
+        &self.args
+    }
+
+    /// Sets the value of the argument, e.g. `count` for plural texts.
+    pub fn set_arg(&mut self, name: impl Into<String>, value: impl ToString) {
+        let name = name.into();
+        let value = value.to_string();
+
+        match self.args.iter_mut().find(|(arg, _)| *arg == name) {
+            Some((_, existing)) if *existing == value => return,
+            Some((_, existing)) => *existing = value,
+            None => self.args.push((name, value)),
+        }
+
+        self.is_dirty = true;
+    }
+
+    pub fn clear_args(&mut self) {
+        if !self.args.is_empty() {
+            self.args.clear();
+            self.is_dirty = true;
+        }
+    }
+
+    pub(crate) fn is_dirty(&self) -> bool {
+        self.is_dirty
+    }
+
+    pub(crate) fn reset_dirty(&mut self) {
+        self.is_dirty = false;
+    }
+}
@@ -0,0 +1,133 @@
+use super::UIAnchor;
+use crate::{
+    math::Vec2,
+    object::{ObjectComponent, ObjectHandle, ObjectId},
+};
+use codegen::Component;
+use specs::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UIFillDirection {
+    LeftToRight,
+    RightToLeft,
+    BottomToTop,
+    TopToBottom,
+}
+
+impl UIFillDirection {
+    /// Returns the anchor of the area filled up to the given normalized value.
+    pub fn fill_anchor(self, value: f32) -> UIAnchor {
+        match self {
+            UIFillDirection::LeftToRight => UIAnchor::new(Vec2::ZERO, Vec2::new(value, 1.0)),
+            UIFillDirection::RightToLeft => UIAnchor::new(Vec2::new(1.0 - value, 0.0), Vec2::ONE),
+            UIFillDirection::BottomToTop => UIAnchor::new(Vec2::ZERO, Vec2::new(1.0, value)),
+            UIFillDirection::TopToBottom => UIAnchor::new(Vec2::new(0.0, 1.0 - value), Vec2::ONE),
+        }
+    }
+
+    /// Returns the anchor of a line placed at the given normalized value, spanning the cross axis.
+    pub fn point_anchor(self, value: f32) -> UIAnchor {
+        match self {
+            UIFillDirection::LeftToRight => {
+                UIAnchor::new(Vec2::new(value, 0.0), Vec2::new(value, 1.0))
+            }
+            UIFillDirection::RightToLeft => {
+                UIAnchor::new(Vec2::new(1.0 - value, 0.0), Vec2::new(1.0 - value, 1.0))
+            }
+            UIFillDirection::BottomToTop => {
+                UIAnchor::new(Vec2::new(0.0, value), Vec2::new(1.0, value))
+            }
+            UIFillDirection::TopToBottom => {
+                UIAnchor::new(Vec2::new(0.0, 1.0 - value), Vec2::new(1.0, 1.0 - value))
+            }
+        }
+    }
+
+    /// Converts a point in the local space of an element into a normalized value along this direction.
+    /// The local space is left-bottom based, so the point must be in range `[0, size]` to be inside.
+    pub fn normalized_value(self, point: Vec2, size: Vec2) -> f32 {
+        let value = match self {
+            UIFillDirection::LeftToRight => point.x / size.x,
+            UIFillDirection::RightToLeft => 1.0 - point.x / size.x,
+            UIFillDirection::BottomToTop => point.y / size.y,
+            UIFillDirection::TopToBottom => 1.0 - point.y / size.y,
+        };
+
+        if value.is_finite() {
+            value.clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+}
+
+/// A progress bar. The anchors of the fill object are driven from the value, so the fill object
+/// should be a child of the progress bar with a [`UIElement`](super::UIElement) and a [`UISize`](super::UISize).
+#[derive(Debug, Clone, Component)]
+#[storage(HashMapStorage)]
+#[auto_register]
+pub struct UIProgressBar {
+    /// The normalized value in range `[0, 1]`.
+    pub value: f32,
+    pub fill_direction: UIFillDirection,
+    pub fill: ObjectId,
+}
+
+impl UIProgressBar {
+    pub fn new(fill: ObjectId, fill_direction: UIFillDirection) -> Self {
+        Self {
+            value: 0.0,
+            fill_direction,
+            fill,
+        }
+    }
+}
+
+pub struct UIProgressBarComponent {
+    object: ObjectHandle,
+}
+
+impl ObjectComponent for UIProgressBarComponent {
+    type Component = UIProgressBar;
+
+    fn new(object: ObjectHandle) -> Self {
+        Self { object }
+    }
+
+    fn object(&self) -> &ObjectHandle {
+        &self.object
+    }
+}
+
+impl UIProgressBarComponent {
+    pub fn value(&self) -> f32 {
+        let world = self.object.ctx.world();
+        let progress_bars = world.read_storage::<UIProgressBar>();
+        progress_bars.get(self.object.entity).unwrap().value
+    }
+
+    /// Sets the normalized value of the progress bar. It will be clamped into range `[0, 1]`.
+    pub fn set_value(&self, value: f32) {
+        let world = self.object.ctx.world();
+        let mut progress_bars = world.write_storage::<UIProgressBar>();
+        progress_bars.get_mut(self.object.entity).unwrap().value = value.clamp(0.0, 1.0);
+    }
+
+    pub fn fill_direction(&self) -> UIFillDirection {
+        let world = self.object.ctx.world();
+        let progress_bars = world.read_storage::<UIProgressBar>();
+        progress_bars
+            .get(self.object.entity)
+            .unwrap()
+            .fill_direction
+    }
+
+    pub fn set_fill_direction(&self, fill_direction: UIFillDirection) {
+        let world = self.object.ctx.world();
+        let mut progress_bars = world.write_storage::<UIProgressBar>();
+        progress_bars
+            .get_mut(self.object.entity)
+            .unwrap()
+            .fill_direction = fill_direction;
+    }
+}
@@ -0,0 +1,57 @@
+use crate::{gfx::SafeAreaInsets, math::Vec2};
+use codegen::Component;
+use specs::prelude::*;
+
+/// Shrinks the rect of an element by the safe area insets of the screen.
+/// It is intended for root-level elements that cover the whole screen, so the edges of the element
+/// are assumed to be the edges of the screen.
+#[derive(Debug, Clone, Component)]
+#[storage(HashMapStorage)]
+#[auto_register]
+pub struct UISafeArea {
+    pub left: bool,
+    pub right: bool,
+    pub top: bool,
+    pub bottom: bool,
+}
+
+impl UISafeArea {
+    pub fn new(left: bool, right: bool, top: bool, bottom: bool) -> Self {
+        Self {
+            left,
+            right,
+            top,
+            bottom,
+        }
+    }
+
+    /// Applies the insets to all edges.
+    pub fn all() -> Self {
+        Self {
+            left: true,
+            right: true,
+            top: true,
+            bottom: true,
+        }
+    }
+
+    /// Shrinks the given rect by the insets of the enabled edges.
+    /// The position is the left-bottom corner of the rect.
+    pub fn apply(&self, insets: &SafeAreaInsets, position: Vec2, size: Vec2) -> (Vec2, Vec2) {
+        let left = if self.left { insets.left } else { 0.0 };
+        let right = if self.right { insets.right } else { 0.0 };
+        let top = if self.top { insets.top } else { 0.0 };
+        let bottom = if self.bottom { insets.bottom } else { 0.0 };
+
+        (
+            Vec2::new(position.x + left, position.y + bottom),
+            Vec2::new(size.x - left - right, size.y - top - bottom),
+        )
+    }
+}
+
+impl Default for UISafeArea {
+    fn default() -> Self {
+        Self::all()
+    }
+}
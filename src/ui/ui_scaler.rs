@@ -1,5 +1,6 @@
 use crate::math::Vec2;
-use specs::{prelude::*, Component};
+use codegen::Component;
+use specs::prelude::*;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum UIScaleMode {
@@ -13,6 +14,7 @@ pub enum UIScaleMode {
 
 #[derive(Component, Debug, Clone)]
 #[storage(HashMapStorage)]
+#[auto_register]
 pub struct UIScaler {
     pub mode: UIScaleMode,
     pub reference_size: Vec2,
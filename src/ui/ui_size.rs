@@ -2,13 +2,50 @@ use crate::{
     math::Vec2,
     object::{ObjectComponent, ObjectHandle},
 };
-use specs::{prelude::*, Component};
+use codegen::Component;
+use specs::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UISizeMode {
+    /// Keeps the size resolved by the anchor and margin, or set externally.
+    Fixed,
+    /// Sizes the element to its content: the unwrapped text of its [`UITextRenderer`](crate::gfx::UITextRenderer),
+    /// or else the texels of the sprite of its [`UIElementRenderer`](crate::gfx::UIElementRenderer). `padding`
+    /// is added on both sides, horizontally and vertically. It overrides the
+    /// [`UIContentSizeFitter`](super::UIContentSizeFitter) of the element.
+    FitContent { padding: Vec2 },
+}
+
+impl UISizeMode {
+    /// Adjusts the given rect to the content measured by `measure`, keeping it centered in the rect.
+    /// The rect is kept if the mode is fixed or there is no content.
+    pub fn fit(
+        &self,
+        position: Vec2,
+        size: Vec2,
+        measure: impl FnOnce() -> Option<Vec2>,
+    ) -> (Vec2, Vec2) {
+        let padding = match self {
+            UISizeMode::Fixed => return (position, size),
+            UISizeMode::FitContent { padding } => *padding,
+        };
+        let fitted_size = if let Some(content_size) = measure() {
+            content_size + padding * 2.0
+        } else {
+            return (position, size);
+        };
+
+        (position + (size - fitted_size) * 0.5, fitted_size)
+    }
+}
 
 #[derive(Debug, Clone, Copy, Component)]
 #[storage(HashMapStorage)]
+#[auto_register]
 pub struct UISize {
     pub width: f32,
     pub height: f32,
+    pub mode: UISizeMode,
 }
 
 impl UISize {
@@ -16,6 +53,7 @@ impl UISize {
         Self {
             width: 0f32,
             height: 0f32,
+            mode: UISizeMode::Fixed,
         }
     }
 
@@ -23,6 +61,15 @@ impl UISize {
         Self {
             width: vec.x,
             height: vec.y,
+            mode: UISizeMode::Fixed,
+        }
+    }
+
+    /// Creates a size that fits the content of the element, with `padding` on both sides.
+    pub fn fit_content(padding: Vec2) -> Self {
+        Self {
+            mode: UISizeMode::FitContent { padding },
+            ..Self::new()
         }
     }
 
@@ -61,4 +108,21 @@ impl UISizeComponent {
         ui_size.width = width;
         ui_size.height = height;
     }
+
+    pub fn mode(&self) -> UISizeMode {
+        let world = self.object.ctx.world();
+        let ui_sizes = world.read_storage::<UISize>();
+        ui_sizes.get(self.object.entity).unwrap().mode
+    }
+
+    pub fn set_mode(&self, mode: UISizeMode) {
+        let mut object_mgr = self.object.ctx.object_mgr_mut();
+        object_mgr
+            .object_hierarchy_mut()
+            .set_dirty(self.object.object_id);
+
+        let world = self.object.ctx.world();
+        let mut ui_sizes = world.write_storage::<UISize>();
+        ui_sizes.get_mut(self.object.entity).unwrap().mode = mode;
+    }
 }
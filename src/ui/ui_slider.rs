@@ -0,0 +1,235 @@
+use super::{UIProgressBar, UISizeComponent};
+use crate::{
+    math::{Vec2, Vec4},
+    object::{Object, ObjectComponent, ObjectHandle, ObjectId},
+    object_event::{
+        object_event_types::{DragEvent, DragStartEvent, ValueChangedEvent},
+        ObjectEventHandler,
+    },
+    transform::TransformComponent,
+    use_context,
+};
+use codegen::Component;
+use specs::prelude::*;
+
+/// A slider. It extends [`UIProgressBar`], which must be attached to the same object;
+/// the normalized value of the progress bar is mapped into range `[min, max]` of the slider.
+/// The anchors of the handle object are driven from the value, so the handle object should be
+/// a child of the slider with a [`UIElement`](super::UIElement) and a [`UISize`](super::UISize).
+#[derive(Debug, Clone, Component)]
+#[storage(HashMapStorage)]
+#[auto_register]
+pub struct UISlider {
+    pub min: f32,
+    pub max: f32,
+    /// The interval between selectable values. Zero means that the value is continuous.
+    pub step: f32,
+    pub handle: ObjectId,
+}
+
+impl UISlider {
+    pub fn new(handle: ObjectId, min: f32, max: f32, step: f32) -> Self {
+        Self {
+            min,
+            max,
+            step,
+            handle,
+        }
+    }
+
+    /// Clamps the given value into range `[min, max]` and snaps it to the nearest step.
+    pub fn snap(&self, value: f32) -> f32 {
+        let value = value.max(self.min).min(self.max);
+
+        if self.step <= 0.0 {
+            return value;
+        }
+
+        let snapped = self.min + ((value - self.min) / self.step).round() * self.step;
+
+        // The maximum must be selectable even if the range is not a multiple of the step.
+        if self.max - value < (value - snapped).abs() {
+            self.max
+        } else {
+            snapped.min(self.max)
+        }
+    }
+
+    /// Converts the given value into a normalized value in range `[0, 1]`.
+    pub fn to_normalized(&self, value: f32) -> f32 {
+        if self.max <= self.min {
+            return 0.0;
+        }
+
+        ((value - self.min) / (self.max - self.min)).clamp(0.0, 1.0)
+    }
+
+    /// Converts the given normalized value into a value in range `[min, max]`.
+    pub fn from_normalized(&self, normalized: f32) -> f32 {
+        self.min + (self.max - self.min) * normalized
+    }
+}
+
+pub struct UISliderComponent {
+    object: ObjectHandle,
+}
+
+impl ObjectComponent for UISliderComponent {
+    type Component = UISlider;
+
+    fn new(object: ObjectHandle) -> Self {
+        Self { object }
+    }
+
+    fn object(&self) -> &ObjectHandle {
+        &self.object
+    }
+}
+
+impl UISliderComponent {
+    pub fn value(&self) -> f32 {
+        let world = self.object.ctx.world();
+        let sliders = world.read_storage::<UISlider>();
+        let progress_bars = world.read_storage::<UIProgressBar>();
+        let slider = sliders.get(self.object.entity).unwrap();
+        let progress_bar = progress_bars.get(self.object.entity).unwrap();
+        slider.from_normalized(progress_bar.value)
+    }
+
+    /// Sets the value of the slider. The value will be clamped and snapped to the step.
+    /// Dispatches [`ValueChangedEvent`] to the slider if the value has been changed.
+    pub fn set_value(&self, value: f32) {
+        let value = {
+            let world = self.object.ctx.world();
+            let sliders = world.read_storage::<UISlider>();
+            let mut progress_bars = world.write_storage::<UIProgressBar>();
+            let slider = sliders.get(self.object.entity).unwrap();
+            let progress_bar = progress_bars.get_mut(self.object.entity).unwrap();
+
+            let value = slider.snap(value);
+
+            if value == slider.from_normalized(progress_bar.value) {
+                return;
+            }
+
+            progress_bar.value = slider.to_normalized(value);
+            value
+        };
+
+        self.object
+            .ctx
+            .object_event_mgr()
+            .dispatch(self.object.object_id, &ValueChangedEvent { value });
+    }
+
+    /// Moves the value by the given number of steps, e.g. in response to arrow keys.
+    /// A continuous slider moves by 1% of its range per step.
+    pub fn step_by(&self, steps: i32) {
+        let step = {
+            let world = self.object.ctx.world();
+            let sliders = world.read_storage::<UISlider>();
+            let slider = sliders.get(self.object.entity).unwrap();
+
+            if slider.step <= 0.0 {
+                (slider.max - slider.min) * 0.01
+            } else {
+                slider.step
+            }
+        };
+
+        self.set_value(self.value() + step * steps as f32);
+    }
+
+    /// Registers a callback that is invoked whenever the value of the slider has been changed.
+    pub fn on_value_changed(&self, mut callback: impl FnMut(Object, f32) + 'static) {
+        self.object.ctx.object_event_mgr().add_handler(
+            ObjectEventHandler::<ValueChangedEvent>::new(
+                Object::new(self.object.entity, self.object.object_id),
+                move |object, event| callback(object, event.value),
+            ),
+        );
+    }
+
+    /// Registers the drag handlers that let the user move the slider by the mouse.
+    /// The slider must have an interactable [`UIElement`](super::UIElement) to receive the drag events.
+    pub fn bind_drag_events(&self) {
+        let object = Object::new(self.object.entity, self.object.object_id);
+        let object_event_mgr = self.object.ctx.object_event_mgr();
+
+        object_event_mgr.add_handler(ObjectEventHandler::<DragStartEvent>::new(
+            object,
+            |object, event| handle_drag(object, event.position),
+        ));
+        object_event_mgr.add_handler(ObjectEventHandler::<DragEvent>::new(
+            object,
+            |object, event| handle_drag(object, event.position),
+        ));
+    }
+}
+
+fn handle_drag(object: Object, position: Vec2) {
+    let object = use_context().object_mgr().object_handle(object.object_id());
+    let inverse_matrix = object
+        .component::<TransformComponent>()
+        .world_inverse_matrix();
+    let point: Vec2 = (Vec4::new(position.x, position.y, 0.0, 1.0) * &inverse_matrix).into();
+    let size = object.component::<UISizeComponent>().size();
+
+    let value = {
+        let world = object.ctx.world();
+        let sliders = world.read_storage::<UISlider>();
+        let progress_bars = world.read_storage::<UIProgressBar>();
+        let slider = sliders.get(object.entity).unwrap();
+        let progress_bar = progress_bars.get(object.entity).unwrap();
+        slider.from_normalized(progress_bar.fill_direction.normalized_value(point, size))
+    };
+
+    object.component::<UISliderComponent>().set_value(value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::num::NonZeroU32;
+
+    fn new_slider(min: f32, max: f32, step: f32) -> UISlider {
+        UISlider::new(ObjectId::new(NonZeroU32::new(1).unwrap()), min, max, step)
+    }
+
+    #[test]
+    fn slider_snap_clamps_value() {
+        let slider = new_slider(-1.0, 1.0, 0.0);
+
+        assert_eq!(slider.snap(-2.0), -1.0);
+        assert_eq!(slider.snap(0.25), 0.25);
+        assert_eq!(slider.snap(2.0), 1.0);
+    }
+
+    #[test]
+    fn slider_snap_steps_value() {
+        let slider = new_slider(0.0, 10.0, 2.5);
+
+        assert_eq!(slider.snap(1.0), 0.0);
+        assert_eq!(slider.snap(1.3), 2.5);
+        assert_eq!(slider.snap(8.9), 10.0);
+
+        let slider = new_slider(0.0, 10.0, 3.0);
+
+        assert_eq!(slider.snap(9.4), 9.0);
+        assert_eq!(slider.snap(9.6), 10.0);
+        assert_eq!(slider.snap(10.0), 10.0);
+    }
+
+    #[test]
+    fn slider_normalized_round_trip() {
+        let slider = new_slider(10.0, 20.0, 0.0);
+
+        assert_eq!(slider.to_normalized(15.0), 0.5);
+        assert_eq!(slider.from_normalized(0.5), 15.0);
+        assert_eq!(slider.to_normalized(25.0), 1.0);
+
+        let slider = new_slider(1.0, 1.0, 0.0);
+
+        assert_eq!(slider.to_normalized(1.0), 0.0);
+    }
+}
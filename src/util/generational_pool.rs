@@ -0,0 +1,165 @@
+/// A handle to an item of a [`GenerationalPool`]. It stops resolving once the item is removed, even if its
+/// slot is reused by another item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GenerationalHandle {
+    pub index: u32,
+    pub generation: u32,
+}
+
+#[derive(Debug, Clone)]
+struct Slot<T> {
+    generation: u32,
+    item: Option<T>,
+}
+
+/// A pool that reuses the slots of removed items, detecting stale handles by generations.
+#[derive(Debug, Clone)]
+pub struct GenerationalPool<T> {
+    slots: Vec<Slot<T>>,
+    free_indices: Vec<u32>,
+    len: usize,
+}
+
+impl<T> GenerationalPool<T> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn insert(&mut self, item: T) -> GenerationalHandle {
+        self.len += 1;
+
+        match self.free_indices.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index as usize];
+                slot.item = Some(item);
+
+                GenerationalHandle {
+                    index,
+                    generation: slot.generation,
+                }
+            }
+            None => {
+                let index = self.slots.len() as u32;
+                self.slots.push(Slot {
+                    generation: 0,
+                    item: Some(item),
+                });
+
+                GenerationalHandle {
+                    index,
+                    generation: 0,
+                }
+            }
+        }
+    }
+
+    /// Removes the item, invalidating all handles to it. Returns `None` if the handle is stale.
+    pub fn remove(&mut self, handle: GenerationalHandle) -> Option<T> {
+        let slot = self.slots.get_mut(handle.index as usize)?;
+
+        if slot.generation != handle.generation {
+            return None;
+        }
+
+        let item = slot.item.take()?;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free_indices.push(handle.index);
+        self.len -= 1;
+
+        Some(item)
+    }
+
+    pub fn contains(&self, handle: GenerationalHandle) -> bool {
+        self.get(handle).is_some()
+    }
+
+    /// Returns the item, or `None` if it has been removed.
+    pub fn get(&self, handle: GenerationalHandle) -> Option<&T> {
+        self.slots
+            .get(handle.index as usize)
+            .filter(|slot| slot.generation == handle.generation)
+            .and_then(|slot| slot.item.as_ref())
+    }
+
+    pub fn get_mut(&mut self, handle: GenerationalHandle) -> Option<&mut T> {
+        self.slots
+            .get_mut(handle.index as usize)
+            .filter(|slot| slot.generation == handle.generation)
+            .and_then(|slot| slot.item.as_mut())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (GenerationalHandle, &T)> {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            slot.item.as_ref().map(|item| {
+                (
+                    GenerationalHandle {
+                        index: index as u32,
+                        generation: slot.generation,
+                    },
+                    item,
+                )
+            })
+        })
+    }
+
+    /// Removes all items. The handles issued so far never resolve again.
+    pub fn clear(&mut self) {
+        self.free_indices.clear();
+
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            if slot.item.take().is_some() {
+                slot.generation = slot.generation.wrapping_add(1);
+            }
+
+            self.free_indices.push(index as u32);
+        }
+
+        self.len = 0;
+    }
+}
+
+impl<T> Default for GenerationalPool<T> {
+    fn default() -> Self {
+        Self {
+            slots: Vec::new(),
+            free_indices: Vec::new(),
+            len: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stale_handle_does_not_resolve() {
+        let mut pool = GenerationalPool::new();
+        let foo = pool.insert("foo");
+        let bar = pool.insert("bar");
+
+        assert_eq!(pool.remove(foo), Some("foo"));
+        assert_eq!(pool.get(foo), None);
+        assert_eq!(pool.remove(foo), None);
+
+        let baz = pool.insert("baz");
+        assert_eq!(baz.index, foo.index);
+        assert_ne!(baz.generation, foo.generation);
+        assert_eq!(pool.get(foo), None);
+        assert_eq!(pool.get(baz), Some(&"baz"));
+        assert_eq!(pool.get(bar), Some(&"bar"));
+        assert_eq!(pool.len(), 2);
+
+        pool.clear();
+        assert!(pool.is_empty());
+        assert_eq!(pool.get(bar), None);
+    }
+}
@@ -1,3 +1,17 @@
+mod generational_pool;
+mod rng;
+mod rng_manager;
 mod slot_map;
+mod spatial_hash;
+mod stable_hasher;
+mod tween;
+mod world_hasher;
 
+pub use generational_pool::*;
+pub use rng::*;
+pub use rng_manager::*;
 pub use slot_map::*;
+pub use spatial_hash::*;
+pub use stable_hasher::*;
+pub use tween::*;
+pub use world_hasher::*;
@@ -0,0 +1,148 @@
+const PCG_MULTIPLIER: u64 = 6364136223846793005;
+const DEFAULT_STREAM: u64 = 1442695040888963407;
+
+/// A deterministic random number generator (PCG32, XSH-RR variant).
+/// The same seed and stream always yield the same sequence on every platform.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Rng {
+    seed: u64,
+    state: u64,
+    increment: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self::with_stream(seed, DEFAULT_STREAM)
+    }
+
+    /// Creates a generator on the given stream. Generators with the same seed but different streams
+    /// yield independent sequences, which is useful to give each system its own generator.
+    pub fn with_stream(seed: u64, stream: u64) -> Self {
+        let mut rng = Self {
+            seed,
+            state: 0,
+            increment: (stream << 1) | 1,
+        };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+        rng
+    }
+
+    /// Creates a generator seeded from the current time. Use it only when reproducibility is not needed.
+    pub fn from_time() -> Self {
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|duration| duration.as_nanos() as u64)
+            .unwrap_or_default();
+        Self::new(seed)
+    }
+
+    /// Returns the seed that the generator is created with, e.g. to record it for replays.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Creates a new generator on a stream drawn from this generator.
+    pub fn fork(&mut self) -> Self {
+        let stream = (self.next_u32() as u64) << 32 | self.next_u32() as u64;
+        Self::with_stream(self.seed, stream)
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let state = self.state;
+        self.state = state
+            .wrapping_mul(PCG_MULTIPLIER)
+            .wrapping_add(self.increment);
+
+        let xor_shifted = (((state >> 18) ^ state) >> 27) as u32;
+        let rotation = (state >> 59) as u32;
+        xor_shifted.rotate_right(rotation)
+    }
+
+    /// Returns a number in range `[0, 1)`.
+    pub fn next_f32(&mut self) -> f32 {
+        // The upper 24 bits fill the mantissa exactly, so the result never rounds up to 1.
+        (self.next_u32() >> 8) as f32 * (1.0 / (1u32 << 24) as f32)
+    }
+
+    /// Returns an integer in range `[lo, hi)`. Returns `lo` if the range is empty.
+    pub fn range(&mut self, lo: i32, hi: i32) -> i32 {
+        if hi <= lo {
+            return lo;
+        }
+
+        let span = hi.abs_diff(lo);
+        // Rejects the values in the last incomplete span to avoid the modulo bias.
+        let threshold = span.wrapping_neg() % span;
+
+        loop {
+            let value = self.next_u32();
+
+            if threshold <= value {
+                return lo.wrapping_add((value % span) as i32);
+            }
+        }
+    }
+
+    /// Returns a number in range `[lo, hi)`.
+    pub fn range_f32(&mut self, lo: f32, hi: f32) -> f32 {
+        lo + (hi - lo) * self.next_f32()
+    }
+
+    /// Shuffles the slice in place (Fisher-Yates).
+    pub fn shuffle<T>(&mut self, slice: &mut [T]) {
+        for index in (1..slice.len()).rev() {
+            let other = self.range(0, index as i32 + 1) as usize;
+            slice.swap(index, other);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_yields_same_sequence() {
+        let mut lhs = Rng::new(42);
+        let mut rhs = Rng::new(42);
+
+        for _ in 0..64 {
+            assert_eq!(lhs.next_u32(), rhs.next_u32());
+        }
+
+        // Matches the reference implementation, so that the sequence never changes across versions.
+        let mut rng = Rng::with_stream(42, 54);
+        let sequence = [rng.next_u32(), rng.next_u32(), rng.next_u32()];
+        assert_eq!(sequence, [0xa15c02b7, 0x7b47f409, 0xba1d3330]);
+
+        let mut lhs = Rng::new(7);
+        let mut rhs = Rng::new(7);
+        let mut lhs_items = [0, 1, 2, 3, 4, 5, 6, 7];
+        let mut rhs_items = lhs_items;
+        lhs.shuffle(&mut lhs_items);
+        rhs.shuffle(&mut rhs_items);
+        assert_eq!(lhs_items, rhs_items);
+        assert_eq!(lhs.fork(), rhs.fork());
+    }
+
+    #[test]
+    fn values_stay_in_range() {
+        let mut rng = Rng::new(1);
+
+        for _ in 0..1000 {
+            let value = rng.next_f32();
+            assert!((0.0..1.0).contains(&value));
+
+            let value = rng.range(-3, 5);
+            assert!((-3..5).contains(&value));
+        }
+
+        assert_eq!(rng.range(2, 2), 2);
+        assert_ne!(
+            Rng::with_stream(1, 1).next_u32(),
+            Rng::with_stream(1, 2).next_u32()
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use super::{Rng, StableHasher};
+use std::{collections::HashMap, hash::Hasher};
+
+/// Keeps named random number generators, all derived from a single run seed. Record the run seed alongside an
+/// input recording and [`reseed`](Self::reseed) with it before playing the recording back, so that the streams
+/// yield the same values again.
+///
+/// Each stream is seeded from the run seed and the name only, so a stream does not change when other streams
+/// are created or drawn from.
+#[derive(Debug, Clone)]
+pub struct RngManager {
+    run_seed: u64,
+    streams: HashMap<String, Rng>,
+}
+
+impl RngManager {
+    pub fn new(run_seed: u64) -> Self {
+        Self {
+            run_seed,
+            streams: HashMap::new(),
+        }
+    }
+
+    pub fn run_seed(&self) -> u64 {
+        self.run_seed
+    }
+
+    /// Restarts every stream from the given run seed.
+    pub fn reseed(&mut self, run_seed: u64) {
+        self.run_seed = run_seed;
+        self.streams.clear();
+    }
+
+    /// Returns the stream of the name, creating it on the first use.
+    pub fn rng(&mut self, name: &str) -> &mut Rng {
+        if !self.streams.contains_key(name) {
+            let stream = Rng::with_stream(self.run_seed, stream_of(name));
+            self.streams.insert(name.to_owned(), stream);
+        }
+
+        self.streams.get_mut(name).unwrap()
+    }
+}
+
+fn stream_of(name: &str) -> u64 {
+    let mut hasher = StableHasher::new();
+    hasher.write(name.as_bytes());
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn streams_do_not_depend_on_each_other() {
+        let mut lhs = RngManager::new(42);
+        let mut rhs = RngManager::new(42);
+
+        // Creating and drawing from another stream first does not shift the gameplay stream.
+        for _ in 0..10 {
+            rhs.rng("particles").next_u32();
+        }
+
+        let sequence = |manager: &mut RngManager| {
+            (0..8)
+                .map(|_| manager.rng("gameplay").range(0, 100))
+                .collect::<Vec<_>>()
+        };
+        let expected = sequence(&mut lhs);
+        assert_eq!(sequence(&mut rhs), expected);
+        assert_ne!(sequence(&mut lhs), expected);
+
+        lhs.reseed(42);
+        assert_eq!(sequence(&mut lhs), expected);
+
+        let mut other = RngManager::new(42);
+        assert_ne!(
+            (0..8)
+                .map(|_| other.rng("particles").range(0, 100))
+                .collect::<Vec<_>>(),
+            expected
+        );
+    }
+}
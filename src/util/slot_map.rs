@@ -43,13 +43,13 @@ impl<T: Sized> SlotMap<T> {
     }
 
     pub fn deallocate(&mut self, id: usize) {
-        let index = if let Some(index) = self.id_index_map.remove(&id) {
-            index
-        } else {
-            return;
-        };
+        self.take(id);
+    }
 
-        self.data.swap_remove(index);
+    /// Removes the item and returns it. The id may be reused by the next allocations.
+    pub fn take(&mut self, id: usize) -> Option<T> {
+        let index = self.id_index_map.remove(&id)?;
+        let item = self.data.swap_remove(index);
 
         let last_index = self.data.len();
 
@@ -59,9 +59,10 @@ impl<T: Sized> SlotMap<T> {
             self.index_id_map.insert(index, last_id);
         }
 
-        self.id_index_map.remove(&id);
         self.index_id_map.remove(&last_index);
         self.free_ids.push(id);
+
+        Some(item)
     }
 }
 
@@ -0,0 +1,150 @@
+use crate::math::Vec3;
+use std::collections::HashMap;
+
+/// Buckets items by their positions into cubic cells, to find nearby items without visiting all of them.
+/// 2D usage is also supported by keeping the z coordinate zero.
+#[derive(Debug, Clone)]
+pub struct SpatialHash<T> {
+    cell_size: f32,
+    cells: HashMap<(i32, i32, i32), Vec<(Vec3, T)>>,
+    len: usize,
+}
+
+impl<T> SpatialHash<T> {
+    /// Creates an empty hash. The cell size should be around the usual query radius.
+    pub fn new(cell_size: f32) -> Self {
+        debug_assert!(0.0 < cell_size, "cell size must be positive");
+
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+            len: 0,
+        }
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn insert(&mut self, pos: Vec3, item: T) {
+        self.cells
+            .entry(self.cell_of(pos))
+            .or_default()
+            .push((pos, item));
+        self.len += 1;
+    }
+
+    /// Returns all items within the given distance from the center, including the boundary.
+    /// If the radius covers more cells than there are allocated, the allocated cells are visited instead, so that
+    /// a large radius costs no more than visiting all items.
+    pub fn query_radius(&self, center: Vec3, radius: f32) -> impl Iterator<Item = (Vec3, &T)> {
+        let min = self.cell_of(center - Vec3::new(radius, radius, radius));
+        let max = self.cell_of(center + Vec3::new(radius, radius, radius));
+        let radius_square = radius * radius;
+
+        let span = |min: i32, max: i32| (max as i64 - min as i64 + 1).max(0) as u128;
+        let cell_count = span(min.0, max.0) * span(min.1, max.1) * span(min.2, max.2);
+        let is_range_small = cell_count <= self.cells.len() as u128;
+
+        let by_range = is_range_small.then(|| {
+            (min.0..=max.0)
+                .flat_map(move |x| (min.1..=max.1).map(move |y| (x, y)))
+                .flat_map(move |(x, y)| (min.2..=max.2).map(move |z| (x, y, z)))
+                .filter_map(|cell| self.cells.get(&cell))
+        });
+        let by_cells = (!is_range_small).then(|| {
+            self.cells
+                .iter()
+                .filter(move |(cell, _)| {
+                    (min.0..=max.0).contains(&cell.0)
+                        && (min.1..=max.1).contains(&cell.1)
+                        && (min.2..=max.2).contains(&cell.2)
+                })
+                .map(|(_, items)| items)
+        });
+
+        by_range
+            .into_iter()
+            .flatten()
+            .chain(by_cells.into_iter().flatten())
+            .flatten()
+            .filter(move |(pos, _)| Vec3::distance_square(*pos, center) <= radius_square)
+            .map(|(pos, item)| (*pos, item))
+    }
+
+    /// Removes all items, keeping the allocated cells for reuse.
+    pub fn clear(&mut self) {
+        for cell in self.cells.values_mut() {
+            cell.clear();
+        }
+
+        self.len = 0;
+    }
+
+    fn cell_of(&self, pos: Vec3) -> (i32, i32, i32) {
+        (
+            (pos.x / self.cell_size).floor() as i32,
+            (pos.y / self.cell_size).floor() as i32,
+            (pos.z / self.cell_size).floor() as i32,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_radius_returns_items_within_range() {
+        let mut hash = SpatialHash::new(1.0);
+        hash.insert(Vec3::new(0.0, 0.0, 0.0), "origin");
+        hash.insert(Vec3::new(1.5, 0.0, 0.0), "inside");
+        hash.insert(Vec3::new(0.0, -2.0, 0.0), "boundary");
+        hash.insert(Vec3::new(0.0, 2.01, 0.0), "just outside");
+        hash.insert(Vec3::new(1.5, 1.5, 0.0), "corner");
+        hash.insert(Vec3::new(-10.0, 0.0, 5.0), "far");
+
+        let mut items = Vec::from_iter(hash.query_radius(Vec3::ZERO, 2.0).map(|(_, item)| *item));
+        items.sort_unstable();
+        assert_eq!(items, vec!["boundary", "inside", "origin"]);
+
+        // The radius covers fewer cells than there are allocated.
+        let items = Vec::from_iter(
+            hash.query_radius(Vec3::new(1.5, 0.1, 0.1), 0.2)
+                .map(|(_, item)| *item),
+        );
+        assert_eq!(items, vec!["inside"]);
+
+        // The radius covers more cells than there are allocated.
+        let mut items = Vec::from_iter(hash.query_radius(Vec3::ZERO, 10.5).map(|(_, item)| *item));
+        items.sort_unstable();
+        assert_eq!(
+            items,
+            vec!["boundary", "corner", "inside", "just outside", "origin"]
+        );
+
+        hash.clear();
+        assert!(hash.is_empty());
+        assert_eq!(hash.query_radius(Vec3::ZERO, 2.0).count(), 0);
+    }
+
+    #[test]
+    fn query_huge_radius_visits_allocated_cells() {
+        let mut hash = SpatialHash::new(0.5);
+        hash.insert(Vec3::new(1.0e6, -1.0e6, 0.0), "far");
+        hash.insert(Vec3::new(0.0, 0.0, 0.0), "origin");
+
+        // Walking the cells in range would take forever.
+        let mut items = Vec::from_iter(hash.query_radius(Vec3::ZERO, 1.0e7).map(|(_, item)| *item));
+        items.sort_unstable();
+        assert_eq!(items, vec!["far", "origin"]);
+    }
+}
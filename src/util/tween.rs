@@ -0,0 +1,344 @@
+use crate::{
+    gfx::Color,
+    math::{Vec2, Vec3, Vec4},
+    time::TimeManager,
+};
+use std::{f32::consts::PI, time::Duration};
+
+/// A value that can be interpolated linearly. `t` may go out of `[0, 1]` for easings that overshoot.
+pub trait Lerp: Copy {
+    fn lerp(from: Self, to: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(from: Self, to: Self, t: f32) -> Self {
+        from + (to - from) * t
+    }
+}
+
+impl Lerp for Vec2 {
+    fn lerp(from: Self, to: Self, t: f32) -> Self {
+        Vec2::lerp_unclamped(from, to, t)
+    }
+}
+
+impl Lerp for Vec3 {
+    fn lerp(from: Self, to: Self, t: f32) -> Self {
+        Vec3::lerp_unclamped(from, to, t)
+    }
+}
+
+impl Lerp for Vec4 {
+    fn lerp(from: Self, to: Self, t: f32) -> Self {
+        Vec4::lerp_unclamped(from, to, t)
+    }
+}
+
+impl Lerp for Color {
+    fn lerp(from: Self, to: Self, t: f32) -> Self {
+        Color::lerp_unclamped(from, to, t)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Easing {
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    QuartIn,
+    QuartOut,
+    QuartInOut,
+    /// Pulls back below the start before moving on.
+    BackIn,
+    /// Overshoots the end before settling.
+    BackOut,
+    BackInOut,
+    /// Oscillates around the start with a growing amplitude.
+    ElasticIn,
+    /// Oscillates around the end with a shrinking amplitude.
+    ElasticOut,
+    ElasticInOut,
+    BounceIn,
+    BounceOut,
+    BounceInOut,
+}
+
+impl Easing {
+    /// Maps the progress in range `[0, 1]` to the interpolation factor.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0f32, 1f32);
+
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => 1f32 - (1f32 - t) * (1f32 - t),
+            Easing::QuadInOut => {
+                if t < 0.5f32 {
+                    2f32 * t * t
+                } else {
+                    1f32 - (-2f32 * t + 2f32).powi(2) * 0.5f32
+                }
+            }
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => 1f32 - (1f32 - t).powi(3),
+            Easing::CubicInOut => {
+                if t < 0.5f32 {
+                    4f32 * t * t * t
+                } else {
+                    1f32 - (-2f32 * t + 2f32).powi(3) * 0.5f32
+                }
+            }
+            Easing::QuartIn => t.powi(4),
+            Easing::QuartOut => 1f32 - (1f32 - t).powi(4),
+            Easing::QuartInOut => {
+                if t < 0.5f32 {
+                    8f32 * t.powi(4)
+                } else {
+                    1f32 - (-2f32 * t + 2f32).powi(4) * 0.5f32
+                }
+            }
+            Easing::BackIn => BACK_C3 * t * t * t - BACK_C1 * t * t,
+            Easing::BackOut => 1f32 + BACK_C3 * (t - 1f32).powi(3) + BACK_C1 * (t - 1f32).powi(2),
+            Easing::BackInOut => {
+                const C2: f32 = BACK_C1 * 1.525f32;
+
+                if t < 0.5f32 {
+                    (2f32 * t).powi(2) * ((C2 + 1f32) * 2f32 * t - C2) * 0.5f32
+                } else {
+                    ((2f32 * t - 2f32).powi(2) * ((C2 + 1f32) * (2f32 * t - 2f32) + C2) + 2f32)
+                        * 0.5f32
+                }
+            }
+            // The oscillations never end exactly at the endpoints, so they are pinned.
+            Easing::ElasticIn | Easing::ElasticOut | Easing::ElasticInOut
+                if t == 0f32 || t == 1f32 =>
+            {
+                t
+            }
+            Easing::ElasticIn => {
+                -(2f32.powf(10f32 * t - 10f32)) * ((10f32 * t - 10.75f32) * ELASTIC_C4).sin()
+            }
+            Easing::ElasticOut => {
+                2f32.powf(-10f32 * t) * ((10f32 * t - 0.75f32) * ELASTIC_C4).sin() + 1f32
+            }
+            Easing::ElasticInOut => {
+                const C5: f32 = 2f32 * PI / 4.5f32;
+                let sin = ((20f32 * t - 11.125f32) * C5).sin();
+
+                if t < 0.5f32 {
+                    -(2f32.powf(20f32 * t - 10f32) * sin) * 0.5f32
+                } else {
+                    2f32.powf(-20f32 * t + 10f32) * sin * 0.5f32 + 1f32
+                }
+            }
+            Easing::BounceIn => 1f32 - bounce_out(1f32 - t),
+            Easing::BounceOut => bounce_out(t),
+            Easing::BounceInOut => {
+                if t < 0.5f32 {
+                    (1f32 - bounce_out(1f32 - 2f32 * t)) * 0.5f32
+                } else {
+                    (1f32 + bounce_out(2f32 * t - 1f32)) * 0.5f32
+                }
+            }
+        }
+    }
+}
+
+const BACK_C1: f32 = 1.70158f32;
+const BACK_C3: f32 = BACK_C1 + 1f32;
+const ELASTIC_C4: f32 = 2f32 * PI / 3f32;
+
+fn bounce_out(t: f32) -> f32 {
+    const N: f32 = 7.5625f32;
+    const D: f32 = 2.75f32;
+
+    if t < 1f32 / D {
+        N * t * t
+    } else if t < 2f32 / D {
+        let t = t - 1.5f32 / D;
+        N * t * t + 0.75f32
+    } else if t < 2.5f32 / D {
+        let t = t - 2.25f32 / D;
+        N * t * t + 0.9375f32
+    } else {
+        let t = t - 2.625f32 / D;
+        N * t * t + 0.984375f32
+    }
+}
+
+/// Animates a value from `from` to `to` over the duration, shaped by the easing.
+#[derive(Debug, Clone)]
+pub struct Tween<T>
+where
+    T: Lerp,
+{
+    from: T,
+    to: T,
+    duration: Duration,
+    elapsed: Duration,
+    easing: Easing,
+}
+
+impl<T> Tween<T>
+where
+    T: Lerp,
+{
+    pub fn new(from: T, to: T, duration: Duration, easing: Easing) -> Self {
+        Self {
+            from,
+            to,
+            duration,
+            elapsed: Duration::ZERO,
+            easing,
+        }
+    }
+
+    pub fn from(&self) -> T {
+        self.from
+    }
+
+    pub fn to(&self) -> T {
+        self.to
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn easing(&self) -> Easing {
+        self.easing
+    }
+
+    /// Advances the tween by the delta time of the frame. Call it once per frame.
+    pub fn update(&mut self, time_mgr: &TimeManager) {
+        self.advance(time_mgr.delta_time());
+    }
+
+    /// Advances the tween by the given time. It stops at the end of the duration.
+    pub fn advance(&mut self, dt: Duration) {
+        self.elapsed = (self.elapsed + dt).min(self.duration);
+    }
+
+    /// Returns the elapsed portion of the duration, in range `[0, 1]`.
+    pub fn progress(&self) -> f32 {
+        if self.duration.is_zero() {
+            return 1f32;
+        }
+
+        self.elapsed.as_secs_f32() / self.duration.as_secs_f32()
+    }
+
+    pub fn value(&self) -> T {
+        T::lerp(self.from, self.to, self.easing.apply(self.progress()))
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.duration <= self.elapsed
+    }
+
+    /// Rewinds the tween to the beginning.
+    pub fn reset(&mut self) {
+        self.elapsed = Duration::ZERO;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample<T: Lerp>(tween: &mut Tween<T>) -> [T; 3] {
+        let value_0 = tween.value();
+        tween.advance(tween.duration() / 2);
+        let value_half = tween.value();
+        tween.advance(tween.duration());
+        [value_0, value_half, tween.value()]
+    }
+
+    #[test]
+    fn tween_samples_linear_and_ease_in_out() {
+        let duration = Duration::from_secs(2);
+
+        let mut tween = Tween::new(10f32, 20f32, duration, Easing::Linear);
+        assert_eq!(sample(&mut tween), [10f32, 15f32, 20f32]);
+        assert!(tween.is_finished());
+
+        let mut tween = Tween::new(0f32, 8f32, duration, Easing::QuadInOut);
+        tween.advance(duration / 4);
+        // Starts slower than linear.
+        assert_eq!(tween.value(), 1f32);
+        tween.reset();
+        assert_eq!(sample(&mut tween), [0f32, 4f32, 8f32]);
+
+        let mut tween = Tween::new(
+            Vec2::new(0f32, 0f32),
+            Vec2::new(2f32, 4f32),
+            duration,
+            Easing::CubicInOut,
+        );
+        assert_eq!(
+            sample(&mut tween),
+            [
+                Vec2::new(0f32, 0f32),
+                Vec2::new(1f32, 2f32),
+                Vec2::new(2f32, 4f32)
+            ]
+        );
+
+        let mut tween = Tween::new(Color::black(), Color::white(), duration, Easing::Linear);
+        assert!(!tween.is_finished());
+        assert_eq!(
+            sample(&mut tween),
+            [
+                Color::black(),
+                Color::from_rgb(0.5f32, 0.5f32, 0.5f32),
+                Color::white()
+            ]
+        );
+    }
+
+    #[test]
+    fn bounce_reaches_endpoints() {
+        for easing in [Easing::BounceIn, Easing::BounceOut, Easing::BounceInOut] {
+            assert!(easing.apply(0f32).abs() < 1e-6);
+            assert!((easing.apply(1f32) - 1f32).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn easings_match_reference_values() {
+        let cases = [
+            (Easing::Linear, 0.25f32, 0.25f32),
+            (Easing::QuadIn, 0.5, 0.25),
+            (Easing::QuadOut, 0.5, 0.75),
+            (Easing::CubicInOut, 0.25, 0.0625),
+            (Easing::QuartIn, 0.5, 0.0625),
+            (Easing::QuartOut, 0.5, 0.9375),
+            (Easing::QuartInOut, 0.25, 0.03125),
+            (Easing::BackIn, 0.5, -0.0876975),
+            (Easing::BackOut, 0.5, 1.0876975),
+            (Easing::BackInOut, 0.25, -0.0996818),
+            (Easing::ElasticIn, 0.5, -0.015625),
+            (Easing::ElasticOut, 0.5, 1.015625),
+            (Easing::ElasticInOut, 0.25, 0.0119694),
+            (Easing::BounceOut, 0.5, 0.765625),
+        ];
+
+        for (easing, t, expected) in cases {
+            let value = easing.apply(t);
+            assert!(
+                (value - expected).abs() < 1e-5,
+                "{:?}({}) = {}, expected {}",
+                easing,
+                t,
+                value,
+                expected
+            );
+            assert!(easing.apply(0f32).abs() < 1e-6);
+            assert!((easing.apply(1f32) - 1f32).abs() < 1e-6);
+        }
+    }
+}
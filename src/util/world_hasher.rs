@@ -0,0 +1,282 @@
+use super::StableHasher;
+use crate::transform::Transform;
+use serde::{Deserialize, Serialize};
+use specs::prelude::*;
+use std::{collections::BTreeMap, hash::Hasher};
+
+/// A component that [`WorldHasher`] can hash. Hash every field that affects the simulation, in a fixed order.
+pub trait WorldHash {
+    fn world_hash(&self, hasher: &mut StableHasher);
+}
+
+impl WorldHash for Transform {
+    fn world_hash(&self, hasher: &mut StableHasher) {
+        for value in [
+            self.position.x,
+            self.position.y,
+            self.position.z,
+            self.rotation.x,
+            self.rotation.y,
+            self.rotation.z,
+            self.rotation.w,
+            self.scale.x,
+            self.scale.y,
+            self.scale.z,
+        ] {
+            hasher.write_f32(value);
+        }
+    }
+}
+
+/// The state of the world at the end of a tick. Record it alongside the input recording, and compare it with the
+/// digests of the playback with [`find_divergence`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TickDigest {
+    pub tick: u64,
+    /// The rolling digest over this tick and every tick before it.
+    pub digest: u64,
+    /// The hashes of the components of each entity, by entity id in ascending order.
+    pub entities: Vec<(u32, u64)>,
+}
+
+impl TickDigest {
+    /// Returns the lowest entity id whose hash differs from the other digest, or that only one of them has.
+    pub fn first_divergent_entity(&self, other: &Self) -> Option<u32> {
+        let (mut lhs, mut rhs) = (self.entities.iter(), other.entities.iter());
+
+        loop {
+            match (lhs.next(), rhs.next()) {
+                (Some(&(lhs_id, lhs_hash)), Some(&(rhs_id, rhs_hash))) => {
+                    if lhs_id != rhs_id {
+                        return Some(lhs_id.min(rhs_id));
+                    }
+
+                    if lhs_hash != rhs_hash {
+                        return Some(lhs_id);
+                    }
+                }
+                (Some(&(id, _)), None) | (None, Some(&(id, _))) => return Some(id),
+                (None, None) => return None,
+            }
+        }
+    }
+}
+
+/// Where a playback diverged from its recording.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WorldDivergence {
+    pub tick: u64,
+    /// The first entity that differs, or `None` if only the rolling digests differ, e.g. when the recording
+    /// diverged in an earlier tick that is not compared.
+    pub entity_id: Option<u32>,
+}
+
+/// Returns the first tick whose digest differs between the recording and the playback. Since the digests roll,
+/// every later tick differs as well.
+pub fn find_divergence(
+    recorded: &[TickDigest],
+    replayed: &[TickDigest],
+) -> Option<WorldDivergence> {
+    recorded
+        .iter()
+        .zip(replayed)
+        .find(|(recorded, replayed)| recorded.digest != replayed.digest)
+        .map(|(recorded, replayed)| WorldDivergence {
+            tick: recorded.tick,
+            entity_id: recorded.first_divergent_entity(replayed),
+        })
+}
+
+type ComponentHashFn = fn(&World, u32, &mut BTreeMap<u32, StableHasher>);
+
+/// Hashes the [`Transform`]s, and the components of the registered types, into a rolling digest every fixed
+/// tick. Two runs of the same recording with the same [`run seed`](super::RngManager::run_seed) must yield the
+/// same digests; the first tick that does not is where the simulation stopped being deterministic.
+pub struct WorldHasher {
+    components: Vec<ComponentHashFn>,
+    tick: u64,
+    digest: u64,
+}
+
+impl WorldHasher {
+    pub fn new() -> Self {
+        Self {
+            components: vec![hash_components::<Transform>],
+            tick: 0,
+            digest: StableHasher::new().finish(),
+        }
+    }
+
+    /// Hashes the components of the type as well. Register the same types in the same order for the recording
+    /// and the playback.
+    pub fn register<C>(&mut self)
+    where
+        C: Component + WorldHash,
+    {
+        self.components.push(hash_components::<C>);
+    }
+
+    /// Returns the number of ticks hashed so far.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn digest(&self) -> u64 {
+        self.digest
+    }
+
+    /// Starts over from the first tick, e.g. before playing a recording back.
+    pub fn reset(&mut self) {
+        self.tick = 0;
+        self.digest = StableHasher::new().finish();
+    }
+
+    /// Hashes the world at the end of a fixed tick and rolls it into the digest.
+    pub fn hash_tick(&mut self, world: &World) -> TickDigest {
+        let mut hashers = BTreeMap::new();
+
+        for (index, hash_components) in self.components.iter().enumerate() {
+            hash_components(world, index as u32, &mut hashers);
+        }
+
+        let entities = hashers
+            .into_iter()
+            .map(|(id, hasher)| (id, hasher.finish()))
+            .collect::<Vec<_>>();
+
+        let mut hasher = StableHasher::new();
+        hasher.write_u64(self.digest);
+        hasher.write_u64(self.tick);
+
+        for &(id, hash) in &entities {
+            hasher.write_u32(id);
+            hasher.write_u64(hash);
+        }
+
+        self.digest = hasher.finish();
+
+        let digest = TickDigest {
+            tick: self.tick,
+            digest: self.digest,
+            entities,
+        };
+        self.tick += 1;
+        digest
+    }
+}
+
+impl Default for WorldHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Hashes the components of the type into the hashers of their entities, tagged with the index of the type so
+/// that the same values of different types differ.
+fn hash_components<C>(world: &World, index: u32, hashers: &mut BTreeMap<u32, StableHasher>)
+where
+    C: Component + WorldHash,
+{
+    let entities = world.entities();
+    let components = world.read_storage::<C>();
+
+    for (entity, component) in (&entities, &components).join() {
+        let hasher = hashers.entry(entity.id()).or_default();
+        hasher.write_u32(index);
+        component.world_hash(hasher);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{math::Vec3, util::RngManager};
+
+    struct Health(u32);
+
+    impl Component for Health {
+        type Storage = VecStorage<Self>;
+    }
+
+    impl WorldHash for Health {
+        fn world_hash(&self, hasher: &mut StableHasher) {
+            hasher.write_u32(self.0);
+        }
+    }
+
+    /// Plays the recorded inputs back from scratch, hashing every tick. `tamper` may change the world after a tick
+    /// is simulated, to simulate nondeterminism.
+    fn play(recording: &[f32], run_seed: u64, tamper: impl Fn(u64, &World)) -> Vec<TickDigest> {
+        let mut world = World::new();
+        world.register::<Transform>();
+        world.register::<Health>();
+
+        for index in 0..4 {
+            world
+                .create_entity()
+                .with(Transform {
+                    position: Vec3::new(index as f32, 0.0, 0.0),
+                    ..Transform::new()
+                })
+                .with(Health(100))
+                .build();
+        }
+
+        let mut rng_mgr = RngManager::new(run_seed);
+        let mut hasher = WorldHasher::new();
+        hasher.register::<Health>();
+
+        recording
+            .iter()
+            .enumerate()
+            .map(|(tick, &input)| {
+                {
+                    let mut transforms = world.write_storage::<Transform>();
+                    let mut healths = world.write_storage::<Health>();
+
+                    for (transform, health) in (&mut transforms, &mut healths).join() {
+                        let rng = rng_mgr.rng("gameplay");
+                        transform.position.x += input * 0.1;
+                        transform.position.y += rng.range_f32(-1.0, 1.0);
+                        health.0 -= rng.range(0, 3) as u32;
+                    }
+                }
+
+                tamper(tick as u64, &world);
+                hasher.hash_tick(&world)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn replays_of_a_recording_hash_identically() {
+        let recording = [0.0, 1.0, 1.0, -0.5, 0.0, 0.25, 1.0, 0.0, -1.0, 0.5];
+        let recorded = play(&recording, 7, |_, _| {});
+        let replayed = play(&recording, 7, |_, _| {});
+
+        assert_eq!(recorded.len(), recording.len());
+        assert_eq!(recorded, replayed);
+        assert_eq!(find_divergence(&recorded, &replayed), None);
+
+        // Another run seed draws other values from the gameplay stream from the first tick on.
+        let reseeded = play(&recording, 8, |_, _| {});
+        assert_eq!(
+            find_divergence(&recorded, &reseeded).map(|divergence| divergence.tick),
+            Some(0)
+        );
+
+        let tampered = play(&recording, 7, |tick, world| {
+            if tick == 5 {
+                let entity = world.entities().entity(2);
+                world.write_storage::<Health>().get_mut(entity).unwrap().0 += 1;
+            }
+        });
+        assert_eq!(
+            find_divergence(&recorded, &tampered),
+            Some(WorldDivergence {
+                tick: 5,
+                entity_id: Some(2),
+            })
+        );
+    }
+}
@@ -1,47 +1,228 @@
+use crate::time::Instant;
+use logging::log_warn;
 use std::{num::NonZeroU32, time::Duration};
-use winit::window::Window;
+use winit::{event_loop::ControlFlow, monitor::MonitorHandle, window::Window};
+
+/// The refresh rate used when the monitor does not report one.
+const FALLBACK_REFRESH_RATE_MILLIHERTZ: u32 = 60_000;
+/// The time left before a frame that is spun instead of slept, since sleeping tends to overshoot.
+const SPIN_MARGIN: Duration = Duration::from_millis(1);
+
+/// What the engine loop should do to reach the next frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FramePacing {
+    /// The frame is due; render it now.
+    Ready,
+    /// Sleeps until the given time, e.g. with [`ControlFlow::WaitUntil`](winit::event_loop::ControlFlow::WaitUntil).
+    WaitUntil(Instant),
+    /// The frame is due within the spin margin; check again without sleeping.
+    Spin,
+}
 
 pub struct TargetFrameInterval {
     target_frame_millihertz: Option<NonZeroU32>,
+    refresh_rate_millihertz: u32,
     interval: Duration,
+    monitor: Option<MonitorHandle>,
 }
 
 impl TargetFrameInterval {
     pub fn new(target_frame_millihertz: Option<NonZeroU32>, window: &Window) -> Self {
-        Self {
+        let mut this = Self {
             target_frame_millihertz,
-            interval: compute_target_frame_interval(
-                target_frame_millihertz
-                    .map(|n| n.get())
-                    .unwrap_or_else(|| get_window_refresh_rate_millihertz(window)),
-            ),
-        }
+            refresh_rate_millihertz: FALLBACK_REFRESH_RATE_MILLIHERTZ,
+            interval: Duration::ZERO,
+            monitor: None,
+        };
+        this.update_window(window);
+        this
     }
 
     pub fn target_frame_millihertz(&self) -> Option<NonZeroU32> {
         self.target_frame_millihertz
     }
 
+    /// Returns the refresh rate of the monitor that the window is on, in hertz.
+    pub fn refresh_rate(&self) -> f32 {
+        self.refresh_rate_millihertz as f32 / 1000f32
+    }
+
     pub fn interval(&self) -> Duration {
         self.interval
     }
 
+    /// Re-reads the refresh rate of the monitor that the window is on.
     pub fn update_window(&mut self, window: &Window) {
-        if self.target_frame_millihertz.is_some() {
-            return;
+        self.monitor = window.current_monitor();
+        self.refresh_rate_millihertz = match self
+            .monitor
+            .as_ref()
+            .and_then(|monitor| monitor.refresh_rate_millihertz())
+        {
+            Some(refresh_rate_millihertz) => refresh_rate_millihertz,
+            None => {
+                log_warn!(
+                    "the monitor does not report its refresh rate; falling back to {} Hz",
+                    FALLBACK_REFRESH_RATE_MILLIHERTZ / 1000
+                );
+                FALLBACK_REFRESH_RATE_MILLIHERTZ
+            }
+        };
+        self.interval = compute_target_frame_interval(
+            self.target_frame_millihertz
+                .map_or(self.refresh_rate_millihertz, |n| n.get()),
+        );
+    }
+
+    /// Updates the refresh rate if the window has been moved to another monitor.
+    /// Returns `true` if the monitor has changed.
+    pub fn handle_window_moved(&mut self, window: &Window) -> bool {
+        if window.current_monitor() == self.monitor {
+            return false;
         }
 
-        self.interval = compute_target_frame_interval(get_window_refresh_rate_millihertz(window));
+        self.update_window(window);
+        true
+    }
+
+    /// Decides how to wait for the frame that follows the frame rendered at `last_frame_time`.
+    pub fn pace(&self, last_frame_time: Instant, now: Instant) -> FramePacing {
+        let next_frame_time = last_frame_time + self.interval;
+
+        if next_frame_time <= now {
+            return FramePacing::Ready;
+        }
+
+        if next_frame_time - now <= SPIN_MARGIN {
+            return FramePacing::Spin;
+        }
+
+        FramePacing::WaitUntil(next_frame_time - SPIN_MARGIN)
     }
 }
 
-fn get_window_refresh_rate_millihertz(window: &Window) -> u32 {
-    window
-        .current_monitor()
-        .and_then(|monitor| monitor.refresh_rate_millihertz())
-        .unwrap_or(60_000)
+/// Decides between polling and waiting for [`EngineLoopMode::Adaptive`](crate::EngineLoopMode::Adaptive). The
+/// loop polls while there is activity, and waits for events once no activity has been seen for `idle_frames`
+/// frames in a row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AdaptiveLoop {
+    idle_frames: u32,
+    idle_count: u32,
+}
+
+impl AdaptiveLoop {
+    pub fn new(idle_frames: u32) -> Self {
+        Self {
+            idle_frames,
+            idle_count: 0,
+        }
+    }
+
+    pub fn is_polling(&self) -> bool {
+        self.idle_count < self.idle_frames
+    }
+
+    pub fn control_flow(&self) -> ControlFlow {
+        if self.is_polling() {
+            ControlFlow::Poll
+        } else {
+            ControlFlow::Wait
+        }
+    }
+
+    /// Records activity, e.g. an input event or a requested redraw. Returns `true` if the loop was waiting, in
+    /// which case a redraw should be requested so that the woken loop renders a frame.
+    pub fn record_activity(&mut self) -> bool {
+        let was_waiting = !self.is_polling();
+        self.idle_count = 0;
+        was_waiting
+    }
+
+    /// Counts a rendered frame as idle unless `is_active`, e.g. while tweens are running.
+    pub fn end_frame(&mut self, is_active: bool) {
+        if is_active {
+            self.idle_count = 0;
+        } else {
+            self.idle_count = self.idle_count.saturating_add(1);
+        }
+    }
 }
 
 fn compute_target_frame_interval(target_frame_millihertz: impl Into<u64>) -> Duration {
-    Duration::from_millis(1000_000 / target_frame_millihertz.into())
+    Duration::from_nanos(1_000_000_000_000 / target_frame_millihertz.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pacing_keeps_frame_interval_with_fake_clock() {
+        let interval = compute_target_frame_interval(60_000u32);
+        let pacer = TargetFrameInterval {
+            target_frame_millihertz: None,
+            refresh_rate_millihertz: 60_000,
+            interval,
+            monitor: None,
+        };
+
+        let start = Instant::now();
+        let mut now = start;
+        let mut last_frame_time = start;
+        let mut frame_times = Vec::new();
+        let mut spins = 0;
+
+        while frame_times.len() < 10 {
+            match pacer.pace(last_frame_time, now) {
+                FramePacing::Ready => {
+                    last_frame_time = now;
+                    frame_times.push(now);
+                }
+                // Oversleeps a bit, as real sleeps do.
+                FramePacing::WaitUntil(until) => now = until + Duration::from_micros(200),
+                FramePacing::Spin => {
+                    spins += 1;
+                    now += Duration::from_micros(100);
+                }
+            }
+        }
+
+        for pair in frame_times.windows(2) {
+            let elapsed = pair[1] - pair[0];
+            assert!(interval <= elapsed && elapsed < interval + Duration::from_micros(100));
+        }
+
+        // Only the spin margin is spun, instead of the whole interval.
+        assert!(spins <= 10 * 8);
+        assert_eq!(interval, Duration::from_nanos(16_666_666));
+    }
+
+    #[test]
+    fn adaptive_loop_waits_when_idle_and_wakes_on_input() {
+        let mut adaptive_loop = AdaptiveLoop::new(3);
+        assert_eq!(adaptive_loop.control_flow(), ControlFlow::Poll);
+
+        // A running tween keeps the loop polling.
+        for _ in 0..5 {
+            adaptive_loop.end_frame(true);
+        }
+        assert_eq!(adaptive_loop.control_flow(), ControlFlow::Poll);
+
+        for _ in 0..2 {
+            adaptive_loop.end_frame(false);
+        }
+        assert_eq!(adaptive_loop.control_flow(), ControlFlow::Poll);
+        adaptive_loop.end_frame(false);
+        assert_eq!(adaptive_loop.control_flow(), ControlFlow::Wait);
+
+        // An input event wakes the loop up, which requests a redraw only once.
+        assert!(adaptive_loop.record_activity());
+        assert_eq!(adaptive_loop.control_flow(), ControlFlow::Poll);
+        assert!(!adaptive_loop.record_activity());
+
+        for _ in 0..3 {
+            adaptive_loop.end_frame(false);
+        }
+        assert_eq!(adaptive_loop.control_flow(), ControlFlow::Wait);
+    }
 }